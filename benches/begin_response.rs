@@ -0,0 +1,73 @@
+//! Cost of turning a started WebAuthn registration into the stored session
+//! state and the `/begin` response body.
+//!
+//! `value_round_trip` is what the server used to do: both objects go through
+//! `serde_json::Value` before being encoded again. `direct` serializes each
+//! once, as `AuthService::create_session_response` does now.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use serde::Serialize;
+use serde_json::value::RawValue;
+use std::hint::black_box;
+use url::Url;
+use uuid::Uuid;
+use webauthn_rs::WebauthnBuilder;
+
+#[derive(Serialize)]
+struct ValueResponse {
+    options: serde_json::Value,
+    session_id: String,
+}
+
+#[derive(Serialize)]
+struct RawResponse {
+    options: Box<RawValue>,
+    session_id: String,
+}
+
+fn begin_response(c: &mut Criterion) {
+    let origin = Url::parse("https://example.com").unwrap();
+    let webauthn = WebauthnBuilder::new("example.com", &origin)
+        .unwrap()
+        .rp_name("Example")
+        .build()
+        .unwrap();
+    let (options, state) = webauthn
+        .start_passkey_registration(Uuid::new_v4(), "alice", "alice", None)
+        .unwrap();
+    let session_id = Uuid::new_v4().to_string();
+
+    let mut group = c.benchmark_group("begin_response");
+
+    group.bench_function("value_round_trip", |b| {
+        b.iter(|| {
+            let state = serde_json::to_value(black_box(&state)).unwrap();
+            let options = serde_json::to_value(black_box(&options)).unwrap();
+            let stored = serde_json::to_vec(&state).unwrap();
+            let body = serde_json::to_vec(&ValueResponse {
+                options,
+                session_id: session_id.clone(),
+            })
+            .unwrap();
+            (stored, body)
+        })
+    });
+
+    group.bench_function("direct", |b| {
+        b.iter(|| {
+            let stored = serde_json::to_vec(black_box(&state)).unwrap();
+            let options = serde_json::value::to_raw_value(black_box(&options)).unwrap();
+            let body = serde_json::to_vec(&RawResponse {
+                options,
+                session_id: session_id.clone(),
+            })
+            .unwrap();
+            (stored, body)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, begin_response);
+criterion_main!(benches);
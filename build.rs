@@ -0,0 +1,15 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/auth.proto");
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("No vendored protoc");
+        // SAFETY: the build script is single threaded.
+        unsafe { std::env::set_var("PROTOC", protoc) };
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/auth.proto"], &["proto"])
+            .expect("Failed to compile proto/auth.proto");
+    }
+}
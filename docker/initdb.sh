@@ -0,0 +1,9 @@
+#!/bin/sh
+# Applies migrations/ on the first start of the Postgres container, in
+# version order: the entrypoint's own lexical order would run V10 before V1.
+set -e
+
+for file in $(ls /migrations/V*__*.sql | sed 's|^/migrations/V\([0-9]*\)__.*|\1 &|' | sort -n | cut -d' ' -f2); do
+    echo "Applying $file"
+    psql -v ON_ERROR_STOP=1 --username "$POSTGRES_USER" --dbname "$POSTGRES_DB" -f "$file"
+done
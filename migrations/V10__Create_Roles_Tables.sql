@@ -0,0 +1,44 @@
+-- Named roles granting fine-grained permissions, replacing the free-form
+-- users.role column. Permissions are `resource:action` scopes checked by the
+-- routes; the admin role holds every scope the server defines.
+CREATE TABLE roles (
+    name TEXT PRIMARY KEY CHECK (LENGTH(name) >= 1),
+    description TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE role_permissions (
+    role TEXT NOT NULL REFERENCES roles(name) ON DELETE CASCADE ON UPDATE CASCADE,
+    permission TEXT NOT NULL CHECK (permission ~ '^[a-z_]+:[a-z_]+$'),
+    PRIMARY KEY (role, permission)
+);
+
+CREATE TABLE user_roles (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL REFERENCES roles(name) ON DELETE CASCADE ON UPDATE CASCADE,
+    granted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, role)
+);
+
+CREATE INDEX idx_user_roles_role ON user_roles(role);
+
+INSERT INTO roles (name, description)
+VALUES ('admin', 'Operates the server through the administrative API');
+
+INSERT INTO role_permissions (role, permission)
+VALUES ('admin', 'admin:actions'),
+       ('admin', 'audit:read'),
+       ('admin', 'banner:write'),
+       ('admin', 'enrollment:read'),
+       ('admin', 'traffic:read');
+
+-- Every role string in use becomes a role, without permissions unless it
+-- is admin, and keeps its users.
+INSERT INTO roles (name)
+SELECT DISTINCT role FROM users WHERE role IS NOT NULL AND role <> ''
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO user_roles (user_id, role)
+SELECT id, role FROM users WHERE role IS NOT NULL AND role <> '';
+
+ALTER TABLE users DROP COLUMN role;
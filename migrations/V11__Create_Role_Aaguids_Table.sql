@@ -0,0 +1,10 @@
+-- Authenticator models a role accepts, by AAGUID. A role with rows here may
+-- only register attested credentials from one of them; a role without rows
+-- accepts any passkey.
+CREATE TABLE role_aaguids (
+    role TEXT NOT NULL REFERENCES roles(name) ON DELETE CASCADE ON UPDATE CASCADE,
+    aaguid UUID NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (role, aaguid)
+);
@@ -0,0 +1,11 @@
+-- Usernames are unique once NFKC normalized and lowercased, so look-alike
+-- spellings such as "Alice" and "ａｌｉｃｅ" resolve to the same account. The
+-- application fills the column; existing rows are backfilled here, and the
+-- index fails to build if two of them already collide.
+ALTER TABLE users ADD COLUMN normalized_username TEXT;
+
+UPDATE users SET normalized_username = LOWER(NORMALIZE(username, NFKC));
+
+ALTER TABLE users ALTER COLUMN normalized_username SET NOT NULL;
+
+CREATE UNIQUE INDEX idx_users_normalized_username ON users (normalized_username);
@@ -0,0 +1,12 @@
+CREATE TABLE login_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ip INET,
+    user_agent TEXT,
+    country TEXT,
+    asn BIGINT,
+    anomalies TEXT[] NOT NULL DEFAULT '{}',
+    logged_in_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_login_history_user_id ON login_history(user_id, logged_in_at DESC);
@@ -0,0 +1,19 @@
+-- update_last_used() assigned the bare identifier NOW, so every UPDATE on
+-- credentials failed. The fixed function gets a new name, which lets the
+-- migration runner tell whether it is in place.
+CREATE OR REPLACE FUNCTION touch_last_used()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.last_used_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_last_used ON credentials;
+
+CREATE TRIGGER trigger_last_used
+BEFORE UPDATE ON credentials
+FOR EACH ROW
+EXECUTE FUNCTION touch_last_used();
+
+DROP FUNCTION IF EXISTS update_last_used();
@@ -0,0 +1,12 @@
+-- The authenticator model reported at registration, so users can tell
+-- their passkeys apart. Only attested credentials kept it in the passkey
+-- itself, so older rows registered without attestation stay NULL.
+ALTER TABLE credentials ADD COLUMN aaguid UUID;
+
+UPDATE credentials
+SET aaguid = COALESCE(
+    passkey #>> '{cred,attestation,metadata,Packed,aaguid}',
+    passkey #>> '{cred,attestation,metadata,Tpm,aaguid}'
+)::uuid;
+
+CREATE INDEX idx_credentials_aaguid ON credentials(aaguid);
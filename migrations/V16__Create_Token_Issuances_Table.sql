@@ -0,0 +1,83 @@
+-- One row per issued token pair. The user id carries no foreign key, so the
+-- trail outlives the account; retention drops whole monthly partitions.
+CREATE TABLE token_issuances (
+    user_id UUID NOT NULL,
+    jti TEXT NOT NULL,
+    kid TEXT NOT NULL,
+    grant_type TEXT NOT NULL CHECK (grant_type IN ('login', 'refresh', 'session_update')),
+    client_app TEXT,
+    ip INET,
+    issued_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+) PARTITION BY RANGE (issued_at);
+
+-- Catches rows when the cleanup job has not created the month's partition.
+CREATE TABLE token_issuances_default PARTITION OF token_issuances DEFAULT;
+
+CREATE INDEX idx_token_issuances_user_id ON token_issuances(user_id, issued_at DESC);
+CREATE INDEX idx_token_issuances_jti ON token_issuances(jti);
+CREATE INDEX idx_token_issuances_issued_at ON token_issuances(issued_at DESC);
+
+CREATE FUNCTION reject_token_issuance_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'token_issuances is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_token_issuances_append_only
+BEFORE UPDATE OR DELETE ON token_issuances
+FOR EACH ROW
+EXECUTE FUNCTION reject_token_issuance_change();
+
+-- Creates the partitions of this month and the next, and drops those that
+-- ended more than retention_days ago (0 keeps them all). Runs as the owner,
+-- since the application role has no DDL grants. Returns the rows dropped.
+CREATE FUNCTION maintain_token_issuances(retention_days INTEGER)
+RETURNS BIGINT
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    month_start TIMESTAMP WITH TIME ZONE;
+    partition_name TEXT;
+    partition_rows BIGINT;
+    dropped BIGINT := 0;
+BEGIN
+    FOR offset_months IN 0..1 LOOP
+        month_start := date_trunc('month', NOW()) + make_interval(months => offset_months);
+        partition_name := 'token_issuances_' || to_char(month_start, 'YYYYMM');
+        IF to_regclass(partition_name) IS NULL THEN
+            EXECUTE format(
+                'CREATE TABLE %I PARTITION OF token_issuances FOR VALUES FROM (%L) TO (%L)',
+                partition_name,
+                month_start,
+                month_start + INTERVAL '1 month'
+            );
+        END IF;
+    END LOOP;
+
+    IF retention_days <= 0 THEN
+        RETURN 0;
+    END IF;
+
+    FOR partition_name IN
+        SELECT c.relname
+        FROM pg_inherits i
+        JOIN pg_class c ON c.oid = i.inhrelid
+        WHERE i.inhparent = 'token_issuances'::regclass
+          AND c.relname ~ '^token_issuances_[0-9]{6}$'
+          AND to_date(right(c.relname, 6), 'YYYYMM') + INTERVAL '1 month'
+              <= NOW() - make_interval(days => retention_days)
+    LOOP
+        EXECUTE format('SELECT COUNT(*) FROM %I', partition_name) INTO partition_rows;
+        EXECUTE format('DROP TABLE %I', partition_name);
+        dropped := dropped + partition_rows;
+    END LOOP;
+
+    RETURN dropped;
+END;
+$$;
+
+SELECT maintain_token_issuances(0);
@@ -0,0 +1,108 @@
+-- Monthly partition upkeep for any table partitioned by range on a
+-- timestamp. Partitions are named <parent>_YYYYMM. Creates those of this
+-- month and the next months_ahead, and drops those that ended more than
+-- retention_days ago (0 keeps them all). Runs as the owner, since the
+-- application role has no DDL grants. Returns the rows dropped.
+CREATE FUNCTION maintain_partitions(parent TEXT, months_ahead INTEGER, retention_days INTEGER)
+RETURNS BIGINT
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    month_start TIMESTAMP WITH TIME ZONE;
+    partition_name TEXT;
+    partition_rows BIGINT;
+    dropped BIGINT := 0;
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_class WHERE oid = to_regclass(parent) AND relkind = 'p'
+    ) THEN
+        RAISE EXCEPTION '% is not a partitioned table', parent;
+    END IF;
+
+    FOR offset_months IN 0..GREATEST(months_ahead, 0) LOOP
+        month_start := date_trunc('month', NOW()) + make_interval(months => offset_months);
+        partition_name := parent || '_' || to_char(month_start, 'YYYYMM');
+        IF to_regclass(partition_name) IS NULL THEN
+            EXECUTE format(
+                'CREATE TABLE %I PARTITION OF %I FOR VALUES FROM (%L) TO (%L)',
+                partition_name,
+                parent,
+                month_start,
+                month_start + INTERVAL '1 month'
+            );
+        END IF;
+    END LOOP;
+
+    IF retention_days <= 0 THEN
+        RETURN 0;
+    END IF;
+
+    FOR partition_name IN
+        SELECT c.relname
+        FROM pg_inherits i
+        JOIN pg_class c ON c.oid = i.inhrelid
+        WHERE i.inhparent = to_regclass(parent)
+          AND c.relname ~ ('^' || parent || '_[0-9]{6}$')
+          AND to_date(right(c.relname, 6), 'YYYYMM') + INTERVAL '1 month'
+              <= NOW() - make_interval(days => retention_days)
+    LOOP
+        EXECUTE format('SELECT COUNT(*) FROM %I', partition_name) INTO partition_rows;
+        EXECUTE format('DROP TABLE %I', partition_name);
+        dropped := dropped + partition_rows;
+    END LOOP;
+
+    RETURN dropped;
+END;
+$$;
+
+-- Superseded by the generic helper.
+DROP FUNCTION maintain_token_issuances(INTEGER);
+
+-- The existing audit_log becomes the partition of the current month, so
+-- its rows are dropped together once the month falls out of retention.
+-- Its indexes are renamed to free the names for the parent, and its key
+-- gives way to the parent's, which has to include the partition column.
+DO $$
+DECLARE
+    current_partition TEXT := 'audit_log_' || to_char(NOW(), 'YYYYMM');
+BEGIN
+    ALTER TABLE audit_log DROP CONSTRAINT audit_log_pkey;
+    ALTER INDEX idx_audit_log_occurred_at RENAME TO idx_audit_log_legacy_occurred_at;
+    ALTER INDEX idx_audit_log_user_id RENAME TO idx_audit_log_legacy_user_id;
+    ALTER INDEX idx_audit_log_event RENAME TO idx_audit_log_legacy_event;
+    EXECUTE format('ALTER TABLE audit_log RENAME TO %I', current_partition);
+
+    CREATE TABLE audit_log (
+        id UUID NOT NULL DEFAULT gen_random_uuid(),
+        event TEXT NOT NULL,
+        outcome TEXT NOT NULL
+            CONSTRAINT audit_log_outcome_check CHECK (outcome IN ('success', 'failure')),
+        user_id UUID
+            CONSTRAINT audit_log_user_id_fkey REFERENCES users(id) ON DELETE SET NULL,
+        username TEXT,
+        ip INET,
+        user_agent TEXT,
+        details JSONB NOT NULL DEFAULT '{}',
+        occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+        PRIMARY KEY (id, occurred_at)
+    ) PARTITION BY RANGE (occurred_at);
+
+    CREATE INDEX idx_audit_log_occurred_at ON audit_log(occurred_at DESC);
+    CREATE INDEX idx_audit_log_user_id ON audit_log(user_id, occurred_at DESC);
+    CREATE INDEX idx_audit_log_event ON audit_log(event, occurred_at DESC);
+
+    EXECUTE format(
+        'ALTER TABLE audit_log ATTACH PARTITION %I FOR VALUES FROM (MINVALUE) TO (%L)',
+        current_partition,
+        date_trunc('month', NOW()) + INTERVAL '1 month'
+    );
+END;
+$$;
+
+-- Catches rows when the cleanup job has not created the month's partition.
+CREATE TABLE audit_log_default PARTITION OF audit_log DEFAULT;
+
+SELECT maintain_partitions('audit_log', 1, 0);
+SELECT maintain_partitions('token_issuances', 1, 0);
@@ -0,0 +1,17 @@
+-- Logins used to edit passkeys in place with jsonb_set, bypassing
+-- webauthn-rs: a stale login could set the counter back. Existing rows are
+-- format 1 until the server rewrites them through Passkey at startup.
+ALTER TABLE credentials ADD COLUMN passkey_format SMALLINT NOT NULL DEFAULT 1;
+ALTER TABLE credentials ALTER COLUMN passkey_format SET DEFAULT 2;
+
+CREATE INDEX idx_credentials_legacy_passkeys ON credentials (id)
+WHERE passkey_format < 2;
+
+-- Rewriting the format is not a use of the credential.
+DROP TRIGGER IF EXISTS trigger_last_used ON credentials;
+
+CREATE TRIGGER trigger_last_used
+BEFORE UPDATE ON credentials
+FOR EACH ROW
+WHEN (NEW.passkey_format = OLD.passkey_format)
+EXECUTE FUNCTION touch_last_used();
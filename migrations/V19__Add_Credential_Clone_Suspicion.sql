@@ -0,0 +1,19 @@
+-- Set when a login reports a signature counter at or below the stored one,
+-- a sign that the authenticator may have been cloned. The credential cannot
+-- sign in again until its owner confirms it.
+ALTER TABLE credentials ADD COLUMN clone_suspected_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_credentials_clone_suspected ON credentials (user_id)
+WHERE clone_suspected_at IS NOT NULL;
+
+-- Locking or confirming a credential is not a use of it.
+DROP TRIGGER IF EXISTS trigger_last_used ON credentials;
+
+CREATE TRIGGER trigger_last_used
+BEFORE UPDATE ON credentials
+FOR EACH ROW
+WHEN (
+    NEW.passkey_format = OLD.passkey_format
+    AND NEW.clone_suspected_at IS NOT DISTINCT FROM OLD.clone_suspected_at
+)
+EXECUTE FUNCTION touch_last_used();
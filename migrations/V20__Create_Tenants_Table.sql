@@ -0,0 +1,36 @@
+-- Applications served by the same deployment. Accounts, passkeys and
+-- ceremonies belong to exactly one tenant, and a username only has to be
+-- unique within it. Existing rows move to the 'default' tenant.
+CREATE TABLE tenants (
+    id TEXT PRIMARY KEY CHECK (id ~ '^[a-z0-9][a-z0-9_-]{0,62}$'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO tenants (id) VALUES ('default');
+
+ALTER TABLE users ADD COLUMN tenant_id TEXT NOT NULL DEFAULT 'default' REFERENCES tenants (id);
+ALTER TABLE users ALTER COLUMN tenant_id DROP DEFAULT;
+ALTER TABLE users ADD CONSTRAINT users_id_tenant_key UNIQUE (id, tenant_id);
+
+ALTER TABLE users DROP CONSTRAINT users_username_key;
+DROP INDEX idx_users_normalized_username;
+DROP INDEX idx_user_username;
+DROP INDEX idx_users_username_status;
+CREATE UNIQUE INDEX idx_users_tenant_username ON users (tenant_id, username);
+CREATE UNIQUE INDEX idx_users_tenant_normalized_username ON users (tenant_id, normalized_username);
+
+-- The composite keys stop a passkey or ceremony from pointing at a user of
+-- another tenant.
+ALTER TABLE credentials ADD COLUMN tenant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE credentials ALTER COLUMN tenant_id DROP DEFAULT;
+ALTER TABLE credentials DROP CONSTRAINT credentials_user_id_fkey;
+ALTER TABLE credentials ADD CONSTRAINT credentials_user_tenant_fkey
+    FOREIGN KEY (user_id, tenant_id) REFERENCES users (id, tenant_id)
+    ON DELETE CASCADE ON UPDATE CASCADE;
+
+ALTER TABLE webauthn_sessions ADD COLUMN tenant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE webauthn_sessions ALTER COLUMN tenant_id DROP DEFAULT;
+ALTER TABLE webauthn_sessions DROP CONSTRAINT webauthn_sessions_user_id_fkey;
+ALTER TABLE webauthn_sessions ADD CONSTRAINT webauthn_sessions_user_tenant_fkey
+    FOREIGN KEY (user_id, tenant_id) REFERENCES users (id, tenant_id)
+    ON DELETE CASCADE ON UPDATE CASCADE;
@@ -0,0 +1,5 @@
+-- The region whose database holds the account, for deployments that keep
+-- users' data in region-specific databases. NULL for the home database.
+ALTER TABLE users ADD COLUMN region TEXT CHECK (region ~ '^[a-z0-9][a-z0-9_-]{0,62}$');
+
+CREATE INDEX idx_users_region ON users (region) WHERE region IS NOT NULL;
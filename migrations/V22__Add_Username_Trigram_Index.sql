@@ -0,0 +1,6 @@
+-- Lets the admin user search match parts of a username, and rank near
+-- misses by similarity, without scanning the table.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_users_normalized_username_trgm ON users
+USING GIN (normalized_username gin_trgm_ops);
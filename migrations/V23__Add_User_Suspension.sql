@@ -0,0 +1,14 @@
+-- The full account lifecycle: pending -> active -> suspended -> deleted.
+-- Suspended accounts cannot sign in or refresh until an administrator lifts
+-- the suspension; deleted ones keep their row for the audit trail.
+ALTER TABLE users DROP CONSTRAINT users_status_check;
+ALTER TABLE users ADD CONSTRAINT users_status_check
+    CHECK (status IN ('pending', 'active', 'suspended', 'deleted'));
+
+ALTER TABLE users ADD COLUMN suspended_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN suspension_reason TEXT;
+
+-- Accounts soft-deleted before the status existed.
+UPDATE users SET status = 'deleted' WHERE NOT is_active;
+
+CREATE INDEX idx_users_suspended ON users (suspended_at) WHERE status = 'suspended';
@@ -0,0 +1,20 @@
+-- Machine accounts: cron jobs and internal services that get access tokens
+-- with a client id and secret instead of a passkey. Only the SHA-256 of the
+-- secret is kept; it carries 244 random bits, so a slow hash adds nothing.
+CREATE TABLE machine_clients (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id TEXT NOT NULL REFERENCES tenants (id),
+    name TEXT NOT NULL CHECK (name ~ '^[a-z0-9][a-z0-9_-]{0,62}$'),
+    secret_hash BYTEA NOT NULL,
+    role TEXT NOT NULL REFERENCES roles (name) ON UPDATE CASCADE,
+    created_by UUID,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    UNIQUE (tenant_id, name)
+);
+
+-- Granted to machine clients unless another role is picked. It starts
+-- without permissions, like any role an administrator has not filled in.
+INSERT INTO roles (name, description)
+VALUES ('service', 'Machine clients authenticating with client credentials')
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,16 @@
+-- Single-use codes that let a registration take a privileged role without
+-- an administrator's access token. Only the SHA-256 of the code is kept;
+-- like machine client secrets it carries enough random bits to need no
+-- slow hash. Revoked codes stay, as a record of who they let in.
+CREATE TABLE invitations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id TEXT NOT NULL REFERENCES tenants (id),
+    code_hash BYTEA NOT NULL UNIQUE,
+    role TEXT NOT NULL REFERENCES roles (name) ON DELETE CASCADE ON UPDATE CASCADE,
+    created_by UUID,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    redeemed_at TIMESTAMP WITH TIME ZONE,
+    redeemed_by TEXT,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
@@ -0,0 +1,12 @@
+-- Invitations may leave the role to the registration, and serve several
+-- registrations until they expire. `redeemed_at` and `redeemed_by` now name
+-- the latest registration.
+ALTER TABLE invitations ALTER COLUMN role DROP NOT NULL;
+ALTER TABLE invitations ADD COLUMN single_use BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE invitations ADD COLUMN uses INTEGER NOT NULL DEFAULT 0;
+
+UPDATE invitations SET uses = 1 WHERE redeemed_at IS NOT NULL;
+
+-- The invitations of a tenant that can still be redeemed or revoked.
+CREATE INDEX idx_invitations_open ON invitations (tenant_id, expires_at)
+    WHERE revoked_at IS NULL;
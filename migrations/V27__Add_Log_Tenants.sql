@@ -0,0 +1,11 @@
+-- Audit entries and token issuances belong to the tenant of the request
+-- that wrote them, so each tenant's administrators only search their own.
+-- Existing rows move to the 'default' tenant.
+ALTER TABLE audit_log ADD COLUMN tenant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE audit_log ALTER COLUMN tenant_id DROP DEFAULT;
+
+ALTER TABLE token_issuances ADD COLUMN tenant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE token_issuances ALTER COLUMN tenant_id DROP DEFAULT;
+
+CREATE INDEX idx_audit_log_tenant ON audit_log (tenant_id, occurred_at DESC);
+CREATE INDEX idx_token_issuances_tenant ON token_issuances (tenant_id, issued_at DESC);
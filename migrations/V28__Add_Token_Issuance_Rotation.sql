@@ -0,0 +1,6 @@
+-- A refresh records the jti of the token it rotated out, so a session is
+-- the newest token of its chain rather than every token it was ever given.
+ALTER TABLE token_issuances ADD COLUMN rotated_from TEXT;
+
+CREATE INDEX idx_token_issuances_rotated_from ON token_issuances (rotated_from)
+    WHERE rotated_from IS NOT NULL;
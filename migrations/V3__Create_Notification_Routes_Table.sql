@@ -0,0 +1,11 @@
+CREATE TABLE notification_routes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event TEXT NOT NULL,
+    channel TEXT NOT NULL CHECK (channel IN ('email', 'webhook', 'sms', 'push')),
+    target TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (event, channel, target)
+);
+
+CREATE INDEX idx_notification_routes_event ON notification_routes(event) WHERE enabled;
@@ -0,0 +1,18 @@
+ALTER TABLE notification_routes ADD COLUMN locale TEXT NOT NULL DEFAULT 'en';
+
+CREATE TABLE notification_templates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event TEXT NOT NULL,
+    channel TEXT NOT NULL CHECK (channel IN ('email', 'webhook', 'sms', 'push')),
+    locale TEXT NOT NULL DEFAULT 'en',
+    subject TEXT,
+    body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (event, channel, locale)
+);
+
+CREATE TRIGGER trigger_notification_templates_updated_at
+BEFORE UPDATE ON notification_templates
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at();
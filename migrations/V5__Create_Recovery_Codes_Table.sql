@@ -0,0 +1,17 @@
+CREATE TABLE recovery_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash BYTEA NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX idx_recovery_codes_user_hash ON recovery_codes(user_id, code_hash) WHERE used_at IS NULL;
+
+ALTER TABLE users
+    ADD COLUMN recovery_failed_attempts INT NOT NULL DEFAULT 0,
+    ADD COLUMN recovery_locked_until TIMESTAMPTZ;
+
+ALTER TABLE webauthn_sessions DROP CONSTRAINT webauthn_sessions_purpose_check;
+ALTER TABLE webauthn_sessions ADD CONSTRAINT webauthn_sessions_purpose_check
+    CHECK (purpose IN ('registration', 'login', 'recovery'));
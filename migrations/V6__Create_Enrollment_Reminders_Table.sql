@@ -0,0 +1,10 @@
+CREATE TABLE enrollment_reminders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    attempt INT NOT NULL CHECK (attempt > 0),
+    sent_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    opened_at TIMESTAMP WITH TIME ZONE,
+    UNIQUE (user_id, attempt)
+);
+
+CREATE INDEX idx_enrollment_reminders_user_id ON enrollment_reminders(user_id);
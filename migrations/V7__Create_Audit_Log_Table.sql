@@ -0,0 +1,15 @@
+CREATE TABLE audit_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event TEXT NOT NULL,
+    outcome TEXT NOT NULL CHECK (outcome IN ('success', 'failure')),
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    username TEXT,
+    ip INET,
+    user_agent TEXT,
+    details JSONB NOT NULL DEFAULT '{}',
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_log_occurred_at ON audit_log(occurred_at DESC);
+CREATE INDEX idx_audit_log_user_id ON audit_log(user_id, occurred_at DESC);
+CREATE INDEX idx_audit_log_event ON audit_log(event, occurred_at DESC);
@@ -0,0 +1,8 @@
+-- Single-row table: the CHECK on id allows at most one banner at a time
+CREATE TABLE login_banner (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    message TEXT NOT NULL,
+    severity TEXT NOT NULL CHECK (severity IN ('info', 'warning', 'critical')),
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
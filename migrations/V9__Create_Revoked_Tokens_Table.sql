@@ -0,0 +1,9 @@
+-- Refresh token revocations recorded while Redis is unavailable. Rows are
+-- moved back to Redis once it recovers, so the table stays small.
+CREATE TABLE revoked_tokens (
+    jti TEXT PRIMARY KEY,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_revoked_tokens_expires_at ON revoked_tokens(expires_at);
@@ -0,0 +1,9 @@
+pub(crate) mod request;
+pub(crate) mod response;
+
+pub(crate) use request::{UpdateCeremonyLimitRequest, UpdateMaintenanceRequest};
+pub(crate) use response::{
+    AccountLockoutResponse, ActionResponse, AttemptMetrics, BreakerStatus, CeremonyLimitResponse,
+    CircuitBreakerEntry, CircuitBreakerListResponse, CircuitBreakerState, ErrorMetrics,
+    MaintenanceResponse, MetricsSnapshotResponse, PoolMetrics,
+};
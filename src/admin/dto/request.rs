@@ -0,0 +1,44 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::{app::AppError, impl_validated_json_request, utils::Validatable};
+
+/// Far above what any instance verifies at once; higher values are typos.
+pub const MAX_CEREMONY_LIMIT: u32 = 10_000;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCeremonyLimitRequest {
+    /// Finish steps allowed to verify at once, 0 for no limit
+    #[schema(example = 4, maximum = 10000)]
+    pub max_concurrent: u32,
+}
+
+impl Validatable for UpdateCeremonyLimitRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.max_concurrent > MAX_CEREMONY_LIMIT {
+            return Err(AppError::BadRequest(format!(
+                "max_concurrent must be at most {}",
+                MAX_CEREMONY_LIMIT
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl_validated_json_request!(UpdateCeremonyLimitRequest);
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateMaintenanceRequest {
+    /// True refuses requests outside `/admin/*`, the health probes and the
+    /// JWKS; false serves them again
+    #[schema(example = true)]
+    pub enabled: bool,
+}
+
+impl Validatable for UpdateMaintenanceRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        Ok(())
+    }
+}
+
+impl_validated_json_request!(UpdateMaintenanceRequest);
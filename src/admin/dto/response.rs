@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use axum::{Json, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::CircuitBreaker;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ActionResponse {
+    #[schema(example = "rotate-signing-key")]
+    pub action: String,
+    #[schema(example = "Access tokens are now signed with key 7c0f3b2a")]
+    pub message: String,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub performed_at: String,
+}
+
+impl IntoResponse for ActionResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CircuitBreakerListResponse {
+    pub circuit_breakers: Vec<CircuitBreakerEntry>,
+}
+
+/// The configuration a breaker was built with.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CircuitBreakerEntry {
+    #[schema(example = "database")]
+    pub name: String,
+    #[schema(example = 5)]
+    pub failure_threshold: u32,
+    #[schema(example = 10)]
+    pub backoff_initial_secs: u64,
+    #[schema(example = 60)]
+    pub backoff_max_secs: u64,
+}
+
+impl From<&CircuitBreaker> for CircuitBreakerEntry {
+    fn from(breaker: &CircuitBreaker) -> Self {
+        let config = breaker.config();
+        Self {
+            name: breaker.name().to_owned(),
+            failure_threshold: config.failure_threshold,
+            backoff_initial_secs: config.backoff_initial_secs,
+            backoff_max_secs: config.backoff_max_secs,
+        }
+    }
+}
+
+impl IntoResponse for CircuitBreakerListResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+/// The failures an unlock cleared from an account.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountLockoutResponse {
+    #[schema(example = "alice")]
+    pub username: String,
+    /// Failed finish steps in a row before the unlock
+    #[schema(example = 10)]
+    pub failures: u32,
+    /// When the lock would have lifted, null when the account was only
+    /// delayed
+    #[schema(example = "2024-01-01T12:15:00Z")]
+    pub locked_until: Option<String>,
+    #[schema(example = "2024-01-01T12:05:00Z")]
+    pub unlocked_at: String,
+}
+
+impl IntoResponse for AccountLockoutResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+/// The maintenance mode every instance applies.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MaintenanceResponse {
+    #[schema(example = true)]
+    pub enabled: bool,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub updated_at: String,
+}
+
+impl IntoResponse for MaintenanceResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+/// The WebAuthn finish step limit of the instance that answered.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CeremonyLimitResponse {
+    /// 0 when finish steps are not limited
+    #[schema(example = 4)]
+    pub max_concurrent: u32,
+    /// Finish steps that could start right now without queueing, null
+    /// when finish steps are not limited
+    #[schema(example = 3)]
+    pub available: Option<usize>,
+}
+
+impl IntoResponse for CeremonyLimitResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+/// A curated view of this instance's metrics for the admin UI. The shape
+/// is stable; rates are per minute over about the last minute, and null
+/// until the instance has been sampled once.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MetricsSnapshotResponse {
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub generated_at: String,
+    pub logins: AttemptMetrics,
+    pub registrations: AttemptMetrics,
+    pub errors: ErrorMetrics,
+    pub circuit_breakers: Vec<CircuitBreakerState>,
+    pub database_pool: PoolMetrics,
+    /// One entry per data residency region, keyed by region
+    pub region_pools: BTreeMap<String, PoolMetrics>,
+}
+
+/// Finished WebAuthn ceremonies since the instance started.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttemptMetrics {
+    #[schema(example = 1200)]
+    pub succeeded_total: u64,
+    #[schema(example = 35)]
+    pub failed_total: u64,
+    #[schema(example = 12.5)]
+    pub succeeded_per_minute: Option<f64>,
+    #[schema(example = 0.25)]
+    pub failed_per_minute: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorMetrics {
+    #[schema(example = 3)]
+    pub database_total: u64,
+    #[schema(example = 0)]
+    pub redis_total: u64,
+    /// Requests rejected with 429 by the rate limiter
+    #[schema(example = 42)]
+    pub rate_limited_total: u64,
+    pub database_per_minute: Option<f64>,
+    pub redis_per_minute: Option<f64>,
+    pub rate_limited_per_minute: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CircuitBreakerState {
+    #[schema(example = "database")]
+    pub name: String,
+    pub state: BreakerStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerStatus {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct PoolMetrics {
+    #[schema(example = 3)]
+    pub active: u64,
+    #[schema(example = 2)]
+    pub idle: u64,
+    #[schema(example = 16)]
+    pub max: u64,
+}
+
+impl PoolMetrics {
+    /// Sets the count for a `state` label of the pool gauges.
+    pub fn set(&mut self, state: Option<&str>, value: f64) {
+        match state {
+            Some("active") => self.active = value as u64,
+            Some("idle") => self.idle = value as u64,
+            Some("max") => self.max = value as u64,
+            _ => {}
+        }
+    }
+}
+
+impl IntoResponse for MetricsSnapshotResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
@@ -0,0 +1,196 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+
+use crate::{
+    admin::dto::{
+        AccountLockoutResponse, ActionResponse, CeremonyLimitResponse, CircuitBreakerListResponse,
+        MaintenanceResponse, MetricsSnapshotResponse, UpdateCeremonyLimitRequest,
+        UpdateMaintenanceRequest,
+    },
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    audit::model::AuditContext,
+    auth::permissions::AdminActions,
+};
+
+/// Run an operational action
+///
+/// Executes one of the whitelisted actions: `flush-prepared-cache`,
+/// `reset-circuit-breakers`, `rotate-cookie-secret` or `rotate-signing-key`.
+/// Breaker resets apply to the instance that handles the request; the
+/// others apply to every instance. Requires `admin:actions`.
+#[utoipa::path(
+    post,
+    path = "/admin/actions/{name}",
+    tag = "Admin",
+    params(("name" = String, Path, description = "Action to run")),
+    responses(
+        (status = 200, description = "Action completed", body = ActionResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "Unknown action", body = crate::app::error::ErrorResponse),
+        (status = 503, description = "Dependency unavailable", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn run_action(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Path(name): Path<String>,
+) -> Result<ActionResponse, AppError> {
+    state.admin_service.run(&name, &admin, &ctx).await
+}
+
+/// List circuit breakers
+///
+/// Returns the failure threshold and backoff each breaker of this instance
+/// was configured with. Requires `admin:actions`.
+#[utoipa::path(
+    get,
+    path = "/admin/circuit-breakers",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Circuit breaker configuration", body = CircuitBreakerListResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn circuit_breakers(
+    _admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+) -> CircuitBreakerListResponse {
+    state.admin_service.circuit_breakers()
+}
+
+/// Metrics snapshot
+///
+/// Login, registration and error counts with per minute rates, circuit
+/// breaker states and database pool usage of this instance, as JSON for
+/// dashboards without a Prometheus server. Requires `admin:actions`.
+#[utoipa::path(
+    get,
+    path = "/admin/metrics.json",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Metrics of this instance", body = MetricsSnapshotResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn metrics_snapshot(
+    _admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+) -> MetricsSnapshotResponse {
+    state.admin_service.metrics_snapshot()
+}
+
+/// WebAuthn finish step limit
+///
+/// Returns how many finish steps this instance verifies at once, and how
+/// many slots are free. Requires `admin:actions`.
+#[utoipa::path(
+    get,
+    path = "/admin/ceremony-limit",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Current limit", body = CeremonyLimitResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn ceremony_limit(
+    _admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+) -> CeremonyLimitResponse {
+    state.admin_service.ceremony_limit()
+}
+
+/// Set the WebAuthn finish step limit
+///
+/// Bounds how many registration, recovery and login finish steps this
+/// instance verifies at once; the rest queue. 0 removes the limit. Lowering
+/// it applies as running ceremonies finish, and a restart goes back to
+/// `WEBAUTHN_MAX_CONCURRENT_FINISHES`. Requires `admin:actions`.
+#[utoipa::path(
+    put,
+    path = "/admin/ceremony-limit",
+    tag = "Admin",
+    request_body = UpdateCeremonyLimitRequest,
+    responses(
+        (status = 200, description = "Limit updated", body = CeremonyLimitResponse),
+        (status = 400, description = "Invalid limit", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn set_ceremony_limit(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: UpdateCeremonyLimitRequest,
+) -> CeremonyLimitResponse {
+    state
+        .admin_service
+        .set_ceremony_limit(request, &admin, &ctx)
+}
+
+/// Set maintenance mode
+///
+/// With `enabled` true every instance answers 503 for everything except
+/// `/admin/*`, the health probes, the JWKS and the login banner; false
+/// serves requests again. Instances pick up the change within 5 seconds.
+/// Requires `admin:actions`.
+#[utoipa::path(
+    put,
+    path = "/admin/maintenance",
+    tag = "Admin",
+    request_body = UpdateMaintenanceRequest,
+    responses(
+        (status = 200, description = "Maintenance mode set", body = MaintenanceResponse),
+        (status = 400, description = "Invalid request", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 503, description = "Redis unavailable", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn set_maintenance(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: UpdateMaintenanceRequest,
+) -> Result<MaintenanceResponse, AppError> {
+    state
+        .admin_service
+        .set_maintenance(request, &admin, &ctx)
+        .await
+}
+
+/// Unlock an account
+///
+/// Lifts the lock that repeated failed logins or registrations put on
+/// `username`, and forgets the failures so no delay applies either.
+/// Applies to every instance. Requires `admin:actions`.
+#[utoipa::path(
+    delete,
+    path = "/admin/lockouts/{username}",
+    tag = "Admin",
+    params(("username" = String, Path, description = "Account to unlock")),
+    responses(
+        (status = 200, description = "Account unlocked", body = AccountLockoutResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "No failures recorded, or throttling disabled", body = crate::app::error::ErrorResponse),
+        (status = 503, description = "Redis unavailable", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn unlock_account(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Path(username): Path<String>,
+) -> Result<AccountLockoutResponse, AppError> {
+    state
+        .admin_service
+        .unlock_account(&username, &admin, &ctx)
+        .await
+}
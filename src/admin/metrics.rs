@@ -0,0 +1,209 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use prometheus::proto::{Metric, MetricFamily};
+
+use crate::admin::dto::{
+    AttemptMetrics, BreakerStatus, CircuitBreakerState, ErrorMetrics, MetricsSnapshotResponse,
+    PoolMetrics,
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+/// Rates are averaged over at least this long once the instance has been up
+/// for it.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// The counters the dashboard shows as rates, read from the registry.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MetricTotals {
+    pub logins_succeeded: f64,
+    pub logins_failed: f64,
+    pub registrations_succeeded: f64,
+    pub registrations_failed: f64,
+    pub database_errors: f64,
+    pub redis_errors: f64,
+    pub rate_limited: f64,
+}
+
+impl MetricTotals {
+    pub fn from_families(families: &[MetricFamily]) -> Self {
+        Self {
+            logins_succeeded: sum(
+                families,
+                "webauthn_login_attempts_total",
+                &[("status", "success")],
+            ),
+            logins_failed: sum(
+                families,
+                "webauthn_login_attempts_total",
+                &[("status", "failure")],
+            ),
+            registrations_succeeded: sum(
+                families,
+                "webauthn_registration_attempts_total",
+                &[("status", "success")],
+            ),
+            registrations_failed: sum(
+                families,
+                "webauthn_registration_attempts_total",
+                &[("status", "failure")],
+            ),
+            database_errors: sum(families, "db_errors_total", &[]),
+            redis_errors: sum(families, "redis_errors_total", &[]),
+            rate_limited: sum(families, "rate_limit_rejections_total", &[]),
+        }
+    }
+
+    /// Per minute increase since `earlier`.
+    fn per_minute(&self, earlier: &Self, elapsed: Duration) -> Self {
+        let minutes = elapsed.as_secs_f64() / 60.0;
+        let rate = |now: f64, then: f64| ((now - then) / minutes).max(0.0);
+        Self {
+            logins_succeeded: rate(self.logins_succeeded, earlier.logins_succeeded),
+            logins_failed: rate(self.logins_failed, earlier.logins_failed),
+            registrations_succeeded: rate(
+                self.registrations_succeeded,
+                earlier.registrations_succeeded,
+            ),
+            registrations_failed: rate(self.registrations_failed, earlier.registrations_failed),
+            database_errors: rate(self.database_errors, earlier.database_errors),
+            redis_errors: rate(self.redis_errors, earlier.redis_errors),
+            rate_limited: rate(self.rate_limited, earlier.rate_limited),
+        }
+    }
+}
+
+/// Keeps recent totals so rates can be served without a Prometheus server
+/// computing them. Each instance only knows its own traffic.
+#[derive(Default)]
+pub struct MetricsSampler {
+    samples: Mutex<VecDeque<(Instant, MetricTotals)>>,
+}
+
+impl MetricsSampler {
+    pub fn spawn(self: &Arc<Self>) {
+        let sampler = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(SAMPLE_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let totals = MetricTotals::from_families(&prometheus::gather());
+                sampler.record(Instant::now(), totals);
+            }
+        });
+    }
+
+    /// Keeps the newest sample at least `RATE_WINDOW` old as the baseline,
+    /// and everything after it.
+    pub fn record(&self, at: Instant, totals: MetricTotals) {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        samples.push_back((at, totals));
+        while samples
+            .get(1)
+            .is_some_and(|(sampled, _)| at.duration_since(*sampled) >= RATE_WINDOW)
+        {
+            samples.pop_front();
+        }
+    }
+
+    /// `None` until a sample older than a second exists.
+    pub fn rates(&self, at: Instant, totals: &MetricTotals) -> Option<MetricTotals> {
+        let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        let (sampled, baseline) = samples.front()?;
+        let elapsed = at.checked_duration_since(*sampled)?;
+        (elapsed >= Duration::from_secs(1)).then(|| totals.per_minute(baseline, elapsed))
+    }
+}
+
+/// Translates the registry into the shape the admin UI reads. Names and
+/// fields only change together with the response schema, whatever happens
+/// to the Prometheus metric names.
+pub fn snapshot(families: &[MetricFamily], sampler: &MetricsSampler) -> MetricsSnapshotResponse {
+    let totals = MetricTotals::from_families(families);
+    let rates = sampler.rates(Instant::now(), &totals);
+    let rate = |pick: fn(&MetricTotals) -> f64| rates.as_ref().map(pick);
+
+    let mut region_pools: BTreeMap<String, PoolMetrics> = BTreeMap::new();
+    for metric in series(families, "db_region_pool_connections") {
+        if let Some(region) = label(metric, "region") {
+            let pool = region_pools.entry(region.to_owned()).or_default();
+            pool.set(label(metric, "state"), metric.get_gauge().value());
+        }
+    }
+    let mut database_pool = PoolMetrics::default();
+    for metric in series(families, "db_pool_connections") {
+        database_pool.set(label(metric, "state"), metric.get_gauge().value());
+    }
+
+    let mut circuit_breakers: Vec<CircuitBreakerState> = series(families, "circuit_breaker_state")
+        .filter_map(|metric| {
+            Some(CircuitBreakerState {
+                name: label(metric, "service")?.to_owned(),
+                state: match metric.get_gauge().value() as u8 {
+                    0 => BreakerStatus::Closed,
+                    1 => BreakerStatus::Open,
+                    _ => BreakerStatus::HalfOpen,
+                },
+            })
+        })
+        .collect();
+    circuit_breakers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    MetricsSnapshotResponse {
+        generated_at: Utc::now().to_rfc3339(),
+        logins: AttemptMetrics {
+            succeeded_total: totals.logins_succeeded as u64,
+            failed_total: totals.logins_failed as u64,
+            succeeded_per_minute: rate(|r| r.logins_succeeded),
+            failed_per_minute: rate(|r| r.logins_failed),
+        },
+        registrations: AttemptMetrics {
+            succeeded_total: totals.registrations_succeeded as u64,
+            failed_total: totals.registrations_failed as u64,
+            succeeded_per_minute: rate(|r| r.registrations_succeeded),
+            failed_per_minute: rate(|r| r.registrations_failed),
+        },
+        errors: ErrorMetrics {
+            database_total: totals.database_errors as u64,
+            redis_total: totals.redis_errors as u64,
+            rate_limited_total: totals.rate_limited as u64,
+            database_per_minute: rate(|r| r.database_errors),
+            redis_per_minute: rate(|r| r.redis_errors),
+            rate_limited_per_minute: rate(|r| r.rate_limited),
+        },
+        circuit_breakers,
+        database_pool,
+        region_pools,
+    }
+}
+
+fn series<'a>(families: &'a [MetricFamily], name: &'a str) -> impl Iterator<Item = &'a Metric> {
+    families
+        .iter()
+        .filter(move |family| family.name() == name)
+        .flat_map(|family| family.get_metric())
+}
+
+fn label<'a>(metric: &'a Metric, name: &str) -> Option<&'a str> {
+    metric
+        .get_label()
+        .iter()
+        .find(|pair| pair.name() == name)
+        .map(|pair| pair.value())
+}
+
+/// Sum of a counter's series whose labels include every pair in `labels`.
+fn sum(families: &[MetricFamily], name: &str, labels: &[(&str, &str)]) -> f64 {
+    series(families, name)
+        .filter(|metric| {
+            labels
+                .iter()
+                .all(|(key, value)| label(metric, key) == Some(*value))
+        })
+        .map(|metric| metric.get_counter().value())
+        .sum()
+}
@@ -0,0 +1,8 @@
+pub(crate) mod dto;
+pub(crate) mod handler;
+pub(crate) mod metrics;
+pub(crate) mod model;
+pub(crate) mod service;
+
+#[cfg(test)]
+mod tests;
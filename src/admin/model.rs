@@ -0,0 +1,48 @@
+use std::fmt;
+
+use crate::app::AppError;
+
+/// The whitelist of operational actions. Anything else is rejected before
+/// reaching the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminAction {
+    FlushPreparedCache,
+    ResetCircuitBreakers,
+    RotateCookieSecret,
+    RotateSigningKey,
+}
+
+impl AdminAction {
+    pub const ALL: [AdminAction; 4] = [
+        AdminAction::FlushPreparedCache,
+        AdminAction::ResetCircuitBreakers,
+        AdminAction::RotateCookieSecret,
+        AdminAction::RotateSigningKey,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdminAction::FlushPreparedCache => "flush-prepared-cache",
+            AdminAction::ResetCircuitBreakers => "reset-circuit-breakers",
+            AdminAction::RotateCookieSecret => "rotate-cookie-secret",
+            AdminAction::RotateSigningKey => "rotate-signing-key",
+        }
+    }
+}
+
+impl fmt::Display for AdminAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<&str> for AdminAction {
+    type Error = AppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.as_str() == value)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown action: {}", value)))
+    }
+}
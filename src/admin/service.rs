@@ -0,0 +1,291 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::{
+    admin::{
+        dto::{
+            AccountLockoutResponse, ActionResponse, CeremonyLimitResponse,
+            CircuitBreakerListResponse, MaintenanceResponse, MetricsSnapshotResponse,
+            UpdateCeremonyLimitRequest, UpdateMaintenanceRequest,
+        },
+        metrics::{self, MetricsSampler},
+        model::AdminAction,
+    },
+    app::{AppError, middleware::maintenance::MaintenanceMode},
+    audit::{
+        model::{AuditContext, AuditEntry, AuditEvent},
+        traits::AuditLogger,
+    },
+    auth::{
+        ceremony_limit::CeremonyLimiter,
+        jwt::{AccessTokenClaims, JwtService, claims::JwtClaims},
+        throttle::AccountThrottle,
+    },
+    config::CircuitBreaker,
+    utils::{PgNotifier, PreparedStatementCache, normalize_username},
+};
+
+pub struct AdminService<J, A>
+where
+    J: JwtService + 'static,
+    A: AuditLogger + 'static,
+{
+    jwt_service: Arc<J>,
+    circuit_breakers: Vec<Arc<CircuitBreaker>>,
+    maintenance: Arc<MaintenanceMode>,
+    audit_logger: Arc<A>,
+    cache_flush: Option<Arc<PgNotifier>>,
+    ceremony_limiter: Arc<CeremonyLimiter>,
+    account_throttle: Option<Arc<AccountThrottle>>,
+    metrics: Arc<MetricsSampler>,
+}
+
+impl<J, A> AdminService<J, A>
+where
+    J: JwtService + 'static,
+    A: AuditLogger + 'static,
+{
+    pub fn new(
+        jwt_service: Arc<J>,
+        circuit_breakers: Vec<Arc<CircuitBreaker>>,
+        maintenance: Arc<MaintenanceMode>,
+        audit_logger: Arc<A>,
+    ) -> Self {
+        Self {
+            jwt_service,
+            circuit_breakers,
+            maintenance,
+            audit_logger,
+            cache_flush: None,
+            ceremony_limiter: Arc::default(),
+            account_throttle: None,
+            metrics: Arc::default(),
+        }
+    }
+
+    /// Passes prepared statement cache flushes on to the other instances.
+    pub fn with_cache_flush(mut self, cache_flush: Option<Arc<PgNotifier>>) -> Self {
+        self.cache_flush = cache_flush;
+        self
+    }
+
+    /// The limiter the auth service gates WebAuthn finish steps with.
+    pub fn with_ceremony_limiter(mut self, ceremony_limiter: Arc<CeremonyLimiter>) -> Self {
+        self.ceremony_limiter = ceremony_limiter;
+        self
+    }
+
+    /// The failure counters the auth service throttles accounts with.
+    pub fn with_account_throttle(mut self, account_throttle: Option<Arc<AccountThrottle>>) -> Self {
+        self.account_throttle = account_throttle;
+        self
+    }
+
+    /// Runs a whitelisted action. Every attempt is audited, whether it
+    /// succeeds or not.
+    pub async fn run(
+        &self,
+        name: &str,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<ActionResponse, AppError> {
+        let result = match AdminAction::try_from(name) {
+            Ok(action) => self.execute(action).await,
+            Err(e) => Err(e),
+        };
+
+        let mut details = serde_json::json!({ "action": name });
+        if let Ok(message) = &result {
+            details["message"] = serde_json::Value::from(message.as_str());
+        }
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AdminAction,
+                ctx,
+                Some(actor.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*actor.sub())
+            .with_details(details),
+        );
+
+        Ok(ActionResponse {
+            action: name.to_owned(),
+            message: result?,
+            performed_at: Utc::now().to_rfc3339(),
+        })
+    }
+
+    pub fn circuit_breakers(&self) -> CircuitBreakerListResponse {
+        CircuitBreakerListResponse {
+            circuit_breakers: self
+                .circuit_breakers
+                .iter()
+                .map(|breaker| breaker.as_ref().into())
+                .collect(),
+        }
+    }
+
+    /// Starts sampling the counters the metrics snapshot turns into rates.
+    pub fn spawn_metrics_sampler(&self) {
+        self.metrics.spawn();
+    }
+
+    pub fn metrics_snapshot(&self) -> MetricsSnapshotResponse {
+        metrics::snapshot(&prometheus::gather(), &self.metrics)
+    }
+
+    pub fn ceremony_limit(&self) -> CeremonyLimitResponse {
+        let max_concurrent = self.ceremony_limiter.limit();
+        CeremonyLimitResponse {
+            max_concurrent,
+            available: (max_concurrent > 0).then(|| self.ceremony_limiter.available()),
+        }
+    }
+
+    /// Changes the finish step limit of this instance until the next
+    /// restart, which goes back to `WEBAUTHN_MAX_CONCURRENT_FINISHES`.
+    pub fn set_ceremony_limit(
+        &self,
+        request: UpdateCeremonyLimitRequest,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> CeremonyLimitResponse {
+        let previous = self.ceremony_limiter.set_limit(request.max_concurrent);
+        tracing::info!(
+            previous,
+            max_concurrent = request.max_concurrent,
+            "WebAuthn finish step limit changed"
+        );
+        self.audit_logger.record(
+            AuditEntry::new(AuditEvent::AdminAction, ctx, Some(actor.username()), Ok(()))
+                .with_user_id(*actor.sub())
+                .with_details(serde_json::json!({
+                    "action": "set-ceremony-limit",
+                    "previous": previous,
+                    "max_concurrent": request.max_concurrent,
+                })),
+        );
+
+        self.ceremony_limit()
+    }
+
+    /// Switches maintenance mode on or off, on every instance at once since
+    /// the flag lives in Redis.
+    pub async fn set_maintenance(
+        &self,
+        request: UpdateMaintenanceRequest,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<MaintenanceResponse, AppError> {
+        let result = self.maintenance.set(request.enabled).await;
+
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AdminAction,
+                ctx,
+                Some(actor.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*actor.sub())
+            .with_details(serde_json::json!({
+                "action": "set-maintenance",
+                "enabled": request.enabled,
+            })),
+        );
+
+        result?;
+        Ok(MaintenanceResponse {
+            enabled: request.enabled,
+            updated_at: Utc::now().to_rfc3339(),
+        })
+    }
+
+    /// Lifts the lock on `username` and forgets its failures, on every
+    /// instance at once since they live in Redis.
+    pub async fn unlock_account(
+        &self,
+        username: &str,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<AccountLockoutResponse, AppError> {
+        let username = normalize_username(username);
+        let result = match &self.account_throttle {
+            Some(throttle) => throttle.unlock(&username).await.and_then(|state| {
+                state.ok_or_else(|| {
+                    AppError::NotFound(String::from("No failures recorded for this account"))
+                })
+            }),
+            None => Err(AppError::NotFound(String::from(
+                "Account throttling is disabled",
+            ))),
+        };
+
+        let mut details = serde_json::json!({ "action": "unlock-account", "target": username });
+        if let Ok(state) = &result {
+            details["failures"] = serde_json::Value::from(state.failures);
+            details["locked"] = serde_json::Value::from(state.locked_until_ms.is_some());
+        }
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AdminAction,
+                ctx,
+                Some(actor.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*actor.sub())
+            .with_details(details),
+        );
+
+        let state = result?;
+        Ok(AccountLockoutResponse {
+            username,
+            failures: state.failures,
+            locked_until: state.locked_until().map(|at| at.to_rfc3339()),
+            unlocked_at: Utc::now().to_rfc3339(),
+        })
+    }
+
+    async fn execute(&self, action: AdminAction) -> Result<String, AppError> {
+        match action {
+            AdminAction::FlushPreparedCache => {
+                let removed = PreparedStatementCache::clear_all();
+                match &self.cache_flush {
+                    Some(cache_flush) => {
+                        cache_flush.notify("").await?;
+                        Ok(format!(
+                            "Flushed {} prepared statements, other instances notified",
+                            removed
+                        ))
+                    }
+                    None => Ok(format!("Flushed {} prepared statements", removed)),
+                }
+            }
+            AdminAction::ResetCircuitBreakers => {
+                let names: Vec<&str> = self
+                    .circuit_breakers
+                    .iter()
+                    .map(|breaker| {
+                        breaker.reset();
+                        breaker.name()
+                    })
+                    .collect();
+                Ok(format!("Reset circuit breakers: {}", names.join(", ")))
+            }
+            AdminAction::RotateCookieSecret => {
+                self.jwt_service.rotate_refresh_secret().await?;
+                Ok(String::from(
+                    "Refresh cookie secret rotated, existing sessions must log in again",
+                ))
+            }
+            AdminAction::RotateSigningKey => {
+                let kid = self.jwt_service.rotate_signing_key().await?;
+                Ok(format!(
+                    "Access tokens are now signed with key {}, previous keys stay valid until their tokens expire",
+                    kid
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::{Duration, Instant};
+
+use prometheus::{CounterVec, GaugeVec, Opts, Registry};
+
+use crate::admin::{
+    dto::{BreakerStatus, PoolMetrics},
+    metrics::{MetricTotals, MetricsSampler, snapshot},
+};
+
+fn registry() -> Registry {
+    let registry = Registry::new();
+
+    let logins = CounterVec::new(
+        Opts::new("webauthn_login_attempts_total", "logins"),
+        &["status"],
+    )
+    .unwrap();
+    logins.with_label_values(&["success"]).inc_by(30.0);
+    logins.with_label_values(&["failure"]).inc_by(4.0);
+    registry.register(Box::new(logins)).unwrap();
+
+    let db_errors = CounterVec::new(
+        Opts::new("db_errors_total", "db errors"),
+        &["operation", "error_type"],
+    )
+    .unwrap();
+    db_errors.with_label_values(&["select", "timeout"]).inc();
+    db_errors
+        .with_label_values(&["insert", "unique"])
+        .inc_by(2.0);
+    registry.register(Box::new(db_errors)).unwrap();
+
+    let breakers =
+        GaugeVec::new(Opts::new("circuit_breaker_state", "breakers"), &["service"]).unwrap();
+    breakers.with_label_values(&["redis"]).set(1.0);
+    breakers.with_label_values(&["database"]).set(0.0);
+    registry.register(Box::new(breakers)).unwrap();
+
+    let pool = GaugeVec::new(Opts::new("db_pool_connections", "pool"), &["state"]).unwrap();
+    pool.with_label_values(&["active"]).set(3.0);
+    pool.with_label_values(&["idle"]).set(1.0);
+    pool.with_label_values(&["max"]).set(16.0);
+    registry.register(Box::new(pool)).unwrap();
+
+    let regions = GaugeVec::new(
+        Opts::new("db_region_pool_connections", "region pools"),
+        &["region", "state"],
+    )
+    .unwrap();
+    regions.with_label_values(&["eu", "max"]).set(8.0);
+    registry.register(Box::new(regions)).unwrap();
+
+    registry
+}
+
+#[test]
+fn test_snapshot_translates_curated_metrics() {
+    let response = snapshot(&registry().gather(), &MetricsSampler::default());
+
+    assert_eq!(response.logins.succeeded_total, 30);
+    assert_eq!(response.logins.failed_total, 4);
+    assert_eq!(response.logins.succeeded_per_minute, None);
+    assert_eq!(response.registrations.succeeded_total, 0);
+    assert_eq!(response.errors.database_total, 3);
+    assert_eq!(response.errors.redis_total, 0);
+
+    let breakers: Vec<(&str, BreakerStatus)> = response
+        .circuit_breakers
+        .iter()
+        .map(|breaker| (breaker.name.as_str(), breaker.state))
+        .collect();
+    assert_eq!(
+        breakers,
+        vec![
+            ("database", BreakerStatus::Closed),
+            ("redis", BreakerStatus::Open)
+        ]
+    );
+    assert_eq!(
+        response.database_pool,
+        PoolMetrics {
+            active: 3,
+            idle: 1,
+            max: 16
+        }
+    );
+    assert_eq!(response.region_pools["eu"].max, 8);
+}
+
+#[test]
+fn test_sampler_rates_are_per_minute_over_the_window() {
+    let sampler = MetricsSampler::default();
+    let start = Instant::now();
+    let totals = |logins: f64| MetricTotals {
+        logins_succeeded: logins,
+        ..MetricTotals::default()
+    };
+
+    assert_eq!(sampler.rates(start, &totals(0.0)), None);
+
+    sampler.record(start, totals(0.0));
+    sampler.record(start + Duration::from_secs(15), totals(5.0));
+    let rates = sampler
+        .rates(start + Duration::from_secs(30), &totals(10.0))
+        .unwrap();
+    assert_eq!(rates.logins_succeeded, 20.0);
+
+    // Past the window, the oldest sample gives way to the newest one at
+    // least a minute old.
+    sampler.record(start + Duration::from_secs(75), totals(30.0));
+    let rates = sampler
+        .rates(start + Duration::from_secs(135), &totals(90.0))
+        .unwrap();
+    assert_eq!(rates.logins_succeeded, 85.0 / 2.0);
+}
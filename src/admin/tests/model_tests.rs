@@ -0,0 +1,20 @@
+use crate::{admin::model::AdminAction, app::AppError};
+
+#[test]
+fn test_action_names_round_trip() {
+    for action in AdminAction::ALL {
+        assert_eq!(AdminAction::try_from(action.as_str()).unwrap(), action);
+    }
+}
+
+#[test]
+fn test_unknown_action_is_rejected() {
+    let result = AdminAction::try_from("drop-database");
+
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+}
+
+#[test]
+fn test_action_names_are_case_sensitive() {
+    assert!(AdminAction::try_from("Toggle-Maintenance").is_err());
+}
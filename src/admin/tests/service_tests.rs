@@ -0,0 +1,231 @@
+use std::sync::{Arc, atomic::Ordering};
+
+use crate::{
+    admin::{
+        dto::{UpdateCeremonyLimitRequest, UpdateMaintenanceRequest},
+        service::AdminService,
+    },
+    app::{AppError, middleware::maintenance::MaintenanceMode},
+    audit::model::{AuditContext, AuditEvent, AuditOutcome},
+    config::{CircuitBreaker, CircuitBreakerConfig},
+    utils::mocks::{MockAuditLogger, MockJwt, admin_claims},
+};
+
+struct Fixture {
+    service: AdminService<MockJwt, MockAuditLogger>,
+    jwt: Arc<MockJwt>,
+    maintenance: Arc<MaintenanceMode>,
+    audit: Arc<MockAuditLogger>,
+}
+
+fn fixture() -> Fixture {
+    let jwt = Arc::new(MockJwt::default());
+    let maintenance = Arc::new(MaintenanceMode::default());
+    let audit = Arc::new(MockAuditLogger::default());
+    let breakers = vec![
+        Arc::new(CircuitBreaker::new(
+            "database",
+            CircuitBreakerConfig::default(),
+        )),
+        Arc::new(CircuitBreaker::new(
+            "redis",
+            CircuitBreakerConfig::default().with_backoff(1, 15),
+        )),
+    ];
+
+    let service = AdminService::new(
+        Arc::clone(&jwt),
+        breakers,
+        Arc::clone(&maintenance),
+        Arc::clone(&audit),
+    );
+    Fixture {
+        service,
+        jwt,
+        maintenance,
+        audit,
+    }
+}
+
+#[tokio::test]
+async fn test_set_maintenance() {
+    let Fixture {
+        service,
+        maintenance,
+        audit,
+        ..
+    } = fixture();
+
+    for enabled in [true, true, false] {
+        let response = service
+            .set_maintenance(
+                UpdateMaintenanceRequest { enabled },
+                &admin_claims(&["admin:actions"]),
+                &AuditContext::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.enabled, enabled);
+        assert_eq!(maintenance.is_enabled(), enabled);
+    }
+
+    let entries = audit.entries.lock().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].details["action"], "set-maintenance");
+    assert_eq!(entries[2].details["enabled"], false);
+}
+
+#[tokio::test]
+async fn test_toggle_maintenance_is_no_longer_an_action() {
+    let Fixture { service, .. } = fixture();
+
+    let result = service
+        .run(
+            "toggle-maintenance",
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await;
+
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_rotate_cookie_secret() {
+    let Fixture { service, jwt, .. } = fixture();
+
+    let response = service
+        .run(
+            "rotate-cookie-secret",
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.action, "rotate-cookie-secret");
+    assert!(jwt.rotated.load(Ordering::Relaxed));
+}
+
+#[tokio::test]
+async fn test_rotate_signing_key_reports_kid() {
+    let Fixture { service, .. } = fixture();
+
+    let response = service
+        .run(
+            "rotate-signing-key",
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert!(response.message.contains("next-key"));
+}
+
+#[tokio::test]
+async fn test_reset_circuit_breakers_lists_breakers() {
+    let Fixture { service, .. } = fixture();
+
+    let response = service
+        .run(
+            "reset-circuit-breakers",
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.message, "Reset circuit breakers: database, redis");
+}
+
+#[test]
+fn test_circuit_breakers_report_their_config() {
+    let Fixture { service, .. } = fixture();
+
+    let response = service.circuit_breakers();
+
+    let redis = &response.circuit_breakers[1];
+    assert_eq!(response.circuit_breakers[0].name, "database");
+    assert_eq!(redis.name, "redis");
+    assert_eq!(redis.backoff_initial_secs, 1);
+    assert_eq!(redis.backoff_max_secs, 15);
+}
+
+#[tokio::test]
+async fn test_flush_prepared_cache() {
+    let Fixture { service, .. } = fixture();
+
+    let response = service
+        .run(
+            "flush-prepared-cache",
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert!(response.message.starts_with("Flushed "));
+}
+
+#[tokio::test]
+async fn test_unknown_action_is_not_found() {
+    let Fixture {
+        service,
+        maintenance,
+        ..
+    } = fixture();
+
+    let result = service
+        .run(
+            "shutdown",
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await;
+
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+    assert!(!maintenance.is_enabled());
+}
+
+#[tokio::test]
+async fn test_actions_are_audited() {
+    let Fixture { service, audit, .. } = fixture();
+    let actor = admin_claims(&["admin:actions"]);
+
+    service
+        .run("reset-circuit-breakers", &actor, &AuditContext::default())
+        .await
+        .unwrap();
+    let _ = service
+        .run("shutdown", &actor, &AuditContext::default())
+        .await;
+
+    let entries = audit.entries.lock().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.event == AuditEvent::AdminAction));
+    assert!(entries.iter().all(|e| e.user_id == Some(actor.sub)));
+    assert_eq!(entries[0].outcome, AuditOutcome::Success);
+    assert_eq!(entries[0].details["action"], "reset-circuit-breakers");
+    assert_eq!(entries[1].outcome, AuditOutcome::Failure);
+    assert_eq!(entries[1].details["action"], "shutdown");
+}
+
+#[tokio::test]
+async fn test_set_ceremony_limit_is_audited() {
+    let Fixture { service, audit, .. } = fixture();
+    assert_eq!(service.ceremony_limit().available, None);
+
+    let response = service.set_ceremony_limit(
+        UpdateCeremonyLimitRequest { max_concurrent: 3 },
+        &admin_claims(&["admin:actions"]),
+        &AuditContext::default(),
+    );
+
+    assert_eq!(response.max_concurrent, 3);
+    assert_eq!(response.available, Some(3));
+    let entries = audit.entries.lock().unwrap();
+    assert_eq!(entries[0].details["action"], "set-ceremony-limit");
+    assert_eq!(entries[0].details["previous"], 0);
+    assert_eq!(entries[0].details["max_concurrent"], 3);
+}
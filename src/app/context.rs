@@ -0,0 +1,215 @@
+use std::net::IpAddr;
+
+use axum::http::{
+    HeaderMap, HeaderName,
+    header::{ORIGIN, USER_AGENT},
+};
+use uuid::Uuid;
+
+use crate::{
+    audit::model::AuditContext,
+    auth::jwt::{AccessTokenClaims, claims::JwtClaims},
+    config::tenant::DEFAULT_TENANT,
+};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+pub const TENANT_HEADER: HeaderName = HeaderName::from_static("x-tenant-id");
+/// The data residency region of the caller, as pinned by the proxy.
+pub const REGION_HEADER: HeaderName = HeaderName::from_static("x-data-region");
+/// ISO 3166-1 alpha-2 code of the client, as resolved by the proxy.
+pub const COUNTRY_HEADER: HeaderName = HeaderName::from_static("x-client-country");
+/// Autonomous system number of the client, as resolved by the proxy.
+pub const ASN_HEADER: HeaderName = HeaderName::from_static("x-client-asn");
+/// Self-declared name of the calling application, e.g. `ios-2.3.1`.
+pub const CLIENT_APP_HEADER: HeaderName = HeaderName::from_static("x-client-app");
+
+const MAX_REQUEST_ID_LEN: usize = 128;
+const MAX_TENANT_LEN: usize = 64;
+const MAX_REGION_LEN: usize = 64;
+const MAX_CLIENT_APP_LEN: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+    static REQUEST_ORIGIN: Option<String>;
+    static REQUEST_TENANT: String;
+    static REQUEST_REGION: Option<String>;
+}
+
+/// Reuses the caller's `X-Request-Id` when it is a sane token, so traces
+/// line up with whatever sits in front of the server.
+pub fn request_id(headers: &HeaderMap) -> String {
+    header_token(headers, &REQUEST_ID_HEADER, MAX_REQUEST_ID_LEN)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Makes `request_id` visible to `current_request_id` while `future` runs.
+pub async fn scope_request_id<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+/// The id of the request being handled, for code with no access to it, such
+/// as error responses. `None` outside a request.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// Makes `origin` visible to `current_origin` while `future` runs.
+pub async fn scope_origin<F: Future>(origin: Option<String>, future: F) -> F::Output {
+    REQUEST_ORIGIN.scope(origin, future).await
+}
+
+/// The `Origin` of the request being handled, which selects the relying
+/// party and cookie attributes. `None` outside a request or without one.
+pub fn current_origin() -> Option<String> {
+    REQUEST_ORIGIN.try_with(Option::clone).ok().flatten()
+}
+
+/// Makes `tenant` visible to `current_tenant` while `future` runs.
+pub async fn scope_tenant<F: Future>(tenant: String, future: F) -> F::Output {
+    REQUEST_TENANT.scope(tenant, future).await
+}
+
+/// The tenant whose data the request being handled may touch, which every
+/// repository query is scoped to. The default tenant outside a request.
+pub fn current_tenant() -> String {
+    REQUEST_TENANT
+        .try_with(String::clone)
+        .unwrap_or_else(|_| DEFAULT_TENANT.to_owned())
+}
+
+/// Makes `region` visible to `current_region` while `future` runs.
+pub async fn scope_region<F: Future>(region: Option<String>, future: F) -> F::Output {
+    REQUEST_REGION.scope(region, future).await
+}
+
+/// The region whose database holds the accounts of the request being
+/// handled, which the auth repositories route to. `None`, the home
+/// database, outside a request or without one.
+pub fn current_region() -> Option<String> {
+    REQUEST_REGION.try_with(Option::clone).ok().flatten()
+}
+
+/// Scopes the tenant, region and origin of `context` while `future` runs.
+/// The tenant must have been resolved, as `attach_context` does.
+pub async fn scope_context<F: Future>(context: &RequestContext, future: F) -> F::Output {
+    let tenant = context
+        .tenant
+        .clone()
+        .unwrap_or_else(|| DEFAULT_TENANT.to_owned());
+    scope_tenant(
+        tenant,
+        scope_region(
+            context.region.clone(),
+            scope_origin(context.origin.clone(), future),
+        ),
+    )
+    .await
+}
+
+/// Everything known about the caller of the current request. Built once by
+/// the context middleware and shared through the request extensions, so new
+/// features read it from here instead of growing their own extraction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    /// Only taken from a trusted proxy, like the forwarded client IP. The
+    /// context middleware replaces it with the tenant the request resolved to.
+    pub tenant: Option<String>,
+    /// Trusted proxy only, as the tenant, and likewise replaced with the
+    /// region the request resolved to.
+    pub region: Option<String>,
+    /// Where the client connects from; trusted proxy only, as the tenant.
+    pub country: Option<String>,
+    pub asn: Option<u32>,
+    /// Sent by the client itself, so it labels but never identifies it.
+    pub client_app: Option<String>,
+    /// The browser's `Origin`, absent on same-origin GETs and non-browser
+    /// clients.
+    pub origin: Option<String>,
+    /// Filled from a valid bearer token; `None` for anonymous callers.
+    pub subject: Option<Subject>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subject {
+    pub user_id: Uuid,
+    pub username: String,
+    pub roles: Vec<String>,
+}
+
+impl RequestContext {
+    pub fn from_headers(headers: &HeaderMap, ip: Option<IpAddr>, trust_proxy: bool) -> Self {
+        let request_id = request_id(headers);
+        let tenant = trust_proxy
+            .then(|| header_token(headers, &TENANT_HEADER, MAX_TENANT_LEN))
+            .flatten();
+        let region = trust_proxy
+            .then(|| header_token(headers, &REGION_HEADER, MAX_REGION_LEN))
+            .flatten();
+        let country = trust_proxy.then(|| country(headers)).flatten();
+        let asn = trust_proxy.then(|| asn(headers)).flatten();
+
+        Self {
+            request_id,
+            ip,
+            user_agent: headers
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned),
+            tenant,
+            region,
+            country,
+            asn,
+            client_app: header_token(headers, &CLIENT_APP_HEADER, MAX_CLIENT_APP_LEN),
+            origin: headers
+                .get(ORIGIN)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned),
+            subject: None,
+        }
+    }
+
+    pub fn with_subject(mut self, claims: &AccessTokenClaims) -> Self {
+        self.subject = Some(Subject {
+            user_id: *claims.sub(),
+            username: claims.username().to_owned(),
+            roles: claims.grants().roles.clone(),
+        });
+        self
+    }
+
+    pub fn audit(&self) -> AuditContext {
+        AuditContext::new(self.ip, self.user_agent.as_deref())
+            .with_network(self.country.clone(), self.asn)
+            .with_client_app(self.client_app.clone())
+    }
+}
+
+fn header_token(headers: &HeaderMap, name: &HeaderName, max_len: usize) -> Option<String> {
+    let value = headers.get(name)?.to_str().ok()?.trim();
+    let valid = !value.is_empty()
+        && value.len() <= max_len
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+
+    valid.then(|| value.to_owned())
+}
+
+fn country(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(COUNTRY_HEADER)?.to_str().ok()?.trim();
+    (value.len() == 2 && value.chars().all(|c| c.is_ascii_alphabetic()))
+        .then(|| value.to_ascii_uppercase())
+}
+
+/// Accepts both `13335` and `AS13335`.
+fn asn(headers: &HeaderMap) -> Option<u32> {
+    let value = headers.get(ASN_HEADER)?.to_str().ok()?.trim();
+    let number = value
+        .strip_prefix("AS")
+        .or_else(|| value.strip_prefix("as"))
+        .unwrap_or(value);
+    number.parse().ok()
+}
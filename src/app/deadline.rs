@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use axum::http::{HeaderMap, HeaderName};
+use tokio::time::Instant;
+
+use crate::app::{AppError, middleware::metrics::track_deadline_skip};
+
+/// Milliseconds the caller is willing to wait, e.g. what is left of its own
+/// deadline. It can only shorten the route's budget.
+pub const REQUEST_TIMEOUT_HEADER: HeaderName = HeaderName::from_static("x-request-timeout");
+
+/// Work starting with less than this left would finish after the caller
+/// has given up.
+const DEADLINE_MARGIN: Duration = Duration::from_millis(5);
+
+tokio::task_local! {
+    static DEADLINE: Deadline;
+}
+
+/// When the current request stops being worth answering. Set by the request
+/// policy middleware, both as a request extension and for the backends,
+/// which check it before each query or command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    pub fn after(budget: Duration) -> Self {
+        Self(Instant::now() + budget)
+    }
+
+    /// The deadline of the request being handled. `None` outside a request
+    /// and on routes without a timeout.
+    pub fn current() -> Option<Self> {
+        DEADLINE.try_with(|deadline| *deadline).ok()
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_nearly_expired(&self) -> bool {
+        self.remaining() <= DEADLINE_MARGIN
+    }
+}
+
+/// Makes `deadline` visible to `Deadline::current` while `future` runs.
+pub async fn scope_deadline<F: Future>(deadline: Deadline, future: F) -> F::Output {
+    DEADLINE.scope(deadline, future).await
+}
+
+/// Refuses to start `backend` work for a request about to time out, so it
+/// does not hold a connection for an answer nobody will read.
+pub fn check_deadline(backend: &'static str) -> Result<(), AppError> {
+    match Deadline::current() {
+        Some(deadline) if deadline.is_nearly_expired() => {
+            track_deadline_skip(backend);
+            Err(AppError::RequestTimeout(String::from(
+                "Request ran out of time",
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The caller's `X-Request-Timeout`; anything but a whole number of
+/// milliseconds is ignored.
+pub fn requested_timeout(headers: &HeaderMap) -> Option<Duration> {
+    let millis = headers
+        .get(REQUEST_TIMEOUT_HEADER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_millis(millis))
+}
@@ -1,11 +1,126 @@
-use std::fmt::{self};
+use std::{
+    collections::BTreeMap,
+    fmt::{self},
+};
 
-use axum::{Json, http::StatusCode, response::IntoResponse};
+use axum::{
+    Json,
+    http::{HeaderValue, StatusCode, header},
+    response::IntoResponse,
+};
+use serde_json::Value;
 
-#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+use crate::app::context::current_request_id;
+
+/// Stable identifiers for errors, so clients branch on these instead of the
+/// message. Every error has one; the generic ones follow the status.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    InternalError,
+    NotFound,
+    AlreadyExists,
+    Unauthorized,
+    Forbidden,
+    AuthenticatorNotAllowed,
+    BadRequest,
+    ServiceUnavailable,
+    RateLimited,
+    RequestTimeout,
+    PayloadTooLarge,
+    RouteNotFound,
+    MethodNotAllowed,
+    /// The token or ceremony was valid once but has expired; sign in again.
+    AuthSessionExpired,
+    AuthTokenRevoked,
+    AuthRefreshTokenMissing,
+    /// The token's `azp` does not match the application the route is
+    /// reserved to.
+    AuthTokenWrongClient,
+    /// The token was issued in another tenant than the request's.
+    AuthTokenWrongTenant,
+    /// `details.permission` names the scope the caller lacks.
+    MissingPermission,
+    /// WebAuthn rejected the passkey's answer to the ceremony: a bad
+    /// signature, another origin or challenge, or a missing user check.
+    PasskeyRejected,
+    /// The passkey may have been cloned and is locked until its owner
+    /// confirms it. `details.credential_id` names it, when one was used.
+    CredentialLocked,
+    /// Well formed, but reserved or a look-alike of another script.
+    UsernameNotAllowed,
+    UsernameTaken,
+    /// The authenticator already holds a passkey for this account.
+    CredentialAlreadyRegistered,
+    /// The role can only be registered by an administrator or with an
+    /// invitation issued for it. `details.role` names it.
+    RoleNotAllowed,
+    /// Registration is by invitation only, and `invitation` was missing or
+    /// is unknown, used up, expired, revoked or for another role.
+    InvitationRequired,
+    /// The account is suspended by an administrator: no sign-in or refresh.
+    AccountSuspended,
+    /// Too many failed sign-ins or registrations in a row locked the account
+    /// until `Retry-After`, or until an administrator unlocks it.
+    AccountLocked,
+    /// `X-Tenant-Id` names a tenant this deployment does not serve.
+    UnknownTenant,
+    /// `X-Data-Region` names a region without a database.
+    UnknownRegion,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct ErrorResponse {
     #[schema(example = "username must be at least 3 characters")]
     pub message: String,
+    /// Stable identifier for errors clients may want to branch on.
+    #[schema(example = "USERNAME_TAKEN")]
+    pub code: ErrorCode,
+    /// Extra fields some codes carry, as documented on the code.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    pub details: BTreeMap<String, Value>,
+    /// Same as the `X-Request-Id` response header; quote it when reporting a problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub request_id: Option<String>,
+}
+
+/// RFC 9457 body, sent instead of `ErrorResponse` when the client asks for
+/// `application/problem+json`.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    #[schema(example = "about:blank")]
+    pub problem_type: String,
+    #[schema(example = "Conflict")]
+    pub title: String,
+    #[schema(example = 409)]
+    pub status: u16,
+    #[schema(example = "already exists: Username already exists")]
+    pub detail: String,
+    pub code: ErrorCode,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    pub details: BTreeMap<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ProblemDetails {
+    pub fn new(status: StatusCode, error: ErrorResponse) -> Self {
+        Self {
+            problem_type: String::from("about:blank"),
+            title: status.canonical_reason().unwrap_or("Error").to_owned(),
+            status: status.as_u16(),
+            detail: error.message,
+            code: error.code,
+            details: error.details,
+            request_id: error.request_id,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -14,9 +129,25 @@ pub enum AppError {
     NotFound(String),
     AlreadyExists(String),
     Unauthorized(String),
+    Forbidden(String),
+    AuthenticatorNotAllowed(String),
     BadRequest(String),
     ServiceUnavailable(String),
     CircuitBreakerOpen(String),
+    TooManyRequests(u64),
+    RequestTimeout(String),
+    PayloadTooLarge(String),
+    RouteNotFound(String),
+    MethodNotAllowed(String),
+    /// Any of the above with a more specific code; built by `with_code`.
+    Detailed(Box<DetailedError>),
+}
+
+#[derive(Debug)]
+pub struct DetailedError {
+    pub error: AppError,
+    pub code: ErrorCode,
+    pub details: BTreeMap<String, Value>,
 }
 
 impl fmt::Display for AppError {
@@ -26,30 +157,128 @@ impl fmt::Display for AppError {
             AppError::NotFound(msg) => write!(f, "not found: {}", msg),
             AppError::AlreadyExists(msg) => write!(f, "already exists: {}", msg),
             AppError::Unauthorized(msg) => write!(f, "unauthorized: {}", msg),
+            AppError::Forbidden(msg) => write!(f, "forbidden: {}", msg),
+            AppError::AuthenticatorNotAllowed(msg) => {
+                write!(f, "authenticator not allowed: {}", msg)
+            }
             AppError::BadRequest(msg) => write!(f, "bad request: {}", msg),
             AppError::ServiceUnavailable(msg) => write!(f, "service unavailable: {}", msg),
             AppError::CircuitBreakerOpen(msg) => write!(f, "circuit breaker open: {}", msg),
+            AppError::TooManyRequests(retry_after) => {
+                write!(f, "too many requests: retry after {} seconds", retry_after)
+            }
+            AppError::RequestTimeout(msg) => write!(f, "request timeout: {}", msg),
+            AppError::PayloadTooLarge(msg) => write!(f, "payload too large: {}", msg),
+            AppError::RouteNotFound(msg) => write!(f, "route not found: {}", msg),
+            AppError::MethodNotAllowed(msg) => write!(f, "method not allowed: {}", msg),
+            AppError::Detailed(detailed) => detailed.error.fmt(f),
         }
     }
 }
 
 impl std::error::Error for AppError {}
 
+impl AppError {
+    /// Replaces the generic code, keeping the status and message.
+    pub fn with_code(self, code: ErrorCode) -> Self {
+        match self {
+            AppError::Detailed(mut detailed) => {
+                detailed.code = code;
+                AppError::Detailed(detailed)
+            }
+            error => AppError::Detailed(Box::new(DetailedError {
+                error,
+                code,
+                details: BTreeMap::new(),
+            })),
+        }
+    }
+
+    pub fn with_detail(self, key: &str, value: impl Into<Value>) -> Self {
+        let code = self.code();
+        let AppError::Detailed(mut detailed) = self.with_code(code) else {
+            unreachable!("with_code always returns a detailed error");
+        };
+        detailed.details.insert(key.to_owned(), value.into());
+        AppError::Detailed(detailed)
+    }
+
+    /// The error without its code and details, for matching on the kind.
+    pub fn kind(&self) -> &AppError {
+        match self {
+            AppError::Detailed(detailed) => detailed.error.kind(),
+            error => error,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::InternalServer(_) => ErrorCode::InternalError,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::AlreadyExists(_) => ErrorCode::AlreadyExists,
+            AppError::Unauthorized(_) => ErrorCode::Unauthorized,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::AuthenticatorNotAllowed(_) => ErrorCode::AuthenticatorNotAllowed,
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::ServiceUnavailable(_) | AppError::CircuitBreakerOpen(_) => {
+                ErrorCode::ServiceUnavailable
+            }
+            AppError::TooManyRequests(_) => ErrorCode::RateLimited,
+            AppError::RequestTimeout(_) => ErrorCode::RequestTimeout,
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::RouteNotFound(_) => ErrorCode::RouteNotFound,
+            AppError::MethodNotAllowed(_) => ErrorCode::MethodNotAllowed,
+            AppError::Detailed(detailed) => detailed.code,
+        }
+    }
+
+    pub fn details(&self) -> Option<&BTreeMap<String, Value>> {
+        match self {
+            AppError::Detailed(detailed) => Some(&detailed.details),
+            _ => None,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self.kind() {
+            AppError::InternalServer(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::AlreadyExists(_) => StatusCode::CONFLICT,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::AuthenticatorNotAllowed(_) => StatusCode::FORBIDDEN,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::CircuitBreakerOpen(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::RequestTimeout(_) => StatusCode::REQUEST_TIMEOUT,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::RouteNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            AppError::Detailed(_) => unreachable!("kind never returns a detailed error"),
+        }
+    }
+}
+
+/// The body also rides along as a response extension, so the problem+json
+/// middleware can rebuild it without parsing.
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
-        let (status, message) = match self {
-            AppError::InternalServer(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            AppError::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
-            AppError::AlreadyExists(_) => (StatusCode::CONFLICT, self.to_string()),
-            AppError::Unauthorized(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
-            AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::ServiceUnavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
-            AppError::CircuitBreakerOpen(_) => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
+        let body = ErrorResponse {
+            message: self.to_string(),
+            code: self.code(),
+            details: self.details().cloned().unwrap_or_default(),
+            request_id: current_request_id(),
         };
 
-        let body = Json(ErrorResponse { message });
-
-        (status, body).into_response()
+        let mut response = (self.status(), Json(&body)).into_response();
+        if let AppError::TooManyRequests(retry_after) = self.kind() {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(*retry_after));
+        }
+        response.extensions_mut().insert(body);
+        response
     }
 }
 
@@ -65,6 +294,13 @@ impl From<tokio_postgres::Error> for AppError {
     }
 }
 
+#[cfg(feature = "sqlx")]
+impl From<sqlx::Error> for AppError {
+    fn from(value: sqlx::Error) -> Self {
+        AppError::InternalServer(value.to_string())
+    }
+}
+
 impl From<redis::RedisError> for AppError {
     fn from(value: redis::RedisError) -> Self {
         AppError::InternalServer(value.to_string())
@@ -91,12 +327,70 @@ impl From<uuid::Error> for AppError {
 
 impl From<axum::extract::rejection::JsonRejection> for AppError {
     fn from(value: axum::extract::rejection::JsonRejection) -> Self {
+        if value.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            AppError::PayloadTooLarge(value.body_text())
+        } else {
+            AppError::BadRequest(value.to_string())
+        }
+    }
+}
+
+/// Reading a body fails with a length error once it passes the route's
+/// limit; anything else is a malformed request.
+impl From<axum::Error> for AppError {
+    fn from(value: axum::Error) -> Self {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(&value);
+        while let Some(error) = source {
+            if error.is::<http_body_util::LengthLimitError>() {
+                return AppError::PayloadTooLarge(String::from("Request body is too large"));
+            }
+            source = error.source();
+        }
         AppError::BadRequest(value.to_string())
     }
 }
 
+impl From<axum::extract::rejection::QueryRejection> for AppError {
+    fn from(value: axum::extract::rejection::QueryRejection) -> Self {
+        AppError::BadRequest(value.to_string())
+    }
+}
+
+impl From<axum::extract::rejection::FormRejection> for AppError {
+    fn from(value: axum::extract::rejection::FormRejection) -> Self {
+        if value.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            AppError::PayloadTooLarge(value.body_text())
+        } else {
+            AppError::BadRequest(value.to_string())
+        }
+    }
+}
+
+#[cfg(feature = "http-client")]
+impl From<reqwest::Error> for AppError {
+    fn from(value: reqwest::Error) -> Self {
+        AppError::ServiceUnavailable(value.to_string())
+    }
+}
+
+#[cfg(feature = "notifications")]
+impl From<minijinja::Error> for AppError {
+    fn from(value: minijinja::Error) -> Self {
+        AppError::InternalServer(value.to_string())
+    }
+}
+
 impl From<jsonwebtoken::errors::Error> for AppError {
     fn from(value: jsonwebtoken::errors::Error) -> Self {
-        AppError::Unauthorized(value.to_string())
+        let expired = matches!(
+            value.kind(),
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature
+        );
+        let error = AppError::Unauthorized(value.to_string());
+        if expired {
+            error.with_code(ErrorCode::AuthSessionExpired)
+        } else {
+            error
+        }
     }
 }
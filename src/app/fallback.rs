@@ -0,0 +1,20 @@
+use axum::http::{Method, Uri};
+
+use crate::app::AppError;
+
+/// Replaces axum's empty 404 so unknown paths get the usual error body.
+pub async fn route_not_found(method: Method, uri: Uri) -> AppError {
+    let path = uri.path();
+    let hint = if path.len() > 1 && path.ends_with('/') {
+        " (routes do not take a trailing slash)"
+    } else {
+        ""
+    };
+
+    AppError::RouteNotFound(format!("no route for {} {}{}", method, path, hint))
+}
+
+/// Replaces axum's empty 405. The router adds the `Allow` header itself.
+pub async fn method_not_allowed(method: Method, uri: Uri) -> AppError {
+    AppError::MethodNotAllowed(format!("{} is not supported on {}", method, uri.path()))
+}
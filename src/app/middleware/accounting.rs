@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    app::{AppState, RequestContext},
+    traffic::model::RequestOutcome,
+};
+
+/// Feeds the per-IP traffic report with the outcome of every API request.
+pub async fn track_request(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let ip = request
+        .extensions()
+        .get::<RequestContext>()
+        .and_then(|context| context.ip);
+    let response = next.run(request).await;
+
+    if let Some(ip) = ip {
+        state
+            .traffic_service
+            .record(ip, RequestOutcome::from_status(response.status()));
+    }
+
+    response
+}
@@ -0,0 +1,19 @@
+use std::sync::Arc;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+
+use crate::{
+    app::{AppError, AppState, middleware::context::base_context},
+    audit::model::AuditContext,
+};
+
+impl FromRequestParts<Arc<AppState>> for AuditContext {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(base_context(parts, state).audit())
+    }
+}
@@ -1,13 +1,19 @@
-use std::sync::Arc;
+use std::{marker::PhantomData, sync::Arc};
 
-use axum::{extract::FromRequestParts, http::request::Parts};
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    http::request::Parts,
+};
 
 use crate::{
-    app::{AppError, AppState},
-    auth::jwt::{AccessTokenClaims, JwtService, claims::JwtClaims},
+    app::{AppError, AppState, ErrorCode, context::current_tenant},
+    auth::{
+        jwt::{AccessTokenClaims, JwtService},
+        permissions::Permission,
+    },
 };
 
-const UNAUTHORIZED_MESSAGE: &str = "You are unauthorized";
+pub(crate) const UNAUTHORIZED_MESSAGE: &str = "You are unauthorized";
 const BEARER_PREFIX: &str = "Bearer ";
 
 impl FromRequestParts<Arc<AppState>> for AccessTokenClaims {
@@ -17,37 +23,76 @@ impl FromRequestParts<Arc<AppState>> for AccessTokenClaims {
         parts: &mut Parts,
         state: &Arc<AppState>,
     ) -> Result<Self, Self::Rejection> {
+        // The request context may have validated the token already.
+        if let Some(claims) = parts.extensions.get::<AccessTokenClaims>() {
+            return Ok(claims.clone());
+        }
+
         let auth_header = extract_auth_header(parts)?;
         is_bearer_token(auth_header)?;
         let token = extract_token(auth_header);
-        let claims = state.jwt_service.validate_access(token).await?;
+        let expected_azp = state.client_apps.expected_party(parts.uri.path());
+        let claims = state
+            .jwt_service
+            .validate_access(token, expected_azp)
+            .await?;
+        parts.extensions.insert(claims.clone());
 
         Ok(claims)
     }
 }
 
-#[cfg_attr(not(feature = "strict"), allow(dead_code))]
-pub struct AdminClaims(pub AccessTokenClaims);
+/// `None` for a caller sending no `Authorization` header. A header that
+/// fails validation is still rejected rather than treated as anonymous.
+impl OptionalFromRequestParts<Arc<AppState>> for AccessTokenClaims {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        if !parts
+            .headers
+            .contains_key(axum::http::header::AUTHORIZATION)
+        {
+            return Ok(None);
+        }
+
+        <Self as FromRequestParts<_>>::from_request_parts(parts, state)
+            .await
+            .map(Some)
+    }
+}
+
+/// Access claims of a caller granted `P`. Rejects with 403 when the token is
+/// valid but lacks the scope, and with 401 when it was issued in another
+/// tenant than the one the request resolved to.
+pub struct RequirePermission<P: Permission>(pub AccessTokenClaims, PhantomData<P>);
 
-impl FromRequestParts<Arc<AppState>> for AdminClaims {
+impl<P: Permission> FromRequestParts<Arc<AppState>> for RequirePermission<P> {
     type Rejection = AppError;
 
     async fn from_request_parts(
         parts: &mut Parts,
         state: &Arc<AppState>,
     ) -> Result<Self, Self::Rejection> {
-        let claims = AccessTokenClaims::from_request_parts(parts, state).await?;
-
-        match claims.role() {
-            Some(role) if role == "admin" => Ok(AdminClaims(claims)),
-            _ => Err(AppError::Unauthorized(String::from(
-                "Admin access required",
-            ))),
+        let claims =
+            <AccessTokenClaims as FromRequestParts<_>>::from_request_parts(parts, state).await?;
+        claims.check_tenant(&current_tenant())?;
+
+        if claims.grants().allows(P::SCOPE) {
+            Ok(RequirePermission(claims, PhantomData))
+        } else {
+            Err(
+                AppError::Forbidden(format!("Missing permission: {}", P::SCOPE))
+                    .with_code(ErrorCode::MissingPermission)
+                    .with_detail("permission", P::SCOPE),
+            )
         }
     }
 }
 
-impl std::ops::Deref for AdminClaims {
+impl<P: Permission> std::ops::Deref for RequirePermission<P> {
     type Target = AccessTokenClaims;
 
     fn deref(&self) -> &Self::Target {
@@ -55,6 +100,24 @@ impl std::ops::Deref for AdminClaims {
     }
 }
 
+/// A caller allowed to introspect tokens, by `INTROSPECTION_*`.
+pub struct IntrospectionClient;
+
+impl FromRequestParts<Arc<AppState>> for IntrospectionClient {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        if state.introspection.authorize(&parts.headers) {
+            Ok(IntrospectionClient)
+        } else {
+            Err(AppError::Unauthorized(UNAUTHORIZED_MESSAGE.to_string()))
+        }
+    }
+}
+
 fn extract_auth_header(parts: &Parts) -> Result<&str, AppError> {
     parts
         .headers
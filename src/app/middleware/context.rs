@@ -0,0 +1,105 @@
+use std::{net::IpAddr, sync::Arc};
+
+use crate::{
+    app::{
+        AppError, AppState,
+        context::{REQUEST_ID_HEADER, RequestContext, request_id, scope_context, scope_request_id},
+    },
+    auth::jwt::AccessTokenClaims,
+    utils::{client_ip, client_ip_from_parts},
+};
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{HeaderMap, HeaderValue, header::AUTHORIZATION, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+
+/// Outermost layer: settles the request id before anything logs, rewrites
+/// `X-Request-Id` so every later layer reads the same value, and echoes it
+/// back on the response, errors included.
+pub async fn propagate_request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request_id(request.headers());
+    let value = HeaderValue::from_str(&request_id).unwrap();
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, value.clone());
+
+    let mut response = scope_request_id(request_id, next.run(request)).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+/// Builds the `RequestContext` for every API request, and scopes its
+/// origin for the relying party and cookie selection and its tenant and
+/// region for the repositories. A tenant or region the deployment does not
+/// serve is rejected.
+pub async fn attach_context(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let ip = client_ip(&request, state.rate_limiter.trust_proxy());
+    let context = resolve_context(&state, request.headers(), ip)?;
+    request.extensions_mut().insert(context.clone());
+
+    Ok(scope_context(&context, next.run(request)).await)
+}
+
+/// The context of a request with these headers, with its tenant and region
+/// resolved. Shared with the callers that do not go through the router.
+pub fn resolve_context(
+    state: &AppState,
+    headers: &HeaderMap,
+    ip: Option<IpAddr>,
+) -> Result<RequestContext, AppError> {
+    let mut context = RequestContext::from_headers(headers, ip, state.rate_limiter.trust_proxy());
+    let tenant = state
+        .tenants
+        .resolve(context.origin.as_deref(), context.tenant.as_deref())?
+        .to_owned();
+    context.tenant = Some(tenant);
+    context.region = state
+        .regions
+        .resolve(context.origin.as_deref(), context.region.as_deref())?
+        .map(str::to_owned);
+    Ok(context)
+}
+
+/// The context as built by the middleware, without the subject.
+pub fn base_context(parts: &Parts, state: &Arc<AppState>) -> RequestContext {
+    parts
+        .extensions
+        .get::<RequestContext>()
+        .cloned()
+        .unwrap_or_else(|| {
+            let trust_proxy = state.rate_limiter.trust_proxy();
+            let ip = client_ip_from_parts(parts, trust_proxy);
+            RequestContext::from_headers(&parts.headers, ip, trust_proxy)
+        })
+}
+
+/// Resolves the subject on first use, so anonymous routes never pay for a
+/// token check. An invalid token leaves the caller anonymous rather than
+/// rejecting: routes that need a user extract `AccessTokenClaims` instead.
+impl FromRequestParts<Arc<AppState>> for RequestContext {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let context = base_context(parts, state);
+        if context.subject.is_some() || !parts.headers.contains_key(AUTHORIZATION) {
+            return Ok(context);
+        }
+
+        let Ok(claims) = AccessTokenClaims::from_request_parts(parts, state).await else {
+            return Ok(context);
+        };
+
+        let context = context.with_subject(&claims);
+        parts.extensions.insert(context.clone());
+        Ok(context)
+    }
+}
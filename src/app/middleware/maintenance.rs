@@ -0,0 +1,145 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use redis::aio::ConnectionManager;
+
+use crate::{
+    app::{AppError, AppState},
+    config::CircuitBreaker,
+    redis_get, redis_set,
+    utils::BaseRedisRepository,
+};
+
+/// Shared so switching maintenance on or off reaches every instance.
+const KEY: &str = "maintenance:enabled";
+
+/// How often an instance rereads the shared flag, which bounds how long the
+/// others keep their old state after a switch.
+pub const MAINTENANCE_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Paths that keep working during maintenance so operators can inspect
+/// the service and switch maintenance off again, and so downstream services
+/// can still verify the access tokens already issued. The login banner stays
+/// up so the login page can explain the outage.
+const EXEMPT_PREFIXES: &[&str] = &[
+    "/admin/",
+    "/healthz",
+    "/livez",
+    "/readyz",
+    "/startupz",
+    "/.well-known/",
+    "/auth/banner",
+];
+
+/// Whether requests are refused for maintenance. The flag lives in Redis and
+/// each instance keeps a copy in memory, so checking it never waits on
+/// Redis. Without Redis (the default) it applies to this instance only.
+#[derive(Default)]
+pub struct MaintenanceMode {
+    enabled: AtomicBool,
+    store: Option<BaseRedisRepository>,
+}
+
+impl MaintenanceMode {
+    pub fn shared(conn_manager: ConnectionManager, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            store: Some(BaseRedisRepository::new(conn_manager, circuit_breaker)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Switches maintenance on or off for every instance. Nothing changes
+    /// when Redis cannot be written, so instances do not disagree.
+    pub async fn set(&self, enabled: bool) -> Result<(), AppError> {
+        if let Some(store) = &self.store {
+            store
+                .execute_with_circuit_breaker(move |mut conn| async move {
+                    use redis::AsyncCommands;
+                    let _: () = redis_set!({ conn.set(KEY, enabled).await })?;
+                    Ok(())
+                })
+                .await?;
+        }
+        self.enabled.store(enabled, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Rereads the shared flag every `MAINTENANCE_REFRESH_INTERVAL`. While
+    /// Redis is unreachable the last known state stays.
+    pub fn spawn_refresh(self: &Arc<Self>) {
+        if self.store.is_none() {
+            return;
+        }
+
+        let mode = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(MAINTENANCE_REFRESH_INTERVAL);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = mode.reload().await {
+                    tracing::debug!("Maintenance mode refresh failed: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn reload(&self) -> Result<(), AppError> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+
+        let enabled = store
+            .execute_with_circuit_breaker(|mut conn| async move {
+                use redis::AsyncCommands;
+                let enabled: Option<bool> = redis_get!({ conn.get(KEY).await })?;
+                Ok(enabled.unwrap_or(false))
+            })
+            .await?;
+        if self.enabled.swap(enabled, Ordering::Relaxed) != enabled {
+            tracing::info!(enabled, "Maintenance mode changed by another instance");
+        }
+        Ok(())
+    }
+
+    pub fn is_exempt(path: &str) -> bool {
+        EXEMPT_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+    }
+
+    /// Refuses a request to `path` while maintenance is on, unless the path
+    /// is exempt. gRPC methods are checked by their full path too, and none
+    /// is exempt.
+    pub fn check(&self, path: &str) -> Result<(), AppError> {
+        if self.is_enabled() && !Self::is_exempt(path) {
+            return Err(AppError::ServiceUnavailable(String::from(
+                "Service is under maintenance",
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub async fn maintenance(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    state.maintenance.check(request.uri().path())?;
+
+    Ok(next.run(request).await)
+}
@@ -2,6 +2,8 @@ use std::sync::LazyLock;
 
 use axum::{http::StatusCode, response::IntoResponse};
 use axum_prometheus::PrometheusMetricLayer;
+#[cfg(feature = "otel")]
+use opentelemetry::{KeyValue, global, metrics::Histogram};
 
 pub static REGISTRATION_ATTEMPTS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
     prometheus::register_counter_vec!(
@@ -21,6 +23,15 @@ pub static LOGIN_ATTEMPTS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
     .unwrap()
 });
 
+pub static RECOVERY_ATTEMPTS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "account_recovery_attempts_total",
+        "Total number of account recovery attempts",
+        &["step", "status"]
+    )
+    .unwrap()
+});
+
 pub static TOKEN_OPERATIONS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
     prometheus::register_counter_vec!(
         "jwt_token_operations_total",
@@ -39,6 +50,51 @@ pub static HEALTH_CHECKS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
     .unwrap()
 });
 
+pub static CREDENTIAL_PAYLOAD_BYTES: LazyLock<prometheus::HistogramVec> = LazyLock::new(|| {
+    prometheus::register_histogram_vec!(
+        "webauthn_credential_payload_bytes",
+        "Size of the WebAuthn credential JSON submitted to finish a ceremony",
+        &["ceremony"],
+        vec![
+            256.0, 512.0, 1024.0, 2048.0, 4096.0, 8192.0, 16384.0, 65536.0
+        ]
+    )
+    .unwrap()
+});
+
+pub static CEREMONY_QUEUE_DURATION: LazyLock<prometheus::HistogramVec> = LazyLock::new(|| {
+    prometheus::register_histogram_vec!(
+        "webauthn_ceremony_queue_seconds",
+        "Time a WebAuthn finish step waited for a verification slot",
+        &["ceremony"],
+        vec![
+            0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5
+        ]
+    )
+    .unwrap()
+});
+
+pub static CEREMONY_DURATION: LazyLock<prometheus::HistogramVec> = LazyLock::new(|| {
+    prometheus::register_histogram_vec!(
+        "webauthn_ceremony_duration_seconds",
+        "Time between beginning and finishing a WebAuthn ceremony",
+        &["ceremony", "outcome"],
+        vec![
+            0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0, 600.0
+        ]
+    )
+    .unwrap()
+});
+
+pub static PENDING_CEREMONIES: LazyLock<prometheus::GaugeVec> = LazyLock::new(|| {
+    prometheus::register_gauge_vec!(
+        "webauthn_pending_ceremonies",
+        "Number of begun WebAuthn ceremonies that have not finished or expired",
+        &["ceremony"]
+    )
+    .unwrap()
+});
+
 pub static DB_QUERY_DURATION: LazyLock<prometheus::HistogramVec> = LazyLock::new(|| {
     prometheus::register_histogram_vec!(
         "db_query_duration_seconds",
@@ -60,6 +116,15 @@ pub static DB_POOL_CONNECTIONS: LazyLock<prometheus::GaugeVec> = LazyLock::new(|
     .unwrap()
 });
 
+pub static DB_REGION_POOL_CONNECTIONS: LazyLock<prometheus::GaugeVec> = LazyLock::new(|| {
+    prometheus::register_gauge_vec!(
+        "db_region_pool_connections",
+        "Number of pool connections to each region's database",
+        &["region", "state"] // active, idle, max
+    )
+    .unwrap()
+});
+
 pub static DB_ERRORS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
     prometheus::register_counter_vec!(
         "db_errors_total",
@@ -81,6 +146,17 @@ pub static REDIS_OPERATION_DURATION: LazyLock<prometheus::HistogramVec> = LazyLo
     .unwrap()
 });
 
+// OTLP copies of the client call durations, under the semantic convention
+// name. They stay no-ops unless an OTLP endpoint is configured.
+#[cfg(feature = "otel")]
+static OTLP_CLIENT_OPERATION_DURATION: LazyLock<Histogram<f64>> = LazyLock::new(|| {
+    global::meter("rs-server")
+        .f64_histogram("db.client.operation.duration")
+        .with_unit("s")
+        .with_description("Duration of database and Redis client operations")
+        .build()
+});
+
 pub static REDIS_ERRORS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
     prometheus::register_counter_vec!(
         "redis_errors_total",
@@ -99,6 +175,197 @@ pub static CIRCUIT_BREAKER_STATE: LazyLock<prometheus::GaugeVec> = LazyLock::new
     .unwrap()
 });
 
+pub static RATE_LIMIT_REJECTIONS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "rate_limit_rejections_total",
+        "Total number of requests rejected by the rate limiter",
+        &["route", "scope"]
+    )
+    .unwrap()
+});
+
+pub static RATE_LIMIT_SHADOW_REJECTIONS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "rate_limit_shadow_rejections_total",
+        "Total number of requests the rate limiter would have rejected in shadow mode",
+        &["route", "scope"]
+    )
+    .unwrap()
+});
+
+pub static FEATURE_FLAG_ATTEMPTS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "feature_flag_attempts_total",
+        "Total number of login and registration attempts by feature flag variant",
+        &["flag", "variant", "flow", "status"]
+    )
+    .unwrap()
+});
+
+pub static REDIS_MEMORY_USED_BYTES: LazyLock<prometheus::Gauge> = LazyLock::new(|| {
+    prometheus::register_gauge!(
+        "redis_memory_used_bytes",
+        "Redis used_memory as of the last memory check"
+    )
+    .unwrap()
+});
+
+pub static REDIS_MEMORY_PRESSURE: LazyLock<prometheus::Gauge> = LazyLock::new(|| {
+    prometheus::register_gauge!(
+        "redis_memory_pressure",
+        "Whether Redis memory is above the pressure threshold (0=no, 1=yes)"
+    )
+    .unwrap()
+});
+
+pub static REDIS_MEMORY_SHED_WRITES: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "redis_memory_shed_writes_total",
+        "Total number of non-essential Redis writes skipped under memory pressure",
+        &["operation"]
+    )
+    .unwrap()
+});
+
+pub static CLEANUP_PURGED_ROWS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "cleanup_purged_rows_total",
+        "Total number of rows deleted by the cleanup job",
+        &["table"]
+    )
+    .unwrap()
+});
+
+pub static REVOCATION_FALLBACK_OPERATIONS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "revocation_fallback_operations_total",
+        "Total number of token revocation operations served without Redis",
+        &["operation", "store"]
+    )
+    .unwrap()
+});
+
+pub static LOGIN_ANOMALIES: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "login_anomalies_total",
+        "Total number of logins from a country, network or device new to the account",
+        &["reason"]
+    )
+    .unwrap()
+});
+
+pub static CLONE_SUSPECTED: LazyLock<prometheus::Counter> = LazyLock::new(|| {
+    prometheus::register_counter!(
+        "webauthn_clone_suspected_total",
+        "Total number of logins refused because the signature counter went backwards"
+    )
+    .unwrap()
+});
+
+pub static ACCOUNT_LOCKOUTS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "account_lockouts_total",
+        "Total number of accounts locked after repeated failed WebAuthn finish steps",
+        &["flow"]
+    )
+    .unwrap()
+});
+
+pub static LOGIN_CACHE: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "login_cache_total",
+        "Login lookup cache reads and invalidations",
+        &["event"] // hit, miss, invalidated
+    )
+    .unwrap()
+});
+
+pub static EVENTS_EXPORTED: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "event_export_total",
+        "Total number of domain events handed to the export sink, by outcome",
+        &["event", "outcome"] // exported, failed, dropped
+    )
+    .unwrap()
+});
+
+pub static PG_NOTIFICATIONS_RECEIVED: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "pg_notifications_received_total",
+        "Total number of Postgres notifications received by this instance",
+        &["channel"]
+    )
+    .unwrap()
+});
+
+pub static PREPARED_STATEMENTS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "db_prepared_statements_total",
+        "Prepared statement cache lookups and removals",
+        &["event"] // hit, miss, evicted, expired, invalidated
+    )
+    .unwrap()
+});
+
+pub static DEADLINE_SKIPS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "request_deadline_skips_total",
+        "Backend operations not started because the request was about to time out",
+        &["backend"] // postgres, redis
+    )
+    .unwrap()
+});
+
+pub static DB_REPLICA_READS: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "db_replica_reads_total",
+        "Reads meant for the replica, by where they were served",
+        &["result"] // replica, fallback
+    )
+    .unwrap()
+});
+
+#[cfg(feature = "http-client")]
+pub static HTTP_CLIENT_REQUEST_DURATION: LazyLock<prometheus::HistogramVec> = LazyLock::new(|| {
+    prometheus::register_histogram_vec!(
+        "http_client_request_duration_seconds",
+        "Outbound HTTP attempt duration in seconds",
+        &["destination", "outcome"], // 2xx, 3xx, 4xx, 5xx, error
+        vec![0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .unwrap()
+});
+
+#[cfg(feature = "http-client")]
+pub static HTTP_CLIENT_RETRIES: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "http_client_retries_total",
+        "Total number of outbound HTTP retries, and of those denied by the retry budget",
+        &["destination", "result"] // attempted, budget_exhausted
+    )
+    .unwrap()
+});
+
+#[cfg(feature = "notifications")]
+pub static NOTIFICATION_STREAM_ENTRIES: LazyLock<prometheus::GaugeVec> = LazyLock::new(|| {
+    prometheus::register_gauge_vec!(
+        "notification_stream_entries",
+        "Entries on the notification stream, as seen by the last worker poll",
+        &["state"] // length, pending
+    )
+    .unwrap()
+});
+
+#[cfg(feature = "notifications")]
+pub static NOTIFICATION_STREAM_PROCESSED: LazyLock<prometheus::CounterVec> = LazyLock::new(|| {
+    prometheus::register_counter_vec!(
+        "notification_stream_processed_total",
+        "Total number of notification stream entries processed by this instance",
+        &["outcome"] // delivered, retried, dropped
+    )
+    .unwrap()
+});
+
 /// Get Prometheus metrics
 ///
 /// Returns all metrics in Prometheus format for scraping by monitoring systems
@@ -138,6 +405,36 @@ pub fn track_login_attempt(success: bool) {
     LOGIN_ATTEMPTS.with_label_values(&[status]).inc();
 }
 
+pub fn track_recovery_attempt(step: &str, success: bool) {
+    let status = if success { "success" } else { "failure" };
+    RECOVERY_ATTEMPTS.with_label_values(&[step, status]).inc();
+}
+
+pub fn track_credential_payload(ceremony: &str, bytes: usize) {
+    CREDENTIAL_PAYLOAD_BYTES
+        .with_label_values(&[ceremony])
+        .observe(bytes as f64);
+}
+
+pub fn track_ceremony_queue(ceremony: &str, duration_secs: f64) {
+    CEREMONY_QUEUE_DURATION
+        .with_label_values(&[ceremony])
+        .observe(duration_secs);
+}
+
+pub fn track_ceremony_duration(ceremony: &str, success: bool, duration_secs: f64) {
+    let outcome = if success { "success" } else { "failure" };
+    CEREMONY_DURATION
+        .with_label_values(&[ceremony, outcome])
+        .observe(duration_secs);
+}
+
+pub fn update_pending_ceremonies(ceremony: &str, count: i64) {
+    PENDING_CEREMONIES
+        .with_label_values(&[ceremony])
+        .set(count as f64);
+}
+
 pub fn track_token_operation(operation: &str, success: bool) {
     let status = if success { "success" } else { "failure" };
     TOKEN_OPERATIONS
@@ -154,6 +451,15 @@ pub fn track_db_query(operation: &str, table: &str, duration_secs: f64) {
     DB_QUERY_DURATION
         .with_label_values(&[operation, table])
         .observe(duration_secs);
+    #[cfg(feature = "otel")]
+    OTLP_CLIENT_OPERATION_DURATION.record(
+        duration_secs,
+        &[
+            KeyValue::new("db.system.name", "postgresql"),
+            KeyValue::new("db.operation.name", operation.to_owned()),
+            KeyValue::new("db.collection.name", table.to_owned()),
+        ],
+    );
 }
 
 pub fn track_db_error(operation: &str, error_type: &str) {
@@ -172,6 +478,18 @@ pub fn update_db_pool_stats(active: usize, idle: usize, max: usize) {
         .set(max as f64);
 }
 
+pub fn update_db_region_pool_stats(region: &str, active: usize, idle: usize, max: usize) {
+    DB_REGION_POOL_CONNECTIONS
+        .with_label_values(&[region, "active"])
+        .set(active as f64);
+    DB_REGION_POOL_CONNECTIONS
+        .with_label_values(&[region, "idle"])
+        .set(idle as f64);
+    DB_REGION_POOL_CONNECTIONS
+        .with_label_values(&[region, "max"])
+        .set(max as f64);
+}
+
 pub fn update_circuit_breaker_state(service: &str, state: u8) {
     // 0=closed, 1=open, 2=half-open
     CIRCUIT_BREAKER_STATE
@@ -183,6 +501,14 @@ pub fn track_redis_operation(operation: &str, duration_secs: f64) {
     REDIS_OPERATION_DURATION
         .with_label_values(&[operation])
         .observe(duration_secs);
+    #[cfg(feature = "otel")]
+    OTLP_CLIENT_OPERATION_DURATION.record(
+        duration_secs,
+        &[
+            KeyValue::new("db.system.name", "redis"),
+            KeyValue::new("db.operation.name", operation.to_owned()),
+        ],
+    );
 }
 
 pub fn track_redis_error(operation: &str, error_type: &str) {
@@ -190,3 +516,116 @@ pub fn track_redis_error(operation: &str, error_type: &str) {
         .with_label_values(&[operation, error_type])
         .inc();
 }
+
+pub fn track_rate_limit_rejection(route: &str, scope: &str) {
+    RATE_LIMIT_REJECTIONS
+        .with_label_values(&[route, scope])
+        .inc();
+}
+
+pub fn track_rate_limit_shadow_rejection(route: &str, scope: &str) {
+    RATE_LIMIT_SHADOW_REJECTIONS
+        .with_label_values(&[route, scope])
+        .inc();
+}
+
+pub fn track_feature_flag_attempt(flag: &str, variant: &str, flow: &str, success: bool) {
+    let status = if success { "success" } else { "failure" };
+    FEATURE_FLAG_ATTEMPTS
+        .with_label_values(&[flag, variant, flow, status])
+        .inc();
+}
+
+pub fn track_redis_memory(used_bytes: u64, pressure: bool) {
+    REDIS_MEMORY_USED_BYTES.set(used_bytes as f64);
+    REDIS_MEMORY_PRESSURE.set(if pressure { 1.0 } else { 0.0 });
+}
+
+pub fn track_redis_shed_write(operation: &str) {
+    REDIS_MEMORY_SHED_WRITES
+        .with_label_values(&[operation])
+        .inc();
+}
+
+pub fn track_cleanup_purge(table: &str, rows: u64) {
+    CLEANUP_PURGED_ROWS
+        .with_label_values(&[table])
+        .inc_by(rows as f64);
+}
+
+pub fn track_revocation_fallback(operation: &str, store: &str) {
+    REVOCATION_FALLBACK_OPERATIONS
+        .with_label_values(&[operation, store])
+        .inc();
+}
+
+pub fn track_login_anomaly(reason: &str) {
+    LOGIN_ANOMALIES.with_label_values(&[reason]).inc();
+}
+
+pub fn track_clone_suspected() {
+    CLONE_SUSPECTED.inc();
+}
+
+pub fn track_account_lockout(flow: &str) {
+    ACCOUNT_LOCKOUTS.with_label_values(&[flow]).inc();
+}
+
+pub fn track_login_cache(event: &str) {
+    LOGIN_CACHE.with_label_values(&[event]).inc();
+}
+
+#[cfg(feature = "http-client")]
+pub fn track_http_client_request(destination: &str, outcome: &str, duration_secs: f64) {
+    HTTP_CLIENT_REQUEST_DURATION
+        .with_label_values(&[destination, outcome])
+        .observe(duration_secs);
+}
+
+#[cfg(feature = "http-client")]
+pub fn track_http_client_retry(destination: &str, result: &str) {
+    HTTP_CLIENT_RETRIES
+        .with_label_values(&[destination, result])
+        .inc();
+}
+
+pub fn track_event_export(event: &str, outcome: &str) {
+    EVENTS_EXPORTED.with_label_values(&[event, outcome]).inc();
+}
+
+pub fn track_pg_notification(channel: &str) {
+    PG_NOTIFICATIONS_RECEIVED
+        .with_label_values(&[channel])
+        .inc();
+}
+
+pub fn track_prepared_statements(event: &str, count: usize) {
+    PREPARED_STATEMENTS
+        .with_label_values(&[event])
+        .inc_by(count as f64);
+}
+
+pub fn track_replica_read(result: &str) {
+    DB_REPLICA_READS.with_label_values(&[result]).inc();
+}
+
+pub fn track_deadline_skip(backend: &str) {
+    DEADLINE_SKIPS.with_label_values(&[backend]).inc();
+}
+
+#[cfg(feature = "notifications")]
+pub fn update_notification_stream(length: u64, pending: u64) {
+    NOTIFICATION_STREAM_ENTRIES
+        .with_label_values(&["length"])
+        .set(length as f64);
+    NOTIFICATION_STREAM_ENTRIES
+        .with_label_values(&["pending"])
+        .set(pending as f64);
+}
+
+#[cfg(feature = "notifications")]
+pub fn track_notification_processed(outcome: &str) {
+    NOTIFICATION_STREAM_PROCESSED
+        .with_label_values(&[outcome])
+        .inc();
+}
@@ -1,5 +1,16 @@
+pub(crate) mod accounting;
+pub(crate) mod audit;
 pub(crate) mod auth;
+pub(crate) mod context;
+pub(crate) mod maintenance;
 pub(crate) mod metrics;
+pub(crate) mod policy;
+pub(crate) mod problem;
+pub(crate) mod rate_limit;
+pub(crate) mod slo;
 pub(crate) mod tracing;
 
 pub(crate) use tracing::init_tracing;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use http_body_util::Limited;
+
+use crate::app::{
+    AppError, AppState,
+    deadline::{Deadline, requested_timeout, scope_deadline},
+};
+
+/// Applies the route's `RoutePolicy`: bodies past its limit are refused with
+/// 413, and a request still running at its deadline is dropped with 408.
+/// The deadline is also handed down as a `Deadline`, so backends stop taking
+/// work for the request shortly before.
+pub async fn enforce_request_policy(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let policy = state.request_policies.for_path(request.uri().path());
+
+    let declared_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared_length.is_some_and(|length| length > policy.max_body_bytes) {
+        return Err(AppError::PayloadTooLarge(String::from(
+            "Request body is too large",
+        )));
+    }
+
+    // Chunked bodies carry no length up front; they fail once they pass it.
+    let mut request = request.map(|body| Body::new(Limited::new(body, policy.max_body_bytes)));
+
+    let budget = state
+        .request_policies
+        .budget(&policy, requested_timeout(request.headers()));
+    let Some(timeout) = budget else {
+        return Ok(next.run(request).await);
+    };
+
+    let deadline = Deadline::after(timeout);
+    request.extensions_mut().insert(deadline);
+
+    tokio::time::timeout(timeout, scope_deadline(deadline, next.run(request)))
+        .await
+        .map_err(|_| {
+            AppError::RequestTimeout(format!(
+                "Request did not complete within {} ms",
+                timeout.as_millis()
+            ))
+        })
+}
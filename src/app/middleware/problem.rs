@@ -0,0 +1,64 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{
+        HeaderMap, HeaderValue,
+        header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE},
+    },
+    middleware::Next,
+    response::Response,
+};
+
+use crate::app::error::{ErrorResponse, ProblemDetails};
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Sends error bodies as RFC 9457 problem details to clients that prefer
+/// them; everyone else keeps getting `ErrorResponse`.
+pub async fn negotiate_error_format(request: Request, next: Next) -> Response {
+    let wants_problem = prefers_problem_json(request.headers());
+    let mut response = next.run(request).await;
+
+    let Some(error) = response.extensions_mut().remove::<ErrorResponse>() else {
+        return response;
+    };
+    if !wants_problem {
+        return response;
+    }
+
+    let problem = ProblemDetails::new(response.status(), error);
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+    Response::from_parts(parts, Body::from(serde_json::to_vec(&problem).unwrap()))
+}
+
+/// True when `Accept` lists problem+json at least as highly as plain JSON.
+/// Wildcards do not count: a client has to ask for the new format.
+pub fn prefers_problem_json(headers: &HeaderMap) -> bool {
+    let (mut problem, mut json) = (0.0_f32, 0.0_f32);
+
+    for value in headers.get_all(ACCEPT) {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        for range in value.split(',') {
+            let mut params = range.split(';');
+            let media = params.next().unwrap_or_default().trim();
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            if media.eq_ignore_ascii_case(PROBLEM_JSON) {
+                problem = problem.max(quality);
+            } else if media.eq_ignore_ascii_case("application/json") {
+                json = json.max(quality);
+            }
+        }
+    }
+
+    problem > 0.0 && problem >= json
+}
@@ -0,0 +1,233 @@
+use std::{net::IpAddr, sync::Arc};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, AppState, RequestContext, middleware::metrics, router::MAX_BODY_BYTES},
+    config::{CircuitBreaker, RateLimitConfig},
+    redis_pipeline,
+    utils::{BaseRedisRepository, normalize_username},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum RateLimitScope {
+    Ip,
+    Username,
+}
+
+impl RateLimitScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RateLimitScope::Ip => "ip",
+            RateLimitScope::Username => "username",
+        }
+    }
+}
+
+/// Requests seen inside the current window, including the one being evaluated.
+#[derive(Debug, Clone, Copy)]
+pub struct WindowUsage {
+    pub count: u64,
+    pub oldest_ms: i64,
+}
+
+impl WindowUsage {
+    /// Seconds until the oldest request leaves the window, or `None` when the
+    /// request fits within `limit`.
+    pub fn retry_after(&self, limit: u64, window_ms: i64, now_ms: i64) -> Option<u64> {
+        if self.count <= limit {
+            return None;
+        }
+
+        let remaining_ms = (self.oldest_ms + window_ms - now_ms).max(0);
+        Some((remaining_ms as u64).div_ceil(1000).max(1))
+    }
+}
+
+#[derive(Deserialize)]
+struct UsernameProbe {
+    username: String,
+}
+
+pub struct RateLimiter {
+    base: BaseRedisRepository,
+    config: RateLimitConfig,
+}
+
+impl RateLimiter {
+    pub fn new(
+        conn_manager: ConnectionManager,
+        circuit_breaker: Arc<CircuitBreaker>,
+        config: RateLimitConfig,
+    ) -> Self {
+        Self {
+            base: BaseRedisRepository::new(conn_manager, circuit_breaker),
+            config,
+        }
+    }
+
+    pub fn trust_proxy(&self) -> bool {
+        self.config.trust_proxy
+    }
+
+    /// Counts a request to `route` against the window of its client IP and,
+    /// when it names one, of its username. The gRPC service checks its
+    /// ceremonies under the REST routes, so both transports share a window.
+    pub async fn check_request(
+        &self,
+        route: &str,
+        ip: Option<IpAddr>,
+        username: Option<&str>,
+    ) -> Result<(), AppError> {
+        if let Some(ip) = ip {
+            self.check(RateLimitScope::Ip, route, &ip.to_string())
+                .await?;
+        }
+
+        if let Some(username) = username.map(normalize_username)
+            && !username.trim().is_empty()
+        {
+            self.check(RateLimitScope::Username, route, &username)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn check(
+        &self,
+        scope: RateLimitScope,
+        route: &str,
+        identifier: &str,
+    ) -> Result<(), AppError> {
+        let now_ms = Utc::now().timestamp_millis();
+
+        match self.record_hit(scope, route, identifier, now_ms).await {
+            Ok(usage) => enforce(&self.config, scope, route, identifier, usage, now_ms),
+            Err(e) => {
+                // Fail open: an unavailable Redis must not lock every user out.
+                tracing::warn!(scope = scope.as_str(), error = %e, "Rate limit check skipped");
+                Ok(())
+            }
+        }
+    }
+
+    async fn record_hit(
+        &self,
+        scope: RateLimitScope,
+        route: &str,
+        identifier: &str,
+        now_ms: i64,
+    ) -> Result<WindowUsage, AppError> {
+        let redis_key = key(scope, route, identifier);
+        let window_ms = self.config.window.as_millis() as i64;
+        let member = Uuid::new_v4().to_string();
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let (count, oldest): (u64, Vec<(String, f64)>) = redis_pipeline!({
+                    redis::pipe()
+                        .atomic()
+                        .zrembyscore(&redis_key, 0, now_ms - window_ms)
+                        .ignore()
+                        .zadd(&redis_key, &member, now_ms)
+                        .ignore()
+                        .zcard(&redis_key)
+                        .zrange_withscores(&redis_key, 0, 0)
+                        .pexpire(&redis_key, window_ms)
+                        .ignore()
+                        .query_async(&mut conn)
+                        .await
+                })?;
+
+                let oldest_ms = oldest.first().map_or(now_ms, |(_, score)| *score as i64);
+
+                Ok(WindowUsage { count, oldest_ms })
+            })
+            .await
+    }
+}
+
+/// Sliding-window limiter for the unauthenticated ceremony entry points.
+/// Requests are counted per client IP and, when the JSON body carries one,
+/// per username so a distributed attack on a single account is also caught.
+pub async fn rate_limit(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let limiter = &state.rate_limiter;
+    let route = request.uri().path().to_owned();
+    let ip = request
+        .extensions()
+        .get::<RequestContext>()
+        .and_then(|context| context.ip);
+
+    let (parts, body) = request.into_parts();
+    let bytes = to_bytes(body, MAX_BODY_BYTES).await?;
+    let probe = serde_json::from_slice::<UsernameProbe>(&bytes).ok();
+
+    limiter
+        .check_request(&route, ip, probe.as_ref().map(|p| p.username.as_str()))
+        .await?;
+
+    Ok(next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await)
+}
+
+/// Turns the window usage into the verdict for one request. Over the limit
+/// in shadow mode, the would-be rejection is only logged and counted.
+pub fn enforce(
+    config: &RateLimitConfig,
+    scope: RateLimitScope,
+    route: &str,
+    identifier: &str,
+    usage: WindowUsage,
+    now_ms: i64,
+) -> Result<(), AppError> {
+    let limit = match scope {
+        RateLimitScope::Ip => config.ip_max_requests,
+        RateLimitScope::Username => config.username_max_requests,
+    };
+    let window_ms = config.window.as_millis() as i64;
+
+    match usage.retry_after(limit, window_ms, now_ms) {
+        Some(retry_after) if config.shadow_mode => {
+            metrics::track_rate_limit_shadow_rejection(route, scope.as_str());
+            tracing::info!(
+                scope = scope.as_str(),
+                route = route,
+                identifier = identifier,
+                count = usage.count,
+                limit = limit,
+                retry_after = retry_after,
+                "Rate limit exceeded (shadow mode, request allowed)"
+            );
+            Ok(())
+        }
+        Some(retry_after) => {
+            metrics::track_rate_limit_rejection(route, scope.as_str());
+            tracing::warn!(
+                scope = scope.as_str(),
+                route = route,
+                identifier = identifier,
+                "Rate limit exceeded"
+            );
+            Err(AppError::TooManyRequests(retry_after))
+        }
+        None => Ok(()),
+    }
+}
+
+fn key(scope: RateLimitScope, route: &str, identifier: &str) -> String {
+    format!("rate_limit:{}:{}:{}", scope.as_str(), route, identifier)
+}
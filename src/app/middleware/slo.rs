@@ -0,0 +1,32 @@
+use std::{sync::Arc, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::app::AppState;
+
+/// Judges requests to routes with an objective by their status and
+/// latency. It wraps the request policy, so requests dropped at their
+/// deadline count against availability.
+pub async fn track_slo(
+    State(state): State<Arc<AppState>>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(route) = matched_path.filter(|path| state.slo_tracker.is_tracked(path.as_str()))
+    else {
+        return next.run(request).await;
+    };
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    state
+        .slo_tracker
+        .record(route.as_str(), response.status(), started.elapsed());
+
+    response
+}
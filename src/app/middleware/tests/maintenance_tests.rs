@@ -0,0 +1,44 @@
+use crate::app::{AppError, middleware::maintenance::MaintenanceMode};
+
+#[test]
+fn test_maintenance_disabled_by_default() {
+    assert!(!MaintenanceMode::default().is_enabled());
+}
+
+#[tokio::test]
+async fn test_set_is_explicit() {
+    let mode = MaintenanceMode::default();
+
+    mode.set(true).await.unwrap();
+    mode.set(true).await.unwrap();
+    assert!(mode.is_enabled());
+    mode.set(false).await.unwrap();
+    assert!(!mode.is_enabled());
+}
+
+#[test]
+fn test_admin_and_health_routes_are_exempt() {
+    assert!(MaintenanceMode::is_exempt("/admin/maintenance"));
+    assert!(MaintenanceMode::is_exempt("/healthz"));
+    assert!(MaintenanceMode::is_exempt("/livez"));
+    assert!(MaintenanceMode::is_exempt("/readyz"));
+    assert!(MaintenanceMode::is_exempt("/startupz"));
+    assert!(MaintenanceMode::is_exempt("/.well-known/jwks.json"));
+    assert!(MaintenanceMode::is_exempt("/auth/banner"));
+    assert!(!MaintenanceMode::is_exempt("/auth/login/begin"));
+}
+
+#[tokio::test]
+async fn test_check_refuses_rest_and_grpc_calls_while_enabled() {
+    let mode = MaintenanceMode::default();
+    assert!(mode.check("/auth.v1.Auth/BeginLogin").is_ok());
+
+    mode.set(true).await.unwrap();
+    for path in ["/auth/login/begin", "/auth.v1.Auth/BeginLogin"] {
+        assert!(matches!(
+            mode.check(path),
+            Err(AppError::ServiceUnavailable(_))
+        ));
+    }
+    assert!(mode.check("/healthz").is_ok());
+}
@@ -0,0 +1,6 @@
+#[cfg(test)]
+mod maintenance_tests;
+#[cfg(test)]
+mod problem_tests;
+#[cfg(test)]
+mod rate_limit_tests;
@@ -0,0 +1,130 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{HeaderMap, HeaderValue, Request, StatusCode, header},
+    middleware::from_fn,
+    response::Response,
+    routing::get,
+};
+use tower::ServiceExt;
+
+use crate::app::{
+    AppError, ErrorCode,
+    error::{ErrorResponse, ProblemDetails},
+    middleware::problem::{PROBLEM_JSON, negotiate_error_format, prefers_problem_json},
+};
+
+fn router() -> Router {
+    Router::new()
+        .route("/ok", get(|| async { "ok" }))
+        .route(
+            "/taken",
+            get(|| async {
+                Err::<(), _>(
+                    AppError::AlreadyExists(String::from("Username already exists"))
+                        .with_code(ErrorCode::UsernameTaken)
+                        .with_detail("username", "alice"),
+                )
+            }),
+        )
+        .route(
+            "/limited",
+            get(|| async { Err::<(), _>(AppError::TooManyRequests(30)) }),
+        )
+        .layer(from_fn(negotiate_error_format))
+}
+
+async fn send(path: &str, accept: Option<&str>) -> Response {
+    let mut request = Request::builder().uri(path);
+    if let Some(accept) = accept {
+        request = request.header(header::ACCEPT, accept);
+    }
+    router()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn body_bytes(response: Response) -> Vec<u8> {
+    to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .to_vec()
+}
+
+fn accept(value: &'static str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+    headers
+}
+
+#[test]
+fn test_prefers_problem_json_only_when_asked() {
+    assert!(prefers_problem_json(&accept("application/problem+json")));
+    assert!(prefers_problem_json(&accept(
+        "application/problem+json, application/json"
+    )));
+    assert!(prefers_problem_json(&accept(
+        "application/json;q=0.5, application/problem+json"
+    )));
+
+    assert!(!prefers_problem_json(&HeaderMap::new()));
+    assert!(!prefers_problem_json(&accept("*/*")));
+    assert!(!prefers_problem_json(&accept("application/json")));
+    assert!(!prefers_problem_json(&accept(
+        "application/problem+json;q=0.2, application/json"
+    )));
+    assert!(!prefers_problem_json(&accept(
+        "application/problem+json;q=0"
+    )));
+}
+
+#[tokio::test]
+async fn test_error_defaults_to_json_with_code_and_details() {
+    let response = send("/taken", None).await;
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    let body: ErrorResponse = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body.code, ErrorCode::UsernameTaken);
+    assert_eq!(body.details["username"], "alice");
+    assert_eq!(body.message, "already exists: Username already exists");
+}
+
+#[tokio::test]
+async fn test_error_as_problem_json_when_accepted() {
+    let response = send("/taken", Some(PROBLEM_JSON)).await;
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+    let bytes = body_bytes(response).await;
+    let problem: ProblemDetails = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(problem.problem_type, "about:blank");
+    assert_eq!(problem.title, "Conflict");
+    assert_eq!(problem.status, 409);
+    assert_eq!(problem.detail, "already exists: Username already exists");
+    assert_eq!(problem.code, ErrorCode::UsernameTaken);
+    assert_eq!(problem.details["username"], "alice");
+
+    let raw: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(raw["type"], "about:blank");
+    assert_eq!(raw["code"], "USERNAME_TAKEN");
+}
+
+#[tokio::test]
+async fn test_problem_json_keeps_headers() {
+    let response = send("/limited", Some(PROBLEM_JSON)).await;
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    let problem: ProblemDetails = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(problem.code, ErrorCode::RateLimited);
+}
+
+#[tokio::test]
+async fn test_success_is_left_alone() {
+    let response = send("/ok", Some(PROBLEM_JSON)).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_bytes(response).await, b"ok");
+}
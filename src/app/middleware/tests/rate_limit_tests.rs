@@ -0,0 +1,145 @@
+use crate::{
+    app::{
+        AppError,
+        middleware::{
+            metrics::{RATE_LIMIT_REJECTIONS, RATE_LIMIT_SHADOW_REJECTIONS},
+            rate_limit::{RateLimitScope, WindowUsage, enforce},
+        },
+    },
+    config::RateLimitConfig,
+};
+
+const WINDOW_MS: i64 = 60_000;
+
+#[test]
+fn test_retry_after_within_limit() {
+    let usage = WindowUsage {
+        count: 5,
+        oldest_ms: 0,
+    };
+    assert_eq!(usage.retry_after(5, WINDOW_MS, 10_000), None);
+}
+
+#[test]
+fn test_retry_after_over_limit() {
+    let usage = WindowUsage {
+        count: 6,
+        oldest_ms: 0,
+    };
+    assert_eq!(usage.retry_after(5, WINDOW_MS, 10_000), Some(50));
+}
+
+#[test]
+fn test_retry_after_rounds_up_partial_seconds() {
+    let usage = WindowUsage {
+        count: 6,
+        oldest_ms: 0,
+    };
+    assert_eq!(usage.retry_after(5, WINDOW_MS, 10_500), Some(50));
+}
+
+#[test]
+fn test_retry_after_is_at_least_one_second() {
+    let usage = WindowUsage {
+        count: 6,
+        oldest_ms: 0,
+    };
+    assert_eq!(usage.retry_after(5, WINDOW_MS, WINDOW_MS + 1), Some(1));
+}
+
+#[test]
+fn test_scope_labels() {
+    assert_eq!(RateLimitScope::Ip.as_str(), "ip");
+    assert_eq!(RateLimitScope::Username.as_str(), "username");
+}
+
+fn over_limit(config: &RateLimitConfig) -> WindowUsage {
+    WindowUsage {
+        count: config.username_max_requests + 1,
+        oldest_ms: 0,
+    }
+}
+
+#[test]
+fn test_shadow_mode_records_rejection_without_enforcing_it() {
+    let config = RateLimitConfig {
+        shadow_mode: true,
+        ..RateLimitConfig::default()
+    };
+    let route = "/shadow/records";
+    let shadowed = RATE_LIMIT_SHADOW_REJECTIONS.with_label_values(&[route, "username"]);
+    let rejected = RATE_LIMIT_REJECTIONS.with_label_values(&[route, "username"]);
+
+    let result = enforce(
+        &config,
+        RateLimitScope::Username,
+        route,
+        "alice",
+        over_limit(&config),
+        10_000,
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(shadowed.get(), 1.0);
+    assert_eq!(rejected.get(), 0.0);
+}
+
+#[test]
+fn test_shadow_mode_ignores_requests_within_limit() {
+    let config = RateLimitConfig {
+        shadow_mode: true,
+        ..RateLimitConfig::default()
+    };
+    let route = "/shadow/within";
+    let usage = WindowUsage {
+        count: config.ip_max_requests,
+        oldest_ms: 0,
+    };
+
+    assert!(
+        enforce(
+            &config,
+            RateLimitScope::Ip,
+            route,
+            "127.0.0.1",
+            usage,
+            10_000
+        )
+        .is_ok()
+    );
+    assert_eq!(
+        RATE_LIMIT_SHADOW_REJECTIONS
+            .with_label_values(&[route, "ip"])
+            .get(),
+        0.0
+    );
+}
+
+#[test]
+fn test_enforced_mode_rejects_over_limit() {
+    let config = RateLimitConfig::default();
+    let route = "/shadow/enforced";
+
+    let result = enforce(
+        &config,
+        RateLimitScope::Username,
+        route,
+        "alice",
+        over_limit(&config),
+        10_000,
+    );
+
+    assert!(matches!(result, Err(AppError::TooManyRequests(50))));
+    assert_eq!(
+        RATE_LIMIT_REJECTIONS
+            .with_label_values(&[route, "username"])
+            .get(),
+        1.0
+    );
+    assert_eq!(
+        RATE_LIMIT_SHADOW_REJECTIONS
+            .with_label_values(&[route, "username"])
+            .get(),
+        0.0
+    );
+}
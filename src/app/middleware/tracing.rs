@@ -1,23 +1,189 @@
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
-
-pub fn init_tracing() {
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::fmt::layer().with_filter(
-                tracing_subscriber::filter::Targets::new()
-                    .with_target("tower_http::trace", tracing::Level::INFO)
-                    .with_target("rs_passkey_auth", tracing::Level::INFO)
-                    .with_default(tracing::Level::INFO),
-            ),
-        )
-        .init();
+use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
+
+#[cfg(feature = "otel")]
+use crate::config::TelemetryConfig;
+
+/// Keeps the OTLP exporters alive; dropping it flushes what is still
+/// buffered, so hold it until the server has shut down.
+#[derive(Default)]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    providers: Option<otel::Providers>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(providers) = self.providers.take() {
+            providers.shutdown();
+        }
+    }
+}
+
+pub fn init_tracing() -> TelemetryGuard {
+    let fmt_layer = tracing_subscriber::fmt::layer().with_filter(
+        tracing_subscriber::filter::Targets::new()
+            .with_target("tower_http::trace", tracing::Level::INFO)
+            .with_target("rs_passkey_auth", tracing::Level::INFO)
+            .with_default(tracing::Level::INFO),
+    );
+
+    #[cfg(feature = "otel")]
+    {
+        let config = TelemetryConfig::from_env();
+        let providers = config.otlp_endpoint.as_deref().map(|endpoint| {
+            otel::Providers::new(endpoint, &config.service_name, config.sampling_ratio)
+        });
+        let otel_layer = providers.as_ref().map(|providers| {
+            tracing_opentelemetry::layer()
+                .with_tracer(providers.tracer())
+                .with_filter(tracing_subscriber::filter::LevelFilter::INFO)
+        });
+
+        tracing_subscriber::registry()
+            .with(fmt_layer)
+            .with(otel_layer)
+            .init();
+
+        if let Some(endpoint) = &config.otlp_endpoint {
+            tracing::info!(
+                "Exporting traces and metrics over OTLP to {} as {} (sampling ratio {})",
+                endpoint,
+                config.service_name,
+                config.sampling_ratio
+            );
+        }
+        TelemetryGuard { providers }
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        tracing_subscriber::registry().with(fmt_layer).init();
+        TelemetryGuard::default()
+    }
+}
+
+/// Continues the caller's trace when the request carries a W3C
+/// `traceparent` header. A no-op until an OTLP endpoint is configured.
+#[cfg(feature = "otel")]
+pub fn continue_remote_trace(span: &tracing::Span, headers: &axum::http::HeaderMap) {
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&otel::HeaderExtractor(headers))
+    });
+    let _ = span.set_parent(parent);
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use std::time::Duration;
+
+    use axum::http::HeaderMap;
+    use opentelemetry::{global, propagation::Extractor, trace::TracerProvider};
+    use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{
+        Resource,
+        metrics::SdkMeterProvider,
+        propagation::TraceContextPropagator,
+        trace::{Sampler, SdkTracer, SdkTracerProvider},
+    };
+
+    const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub struct Providers {
+        tracer: SdkTracerProvider,
+        meter: SdkMeterProvider,
+    }
+
+    impl Providers {
+        /// Builds both exporters and installs them globally. An endpoint
+        /// that cannot be used is a configuration error and panics.
+        pub fn new(endpoint: &str, service_name: &str, sampling_ratio: f64) -> Self {
+            let resource = Resource::builder()
+                .with_service_name(service_name.to_owned())
+                .build();
+
+            let span_exporter = SpanExporter::builder()
+                .with_http()
+                .with_endpoint(format!("{}/v1/traces", endpoint))
+                .with_timeout(EXPORT_TIMEOUT)
+                .build()
+                .unwrap_or_else(|e| panic!("Invalid OTEL_EXPORTER_OTLP_ENDPOINT: {}", e));
+            let tracer = SdkTracerProvider::builder()
+                .with_resource(resource.clone())
+                .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                    sampling_ratio,
+                ))))
+                .with_batch_exporter(span_exporter)
+                .build();
+
+            let metric_exporter = MetricExporter::builder()
+                .with_http()
+                .with_endpoint(format!("{}/v1/metrics", endpoint))
+                .with_timeout(EXPORT_TIMEOUT)
+                .build()
+                .unwrap_or_else(|e| panic!("Invalid OTEL_EXPORTER_OTLP_ENDPOINT: {}", e));
+            let meter = SdkMeterProvider::builder()
+                .with_resource(resource)
+                .with_periodic_exporter(metric_exporter)
+                .build();
+
+            global::set_text_map_propagator(TraceContextPropagator::new());
+            global::set_tracer_provider(tracer.clone());
+            global::set_meter_provider(meter.clone());
+
+            Self { tracer, meter }
+        }
+
+        pub fn tracer(&self) -> SdkTracer {
+            self.tracer.tracer("rs-server")
+        }
+
+        pub fn shutdown(self) {
+            if let Err(e) = self.tracer.shutdown() {
+                tracing::warn!("Failed to flush OTLP traces: {}", e);
+            }
+            if let Err(e) = self.meter.shutdown() {
+                tracing::warn!("Failed to flush OTLP metrics: {}", e);
+            }
+        }
+    }
+
+    pub struct HeaderExtractor<'a>(pub &'a HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(|key| key.as_str()).collect()
+        }
+    }
 }
 
 #[macro_export]
 macro_rules! http_trace_layer {
     () => {
         TraceLayer::new_for_http()
-            .make_span_with(tower_http::trace::DefaultMakeSpan::new().level(tracing::Level::INFO))
+            .make_span_with(|request: &axum::http::Request<_>| {
+                // Set by `propagate_request_id`, which runs first.
+                let request_id = request
+                    .headers()
+                    .get("x-request-id")
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default();
+                let span = tracing::info_span!(
+                    "request",
+                    method = %request.method(),
+                    uri = %request.uri(),
+                    request_id,
+                );
+                #[cfg(feature = "otel")]
+                $crate::app::middleware::tracing::continue_remote_trace(&span, request.headers());
+                span
+            })
             .on_request(|request: &axum::http::Request<_>, _span: &tracing::Span| {
                 tracing::info!("Started {} {}", request.method(), request.uri());
             })
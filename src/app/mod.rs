@@ -1,11 +1,21 @@
+pub(crate) mod context;
+pub(crate) mod deadline;
 pub(crate) mod error;
+pub(crate) mod fallback;
 pub(crate) mod middleware;
+pub(crate) mod openapi;
 pub(crate) mod router;
 pub(crate) mod server;
+pub(crate) mod startup;
 pub(crate) mod state;
 
-pub(crate) use error::AppError;
+pub(crate) use context::RequestContext;
+pub(crate) use error::{AppError, ErrorCode};
 pub(crate) use middleware::init_tracing;
 pub(crate) use router::create_router;
 pub(crate) use server::{ServerConfig, start_server};
+pub(crate) use startup::StartupGate;
 pub(crate) use state::{AppConfig, AppState};
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    extract::{Query, State},
+    http::{HeaderMap, HeaderValue, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use utoipa::openapi::OpenApi;
+
+const OPENAPI_3_0_VERSION: &str = "3.0.3";
+const YAML_CONTENT_TYPE: &str = "application/yaml";
+
+/// The API description pre-rendered in every served variant. utoipa emits
+/// OpenAPI 3.1; the 3.0 variant is derived from it for older generators.
+pub struct OpenApiDocuments {
+    json: String,
+    yaml: String,
+    json_3_0: String,
+    yaml_3_0: String,
+}
+
+impl OpenApiDocuments {
+    pub fn new(api: &OpenApi) -> Self {
+        let value = serde_json::to_value(api).unwrap();
+        let mut value_3_0 = value.clone();
+        downgrade_to_3_0(&mut value_3_0);
+
+        Self {
+            json: serde_json::to_string(&value).unwrap(),
+            yaml: serde_norway::to_string(&value).unwrap(),
+            json_3_0: serde_json::to_string(&value_3_0).unwrap(),
+            yaml_3_0: serde_norway::to_string(&value_3_0).unwrap(),
+        }
+    }
+
+    fn select(&self, format: DocumentFormat, version: SpecVersion) -> Response {
+        let (body, content_type) = match (format, version) {
+            (DocumentFormat::Json, SpecVersion::V3_1) => (&self.json, "application/json"),
+            (DocumentFormat::Json, SpecVersion::V3_0) => (&self.json_3_0, "application/json"),
+            (DocumentFormat::Yaml, SpecVersion::V3_1) => (&self.yaml, YAML_CONTENT_TYPE),
+            (DocumentFormat::Yaml, SpecVersion::V3_0) => (&self.yaml_3_0, YAML_CONTENT_TYPE),
+        };
+
+        (
+            [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))],
+            body.clone(),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Json,
+    Yaml,
+}
+
+impl DocumentFormat {
+    /// YAML only when the client asks for it, since JSON is what Swagger UI
+    /// and most tooling expect.
+    pub fn from_accept(headers: &HeaderMap) -> Self {
+        let accepts_yaml = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|media| media.split(';').next().unwrap_or("").trim())
+            .any(|media| {
+                matches!(
+                    media,
+                    "application/yaml" | "application/x-yaml" | "text/yaml"
+                )
+            });
+
+        if accepts_yaml { Self::Yaml } else { Self::Json }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum SpecVersion {
+    #[serde(rename = "3.0")]
+    V3_0,
+    #[default]
+    #[serde(rename = "3.1")]
+    V3_1,
+}
+
+#[derive(Debug, Deserialize)]
+struct NegotiateQuery {
+    #[serde(default)]
+    version: SpecVersion,
+}
+
+/// Routes for the YAML and 3.0 variants. `/api-docs/openapi.json` stays with
+/// Swagger UI, which serves the 3.1 JSON itself, unless the `swagger-ui`
+/// feature is off.
+pub fn openapi_routes(documents: Arc<OpenApiDocuments>) -> Router {
+    let router = Router::new();
+
+    #[cfg(not(feature = "swagger-ui"))]
+    let router = router.route(
+        "/api-docs/openapi.json",
+        get(|State(docs): State<Arc<OpenApiDocuments>>| async move {
+            docs.select(DocumentFormat::Json, SpecVersion::V3_1)
+        }),
+    );
+
+    router
+        .route("/api-docs/openapi", get(negotiate))
+        .route(
+            "/api-docs/openapi.yaml",
+            get(|State(docs): State<Arc<OpenApiDocuments>>| async move {
+                docs.select(DocumentFormat::Yaml, SpecVersion::V3_1)
+            }),
+        )
+        .route(
+            "/api-docs/openapi-3.0.json",
+            get(|State(docs): State<Arc<OpenApiDocuments>>| async move {
+                docs.select(DocumentFormat::Json, SpecVersion::V3_0)
+            }),
+        )
+        .route(
+            "/api-docs/openapi-3.0.yaml",
+            get(|State(docs): State<Arc<OpenApiDocuments>>| async move {
+                docs.select(DocumentFormat::Yaml, SpecVersion::V3_0)
+            }),
+        )
+        .with_state(documents)
+}
+
+async fn negotiate(
+    State(documents): State<Arc<OpenApiDocuments>>,
+    Query(query): Query<NegotiateQuery>,
+    headers: HeaderMap,
+) -> Response {
+    documents.select(DocumentFormat::from_accept(&headers), query.version)
+}
+
+/// Rewrites an OpenAPI 3.1 document into its closest 3.0 equivalent. Only the
+/// constructs utoipa generates are handled: null in type arrays and `oneOf`,
+/// schema `examples`, and the 3.1-only top-level fields.
+pub fn downgrade_to_3_0(document: &mut Value) {
+    let Some(root) = document.as_object_mut() else {
+        return;
+    };
+
+    root.insert(String::from("openapi"), Value::from(OPENAPI_3_0_VERSION));
+    root.remove("webhooks");
+    root.remove("jsonSchemaDialect");
+    if let Some(Value::Object(license)) = root
+        .get_mut("info")
+        .and_then(|info| info.get_mut("license"))
+    {
+        license.remove("identifier");
+    }
+
+    if let Some(Value::Object(schemas)) = root
+        .get_mut("components")
+        .and_then(|components| components.get_mut("schemas"))
+    {
+        schemas.values_mut().for_each(downgrade_schema);
+    }
+
+    if let Some(Value::Object(paths)) = root.get_mut("paths") {
+        for operation in paths.values_mut().filter_map(Value::as_object_mut) {
+            for value in operation.values_mut() {
+                downgrade_operation(value);
+            }
+        }
+    }
+}
+
+fn downgrade_operation(operation: &mut Value) {
+    let Some(operation) = operation.as_object_mut() else {
+        return;
+    };
+
+    if let Some(Value::Array(parameters)) = operation.get_mut("parameters") {
+        for parameter in parameters {
+            if let Some(schema) = parameter.get_mut("schema") {
+                downgrade_schema(schema);
+            }
+        }
+    }
+
+    if let Some(body) = operation.get_mut("requestBody") {
+        downgrade_content(body);
+    }
+
+    if let Some(Value::Object(responses)) = operation.get_mut("responses") {
+        responses.values_mut().for_each(downgrade_content);
+    }
+}
+
+fn downgrade_content(holder: &mut Value) {
+    if let Some(Value::Object(content)) = holder.get_mut("content") {
+        for media in content.values_mut() {
+            if let Some(schema) = media.get_mut("schema") {
+                downgrade_schema(schema);
+            }
+        }
+    }
+}
+
+fn downgrade_schema(schema: &mut Value) {
+    let Some(object) = schema.as_object_mut() else {
+        return;
+    };
+
+    for key in ["properties", "patternProperties"] {
+        if let Some(Value::Object(children)) = object.get_mut(key) {
+            children.values_mut().for_each(downgrade_schema);
+        }
+    }
+    for key in ["items", "additionalProperties", "not"] {
+        if let Some(child) = object.get_mut(key) {
+            downgrade_schema(child);
+        }
+    }
+    for key in ["allOf", "anyOf", "oneOf"] {
+        if let Some(Value::Array(children)) = object.get_mut(key) {
+            children.iter_mut().for_each(downgrade_schema);
+        }
+    }
+
+    downgrade_nullable_type(object);
+    downgrade_nullable_composition(object, "oneOf");
+    downgrade_nullable_composition(object, "anyOf");
+
+    if let Some(Value::Array(examples)) = object.remove("examples")
+        && let Some(example) = examples.into_iter().next()
+    {
+        object.entry("example").or_insert(example);
+    }
+}
+
+/// `type: [T, "null"]` becomes `type: T, nullable: true`.
+fn downgrade_nullable_type(object: &mut Map<String, Value>) {
+    let Some(Value::Array(types)) = object.get("type") else {
+        return;
+    };
+
+    let nullable = types.iter().any(|t| t == "null");
+    let remaining: Vec<Value> = types.iter().filter(|t| *t != "null").cloned().collect();
+
+    match remaining.as_slice() {
+        [single] => {
+            object.insert(String::from("type"), single.clone());
+        }
+        // 3.0 has no multi-type schemas; leaving the type out accepts any.
+        _ => {
+            object.remove("type");
+        }
+    }
+    if nullable {
+        object.insert(String::from("nullable"), Value::Bool(true));
+    }
+}
+
+/// `oneOf: [{type: null}, X]` becomes `allOf: [X], nullable: true`, since a
+/// 3.0 `$ref` ignores its siblings.
+fn downgrade_nullable_composition(object: &mut Map<String, Value>, key: &str) {
+    let Some(Value::Array(variants)) = object.get(key) else {
+        return;
+    };
+
+    let is_null = |variant: &Value| variant.get("type").is_some_and(|t| t == "null");
+    if !variants.iter().any(is_null) {
+        return;
+    }
+
+    let remaining: Vec<Value> = variants.iter().filter(|v| !is_null(v)).cloned().collect();
+    let target = if remaining.len() == 1 { "allOf" } else { key };
+
+    object.remove(key);
+    object.insert(String::from(target), Value::Array(remaining));
+    object.insert(String::from("nullable"), Value::Bool(true));
+}
@@ -1,23 +1,101 @@
 use axum::{
     extract::DefaultBodyLimit,
-    routing::{get, post},
+    middleware::{from_fn, from_fn_with_state},
+    routing::{delete, get, patch, post, put},
 };
+use std::sync::Arc;
+
 use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
 use utoipa::OpenApi;
 use utoipa_axum::router::OpenApiRouter;
+#[cfg(feature = "swagger-ui")]
 use utoipa_swagger_ui::SwaggerUi;
 
+#[cfg(feature = "enrollment-reminders")]
+use crate::enrollment::{self, dto::ReminderStatsResponse};
 use crate::{
-    app::{AppState, error::ErrorResponse, middleware::metrics},
+    admin::{
+        self,
+        dto::{
+            AccountLockoutResponse, ActionResponse, AttemptMetrics, BreakerStatus,
+            CeremonyLimitResponse, CircuitBreakerEntry, CircuitBreakerListResponse,
+            CircuitBreakerState, ErrorMetrics, MaintenanceResponse, MetricsSnapshotResponse,
+            PoolMetrics, UpdateCeremonyLimitRequest, UpdateMaintenanceRequest,
+        },
+    },
+    app::{
+        AppState,
+        error::{ErrorCode, ErrorResponse, ProblemDetails},
+        fallback,
+        middleware::{accounting, context, maintenance, metrics, policy, problem, rate_limit, slo},
+        openapi::{OpenApiDocuments, openapi_routes},
+    },
+    audit::{
+        self,
+        dto::{AuditLogEntry, AuditLogResponse},
+    },
     auth::{
         dto::{
-            BeginRequest, BeginResponse, FinishRequest, HealthChecks, HealthResponse, HealthStatus,
-            MessageResponse, ServiceHealth, TokenResponse,
+            BeginRequest, BeginResponse, CredentialEntry, CredentialListResponse, FinishRequest,
+            HealthChecks, HealthResponse, HealthStatus, IntrospectionRequest,
+            IntrospectionResponse, JwksResponse, LivenessResponse, LoginOptionsResponse, LoginStep,
+            MessageResponse, ProfileResponse, RecoveryRequest, RegistrationResponse, ServiceHealth,
+            StartupResponse, TokenResponse, UpdateSessionRequest, VerifyEmailRequest,
         },
         handler,
     },
-    http_trace_layer,
+    banner::{
+        self,
+        dto::{BannerResponse, CurrentBannerResponse, UpdateBannerRequest},
+    },
+    credential_revocation::{
+        self,
+        dto::{RevokeByAaguidRequest, RevokeByAaguidResponse},
+    },
+    duplicates::{
+        self,
+        dto::{
+            DuplicateGroupEntry, DuplicateReportResponse, DuplicateUserEntry,
+            MergeDuplicatesRequest, MergeResponse,
+        },
+    },
+    events, http_trace_layer,
+    invitations::{
+        self,
+        dto::{CreateInvitationRequest, CreatedInvitationResponse, InvitationResponse},
+    },
+    machine_clients::{
+        self,
+        dto::{
+            ClientTokenRequest, ClientTokenResponse, CreateClientRequest, CreatedClientResponse,
+            MachineClientResponse,
+        },
+    },
+    reports::{
+        self,
+        dto::{AgeBucketCounts, UnenrolledReportResponse, UnenrolledUserEntry},
+    },
+    sessions::{
+        self,
+        dto::{RevokeSessionsRequest, RevokeSessionsResponse},
+    },
+    suspensions::{
+        self,
+        dto::{AccountStateResponse, SuspendUserRequest},
+    },
+    token_issuance::{
+        self,
+        dto::{IssuanceLogEntry, IssuanceLogResponse},
+    },
+    traffic::{
+        self,
+        dto::{IpTrafficSummary, TrafficReportResponse},
+    },
+    user_search::{
+        self,
+        dto::{UserSearchEntry, UserSearchResponse},
+    },
 };
 
 #[derive(OpenApi)]
@@ -25,31 +103,135 @@ use crate::{
     paths(
         handler::begin_register,
         handler::finish_register,
+        handler::verify_email,
+        handler::login_options,
         handler::begin_login,
         handler::finish_login,
+        handler::begin_recovery,
+        handler::finish_recovery,
         handler::refresh,
+        handler::update_session,
         handler::logout,
-        handler::healthz,
+        handler::me,
+        handler::delete_me,
+        handler::credentials,
+        handler::unlock_credential,
+        handler::introspect,
+        machine_clients::handler::token,
+        events::handler::stream,
+        handler::jwks,
+        banner::handler::current,
+        handler::livez,
+        handler::readyz,
+        handler::startupz,
+        traffic::handler::top_ips,
+        admin::handler::run_action,
+        admin::handler::circuit_breakers,
+        admin::handler::metrics_snapshot,
+        admin::handler::ceremony_limit,
+        admin::handler::set_ceremony_limit,
+        admin::handler::set_maintenance,
+        admin::handler::unlock_account,
+        audit::handler::search,
+        token_issuance::handler::search,
+        reports::handler::unenrolled,
+        user_search::handler::search,
+        duplicates::handler::list,
+        duplicates::handler::merge,
+        sessions::handler::revoke,
+        credential_revocation::handler::revoke_by_aaguid,
+        suspensions::handler::suspend,
+        suspensions::handler::reinstate,
+        machine_clients::handler::create,
+        machine_clients::handler::delete,
+        invitations::handler::create,
+        invitations::handler::revoke,
+        banner::handler::update,
+        banner::handler::clear,
         metrics::metrics_handler,
     ),
     components(
         schemas(
             BeginRequest,
             FinishRequest,
+            RecoveryRequest,
+            UpdateSessionRequest,
+            VerifyEmailRequest,
             BeginResponse,
+            LoginOptionsResponse,
+            LoginStep,
             MessageResponse,
+            RegistrationResponse,
             TokenResponse,
+            ProfileResponse,
+            CredentialListResponse,
+            CredentialEntry,
+            IntrospectionRequest,
+            IntrospectionResponse,
+            JwksResponse,
             ErrorResponse,
+            ErrorCode,
+            ProblemDetails,
             HealthResponse,
             ServiceHealth,
             HealthChecks,
             HealthStatus,
+            LivenessResponse,
+            StartupResponse,
+            TrafficReportResponse,
+            IpTrafficSummary,
+            ActionResponse,
+            CircuitBreakerListResponse,
+            CircuitBreakerEntry,
+            MetricsSnapshotResponse,
+            AttemptMetrics,
+            ErrorMetrics,
+            CircuitBreakerState,
+            BreakerStatus,
+            PoolMetrics,
+            CeremonyLimitResponse,
+            AccountLockoutResponse,
+            UpdateCeremonyLimitRequest,
+            MaintenanceResponse,
+            UpdateMaintenanceRequest,
+            AuditLogResponse,
+            AuditLogEntry,
+            IssuanceLogResponse,
+            IssuanceLogEntry,
+            UnenrolledReportResponse,
+            AgeBucketCounts,
+            UnenrolledUserEntry,
+            UserSearchResponse,
+            UserSearchEntry,
+            DuplicateReportResponse,
+            DuplicateGroupEntry,
+            DuplicateUserEntry,
+            MergeDuplicatesRequest,
+            MergeResponse,
+            RevokeSessionsRequest,
+            RevokeSessionsResponse,
+            RevokeByAaguidRequest,
+            RevokeByAaguidResponse,
+            SuspendUserRequest,
+            AccountStateResponse,
+            CreateClientRequest,
+            CreatedClientResponse,
+            MachineClientResponse,
+            ClientTokenRequest,
+            ClientTokenResponse,
+            CreateInvitationRequest,
+            CreatedInvitationResponse,
+            InvitationResponse,
+            UpdateBannerRequest,
+            CurrentBannerResponse,
+            BannerResponse,
         )
     ),
     tags(
         (name = "Authentication", description = "WebAuthn-based authentication endpoints"),
          (name = "Monitoring", description = "Prometheus metrics endpoint"),
-          (name = "Health", description = "Health check endpoints")
+          (name = "Health", description = "Health check endpoints"),
+          (name = "Admin", description = "Administrative endpoints (each requires a permission scope)")
     ),
     info(
         title = "server API",
@@ -63,25 +245,184 @@ use crate::{
 )]
 struct ApiDoc;
 
-pub fn create_router(state: std::sync::Arc<AppState>) -> axum::Router {
-    let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .route("/auth/register/begin", post(handler::begin_register))
+#[cfg(feature = "enrollment-reminders")]
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        enrollment::handler::open_reminder,
+        enrollment::handler::reminder_stats,
+    ),
+    components(schemas(ReminderStatsResponse)),
+    tags(
+        (name = "Enrollment", description = "Passkey enrollment reminder tracking"),
+    )
+)]
+struct EnrollmentApiDoc;
+
+/// The API description of the subsystems compiled into this build.
+fn api_doc() -> utoipa::openapi::OpenApi {
+    #[allow(unused_mut)]
+    let mut api = ApiDoc::openapi();
+    #[cfg(feature = "enrollment-reminders")]
+    api.merge(EnrollmentApiDoc::openapi());
+    api
+}
+
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+pub fn create_router(state: Arc<AppState>) -> axum::Router {
+    let rate_limit_layer = from_fn_with_state(Arc::clone(&state), rate_limit::rate_limit);
+
+    let router = OpenApiRouter::with_openapi(api_doc())
+        .route(
+            "/auth/register/begin",
+            post(handler::begin_register).layer(rate_limit_layer.clone()),
+        )
         .route("/auth/register/finish", post(handler::finish_register))
-        .route("/auth/login/begin", post(handler::begin_login))
+        .route(
+            "/auth/register/verify",
+            post(handler::verify_email).layer(rate_limit_layer.clone()),
+        )
+        .route(
+            "/auth/login/options",
+            get(handler::login_options).layer(rate_limit_layer.clone()),
+        )
+        .route(
+            "/auth/login/begin",
+            post(handler::begin_login).layer(rate_limit_layer.clone()),
+        )
         .route("/auth/login/finish", post(handler::finish_login))
+        .route(
+            "/auth/recover/begin",
+            post(handler::begin_recovery).layer(rate_limit_layer.clone()),
+        )
+        .route("/auth/recover/finish", post(handler::finish_recovery))
         .route("/auth/refresh", post(handler::refresh))
+        .route("/auth/session", patch(handler::update_session))
         .route("/auth/logout", post(handler::logout))
-        .route("/healthz", get(handler::healthz))
+        .route("/auth/me", get(handler::me).delete(handler::delete_me))
+        .route("/auth/credentials", get(handler::credentials))
+        .route(
+            "/auth/credentials/{id}/unlock",
+            post(handler::unlock_credential),
+        )
+        .route("/auth/events", get(events::handler::stream))
+        .route("/auth/banner", get(banner::handler::current))
+        .route("/auth/introspect", post(handler::introspect))
+        .route(
+            "/auth/token",
+            post(machine_clients::handler::token).layer(rate_limit_layer),
+        )
+        .route("/.well-known/jwks.json", get(handler::jwks))
+        .route("/livez", get(handler::livez))
+        .route("/readyz", get(handler::readyz))
+        .route("/healthz", get(handler::readyz))
+        .route("/startupz", get(handler::startupz))
+        .route("/admin/traffic/top-ips", get(traffic::handler::top_ips))
+        .route("/admin/actions/{name}", post(admin::handler::run_action))
+        .route("/admin/metrics.json", get(admin::handler::metrics_snapshot))
+        .route(
+            "/admin/circuit-breakers",
+            get(admin::handler::circuit_breakers),
+        )
+        .route(
+            "/admin/ceremony-limit",
+            get(admin::handler::ceremony_limit).put(admin::handler::set_ceremony_limit),
+        )
+        .route("/admin/maintenance", put(admin::handler::set_maintenance))
+        .route(
+            "/admin/lockouts/{username}",
+            delete(admin::handler::unlock_account),
+        )
+        .route("/admin/clients", post(machine_clients::handler::create))
+        .route(
+            "/admin/clients/{client_id}",
+            delete(machine_clients::handler::delete),
+        )
+        .route("/admin/invites", post(invitations::handler::create))
+        .route(
+            "/admin/invites/{invitation_id}",
+            delete(invitations::handler::revoke),
+        )
+        .route("/admin/audit", get(audit::handler::search))
+        .route(
+            "/admin/tokens/issuances",
+            get(token_issuance::handler::search),
+        )
+        .route(
+            "/admin/reports/unenrolled",
+            get(reports::handler::unenrolled),
+        )
+        .route("/admin/users/search", get(user_search::handler::search))
+        .route("/admin/users/duplicates", get(duplicates::handler::list))
+        .route(
+            "/admin/users/duplicates/merge",
+            post(duplicates::handler::merge),
+        )
+        .route("/admin/sessions/revoke", post(sessions::handler::revoke))
+        .route(
+            "/admin/credentials/revoke-by-aaguid",
+            post(credential_revocation::handler::revoke_by_aaguid),
+        )
+        .route(
+            "/admin/users/{user_id}/suspension",
+            put(suspensions::handler::suspend).delete(suspensions::handler::reinstate),
+        )
+        .route(
+            "/admin/banner",
+            put(banner::handler::update).delete(banner::handler::clear),
+        );
+
+    #[cfg(feature = "enrollment-reminders")]
+    let router = router
+        .route(
+            "/enrollment/reminders/{token}",
+            get(enrollment::handler::open_reminder),
+        )
+        .route(
+            "/admin/enrollment/reminders",
+            get(enrollment::handler::reminder_stats),
+        );
+
+    let (router, api) = router
+        .layer(from_fn_with_state(
+            Arc::clone(&state),
+            maintenance::maintenance,
+        ))
+        .layer(from_fn_with_state(
+            Arc::clone(&state),
+            accounting::track_request,
+        ))
+        .layer(from_fn_with_state(
+            Arc::clone(&state),
+            context::attach_context,
+        ))
+        .layer(from_fn_with_state(
+            Arc::clone(&state),
+            policy::enforce_request_policy,
+        ))
+        .layer(from_fn_with_state(Arc::clone(&state), slo::track_slo))
         .with_state(state)
         .split_for_parts();
 
     let service_builder = ServiceBuilder::new()
-        .layer(DefaultBodyLimit::max(1024 * 1024))
+        .layer(from_fn(context::propagate_request_id))
+        .layer(from_fn(problem::negotiate_error_format))
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
         .layer(http_trace_layer!())
         .layer(metrics::create_prometheus_layer());
 
-    router
+    let documents = Arc::new(OpenApiDocuments::new(&api));
+
+    let router = router
         .route("/metrics", get(metrics::metrics_handler))
-        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", api))
+        .merge(openapi_routes(documents));
+
+    #[cfg(feature = "swagger-ui")]
+    let router = router.merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", api));
+
+    router
+        .fallback(fallback::route_not_found)
+        .method_not_allowed_fallback(fallback::method_not_allowed)
         .layer(service_builder)
 }
@@ -1,33 +1,412 @@
-use axum::Router;
+use std::{
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+    pin::pin,
+    time::Duration,
+};
+
+use axum::{
+    Router,
+    extract::ConnectInfo,
+    serve::{Listener, ListenerExt},
+};
+use hyper::{Request, body::Incoming};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::{conn::auto::Builder, graceful::GracefulShutdown},
+    service::TowerToHyperService,
+};
+use socket2::{Domain, Socket, Type};
 use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+use crate::config::env::env_opt;
+
+const DEFAULT_PORT: u16 = 8080;
+const LISTEN_BACKLOG: i32 = 1024;
+/// hyper's own defaults, kept when the variables are unset.
+const DEFAULT_HTTP2_MAX_CONCURRENT_STREAMS: u32 = 200;
+const DEFAULT_HTTP2_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Where the server accepts connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindAddr {
+    /// `dual_stack` lets an IPv6 socket accept IPv4 clients as mapped addresses.
+    Tcp {
+        addr: SocketAddr,
+        dual_stack: bool,
+    },
+    Unix(PathBuf),
+}
 
+impl fmt::Display for BindAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp { addr, .. } => write!(f, "http://{}", addr),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerConfigError {
+    InvalidHost(String),
+    InvalidPort(String),
+    InvalidDualStack(String),
+    DualStackRequiresIpv6(IpAddr),
+    /// `UNIX_SOCKET` cannot be combined with `HOST`, `PORT` or dual-stack.
+    UnixSocketConflict,
+    UnixSocketUnsupported,
+    /// A connection tuning variable that is not a valid boolean or positive number.
+    InvalidTuning {
+        name: &'static str,
+        value: String,
+    },
+}
+
+impl fmt::Display for ServerConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHost(value) => write!(f, "HOST is not an IP address: {}", value),
+            Self::InvalidPort(value) => write!(f, "PORT must be between 1 and 65535: {}", value),
+            Self::InvalidDualStack(value) => {
+                write!(f, "IPV6_DUAL_STACK must be true or false: {}", value)
+            }
+            Self::DualStackRequiresIpv6(ip) => {
+                write!(f, "IPV6_DUAL_STACK needs an IPv6 HOST, got {}", ip)
+            }
+            Self::UnixSocketConflict => write!(
+                f,
+                "UNIX_SOCKET cannot be combined with HOST, PORT or IPV6_DUAL_STACK"
+            ),
+            Self::UnixSocketUnsupported => {
+                write!(f, "UNIX_SOCKET is only supported on Unix platforms")
+            }
+            Self::InvalidTuning { name, value } => write!(f, "{} is invalid: {}", name, value),
+        }
+    }
+}
+
+impl std::error::Error for ServerConfigError {}
+
+/// Per-connection protocol settings. The defaults serve HTTP/1.1 only,
+/// without an idle timeout, as the server always has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionTuning {
+    /// Also accept HTTP/2 over cleartext (prior knowledge), as load
+    /// balancers speak to backends.
+    pub http2: bool,
+    /// Whether HTTP/1.1 connections are reused for further requests.
+    pub keep_alive: bool,
+    /// How long a connection may wait for the next request's headers, which
+    /// closes idle HTTP/1.1 keep-alive connections after this long.
+    pub keep_alive_timeout: Option<Duration>,
+    /// Interval of HTTP/2 keep-alive pings; `None` sends none.
+    pub http2_keep_alive_interval: Option<Duration>,
+    /// How long an HTTP/2 ping may go unanswered before the connection closes.
+    pub http2_keep_alive_timeout: Duration,
+    pub http2_max_concurrent_streams: u32,
+    /// Set `TCP_NODELAY` on accepted TCP connections.
+    pub tcp_nodelay: bool,
+}
+
+impl Default for ConnectionTuning {
+    fn default() -> Self {
+        Self {
+            http2: false,
+            keep_alive: true,
+            keep_alive_timeout: None,
+            http2_keep_alive_interval: None,
+            http2_keep_alive_timeout: DEFAULT_HTTP2_KEEP_ALIVE_TIMEOUT,
+            http2_max_concurrent_streams: DEFAULT_HTTP2_MAX_CONCURRENT_STREAMS,
+            tcp_nodelay: false,
+        }
+    }
+}
+
+impl ConnectionTuning {
+    /// Reads `HTTP2_ENABLED`, `HTTP_KEEP_ALIVE`, `HTTP_KEEP_ALIVE_TIMEOUT_SECS`,
+    /// `HTTP2_KEEP_ALIVE_INTERVAL_SECS`, `HTTP2_KEEP_ALIVE_TIMEOUT_SECS`,
+    /// `HTTP2_MAX_CONCURRENT_STREAMS` and `TCP_NODELAY`.
+    fn from_lookup(lookup: &impl Fn(&str) -> Option<String>) -> Result<Self, ServerConfigError> {
+        let defaults = Self::default();
+        let flag = |name| parse_tuning(lookup, name, |value| value.parse::<bool>().ok());
+        let secs = |name| {
+            parse_tuning(lookup, name, |value| {
+                value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|secs| *secs > 0)
+                    .map(Duration::from_secs)
+            })
+        };
+
+        Ok(Self {
+            http2: flag("HTTP2_ENABLED")?.unwrap_or(defaults.http2),
+            keep_alive: flag("HTTP_KEEP_ALIVE")?.unwrap_or(defaults.keep_alive),
+            keep_alive_timeout: secs("HTTP_KEEP_ALIVE_TIMEOUT_SECS")?,
+            http2_keep_alive_interval: secs("HTTP2_KEEP_ALIVE_INTERVAL_SECS")?,
+            http2_keep_alive_timeout: secs("HTTP2_KEEP_ALIVE_TIMEOUT_SECS")?
+                .unwrap_or(defaults.http2_keep_alive_timeout),
+            http2_max_concurrent_streams: parse_tuning(
+                lookup,
+                "HTTP2_MAX_CONCURRENT_STREAMS",
+                |value| value.parse::<u32>().ok().filter(|max| *max > 0),
+            )?
+            .unwrap_or(defaults.http2_max_concurrent_streams),
+            tcp_nodelay: flag("TCP_NODELAY")?.unwrap_or(defaults.tcp_nodelay),
+        })
+    }
+
+    pub(crate) fn connection_builder(&self) -> Builder<TokioExecutor> {
+        let mut builder = Builder::new(TokioExecutor::new());
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .keep_alive(self.keep_alive)
+            .header_read_timeout(self.keep_alive_timeout);
+
+        if !self.http2 {
+            return builder.http1_only();
+        }
+
+        builder
+            .http2()
+            .timer(TokioTimer::new())
+            .max_concurrent_streams(self.http2_max_concurrent_streams)
+            .keep_alive_interval(self.http2_keep_alive_interval)
+            .keep_alive_timeout(self.http2_keep_alive_timeout);
+        builder
+    }
+}
+
+fn parse_tuning<T>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    name: &'static str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<Option<T>, ServerConfigError> {
+    match lookup(name) {
+        Some(value) => parse(value.trim())
+            .map(Some)
+            .ok_or(ServerConfigError::InvalidTuning { name, value }),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ServerConfig {
-    pub bind_addr: String,
+    pub bind_addr: BindAddr,
+    pub tuning: ConnectionTuning,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
-            bind_addr: "0.0.0.0:8080".to_owned(),
+            bind_addr: BindAddr::Tcp {
+                addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), DEFAULT_PORT),
+                dual_stack: false,
+            },
+            tuning: ConnectionTuning::default(),
         }
     }
 }
 
-pub async fn start_server(app: Router, bind_addr: &str) {
-    let listener = TcpListener::bind(bind_addr).await.unwrap();
+impl ServerConfig {
+    pub fn from_env() -> Result<Self, ServerConfigError> {
+        Self::from_lookup(env_opt)
+    }
 
-    tracing::info!("Server listening on http://{}", bind_addr);
-    tracing::info!("Swagger UI available at http://{}/swagger-ui", bind_addr);
+    /// Builds the config from `HOST`, `PORT`, `UNIX_SOCKET`,
+    /// `IPV6_DUAL_STACK` and the connection tuning variables as returned by
+    /// `lookup`.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ServerConfigError> {
+        let tuning = ConnectionTuning::from_lookup(&lookup)?;
+        let host = lookup("HOST");
+        let port = lookup("PORT");
+        let dual_stack = match lookup("IPV6_DUAL_STACK") {
+            Some(value) => Some(
+                value
+                    .trim()
+                    .parse::<bool>()
+                    .map_err(|_| ServerConfigError::InvalidDualStack(value))?,
+            ),
+            None => None,
+        };
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+        if let Some(path) = lookup("UNIX_SOCKET") {
+            if host.is_some() || port.is_some() || dual_stack.is_some() {
+                return Err(ServerConfigError::UnixSocketConflict);
+            }
+            if !cfg!(unix) {
+                return Err(ServerConfigError::UnixSocketUnsupported);
+            }
+            return Ok(Self {
+                bind_addr: BindAddr::Unix(PathBuf::from(path)),
+                tuning,
+            });
+        }
+
+        let dual_stack = dual_stack.unwrap_or(false);
+        let ip = match host {
+            Some(host) => parse_host(&host)?,
+            None if dual_stack => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            None => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        };
+        if dual_stack && !ip.is_ipv6() {
+            return Err(ServerConfigError::DualStackRequiresIpv6(ip));
+        }
+
+        let port = match port {
+            Some(port) => match port.trim().parse::<u16>() {
+                Ok(parsed) if parsed > 0 => parsed,
+                _ => return Err(ServerConfigError::InvalidPort(port)),
+            },
+            None => DEFAULT_PORT,
+        };
+
+        Ok(Self {
+            bind_addr: BindAddr::Tcp {
+                addr: SocketAddr::new(ip, port),
+                dual_stack,
+            },
+            tuning,
+        })
+    }
+}
+
+/// Accepts bare and bracketed IPv6 literals, as `HOST=[::1]` is a common spelling.
+fn parse_host(host: &str) -> Result<IpAddr, ServerConfigError> {
+    let trimmed = host.trim();
+    let literal = trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .unwrap_or(trimmed);
+
+    literal
+        .parse()
+        .map_err(|_| ServerConfigError::InvalidHost(host.to_owned()))
+}
+
+pub async fn start_server(app: Router, config: &ServerConfig) -> io::Result<()> {
+    let builder = config.tuning.connection_builder();
+
+    match &config.bind_addr {
+        BindAddr::Tcp { addr, dual_stack } => {
+            let nodelay = config.tuning.tcp_nodelay;
+            let listener = bind_tcp(*addr, *dual_stack)?.tap_io(move |tcp| {
+                if let Err(e) = tcp.set_nodelay(nodelay) {
+                    tracing::debug!("Failed to set TCP_NODELAY: {}", e);
+                }
+            });
+            log_listening(&config.bind_addr);
+
+            serve(listener, app, builder, Some, shutdown_signal()).await;
+        }
+        #[cfg(unix)]
+        BindAddr::Unix(path) => {
+            let listener = bind_unix(path)?;
+            log_listening(&config.bind_addr);
+
+            // Peers on a Unix socket have no IP, so the client address is
+            // only known through a trusted proxy's X-Forwarded-For.
+            serve(listener, app, builder, |_| None, shutdown_signal()).await;
+            if let Err(e) = std::fs::remove_file(path) {
+                tracing::warn!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+        #[cfg(not(unix))]
+        BindAddr::Unix(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                ServerConfigError::UnixSocketUnsupported,
+            ));
+        }
+    }
 
     tracing::info!("Server shutdown completed");
+    Ok(())
+}
+
+/// Accepts connections until `shutdown` completes, then waits for the open
+/// ones to finish their requests. `peer_addr` yields what handlers see as
+/// `ConnectInfo<SocketAddr>`.
+pub(crate) async fn serve<L: Listener>(
+    mut listener: L,
+    app: Router,
+    builder: Builder<TokioExecutor>,
+    peer_addr: fn(L::Addr) -> Option<SocketAddr>,
+    shutdown: impl Future<Output = ()>,
+) {
+    let graceful = GracefulShutdown::new();
+    let mut shutdown = pin!(shutdown);
+
+    loop {
+        let (io, remote_addr) = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => break,
+        };
+
+        let remote_addr = peer_addr(remote_addr);
+        let service = app.clone().map_request(move |mut req: Request<Incoming>| {
+            if let Some(addr) = remote_addr {
+                req.extensions_mut().insert(ConnectInfo(addr));
+            }
+            req
+        });
+        let conn = builder
+            .serve_connection(TokioIo::new(io), TowerToHyperService::new(service))
+            .into_owned();
+        let conn = graceful.watch(conn);
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                tracing::debug!("Connection closed with error: {}", e);
+            }
+        });
+    }
+
+    drop(listener);
+    graceful.shutdown().await;
+}
+
+fn log_listening(bind_addr: &BindAddr) {
+    tracing::info!("Server listening on {}", bind_addr);
+    #[cfg(feature = "swagger-ui")]
+    tracing::info!("Swagger UI available at {}/swagger-ui", bind_addr);
+}
+
+/// IPv6 sockets are always created with `IPV6_V6ONLY` set explicitly, so
+/// dual-stack does not depend on the host's `bindv6only` default.
+fn bind_tcp(addr: SocketAddr, dual_stack: bool) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(!dual_stack)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+/// A socket file left behind by an unclean exit is replaced; any other kind
+/// of file at `path` is left alone and the bind fails.
+#[cfg(unix)]
+fn bind_unix(path: &std::path::Path) -> io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path)
+        && metadata.file_type().is_socket()
+    {
+        std::fs::remove_file(path)?;
+    }
+
+    tokio::net::UnixListener::bind(path)
 }
 
-async fn shutdown_signal() {
+pub(crate) async fn shutdown_signal() {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
             .await
@@ -0,0 +1,64 @@
+use std::{
+    convert::Infallible,
+    sync::{Arc, OnceLock},
+};
+
+use axum::{
+    Router,
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use tower::{ServiceExt, service_fn};
+
+use crate::{
+    app::AppError,
+    auth::dto::{HealthStatus, LivenessResponse},
+};
+
+/// Lets the server listen before Postgres and Redis are reachable. Until
+/// `open` hands it the application, the process reports itself alive but not
+/// ready or started, and every other route answers 503.
+#[derive(Clone, Default)]
+pub struct StartupGate {
+    app: Arc<OnceLock<Router>>,
+}
+
+impl StartupGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Router to serve from the start, forwarding to the application once
+    /// it is open.
+    pub fn router(&self) -> Router {
+        let app = Arc::clone(&self.app);
+        Router::new().fallback_service(service_fn(move |req: Request| {
+            let app = app.get().cloned();
+            async move {
+                Ok::<_, Infallible>(match app {
+                    Some(app) => app.oneshot(req).await.unwrap_or_else(|e| match e {}),
+                    None => degraded(req.uri().path()),
+                })
+            }
+        }))
+    }
+
+    /// Starts serving `app`. Later calls are ignored.
+    pub fn open(&self, app: Router) {
+        if self.app.set(app).is_err() {
+            tracing::warn!("Application was already open");
+        }
+    }
+}
+
+fn degraded(path: &str) -> Response {
+    match path {
+        "/livez" => LivenessResponse {
+            status: HealthStatus::Healthy,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+        .into_response(),
+        _ => AppError::ServiceUnavailable(String::from("Waiting for Postgres and Redis"))
+            .into_response(),
+    }
+}
@@ -1,86 +1,930 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use deadpool_postgres::Pool;
-use redis::aio::ConnectionManager;
-use webauthn_rs::Webauthn;
+use redis::{Client, aio::ConnectionManager};
+use webauthn_rs::prelude::AttestationCaList;
 
+#[cfg(not(feature = "notifications"))]
+use crate::notification::disabled::DisabledNotifications;
 use crate::{
-    auth::{self, jwt::Jwt, service::AuthService},
+    admin::service::AdminService,
+    app::{
+        context::scope_region,
+        middleware::{maintenance::MaintenanceMode, metrics, rate_limit::RateLimiter},
+    },
+    audit::{self, service::AuditService},
+    auth::{
+        self,
+        ceremony::{CeremonySealer, RedisNonces},
+        ceremony_limit::CeremonyLimiter,
+        jwt::{
+            Jwt,
+            keys::AccessKeys,
+            revocation::{
+                LayeredRevocations, PostgresRevocations, RedisRevocations, VALIDATION_LEEWAY_SECS,
+            },
+        },
+        login_cache::{CachedRepository, LoginCache},
+        passkey_format::migrate_legacy_passkeys,
+        service::AuthService,
+        throttle::AccountThrottle,
+        traits::{AuthRepository, LoginStore},
+    },
+    banner::{self, service::BannerService},
+    cleanup::{self, service::CleanupService},
     config::{
-        CircuitBreaker, CircuitBreakerConfig, DbConfig, JwtConfig, OriginConfig, RedisConfig,
+        AccountThrottleConfig, CircuitBreaker, CircuitBreakerConfig, CleanupConfig,
+        ClientAppConfig, CookieConfig, CorsConfig, DbConfig, DbListenConfig, EventExportConfig,
+        FeatureFlags, IntrospectionConfig, JwtConfig, LoginCacheConfig, LoginHints, OriginConfig,
+        RateLimitConfig, RedisConfig, RedisMemoryConfig, RegionConfig, RequestPolicyConfig,
+        RevocationConfig, RolePolicy, SloConfig, StartupConfig, TenantConfig, UsernamePolicy,
         WebAuthnConfig,
+        webauthn::{ExtensionsConfig, RelyingParties, StatelessChallengeConfig},
+    },
+    credential_revocation::{self, service::CredentialRevocationService},
+    duplicates::{self, service::DuplicateService},
+    event_export::{self, EventExporter, traits::EventSink},
+    events::EventBus,
+    invitations::{self, service::InvitationService},
+    login_history::{self, service::LoginHistoryService},
+    machine_clients::{self, service::MachineClientService},
+    reports::{self, service::ReportService},
+    sessions::{self, service::SessionService},
+    slo::SloTracker,
+    suspensions::{self, service::SuspensionService},
+    token_issuance::{self, service::IssuanceService},
+    traffic::{self, service::TrafficService},
+    user_search::{self, service::UserSearchService},
+    utils::{
+        CookieService, MemoryMonitor, MemoryPressure, PgListener, PgNotifier,
+        PreparedStatementCache, ReadReplica, RedisShard, RedisShards, run_migrations,
+        set_statement_cache_limits, set_username_policy,
     },
-    utils::CookieService,
 };
+#[cfg(feature = "notifications")]
+use crate::{
+    auth::verification::EmailVerifier,
+    config::{EmailVerificationConfig, NotificationConfig},
+    notification::{self, service::NotificationService, stream::NotificationStream},
+};
+#[cfg(feature = "enrollment-reminders")]
+use crate::{
+    config::EnrollmentConfig,
+    enrollment::{self, service::EnrollmentService},
+};
+#[cfg(feature = "http-client")]
+use crate::{config::HttpClientConfig, utils::HttpClientService};
 
 pub struct AppConfig {
-    pub webauthn: Webauthn,
+    pub webauthn: RelyingParties,
+    pub stateless_challenges: Option<StatelessChallengeConfig>,
+    pub attestation_cas: Option<AttestationCaList>,
+    pub webauthn_extensions: ExtensionsConfig,
+    pub webauthn_max_concurrent_finishes: u32,
+    #[cfg(feature = "notifications")]
+    pub email_verification: Option<EmailVerificationConfig>,
     pub db: Pool,
+    /// Connections the hot queries are prepared on right after startup.
+    pub statement_warmup_connections: usize,
+    #[cfg(feature = "sqlx")]
+    pub sqlx_db: sqlx::PgPool,
+    /// Serves reads that tolerate replication lag, when configured.
+    pub db_replica: Option<Pool>,
+    #[cfg(feature = "sqlx")]
+    pub sqlx_db_replica: Option<sqlx::PgPool>,
+    /// The database of each data residency region.
+    pub db_regions: Vec<(Box<str>, Pool)>,
+    #[cfg(feature = "sqlx")]
+    pub sqlx_db_regions: Vec<(Box<str>, sqlx::PgPool)>,
+    /// Not spawned yet, so handlers can still be registered.
+    pub db_listener: Option<(PgListener, DbListenConfig)>,
     pub redis_manager: ConnectionManager,
+    pub redis_client: Client,
+    pub redis_shards: Vec<(Box<str>, ConnectionManager)>,
+    /// Names of the shards `redis_shards` connects to, and of the shards
+    /// before the last ring change while those are still read.
+    pub redis_ring: Vec<Box<str>>,
+    pub redis_previous_ring: Option<Vec<Box<str>>>,
+    pub redis_memory_config: RedisMemoryConfig,
     pub jwt_config: JwtConfig,
+    pub access_keys: AccessKeys,
+    pub cookie_config: CookieConfig,
     pub origin_config: OriginConfig,
-    pub circuit_breaker_config: CircuitBreakerConfig,
+    pub tenant_config: TenantConfig,
+    pub region_config: RegionConfig,
+    pub cors_config: CorsConfig,
+    pub db_circuit_breaker_config: CircuitBreakerConfig,
+    pub db_replica_circuit_breaker_config: CircuitBreakerConfig,
+    pub db_region_circuit_breaker_config: CircuitBreakerConfig,
+    pub redis_circuit_breaker_config: CircuitBreakerConfig,
+    #[cfg(feature = "http-client")]
+    pub http_client: Arc<HttpClientService>,
+    pub rate_limit_config: RateLimitConfig,
+    pub account_throttle_config: AccountThrottleConfig,
+    pub login_cache_config: LoginCacheConfig,
+    #[cfg(feature = "notifications")]
+    pub notification_config: NotificationConfig,
+    #[cfg(feature = "enrollment-reminders")]
+    pub enrollment_config: EnrollmentConfig,
+    pub cleanup_config: CleanupConfig,
+    pub revocation_config: RevocationConfig,
+    /// Set with `EVENT_EXPORT_URL`, already connected.
+    pub event_export: Option<(EventExportConfig, Arc<dyn EventSink>)>,
+    pub request_policy_config: RequestPolicyConfig,
+    pub introspection_config: IntrospectionConfig,
+    pub client_app_config: ClientAppConfig,
+    pub feature_flags: FeatureFlags,
+    pub slo_config: SloConfig,
+    pub username_policy: UsernamePolicy,
+    pub login_hints: LoginHints,
+    pub role_policy: RolePolicy,
 }
 
 impl AppConfig {
     pub async fn from_env() -> Self {
-        let db_config = DbConfig::from_env();
+        Self::connect(
+            DbConfig::from_env(),
+            RedisConfig::from_env(),
+            OriginConfig::from_env(),
+            WebAuthnConfig::from_env(),
+            JwtConfig::from_env(),
+        )
+        .await
+    }
+
+    /// Builds the configuration around the settings that have no defaults,
+    /// reading the rest from the environment.
+    pub async fn connect(
+        db_config: DbConfig,
+        redis_config: RedisConfig,
+        origin_config: OriginConfig,
+        webauthn_config: WebAuthnConfig,
+        jwt_config: JwtConfig,
+    ) -> Self {
+        let startup = StartupConfig::from_env();
+        set_statement_cache_limits(db_config.statement_cache.clone());
+        let statement_warmup_connections = db_config.statement_cache.warmup_connections;
         let db = db_config.create_pool();
+        #[cfg(feature = "sqlx")]
+        let sqlx_db = db_config.create_sqlx_pool();
+        let replica_config = db_config.for_replica();
+        let db_replica = replica_config.as_ref().map(DbConfig::create_pool);
+        #[cfg(feature = "sqlx")]
+        let sqlx_db_replica = replica_config.as_ref().map(DbConfig::create_sqlx_pool);
+        let db_regions: Vec<_> = db_config
+            .regions
+            .iter()
+            .map(|region| {
+                (
+                    region.name.clone(),
+                    db_config.for_region(region).create_pool(),
+                )
+            })
+            .collect();
+        wait_for_databases(&startup, &db, &db_regions).await;
+        if db_config.run_migrations {
+            migrate(&db_config, "home").await;
+            for region in &db_config.regions {
+                migrate(&db_config.for_region(region), &region.name).await;
+            }
+        }
+        #[cfg(feature = "sqlx")]
+        let sqlx_db_regions = db_config
+            .regions
+            .iter()
+            .map(|region| {
+                (
+                    region.name.clone(),
+                    db_config.for_region(region).create_sqlx_pool(),
+                )
+            })
+            .collect();
+        let db_listener =
+            DbListenConfig::from_env().map(|listen| (db_config.create_listener(&listen), listen));
 
-        let origin_config = OriginConfig::from_env();
-        let webauthn_config = WebAuthnConfig::from_env();
         let webauthn = webauthn_config.create_webauthn(&origin_config);
+        let stateless_challenges = webauthn_config.stateless;
+        let attestation_cas = webauthn_config.attestation_cas;
+        let webauthn_extensions = webauthn_config.extensions;
+        let webauthn_max_concurrent_finishes = webauthn_config.max_concurrent_finishes;
+        #[cfg(feature = "notifications")]
+        let email_verification = EmailVerificationConfig::from_env();
 
-        let redis_config = RedisConfig::from_env();
-        let redis_manager = redis_config.create_conn_manager().await;
+        let redis_manager = redis_config.create_conn_manager(&startup).await;
+        let redis_client = redis_config.create_client();
+        let redis_shards = redis_config.create_shard_managers(&startup).await;
+        redis_config
+            .check_shard_ring(
+                &redis_manager,
+                jwt_config
+                    .refresh_token_duration()
+                    .max(jwt_config.trusted_refresh_token_duration())
+                    + Duration::from_secs(VALIDATION_LEEWAY_SECS as u64),
+            )
+            .await;
+        let redis_memory_config = RedisMemoryConfig::from_env();
 
-        let jwt_config = JwtConfig::from_env();
+        let tenant_config = TenantConfig::from_env(&origin_config);
+        let region_config = RegionConfig::from_env(&origin_config, &db_config.regions);
+        let cookie_config = CookieConfig::from_env(
+            jwt_config.refresh_token_duration(),
+            jwt_config.trusted_refresh_token_duration(),
+        );
 
-        let circuit_breaker_config = CircuitBreakerConfig::default();
+        let db_circuit_breaker_config = CircuitBreakerConfig::from_env("DB");
+        let db_replica_circuit_breaker_config = CircuitBreakerConfig::from_env("DB_REPLICA");
+        let db_region_circuit_breaker_config = CircuitBreakerConfig::from_env("DB_REGION");
+        let redis_circuit_breaker_config = CircuitBreakerConfig::from_env("REDIS");
+        #[cfg(feature = "http-client")]
+        let http_client = Arc::new(HttpClientService::new(HttpClientConfig::from_env()));
+        #[cfg(feature = "kms")]
+        let access_keys = match jwt_config.kms() {
+            Some(kms) => {
+                AccessKeys::from_signer(
+                    kms.create_signer(Arc::clone(&http_client)),
+                    &kms.public_pem,
+                    kms.kid.as_deref(),
+                )
+                .await
+            }
+            None => AccessKeys::from_config(&jwt_config),
+        };
+        #[cfg(not(feature = "kms"))]
+        let access_keys = AccessKeys::from_config(&jwt_config);
+        let access_keys =
+            access_keys.unwrap_or_else(|e| panic!("Invalid JWT access token key: {}", e));
+        let rate_limit_config = RateLimitConfig::from_env();
+        let account_throttle_config = AccountThrottleConfig::from_env();
+        let login_cache_config = LoginCacheConfig::from_env();
+        #[cfg(feature = "notifications")]
+        let notification_config = NotificationConfig::from_env();
+        #[cfg(feature = "enrollment-reminders")]
+        let enrollment_config = EnrollmentConfig::from_env();
+        let cleanup_config = CleanupConfig::from_env();
+        let revocation_config = RevocationConfig::from_env();
+        let event_export = match EventExportConfig::from_env() {
+            Some(config) => {
+                let sink = event_export::connect_sink(&config).await;
+                tracing::info!("Exporting domain events to {}", sink.name());
+                Some((config, sink))
+            }
+            None => None,
+        };
+        let request_policy_config = RequestPolicyConfig::from_env();
+        let cors_config = CorsConfig::from_env();
+        let introspection_config = IntrospectionConfig::from_env();
+        let client_app_config = ClientAppConfig::from_env();
+        let feature_flags = FeatureFlags::from_env();
+        let slo_config = SloConfig::from_env();
+        let username_policy = UsernamePolicy::from_env();
+        let login_hints = LoginHints::from_env();
+        let role_policy = RolePolicy::from_env();
 
         Self {
             webauthn,
+            stateless_challenges,
+            attestation_cas,
+            webauthn_extensions,
+            webauthn_max_concurrent_finishes,
+            #[cfg(feature = "notifications")]
+            email_verification,
             db,
+            statement_warmup_connections,
+            #[cfg(feature = "sqlx")]
+            sqlx_db,
+            db_replica,
+            #[cfg(feature = "sqlx")]
+            sqlx_db_replica,
+            db_regions,
+            #[cfg(feature = "sqlx")]
+            sqlx_db_regions,
+            db_listener,
             redis_manager,
+            redis_client,
+            redis_shards,
+            redis_ring: redis_config.ring(),
+            redis_previous_ring: redis_config.previous_ring(),
+            redis_memory_config,
             jwt_config,
+            access_keys,
+            cookie_config,
             origin_config,
-            circuit_breaker_config,
+            tenant_config,
+            region_config,
+            cors_config,
+            db_circuit_breaker_config,
+            db_replica_circuit_breaker_config,
+            db_region_circuit_breaker_config,
+            redis_circuit_breaker_config,
+            #[cfg(feature = "http-client")]
+            http_client,
+            rate_limit_config,
+            account_throttle_config,
+            login_cache_config,
+            #[cfg(feature = "notifications")]
+            notification_config,
+            #[cfg(feature = "enrollment-reminders")]
+            enrollment_config,
+            cleanup_config,
+            revocation_config,
+            event_export,
+            request_policy_config,
+            introspection_config,
+            client_app_config,
+            feature_flags,
+            slo_config,
+            username_policy,
+            login_hints,
+            role_policy,
         }
     }
 }
 
+#[cfg(feature = "notifications")]
+type AppNotifications = NotificationService<notification::Repository>;
+#[cfg(not(feature = "notifications"))]
+type AppNotifications = DisabledNotifications;
+
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
+type AppAuthRepository = auth::Repository;
+#[cfg(feature = "memory-store")]
+type AppAuthRepository = auth::MemoryRepository;
+#[cfg(feature = "sqlx")]
+type AppAuthRepository = auth::SqlxRepository;
+
+type AppAuthService = AuthService<
+    CachedRepository<AppAuthRepository>,
+    Jwt,
+    AppNotifications,
+    AuditService<audit::Repository>,
+    RedisNonces,
+>;
+
 pub struct AppState {
-    pub auth_service: Arc<AuthService<auth::Repository, Jwt>>,
+    pub auth_service: Arc<AppAuthService>,
     pub jwt_service: Arc<Jwt>,
     pub cookie_service: Arc<CookieService>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub traffic_service: Arc<TrafficService<traffic::Repository>>,
+    pub admin_service: Arc<AdminService<Jwt, AuditService<audit::Repository>>>,
+    pub audit_service: Arc<AuditService<audit::Repository>>,
+    pub issuance_service: Arc<IssuanceService<token_issuance::Repository>>,
+    pub banner_service: Arc<BannerService<banner::Repository, AuditService<audit::Repository>>>,
+    pub report_service: Arc<ReportService<reports::Repository>>,
+    pub user_search_service: Arc<UserSearchService<user_search::Repository>>,
+    pub duplicate_service: Arc<
+        DuplicateService<
+            CachedRepository<duplicates::Repository>,
+            Jwt,
+            AuditService<audit::Repository>,
+        >,
+    >,
+    pub session_service:
+        Arc<SessionService<sessions::Repository, Jwt, AuditService<audit::Repository>>>,
+    pub credential_revocation_service: Arc<
+        CredentialRevocationService<
+            CachedRepository<credential_revocation::Repository>,
+            AppNotifications,
+            AuditService<audit::Repository>,
+        >,
+    >,
+    pub suspension_service: Arc<
+        SuspensionService<
+            CachedRepository<suspensions::Repository>,
+            AuditService<audit::Repository>,
+        >,
+    >,
+    pub machine_client_service: Arc<
+        MachineClientService<machine_clients::Repository, Jwt, AuditService<audit::Repository>>,
+    >,
+    pub invitation_service:
+        Arc<InvitationService<invitations::Repository, AuditService<audit::Repository>>>,
+    pub maintenance: Arc<MaintenanceMode>,
+    pub event_bus: Arc<EventBus>,
+    pub request_policies: RequestPolicyConfig,
+    /// Resolves the tenant every request is scoped to.
+    pub tenants: TenantConfig,
+    /// Resolves the region whose database a request's accounts live in.
+    pub regions: RegionConfig,
+    /// Who may call `/auth/introspect`.
+    pub introspection: IntrospectionConfig,
+    /// The client registry, for binding tokens to applications.
+    pub client_apps: Arc<ClientAppConfig>,
+    /// Percentage rollouts of new flows.
+    pub feature_flags: Arc<FeatureFlags>,
+    pub slo_tracker: Arc<SloTracker>,
+    /// Outbound HTTP for integrations, with a breaker per destination.
+    #[cfg(feature = "http-client")]
+    #[cfg_attr(not(feature = "strict"), allow(dead_code))]
+    pub http_client: Arc<HttpClientService>,
+    #[cfg(feature = "enrollment-reminders")]
+    pub enrollment_service: Arc<EnrollmentService<enrollment::Repository, AppNotifications>>,
 }
 
 impl AppState {
     pub fn new(params: AppConfig) -> Arc<Self> {
+        set_username_policy(params.username_policy);
         let db_circuit_breaker = Arc::new(CircuitBreaker::new(
             "database",
-            params.circuit_breaker_config,
+            params.db_circuit_breaker_config,
+        ));
+        let redis_circuit_breaker = Arc::new(CircuitBreaker::new(
+            "redis",
+            params.redis_circuit_breaker_config,
         ));
-        let redis_circuit_breaker =
-            Arc::new(CircuitBreaker::new("redis", params.circuit_breaker_config));
+        let replica_circuit_breaker = params.db_replica.is_some().then(|| {
+            Arc::new(CircuitBreaker::new(
+                "database-replica",
+                params.db_replica_circuit_breaker_config,
+            ))
+        });
+        let replica =
+            params
+                .db_replica
+                .zip(replica_circuit_breaker.clone())
+                .map(|(db, circuit_breaker)| ReadReplica {
+                    db,
+                    circuit_breaker,
+                });
+        let region_circuit_breakers: Vec<(Box<str>, Arc<CircuitBreaker>)> = params
+            .db_regions
+            .iter()
+            .map(|(name, _)| {
+                let circuit_breaker = CircuitBreaker::new(
+                    &format!("database-region:{}", name),
+                    params.db_region_circuit_breaker_config,
+                );
+                (name.clone(), Arc::new(circuit_breaker))
+            })
+            .collect();
 
-        let user_repo = Arc::new(auth::Repository::new(params.db, db_circuit_breaker));
+        #[cfg(feature = "http-client")]
+        let http_client = params.http_client;
+        #[cfg(feature = "notifications")]
+        let notification_service = {
+            let stream = params.notification_config.stream.clone().map(|config| {
+                Arc::new(NotificationStream::new(
+                    params.redis_client.clone(),
+                    params.redis_manager.clone(),
+                    Arc::clone(&redis_circuit_breaker),
+                    config,
+                ))
+            });
+            let notification_service = Arc::new(
+                NotificationService::new(
+                    Arc::new(notification::Repository::new(
+                        params.db.clone(),
+                        Arc::clone(&db_circuit_breaker),
+                    )),
+                    params.notification_config.create_notifiers(&http_client),
+                    Arc::new(params.notification_config.create_renderer()),
+                )
+                .with_stream(stream),
+            );
+            notification_service.spawn_worker();
+            notification_service
+        };
+        #[cfg(not(feature = "notifications"))]
+        let notification_service = Arc::new(DisabledNotifications);
+        let audit_repo = Arc::new(
+            audit::Repository::new(params.db.clone(), Arc::clone(&db_circuit_breaker))
+                .with_replica(replica.clone()),
+        );
+        let audit_service = Arc::new(AuditService::new(audit_repo));
+        let banner_repo = Arc::new(banner::Repository::new(
+            params.db.clone(),
+            Arc::clone(&db_circuit_breaker),
+        ));
+        let banner_service = Arc::new(BannerService::new(banner_repo, Arc::clone(&audit_service)));
+        let report_service = Arc::new(ReportService::new(Arc::new(reports::Repository::new(
+            params.db.clone(),
+            Arc::clone(&db_circuit_breaker),
+        ))));
+        let user_search_service = Arc::new(UserSearchService::new(Arc::new(
+            user_search::Repository::new(params.db.clone(), Arc::clone(&db_circuit_breaker)),
+        )));
+        let login_cache = params.login_cache_config.enabled.then(|| {
+            Arc::new(LoginCache::new(
+                params.redis_manager.clone(),
+                Arc::clone(&redis_circuit_breaker),
+                params.login_cache_config.ttl,
+            )) as Arc<dyn LoginStore>
+        });
+        let credential_revocation_service = Arc::new(CredentialRevocationService::new(
+            Arc::new(CachedRepository::new(
+                credential_revocation::Repository::new(
+                    params.db.clone(),
+                    Arc::clone(&db_circuit_breaker),
+                ),
+                login_cache.clone(),
+            )),
+            Arc::clone(&notification_service),
+            Arc::clone(&audit_service),
+        ));
+        let suspension_service = Arc::new(SuspensionService::new(
+            Arc::new(CachedRepository::new(
+                suspensions::Repository::new(params.db.clone(), Arc::clone(&db_circuit_breaker)),
+                login_cache.clone(),
+            )),
+            Arc::clone(&audit_service),
+        ));
+        let machine_client_repo = Arc::new(machine_clients::Repository::new(
+            params.db.clone(),
+            Arc::clone(&db_circuit_breaker),
+        ));
+        let invitation_service = Arc::new(InvitationService::new(
+            Arc::new(invitations::Repository::new(
+                params.db.clone(),
+                Arc::clone(&db_circuit_breaker),
+            )),
+            Arc::clone(&audit_service),
+            params.role_policy.clone(),
+        ));
+        let duplicate_repo = Arc::new(CachedRepository::new(
+            duplicates::Repository::new(params.db.clone(), Arc::clone(&db_circuit_breaker)),
+            login_cache.clone(),
+        ));
+        let session_repo = Arc::new(sessions::Repository::new(
+            params.db.clone(),
+            Arc::clone(&db_circuit_breaker),
+        ));
+        let login_history_repo = Arc::new(login_history::Repository::new(
+            params.db.clone(),
+            Arc::clone(&db_circuit_breaker),
+        ));
+        let issuance_service = Arc::new(IssuanceService::new(Arc::new(
+            token_issuance::Repository::new(params.db.clone(), Arc::clone(&db_circuit_breaker))
+                .with_replica(replica.clone()),
+        )));
+        #[cfg(feature = "enrollment-reminders")]
+        let enrollment_service = {
+            let enrollment_repo = Arc::new(enrollment::Repository::new(
+                params.db.clone(),
+                Arc::clone(&db_circuit_breaker),
+            ));
+            let enrollment_service = Arc::new(EnrollmentService::new(
+                enrollment_repo,
+                Arc::clone(&notification_service),
+                params.enrollment_config,
+            ));
+            enrollment_service.spawn_campaign();
+            enrollment_service
+        };
+        let cleanup_repo = Arc::new(cleanup::Repository::new(
+            params.db.clone(),
+            Arc::clone(&db_circuit_breaker),
+        ));
+        let cleanup_service = Arc::new(CleanupService::new(cleanup_repo, params.cleanup_config));
+        cleanup_service.spawn_purge();
+        if params.stateless_challenges.is_none() {
+            cleanup_service.spawn_pending_sampler();
+        }
+        let cache_flush = params.db_listener.map(|(mut listener, listen)| {
+            listener.on(&listen.cache_channel, |_| {
+                let removed = PreparedStatementCache::clear_all();
+                tracing::info!("Flushed {} prepared statements on notification", removed);
+            });
+            listener.spawn();
+            Arc::new(PgNotifier::new(
+                params.db.clone(),
+                Arc::clone(&db_circuit_breaker),
+                &listen.cache_channel,
+            ))
+        });
+        let revocation_fallback = params.revocation_config.fallback_max_outage.map(|_| {
+            PostgresRevocations::new(
+                params.db.clone(),
+                Arc::clone(&db_circuit_breaker),
+                params.revocation_config.fallback_max_entries,
+            )
+        });
+        #[cfg(feature = "memory-store")]
+        let user_repo = auth::MemoryRepository::new();
+        #[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
+        let user_repo = auth::Repository::new(params.db, Arc::clone(&db_circuit_breaker))
+            .with_replica(replica)
+            .with_regions(
+                params
+                    .db_regions
+                    .into_iter()
+                    .zip(region_circuit_breakers.iter())
+                    .map(
+                        |((name, db), (_, circuit_breaker))| crate::utils::RegionDatabase {
+                            name,
+                            db,
+                            circuit_breaker: Arc::clone(circuit_breaker),
+                        },
+                    )
+                    .collect(),
+            );
+        #[cfg(feature = "sqlx")]
+        let user_repo = auth::SqlxRepository::new(params.sqlx_db, Arc::clone(&db_circuit_breaker))
+            .with_replica(params.sqlx_db_replica.zip(replica_circuit_breaker.clone()))
+            .with_regions(
+                params
+                    .sqlx_db_regions
+                    .into_iter()
+                    .zip(region_circuit_breakers.iter())
+                    .map(|((name, db), (_, circuit_breaker))| {
+                        (name, db, Arc::clone(circuit_breaker))
+                    })
+                    .collect(),
+            );
+        let user_repo = Arc::new(CachedRepository::new(user_repo, login_cache));
+        {
+            let user_repo = Arc::clone(&user_repo);
+            let tenants = params.tenant_config.tenants().to_vec();
+            let regions = params.region_config.regions().to_vec();
+            tokio::spawn(async move {
+                let databases = std::iter::once(None).chain(regions.iter().map(Some));
+                for region in databases {
+                    let registered = scope_region(
+                        region.map(|region| region.to_string()),
+                        user_repo.register_tenants(&tenants),
+                    )
+                    .await;
+                    if let Err(e) = registered {
+                        tracing::error!(
+                            region = ?region,
+                            "Failed to register tenants {:?}: {}",
+                            tenants,
+                            e
+                        );
+                    }
+                }
+            });
+        }
+        if params.statement_warmup_connections > 0 {
+            let user_repo = Arc::clone(&user_repo);
+            let connections = params.statement_warmup_connections;
+            tokio::spawn(async move {
+                let started = Instant::now();
+                match user_repo.warm_up(connections).await {
+                    Ok(prepared) => tracing::info!(
+                        "Warmed the statement cache with {} statements in {:?}",
+                        prepared,
+                        started.elapsed()
+                    ),
+                    Err(e) => tracing::warn!("Statement cache warm-up failed: {}", e),
+                }
+            });
+        }
+        let blacklist_shards = (!params.redis_shards.is_empty()).then(|| {
+            Arc::new(RedisShards::new(
+                params
+                    .redis_shards
+                    .into_iter()
+                    .map(|(name, connection_manager)| RedisShard {
+                        circuit_breaker: Arc::new(CircuitBreaker::new(
+                            &format!("redis-shard:{}", name),
+                            params.redis_circuit_breaker_config,
+                        )),
+                        name,
+                        connection_manager,
+                    })
+                    .collect(),
+                &params.redis_ring,
+                params.redis_previous_ring.as_deref(),
+            ))
+        });
+        let memory_pressure = Arc::new(MemoryPressure::default());
+        MemoryMonitor::new(
+            params.redis_manager.clone(),
+            Arc::clone(&redis_circuit_breaker),
+            params.redis_memory_config,
+            Arc::clone(&memory_pressure),
+        )
+        .spawn();
+        let rate_limiter = Arc::new(RateLimiter::new(
+            params.redis_manager.clone(),
+            Arc::clone(&redis_circuit_breaker),
+            params.rate_limit_config,
+        ));
+        let traffic_repo = Arc::new(traffic::Repository::new(
+            params.redis_manager.clone(),
+            Arc::clone(&redis_circuit_breaker),
+        ));
+        let traffic_service = Arc::new(TrafficService::new(
+            traffic_repo,
+            Arc::clone(&memory_pressure),
+        ));
+        let account_throttle = params.account_throttle_config.enabled.then(|| {
+            Arc::new(AccountThrottle::new(
+                params.redis_manager.clone(),
+                Arc::clone(&redis_circuit_breaker),
+                params.account_throttle_config,
+            ))
+        });
+        let maintenance = Arc::new(MaintenanceMode::shared(
+            params.redis_manager.clone(),
+            Arc::clone(&redis_circuit_breaker),
+        ));
+        maintenance.spawn_refresh();
+        let challenge_nonces = Arc::new(RedisNonces::new(
+            params.redis_manager.clone(),
+            Arc::clone(&redis_circuit_breaker),
+        ));
+        let event_bus = Arc::new(EventBus::new(
+            params.redis_client,
+            params.redis_manager.clone(),
+            Arc::clone(&redis_circuit_breaker),
+        ));
+        event_bus.spawn_listener();
+        let login_history_service = Arc::new(
+            LoginHistoryService::new(login_history_repo).with_events(Arc::clone(&event_bus) as _),
+        );
+        #[cfg(feature = "notifications")]
+        let email_verifier = params.email_verification.as_ref().map(|config| {
+            EmailVerifier::new(
+                params.redis_manager.clone(),
+                Arc::clone(&redis_circuit_breaker),
+                Arc::new(
+                    params
+                        .notification_config
+                        .create_verification_sender(config, &http_client),
+                ),
+                config.ttl,
+            )
+        });
+        #[cfg(not(feature = "notifications"))]
+        let email_verifier = None;
+        let revocations = LayeredRevocations::new(
+            RedisRevocations::new(
+                params.redis_manager.clone(),
+                Arc::clone(&redis_circuit_breaker),
+                blacklist_shards.clone(),
+                memory_pressure,
+            ),
+            revocation_fallback,
+            &params.revocation_config,
+        );
         let jwt_service = Arc::new(Jwt::new(
             &params.jwt_config,
+            params.access_keys,
             params.redis_manager,
-            redis_circuit_breaker,
+            Arc::clone(&redis_circuit_breaker),
+            revocations,
+        ));
+        jwt_service.spawn_key_rotation();
+        jwt_service.spawn_keyring_refresh();
+        let client_apps = Arc::new(params.client_app_config);
+        let ceremony_limiter = Arc::new(CeremonyLimiter::new(
+            params.webauthn_max_concurrent_finishes,
+        ));
+        let auth_service = Arc::new(
+            AuthService::new(
+                params.webauthn,
+                user_repo,
+                Arc::clone(&jwt_service),
+                notification_service,
+                Arc::clone(&audit_service),
+                params
+                    .stateless_challenges
+                    .as_ref()
+                    .map(CeremonySealer::new),
+                challenge_nonces,
+            )
+            .with_email_verification(email_verifier)
+            .with_attestation_cas(params.attestation_cas)
+            .with_extensions(params.webauthn_extensions)
+            .with_events(Arc::clone(&event_bus) as _)
+            .with_exporter(params.event_export.map(|(config, sink)| {
+                Arc::new(EventExporter::new(sink, config.queue_capacity)) as _
+            }))
+            .with_login_history(login_history_service)
+            .with_issuance_log(Arc::clone(&issuance_service) as _)
+            .with_client_apps(Arc::clone(&client_apps))
+            .with_ceremony_limiter(Arc::clone(&ceremony_limiter))
+            .with_regions(params.region_config.regions().to_vec())
+            .with_login_hints(params.login_hints)
+            .with_account_throttle(account_throttle.clone())
+            .with_role_policy(params.role_policy)
+            .with_invitations(Arc::clone(&invitation_service) as _),
+        );
+        let cookie_service = Arc::new(CookieService::new(
+            &params.origin_config,
+            &params.cookie_config,
+        ));
+        let slo_tracker = Arc::new(SloTracker::new(params.slo_config));
+        slo_tracker.register();
+        let mut circuit_breakers = vec![db_circuit_breaker, redis_circuit_breaker];
+        circuit_breakers.extend(replica_circuit_breaker);
+        circuit_breakers.extend(
+            region_circuit_breakers
+                .into_iter()
+                .map(|(_, circuit_breaker)| circuit_breaker),
+        );
+        if let Some(shards) = &blacklist_shards {
+            circuit_breakers.extend(shards.circuit_breakers());
+        }
+        let duplicate_service = Arc::new(DuplicateService::new(
+            duplicate_repo,
+            Arc::clone(&jwt_service),
+            Arc::clone(&audit_service),
         ));
-        let auth_service = Arc::new(AuthService::new(
-            params.webauthn,
-            user_repo,
+        let session_service = Arc::new(SessionService::new(
+            session_repo,
             Arc::clone(&jwt_service),
+            Arc::clone(&audit_service),
         ));
-        let cookie_service = Arc::new(CookieService::new(&params.origin_config));
+        let machine_client_service = Arc::new(MachineClientService::new(
+            machine_client_repo,
+            Arc::clone(&jwt_service),
+            Arc::clone(&audit_service),
+        ));
+        let admin_service = Arc::new(
+            AdminService::new(
+                Arc::clone(&jwt_service),
+                circuit_breakers,
+                Arc::clone(&maintenance),
+                Arc::clone(&audit_service),
+            )
+            .with_cache_flush(cache_flush)
+            .with_ceremony_limiter(ceremony_limiter)
+            .with_account_throttle(account_throttle),
+        );
+        admin_service.spawn_metrics_sampler();
 
         Arc::new(Self {
             auth_service,
             jwt_service,
             cookie_service,
+            rate_limiter,
+            traffic_service,
+            admin_service,
+            audit_service,
+            issuance_service,
+            banner_service,
+            report_service,
+            user_search_service,
+            duplicate_service,
+            session_service,
+            credential_revocation_service,
+            suspension_service,
+            machine_client_service,
+            invitation_service,
+            maintenance,
+            event_bus,
+            request_policies: params.request_policy_config,
+            tenants: params.tenant_config,
+            regions: params.region_config,
+            introspection: params.introspection_config,
+            client_apps,
+            feature_flags: Arc::new(params.feature_flags),
+            slo_tracker,
+            #[cfg(feature = "http-client")]
+            http_client,
+            #[cfg(feature = "enrollment-reminders")]
+            enrollment_service,
         })
     }
+
+    /// Counts a registration step in the metrics and under the user's
+    /// feature flag variants. Shared by the REST and gRPC handlers.
+    pub fn track_registration(&self, username: &str, success: bool) {
+        metrics::track_registration_attempt(success);
+        self.feature_flags.track("register", username, success);
+    }
+
+    /// Counts a login step, like `track_registration`.
+    pub fn track_login(&self, username: &str, success: bool) {
+        metrics::track_login_attempt(success);
+        self.feature_flags.track("login", username, success);
+    }
+}
+
+/// Holds startup until the primary and every regional database hand out a
+/// connection, so migrations and statement warmup find them up.
+async fn wait_for_databases(startup: &StartupConfig, db: &Pool, regions: &[(Box<str>, Pool)]) {
+    drop(startup.retry("Postgres", || db.get()).await);
+    for (region, pool) in regions {
+        let target = format!("Postgres {}", region);
+        drop(startup.retry(&target, || pool.get()).await);
+    }
+}
+
+/// Brings one database's schema and stored passkeys up to date.
+async fn migrate(db_config: &DbConfig, database: &str) {
+    let mut client = db_config.connect_migrator().await;
+    let applied = run_migrations(&mut client)
+        .await
+        .unwrap_or_else(|e| panic!("Database migrations failed on {}: {}", database, e));
+    tracing::info!(
+        "Database schema of {} up to date ({} migrations applied)",
+        database,
+        applied.len()
+    );
+    let rewritten = migrate_legacy_passkeys(&mut client)
+        .await
+        .unwrap_or_else(|e| panic!("Passkey migration failed on {}: {}", database, e));
+    if rewritten > 0 {
+        tracing::info!(
+            "Rewrote {} passkeys of {} in the current format",
+            rewritten,
+            database
+        );
+    }
 }
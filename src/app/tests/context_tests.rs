@@ -0,0 +1,170 @@
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    time::Duration,
+};
+
+use axum::{
+    body::to_bytes,
+    http::{HeaderMap, HeaderValue, header::USER_AGENT},
+    response::IntoResponse,
+};
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        AppError,
+        context::{
+            ASN_HEADER, CLIENT_APP_HEADER, COUNTRY_HEADER, REQUEST_ID_HEADER, RequestContext,
+            TENANT_HEADER, current_request_id, scope_request_id,
+        },
+        error::ErrorResponse,
+    },
+    auth::{jwt::AccessTokenClaims, model::Grants},
+};
+
+const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+fn headers(pairs: &[(axum::http::HeaderName, &'static str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        headers.insert(name.clone(), HeaderValue::from_static(value));
+    }
+    headers
+}
+
+#[test]
+fn test_request_id_is_generated_when_missing() {
+    let context = RequestContext::from_headers(&HeaderMap::new(), Some(IP), false);
+
+    assert!(Uuid::parse_str(&context.request_id).is_ok());
+    assert_eq!(context.ip, Some(IP));
+    assert!(context.subject.is_none());
+}
+
+#[test]
+fn test_inbound_request_id_is_reused() {
+    let context = RequestContext::from_headers(
+        &headers(&[(REQUEST_ID_HEADER, "edge-1234:abc")]),
+        None,
+        false,
+    );
+
+    assert_eq!(context.request_id, "edge-1234:abc");
+}
+
+#[test]
+fn test_unsafe_request_id_is_replaced() {
+    let context = RequestContext::from_headers(
+        &headers(&[(REQUEST_ID_HEADER, "bad id\twith spaces")]),
+        None,
+        false,
+    );
+
+    assert_ne!(context.request_id, "bad id\twith spaces");
+    assert!(Uuid::parse_str(&context.request_id).is_ok());
+}
+
+#[test]
+fn test_tenant_requires_trusted_proxy() {
+    let headers = headers(&[(TENANT_HEADER, "acme")]);
+
+    let untrusted = RequestContext::from_headers(&headers, None, false);
+    let trusted = RequestContext::from_headers(&headers, None, true);
+
+    assert_eq!(untrusted.tenant, None);
+    assert_eq!(trusted.tenant.as_deref(), Some("acme"));
+}
+
+#[test]
+fn test_client_app_is_read_without_proxy() {
+    let context =
+        RequestContext::from_headers(&headers(&[(CLIENT_APP_HEADER, "ios-2.3.1")]), None, false);
+
+    assert_eq!(context.client_app.as_deref(), Some("ios-2.3.1"));
+    assert_eq!(context.audit().client_app.as_deref(), Some("ios-2.3.1"));
+
+    let invalid = headers(&[(CLIENT_APP_HEADER, "<script>")]);
+    assert_eq!(
+        RequestContext::from_headers(&invalid, None, false).client_app,
+        None
+    );
+}
+
+#[test]
+fn test_network_requires_trusted_proxy() {
+    let forwarded = headers(&[(COUNTRY_HEADER, "de"), (ASN_HEADER, "AS3320")]);
+
+    let untrusted = RequestContext::from_headers(&forwarded, None, false);
+    let trusted = RequestContext::from_headers(&forwarded, None, true);
+
+    assert_eq!((untrusted.country, untrusted.asn), (None, None));
+    assert_eq!(trusted.country.as_deref(), Some("DE"));
+    assert_eq!(trusted.asn, Some(3320));
+    assert_eq!(trusted.audit().country.as_deref(), Some("DE"));
+
+    let invalid = headers(&[(COUNTRY_HEADER, "Germany"), (ASN_HEADER, "none")]);
+    let context = RequestContext::from_headers(&invalid, None, true);
+    assert_eq!((context.country, context.asn), (None, None));
+}
+
+#[test]
+fn test_subject_and_audit_context() {
+    let claims = AccessTokenClaims::new(
+        Uuid::new_v4(),
+        String::from("alice"),
+        Grants {
+            roles: vec![String::from("admin")],
+            permissions: vec![String::from("audit:read")],
+        },
+        Duration::from_secs(60),
+    );
+
+    let context =
+        RequestContext::from_headers(&headers(&[(USER_AGENT, "curl/8.0")]), Some(IP), false)
+            .with_subject(&claims);
+
+    let subject = context.subject.as_ref().unwrap();
+    assert_eq!(subject.user_id, claims.sub);
+    assert_eq!(subject.username, "alice");
+    assert_eq!(subject.roles, ["admin"]);
+
+    let audit = context.audit();
+    assert_eq!(audit.ip, Some(IP));
+    assert_eq!(audit.user_agent.as_deref(), Some("curl/8.0"));
+}
+
+#[tokio::test]
+async fn test_current_request_id_is_scoped_to_the_request() {
+    assert_eq!(current_request_id(), None);
+
+    let inside = scope_request_id(String::from("req-1"), async { current_request_id() }).await;
+
+    assert_eq!(inside.as_deref(), Some("req-1"));
+    assert_eq!(current_request_id(), None);
+}
+
+async fn error_body(error: AppError) -> ErrorResponse {
+    let body = to_bytes(error.into_response().into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_error_response_carries_request_id() {
+    let body = scope_request_id(
+        String::from("req-2"),
+        error_body(AppError::NotFound(String::from("missing"))),
+    )
+    .await;
+
+    assert_eq!(body.request_id.as_deref(), Some("req-2"));
+    assert_eq!(body.message, "not found: missing");
+}
+
+#[tokio::test]
+async fn test_error_response_outside_a_request_has_no_id() {
+    let body = error_body(AppError::BadRequest(String::from("nope"))).await;
+
+    assert_eq!(body.request_id, None);
+}
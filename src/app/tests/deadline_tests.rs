@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use axum::http::{HeaderMap, HeaderValue};
+
+use crate::app::{
+    AppError,
+    deadline::{
+        Deadline, REQUEST_TIMEOUT_HEADER, check_deadline, requested_timeout, scope_deadline,
+    },
+};
+
+#[tokio::test]
+async fn test_no_deadline_outside_a_request() {
+    assert_eq!(Deadline::current(), None);
+    assert!(check_deadline("postgres").is_ok());
+}
+
+#[tokio::test]
+async fn test_work_starts_with_time_left() {
+    let deadline = Deadline::after(Duration::from_secs(5));
+
+    scope_deadline(deadline, async {
+        assert_eq!(Deadline::current(), Some(deadline));
+        assert!(check_deadline("postgres").is_ok());
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_work_is_refused_near_the_deadline() {
+    let deadline = Deadline::after(Duration::from_millis(1));
+
+    scope_deadline(deadline, async {
+        assert!(deadline.is_nearly_expired());
+        assert!(matches!(
+            check_deadline("redis"),
+            Err(AppError::RequestTimeout(_))
+        ));
+    })
+    .await;
+}
+
+#[test]
+fn test_requested_timeout_is_read_in_milliseconds() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(requested_timeout(&headers), None);
+
+    headers.insert(REQUEST_TIMEOUT_HEADER, HeaderValue::from_static(" 250 "));
+    assert_eq!(
+        requested_timeout(&headers),
+        Some(Duration::from_millis(250))
+    );
+
+    for invalid in ["-1", "1.5", "2s"] {
+        headers.insert(REQUEST_TIMEOUT_HEADER, HeaderValue::from_static(invalid));
+        assert_eq!(requested_timeout(&headers), None, "{}", invalid);
+    }
+}
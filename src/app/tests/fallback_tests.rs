@@ -0,0 +1,79 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header},
+    response::Response,
+    routing::{get, post},
+};
+use tower::ServiceExt;
+
+use crate::app::{
+    error::{ErrorCode, ErrorResponse},
+    fallback,
+};
+
+fn router() -> Router {
+    Router::new()
+        .route("/auth/logout", post(|| async { "ok" }))
+        .route("/livez", get(|| async { "ok" }))
+        .fallback(fallback::route_not_found)
+        .method_not_allowed_fallback(fallback::method_not_allowed)
+}
+
+async fn send(method: Method, path: &str) -> Response {
+    router()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+async fn error_body(response: Response) -> ErrorResponse {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_unknown_route_returns_error_body() {
+    let response = send(Method::GET, "/nope").await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = error_body(response).await;
+    assert_eq!(body.code, ErrorCode::RouteNotFound);
+    assert!(body.message.contains("GET /nope"));
+}
+
+#[tokio::test]
+async fn test_trailing_slash_is_explained() {
+    let response = send(Method::POST, "/auth/logout/").await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(
+        error_body(response)
+            .await
+            .message
+            .contains("trailing slash")
+    );
+}
+
+#[tokio::test]
+async fn test_wrong_method_returns_405_with_allow() {
+    let response = send(Method::GET, "/auth/logout").await;
+
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()[header::ALLOW], "POST");
+    let body = error_body(response).await;
+    assert_eq!(body.code, ErrorCode::MethodNotAllowed);
+}
+
+#[tokio::test]
+async fn test_matched_routes_are_untouched() {
+    let response = send(Method::GET, "/livez").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,14 @@
+#[cfg(test)]
+mod context_tests;
+#[cfg(test)]
+mod deadline_tests;
+#[cfg(test)]
+mod fallback_tests;
+#[cfg(test)]
+mod openapi_tests;
+#[cfg(test)]
+mod policy_tests;
+#[cfg(test)]
+mod server_tests;
+#[cfg(test)]
+mod startup_tests;
@@ -0,0 +1,110 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use serde_json::{Value, json};
+use utoipa::{OpenApi, ToSchema};
+
+use crate::app::openapi::{DocumentFormat, OpenApiDocuments, downgrade_to_3_0};
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+struct Inner {
+    value: i32,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+struct Outer {
+    name: Option<String>,
+    inner: Option<Inner>,
+    tags: Vec<String>,
+}
+
+#[derive(OpenApi)]
+#[openapi(components(schemas(Inner, Outer)))]
+struct TestDoc;
+
+fn downgraded() -> Value {
+    let mut value = serde_json::to_value(TestDoc::openapi()).unwrap();
+    downgrade_to_3_0(&mut value);
+    value
+}
+
+fn accept(value: &'static str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+    headers
+}
+
+#[test]
+fn test_downgrade_sets_version() {
+    assert_eq!(downgraded()["openapi"], "3.0.3");
+}
+
+#[test]
+fn test_nullable_type_array_becomes_nullable_flag() {
+    let name = &downgraded()["components"]["schemas"]["Outer"]["properties"]["name"];
+
+    assert_eq!(name["type"], "string");
+    assert_eq!(name["nullable"], true);
+}
+
+#[test]
+fn test_nullable_reference_is_wrapped_in_all_of() {
+    let inner = &downgraded()["components"]["schemas"]["Outer"]["properties"]["inner"];
+
+    assert_eq!(inner["nullable"], true);
+    assert_eq!(inner["allOf"][0]["$ref"], "#/components/schemas/Inner");
+    assert!(inner.get("oneOf").is_none());
+}
+
+#[test]
+fn test_non_nullable_schemas_are_untouched() {
+    let outer = &downgraded()["components"]["schemas"]["Outer"];
+
+    assert_eq!(outer["properties"]["tags"]["type"], "array");
+    assert!(outer["properties"]["tags"].get("nullable").is_none());
+}
+
+#[test]
+fn test_schema_examples_become_example() {
+    let mut document = json!({
+        "openapi": "3.1.0",
+        "info": { "license": { "name": "MIT", "identifier": "MIT" } },
+        "paths": {
+            "/users": { "get": { "parameters": [
+                { "name": "q", "schema": { "type": ["string", "null"], "examples": ["alice"] } }
+            ] } }
+        }
+    });
+
+    downgrade_to_3_0(&mut document);
+
+    let schema = &document["paths"]["/users"]["get"]["parameters"][0]["schema"];
+    assert_eq!(schema["example"], "alice");
+    assert_eq!(schema["nullable"], true);
+    assert!(document["info"]["license"].get("identifier").is_none());
+}
+
+#[test]
+fn test_yaml_is_negotiated_from_accept() {
+    assert_eq!(
+        DocumentFormat::from_accept(&accept("application/yaml")),
+        DocumentFormat::Yaml
+    );
+    assert_eq!(
+        DocumentFormat::from_accept(&accept("text/html, application/x-yaml;q=0.9")),
+        DocumentFormat::Yaml
+    );
+    assert_eq!(
+        DocumentFormat::from_accept(&accept("application/json")),
+        DocumentFormat::Json
+    );
+    assert_eq!(
+        DocumentFormat::from_accept(&HeaderMap::new()),
+        DocumentFormat::Json
+    );
+}
+
+#[test]
+fn test_documents_render_every_variant() {
+    OpenApiDocuments::new(&TestDoc::openapi());
+}
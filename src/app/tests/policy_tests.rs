@@ -0,0 +1,37 @@
+use axum::{
+    body::{Body, to_bytes},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use http_body_util::Limited;
+
+use crate::app::AppError;
+
+async fn read_limited(body: &'static str, limit: usize) -> Result<String, AppError> {
+    let body = Body::new(Limited::new(Body::from(body), limit));
+    let bytes = to_bytes(body, usize::MAX).await?;
+    Ok(String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_body_within_limit_is_read() {
+    assert_eq!(read_limited("hello", 5).await.unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn test_body_past_limit_is_payload_too_large() {
+    let error = read_limited("hello world", 5).await.unwrap_err();
+
+    assert!(matches!(error, AppError::PayloadTooLarge(_)));
+    assert_eq!(
+        error.into_response().status(),
+        StatusCode::PAYLOAD_TOO_LARGE
+    );
+}
+
+#[test]
+fn test_timeout_maps_to_408() {
+    let response = AppError::RequestTimeout(String::from("slow")).into_response();
+
+    assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use axum::{Router, routing::get};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+};
+
+use crate::app::server::{BindAddr, ConnectionTuning, ServerConfig, ServerConfigError, serve};
+
+fn config(vars: &[(&str, &str)]) -> Result<ServerConfig, ServerConfigError> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    ServerConfig::from_lookup(|key| vars.get(key).cloned())
+}
+
+fn tcp(addr: &str, dual_stack: bool) -> BindAddr {
+    BindAddr::Tcp {
+        addr: addr.parse().unwrap(),
+        dual_stack,
+    }
+}
+
+#[test]
+fn test_defaults_match_the_previous_bind_address() {
+    assert_eq!(config(&[]).unwrap(), ServerConfig::default());
+    assert_eq!(config(&[]).unwrap().bind_addr, tcp("0.0.0.0:8080", false));
+}
+
+#[test]
+fn test_host_and_port_are_read() {
+    let config = config(&[("HOST", "127.0.0.1"), ("PORT", "3000")]).unwrap();
+
+    assert_eq!(config.bind_addr, tcp("127.0.0.1:3000", false));
+}
+
+#[test]
+fn test_bracketed_ipv6_host() {
+    let config = config(&[("HOST", "[::1]")]).unwrap();
+
+    assert_eq!(config.bind_addr, tcp("[::1]:8080", false));
+}
+
+#[test]
+fn test_dual_stack_defaults_to_the_ipv6_wildcard() {
+    let config = config(&[("IPV6_DUAL_STACK", "true")]).unwrap();
+
+    assert_eq!(
+        config.bind_addr,
+        BindAddr::Tcp {
+            addr: SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 8080),
+            dual_stack: true,
+        }
+    );
+}
+
+#[test]
+fn test_invalid_values_are_errors() {
+    assert_eq!(
+        config(&[("HOST", "localhost")]),
+        Err(ServerConfigError::InvalidHost("localhost".into()))
+    );
+    assert_eq!(
+        config(&[("PORT", "0")]),
+        Err(ServerConfigError::InvalidPort("0".into()))
+    );
+    assert_eq!(
+        config(&[("PORT", "70000")]),
+        Err(ServerConfigError::InvalidPort("70000".into()))
+    );
+    assert_eq!(
+        config(&[("IPV6_DUAL_STACK", "yes")]),
+        Err(ServerConfigError::InvalidDualStack("yes".into()))
+    );
+}
+
+#[test]
+fn test_dual_stack_requires_an_ipv6_host() {
+    assert_eq!(
+        config(&[("HOST", "0.0.0.0"), ("IPV6_DUAL_STACK", "true")]),
+        Err(ServerConfigError::DualStackRequiresIpv6(
+            "0.0.0.0".parse().unwrap()
+        ))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix_socket_replaces_tcp() {
+    let config = config(&[("UNIX_SOCKET", "/run/rs-server.sock")]).unwrap();
+
+    assert_eq!(
+        config.bind_addr,
+        BindAddr::Unix(std::path::PathBuf::from("/run/rs-server.sock"))
+    );
+}
+
+#[test]
+fn test_unix_socket_conflicts_with_tcp_settings() {
+    assert_eq!(
+        config(&[("UNIX_SOCKET", "/run/rs-server.sock"), ("PORT", "8080")]),
+        Err(ServerConfigError::UnixSocketConflict)
+    );
+}
+
+#[test]
+fn test_tuning_variables_are_read() {
+    let config = config(&[
+        ("HTTP2_ENABLED", "true"),
+        ("HTTP_KEEP_ALIVE", "false"),
+        ("HTTP_KEEP_ALIVE_TIMEOUT_SECS", "75"),
+        ("HTTP2_KEEP_ALIVE_INTERVAL_SECS", "30"),
+        ("HTTP2_KEEP_ALIVE_TIMEOUT_SECS", "10"),
+        ("HTTP2_MAX_CONCURRENT_STREAMS", "1000"),
+        ("TCP_NODELAY", "true"),
+    ])
+    .unwrap();
+
+    assert_eq!(
+        config.tuning,
+        ConnectionTuning {
+            http2: true,
+            keep_alive: false,
+            keep_alive_timeout: Some(Duration::from_secs(75)),
+            http2_keep_alive_interval: Some(Duration::from_secs(30)),
+            http2_keep_alive_timeout: Duration::from_secs(10),
+            http2_max_concurrent_streams: 1000,
+            tcp_nodelay: true,
+        }
+    );
+}
+
+#[test]
+fn test_invalid_tuning_values_are_errors() {
+    assert_eq!(
+        config(&[("HTTP2_MAX_CONCURRENT_STREAMS", "0")]),
+        Err(ServerConfigError::InvalidTuning {
+            name: "HTTP2_MAX_CONCURRENT_STREAMS",
+            value: "0".into(),
+        })
+    );
+    assert_eq!(
+        config(&[("HTTP_KEEP_ALIVE_TIMEOUT_SECS", "soon")]),
+        Err(ServerConfigError::InvalidTuning {
+            name: "HTTP_KEEP_ALIVE_TIMEOUT_SECS",
+            value: "soon".into(),
+        })
+    );
+}
+
+/// Sends the HTTP/2 client preface and an empty SETTINGS frame, returning
+/// the first bytes the server answers with.
+async fn h2_preface_reply(tuning: ConnectionTuning) -> Vec<u8> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (stop, stopped) = oneshot::channel::<()>();
+    let app = Router::new().route("/", get(|| async { "ok" }));
+    let server = tokio::spawn(serve(
+        listener,
+        app,
+        tuning.connection_builder(),
+        Some,
+        async {
+            let _ = stopped.await;
+        },
+    ));
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0\0\x04\0\0\0\0\0")
+        .await
+        .unwrap();
+    let mut reply = vec![0; 16];
+    let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut reply))
+        .await
+        .unwrap()
+        .unwrap();
+    reply.truncate(read);
+
+    drop(stream);
+    stop.send(()).unwrap();
+    server.await.unwrap();
+    reply
+}
+
+#[tokio::test]
+async fn test_http2_prior_knowledge_needs_http2_enabled() {
+    let enabled = h2_preface_reply(ConnectionTuning {
+        http2: true,
+        ..ConnectionTuning::default()
+    })
+    .await;
+    let disabled = h2_preface_reply(ConnectionTuning::default()).await;
+
+    // A SETTINGS frame has type 0x4 in its fourth byte; an HTTP/1.1-only
+    // connection closes on the preface without answering.
+    assert_eq!(enabled.get(3), Some(&0x4));
+    assert!(disabled.is_empty(), "{:?}", disabled);
+}
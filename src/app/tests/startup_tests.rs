@@ -0,0 +1,56 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::get,
+};
+use tower::ServiceExt;
+
+use crate::app::StartupGate;
+
+async fn status(gate: &StartupGate, path: &str) -> StatusCode {
+    gate.router()
+        .oneshot(Request::builder().uri(path).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_alive_but_not_ready_while_connecting() {
+    let gate = StartupGate::new();
+
+    assert_eq!(status(&gate, "/livez").await, StatusCode::OK);
+    assert_eq!(
+        status(&gate, "/readyz").await,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(
+        status(&gate, "/startupz").await,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(
+        status(&gate, "/auth/login/begin").await,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+}
+
+#[tokio::test]
+async fn test_open_forwards_to_the_application() {
+    let gate = StartupGate::new();
+    let router = gate.router();
+
+    gate.open(Router::new().route("/readyz", get(|| async { "ok" })));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/readyz")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(status(&gate, "/missing").await, StatusCode::NOT_FOUND);
+}
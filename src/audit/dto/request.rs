@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::model::{AuditEvent, AuditFilter, AuditOutcome},
+    impl_validated_query_request,
+    utils::Validatable,
+};
+
+pub const DEFAULT_AUDIT_LIMIT: u32 = 100;
+pub const MAX_AUDIT_LIMIT: u32 = 500;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    /// Only entries for this user
+    pub user_id: Option<Uuid>,
+    /// Event name, e.g. `login` or `admin_action`
+    #[param(example = "login")]
+    pub event: Option<String>,
+    /// `success` or `failure`
+    #[param(example = "failure")]
+    pub outcome: Option<String>,
+    /// Inclusive lower bound (RFC 3339)
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound (RFC 3339); pass the oldest `occurred_at` seen to page back
+    pub to: Option<DateTime<Utc>>,
+    /// Number of entries to return
+    #[param(example = 100, minimum = 1, maximum = 500)]
+    pub limit: Option<u32>,
+}
+
+impl AuditLogQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_AUDIT_LIMIT)
+    }
+
+    pub fn to_filter(&self) -> Result<AuditFilter, AppError> {
+        Ok(AuditFilter {
+            user_id: self.user_id,
+            event: self
+                .event
+                .as_deref()
+                .map(AuditEvent::try_from)
+                .transpose()?,
+            outcome: self
+                .outcome
+                .as_deref()
+                .map(AuditOutcome::try_from)
+                .transpose()?,
+            from: self.from,
+            to: self.to,
+            limit: i64::from(self.limit()),
+        })
+    }
+}
+
+impl Validatable for AuditLogQuery {
+    fn validate(&self) -> Result<(), AppError> {
+        if !(1..=MAX_AUDIT_LIMIT).contains(&self.limit()) {
+            return Err(AppError::BadRequest(format!(
+                "limit must be between 1 and {}",
+                MAX_AUDIT_LIMIT
+            )));
+        }
+
+        if let (Some(from), Some(to)) = (self.from, self.to)
+            && from >= to
+        {
+            return Err(AppError::BadRequest(String::from(
+                "from must be earlier than to",
+            )));
+        }
+
+        self.to_filter().map(|_| ())
+    }
+}
+
+impl_validated_query_request!(AuditLogQuery);
@@ -0,0 +1,53 @@
+use axum::{Json, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::audit::model::AuditRecord;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditLogEntry>,
+}
+
+impl IntoResponse for AuditLogResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    #[schema(example = "login")]
+    pub event: String,
+    #[schema(example = "failure")]
+    pub outcome: String,
+    pub user_id: Option<Uuid>,
+    #[schema(example = "john_doe")]
+    pub username: Option<String>,
+    #[schema(example = "203.0.113.7")]
+    pub ip: Option<String>,
+    #[schema(example = "Mozilla/5.0")]
+    pub user_agent: Option<String>,
+    #[schema(value_type = Object, example = json!({"error": "Authentication failed"}))]
+    pub details: serde_json::Value,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub occurred_at: String,
+}
+
+impl From<AuditRecord> for AuditLogEntry {
+    fn from(record: AuditRecord) -> Self {
+        Self {
+            id: record.id,
+            event: record.event,
+            outcome: record.outcome,
+            user_id: record.user_id,
+            username: record.username,
+            ip: record.ip.map(|ip| ip.to_string()),
+            user_agent: record.user_agent,
+            details: record.details,
+            occurred_at: record.occurred_at.to_rfc3339(),
+        }
+    }
+}
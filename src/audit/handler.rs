@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    audit::dto::{AuditLogQuery, AuditLogResponse},
+    auth::permissions::AuditRead,
+};
+
+/// Query the audit trail
+///
+/// Returns security-relevant events, newest first, optionally filtered by
+/// user, event, outcome and time range. Requires `audit:read`.
+#[utoipa::path(
+    get,
+    path = "/admin/audit",
+    tag = "Admin",
+    params(AuditLogQuery),
+    responses(
+        (status = 200, description = "Audit entries", body = AuditLogResponse),
+        (status = 400, description = "Invalid query parameters", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn search(
+    _admin: RequirePermission<AuditRead>,
+    State(state): State<Arc<AppState>>,
+    query: AuditLogQuery,
+) -> Result<AuditLogResponse, AppError> {
+    state.audit_service.search(query).await
+}
@@ -0,0 +1,243 @@
+use std::{fmt, net::IpAddr};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    utils::FromRow,
+};
+
+/// Longest user agent kept; anything beyond is client-controlled noise.
+pub const MAX_USER_AGENT_LEN: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEvent {
+    Registration,
+    Login,
+    Refresh,
+    Logout,
+    RecoveryAttempt,
+    Recovery,
+    CredentialDeleted,
+    CredentialUnlocked,
+    AdminAction,
+    SessionUpdated,
+    AccountDeleted,
+    EmailVerification,
+    ClientCredentials,
+}
+
+impl AuditEvent {
+    pub const ALL: [AuditEvent; 13] = [
+        AuditEvent::Registration,
+        AuditEvent::Login,
+        AuditEvent::Refresh,
+        AuditEvent::Logout,
+        AuditEvent::RecoveryAttempt,
+        AuditEvent::Recovery,
+        AuditEvent::CredentialDeleted,
+        AuditEvent::CredentialUnlocked,
+        AuditEvent::AdminAction,
+        AuditEvent::SessionUpdated,
+        AuditEvent::AccountDeleted,
+        AuditEvent::EmailVerification,
+        AuditEvent::ClientCredentials,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditEvent::Registration => "registration",
+            AuditEvent::Login => "login",
+            AuditEvent::Refresh => "refresh",
+            AuditEvent::Logout => "logout",
+            AuditEvent::RecoveryAttempt => "recovery_attempt",
+            AuditEvent::Recovery => "recovery",
+            AuditEvent::CredentialDeleted => "credential_deleted",
+            AuditEvent::CredentialUnlocked => "credential_unlocked",
+            AuditEvent::AdminAction => "admin_action",
+            AuditEvent::SessionUpdated => "session_updated",
+            AuditEvent::AccountDeleted => "account_deleted",
+            AuditEvent::EmailVerification => "email_verification",
+            AuditEvent::ClientCredentials => "client_credentials",
+        }
+    }
+}
+
+impl fmt::Display for AuditEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<&str> for AuditEvent {
+    type Error = AppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|event| event.as_str() == value)
+            .ok_or_else(|| AppError::BadRequest(format!("Unknown audit event: {}", value)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Failure => "failure",
+        }
+    }
+}
+
+impl TryFrom<&str> for AuditOutcome {
+    type Error = AppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "success" => Ok(AuditOutcome::Success),
+            "failure" => Ok(AuditOutcome::Failure),
+            other => Err(AppError::BadRequest(format!(
+                "Unknown audit outcome: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Who is calling, as far as the transport can tell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditContext {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    /// Not part of the audit row; login history compares them per account.
+    pub country: Option<String>,
+    pub asn: Option<u32>,
+    /// Not part of the audit row either; token issuances keep it.
+    pub client_app: Option<String>,
+}
+
+impl AuditContext {
+    pub fn new(ip: Option<IpAddr>, user_agent: Option<&str>) -> Self {
+        Self {
+            ip,
+            user_agent: user_agent.map(|agent| agent.chars().take(MAX_USER_AGENT_LEN).collect()),
+            country: None,
+            asn: None,
+            client_app: None,
+        }
+    }
+
+    pub fn with_network(mut self, country: Option<String>, asn: Option<u32>) -> Self {
+        self.country = country;
+        self.asn = asn;
+        self
+    }
+
+    pub fn with_client_app(mut self, client_app: Option<String>) -> Self {
+        self.client_app = client_app;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub event: AuditEvent,
+    pub outcome: AuditOutcome,
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub context: AuditContext,
+    pub details: serde_json::Value,
+    /// The tenant of the request. The entry belongs to it, and `username`
+    /// is resolved to an account within it.
+    pub tenant: String,
+}
+
+impl AuditEntry {
+    /// Failures keep the error message and code, so the trail explains what
+    /// went wrong.
+    pub fn new(
+        event: AuditEvent,
+        context: &AuditContext,
+        username: Option<&str>,
+        result: Result<(), &AppError>,
+    ) -> Self {
+        let (outcome, details) = match result {
+            Ok(()) => (AuditOutcome::Success, serde_json::json!({})),
+            Err(e) => (
+                AuditOutcome::Failure,
+                serde_json::json!({ "error": e.to_string(), "code": e.code() }),
+            ),
+        };
+
+        Self {
+            event,
+            outcome,
+            user_id: None,
+            username: username.map(str::to_owned),
+            context: context.clone(),
+            details,
+            tenant: current_tenant(),
+        }
+    }
+
+    pub fn with_user_id(mut self, user_id: Uuid) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    /// Merges extra fields into the details object.
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        if let (Some(target), serde_json::Value::Object(extra)) =
+            (self.details.as_object_mut(), details)
+        {
+            target.extend(extra);
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub id: Uuid,
+    pub event: String,
+    pub outcome: String,
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub details: serde_json::Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl FromRow for AuditRecord {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(AuditRecord {
+            id: row.try_get("id")?,
+            event: row.try_get("event")?,
+            outcome: row.try_get("outcome")?,
+            user_id: row.try_get("user_id")?,
+            username: row.try_get("username")?,
+            ip: row.try_get("ip")?,
+            user_agent: row.try_get("user_agent")?,
+            details: row.try_get("details")?,
+            occurred_at: row.try_get("occurred_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub user_id: Option<Uuid>,
+    pub event: Option<AuditEvent>,
+    pub outcome: Option<AuditOutcome>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: i64,
+}
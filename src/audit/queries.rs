@@ -0,0 +1,22 @@
+/// Every query takes the tenant of the request as its last parameter.
+pub mod audit_log {
+    /// The user id is resolved from the username, within the entry's tenant,
+    /// when the caller only knows the latter, e.g. for a failed login.
+    pub const INSERT: &str = "INSERT INTO audit_log
+             (event, outcome, user_id, username, ip, user_agent, details, tenant_id)
+         VALUES ($1, $2,
+                 COALESCE($3, (SELECT id FROM users WHERE username = $4 AND tenant_id = $8)),
+                 $4, $5, $6, $7, $8)";
+
+    pub const SEARCH: &str = "SELECT id, event, outcome, user_id, username, ip, user_agent,
+                details, occurred_at
+         FROM audit_log
+         WHERE ($1::uuid IS NULL OR user_id = $1)
+           AND ($2::text IS NULL OR event = $2)
+           AND ($3::text IS NULL OR outcome = $3)
+           AND ($4::timestamptz IS NULL OR occurred_at >= $4)
+           AND ($5::timestamptz IS NULL OR occurred_at < $5)
+           AND tenant_id = $7
+         ORDER BY occurred_at DESC
+         LIMIT $6";
+}
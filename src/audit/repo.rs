@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    audit::{
+        model::{AuditEntry, AuditFilter, AuditRecord},
+        queries,
+        traits::AuditRepository,
+    },
+    config::CircuitBreaker,
+    db_insert, db_select,
+    utils::{BaseRepository, FromRow, QueryKind, ReadReplica},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+
+    /// Runs searches on `replica` when there is one.
+    pub fn with_replica(mut self, replica: Option<ReadReplica>) -> Self {
+        self.base = self.base.with_replica(replica);
+        self
+    }
+}
+
+impl AuditRepository for Repository {
+    async fn insert(&self, entry: &AuditEntry) -> Result<(), AppError> {
+        db_insert!("audit_log", {
+            self.base
+                .execute_prepared_raw(
+                    queries::audit_log::INSERT,
+                    &[
+                        &entry.event.as_str() as &(dyn ToSql + Sync),
+                        &entry.outcome.as_str(),
+                        &entry.user_id,
+                        &entry.username,
+                        &entry.context.ip,
+                        &entry.context.user_agent,
+                        &entry.details,
+                        &entry.tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        Ok(())
+    }
+
+    async fn search(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("audit_log", {
+            self.base
+                .execute_prepared_on(
+                    QueryKind::Read,
+                    queries::audit_log::SEARCH,
+                    &[
+                        &filter.user_id as &(dyn ToSql + Sync),
+                        &filter.event.map(|event| event.as_str()),
+                        &filter.outcome.map(|outcome| outcome.as_str()),
+                        &filter.from,
+                        &filter.to,
+                        &filter.limit,
+                        &tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        rows.iter().map(AuditRecord::from_row).collect()
+    }
+}
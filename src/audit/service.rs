@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use tracing::Instrument;
+
+use crate::{
+    app::AppError,
+    audit::{
+        dto::{AuditLogQuery, AuditLogResponse},
+        model::{AuditEntry, AuditOutcome},
+        traits::{AuditLogger, AuditRepository},
+    },
+};
+
+pub struct AuditService<R>
+where
+    R: AuditRepository + 'static,
+{
+    audit_repo: Arc<R>,
+}
+
+impl<R> AuditService<R>
+where
+    R: AuditRepository + 'static,
+{
+    pub fn new(audit_repo: Arc<R>) -> Self {
+        Self { audit_repo }
+    }
+
+    pub async fn search(&self, query: AuditLogQuery) -> Result<AuditLogResponse, AppError> {
+        let records = self.audit_repo.search(&query.to_filter()?).await?;
+
+        Ok(AuditLogResponse {
+            entries: records.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+impl<R> AuditLogger for AuditService<R>
+where
+    R: AuditRepository + 'static,
+{
+    /// Also emitted on the `audit` tracing target, so the trail survives a
+    /// database outage in the logs.
+    fn record(&self, entry: AuditEntry) {
+        let event = entry.event.as_str();
+        let outcome = entry.outcome.as_str();
+        let username = entry.username.as_deref().unwrap_or("-");
+        let ip = entry.context.ip.map(|ip| ip.to_string());
+
+        match entry.outcome {
+            AuditOutcome::Success => {
+                tracing::info!(target: "audit", event, outcome, username, ip, details = %entry.details)
+            }
+            AuditOutcome::Failure => {
+                tracing::warn!(target: "audit", event, outcome, username, ip, details = %entry.details)
+            }
+        }
+
+        let audit_repo = Arc::clone(&self.audit_repo);
+        tokio::spawn(
+            async move {
+                if let Err(e) = audit_repo.insert(&entry).await {
+                    tracing::error!(
+                        event = entry.event.as_str(),
+                        "Failed to persist audit entry: {}",
+                        e
+                    );
+                }
+            }
+            .in_current_span(),
+        );
+    }
+}
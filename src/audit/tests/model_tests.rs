@@ -0,0 +1,95 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use crate::{
+    app::AppError,
+    audit::model::{AuditContext, AuditEntry, AuditEvent, AuditOutcome, MAX_USER_AGENT_LEN},
+};
+
+#[test]
+fn test_event_round_trip() {
+    for event in AuditEvent::ALL {
+        assert_eq!(AuditEvent::try_from(event.as_str()).unwrap(), event);
+    }
+}
+
+#[test]
+fn test_unknown_event_is_bad_request() {
+    assert!(matches!(
+        AuditEvent::try_from("sudo"),
+        Err(AppError::BadRequest(_))
+    ));
+}
+
+#[test]
+fn test_outcome_round_trip() {
+    for outcome in [AuditOutcome::Success, AuditOutcome::Failure] {
+        assert_eq!(AuditOutcome::try_from(outcome.as_str()).unwrap(), outcome);
+    }
+}
+
+#[test]
+fn test_context_truncates_user_agent() {
+    let agent = "a".repeat(MAX_USER_AGENT_LEN + 100);
+    let ctx = AuditContext::new(Some(IpAddr::V4(Ipv4Addr::LOCALHOST)), Some(&agent));
+
+    assert_eq!(ctx.user_agent.unwrap().len(), MAX_USER_AGENT_LEN);
+}
+
+#[test]
+fn test_success_entry_has_empty_details() {
+    let entry = AuditEntry::new(
+        AuditEvent::Login,
+        &AuditContext::default(),
+        Some("alice"),
+        Ok(()),
+    );
+
+    assert_eq!(entry.outcome, AuditOutcome::Success);
+    assert_eq!(entry.username.as_deref(), Some("alice"));
+    assert_eq!(entry.details, serde_json::json!({}));
+}
+
+#[test]
+fn test_failure_entry_keeps_error() {
+    let error = AppError::Unauthorized(String::from("Invalid passkey"));
+    let entry = AuditEntry::new(
+        AuditEvent::Login,
+        &AuditContext::default(),
+        None,
+        Err(&error),
+    );
+
+    assert_eq!(entry.outcome, AuditOutcome::Failure);
+    assert_eq!(entry.details["error"], error.to_string());
+    assert_eq!(entry.details["code"], "UNAUTHORIZED");
+}
+
+#[test]
+fn test_failure_entry_keeps_error_code() {
+    let error = AppError::AuthenticatorNotAllowed(String::from(
+        "Role ops requires an attested authenticator",
+    ));
+    let entry = AuditEntry::new(
+        AuditEvent::Registration,
+        &AuditContext::default(),
+        Some("alice"),
+        Err(&error),
+    );
+
+    assert_eq!(entry.details["code"], "AUTHENTICATOR_NOT_ALLOWED");
+}
+
+#[test]
+fn test_with_details_merges_fields() {
+    let error = AppError::BadRequest(String::from("nope"));
+    let entry = AuditEntry::new(
+        AuditEvent::AdminAction,
+        &AuditContext::default(),
+        Some("root"),
+        Err(&error),
+    )
+    .with_details(serde_json::json!({ "action": "shutdown" }));
+
+    assert_eq!(entry.details["action"], "shutdown");
+    assert!(entry.details.get("error").is_some());
+}
@@ -0,0 +1,66 @@
+use chrono::{Duration, Utc};
+
+use crate::{
+    app::AppError,
+    audit::{
+        dto::AuditLogQuery,
+        model::{AuditEvent, AuditOutcome},
+    },
+    utils::Validatable,
+};
+
+#[test]
+fn test_default_query_is_valid() {
+    let query = AuditLogQuery::default();
+
+    assert!(query.validate().is_ok());
+    assert_eq!(query.to_filter().unwrap().limit, 100);
+}
+
+#[test]
+fn test_limit_out_of_range() {
+    for limit in [0, 501] {
+        let query = AuditLogQuery {
+            limit: Some(limit),
+            ..Default::default()
+        };
+        assert!(matches!(query.validate(), Err(AppError::BadRequest(_))));
+    }
+}
+
+#[test]
+fn test_inverted_range_rejected() {
+    let now = Utc::now();
+    let query = AuditLogQuery {
+        from: Some(now),
+        to: Some(now - Duration::hours(1)),
+        ..Default::default()
+    };
+
+    assert!(matches!(query.validate(), Err(AppError::BadRequest(_))));
+}
+
+#[test]
+fn test_unknown_event_rejected() {
+    let query = AuditLogQuery {
+        event: Some(String::from("sudo")),
+        ..Default::default()
+    };
+
+    assert!(matches!(query.validate(), Err(AppError::BadRequest(_))));
+}
+
+#[test]
+fn test_filter_parses_event_and_outcome() {
+    let query = AuditLogQuery {
+        event: Some(String::from("recovery_attempt")),
+        outcome: Some(String::from("failure")),
+        limit: Some(20),
+        ..Default::default()
+    };
+
+    let filter = query.to_filter().unwrap();
+    assert_eq!(filter.event, Some(AuditEvent::RecoveryAttempt));
+    assert_eq!(filter.outcome, Some(AuditOutcome::Failure));
+    assert_eq!(filter.limit, 20);
+}
@@ -0,0 +1,103 @@
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::{
+        dto::AuditLogQuery,
+        model::{AuditContext, AuditEntry, AuditEvent, AuditFilter, AuditRecord},
+        service::AuditService,
+        traits::{AuditLogger, AuditRepository},
+    },
+};
+
+#[derive(Default)]
+struct MockRepository {
+    records: Vec<AuditRecord>,
+    inserted: Mutex<Vec<AuditEntry>>,
+    searched_with: Mutex<Option<AuditFilter>>,
+}
+
+impl AuditRepository for MockRepository {
+    async fn insert(&self, entry: &AuditEntry) -> Result<(), AppError> {
+        self.inserted.lock().unwrap().push(entry.clone());
+        Ok(())
+    }
+
+    async fn search(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>, AppError> {
+        *self.searched_with.lock().unwrap() = Some(filter.clone());
+        Ok(self.records.clone())
+    }
+}
+
+fn record(event: &str) -> AuditRecord {
+    AuditRecord {
+        id: Uuid::new_v4(),
+        event: String::from(event),
+        outcome: String::from("success"),
+        user_id: Some(Uuid::new_v4()),
+        username: Some(String::from("alice")),
+        ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+        user_agent: Some(String::from("curl/8.0")),
+        details: serde_json::json!({}),
+        occurred_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn test_search_maps_records() {
+    let repo = Arc::new(MockRepository {
+        records: vec![record("login"), record("logout")],
+        ..Default::default()
+    });
+    let service = AuditService::new(Arc::clone(&repo));
+
+    let response = service
+        .search(AuditLogQuery {
+            event: Some(String::from("login")),
+            limit: Some(10),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(response.entries.len(), 2);
+    assert_eq!(response.entries[0].event, "login");
+    assert_eq!(response.entries[0].ip.as_deref(), Some("127.0.0.1"));
+
+    let filter = repo.searched_with.lock().unwrap().clone().unwrap();
+    assert_eq!(filter.event, Some(AuditEvent::Login));
+    assert_eq!(filter.limit, 10);
+}
+
+#[tokio::test]
+async fn test_record_persists_entry() {
+    let repo = Arc::new(MockRepository::default());
+    let service = AuditService::new(Arc::clone(&repo));
+    let ctx = AuditContext::new(Some(IpAddr::V4(Ipv4Addr::LOCALHOST)), Some("curl/8.0"));
+
+    service.record(AuditEntry::new(
+        AuditEvent::Logout,
+        &ctx,
+        Some("alice"),
+        Ok(()),
+    ));
+
+    // Persisting is spawned so the audited request never waits on it.
+    for _ in 0..50 {
+        if !repo.inserted.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let inserted = repo.inserted.lock().unwrap();
+    assert_eq!(inserted.len(), 1);
+    assert_eq!(inserted[0].context, ctx);
+}
@@ -0,0 +1,20 @@
+use std::future::Future;
+
+use crate::{
+    app::AppError,
+    audit::model::{AuditEntry, AuditFilter, AuditRecord},
+};
+
+pub trait AuditRepository: Send + Sync {
+    fn insert(&self, entry: &AuditEntry) -> impl Future<Output = Result<(), AppError>> + Send;
+    fn search(
+        &self,
+        filter: &AuditFilter,
+    ) -> impl Future<Output = Result<Vec<AuditRecord>, AppError>> + Send;
+}
+
+/// Entry point used by other features. Recording never blocks or fails the
+/// request being audited.
+pub trait AuditLogger: Send + Sync {
+    fn record(&self, entry: AuditEntry);
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_cbor_2::Value;
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    AttestationMetadata, AttestedPasskey, AttestedPasskeyRegistration, PasskeyRegistration,
+    RegisterPublicKeyCredential,
+};
+
+use crate::app::AppError;
+
+/// Ceremony state of a credential enrollment. Untagged, so a plain passkey
+/// registration is stored exactly as before; `Attested` is tried first since
+/// only it carries a CA list.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EnrollmentState {
+    Attested(AttestedPasskeyRegistration),
+    Passkey(PasskeyRegistration),
+}
+
+/// What an enrollment keeps between its two steps: the ceremony state and,
+/// for a registration admitted by an invitation, the invitation to redeem
+/// once the passkey is registered. The state is flattened, so enrollments
+/// without an invitation are stored exactly as before.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoredEnrollment {
+    #[serde(flatten)]
+    pub state: EnrollmentState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invitation: Option<Uuid>,
+}
+
+/// The AAGUID allowlists of a user's restricted roles, from `role_aaguids`.
+/// Roles without an allowlist accept any passkey and are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AaguidPolicy {
+    allowlists: BTreeMap<String, Vec<Uuid>>,
+}
+
+impl FromIterator<(String, Uuid)> for AaguidPolicy {
+    fn from_iter<I: IntoIterator<Item = (String, Uuid)>>(pairs: I) -> Self {
+        let mut allowlists: BTreeMap<String, Vec<Uuid>> = BTreeMap::new();
+        for (role, aaguid) in pairs {
+            allowlists.entry(role).or_default().push(aaguid);
+        }
+        Self { allowlists }
+    }
+}
+
+impl AaguidPolicy {
+    pub fn is_restricted(&self) -> bool {
+        !self.allowlists.is_empty()
+    }
+
+    /// Every restricted role must accept the authenticator, so holding more
+    /// roles never loosens the policy. `None` is a credential registered
+    /// without attestation, which no allowlist accepts.
+    pub fn check(&self, aaguid: Option<Uuid>) -> Result<(), AppError> {
+        for (role, allowed) in &self.allowlists {
+            match aaguid {
+                Some(aaguid) if allowed.contains(&aaguid) => {}
+                Some(aaguid) => {
+                    return Err(AppError::AuthenticatorNotAllowed(format!(
+                        "Authenticator {} is not allowed for role {}",
+                        aaguid, role
+                    )));
+                }
+                None => {
+                    return Err(AppError::AuthenticatorNotAllowed(format!(
+                        "Role {} requires an attested authenticator",
+                        role
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The authenticator model vouched for by the attestation, when its format
+/// reports one.
+pub fn attested_aaguid(passkey: &AttestedPasskey) -> Option<Uuid> {
+    match passkey.attestation().metadata {
+        AttestationMetadata::Packed { aaguid } | AttestationMetadata::Tpm { aaguid, .. } => {
+            Some(aaguid)
+        }
+        _ => None,
+    }
+}
+
+/// Offset of the AAGUID in authenticator data, after the RP ID hash, the
+/// flags and the signature counter.
+const AUTH_DATA_AAGUID_OFFSET: usize = 32 + 1 + 4;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+/// The authenticator model the client reported in a registration, whether
+/// or not it was attested. Unlike `attested_aaguid` nothing vouches for it,
+/// so it only labels the credential and never satisfies an allowlist. The
+/// zero AAGUID that privacy-preserving authenticators send is `None`.
+pub fn reported_aaguid(credential: &RegisterPublicKeyCredential) -> Option<Uuid> {
+    let object: Value =
+        serde_cbor_2::from_slice(credential.response.attestation_object.as_ref()).ok()?;
+    let Value::Map(entries) = object else {
+        return None;
+    };
+    let Some(Value::Bytes(auth_data)) = entries.get(&Value::Text(String::from("authData"))) else {
+        return None;
+    };
+    if auth_data.get(32)? & FLAG_ATTESTED_CREDENTIAL == 0 {
+        return None;
+    }
+
+    let bytes = auth_data.get(AUTH_DATA_AAGUID_OFFSET..AUTH_DATA_AAGUID_OFFSET + 16)?;
+    let aaguid = Uuid::from_slice(bytes).ok()?;
+    (!aaguid.is_nil()).then_some(aaguid)
+}
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, ErrorCode, middleware::metrics::track_ceremony_duration},
+    auth::{queries, traits::ChallengeNonces},
+    config::{CircuitBreaker, webauthn::StatelessChallengeConfig},
+    redis_set,
+    utils::{BaseRedisRepository, crypto::Cipher},
+};
+
+/// Ceremony state handed to the client in stateless mode. `nonce` makes each
+/// one single use; `purpose` keeps a login blob out of the registration flow.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SealedCeremony<S> {
+    pub user_id: Uuid,
+    pub purpose: String,
+    pub nonce: Uuid,
+    pub exp: i64,
+    pub state: S,
+}
+
+/// Encrypts and authenticates ceremony state, so a client can carry it
+/// between begin and finish without reading or changing it.
+pub struct CeremonySealer {
+    cipher: Cipher,
+    ttl_secs: i64,
+}
+
+impl CeremonySealer {
+    pub fn new(config: &StatelessChallengeConfig) -> Self {
+        Self {
+            cipher: Cipher::new(&config.key).unwrap(),
+            ttl_secs: config.ttl.as_secs() as i64,
+        }
+    }
+
+    pub fn seal<S: Serialize>(
+        &self,
+        user_id: Uuid,
+        purpose: &str,
+        state: &S,
+        now: i64,
+    ) -> Result<String, AppError> {
+        let plaintext = serde_json::to_vec(&SealedCeremony {
+            user_id,
+            purpose: purpose.to_owned(),
+            nonce: Uuid::new_v4(),
+            exp: now + self.ttl_secs,
+            state,
+        })?;
+
+        let sealed = self
+            .cipher
+            .seal(&plaintext, purpose.as_bytes())
+            .ok_or_else(|| AppError::InternalServer(String::from("Failed to seal session")))?;
+        Ok(BASE64_URL_SAFE_NO_PAD.encode(sealed))
+    }
+
+    /// Any blob that was not sealed by this key for `purpose` looks like an
+    /// unknown session, the same as a missing row in stateful mode.
+    pub fn open<S: DeserializeOwned>(
+        &self,
+        token: &str,
+        purpose: &str,
+        now: i64,
+    ) -> Result<SealedCeremony<S>, AppError> {
+        let not_found = || AppError::NotFound(String::from("User or session not found"));
+
+        let sealed = BASE64_URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| not_found())?;
+        let plaintext = self
+            .cipher
+            .open(&sealed, purpose.as_bytes())
+            .ok_or_else(not_found)?;
+
+        let ceremony: SealedCeremony<S> = serde_json::from_slice(&plaintext)?;
+        if ceremony.purpose != purpose {
+            return Err(not_found());
+        }
+        if ceremony.exp <= now {
+            return Err(AppError::Unauthorized(String::from("Session expired"))
+                .with_code(ErrorCode::AuthSessionExpired));
+        }
+        Ok(ceremony)
+    }
+
+    /// When the ceremony was begun, as the blob only records its expiry.
+    pub fn started_at<S>(&self, ceremony: &SealedCeremony<S>) -> i64 {
+        ceremony.exp - self.ttl_secs
+    }
+}
+
+/// Times a ceremony from its begin step to the end of its finish step.
+/// Dropped without `succeed`, the finish step counts as failed, so every
+/// early return is observed too.
+pub struct CeremonyClock {
+    ceremony: String,
+    started_at: DateTime<Utc>,
+    observed: bool,
+}
+
+impl CeremonyClock {
+    pub fn new(ceremony: &str, started_at: DateTime<Utc>) -> Self {
+        Self {
+            ceremony: ceremony.to_owned(),
+            started_at,
+            observed: false,
+        }
+    }
+
+    pub fn succeed(mut self) {
+        self.observe(true);
+    }
+
+    fn observe(&mut self, success: bool) {
+        if std::mem::replace(&mut self.observed, true) {
+            return;
+        }
+        let elapsed = (Utc::now() - self.started_at).num_milliseconds().max(0);
+        track_ceremony_duration(&self.ceremony, success, elapsed as f64 / 1000.0);
+    }
+}
+
+impl Drop for CeremonyClock {
+    fn drop(&mut self) {
+        self.observe(false);
+    }
+}
+
+pub struct RedisNonces {
+    base: BaseRedisRepository,
+}
+
+impl RedisNonces {
+    pub fn new(conn_manager: ConnectionManager, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRedisRepository::new(conn_manager, circuit_breaker),
+        }
+    }
+}
+
+impl ChallengeNonces for RedisNonces {
+    async fn consume(&self, nonce: Uuid, ttl_secs: u64) -> Result<bool, AppError> {
+        let key = queries::ceremony_nonces::key(&nonce);
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let acquired: Option<String> = redis_set!({
+                    redis::cmd("SET")
+                        .arg(&key)
+                        .arg(1)
+                        .arg("NX")
+                        .arg("EX")
+                        .arg(ttl_secs.max(1))
+                        .query_async(&mut conn)
+                        .await
+                })?;
+                Ok(acquired.is_some())
+            })
+            .await
+    }
+}
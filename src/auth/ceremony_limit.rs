@@ -0,0 +1,86 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::app::{AppError, middleware::metrics::track_ceremony_queue};
+
+/// Permits handed out while the limit is off. Large enough never to be
+/// waited on, small enough for `acquire_many` to take back when a limit is
+/// set again.
+const UNLIMITED_PERMITS: u32 = u32::MAX >> 1;
+
+/// Bounds how many WebAuthn finish steps verify signatures and attestations
+/// at once. The limit can change while the server runs; lowering it takes
+/// effect as the ceremonies already past the gate finish.
+pub struct CeremonyLimiter {
+    semaphore: Arc<Semaphore>,
+    /// 0 means unlimited.
+    limit: Mutex<u32>,
+}
+
+impl CeremonyLimiter {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(permits(limit) as usize)),
+            limit: Mutex::new(limit),
+        }
+    }
+
+    pub fn limit(&self) -> u32 {
+        *self.limit.lock().unwrap()
+    }
+
+    /// Finish steps that could start right now without queueing.
+    pub fn available(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+
+    /// Sets the new limit and returns the previous one.
+    pub fn set_limit(&self, limit: u32) -> u32 {
+        let mut current = self.limit.lock().unwrap();
+        let (old, new) = (permits(*current), permits(limit));
+
+        if new > old {
+            self.semaphore.add_permits((new - old) as usize);
+        } else if new < old {
+            let deficit = old - new;
+            let forgotten = self.semaphore.forget_permits(deficit as usize) as u32;
+            if forgotten < deficit {
+                // The rest are held by running ceremonies. Waiting for them
+                // here also queues new ceremonies behind the lower limit,
+                // since the semaphore is fair.
+                let semaphore = Arc::clone(&self.semaphore);
+                tokio::spawn(async move {
+                    if let Ok(permits) = semaphore.acquire_many_owned(deficit - forgotten).await {
+                        permits.forget();
+                    }
+                });
+            }
+        }
+
+        std::mem::replace(&mut *current, limit)
+    }
+
+    /// Waits for a slot and records how long that took.
+    pub async fn acquire(&self, ceremony: &str) -> Result<SemaphorePermit<'_>, AppError> {
+        let start = Instant::now();
+        let permit = self.semaphore.acquire().await.map_err(|_| {
+            AppError::ServiceUnavailable(String::from("Ceremony limiter is closed"))
+        })?;
+        track_ceremony_queue(ceremony, start.elapsed().as_secs_f64());
+        Ok(permit)
+    }
+}
+
+impl Default for CeremonyLimiter {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+fn permits(limit: u32) -> u32 {
+    if limit == 0 { UNLIMITED_PERMITS } else { limit }
+}
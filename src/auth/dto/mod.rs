@@ -1,10 +1,15 @@
 pub(crate) mod request;
 pub(crate) mod response;
 
-pub(crate) use request::{BeginRequest, FinishRequest};
+pub(crate) use request::{
+    BeginRequest, ExtensionInputs, FinishRequest, IntrospectionRequest, LargeBlobInput,
+    LoginOptionsQuery, RecoveryRequest, UpdateSessionRequest, VerifyEmailRequest,
+};
 pub(crate) use response::{
-    BeginResponse, HealthChecks, HealthResponse, HealthStatus, MessageResponse, ServiceHealth,
-    TokenResponse,
+    BeginResponse, CredentialEntry, CredentialInfo, CredentialListResponse, ExtensionOutputs,
+    HealthChecks, HealthResponse, HealthStatus, IntrospectionResponse, JwksResponse,
+    LivenessResponse, LoginOptionsResponse, LoginStep, MessageResponse, ProfileResponse,
+    RegistrationResponse, ServiceHealth, StartupResponse, TokenResponse,
 };
 
 #[cfg(test)]
@@ -1,35 +1,119 @@
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
 use serde::Deserialize;
-use utoipa::ToSchema;
+use serde_json::value::RawValue;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::{
     app::AppError,
-    impl_validated_json_request,
-    utils::{Validatable, validate_json_credentials, validate_text, validate_username},
+    impl_validated_form_request, impl_validated_json_request, impl_validated_query_request,
+    utils::{
+        Validatable, validate_device_name, validate_email, validate_json_credentials,
+        validate_text, validate_username,
+    },
 };
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct BeginRequest {
     #[schema(example = "john_doe", min_length = 3)]
     pub username: String,
+    /// Must be in `REGISTRATION_ROLES` or `PRIVILEGED_ROLES`; the latter
+    /// need an administrator's access token or `invitation`.
     #[schema(example = "admin")]
     pub role: Option<String>,
+    /// Code of an invitation, redeemed when the registration finishes.
+    /// Needed for a privileged `role`, and for any registration in
+    /// invite-only mode.
+    #[schema(example = "q8Kx1yH7V0m3Tf2b9WcR4nLs6dPzAe5jUgYoBi0kXhE")]
+    pub invitation: Option<String>,
+    /// Registration only: required when email verification is enabled.
+    #[schema(example = "john.doe@example.com", max_length = 254)]
+    pub email: Option<String>,
+    /// Inputs for the WebAuthn extensions enabled in `WEBAUTHN_EXTENSIONS`.
+    pub extensions: Option<ExtensionInputs>,
 }
 
 impl Validatable for BeginRequest {
     fn validate(&self) -> Result<(), AppError> {
         validate_username(&self.username)?;
+        if let Some(role) = &self.role {
+            validate_text(role, "Role")?;
+        }
+        if let Some(invitation) = &self.invitation {
+            validate_text(invitation, "Invitation")?;
+        }
+        if let Some(email) = &self.email {
+            validate_email(email)?;
+        }
+        if let Some(extensions) = &self.extensions {
+            extensions.validate()?;
+        }
         Ok(())
     }
 }
 
+const MAX_PRF_SALT_BYTES: usize = 64;
+// The smallest largeBlob store CTAP 2.1 lets an authenticator offer.
+const MAX_LARGE_BLOB_BYTES: usize = 1024;
+
+/// Forwarded to the authenticator as is; binary values are base64url.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ExtensionInputs {
+    /// Evaluated by PRF into a secret only this credential can reproduce.
+    #[schema(example = "c2FsdC1mb3ItdGhlLWVuY3J5cHRpb24ta2V5")]
+    pub prf_salt: Option<String>,
+    /// Login only.
+    pub large_blob: Option<LargeBlobInput>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LargeBlobInput {
+    Read,
+    Write(String),
+}
+
+impl Validatable for ExtensionInputs {
+    fn validate(&self) -> Result<(), AppError> {
+        if let Some(salt) = &self.prf_salt {
+            validate_base64url(salt, "PRF salt", MAX_PRF_SALT_BYTES)?;
+        }
+        if let Some(LargeBlobInput::Write(blob)) = &self.large_blob {
+            validate_base64url(blob, "Large blob", MAX_LARGE_BLOB_BYTES)?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_base64url(value: &str, field: &str, max_bytes: usize) -> Result<(), AppError> {
+    let bytes = BASE64_URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|_| AppError::BadRequest(format!("{} must be base64url", field)))?;
+
+    if bytes.is_empty() || bytes.len() > max_bytes {
+        return Err(AppError::BadRequest(format!(
+            "{} must be between 1 and {} bytes",
+            field, max_bytes
+        )));
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct FinishRequest {
     #[schema(example = "john_doe")]
     pub username: String,
     #[schema(example = "550e8400-e29b-41d4-a716-446655440000")]
     pub session_id: String,
-    #[schema(example = json!({"id": "AQIDBAUGBwgJCgsMDQ4PEA", "rawId": "AQIDBAUGBwgJCgsMDQ4PEA", "type": "public-key"}))]
-    pub credentials: serde_json::Value,
+    /// Kept as raw JSON and parsed once into the WebAuthn response type.
+    #[schema(value_type = Object, example = json!({"id": "AQIDBAUGBwgJCgsMDQ4PEA", "rawId": "AQIDBAUGBwgJCgsMDQ4PEA", "type": "public-key"}))]
+    pub credentials: Box<RawValue>,
+    /// Login only: label for the session, shown back to the user.
+    #[schema(example = "Work laptop", max_length = 64)]
+    pub device_name: Option<String>,
+    /// Login only: keeps this session signed in for the trusted lifetime.
+    #[serde(default)]
+    pub trusted: bool,
 }
 
 impl Validatable for FinishRequest {
@@ -37,9 +121,104 @@ impl Validatable for FinishRequest {
         validate_username(&self.username)?;
         validate_text(&self.session_id, "Session ID")?;
         validate_json_credentials(&self.credentials)?;
+        if let Some(name) = &self.device_name {
+            validate_device_name(name)?;
+        }
         Ok(())
     }
 }
 
+/// Changes to the current session. Trust can only be granted at login,
+/// right after the passkey check, so `trusted` may only be set to false.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSessionRequest {
+    #[schema(example = "Work laptop", max_length = 64)]
+    pub device_name: Option<String>,
+    #[schema(example = false)]
+    pub trusted: Option<bool>,
+}
+
+impl Validatable for UpdateSessionRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if let Some(name) = &self.device_name {
+            validate_device_name(name)?;
+        }
+        if self.trusted == Some(true) {
+            return Err(AppError::BadRequest(String::from(
+                "A session can only be trusted at login",
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RecoveryRequest {
+    #[schema(example = "john_doe")]
+    pub username: String,
+    #[schema(example = "7KQ2-M9XD-4TRB")]
+    pub recovery_code: String,
+}
+
+impl Validatable for RecoveryRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        validate_username(&self.username)?;
+        validate_text(&self.recovery_code, "Recovery code")?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyEmailRequest {
+    #[schema(example = "john_doe")]
+    pub username: String,
+    #[schema(example = "q3v5Zk2b9TgQyH1nL0cRwA")]
+    pub token: String,
+}
+
+impl Validatable for VerifyEmailRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        validate_username(&self.username)?;
+        validate_text(&self.token, "Token")?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LoginOptionsQuery {
+    /// The username the user typed so far
+    #[param(example = "john_doe")]
+    pub username: String,
+}
+
+impl Validatable for LoginOptionsQuery {
+    fn validate(&self) -> Result<(), AppError> {
+        validate_username(&self.username)
+    }
+}
+
+/// An RFC 7662 introspection request, form encoded.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct IntrospectionRequest {
+    #[schema(example = "eyJhbGciOiJFZERTQSIsInR5cCI6IkpXVCJ9...")]
+    pub token: String,
+    /// Accepted for compatibility; only access tokens are introspected.
+    #[cfg_attr(not(feature = "strict"), allow(dead_code))]
+    #[schema(example = "access_token")]
+    pub token_type_hint: Option<String>,
+}
+
+impl Validatable for IntrospectionRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        validate_text(&self.token, "Token")
+    }
+}
+
 impl_validated_json_request!(BeginRequest);
 impl_validated_json_request!(FinishRequest);
+impl_validated_json_request!(RecoveryRequest);
+impl_validated_json_request!(UpdateSessionRequest);
+impl_validated_json_request!(VerifyEmailRequest);
+impl_validated_form_request!(IntrospectionRequest);
+impl_validated_query_request!(LoginOptionsQuery);
@@ -1,11 +1,31 @@
-use axum::{response::IntoResponse, Json};
+use std::collections::BTreeMap;
+
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::jwk::Jwk;
 use serde::Serialize;
+use serde_json::value::RawValue;
 use utoipa::ToSchema;
+use uuid::Uuid;
+use webauthn_rs::prelude::Credential;
+
+use crate::auth::{
+    jwt::AccessTokenClaims,
+    model::{Grants, StoredCredential, User},
+};
 
+/// `options` is serialized once when the ceremony starts and copied into the
+/// body as is.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct BeginResponse {
-    #[schema(example = json!({"challenge": "Y2hhbGxlbmdl", "rp": {"name": "Example", "id": "example.com"}}))]
-    pub options: serde_json::Value,
+    #[schema(value_type = Object, example = json!({"challenge": "Y2hhbGxlbmdl", "rp": {"name": "Example", "id": "example.com"}}))]
+    pub options: Box<RawValue>,
+    /// Opaque: a session id, or the sealed ceremony state in stateless mode.
     #[schema(example = "550e8400-e29b-41d4-a716-446655440000")]
     pub session_id: String,
 }
@@ -28,6 +48,28 @@ impl IntoResponse for MessageResponse {
     }
 }
 
+/// Returned when a passkey is enrolled. Recovery codes are shown only once.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RegistrationResponse {
+    #[schema(example = "Registration completed successfully!")]
+    pub message: String,
+    #[schema(example = json!(["7KQ2-M9XD-4TRB", "P3HW-0ZNA-8CVE"]))]
+    pub recovery_codes: Vec<String>,
+    /// Set when the account stays pending until the email is verified.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    #[schema(example = false)]
+    pub verification_pending: bool,
+    pub credential: CredentialInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<ExtensionOutputs>,
+}
+
+impl IntoResponse for RegistrationResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TokenResponse {
     #[schema(example = "Login completed successfully")]
@@ -36,6 +78,12 @@ pub struct TokenResponse {
         example = "v4.public.eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ"
     )]
     pub access_token: String,
+    /// Login only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential: Option<CredentialInfo>,
+    /// Login only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<ExtensionOutputs>,
 }
 
 impl IntoResponse for TokenResponse {
@@ -44,6 +92,82 @@ impl IntoResponse for TokenResponse {
     }
 }
 
+/// What the login form should offer next for a username.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginStep {
+    /// Start a passkey login.
+    Login,
+    /// Every passkey is locked: offer account recovery.
+    Recover,
+    /// No account with a passkey: offer registration.
+    Register,
+}
+
+/// Whether to show the passkey button, autofill, recovery or registration.
+/// With `LOGIN_HINTS=uniform` every username gets the same answer.
+#[derive(Debug, PartialEq, Eq, Serialize, ToSchema)]
+pub struct LoginOptionsResponse {
+    /// A passkey login can start for this username.
+    pub passkey: bool,
+    /// Worth requesting conditional mediation: the account has a synced
+    /// passkey, which browsers offer in username autofill.
+    pub conditional_ui: bool,
+    pub next: LoginStep,
+}
+
+impl LoginOptionsResponse {
+    pub fn login(conditional_ui: bool) -> Self {
+        Self {
+            passkey: true,
+            conditional_ui,
+            next: LoginStep::Login,
+        }
+    }
+
+    pub fn without_passkey(next: LoginStep) -> Self {
+        Self {
+            passkey: false,
+            conditional_ui: false,
+            next,
+        }
+    }
+}
+
+impl IntoResponse for LoginOptionsResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+/// What the ceremony showed about the passkey, so the frontend can tailor
+/// what it tells the user next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct CredentialInfo {
+    /// `credProps.rk`: whether the passkey is discoverable and can sign in
+    /// without a username. An unsigned hint from the browser, absent when it
+    /// does not report one and at login.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = true)]
+    pub discoverable: Option<bool>,
+    /// The authenticator verified the user with a PIN or biometric, not
+    /// only their presence.
+    #[schema(example = true)]
+    pub user_verified: bool,
+}
+
+/// What the client reported for the extensions enabled on this server,
+/// copied from the credential's `clientExtensionResults`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExtensionOutputs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Object, example = json!({"enabled": true}))]
+    pub prf: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Object, example = json!({"written": true}))]
+    pub large_blob: Option<serde_json::Value>,
+}
+
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct HealthResponse {
     #[schema(example = "2024-01-01T12:00:00Z")]
@@ -61,6 +185,9 @@ impl IntoResponse for HealthResponse {
 pub struct HealthChecks {
     pub database: ServiceHealth,
     pub redis: ServiceHealth,
+    /// The database of each data residency region, by name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub regions: BTreeMap<String, ServiceHealth>,
 }
 
 #[derive(Debug, Serialize, utoipa::ToSchema)]
@@ -79,3 +206,228 @@ pub enum HealthStatus {
     Healthy,
     Unhealthy,
 }
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LivenessResponse {
+    #[schema(example = "healthy")]
+    pub status: HealthStatus,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub timestamp: String,
+}
+
+impl IntoResponse for LivenessResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+/// Migration status for the startup probe, answered with 503 until every
+/// migration is applied so the orchestrator keeps waiting.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StartupResponse {
+    #[schema(example = "healthy")]
+    pub status: HealthStatus,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub timestamp: String,
+    #[schema(example = json!(["V1__Create_User_Table", "V2__Create_Webauthn_Table"]))]
+    pub applied_migrations: Vec<String>,
+    #[schema(example = json!([]))]
+    pub pending_migrations: Vec<String>,
+}
+
+impl IntoResponse for StartupResponse {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self.status {
+            HealthStatus::Healthy => StatusCode::OK,
+            HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+/// Public keys for verifying access tokens, per RFC 7517.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JwksResponse {
+    #[schema(value_type = Vec<Object>, example = json!([{"kty": "OKP", "crv": "Ed25519", "x": "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo", "use": "sig", "alg": "EdDSA", "kid": "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k"}]))]
+    pub keys: Vec<Jwk>,
+}
+
+impl IntoResponse for JwksResponse {
+    fn into_response(self) -> axum::response::Response {
+        // Short enough that verifiers pick up a new key soon after a restart.
+        ([(header::CACHE_CONTROL, "public, max-age=300")], Json(self)).into_response()
+    }
+}
+
+/// An RFC 7662 introspection answer. An invalid, expired or revoked token
+/// only gets `active: false`, without saying which.
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub sub: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "alice")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = json!(["admin"]))]
+    pub roles: Option<Vec<String>>,
+    /// The token's permissions, space separated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "audit:read traffic:read")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 1735689600)]
+    pub iat: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 1735690500)]
+    pub exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "access_token")]
+    pub token_type: Option<String>,
+    /// The token's `azp`: the client application it was issued to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "web")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "https://auth.example.com")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "example-api")]
+    pub aud: Option<String>,
+}
+
+impl IntrospectionResponse {
+    pub fn new(claims: Option<AccessTokenClaims>) -> Self {
+        let Some(claims) = claims else {
+            return Self::default();
+        };
+
+        Self {
+            active: true,
+            sub: Some(claims.sub),
+            username: Some(claims.username),
+            scope: Some(claims.grants.permissions.join(" ")),
+            roles: Some(claims.grants.roles),
+            iat: Some(claims.iat),
+            exp: Some(claims.exp),
+            token_type: Some(String::from("access_token")),
+            client_id: claims.azp,
+            iss: claims.iss,
+            aud: claims.aud,
+        }
+    }
+}
+
+impl IntoResponse for IntrospectionResponse {
+    fn into_response(self) -> axum::response::Response {
+        ([(header::CACHE_CONTROL, "no-store")], Json(self)).into_response()
+    }
+}
+
+/// The authenticated user as currently stored, which may differ from the
+/// claims of a token issued before a role change.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProfileResponse {
+    #[schema(example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub id: Uuid,
+    #[schema(example = "alice")]
+    pub username: String,
+    #[schema(example = json!(["admin"]))]
+    pub roles: Vec<String>,
+    #[schema(example = json!(["audit:read", "traffic:read"]))]
+    pub permissions: Vec<String>,
+    #[schema(example = "active")]
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ProfileResponse {
+    pub fn new(user: User, grants: Grants) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            roles: grants.roles,
+            permissions: grants.permissions,
+            status: user.status,
+            created_at: user.created_at,
+        }
+    }
+}
+
+impl IntoResponse for ProfileResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+/// The passkeys of the authenticated user, oldest first.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CredentialListResponse {
+    pub credentials: Vec<CredentialEntry>,
+}
+
+impl CredentialListResponse {
+    pub fn new(credentials: Vec<StoredCredential>) -> Self {
+        Self {
+            credentials: credentials.into_iter().map(CredentialEntry::from).collect(),
+        }
+    }
+}
+
+impl IntoResponse for CredentialListResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+/// What the authenticator reported about a passkey. A backed up credential
+/// is synced by a provider such as iCloud Keychain; one that is not even
+/// eligible is bound to a device such as a security key.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CredentialEntry {
+    /// Credential id, unpadded base64url.
+    #[schema(example = "mJ3Zt2nqQ0K8c1Fh8v3q5w")]
+    pub id: String,
+    /// Authenticator model, absent when the authenticator did not tell.
+    #[schema(example = "fbfc3007-154e-4ecc-8c0b-6e020557d7bd")]
+    pub aaguid: Option<Uuid>,
+    pub backup_eligible: bool,
+    pub backup_state: bool,
+    #[schema(example = json!(["internal", "hybrid"]))]
+    pub transports: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Set while the passkey is locked because its signature counter went
+    /// backwards, a sign it may have been cloned. Confirm it to sign in with
+    /// it again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clone_suspected_at: Option<DateTime<Utc>>,
+}
+
+impl From<StoredCredential> for CredentialEntry {
+    fn from(stored: StoredCredential) -> Self {
+        let credential = Credential::from(stored.passkey);
+        let transports = credential
+            .transports
+            .iter()
+            .flatten()
+            .filter_map(|transport| match serde_json::to_value(transport) {
+                Ok(serde_json::Value::String(name)) if name != "unknown" => Some(name),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            id: BASE64_URL_SAFE_NO_PAD.encode(credential.cred_id.as_slice()),
+            aaguid: stored.aaguid,
+            backup_eligible: credential.backup_eligible,
+            backup_state: credential.backup_state,
+            transports,
+            created_at: stored.created_at,
+            last_used_at: stored.last_used_at,
+            clone_suspected_at: stored.clone_suspected_at,
+        }
+    }
+}
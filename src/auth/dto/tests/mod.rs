@@ -1,2 +1,4 @@
 #[cfg(test)]
 mod request_tests;
+#[cfg(test)]
+mod response_tests;
@@ -1,14 +1,25 @@
+use serde_json::value::RawValue;
+
 use crate::{
     app::AppError,
-    auth::dto::{BeginRequest, FinishRequest},
+    auth::dto::{
+        BeginRequest, ExtensionInputs, FinishRequest, LargeBlobInput, UpdateSessionRequest,
+    },
     utils::Validatable,
 };
 
+fn raw(value: serde_json::Value) -> Box<RawValue> {
+    serde_json::value::to_raw_value(&value).unwrap()
+}
+
 #[test]
 fn test_begin_request_valid() {
     let request = BeginRequest {
         username: "john_doe".to_string(),
         role: Some("admin".to_string()),
+        invitation: None,
+        email: None,
+        extensions: None,
     };
     let result = request.validate();
     assert!(result.is_ok());
@@ -19,6 +30,9 @@ fn test_begin_request_valid_without_role() {
     let request = BeginRequest {
         username: "john_doe".to_string(),
         role: None,
+        invitation: None,
+        email: None,
+        extensions: None,
     };
     let result = request.validate();
     assert!(result.is_ok());
@@ -29,16 +43,66 @@ fn test_begin_request_valid_minimum_username() {
     let request = BeginRequest {
         username: "abc".to_string(),
         role: None,
+        invitation: None,
+        email: None,
+        extensions: None,
     };
     let result = request.validate();
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_begin_request_rejects_invalid_email() {
+    let request = BeginRequest {
+        username: "john_doe".to_string(),
+        role: None,
+        invitation: None,
+        email: Some("john_doe".to_string()),
+        extensions: None,
+    };
+    match request.validate() {
+        Err(AppError::BadRequest(msg)) => {
+            assert_eq!(msg, "Invalid email address");
+        }
+        _ => panic!("Expected BadRequest error"),
+    }
+}
+
+#[test]
+fn test_begin_request_rejects_blank_role_and_invitation() {
+    let request = BeginRequest {
+        username: "john_doe".to_string(),
+        role: Some("  ".to_string()),
+        invitation: None,
+        email: None,
+        extensions: None,
+    };
+    match request.validate() {
+        Err(AppError::BadRequest(msg)) => assert_eq!(msg, "Role cannot be empty"),
+        _ => panic!("Expected BadRequest error"),
+    }
+
+    let request = BeginRequest {
+        username: "john_doe".to_string(),
+        role: Some("admin".to_string()),
+        invitation: Some(String::new()),
+        email: None,
+        extensions: None,
+    };
+    match request.validate() {
+        Err(AppError::BadRequest(msg)) => assert_eq!(msg, "Invitation cannot be empty"),
+        _ => panic!("Expected BadRequest error"),
+    }
+}
+
 #[test]
 fn test_begin_request_username_too_short() {
     let request = BeginRequest {
         username: "ab".to_string(),
         role: None,
+        invitation: None,
+        email: None,
+        extensions: None,
     };
     let result = request.validate();
     assert!(result.is_err());
@@ -55,6 +119,9 @@ fn test_begin_request_username_empty() {
     let request = BeginRequest {
         username: String::new(),
         role: None,
+        invitation: None,
+        email: None,
+        extensions: None,
     };
     let result = request.validate();
     assert!(result.is_err());
@@ -71,6 +138,9 @@ fn test_begin_request_username_only_whitespace() {
     let request = BeginRequest {
         username: "   ".to_string(),
         role: None,
+        invitation: None,
+        email: None,
+        extensions: None,
     };
     let result = request.validate();
     assert!(result.is_err());
@@ -82,18 +152,65 @@ fn test_begin_request_username_only_whitespace() {
     }
 }
 
+#[test]
+fn test_begin_request_extension_inputs() {
+    let parsed: BeginRequest = serde_json::from_value(serde_json::json!({
+        "username": "john_doe",
+        "extensions": { "prf_salt": "AQID", "large_blob": { "write": "AQID" } },
+    }))
+    .unwrap();
+    assert!(parsed.validate().is_ok());
+
+    let request = BeginRequest {
+        username: "john_doe".to_string(),
+        role: None,
+        invitation: None,
+        email: None,
+        extensions: Some(ExtensionInputs {
+            prf_salt: Some("not base64url!".to_string()),
+            large_blob: Some(LargeBlobInput::Read),
+        }),
+    };
+    match request.validate() {
+        Err(AppError::BadRequest(msg)) => assert_eq!(msg, "PRF salt must be base64url"),
+        _ => panic!("Expected BadRequest error"),
+    }
+}
+
+#[test]
+fn test_begin_request_large_blob_too_big() {
+    let request = BeginRequest {
+        username: "john_doe".to_string(),
+        role: None,
+        invitation: None,
+        email: None,
+        extensions: Some(ExtensionInputs {
+            prf_salt: None,
+            large_blob: Some(LargeBlobInput::Write("A".repeat(2000))),
+        }),
+    };
+    match request.validate() {
+        Err(AppError::BadRequest(msg)) => {
+            assert_eq!(msg, "Large blob must be between 1 and 1024 bytes")
+        }
+        _ => panic!("Expected BadRequest error"),
+    }
+}
+
 #[test]
 fn test_finish_request_valid() {
-    let credentials = serde_json::json!({
+    let credentials = raw(serde_json::json!({
         "id": "AQIDBAUGBwgJCgsMDQ4PEA",
         "rawId": "AQIDBAUGBwgJCgsMDQ4PEA",
         "type": "public-key"
-    });
+    }));
 
     let request = FinishRequest {
         username: "john_doe".to_string(),
         session_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
         credentials,
+        device_name: None,
+        trusted: false,
     };
 
     let result = request.validate();
@@ -102,15 +219,17 @@ fn test_finish_request_valid() {
 
 #[test]
 fn test_finish_request_username_empty() {
-    let credentials = serde_json::json!({
+    let credentials = raw(serde_json::json!({
         "id": "test_id",
         "type": "public-key"
-    });
+    }));
 
     let request = FinishRequest {
         username: String::new(),
         session_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
         credentials,
+        device_name: None,
+        trusted: false,
     };
 
     let result = request.validate();
@@ -119,15 +238,17 @@ fn test_finish_request_username_empty() {
 
 #[test]
 fn test_finish_request_username_too_short() {
-    let credentials = serde_json::json!({
+    let credentials = raw(serde_json::json!({
         "id": "test_id",
         "type": "public-key"
-    });
+    }));
 
     let request = FinishRequest {
         username: "ab".to_string(),
         session_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
         credentials,
+        device_name: None,
+        trusted: false,
     };
 
     let result = request.validate();
@@ -136,15 +257,17 @@ fn test_finish_request_username_too_short() {
 
 #[test]
 fn test_finish_request_session_id_empty() {
-    let credentials = serde_json::json!({
+    let credentials = raw(serde_json::json!({
         "id": "test_id",
         "type": "public-key"
-    });
+    }));
 
     let request = FinishRequest {
         username: "john_doe".to_string(),
         session_id: String::new(),
         credentials,
+        device_name: None,
+        trusted: false,
     };
 
     let result = request.validate();
@@ -159,15 +282,17 @@ fn test_finish_request_session_id_empty() {
 
 #[test]
 fn test_finish_request_session_id_whitespace() {
-    let credentials = serde_json::json!({
+    let credentials = raw(serde_json::json!({
         "id": "test_id",
         "type": "public-key"
-    });
+    }));
 
     let request = FinishRequest {
         username: "john_doe".to_string(),
         session_id: "   ".to_string(),
         credentials,
+        device_name: None,
+        trusted: false,
     };
 
     let result = request.validate();
@@ -179,7 +304,9 @@ fn test_finish_request_credentials_null() {
     let request = FinishRequest {
         username: "john_doe".to_string(),
         session_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
-        credentials: serde_json::json!(null),
+        credentials: raw(serde_json::json!(null)),
+        device_name: None,
+        trusted: false,
     };
 
     let result = request.validate();
@@ -197,7 +324,9 @@ fn test_finish_request_credentials_not_object() {
     let request = FinishRequest {
         username: "john_doe".to_string(),
         session_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
-        credentials: serde_json::json!("not_an_object"),
+        credentials: raw(serde_json::json!("not_an_object")),
+        device_name: None,
+        trusted: false,
     };
 
     let result = request.validate();
@@ -215,7 +344,9 @@ fn test_finish_request_credentials_empty_object() {
     let request = FinishRequest {
         username: "john_doe".to_string(),
         session_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
-        credentials: serde_json::json!({}),
+        credentials: raw(serde_json::json!({})),
+        device_name: None,
+        trusted: false,
     };
 
     let result = request.validate();
@@ -233,7 +364,9 @@ fn test_finish_request_credentials_array() {
     let request = FinishRequest {
         username: "john_doe".to_string(),
         session_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
-        credentials: serde_json::json!([1, 2, 3]),
+        credentials: raw(serde_json::json!([1, 2, 3])),
+        device_name: None,
+        trusted: false,
     };
 
     let result = request.validate();
@@ -245,9 +378,81 @@ fn test_finish_request_all_fields_invalid() {
     let request = FinishRequest {
         username: String::new(),
         session_id: String::new(),
-        credentials: serde_json::json!(null),
+        credentials: raw(serde_json::json!(null)),
+        device_name: None,
+        trusted: false,
+    };
+
+    let result = request.validate();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_finish_request_device_name_too_long() {
+    let request = FinishRequest {
+        username: "john_doe".to_string(),
+        session_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+        credentials: raw(serde_json::json!({"id": "test_id", "type": "public-key"})),
+        device_name: Some("x".repeat(65)),
+        trusted: true,
     };
 
     let result = request.validate();
     assert!(result.is_err());
 }
+
+#[test]
+fn test_finish_request_defaults_to_untrusted() {
+    let request: FinishRequest = serde_json::from_str(
+        r#"{
+            "username": "john_doe",
+            "session_id": "550e8400-e29b-41d4-a716-446655440000",
+            "credentials": {"id": "test_id", "type": "public-key"}
+        }"#,
+    )
+    .unwrap();
+
+    assert!(!request.trusted);
+    assert!(request.device_name.is_none());
+}
+
+#[test]
+fn test_update_session_request_rename() {
+    let request = UpdateSessionRequest {
+        device_name: Some("Work laptop".to_string()),
+        trusted: Some(false),
+    };
+
+    assert!(request.validate().is_ok());
+}
+
+#[test]
+fn test_update_session_request_cannot_grant_trust() {
+    let request = UpdateSessionRequest {
+        device_name: None,
+        trusted: Some(true),
+    };
+
+    match request.validate() {
+        Err(AppError::BadRequest(msg)) => {
+            assert_eq!(msg, "A session can only be trusted at login");
+        }
+        _ => panic!("Expected BadRequest error"),
+    }
+}
+
+#[test]
+fn test_finish_request_credentials_too_large() {
+    let request = FinishRequest {
+        username: "john_doe".to_string(),
+        session_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+        credentials: raw(serde_json::json!({ "id": "a".repeat(70 * 1024) })),
+        device_name: None,
+        trusted: false,
+    };
+
+    match request.validate() {
+        Err(AppError::BadRequest(msg)) => assert_eq!(msg, "Credentials payload too large"),
+        _ => panic!("Expected BadRequest error"),
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::auth::{
+    dto::{
+        CredentialInfo, IntrospectionResponse, LoginOptionsResponse, LoginStep, ProfileResponse,
+    },
+    jwt::AccessTokenClaims,
+    model::{Grants, User},
+};
+
+fn user() -> User {
+    User {
+        id: Uuid::new_v4(),
+        username: String::from("john_doe"),
+        status: String::from("active"),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        is_active: true,
+    }
+}
+
+#[test]
+fn test_profile_exposes_public_fields_only() {
+    let user = user();
+    let grants = Grants {
+        roles: vec![String::from("admin")],
+        permissions: vec![String::from("audit:read")],
+    };
+    let json = serde_json::to_value(ProfileResponse::new(user.clone(), grants)).unwrap();
+
+    assert_eq!(json["id"], user.id.to_string());
+    assert_eq!(json["username"], "john_doe");
+    assert_eq!(json["roles"], serde_json::json!(["admin"]));
+    assert_eq!(json["permissions"], serde_json::json!(["audit:read"]));
+    assert_eq!(json["status"], "active");
+    assert!(json.get("created_at").is_some());
+    assert!(json.get("updated_at").is_none());
+    assert!(json.get("is_active").is_none());
+}
+
+#[test]
+fn test_profile_lists_no_roles_as_empty() {
+    let json = serde_json::to_value(ProfileResponse::new(user(), Grants::default())).unwrap();
+
+    assert_eq!(json["roles"], serde_json::json!([]));
+    assert_eq!(json["permissions"], serde_json::json!([]));
+}
+
+#[test]
+fn test_credential_info_omits_unreported_discoverability() {
+    let reported = CredentialInfo {
+        discoverable: Some(true),
+        user_verified: true,
+    };
+    let unreported = CredentialInfo {
+        discoverable: None,
+        user_verified: true,
+    };
+
+    assert_eq!(
+        serde_json::to_value(reported).unwrap(),
+        serde_json::json!({ "discoverable": true, "user_verified": true })
+    );
+    assert_eq!(
+        serde_json::to_value(unreported).unwrap(),
+        serde_json::json!({ "user_verified": true })
+    );
+}
+
+#[test]
+fn test_introspection_of_inactive_token_only_says_so() {
+    let json = serde_json::to_value(IntrospectionResponse::new(None)).unwrap();
+
+    assert_eq!(json, serde_json::json!({ "active": false }));
+}
+
+#[test]
+fn test_introspection_of_active_token_carries_claims() {
+    let user_id = Uuid::new_v4();
+    let grants = Grants {
+        roles: vec![String::from("admin")],
+        permissions: vec![String::from("audit:read"), String::from("traffic:read")],
+    };
+    let claims = AccessTokenClaims::new(
+        user_id,
+        String::from("john_doe"),
+        grants,
+        Duration::from_secs(900),
+    );
+    let exp = claims.exp;
+
+    let json = serde_json::to_value(IntrospectionResponse::new(Some(claims))).unwrap();
+
+    assert_eq!(json["active"], true);
+    assert_eq!(json["sub"], user_id.to_string());
+    assert_eq!(json["username"], "john_doe");
+    assert_eq!(json["roles"], serde_json::json!(["admin"]));
+    assert_eq!(json["scope"], "audit:read traffic:read");
+    assert_eq!(json["exp"], exp);
+    assert_eq!(json["token_type"], "access_token");
+}
+
+#[test]
+fn test_login_options_name_the_next_step() {
+    assert_eq!(
+        serde_json::to_value(LoginOptionsResponse::login(true)).unwrap(),
+        serde_json::json!({ "passkey": true, "conditional_ui": true, "next": "login" })
+    );
+    assert_eq!(
+        serde_json::to_value(LoginOptionsResponse::without_passkey(LoginStep::Recover)).unwrap(),
+        serde_json::json!({ "passkey": false, "conditional_ui": false, "next": "recover" })
+    );
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json, value::RawValue};
+
+use crate::{
+    app::AppError,
+    auth::dto::{ExtensionInputs, ExtensionOutputs, LargeBlobInput},
+    config::webauthn::ExtensionsConfig,
+};
+
+/// Extension inputs by their WebAuthn name.
+pub type Extensions = Map<String, Value>;
+
+/// Registration asks for every enabled extension: PRF support, evaluated too
+/// when a salt is given, and a large blob store if the authenticator has one.
+pub fn registration_inputs(
+    config: ExtensionsConfig,
+    inputs: Option<&ExtensionInputs>,
+) -> Result<Extensions, AppError> {
+    let mut extensions = Extensions::new();
+    let salt = inputs.and_then(|inputs| inputs.prf_salt.as_deref());
+    if inputs.is_some_and(|inputs| inputs.large_blob.is_some()) {
+        return Err(AppError::BadRequest(String::from(
+            "A large blob can only be read or written at login",
+        )));
+    }
+
+    if config.prf {
+        extensions.insert(String::from("prf"), prf_input(salt));
+    } else if salt.is_some() {
+        return Err(not_enabled("prf"));
+    }
+    if config.large_blob {
+        extensions.insert(String::from("largeBlob"), json!({ "support": "preferred" }));
+    }
+
+    Ok(extensions)
+}
+
+/// Login only carries what the client asked for.
+pub fn authentication_inputs(
+    config: ExtensionsConfig,
+    inputs: Option<&ExtensionInputs>,
+) -> Result<Extensions, AppError> {
+    let mut extensions = Extensions::new();
+    let Some(inputs) = inputs else {
+        return Ok(extensions);
+    };
+
+    if let Some(salt) = &inputs.prf_salt {
+        if !config.prf {
+            return Err(not_enabled("prf"));
+        }
+        extensions.insert(String::from("prf"), prf_input(Some(salt)));
+    }
+    if let Some(large_blob) = &inputs.large_blob {
+        if !config.large_blob {
+            return Err(not_enabled("large_blob"));
+        }
+        extensions.insert(
+            String::from("largeBlob"),
+            match large_blob {
+                LargeBlobInput::Read => json!({ "read": true }),
+                LargeBlobInput::Write(blob) => json!({ "write": blob }),
+            },
+        );
+    }
+
+    Ok(extensions)
+}
+
+/// Serializes the options, adding `extensions` next to the ones webauthn-rs
+/// set itself and `rp_icon` to the relying party of creation options.
+/// Without either, this is the plain single serialization.
+pub fn options_with_extensions<O: Serialize>(
+    options: &O,
+    extensions: Extensions,
+    rp_icon: Option<&str>,
+) -> Result<Box<RawValue>, AppError> {
+    if extensions.is_empty() && rp_icon.is_none() {
+        return Ok(serde_json::value::to_raw_value(options)?);
+    }
+
+    let mut options = serde_json::to_value(options)?;
+    let public_key = options
+        .get_mut("publicKey")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| AppError::InternalServer(String::from("Options without publicKey")))?;
+    if let Some(icon) = rp_icon
+        && let Some(rp) = public_key.get_mut("rp").and_then(Value::as_object_mut)
+    {
+        rp.insert(String::from("icon"), Value::from(icon));
+    }
+    if !extensions.is_empty() {
+        match public_key.entry("extensions").or_insert(Value::Null) {
+            Value::Object(current) => current.extend(extensions),
+            other => *other = Value::Object(extensions),
+        }
+    }
+
+    Ok(serde_json::value::to_raw_value(&options)?)
+}
+
+#[derive(Deserialize)]
+struct ClientResults {
+    #[serde(default, rename = "clientExtensionResults", alias = "extensions")]
+    results: Option<RawResults>,
+}
+
+#[derive(Deserialize)]
+struct RawResults {
+    prf: Option<Value>,
+    #[serde(rename = "largeBlob")]
+    large_blob: Option<Value>,
+}
+
+/// Picks the outputs of the enabled extensions out of the credential. The
+/// payload is only parsed a second time when some extension is enabled.
+/// PRF results are key material the client already holds, so they are never
+/// echoed back.
+pub fn client_outputs(
+    config: ExtensionsConfig,
+    credentials: &RawValue,
+) -> Result<Option<ExtensionOutputs>, AppError> {
+    if !config.prf && !config.large_blob {
+        return Ok(None);
+    }
+
+    let Some(results) = serde_json::from_str::<ClientResults>(credentials.get())?.results else {
+        return Ok(None);
+    };
+    let outputs = ExtensionOutputs {
+        prf: results.prf.filter(|_| config.prf).map(without_prf_results),
+        large_blob: results.large_blob.filter(|_| config.large_blob),
+    };
+
+    Ok((outputs.prf.is_some() || outputs.large_blob.is_some()).then_some(outputs))
+}
+
+fn without_prf_results(mut prf: Value) -> Value {
+    if let Value::Object(prf) = &mut prf {
+        prf.remove("results");
+    }
+    prf
+}
+
+fn prf_input(salt: Option<&str>) -> Value {
+    match salt {
+        Some(salt) => json!({ "eval": { "first": salt } }),
+        None => json!({}),
+    }
+}
+
+fn not_enabled(name: &str) -> AppError {
+    AppError::BadRequest(format!("The {} extension is not enabled", name))
+}
@@ -1,19 +1,31 @@
 use std::sync::Arc;
 
-use axum::extract::State;
+use axum::extract::{Path, State};
 use axum_extra::extract::CookieJar;
 
 use crate::{
-    app::{AppError, AppState, middleware::metrics},
+    app::{
+        AppError, AppState,
+        middleware::{auth::IntrospectionClient, metrics},
+    },
+    audit::model::AuditContext,
     auth::dto::{
-        BeginRequest, BeginResponse, FinishRequest, HealthResponse, MessageResponse, TokenResponse,
+        BeginRequest, BeginResponse, CredentialListResponse, FinishRequest, HealthResponse,
+        HealthStatus, IntrospectionRequest, IntrospectionResponse, JwksResponse, LivenessResponse,
+        LoginOptionsQuery, LoginOptionsResponse, MessageResponse, ProfileResponse, RecoveryRequest,
+        RegistrationResponse, StartupResponse, TokenResponse, UpdateSessionRequest,
+        VerifyEmailRequest,
     },
+    auth::jwt::AccessTokenClaims,
 };
 
 /// Begin user registration
 ///
 /// Initiates the WebAuthn registration process for a new user.
 /// Returns challenge options that the client needs to use for credential creation.
+/// A privileged `role` needs an access token with `admin:actions` or an
+/// `invitation` issued for the role; with `REGISTRATION_INVITE_ONLY` every
+/// registration needs one or the other.
 #[utoipa::path(
     post,
     path = "/auth/register/begin",
@@ -21,45 +33,161 @@ use crate::{
     request_body = BeginRequest,
     responses(
         (status = 200, description = "Registration process started successfully", body = BeginResponse),
-        (status = 400, description = "Invalid request data", body = crate::app::error::ErrorResponse),
+        (status = 400, description = "Invalid request data or unknown role", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Privileged role or invite-only registration without an administrator or invitation", body = crate::app::error::ErrorResponse),
         (status = 409, description = "User already exists", body = crate::app::error::ErrorResponse),
         (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
     )
 )]
 pub async fn begin_register(
     State(state): State<Arc<AppState>>,
+    registrar: Option<AccessTokenClaims>,
     request: BeginRequest,
 ) -> Result<BeginResponse, AppError> {
-    let response = state.auth_service.begin_register(request).await;
-    metrics::track_registration_attempt(response.is_ok());
+    let username = request.username.clone();
+    let response = state
+        .auth_service
+        .begin_register(request, registrar.as_ref())
+        .await;
+    state.track_registration(&username, response.is_ok());
     response
 }
 
 /// Finish user registration
 ///
 /// Completes the WebAuthn registration process by verifying the client's credential
-/// and storing it in the database.
+/// and storing it in the database. Returns one-time recovery codes that are never shown again.
 #[utoipa::path(
     post,
     path = "/auth/register/finish",
     tag = "Authentication",
     request_body = FinishRequest,
     responses(
-        (status = 200, description = "Registration completed successfully!", body = MessageResponse),
+        (status = 200, description = "Registration completed successfully!", body = RegistrationResponse),
         (status = 400, description = "Invalid request data or credentials", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Authenticator not allowed for the user's roles (`AUTHENTICATOR_NOT_ALLOWED`)", body = crate::app::error::ErrorResponse),
         (status = 404, description = "Session not found", body = crate::app::error::ErrorResponse),
         (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
     )
 )]
 pub async fn finish_register(
     State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
     request: FinishRequest,
+) -> Result<RegistrationResponse, AppError> {
+    let username = request.username.clone();
+    let response = state.auth_service.finish_register(request, &ctx).await;
+    state.track_registration(&username, response.is_ok());
+    response
+}
+
+/// Verify registration email
+///
+/// Confirms the address given at registration with the token sent to it. The account
+/// becomes active once both the email and the passkey are verified, in either order.
+#[utoipa::path(
+    post,
+    path = "/auth/register/verify",
+    tag = "Authentication",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 200, description = "Email verified", body = MessageResponse),
+        (status = 400, description = "Invalid request data", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Invalid or expired verification token", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "User not found or email verification disabled", body = crate::app::error::ErrorResponse),
+        (status = 429, description = "Too many attempts", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn verify_email(
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: VerifyEmailRequest,
 ) -> Result<MessageResponse, AppError> {
-    let response = state.auth_service.finish_register(request).await;
-    metrics::track_registration_attempt(response.is_ok());
+    state.auth_service.verify_email(request, &ctx).await
+}
+
+/// Begin account recovery
+///
+/// Consumes a one-time recovery code and starts registering a replacement passkey.
+/// Repeated invalid codes temporarily lock recovery for the account.
+#[utoipa::path(
+    post,
+    path = "/auth/recover/begin",
+    tag = "Authentication",
+    request_body = RecoveryRequest,
+    responses(
+        (status = 200, description = "Recovery process started successfully", body = BeginResponse),
+        (status = 400, description = "Invalid request data", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Invalid recovery code", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "User not found", body = crate::app::error::ErrorResponse),
+        (status = 429, description = "Recovery temporarily locked", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn begin_recovery(
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: RecoveryRequest,
+) -> Result<BeginResponse, AppError> {
+    let response = state.auth_service.begin_recovery(request, &ctx).await;
+    metrics::track_recovery_attempt("begin", response.is_ok());
     response
 }
 
+/// Finish account recovery
+///
+/// Verifies the new credential, replaces all previous passkeys with it
+/// and returns a fresh set of recovery codes.
+#[utoipa::path(
+    post,
+    path = "/auth/recover/finish",
+    tag = "Authentication",
+    request_body = FinishRequest,
+    responses(
+        (status = 200, description = "Account recovery completed successfully!", body = RegistrationResponse),
+        (status = 400, description = "Invalid request data or credentials", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Authenticator not allowed for the user's roles (`AUTHENTICATOR_NOT_ALLOWED`)", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "Session not found", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn finish_recovery(
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: FinishRequest,
+) -> Result<RegistrationResponse, AppError> {
+    let response = state.auth_service.finish_recovery(request, &ctx).await;
+    metrics::track_recovery_attempt("finish", response.is_ok());
+    response
+}
+
+/// Login options for a username
+///
+/// Tells the login form whether to offer the passkey button and autofill,
+/// account recovery or registration. Served only with `LOGIN_HINTS` set;
+/// with `uniform` every username gets the same answer.
+#[utoipa::path(
+    get,
+    path = "/auth/login/options",
+    tag = "Authentication",
+    params(LoginOptionsQuery),
+    responses(
+        (status = 200, description = "What to offer for this username", body = LoginOptionsResponse),
+        (status = 400, description = "Invalid username", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "Login hints are disabled", body = crate::app::error::ErrorResponse),
+        (status = 429, description = "Too many requests", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn login_options(
+    State(state): State<Arc<AppState>>,
+    query: LoginOptionsQuery,
+) -> Result<LoginOptionsResponse, AppError> {
+    state.auth_service.login_options(&query.username).await
+}
+
 /// Begin user login
 ///
 /// Initiates the WebAuthn authentication process for an existing user.
@@ -72,6 +200,7 @@ pub async fn finish_register(
     responses(
         (status = 200, description = "Login process started successfully", body = BeginResponse),
         (status = 400, description = "Invalid request data", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Every passkey is locked as possibly cloned", body = crate::app::error::ErrorResponse),
         (status = 404, description = "User not found", body = crate::app::error::ErrorResponse),
         (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
     )
@@ -80,8 +209,9 @@ pub async fn begin_login(
     State(state): State<Arc<AppState>>,
     request: BeginRequest,
 ) -> Result<BeginResponse, AppError> {
+    let username = request.username.clone();
     let response = state.auth_service.begin_login(request).await;
-    metrics::track_login_attempt(response.is_ok());
+    state.track_login(&username, response.is_ok());
     response
 }
 
@@ -98,6 +228,7 @@ pub async fn begin_login(
         (status = 200, description = "Login completed successfully!", body = TokenResponse),
         (status = 400, description = "Invalid credentials", body = crate::app::error::ErrorResponse),
         (status = 401, description = "Authentication failed", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Passkey locked as possibly cloned", body = crate::app::error::ErrorResponse),
         (status = 404, description = "User or session not found", body = crate::app::error::ErrorResponse),
         (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
     )
@@ -105,15 +236,17 @@ pub async fn begin_login(
 pub async fn finish_login(
     jar: CookieJar,
     State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
     request: FinishRequest,
 ) -> Result<(CookieJar, TokenResponse), AppError> {
-    let result = state.auth_service.finish_login(request).await;
-    metrics::track_login_attempt(result.is_ok());
+    let username = request.username.clone();
+    let result = state.auth_service.finish_login(request, &ctx).await;
+    state.track_login(&username, result.is_ok());
     let (response, refresh_token) = result?;
 
     let cookie = state
         .cookie_service
-        .create_refresh_token_cookie(&refresh_token);
+        .create_refresh_token_cookie(&refresh_token.value, refresh_token.trusted);
     let updated_jar = jar.add(cookie);
 
     Ok((updated_jar, response))
@@ -135,15 +268,57 @@ pub async fn finish_login(
 pub async fn refresh(
     jar: CookieJar,
     State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
 ) -> Result<(CookieJar, TokenResponse), AppError> {
     let refresh_token = state.cookie_service.get_refresh_token_from_jar(&jar)?;
-    let result = state.auth_service.refresh(refresh_token.as_str()).await;
+    let result = state
+        .auth_service
+        .refresh(refresh_token.as_str(), &ctx)
+        .await;
     metrics::track_token_operation("refresh", result.is_ok());
     let (response, new_refresh_token) = result?;
 
     let cookie = state
         .cookie_service
-        .create_refresh_token_cookie(&new_refresh_token);
+        .create_refresh_token_cookie(&new_refresh_token.value, new_refresh_token.trusted);
+    let updated_jar = jar.add(cookie);
+
+    Ok((updated_jar, response))
+}
+
+/// Update current session
+///
+/// Renames the session holding the refresh cookie or withdraws its trusted
+/// status. Rotates the refresh token and returns a new access token.
+#[utoipa::path(
+    patch,
+    path = "/auth/session",
+    tag = "Authentication",
+    request_body = UpdateSessionRequest,
+    responses(
+        (status = 200, description = "Session updated successfully!", body = TokenResponse),
+        (status = 400, description = "Invalid session changes", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Invalid or expired refresh token", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn update_session(
+    jar: CookieJar,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: UpdateSessionRequest,
+) -> Result<(CookieJar, TokenResponse), AppError> {
+    let refresh_token = state.cookie_service.get_refresh_token_from_jar(&jar)?;
+    let result = state
+        .auth_service
+        .update_session(refresh_token.as_str(), request, &ctx)
+        .await;
+    metrics::track_token_operation("update_session", result.is_ok());
+    let (response, new_refresh_token) = result?;
+
+    let cookie = state
+        .cookie_service
+        .create_refresh_token_cookie(&new_refresh_token.value, new_refresh_token.trusted);
     let updated_jar = jar.add(cookie);
 
     Ok((updated_jar, response))
@@ -164,12 +339,16 @@ pub async fn refresh(
 pub async fn logout(
     jar: CookieJar,
     State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
 ) -> Result<(CookieJar, MessageResponse), AppError> {
     let refresh_token = state
         .cookie_service
         .get_refresh_token_from_jar(&jar)
         .unwrap_or_default();
-    let response = state.auth_service.logout(refresh_token.as_str()).await;
+    let response = state
+        .auth_service
+        .logout(refresh_token.as_str(), &ctx)
+        .await;
     metrics::track_token_operation("logout", response.is_ok());
 
     let clear_cookie = state.cookie_service.clear_refresh_token_cookie();
@@ -178,21 +357,208 @@ pub async fn logout(
     Ok((updated_jar, response?))
 }
 
-/// Comprehensive health check
+/// Current user profile
+///
+/// Returns the profile of the user the access token was issued to, as
+/// currently stored.
+#[utoipa::path(
+    get,
+    path = "/auth/me",
+    tag = "Authentication",
+    responses(
+        (status = 200, description = "Profile of the authenticated user", body = ProfileResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "User not found", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn me(
+    claims: AccessTokenClaims,
+    State(state): State<Arc<AppState>>,
+) -> Result<ProfileResponse, AppError> {
+    state.auth_service.profile(&claims).await
+}
+
+/// Own passkeys
+///
+/// Lists the passkeys of the authenticated user with what their
+/// authenticators reported: the model (AAGUID), whether the key is synced or
+/// bound to one device, the transports it can be reached over, and when it
+/// was registered and last used.
+#[utoipa::path(
+    get,
+    path = "/auth/credentials",
+    tag = "Authentication",
+    responses(
+        (status = 200, description = "Passkeys of the authenticated user", body = CredentialListResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 503, description = "Dependency unavailable", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn credentials(
+    claims: AccessTokenClaims,
+    State(state): State<Arc<AppState>>,
+) -> Result<CredentialListResponse, AppError> {
+    state.auth_service.credentials(&claims).await
+}
+
+/// Unlock a passkey
+///
+/// Lifts the lock on one of the caller's passkeys, set when a login with it
+/// reported a signature counter that went backwards. Call it once the user
+/// confirmed the authenticator is theirs; its counter starts over.
+#[utoipa::path(
+    post,
+    path = "/auth/credentials/{id}/unlock",
+    tag = "Authentication",
+    params(("id" = String, Path, description = "Credential id, unpadded base64url")),
+    responses(
+        (status = 200, description = "Passkey unlocked successfully!", body = MessageResponse),
+        (status = 400, description = "Malformed credential id", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "No locked passkey with this id", body = crate::app::error::ErrorResponse),
+        (status = 503, description = "Dependency unavailable", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn unlock_credential(
+    claims: AccessTokenClaims,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Path(id): Path<String>,
+) -> Result<MessageResponse, AppError> {
+    state
+        .auth_service
+        .unlock_credential(&claims, &id, &ctx)
+        .await
+}
+
+/// Delete own account
+///
+/// Revokes every refresh token of the user, removes their passkeys, recovery
+/// codes and pending ceremonies, and deactivates the account. Access tokens
+/// already issued keep working until they expire.
+#[utoipa::path(
+    delete,
+    path = "/auth/me",
+    tag = "Authentication",
+    responses(
+        (status = 200, description = "Account deleted successfully!", body = MessageResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "User not found", body = crate::app::error::ErrorResponse),
+        (status = 503, description = "Dependency unavailable", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn delete_me(
+    jar: CookieJar,
+    claims: AccessTokenClaims,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+) -> Result<(CookieJar, MessageResponse), AppError> {
+    let response = state.auth_service.delete_account(&claims, &ctx).await?;
+    let updated_jar = jar.add(state.cookie_service.clear_refresh_token_cookie());
+
+    Ok((updated_jar, response))
+}
+
+/// Access token introspection
+///
+/// Tells a resource server whether an access token is active, per RFC 7662,
+/// checking its signature, expiry and revocation. The caller authenticates
+/// with the client credentials in `INTROSPECTION_CLIENT_ID` and
+/// `INTROSPECTION_CLIENT_SECRET` over HTTP Basic, or with a client
+/// certificate whose subject the TLS proxy forwards.
+#[utoipa::path(
+    post,
+    path = "/auth/introspect",
+    tag = "Authentication",
+    request_body(content = IntrospectionRequest, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Whether the token is active, with its claims if it is", body = IntrospectionResponse),
+        (status = 400, description = "Missing token", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid client authentication", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn introspect(
+    _client: IntrospectionClient,
+    State(state): State<Arc<AppState>>,
+    request: IntrospectionRequest,
+) -> Result<IntrospectionResponse, AppError> {
+    let response = state.auth_service.introspect(&request.token).await;
+    metrics::track_token_operation("introspect", response.is_ok());
+    response
+}
+
+/// Access token verification keys
+///
+/// JSON Web Key Set with every public key access tokens may currently be
+/// signed with, including retired keys whose tokens have not expired yet.
+/// Downstream services pick the key matching the token's `kid` header.
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "Authentication",
+    responses(
+        (status = 200, description = "Public signing keys", body = JwksResponse),
+    )
+)]
+pub async fn jwks(State(state): State<Arc<AppState>>) -> JwksResponse {
+    JwksResponse {
+        keys: state.jwt_service.access_keyring().jwks().keys,
+    }
+}
+
+/// Liveness probe
+///
+/// Answers as long as the process can serve requests. Touches no dependency,
+/// so a database or Redis outage never gets the process restarted.
+#[utoipa::path(
+    get,
+    path = "/livez",
+    tag = "Health",
+    responses(
+        (status = 200, description = "The process is alive", body = LivenessResponse),
+    )
+)]
+pub async fn livez() -> LivenessResponse {
+    LivenessResponse {
+        status: HealthStatus::Healthy,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+/// Readiness probe
 ///
 /// Checks the health of all critical services including database, Redis.
 /// Returns detailed status information and appropriate HTTP status codes.
+/// Also served at `/healthz` for existing probes.
 #[utoipa::path(
     get,
-    path = "/healthz",
+    path = "/readyz",
     tag = "Health",
     responses(
         (status = 200, description = "All services are healthy", body = HealthResponse),
         (status = 503, description = "One or more services are unhealthy", body = HealthResponse),
     )
 )]
-pub async fn healthz(State(state): State<Arc<AppState>>) -> Result<HealthResponse, AppError> {
+pub async fn readyz(State(state): State<Arc<AppState>>) -> Result<HealthResponse, AppError> {
     let response = state.auth_service.check_health().await;
     metrics::track_health_check(response.is_ok());
     response
 }
+
+/// Startup probe
+///
+/// Reports which database migrations are applied, answering 503 while any is
+/// pending so slow first deployments are not killed by the liveness probe.
+#[utoipa::path(
+    get,
+    path = "/startupz",
+    tag = "Health",
+    responses(
+        (status = 200, description = "All migrations are applied", body = StartupResponse),
+        (status = 503, description = "Migrations are pending or the database is unreachable", body = StartupResponse),
+    )
+)]
+pub async fn startupz(State(state): State<Arc<AppState>>) -> Result<StartupResponse, AppError> {
+    state.auth_service.check_startup().await
+}
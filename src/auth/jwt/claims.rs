@@ -2,51 +2,145 @@ use std::time::Duration;
 
 use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
 use chrono::Utc;
-use jsonwebtoken::{Algorithm, Header, Validation, decode, encode};
+use jsonwebtoken::{Algorithm, EncodingKey, Header, Validation, decode, decode_header, encode};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    app::AppError,
-    auth::{jwt::Jwt, jwt::JwtService},
+    app::{AppError, ErrorCode, context::current_tenant},
+    auth::{
+        jwt::Jwt,
+        jwt::JwtService,
+        jwt::keys::AccessKeys,
+        jwt::queries,
+        model::{Grants, SessionDevice},
+    },
+    config::tenant::DEFAULT_TENANT,
 };
 
+/// Who tokens are issued by and for, from `JWT_ISSUER` and `JWT_AUDIENCE`.
+/// Once set, tokens without the same claims are refused, including those of
+/// another deployment that shares the signing secret.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenScope {
+    pub issuer: Option<Box<str>>,
+    pub audience: Option<Box<str>>,
+}
+
+impl TokenScope {
+    /// Requires the configured claims on top of the checks of `validation`.
+    pub fn apply(&self, validation: &mut Validation) {
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+            validation.required_spec_claims.insert(String::from("iss"));
+        }
+        if let Some(audience) = &self.audience {
+            validation.set_audience(&[audience]);
+            validation.required_spec_claims.insert(String::from("aud"));
+        }
+    }
+
+    fn issuer(&self) -> Option<String> {
+        self.issuer.as_deref().map(str::to_owned)
+    }
+
+    fn audience(&self) -> Option<String> {
+        self.audience.as_deref().map(str::to_owned)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccessTokenClaims {
     pub sub: Uuid,
     pub username: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub role: Option<String>,
+    #[serde(flatten)]
+    pub grants: Grants,
     pub iat: i64,
     pub exp: i64,
+    /// The registered client application the token was issued to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    /// The tenant of the request the token was issued in. Tokens issued
+    /// before the claim existed belong to the default tenant.
+    #[serde(default = "default_tenant")]
+    pub tenant: String,
+}
+
+fn default_tenant() -> String {
+    DEFAULT_TENANT.to_owned()
 }
 
 impl AccessTokenClaims {
-    pub fn new(user_id: Uuid, username: String, role: Option<String>, duration: Duration) -> Self {
+    pub fn new(user_id: Uuid, username: String, grants: Grants, duration: Duration) -> Self {
         let now = Utc::now();
         let exp = now + chrono::Duration::from_std(duration).unwrap();
 
         Self {
             sub: user_id,
             username,
-            role,
+            grants,
             iat: now.timestamp(),
             exp: exp.timestamp(),
+            azp: None,
+            iss: None,
+            aud: None,
+            tenant: current_tenant(),
         }
     }
 
+    pub fn with_azp(mut self, azp: Option<String>) -> Self {
+        self.azp = azp;
+        self
+    }
+
+    pub fn with_scope(mut self, scope: &TokenScope) -> Self {
+        self.iss = scope.issuer();
+        self.aud = scope.audience();
+        self
+    }
+
+    /// Tokens of one application are refused on routes reserved to
+    /// another, and so are unbound tokens.
+    pub fn check_azp(&self, expected: Option<&str>) -> Result<(), AppError> {
+        match expected {
+            Some(expected) if self.azp.as_deref() != Some(expected) => Err(AppError::Unauthorized(
+                String::from("Token was issued to another application"),
+            )
+            .with_code(ErrorCode::AuthTokenWrongClient)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Grants hold in the tenant the token was issued in only, so an
+    /// administrator of one tenant is nobody in another.
+    pub fn check_tenant(&self, tenant: &str) -> Result<(), AppError> {
+        if self.tenant != tenant {
+            return Err(AppError::Unauthorized(String::from(
+                "Token was issued for another tenant",
+            ))
+            .with_code(ErrorCode::AuthTokenWrongTenant));
+        }
+        Ok(())
+    }
+
     pub async fn validate(jwt: &Jwt, token: &str) -> Result<Self, AppError> {
-        let validation = Validation::new(Algorithm::EdDSA);
-        let token_data = decode::<Self>(token, &jwt.access_decoding_key, &validation)?;
+        let kid = decode_header(token)?.kid;
+        let keyring = jwt.keyring_for(kid.as_deref()).await;
+        let keys = keyring
+            .find(kid.as_deref())
+            .ok_or_else(|| AppError::Unauthorized(String::from("Unknown signing key")))?;
+        let mut validation = keys.validation();
+        jwt.scope().apply(&mut validation);
+        let token_data = decode::<Self>(token, keys.decoding_key(), &validation)?;
         Ok(token_data.claims)
     }
 
-    pub fn to_token(&self, jwt: &Jwt) -> String {
-        let mut header = Header::new(Algorithm::EdDSA);
-        header.typ = Some("JWT".to_string());
-
-        encode(&header, self, &jwt.access_encoding_key)
-            .expect("Invalid token type for access token creation")
+    pub async fn to_token(&self, keys: &AccessKeys) -> Result<String, AppError> {
+        keys.sign(self).await
     }
 }
 
@@ -54,45 +148,70 @@ impl AccessTokenClaims {
 pub struct RefreshTokenClaims {
     pub sub: Uuid,
     pub username: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub role: Option<String>,
     pub jti: String,
     pub iat: i64,
     pub exp: i64,
+    #[serde(flatten)]
+    pub device: SessionDevice,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
 }
 
 impl RefreshTokenClaims {
-    pub fn new(user_id: Uuid, username: String, role: Option<String>, duration: Duration) -> Self {
+    pub fn new(user_id: Uuid, username: String, device: SessionDevice, duration: Duration) -> Self {
         let now = Utc::now();
         let exp = now + chrono::Duration::from_std(duration).unwrap();
 
         Self {
             sub: user_id,
             username,
-            role,
             jti: Self::generate_jti(),
             iat: now.timestamp(),
             exp: exp.timestamp(),
+            device,
+            iss: None,
+            aud: None,
         }
     }
 
+    pub fn with_scope(mut self, scope: &TokenScope) -> Self {
+        self.iss = scope.issuer();
+        self.aud = scope.audience();
+        self
+    }
+
     pub async fn validate(jwt: &Jwt, token: &str) -> Result<Self, AppError> {
-        let validation = Validation::new(jsonwebtoken::Algorithm::HS256);
-        let token_data = decode::<Self>(token, &jwt.refresh_decoding_key, &validation)?;
+        let mut validation = Validation::new(jsonwebtoken::Algorithm::HS256);
+        jwt.scope().apply(&mut validation);
+        let token_data = match decode::<Self>(token, &jwt.refresh_keys().decoding_key, &validation)
+        {
+            Err(e) if *e.kind() == jsonwebtoken::errors::ErrorKind::InvalidSignature => {
+                let keys = jwt.refresh_keys_after_mismatch().await;
+                decode::<Self>(token, &keys.decoding_key, &validation)?
+            }
+            result => result?,
+        };
         let claims = token_data.claims;
 
-        if jwt.is_blacklisted(&claims.jti).await? {
-            return Err(AppError::Unauthorized("Token has been revoked".to_string()));
+        if jwt.is_blacklisted(&claims.jti).await?
+            || jwt
+                .is_blacklisted(&queries::revoked_subjects::jti(&claims.sub))
+                .await?
+        {
+            return Err(AppError::Unauthorized("Token has been revoked".to_string())
+                .with_code(ErrorCode::AuthTokenRevoked));
         }
 
         Ok(claims)
     }
 
-    pub fn to_token(&self, jwt: &Jwt) -> String {
+    pub fn to_token(&self, encoding_key: &EncodingKey) -> String {
         let mut header = Header::new(Algorithm::HS256);
         header.typ = Some("JWT".to_string());
 
-        encode(&header, self, &jwt.refresh_encoding_key).expect("Expected Refresh token claims")
+        encode(&header, self, encoding_key).expect("Expected Refresh token claims")
     }
 
     fn generate_jti() -> String {
@@ -104,7 +223,6 @@ impl RefreshTokenClaims {
 pub trait JwtClaims {
     fn sub(&self) -> &Uuid;
     fn username(&self) -> &str;
-    fn role(&self) -> Option<&str>;
     fn exp(&self) -> i64;
 }
 
@@ -117,10 +235,6 @@ impl JwtClaims for AccessTokenClaims {
         &self.username
     }
 
-    fn role(&self) -> Option<&str> {
-        self.role.as_deref()
-    }
-
     fn exp(&self) -> i64 {
         self.exp
     }
@@ -135,17 +249,23 @@ impl JwtClaims for RefreshTokenClaims {
         &self.username
     }
 
-    fn role(&self) -> Option<&str> {
-        self.role.as_deref()
-    }
-
     fn exp(&self) -> i64 {
         self.exp
     }
 }
 
+impl AccessTokenClaims {
+    pub fn grants(&self) -> &Grants {
+        &self.grants
+    }
+}
+
 impl RefreshTokenClaims {
     pub fn jti(&self) -> &str {
         &self.jti
     }
+
+    pub fn device(&self) -> &SessionDevice {
+        &self.device
+    }
 }
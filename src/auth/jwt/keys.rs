@@ -0,0 +1,550 @@
+use std::sync::Arc;
+
+use base64::{
+    Engine,
+    prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD},
+};
+use chrono::Utc;
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+        EllipticCurveKeyType, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType,
+        PublicKeyUse,
+    },
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    app::AppError,
+    auth::jwt::{signer::LocalSigner, traits::TokenSigner},
+    config::JwtConfig,
+    utils::crypto::{Cipher, ed25519_public_key, random_bytes},
+};
+
+// DER prefixes of SubjectPublicKeyInfo, followed by the raw public key.
+const ED25519_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, // SEQUENCE (42 bytes)
+    0x30, 0x05, // SEQUENCE (5 bytes)
+    0x06, 0x03, 0x2b, 0x65, 0x70, // OID for Ed25519
+    0x03, 0x21, // BIT STRING (33 bytes)
+    0x00, // no unused bits
+];
+const P256_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x59, // SEQUENCE (89 bytes)
+    0x30, 0x13, // SEQUENCE (19 bytes)
+    0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, // OID for id-ecPublicKey
+    0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, // OID for P-256
+    0x03, 0x42, // BIT STRING (66 bytes)
+    0x00, // no unused bits
+    0x04, // uncompressed point
+];
+
+/// One access token signer, its public key and JWK.
+pub struct AccessKeys {
+    algorithm: Algorithm,
+    kid: String,
+    signer: Arc<dyn TokenSigner>,
+    decoding_key: DecodingKey,
+    jwk: Jwk,
+}
+
+impl AccessKeys {
+    pub fn from_config(config: &JwtConfig) -> Result<Self, String> {
+        match config.access_key() {
+            Some(key) => Self::from_pem(
+                key.algorithm,
+                &key.private_pem,
+                &key.public_pem,
+                key.key_id.as_deref(),
+            ),
+            None => {
+                tracing::warn!(
+                    "JWT_PRIVATE_KEY is not set, deriving the access token key from JWT_SECRET_KEY"
+                );
+                Self::derive_from_secret(config.as_bytes())
+            }
+        }
+    }
+
+    /// `kid` defaults to the RFC 7638 thumbprint, so it changes exactly when the key does.
+    pub fn from_pem(
+        algorithm: Algorithm,
+        private_pem: &[u8],
+        public_pem: &[u8],
+        kid: Option<&str>,
+    ) -> Result<Self, String> {
+        let (encoding_key, decoding_key) = match algorithm {
+            Algorithm::EdDSA => (
+                EncodingKey::from_ed_pem(private_pem),
+                DecodingKey::from_ed_pem(public_pem),
+            ),
+            Algorithm::ES256 => (
+                EncodingKey::from_ec_pem(private_pem),
+                DecodingKey::from_ec_pem(public_pem),
+            ),
+            other => return Err(format!("Unsupported access token algorithm {:?}", other)),
+        };
+        let encoding_key = encoding_key.map_err(|e| format!("Invalid private key: {}", e))?;
+        let decoding_key = decoding_key.map_err(|e| format!("Invalid public key: {}", e))?;
+
+        let signer = Arc::new(LocalSigner::new(algorithm, encoding_key.clone()));
+        let keys = Self::with_signer(signer, decoding_key, public_pem, kid)?;
+        let probe = encode(&keys.header(), &Probe::new(), &encoding_key)
+            .map_err(|e| format!("Cannot sign with private key: {}", e))?;
+        keys.verify_pair(&probe)?;
+        Ok(keys)
+    }
+
+    /// Keys signed by `signer`, e.g. in a KMS, checked against `public_pem`
+    /// with one signature at startup. Only the public key is ever loaded.
+    #[cfg_attr(not(feature = "kms"), allow(dead_code))]
+    pub async fn from_signer(
+        signer: Arc<dyn TokenSigner>,
+        public_pem: &[u8],
+        kid: Option<&str>,
+    ) -> Result<Self, String> {
+        let decoding_key = match signer.algorithm() {
+            Algorithm::EdDSA => DecodingKey::from_ed_pem(public_pem),
+            Algorithm::ES256 => DecodingKey::from_ec_pem(public_pem),
+            other => return Err(format!("Unsupported access token algorithm {:?}", other)),
+        }
+        .map_err(|e| format!("Invalid public key: {}", e))?;
+
+        let keys = Self::with_signer(signer, decoding_key, public_pem, kid)?;
+        let probe = keys
+            .sign(&Probe::new())
+            .await
+            .map_err(|e| format!("Cannot sign with the signer: {}", e))?;
+        keys.verify_pair(&probe)?;
+        Ok(keys)
+    }
+
+    fn with_signer(
+        signer: Arc<dyn TokenSigner>,
+        decoding_key: DecodingKey,
+        public_pem: &[u8],
+        kid: Option<&str>,
+    ) -> Result<Self, String> {
+        let algorithm = signer.algorithm();
+        let mut jwk = Self::public_jwk(algorithm, public_pem)?;
+        let kid = kid.map_or_else(|| Self::thumbprint(&jwk), str::to_owned);
+        jwk.common.key_id = Some(kid.clone());
+
+        Ok(Self {
+            algorithm,
+            kid,
+            signer,
+            decoding_key,
+            jwk,
+        })
+    }
+
+    /// Legacy mode: an Ed25519 key built from the first 32 bytes of the secret.
+    pub fn derive_from_secret(secret: &[u8]) -> Result<Self, String> {
+        let mut seed = [0u8; 32];
+        let len = std::cmp::min(secret.len(), 32);
+        seed[..len].copy_from_slice(&secret[..len]);
+
+        let (private_pem, public_pem) = ed25519_pems(&seed);
+        Self::from_pem(Algorithm::EdDSA, &private_pem, &public_pem, None)
+    }
+
+    pub fn header(&self) -> Header {
+        let mut header = Header::new(self.algorithm);
+        header.typ = Some("JWT".to_string());
+        header.kid = Some(self.kid.clone());
+        header
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    /// A compact JWS of `claims` under `header()`.
+    pub async fn sign<T: Serialize>(&self, claims: &T) -> Result<String, AppError> {
+        let input = format!("{}.{}", encode_part(&self.header())?, encode_part(claims)?);
+
+        let signature = self.signer.sign(input.as_bytes()).await?;
+        Ok(format!(
+            "{}.{}",
+            input,
+            BASE64_URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    pub fn decoding_key(&self) -> &DecodingKey {
+        &self.decoding_key
+    }
+
+    pub fn validation(&self) -> Validation {
+        Validation::new(self.algorithm)
+    }
+
+    fn public_jwk(algorithm: Algorithm, public_pem: &[u8]) -> Result<Jwk, String> {
+        let der = from_pem("PUBLIC KEY", public_pem)?;
+
+        let (key_algorithm, parameters) = match algorithm {
+            Algorithm::EdDSA => {
+                let x = der
+                    .strip_prefix(ED25519_SPKI_PREFIX)
+                    .filter(|x| x.len() == 32)
+                    .ok_or("Public key is not an Ed25519 key")?;
+                (
+                    KeyAlgorithm::EdDSA,
+                    AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x: BASE64_URL_SAFE_NO_PAD.encode(x),
+                    }),
+                )
+            }
+            _ => {
+                let point = der
+                    .strip_prefix(P256_SPKI_PREFIX)
+                    .filter(|point| point.len() == 64)
+                    .ok_or("Public key is not a P-256 key")?;
+                (
+                    KeyAlgorithm::ES256,
+                    AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                        key_type: EllipticCurveKeyType::EC,
+                        curve: EllipticCurve::P256,
+                        x: BASE64_URL_SAFE_NO_PAD.encode(&point[..32]),
+                        y: BASE64_URL_SAFE_NO_PAD.encode(&point[32..]),
+                    }),
+                )
+            }
+        };
+
+        Ok(Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(key_algorithm),
+                ..Default::default()
+            },
+            algorithm: parameters,
+        })
+    }
+
+    // Built by hand: members must be in lexicographic order with no whitespace.
+    fn thumbprint(jwk: &Jwk) -> String {
+        let canonical = match &jwk.algorithm {
+            AlgorithmParameters::OctetKeyPair(key) => {
+                format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, key.x)
+            }
+            AlgorithmParameters::EllipticCurve(key) => format!(
+                r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#,
+                key.x, key.y
+            ),
+            _ => unreachable!("only EdDSA and ES256 keys are built"),
+        };
+        BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+    }
+
+    /// Catches a private and public key that do not belong together at
+    /// startup, instead of on the first authenticated request.
+    fn verify_pair(&self, probe: &str) -> Result<(), String> {
+        decode::<Probe>(probe, &self.decoding_key, &self.validation())
+            .map(|_| ())
+            .map_err(|_| String::from("Private and public keys do not match"))
+    }
+}
+
+fn encode_part<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_vec(value)
+        .map(|json| BASE64_URL_SAFE_NO_PAD.encode(json))
+        .map_err(|e| AppError::InternalServer(format!("Failed to encode token: {}", e)))
+}
+
+/// The token signed once at startup to check a key against its public half.
+#[derive(Serialize, Deserialize)]
+struct Probe {
+    exp: i64,
+}
+
+impl Probe {
+    fn new() -> Self {
+        Self {
+            exp: Utc::now().timestamp() + 60,
+        }
+    }
+}
+
+const SEALED_PREFIX: &str = "sealed:";
+
+/// Seals the secrets rotation shares through Redis with
+/// `JWT_KEY_ENCRYPTION_KEY`, so reading Redis is not enough to sign tokens.
+/// The label is bound to the ciphertext: a sealed value cannot be moved
+/// under another kid.
+pub struct KeyEncryption {
+    cipher: Cipher,
+}
+
+impl KeyEncryption {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Cipher::new(key).expect("a 32-byte key is a valid cipher key"),
+        }
+    }
+
+    pub fn seal(&self, secret: &str, label: &str) -> Result<String, String> {
+        self.cipher
+            .seal(secret.as_bytes(), label.as_bytes())
+            .map(|sealed| format!("{}{}", SEALED_PREFIX, BASE64_STANDARD.encode(sealed)))
+            .ok_or_else(|| String::from("Failed to seal key"))
+    }
+}
+
+/// Opens what `KeyEncryption::seal` stored under `label`. Without an
+/// encryption key, values stored in the clear before one was set are still
+/// read; with one they are refused, so writing to Redis cannot plant a key.
+pub fn open_secret(
+    encryption: Option<&KeyEncryption>,
+    stored: &str,
+    label: &str,
+) -> Result<String, String> {
+    match (encryption, stored.strip_prefix(SEALED_PREFIX)) {
+        (Some(encryption), Some(sealed)) => {
+            let sealed = BASE64_STANDARD
+                .decode(sealed)
+                .map_err(|e| format!("Malformed sealed key: {}", e))?;
+            let secret = encryption
+                .cipher
+                .open(&sealed, label.as_bytes())
+                .ok_or_else(|| String::from("Key not sealed with JWT_KEY_ENCRYPTION_KEY"))?;
+            String::from_utf8(secret).map_err(|e| format!("Malformed sealed key: {}", e))
+        }
+        (Some(_), None) => Err(String::from(
+            "Key stored in the clear while JWT_KEY_ENCRYPTION_KEY is set",
+        )),
+        (None, Some(_)) => Err(String::from(
+            "Key is sealed but JWT_KEY_ENCRYPTION_KEY is not set",
+        )),
+        (None, None) => Ok(stored.to_owned()),
+    }
+}
+
+/// A key generated by rotation, shared with every instance through Redis.
+#[derive(Debug, Clone)]
+pub struct StoredKey {
+    pub kid: String,
+    pub private_pem: String,
+    pub public_pem: String,
+    pub created_at: i64,
+}
+
+/// How a `StoredKey` sits in Redis: `private_pem` is sealed, or in the
+/// clear for keys rotated before sealing.
+#[derive(Serialize, Deserialize)]
+struct StoredKeyEntry {
+    kid: String,
+    private_pem: String,
+    public_pem: String,
+    created_at: i64,
+}
+
+impl StoredKey {
+    /// Rotated keys are always Ed25519, whatever `JWT_ALGORITHM` says, since
+    /// they need no external tooling to create.
+    pub fn generate(created_at: i64) -> Result<Self, String> {
+        let seed: [u8; 32] = random_bytes();
+
+        let (private_pem, public_pem) = ed25519_pems(&seed);
+        let keys = AccessKeys::from_pem(Algorithm::EdDSA, &private_pem, &public_pem, None)?;
+
+        Ok(Self {
+            kid: keys.kid,
+            private_pem: String::from_utf8_lossy(&private_pem).into_owned(),
+            public_pem: String::from_utf8_lossy(&public_pem).into_owned(),
+            created_at,
+        })
+    }
+
+    /// The Redis entry, with the private key sealed under the kid.
+    pub fn seal(&self, encryption: &KeyEncryption) -> Result<String, String> {
+        serde_json::to_string(&StoredKeyEntry {
+            kid: self.kid.clone(),
+            private_pem: encryption.seal(&self.private_pem, &self.kid)?,
+            public_pem: self.public_pem.clone(),
+            created_at: self.created_at,
+        })
+        .map_err(|e| format!("Failed to store signing key: {}", e))
+    }
+
+    pub fn open(entry: &str, encryption: Option<&KeyEncryption>) -> Result<Self, String> {
+        let entry: StoredKeyEntry =
+            serde_json::from_str(entry).map_err(|e| format!("Malformed key: {}", e))?;
+        Ok(Self {
+            private_pem: open_secret(encryption, &entry.private_pem, &entry.kid)?,
+            kid: entry.kid,
+            public_pem: entry.public_pem,
+            created_at: entry.created_at,
+        })
+    }
+
+    pub fn to_keys(&self) -> Result<AccessKeys, String> {
+        AccessKeys::from_pem(
+            Algorithm::EdDSA,
+            self.private_pem.as_bytes(),
+            self.public_pem.as_bytes(),
+            Some(&self.kid),
+        )
+    }
+}
+
+/// Which stored keys are still needed. A key retires when its successor is
+/// created and must keep verifying for `retention_secs` after that, until
+/// every token it signed has expired.
+#[derive(Debug)]
+pub struct KeyringPlan<'a> {
+    /// Newest first; the first one signs.
+    pub live: Vec<&'a StoredKey>,
+    pub expired: Vec<&'a str>,
+    /// Whether the configured key still verifies.
+    pub base_live: bool,
+}
+
+impl<'a> KeyringPlan<'a> {
+    pub fn new(stored: &'a [StoredKey], now: i64, retention_secs: i64) -> Self {
+        let mut keys: Vec<&StoredKey> = stored.iter().collect();
+        keys.sort_by_key(|key| std::cmp::Reverse(key.created_at));
+
+        let mut live = Vec::new();
+        let mut expired = Vec::new();
+        let mut successor: Option<&StoredKey> = None;
+        for key in keys {
+            match successor {
+                Some(newer) if newer.created_at + retention_secs <= now => {
+                    expired.push(key.kid.as_str())
+                }
+                _ => live.push(key),
+            }
+            successor = Some(key);
+        }
+
+        let base_live = successor.is_none_or(|oldest| oldest.created_at + retention_secs > now);
+
+        Self {
+            live,
+            expired,
+            base_live,
+        }
+    }
+}
+
+/// Every key access tokens may be verified with, plus the one signing new
+/// tokens. `generation` is the kid of the newest rotated key, if any.
+pub struct AccessKeyring {
+    generation: Option<String>,
+    signing: Arc<AccessKeys>,
+    keys: Vec<Arc<AccessKeys>>,
+}
+
+impl AccessKeyring {
+    pub fn new(base: Arc<AccessKeys>) -> Self {
+        Self {
+            generation: None,
+            signing: Arc::clone(&base),
+            keys: vec![base],
+        }
+    }
+
+    /// Entries that fail to parse are skipped, so one bad key in Redis does
+    /// not take token issuance down with it.
+    pub fn from_stored(base: &Arc<AccessKeys>, stored: &[StoredKey], plan: &KeyringPlan) -> Self {
+        let mut keys: Vec<Arc<AccessKeys>> = plan
+            .live
+            .iter()
+            .filter_map(|key| match key.to_keys() {
+                Ok(keys) => Some(Arc::new(keys)),
+                Err(e) => {
+                    tracing::error!(kid = key.kid, "Skipping unusable access token key: {}", e);
+                    None
+                }
+            })
+            .collect();
+        if plan.base_live || keys.is_empty() {
+            keys.push(Arc::clone(base));
+        }
+
+        Self {
+            generation: stored
+                .iter()
+                .max_by_key(|key| key.created_at)
+                .map(|key| key.kid.clone()),
+            signing: Arc::clone(&keys[0]),
+            keys,
+        }
+    }
+
+    pub fn generation(&self) -> Option<&str> {
+        self.generation.as_deref()
+    }
+
+    pub fn signing(&self) -> &AccessKeys {
+        &self.signing
+    }
+
+    /// Tokens without a `kid` predate it and can only come from the configured key.
+    pub fn find(&self, kid: Option<&str>) -> Option<&AccessKeys> {
+        match kid {
+            Some(kid) => self.keys.iter().find(|keys| keys.kid == kid),
+            None => self.keys.last(),
+        }
+        .map(Arc::as_ref)
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.keys.iter().map(|keys| keys.jwk.clone()).collect(),
+        }
+    }
+}
+
+fn ed25519_pems(seed: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+    // PKCS#8 header per Ed25519
+    let mut pkcs8 = vec![
+        0x30, 0x2e, // SEQUENCE (46 bytes)
+        0x02, 0x01, 0x00, // INTEGER (version = 0)
+        0x30, 0x05, // SEQUENCE (5 bytes)
+        0x06, 0x03, 0x2b, 0x65, 0x70, // OID for Ed25519
+        0x04, 0x22, // OCTET STRING (34 bytes)
+        0x04, 0x20, // OCTET STRING (32 bytes)
+    ];
+    pkcs8.extend_from_slice(seed);
+
+    let mut spki = ED25519_SPKI_PREFIX.to_vec();
+    spki.extend_from_slice(&ed25519_public_key(seed));
+
+    (to_pem("PRIVATE KEY", &pkcs8), to_pem("PUBLIC KEY", &spki))
+}
+
+fn to_pem(label: &str, der: &[u8]) -> Vec<u8> {
+    let encoded = BASE64_STANDARD.encode(der);
+
+    let mut pem = format!("-----BEGIN {}-----\n", label).into_bytes();
+    for chunk in encoded.as_bytes().chunks(64) {
+        pem.extend_from_slice(chunk);
+        pem.push(b'\n');
+    }
+    pem.extend_from_slice(format!("-----END {}-----\n", label).as_bytes());
+    pem
+}
+
+fn from_pem(label: &str, pem: &[u8]) -> Result<Vec<u8>, String> {
+    let pem = std::str::from_utf8(pem).map_err(|_| format!("{} is not valid PEM", label))?;
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+
+    let body = pem
+        .split_once(&begin)
+        .and_then(|(_, rest)| rest.split_once(&end))
+        .map(|(body, _)| body)
+        .ok_or_else(|| format!("Expected a PEM block labelled {}", label))?;
+
+    let encoded: String = body.split_whitespace().collect();
+    BASE64_STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("{} is not valid base64: {}", label, e))
+}
@@ -0,0 +1,368 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use jsonwebtoken::Algorithm;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::{
+    app::AppError,
+    auth::jwt::traits::{SignFuture, TokenSigner},
+    config::jwt::AwsCredentials,
+    utils::HttpClientService,
+};
+
+/// Where GCE, GKE and Cloud Run hand out the service account's access token.
+pub const GCP_METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+/// A cached GCP access token is replaced this long before it expires.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+/// P-256 scalars, the length of `r` and `s` in an ES256 signature.
+const P256_SCALAR_LEN: usize = 32;
+
+/// Signs with an asymmetric `ECC_NIST_P256` key in AWS KMS, authenticating
+/// with SigV4.
+pub struct AwsKmsSigner {
+    http: Arc<HttpClientService>,
+    endpoint: Url,
+    region: String,
+    key_id: String,
+    credentials: AwsCredentials,
+}
+
+#[derive(Deserialize)]
+struct AwsSignResponse {
+    #[serde(rename = "Signature")]
+    signature: String,
+}
+
+impl AwsKmsSigner {
+    pub fn new(
+        http: Arc<HttpClientService>,
+        endpoint: Url,
+        region: String,
+        key_id: String,
+        credentials: AwsCredentials,
+    ) -> Self {
+        Self {
+            http,
+            endpoint,
+            region,
+            key_id,
+            credentials,
+        }
+    }
+
+    async fn sign_digest(&self, digest: &[u8]) -> Result<Vec<u8>, AppError> {
+        let body = serde_json::to_vec(&json!({
+            "KeyId": self.key_id,
+            "Message": BASE64_STANDARD.encode(digest),
+            "MessageType": "DIGEST",
+            "SigningAlgorithm": "ECDSA_SHA_256",
+        }))?;
+        let headers = [
+            ("content-type", "application/x-amz-json-1.1"),
+            ("x-amz-target", "TrentService.Sign"),
+        ];
+
+        let signed = sigv4_headers(
+            &self.credentials,
+            &SigV4Request {
+                region: &self.region,
+                service: "kms",
+                method: "POST",
+                host: &host_header(&self.endpoint),
+                path: self.endpoint.path(),
+                query: "",
+                headers: &headers,
+                body: &body,
+            },
+            Utc::now(),
+        );
+
+        let mut request = self.http.client().post(self.endpoint.clone()).body(body);
+        for (name, value) in headers.iter().copied().chain(
+            signed
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        ) {
+            request = request.header(name, value);
+        }
+
+        let response: AwsSignResponse = self
+            .http
+            .execute(request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        der_to_jose(&decode_signature(&response.signature)?)
+    }
+}
+
+impl TokenSigner for AwsKmsSigner {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::ES256
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a> {
+        Box::pin(async move { self.sign_digest(&Sha256::digest(message)).await })
+    }
+}
+
+/// Signs with an `EC_SIGN_P256_SHA256` key version in Cloud KMS, using the
+/// service account of the instance the server runs on.
+pub struct GcpKmsSigner {
+    http: Arc<HttpClientService>,
+    sign_url: Url,
+    token_url: Url,
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+#[derive(Deserialize)]
+struct GcpTokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct GcpSignResponse {
+    signature: String,
+}
+
+impl GcpKmsSigner {
+    /// `key_name` is the full key version resource name, from `projects/`
+    /// to `cryptoKeyVersions/N`.
+    pub fn new(
+        http: Arc<HttpClientService>,
+        endpoint: &Url,
+        key_name: &str,
+        token_url: Url,
+    ) -> Result<Self, String> {
+        let sign_url = endpoint
+            .join(&format!("v1/{}:asymmetricSign", key_name))
+            .map_err(|e| format!("Invalid Cloud KMS key name {}: {}", key_name, e))?;
+
+        Ok(Self {
+            http,
+            sign_url,
+            token_url,
+            token: Mutex::new(None),
+        })
+    }
+
+    async fn access_token(&self) -> Result<String, AppError> {
+        let mut cached = self.token.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref()
+            && Instant::now() + TOKEN_REFRESH_MARGIN < *expires_at
+        {
+            return Ok(token.clone());
+        }
+
+        let request = self
+            .http
+            .client()
+            .get(self.token_url.clone())
+            .header("Metadata-Flavor", "Google");
+        let response: GcpTokenResponse = self
+            .http
+            .execute(request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let expires_at = Instant::now() + Duration::from_secs(response.expires_in);
+        *cached = Some((response.access_token.clone(), expires_at));
+        Ok(response.access_token)
+    }
+
+    async fn sign_digest(&self, digest: &[u8]) -> Result<Vec<u8>, AppError> {
+        let request = self
+            .http
+            .client()
+            .post(self.sign_url.clone())
+            .bearer_auth(self.access_token().await?)
+            .json(&json!({ "digest": { "sha256": BASE64_STANDARD.encode(digest) } }));
+
+        let response: GcpSignResponse = self
+            .http
+            .execute(request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        der_to_jose(&decode_signature(&response.signature)?)
+    }
+}
+
+impl TokenSigner for GcpKmsSigner {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::ES256
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a> {
+        Box::pin(async move { self.sign_digest(&Sha256::digest(message)).await })
+    }
+}
+
+fn decode_signature(signature: &str) -> Result<Vec<u8>, AppError> {
+    BASE64_STANDARD
+        .decode(signature)
+        .map_err(|e| AppError::InternalServer(format!("KMS returned malformed base64: {}", e)))
+}
+
+/// KMS returns ECDSA signatures DER-encoded, while JWS wants `r || s`, each
+/// left-padded to the scalar length.
+pub fn der_to_jose(der: &[u8]) -> Result<Vec<u8>, AppError> {
+    let malformed = || AppError::InternalServer(String::from("KMS returned a malformed signature"));
+
+    // A P-256 signature is at most 72 bytes, so every length is short-form.
+    let body = match der {
+        [0x30, len, body @ ..] if *len as usize == body.len() => body,
+        _ => return Err(malformed()),
+    };
+    let (r, rest) = der_integer(body).ok_or_else(malformed)?;
+    let (s, rest) = der_integer(rest).ok_or_else(malformed)?;
+    if !rest.is_empty() {
+        return Err(malformed());
+    }
+
+    let mut signature = vec![0u8; 2 * P256_SCALAR_LEN];
+    signature[P256_SCALAR_LEN - r.len()..P256_SCALAR_LEN].copy_from_slice(r);
+    signature[2 * P256_SCALAR_LEN - s.len()..].copy_from_slice(s);
+    Ok(signature)
+}
+
+/// One INTEGER without its leading zero bytes, and what follows it.
+fn der_integer(input: &[u8]) -> Option<(&[u8], &[u8])> {
+    let [0x02, len, rest @ ..] = input else {
+        return None;
+    };
+    let (value, rest) = rest.split_at_checked(*len as usize)?;
+    let start = value
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(value.len());
+    let value = &value[start..];
+
+    (value.len() <= P256_SCALAR_LEN).then_some((value, rest))
+}
+
+/// What goes into a SigV4 signature. `headers` are sent as given, besides
+/// the `host`, `x-amz-date` and session token headers that are added.
+pub struct SigV4Request<'a> {
+    pub region: &'a str,
+    pub service: &'a str,
+    pub method: &'a str,
+    pub host: &'a str,
+    pub path: &'a str,
+    /// Already in canonical form: sorted and percent-encoded.
+    pub query: &'a str,
+    pub headers: &'a [(&'a str, &'a str)],
+    pub body: &'a [u8],
+}
+
+/// The headers that authenticate `request`, `authorization` last.
+pub fn sigv4_headers(
+    credentials: &AwsCredentials,
+    request: &SigV4Request,
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let mut added = vec![(String::from("x-amz-date"), amz_date.clone())];
+    if let Some(token) = &credentials.session_token {
+        added.push((String::from("x-amz-security-token"), token.clone()));
+    }
+
+    let mut canonical_headers: Vec<(String, String)> = request
+        .headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_owned()))
+        .chain([(String::from("host"), request.host.to_owned())])
+        .chain(added.iter().cloned())
+        .collect();
+    canonical_headers.sort();
+    let signed_headers = canonical_headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method,
+        request.path,
+        request.query,
+        canonical_headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect::<String>(),
+        signed_headers,
+        hex(&Sha256::digest(request.body)),
+    );
+
+    let scope = format!(
+        "{}/{}/{}/aws4_request",
+        date, request.region, request.service
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let signing_key = [
+        request.region.as_bytes(),
+        request.service.as_bytes(),
+        b"aws4_request",
+    ]
+    .iter()
+    .fold(
+        hmac_sha256(
+            format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+            date.as_bytes(),
+        ),
+        |key, part| hmac_sha256(&key, part),
+    );
+    let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+    added.push((
+        String::from("authorization"),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    added
+}
+
+/// The `Host` header reqwest sends for `url`.
+fn host_header(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_owned(),
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -1,8 +1,14 @@
 pub mod claims;
+pub mod keys;
+#[cfg(feature = "kms")]
+pub mod kms;
+pub mod opaque;
 mod queries;
+pub mod revocation;
 pub mod service;
+pub mod signer;
 pub mod traits;
 
 pub(crate) use claims::{AccessTokenClaims, RefreshTokenClaims};
-pub(crate) use service::{Jwt, TokenPair};
+pub(crate) use service::{Jwt, RefreshToken, RotatedPair, TokenPair};
 pub(crate) use traits::JwtService;
@@ -0,0 +1,36 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
+
+use crate::utils::crypto::{random_bytes, sha256};
+
+/// Recorded in the issuance log in place of a signing key id.
+pub const OPAQUE_KID: &str = "opaque";
+
+/// An access token that carries nothing but 256 random bits. Redis maps its
+/// hash to the claims, so a leaked snapshot holds no usable tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpaqueToken(String);
+
+impl OpaqueToken {
+    pub fn generate() -> Self {
+        let entropy: [u8; 32] = random_bytes();
+        Self(BASE64_URL_SAFE_NO_PAD.encode(entropy))
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    pub fn hash(&self) -> String {
+        Self::hash_input(&self.0)
+    }
+
+    pub fn hash_input(input: &str) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(sha256(input.as_bytes()))
+    }
+
+    /// Whether `token` has the shape of an opaque token rather than a JWT,
+    /// whose three segments are separated by dots.
+    pub fn matches(token: &str) -> bool {
+        !token.contains('.')
+    }
+}
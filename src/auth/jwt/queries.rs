@@ -3,3 +3,63 @@ pub mod blacklist {
         format!("blacklist:{}", jti)
     }
 }
+
+pub mod revoked_subjects {
+    /// Stored alongside revoked jtis; the prefix keeps it from matching one.
+    pub fn jti(user_id: &uuid::Uuid) -> String {
+        format!("user:{}", user_id)
+    }
+}
+
+pub mod opaque_tokens {
+    /// Claims of the opaque access token with this hash, expiring with it.
+    pub fn key(hash: &str) -> String {
+        format!("jwt:opaque:{}", hash)
+    }
+
+    /// Hashes of the user's opaque tokens, so revoking the user finds them.
+    pub fn user_key(user_id: &uuid::Uuid) -> String {
+        format!("jwt:opaque_user:{}", user_id)
+    }
+}
+
+pub mod refresh_grace {
+    /// What a refresh returned, under the hash of the token it rotated out,
+    /// until the grace window closes or the token is replayed.
+    pub fn key(hash: &str) -> String {
+        format!("jwt:refresh_grace:{}", hash)
+    }
+}
+
+pub mod refresh_secret {
+    /// Shared so a rotation applies to every instance and survives restarts.
+    /// Sealed with `JWT_KEY_ENCRYPTION_KEY`, the key name as its label.
+    pub const KEY: &str = "jwt:refresh_secret";
+}
+
+pub mod access_keys {
+    /// Hash of rotated signing keys by kid, shared by every instance.
+    pub const KEYS: &str = "jwt:access_keys";
+    /// Kid of the newest rotated key; instances reload the hash when it changes.
+    pub const GENERATION: &str = "jwt:access_keys:generation";
+    /// Held briefly so only one instance performs a scheduled rotation.
+    pub const ROTATION_LOCK: &str = "jwt:access_keys:rotation_lock";
+}
+
+pub mod revoked_tokens {
+    /// Refuses new rows once the table holds `$3` entries, so a long outage
+    /// cannot grow it without bound.
+    pub const INSERT: &str = "INSERT INTO revoked_tokens (jti, expires_at)
+         SELECT $1, $2
+         WHERE (SELECT COUNT(*) FROM revoked_tokens) < $3
+         ON CONFLICT (jti) DO UPDATE SET expires_at = EXCLUDED.expires_at";
+
+    pub const EXISTS: &str = "SELECT EXISTS (
+             SELECT 1 FROM revoked_tokens WHERE jti = $1 AND expires_at > NOW()
+         ) AS revoked";
+
+    pub const DRAIN: &str = "WITH drained AS (
+             DELETE FROM revoked_tokens RETURNING jti, expires_at
+         )
+         SELECT jti, expires_at FROM drained WHERE expires_at > NOW()";
+}
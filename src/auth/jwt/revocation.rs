@@ -0,0 +1,361 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use redis::aio::ConnectionManager;
+
+use crate::app::AppError;
+use crate::app::middleware::metrics::track_revocation_fallback;
+use crate::auth::jwt::traits::{FallbackRevocationStore, RevocationStore};
+use crate::config::{CircuitBreaker, RevocationConfig};
+use crate::utils::{BaseRedisRepository, BaseRepository, MemoryPressure, RedisShards};
+use crate::{db_delete, db_insert, db_select, redis_exists, redis_set};
+
+use super::queries;
+
+// jsonwebtoken's default leeway: tokens still validate this long past `exp`.
+pub const VALIDATION_LEEWAY_SECS: i64 = 60;
+
+/// How long a revoked token stays blacklisted: until it would stop
+/// validating anyway. Under memory pressure the leeway margin is dropped so
+/// entries for tokens about to expire free their memory sooner.
+pub fn blacklist_ttl(exp: i64, now: i64, memory_pressure: bool) -> u64 {
+    let margin = if memory_pressure {
+        0
+    } else {
+        VALIDATION_LEEWAY_SECS
+    };
+    (exp - now + margin).max(1) as u64
+}
+
+/// The authoritative blacklist, sharded across Redis when shards are set.
+pub struct RedisRevocations {
+    base: BaseRedisRepository,
+    memory_pressure: Arc<MemoryPressure>,
+}
+
+impl RedisRevocations {
+    pub fn new(
+        conn_manager: ConnectionManager,
+        circuit_breaker: Arc<CircuitBreaker>,
+        shards: Option<Arc<RedisShards>>,
+        memory_pressure: Arc<MemoryPressure>,
+    ) -> Self {
+        Self {
+            base: BaseRedisRepository::new(conn_manager, circuit_breaker).with_shards(shards),
+            memory_pressure,
+        }
+    }
+}
+
+impl RevocationStore for RedisRevocations {
+    async fn revoke(&self, jti: &str, exp: i64) -> Result<(), AppError> {
+        let redis_key = queries::blacklist::key(jti);
+        let key = redis_key.as_str();
+        let ttl = blacklist_ttl(exp, Utc::now().timestamp(), self.memory_pressure.is_high());
+
+        self.base
+            .execute_on_shard(key, move |conn| async move {
+                let mut conn = conn.clone();
+                use redis::AsyncCommands;
+                let _: () = redis_set!({ conn.set_ex(key, "1", ttl).await })?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        let redis_key = queries::blacklist::key(jti);
+        let key = redis_key.as_str();
+        let exists = move |conn: ConnectionManager| async move {
+            let mut conn = conn.clone();
+            use redis::AsyncCommands;
+            let exists: bool = redis_exists!({ conn.exists(key).await })?;
+            Ok(exists)
+        };
+
+        if self.base.execute_on_shard(key, exists).await? {
+            return Ok(true);
+        }
+        // Revoked before the ring changed, the entry is on its old owner.
+        Ok(self
+            .base
+            .execute_on_previous_shard(key, exists)
+            .await?
+            .unwrap_or(false))
+    }
+}
+
+/// Holds revocations made while Redis is down, shared by every instance.
+/// Capped so a long outage cannot fill the database.
+pub struct PostgresRevocations {
+    base: BaseRepository,
+    max_entries: i64,
+}
+
+impl PostgresRevocations {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>, max_entries: i64) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+            max_entries,
+        }
+    }
+}
+
+impl RevocationStore for PostgresRevocations {
+    async fn revoke(&self, jti: &str, exp: i64) -> Result<(), AppError> {
+        let expires_at = DateTime::from_timestamp(exp + VALIDATION_LEEWAY_SECS, 0)
+            .ok_or_else(|| AppError::BadRequest(String::from("Invalid token expiry")))?;
+
+        let inserted = db_insert!("revoked_tokens", {
+            self.base
+                .execute_prepared_raw(
+                    queries::revoked_tokens::INSERT,
+                    &[
+                        &jti as &(dyn tokio_postgres::types::ToSql + Sync),
+                        &expires_at,
+                        &self.max_entries,
+                    ],
+                )
+                .await
+        })?;
+
+        if inserted == 0 {
+            return Err(AppError::ServiceUnavailable(String::from(
+                "Revocation fallback store is full",
+            )));
+        }
+        Ok(())
+    }
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        let row = db_select!("revoked_tokens", {
+            self.base
+                .execute_prepared_opt(
+                    queries::revoked_tokens::EXISTS,
+                    &[&jti as &(dyn tokio_postgres::types::ToSql + Sync)],
+                )
+                .await
+        })?;
+
+        Ok(row.is_some_and(|row| row.get("revoked")))
+    }
+}
+
+impl FallbackRevocationStore for PostgresRevocations {
+    async fn drain(&self) -> Result<Vec<(String, i64)>, AppError> {
+        let rows = db_delete!("revoked_tokens", {
+            self.base
+                .execute_prepared(queries::revoked_tokens::DRAIN, &[])
+                .await
+        })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let expires_at: DateTime<Utc> = row.get("expires_at");
+                (
+                    row.get("jti"),
+                    expires_at.timestamp() - VALIDATION_LEEWAY_SECS,
+                )
+            })
+            .collect())
+    }
+}
+
+/// The most recent revocations made by this instance, dropping the oldest
+/// once full. Lets a token revoked here be refused even when no store can be
+/// reached.
+pub struct RecentRevocations {
+    capacity: usize,
+    entries: Mutex<(HashMap<String, i64>, VecDeque<String>)>,
+}
+
+impl RecentRevocations {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new((HashMap::new(), VecDeque::new())),
+        }
+    }
+
+    pub fn insert(&self, jti: &str, exp: i64) {
+        let mut guard = self.entries.lock().unwrap();
+        let (expiries, order) = &mut *guard;
+
+        if expiries.insert(jti.to_owned(), exp).is_none() {
+            order.push_back(jti.to_owned());
+        }
+        while order.len() > self.capacity {
+            if let Some(oldest) = order.pop_front() {
+                expiries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn contains(&self, jti: &str, now: i64) -> bool {
+        let guard = self.entries.lock().unwrap();
+        guard
+            .0
+            .get(jti)
+            .is_some_and(|exp| *exp + VALIDATION_LEEWAY_SECS >= now)
+    }
+}
+
+/// Redis first, then the in-memory and fallback stores for at most
+/// `max_outage` after Redis starts failing. Past that, or without a fallback,
+/// revocation fails closed like a plain Redis blacklist.
+///
+/// During the window a check only sees revocations made by this instance or
+/// written to the fallback; anything revoked in Redis is invisible until it
+/// recovers. Fallback entries are copied back to Redis on the first
+/// successful call afterwards.
+pub struct LayeredRevocations<P, F> {
+    primary: P,
+    fallback: Option<F>,
+    recent: RecentRevocations,
+    max_outage: Option<Duration>,
+    outage_started: Mutex<Option<Instant>>,
+    needs_sync: AtomicBool,
+}
+
+impl<P: RevocationStore, F: FallbackRevocationStore> LayeredRevocations<P, F> {
+    pub fn new(primary: P, fallback: Option<F>, config: &RevocationConfig) -> Self {
+        Self {
+            // Another instance may have left entries behind before a restart.
+            needs_sync: AtomicBool::new(fallback.is_some()),
+            primary,
+            fallback,
+            recent: RecentRevocations::new(config.memory_entries),
+            max_outage: config.fallback_max_outage,
+            outage_started: Mutex::new(None),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    #[cfg(test)]
+    pub fn fallback(&self) -> Option<&F> {
+        self.fallback.as_ref()
+    }
+
+    /// Records the failure and tells whether the fallback may still answer.
+    fn within_outage_window(&self) -> bool {
+        let Some(max_outage) = self.max_outage else {
+            return false;
+        };
+
+        let mut started = self.outage_started.lock().unwrap();
+        let started = *started.get_or_insert_with(Instant::now);
+        started.elapsed() <= max_outage
+    }
+
+    /// Ends the outage and copies pending fallback entries back to the
+    /// primary. Returns the restored ids, which the primary did not know
+    /// about when it answered the current call.
+    async fn primary_recovered(&self) -> Vec<String> {
+        *self.outage_started.lock().unwrap() = None;
+
+        let Some(fallback) = &self.fallback else {
+            return Vec::new();
+        };
+        if !self.needs_sync.swap(false, Ordering::AcqRel) {
+            return Vec::new();
+        }
+
+        let entries = match fallback.drain().await {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Failed to read fallback revocations: {}", e);
+                self.needs_sync.store(true, Ordering::Release);
+                return Vec::new();
+            }
+        };
+
+        let mut restored = Vec::with_capacity(entries.len());
+        for (jti, exp) in entries {
+            if let Err(e) = self.primary.revoke(&jti, exp).await {
+                tracing::warn!("Failed to restore fallback revocation: {}", e);
+                self.needs_sync.store(true, Ordering::Release);
+                if let Err(e) = fallback.revoke(&jti, exp).await {
+                    tracing::error!(jti, "Dropped fallback revocation: {}", e);
+                }
+            } else {
+                track_revocation_fallback("sync", "postgres");
+            }
+            restored.push(jti);
+        }
+        restored
+    }
+}
+
+impl<P: RevocationStore, F: FallbackRevocationStore> RevocationStore for LayeredRevocations<P, F> {
+    async fn revoke(&self, jti: &str, exp: i64) -> Result<(), AppError> {
+        self.recent.insert(jti, exp);
+
+        let error = match self.primary.revoke(jti, exp).await {
+            Ok(()) => {
+                self.primary_recovered().await;
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        let Some(fallback) = self
+            .fallback
+            .as_ref()
+            .filter(|_| self.within_outage_window())
+        else {
+            return Err(error);
+        };
+
+        tracing::warn!("Recording revocation in the fallback store: {}", error);
+        fallback.revoke(jti, exp).await.map_err(|e| {
+            tracing::error!("Fallback revocation store failed: {}", e);
+            error
+        })?;
+        self.needs_sync.store(true, Ordering::Release);
+        track_revocation_fallback("revoke", "postgres");
+        Ok(())
+    }
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        let now = Utc::now().timestamp();
+
+        let error = match self.primary.is_revoked(jti).await {
+            Ok(revoked) => {
+                let restored = self.primary_recovered().await;
+                return Ok(revoked
+                    || self.recent.contains(jti, now)
+                    || restored.iter().any(|r| r == jti));
+            }
+            Err(e) => e,
+        };
+
+        let Some(fallback) = self
+            .fallback
+            .as_ref()
+            .filter(|_| self.within_outage_window())
+        else {
+            return Err(error);
+        };
+
+        if self.recent.contains(jti, now) {
+            track_revocation_fallback("check", "memory");
+            return Ok(true);
+        }
+
+        let revoked = fallback.is_revoked(jti).await.map_err(|e| {
+            tracing::error!("Fallback revocation store failed: {}", e);
+            error
+        })?;
+        track_revocation_fallback("check", "postgres");
+        Ok(revoked)
+    }
+}
@@ -0,0 +1,38 @@
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use jsonwebtoken::{Algorithm, EncodingKey, crypto};
+
+use crate::{
+    app::AppError,
+    auth::jwt::traits::{SignFuture, TokenSigner},
+};
+
+/// A private key held in process memory, from PEM or rotation.
+pub struct LocalSigner {
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+impl LocalSigner {
+    pub fn new(algorithm: Algorithm, key: EncodingKey) -> Self {
+        Self { algorithm, key }
+    }
+
+    fn sign_now(&self, message: &[u8]) -> Result<Vec<u8>, AppError> {
+        let signature = crypto::sign(message, &self.key, self.algorithm)
+            .map_err(|e| AppError::InternalServer(format!("Failed to sign token: {}", e)))?;
+        BASE64_URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|e| AppError::InternalServer(format!("Malformed signature: {}", e)))
+    }
+}
+
+impl TokenSigner for LocalSigner {
+    fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a> {
+        let signature = self.sign_now(message);
+        Box::pin(async move { signature })
+    }
+}
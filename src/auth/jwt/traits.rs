@@ -1,24 +1,99 @@
+use std::{future::Future, pin::Pin};
+
+use jsonwebtoken::Algorithm;
 use uuid::Uuid;
 
 use crate::{
     app::AppError,
     auth::{
         dto::ServiceHealth,
-        jwt::{AccessTokenClaims, RefreshTokenClaims, TokenPair},
+        jwt::{AccessTokenClaims, RefreshTokenClaims, RotatedPair, TokenPair},
+        model::{Grants, SessionDevice},
     },
 };
 
 pub trait JwtService: Send + Sync {
     fn check_redis(&self) -> impl Future<Output = ServiceHealth> + Send;
-    fn generate_token_pair(&self, user_id: Uuid, username: &str, role: Option<&str>) -> TokenPair;
+    fn generate_token_pair(
+        &self,
+        user_id: Uuid,
+        username: &str,
+        grants: Grants,
+        device: &SessionDevice,
+    ) -> impl Future<Output = Result<TokenPair, AppError>> + Send;
+    /// An access token alone, for machine clients that have no session to
+    /// refresh, with the claims it carries.
+    fn issue_access_token(
+        &self,
+        subject: Uuid,
+        name: &str,
+        grants: Grants,
+    ) -> impl Future<Output = Result<(String, AccessTokenClaims), AppError>> + Send;
     fn validate_refresh(
         &self,
         token: &str,
     ) -> impl Future<Output = Result<RefreshTokenClaims, AppError>> + Send;
+    /// With `expected_azp`, also refuses tokens issued to any other client
+    /// application.
     fn validate_access(
         &self,
         token: &str,
+        expected_azp: Option<&str>,
     ) -> impl Future<Output = Result<AccessTokenClaims, AppError>> + Send;
+    /// The claims of `token` while it is valid, unexpired and its subject
+    /// not revoked; `None` otherwise, without saying which.
+    fn introspect_access(
+        &self,
+        token: &str,
+    ) -> impl Future<Output = Result<Option<AccessTokenClaims>, AppError>> + Send;
     fn blacklist(&self, jti: &str, exp: i64) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Keeps what rotating out `previous` returned for the refresh grace
+    /// window. A no-op with the window off.
+    fn remember_rotation(
+        &self,
+        previous: &str,
+        pair: &RotatedPair,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Takes what rotating out `previous` returned, at most once, while the
+    /// grace window lasts and the new refresh token is still valid.
+    fn take_rotation(
+        &self,
+        previous: &str,
+    ) -> impl Future<Output = Result<Option<RotatedPair>, AppError>> + Send;
+    /// Revokes every refresh token issued to the user so far, and deletes
+    /// their opaque access tokens. Signed access tokens are not tracked and
+    /// stay valid until they expire.
+    fn revoke_subject(&self, user_id: Uuid) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Replaces the refresh token secret, invalidating every refresh cookie
+    /// issued so far.
+    fn rotate_refresh_secret(&self) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Starts signing access tokens with a freshly generated key and returns
+    /// its kid. Older keys keep verifying until their tokens have expired.
+    fn rotate_signing_key(&self) -> impl Future<Output = Result<String, AppError>> + Send;
     fn is_blacklisted(&self, jti: &str) -> impl Future<Output = Result<bool, AppError>> + Send;
 }
+
+/// Where revoked refresh token ids live until the token would expire anyway.
+pub trait RevocationStore: Send + Sync {
+    /// Records `jti` as revoked; `exp` is the token's own expiry.
+    fn revoke(&self, jti: &str, exp: i64) -> impl Future<Output = Result<(), AppError>> + Send;
+    fn is_revoked(&self, jti: &str) -> impl Future<Output = Result<bool, AppError>> + Send;
+}
+
+/// A store that only holds revocations while the primary is down, and hands
+/// them back once it recovers.
+pub trait FallbackRevocationStore: RevocationStore {
+    /// Removes and returns every unexpired revocation as `(jti, exp)`.
+    fn drain(&self) -> impl Future<Output = Result<Vec<(String, i64)>, AppError>> + Send;
+}
+
+pub type SignFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>, AppError>> + Send + 'a>>;
+
+/// Signs access tokens. The key may live outside the process, in a KMS, so
+/// the trait is object safe and signing is asynchronous.
+pub trait TokenSigner: Send + Sync {
+    fn algorithm(&self) -> Algorithm;
+    /// The raw JWS signature of `message`: 64 bytes for both EdDSA and ES256,
+    /// the latter as `r || s`.
+    fn sign<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a>;
+}
@@ -0,0 +1,461 @@
+use std::{collections::BTreeSet, fmt::Debug, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use webauthn_rs::prelude::{AuthenticationResult, Passkey};
+
+use crate::{
+    app::{AppError, context::current_tenant, middleware::metrics::track_login_cache},
+    auth::{
+        attestation::AaguidPolicy,
+        dto::ServiceHealth,
+        model::{Grants, MigrationStatus, RecoveryState, StoredCredential, User, WebAuthnSession},
+        queries,
+        traits::{AuthRepository, LoginStore, LoginStoreFuture},
+    },
+    config::CircuitBreaker,
+    credential_revocation::{
+        model::{AaguidMatches, RevokedCredential},
+        traits::CredentialRevocationRepository,
+    },
+    duplicates::{
+        model::{MergePlan, StoredIdentity},
+        traits::DuplicateRepository,
+    },
+    redis_delete, redis_get, redis_set,
+    suspensions::{model::AccountState, traits::SuspensionRepository},
+    utils::{BaseRedisRepository, normalize_username},
+};
+
+/// What `get_active_user_with_credential` returned, locked passkeys already
+/// left out.
+#[derive(Serialize, Deserialize)]
+struct CachedLogin {
+    user: User,
+    passkeys: Vec<Passkey>,
+}
+
+/// The Redis `LoginStore`. The TTL bounds an invalidation that Redis
+/// missed. Like the account throttle, it fails open: with Redis down,
+/// logins read the database.
+pub struct LoginCache {
+    base: BaseRedisRepository,
+    ttl: Duration,
+}
+
+impl LoginCache {
+    pub fn new(
+        conn_manager: ConnectionManager,
+        circuit_breaker: Arc<CircuitBreaker>,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            base: BaseRedisRepository::new(conn_manager, circuit_breaker),
+            ttl,
+        }
+    }
+
+    async fn load(&self, username: &str) -> Option<(User, Vec<Passkey>)> {
+        let key = queries::login_cache::key(&current_tenant(), &normalize_username(username));
+
+        let value = self
+            .base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                use redis::AsyncCommands;
+                let value: Option<String> = redis_get!({ conn.get(&key).await })?;
+                Ok(value)
+            })
+            .await;
+
+        let cached = match value {
+            Ok(Some(value)) => serde_json::from_str::<CachedLogin>(&value)
+                .inspect_err(|e| tracing::warn!("Discarding malformed login cache entry: {}", e))
+                .ok(),
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!(error = %e, "Login cache read skipped");
+                None
+            }
+        };
+        cached.map(|cached| (cached.user, cached.passkeys))
+    }
+
+    async fn delete(&self, username: &str) {
+        let key = queries::login_cache::key(&current_tenant(), &normalize_username(username));
+
+        let deleted = self
+            .base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let () =
+                    redis_delete!({ redis::cmd("DEL").arg(&key).query_async(&mut conn).await })?;
+                Ok(())
+            })
+            .await;
+        match deleted {
+            Ok(()) => track_login_cache("invalidated"),
+            Err(e) => tracing::error!("Failed to invalidate the login of {}: {}", username, e),
+        }
+    }
+
+    async fn store(
+        &self,
+        username: &str,
+        user: &User,
+        passkeys: &[Passkey],
+    ) -> Result<(), AppError> {
+        let key = queries::login_cache::key(&current_tenant(), &normalize_username(username));
+        let value = serde_json::to_string(&CachedLogin {
+            user: user.clone(),
+            passkeys: passkeys.to_vec(),
+        })?;
+        let ttl_secs = self.ttl.as_secs();
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                use redis::AsyncCommands;
+                let () = redis_set!({ conn.set_ex(&key, value, ttl_secs).await })?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+impl LoginStore for LoginCache {
+    fn get<'a>(&'a self, username: &'a str) -> LoginStoreFuture<'a, Option<(User, Vec<Passkey>)>> {
+        Box::pin(self.load(username))
+    }
+
+    fn put<'a>(
+        &'a self,
+        username: &'a str,
+        user: &'a User,
+        passkeys: &'a [Passkey],
+    ) -> LoginStoreFuture<'a> {
+        Box::pin(async move {
+            if let Err(e) = self.store(username, user, passkeys).await {
+                tracing::warn!("Failed to cache the login of {}: {}", username, e);
+            }
+        })
+    }
+
+    fn invalidate<'a>(&'a self, username: &'a str) -> LoginStoreFuture<'a> {
+        Box::pin(self.delete(username))
+    }
+}
+
+/// Wraps the repositories that read or change what a login begins with, so
+/// `begin_login` reads through the cache and every write that could make an
+/// entry stale drops it. Only lookups that found the user are cached, so a
+/// new registration is seen at once. Without a store it only delegates.
+pub struct CachedRepository<R> {
+    inner: R,
+    cache: Option<Arc<dyn LoginStore>>,
+}
+
+impl<R> CachedRepository<R> {
+    pub fn new(inner: R, cache: Option<Arc<dyn LoginStore>>) -> Self {
+        Self { inner, cache }
+    }
+
+    async fn invalidate(&self, username: &str) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(username).await;
+        }
+    }
+}
+
+impl<R: AuthRepository> CachedRepository<R> {
+    /// The cache is keyed on usernames, which writes by user id look up
+    /// first. Those writes are rare, unlike the logins the cache serves.
+    async fn cached_username(&self, user_id: Uuid) -> Option<String> {
+        self.cache.as_ref()?;
+        match self.inner.get_user_by_id(user_id).await {
+            Ok(user) => Some(user.username),
+            Err(e) => {
+                tracing::error!("Failed to invalidate the login of {}: {}", user_id, e);
+                None
+            }
+        }
+    }
+
+    async fn invalidate_user(&self, user_id: Uuid) {
+        if let Some(username) = self.cached_username(user_id).await {
+            self.invalidate(&username).await;
+        }
+    }
+}
+
+impl<R: AuthRepository> AuthRepository for CachedRepository<R> {
+    async fn check_db(&self) -> ServiceHealth {
+        self.inner.check_db().await
+    }
+
+    async fn check_migrations(&self) -> Result<MigrationStatus, AppError> {
+        self.inner.check_migrations().await
+    }
+
+    async fn warm_up(&self, connections: usize) -> Result<usize, AppError> {
+        self.inner.warm_up(connections).await
+    }
+
+    async fn register_tenants(&self, tenants: &[Box<str>]) -> Result<(), AppError> {
+        self.inner.register_tenants(tenants).await
+    }
+
+    async fn create_user(&self, username: &str, role: Option<&str>) -> Result<User, AppError> {
+        self.inner.create_user(username, role).await
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<User, AppError> {
+        self.inner.get_user_by_username(username).await
+    }
+
+    async fn get_user_by_id(&self, user_id: Uuid) -> Result<User, AppError> {
+        self.inner.get_user_by_id(user_id).await
+    }
+
+    async fn get_grants(&self, user_id: Uuid) -> Result<Grants, AppError> {
+        self.inner.get_grants(user_id).await
+    }
+
+    async fn get_aaguid_policy(&self, user_id: Uuid) -> Result<AaguidPolicy, AppError> {
+        self.inner.get_aaguid_policy(user_id).await
+    }
+
+    async fn get_user_and_session(
+        &self,
+        session_id: Uuid,
+        username: &str,
+        purpose: &str,
+    ) -> Result<(User, WebAuthnSession), AppError> {
+        self.inner
+            .get_user_and_session(session_id, username, purpose)
+            .await
+    }
+
+    async fn get_active_user_with_credential(
+        &self,
+        username: &str,
+    ) -> Result<(User, Vec<Passkey>), AppError> {
+        let Some(cache) = &self.cache else {
+            return self.inner.get_active_user_with_credential(username).await;
+        };
+        let cached = cache.get(username).await;
+        track_login_cache(if cached.is_some() { "hit" } else { "miss" });
+        if let Some(cached) = cached {
+            return Ok(cached);
+        }
+
+        let (user, passkeys) = self.inner.get_active_user_with_credential(username).await?;
+        cache.put(username, &user, &passkeys).await;
+        Ok((user, passkeys))
+    }
+
+    async fn create_webauthn_session<S: Serialize + Debug + Sync>(
+        &self,
+        user_id: Uuid,
+        state: &S,
+        purpose: &str,
+    ) -> Result<Uuid, AppError> {
+        self.inner
+            .create_webauthn_session(user_id, state, purpose)
+            .await
+    }
+
+    async fn delete_webauthn_session(&self, id: Uuid) -> Result<(), AppError> {
+        self.inner.delete_webauthn_session(id).await
+    }
+
+    /// Drops the entry rather than patching it: two logins finishing at
+    /// once would each write back their own copy, and the older counter
+    /// could win. The next login reads the new counter from the database.
+    async fn update_credential(
+        &self,
+        username: &str,
+        result: &AuthenticationResult,
+    ) -> Result<(), AppError> {
+        self.inner.update_credential(username, result).await?;
+        self.invalidate(username).await;
+        Ok(())
+    }
+
+    async fn lock_cloned_credential(
+        &self,
+        user_id: Uuid,
+        cred_id: &[u8],
+    ) -> Result<bool, AppError> {
+        let locked = self.inner.lock_cloned_credential(user_id, cred_id).await?;
+        if locked {
+            self.invalidate_user(user_id).await;
+        }
+        Ok(locked)
+    }
+
+    async fn unlock_credential(&self, user_id: Uuid, cred_id: &[u8]) -> Result<(), AppError> {
+        self.inner.unlock_credential(user_id, cred_id).await?;
+        self.invalidate_user(user_id).await;
+        Ok(())
+    }
+
+    async fn list_credentials(&self, user_id: Uuid) -> Result<Vec<StoredCredential>, AppError> {
+        self.inner.list_credentials(user_id).await
+    }
+
+    async fn complete_registration(
+        &self,
+        user_id: Uuid,
+        username: &str,
+        passkey: &Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+        activate: bool,
+    ) -> Result<(), AppError> {
+        self.inner
+            .complete_registration(
+                user_id,
+                username,
+                passkey,
+                aaguid,
+                recovery_code_hashes,
+                activate,
+            )
+            .await?;
+        self.invalidate(username).await;
+        Ok(())
+    }
+
+    async fn activate_pending_user(&self, username: &str) -> Result<(), AppError> {
+        self.inner.activate_pending_user(username).await?;
+        self.invalidate(username).await;
+        Ok(())
+    }
+
+    async fn get_recovery_state(&self, username: &str) -> Result<RecoveryState, AppError> {
+        self.inner.get_recovery_state(username).await
+    }
+
+    async fn consume_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &[u8],
+    ) -> Result<bool, AppError> {
+        self.inner.consume_recovery_code(user_id, code_hash).await
+    }
+
+    async fn record_recovery_failure(
+        &self,
+        user_id: Uuid,
+        max_attempts: i32,
+        lock_until: DateTime<Utc>,
+    ) -> Result<bool, AppError> {
+        self.inner
+            .record_recovery_failure(user_id, max_attempts, lock_until)
+            .await
+    }
+
+    async fn reset_recovery_failures(&self, user_id: Uuid) -> Result<(), AppError> {
+        self.inner.reset_recovery_failures(user_id).await
+    }
+
+    async fn complete_recovery(
+        &self,
+        user_id: Uuid,
+        passkey: &Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+    ) -> Result<(), AppError> {
+        self.inner
+            .complete_recovery(user_id, passkey, aaguid, recovery_code_hashes)
+            .await?;
+        self.invalidate_user(user_id).await;
+        Ok(())
+    }
+
+    /// The username is looked up first: a deleted user is not found.
+    async fn delete_account(&self, user_id: Uuid) -> Result<(), AppError> {
+        let username = self.cached_username(user_id).await;
+        self.inner.delete_account(user_id).await?;
+        if let Some(username) = username {
+            self.invalidate(&username).await;
+        }
+        Ok(())
+    }
+}
+
+impl<R: SuspensionRepository> SuspensionRepository for CachedRepository<R> {
+    async fn find(&self, user_id: Uuid) -> Result<Option<AccountState>, AppError> {
+        self.inner.find(user_id).await
+    }
+
+    async fn suspend(
+        &self,
+        user_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<Option<AccountState>, AppError> {
+        let updated = self.inner.suspend(user_id, reason).await?;
+        if let Some(updated) = &updated {
+            self.invalidate(&updated.username).await;
+        }
+        Ok(updated)
+    }
+
+    async fn reinstate(&self, user_id: Uuid) -> Result<Option<AccountState>, AppError> {
+        let updated = self.inner.reinstate(user_id).await?;
+        if let Some(updated) = &updated {
+            self.invalidate(&updated.username).await;
+        }
+        Ok(updated)
+    }
+}
+
+impl<R: CredentialRevocationRepository> CredentialRevocationRepository for CachedRepository<R> {
+    async fn count_by_aaguid(&self, aaguid: Uuid) -> Result<AaguidMatches, AppError> {
+        self.inner.count_by_aaguid(aaguid).await
+    }
+
+    async fn delete_batch_by_aaguid(
+        &self,
+        aaguid: Uuid,
+        batch_size: i64,
+    ) -> Result<Vec<RevokedCredential>, AppError> {
+        let batch = self
+            .inner
+            .delete_batch_by_aaguid(aaguid, batch_size)
+            .await?;
+        let usernames: BTreeSet<&str> = batch
+            .iter()
+            .map(|revoked| revoked.username.as_str())
+            .collect();
+        for username in usernames {
+            self.invalidate(username).await;
+        }
+        Ok(batch)
+    }
+
+    async fn users_without_credentials(&self, user_ids: &[Uuid]) -> Result<Vec<Uuid>, AppError> {
+        self.inner.users_without_credentials(user_ids).await
+    }
+}
+
+impl<R: DuplicateRepository> DuplicateRepository for CachedRepository<R> {
+    async fn active_users(&self) -> Result<Vec<StoredIdentity>, AppError> {
+        self.inner.active_users().await
+    }
+
+    async fn users_by_id(&self, ids: &[Uuid]) -> Result<Vec<StoredIdentity>, AppError> {
+        self.inner.users_by_id(ids).await
+    }
+
+    async fn canonical_holder(&self, canonical: &str) -> Result<Option<Uuid>, AppError> {
+        self.inner.canonical_holder(canonical).await
+    }
+
+    async fn merge(&self, plan: &MergePlan) -> Result<u64, AppError> {
+        let moved = self.inner.merge(plan).await?;
+        // Every merged account shares the canonical form, and so the key.
+        self.invalidate(&plan.canonical).await;
+        Ok(moved)
+    }
+}
@@ -0,0 +1,633 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Debug,
+    sync::{Mutex, MutexGuard},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+use webauthn_rs::prelude::{AuthenticationResult, Passkey};
+
+use crate::{
+    app::{AppError, ErrorCode, context::current_tenant},
+    auth::{
+        attestation::AaguidPolicy,
+        dto::{HealthStatus, ServiceHealth},
+        model::{
+            Grants, MigrationStatus, RecoveryState, StoredCredential, User, UserStatus,
+            WebAuthnSession, account_suspended,
+        },
+        passkey_format::{
+            apply_authentication, credential_locked, reset_counter, unlocked_passkeys,
+        },
+        traits::AuthRepository,
+    },
+    config::tenant::DEFAULT_TENANT,
+    utils::normalize_username,
+};
+
+/// Permissions of the `admin` role as seeded by the migrations.
+const ADMIN_PERMISSIONS: [&str; 5] = [
+    "admin:actions",
+    "audit:read",
+    "banner:write",
+    "enrollment:read",
+    "traffic:read",
+];
+
+/// `AuthRepository` held in process memory, enabled by the `memory-store`
+/// feature for local development and tests without Postgres. It mirrors the
+/// SQL backends, constraints and tenancy included, but keeps nothing across
+/// restarts.
+pub struct MemoryRepository {
+    store: Mutex<Store>,
+}
+
+#[derive(Default)]
+struct Store {
+    tenants: BTreeSet<String>,
+    users: HashMap<Uuid, StoredUser>,
+    /// Role name to the permissions it grants.
+    roles: BTreeMap<String, BTreeSet<String>>,
+    role_aaguids: Vec<(String, Uuid)>,
+    user_roles: HashMap<Uuid, BTreeSet<String>>,
+    credentials: Vec<StoredPasskey>,
+    recovery_codes: Vec<StoredRecoveryCode>,
+    /// Keyed by id, with the tenant of the ceremony.
+    sessions: HashMap<Uuid, (String, WebAuthnSession)>,
+}
+
+struct StoredUser {
+    user: User,
+    tenant: String,
+    normalized_username: String,
+    recovery_failed_attempts: i32,
+    recovery_locked_until: Option<DateTime<Utc>>,
+}
+
+struct StoredPasskey {
+    id: Vec<u8>,
+    user_id: Uuid,
+    tenant: String,
+    /// As JSON, as stored in `credentials.passkey`.
+    passkey: serde_json::Value,
+    aaguid: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+    clone_suspected_at: Option<DateTime<Utc>>,
+}
+
+struct StoredRecoveryCode {
+    user_id: Uuid,
+    code_hash: Vec<u8>,
+    used: bool,
+}
+
+impl Default for MemoryRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryRepository {
+    /// Starts empty apart from the `admin` role and the default tenant.
+    pub fn new() -> Self {
+        let mut store = Store::default();
+        store.tenants.insert(DEFAULT_TENANT.to_owned());
+        store.roles.insert(
+            String::from("admin"),
+            ADMIN_PERMISSIONS.iter().map(|p| p.to_string()).collect(),
+        );
+
+        Self {
+            store: Mutex::new(store),
+        }
+    }
+
+    /// Adds a role, like a row in `roles` with its `role_permissions`.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_role(self, name: &str, permissions: &[&str]) -> Self {
+        self.lock().roles.insert(
+            name.to_owned(),
+            permissions.iter().map(|p| p.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Restricts `role` to the given authenticator models, like rows in
+    /// `role_aaguids`.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_aaguids(self, role: &str, aaguids: &[Uuid]) -> Self {
+        self.lock()
+            .role_aaguids
+            .extend(aaguids.iter().map(|aaguid| (role.to_owned(), *aaguid)));
+        self
+    }
+
+    /// A panic while holding the lock leaves nothing half-written that a
+    /// later call could trip over, so poisoning is ignored.
+    fn lock(&self) -> MutexGuard<'_, Store> {
+        self.store
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Store {
+    /// Only users of the current tenant exist for a lookup.
+    fn user(&self, user_id: Uuid) -> Option<&StoredUser> {
+        let tenant = current_tenant();
+        self.users
+            .get(&user_id)
+            .filter(|stored| stored.tenant == tenant)
+    }
+
+    fn user_mut(&mut self, user_id: Uuid) -> Option<&mut StoredUser> {
+        let tenant = current_tenant();
+        self.users
+            .get_mut(&user_id)
+            .filter(|stored| stored.tenant == tenant)
+    }
+
+    fn user_by_username(&self, username: &str) -> Option<&StoredUser> {
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+        self.users
+            .values()
+            .find(|stored| stored.tenant == tenant && stored.normalized_username == normalized)
+    }
+
+    fn user_by_username_mut(&mut self, username: &str) -> Option<&mut StoredUser> {
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+        self.users
+            .values_mut()
+            .find(|stored| stored.tenant == tenant && stored.normalized_username == normalized)
+    }
+
+    fn activate(&mut self, username: &str) {
+        if let Some(stored) = self.user_by_username_mut(username)
+            && stored.user.status == UserStatus::Pending.as_str()
+        {
+            stored.user.status = String::from("active");
+            stored.user.updated_at = Utc::now();
+        }
+    }
+
+    fn create_credential(
+        &mut self,
+        user_id: Uuid,
+        passkey: &Passkey,
+        aaguid: Option<Uuid>,
+    ) -> Result<(), AppError> {
+        let cred_id = passkey.cred_id().as_slice().to_vec();
+        if self.credentials.iter().any(|stored| stored.id == cred_id) {
+            return Err(AppError::AlreadyExists(String::from(
+                "Credential already exists",
+            )));
+        }
+
+        self.credentials.push(StoredPasskey {
+            id: cred_id,
+            user_id,
+            tenant: current_tenant(),
+            passkey: serde_json::to_value(passkey)?,
+            aaguid,
+            created_at: Utc::now(),
+            last_used_at: None,
+            clone_suspected_at: None,
+        });
+        Ok(())
+    }
+
+    fn replace_recovery_codes(&mut self, user_id: Uuid, code_hashes: &[Vec<u8>]) {
+        self.recovery_codes.retain(|code| code.user_id != user_id);
+        self.recovery_codes
+            .extend(code_hashes.iter().map(|code_hash| StoredRecoveryCode {
+                user_id,
+                code_hash: code_hash.clone(),
+                used: false,
+            }));
+    }
+}
+
+impl AuthRepository for MemoryRepository {
+    async fn check_db(&self) -> ServiceHealth {
+        ServiceHealth {
+            status: HealthStatus::Healthy,
+            message: String::from("In-memory store, nothing to connect to"),
+            response_time_ms: Some(0),
+        }
+    }
+
+    /// There is no schema to migrate.
+    async fn check_migrations(&self) -> Result<MigrationStatus, AppError> {
+        Ok(MigrationStatus::from_missing_tables(&[]))
+    }
+
+    async fn warm_up(&self, _connections: usize) -> Result<usize, AppError> {
+        Ok(0)
+    }
+
+    async fn register_tenants(&self, tenants: &[Box<str>]) -> Result<(), AppError> {
+        self.lock()
+            .tenants
+            .extend(tenants.iter().map(|tenant| tenant.to_string()));
+        Ok(())
+    }
+
+    async fn create_user(&self, username: &str, role: Option<&str>) -> Result<User, AppError> {
+        let mut store = self.lock();
+
+        if let Some(existing) = store.user_by_username(username) {
+            let holds_roles = store
+                .user_roles
+                .get(&existing.user.id)
+                .is_some_and(|roles| !roles.is_empty());
+            if existing.user.status != UserStatus::Pending.as_str() || role.is_some() || holds_roles
+            {
+                return Err(
+                    AppError::AlreadyExists(String::from("Username already exists"))
+                        .with_code(ErrorCode::UsernameTaken),
+                );
+            }
+            return Ok(existing.user.clone());
+        }
+
+        if let Some(role) = role
+            && !store.roles.contains_key(role)
+        {
+            return Err(AppError::BadRequest(format!("Unknown role: {}", role)));
+        }
+
+        let now = Utc::now();
+        let user = User {
+            id: Uuid::new_v4(),
+            username: username.to_owned(),
+            status: String::from("pending"),
+            created_at: now,
+            updated_at: now,
+            is_active: true,
+        };
+        store.users.insert(
+            user.id,
+            StoredUser {
+                user: user.clone(),
+                tenant: current_tenant(),
+                normalized_username: normalize_username(username),
+                recovery_failed_attempts: 0,
+                recovery_locked_until: None,
+            },
+        );
+        if let Some(role) = role {
+            store
+                .user_roles
+                .entry(user.id)
+                .or_default()
+                .insert(role.to_owned());
+        }
+
+        Ok(user)
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<User, AppError> {
+        self.lock()
+            .user_by_username(username)
+            .map(|stored| stored.user.clone())
+            .ok_or_else(|| AppError::NotFound("Username not found".to_string()))
+    }
+
+    async fn get_user_by_id(&self, user_id: Uuid) -> Result<User, AppError> {
+        self.lock()
+            .user(user_id)
+            .filter(|stored| stored.user.is_active)
+            .map(|stored| stored.user.clone())
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+    }
+
+    async fn get_grants(&self, user_id: Uuid) -> Result<Grants, AppError> {
+        let store = self.lock();
+        match store.user(user_id) {
+            None => return Err(AppError::NotFound("User not found".to_string())),
+            Some(stored) if stored.user.is_suspended() => return Err(account_suspended()),
+            Some(_) => {}
+        }
+        let roles = store.user_roles.get(&user_id).cloned().unwrap_or_default();
+        let permissions: BTreeSet<String> = roles
+            .iter()
+            .filter_map(|role| store.roles.get(role))
+            .flatten()
+            .cloned()
+            .collect();
+
+        Ok(Grants {
+            roles: roles.into_iter().collect(),
+            permissions: permissions.into_iter().collect(),
+        })
+    }
+
+    async fn get_aaguid_policy(&self, user_id: Uuid) -> Result<AaguidPolicy, AppError> {
+        let store = self.lock();
+        let Some(roles) = store
+            .user(user_id)
+            .and_then(|_| store.user_roles.get(&user_id))
+        else {
+            return Ok(AaguidPolicy::default());
+        };
+
+        Ok(store
+            .role_aaguids
+            .iter()
+            .filter(|(role, _)| roles.contains(role))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_user_and_session(
+        &self,
+        session_id: Uuid,
+        username: &str,
+        purpose: &str,
+    ) -> Result<(User, WebAuthnSession), AppError> {
+        let store = self.lock();
+        let normalized = normalize_username(username);
+
+        store
+            .sessions
+            .get(&session_id)
+            .filter(|(_, session)| session.purpose == purpose)
+            .and_then(|(_, session)| {
+                let stored = store.user(session.user_id)?;
+                (stored.normalized_username == normalized)
+                    .then(|| (stored.user.clone(), session.clone()))
+            })
+            .ok_or_else(|| AppError::NotFound("User or session not found".to_string()))
+    }
+
+    async fn get_active_user_with_credential(
+        &self,
+        username: &str,
+    ) -> Result<(User, Vec<Passkey>), AppError> {
+        let store = self.lock();
+        let not_found = || AppError::NotFound("User or credentials not found".to_string());
+
+        let user = store
+            .user_by_username(username)
+            .filter(|stored| stored.user.status == "active" || stored.user.is_suspended())
+            .map(|stored| stored.user.clone())
+            .ok_or_else(not_found)?;
+        if user.is_suspended() {
+            return Err(account_suspended());
+        }
+
+        let passkeys = unlocked_passkeys(
+            store
+                .credentials
+                .iter()
+                .filter(|stored| stored.user_id == user.id)
+                .map(|stored| (stored.passkey.clone(), stored.clone_suspected_at)),
+        )?;
+
+        if passkeys.is_empty() {
+            return Err(not_found());
+        }
+        Ok((user, passkeys))
+    }
+
+    async fn create_webauthn_session<S: Serialize + Debug + Sync>(
+        &self,
+        user_id: Uuid,
+        state: &S,
+        purpose: &str,
+    ) -> Result<Uuid, AppError> {
+        let now = Utc::now();
+        let session = WebAuthnSession {
+            id: Uuid::new_v4(),
+            user_id,
+            data: serde_json::to_value(state)?,
+            purpose: purpose.to_owned(),
+            created_at: now,
+            expires_at: now + chrono::Duration::minutes(30),
+        };
+        let id = session.id;
+
+        self.lock().sessions.insert(id, (current_tenant(), session));
+        Ok(id)
+    }
+
+    async fn delete_webauthn_session(&self, id: Uuid) -> Result<(), AppError> {
+        let mut store = self.lock();
+        let tenant = current_tenant();
+        match store.sessions.get(&id) {
+            Some((owner, _)) if *owner == tenant => {
+                store.sessions.remove(&id);
+                Ok(())
+            }
+            _ => Err(AppError::NotFound("Session not found".to_string())),
+        }
+    }
+
+    async fn update_credential(
+        &self,
+        _username: &str,
+        result: &AuthenticationResult,
+    ) -> Result<(), AppError> {
+        let mut store = self.lock();
+        let tenant = current_tenant();
+        let stored = store
+            .credentials
+            .iter_mut()
+            .find(|stored| {
+                stored.tenant == tenant && stored.id.as_slice() == result.cred_id().as_slice()
+            })
+            .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
+        if stored.clone_suspected_at.is_some() {
+            return Err(credential_locked(&stored.id));
+        }
+
+        stored.passkey = apply_authentication(stored.passkey.take(), result)?;
+        stored.last_used_at = Some(Utc::now());
+        Ok(())
+    }
+
+    async fn lock_cloned_credential(
+        &self,
+        user_id: Uuid,
+        cred_id: &[u8],
+    ) -> Result<bool, AppError> {
+        let mut store = self.lock();
+        let tenant = current_tenant();
+        let Some(stored) = store.credentials.iter_mut().find(|stored| {
+            stored.tenant == tenant
+                && stored.id == cred_id
+                && stored.user_id == user_id
+                && stored.clone_suspected_at.is_none()
+        }) else {
+            return Ok(false);
+        };
+
+        stored.clone_suspected_at = Some(Utc::now());
+        Ok(true)
+    }
+
+    async fn unlock_credential(&self, user_id: Uuid, cred_id: &[u8]) -> Result<(), AppError> {
+        let mut store = self.lock();
+        let tenant = current_tenant();
+        let stored = store
+            .credentials
+            .iter_mut()
+            .find(|stored| {
+                stored.tenant == tenant
+                    && stored.id == cred_id
+                    && stored.user_id == user_id
+                    && stored.clone_suspected_at.is_some()
+            })
+            .ok_or_else(|| AppError::NotFound("Locked credential not found".to_string()))?;
+
+        stored.passkey = reset_counter(stored.passkey.take())?;
+        stored.clone_suspected_at = None;
+        Ok(())
+    }
+
+    async fn list_credentials(&self, user_id: Uuid) -> Result<Vec<StoredCredential>, AppError> {
+        let tenant = current_tenant();
+        self.lock()
+            .credentials
+            .iter()
+            .filter(|stored| stored.tenant == tenant && stored.user_id == user_id)
+            .map(|stored| {
+                Ok(StoredCredential {
+                    passkey: serde_json::from_value(stored.passkey.clone())?,
+                    aaguid: stored.aaguid,
+                    created_at: stored.created_at,
+                    last_used_at: stored.last_used_at,
+                    clone_suspected_at: stored.clone_suspected_at,
+                })
+            })
+            .collect()
+    }
+
+    async fn complete_registration(
+        &self,
+        user_id: Uuid,
+        username: &str,
+        passkey: &Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+        activate: bool,
+    ) -> Result<(), AppError> {
+        let mut store = self.lock();
+
+        store.create_credential(user_id, passkey, aaguid)?;
+        if activate {
+            store.activate(username);
+        }
+        store.replace_recovery_codes(user_id, recovery_code_hashes);
+        Ok(())
+    }
+
+    async fn activate_pending_user(&self, username: &str) -> Result<(), AppError> {
+        self.lock().activate(username);
+        Ok(())
+    }
+
+    async fn get_recovery_state(&self, username: &str) -> Result<RecoveryState, AppError> {
+        self.lock()
+            .user_by_username(username)
+            .filter(|stored| stored.user.status == "active")
+            .map(|stored| RecoveryState {
+                user: stored.user.clone(),
+                locked_until: stored.recovery_locked_until,
+            })
+            .ok_or_else(|| AppError::NotFound("Username not found".to_string()))
+    }
+
+    async fn consume_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &[u8],
+    ) -> Result<bool, AppError> {
+        let mut store = self.lock();
+        let code = store
+            .recovery_codes
+            .iter_mut()
+            .find(|code| code.user_id == user_id && code.code_hash == code_hash && !code.used);
+
+        Ok(match code {
+            Some(code) => {
+                code.used = true;
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn record_recovery_failure(
+        &self,
+        user_id: Uuid,
+        max_attempts: i32,
+        lock_until: DateTime<Utc>,
+    ) -> Result<bool, AppError> {
+        let mut store = self.lock();
+        let stored = store
+            .user_mut(user_id)
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if stored.recovery_failed_attempts + 1 >= max_attempts {
+            stored.recovery_failed_attempts = 0;
+            stored.recovery_locked_until = Some(lock_until);
+        } else {
+            stored.recovery_failed_attempts += 1;
+        }
+        Ok(stored.recovery_locked_until == Some(lock_until))
+    }
+
+    async fn reset_recovery_failures(&self, user_id: Uuid) -> Result<(), AppError> {
+        if let Some(stored) = self.lock().user_mut(user_id) {
+            stored.recovery_failed_attempts = 0;
+            stored.recovery_locked_until = None;
+        }
+        Ok(())
+    }
+
+    async fn complete_recovery(
+        &self,
+        user_id: Uuid,
+        passkey: &Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+    ) -> Result<(), AppError> {
+        let mut store = self.lock();
+        let tenant = current_tenant();
+
+        store
+            .credentials
+            .retain(|stored| stored.tenant != tenant || stored.user_id != user_id);
+        store.create_credential(user_id, passkey, aaguid)?;
+        store.replace_recovery_codes(user_id, recovery_code_hashes);
+        Ok(())
+    }
+
+    async fn delete_account(&self, user_id: Uuid) -> Result<(), AppError> {
+        let mut store = self.lock();
+
+        let stored = store
+            .user_mut(user_id)
+            .filter(|stored| stored.user.is_active)
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        stored.user.is_active = false;
+        stored.user.status = String::from("deleted");
+        stored.user.username = format!("deleted-{}", user_id);
+        stored.normalized_username = stored.user.username.clone();
+        stored.user.updated_at = Utc::now();
+
+        store.credentials.retain(|stored| stored.user_id != user_id);
+        store.recovery_codes.retain(|code| code.user_id != user_id);
+        store
+            .sessions
+            .retain(|_, (_, session)| session.user_id != user_id);
+        store.user_roles.remove(&user_id);
+        Ok(())
+    }
+}
@@ -1,10 +1,38 @@
+pub(crate) mod attestation;
+pub(crate) mod ceremony;
+pub(crate) mod ceremony_limit;
 pub(crate) mod dto;
+pub(crate) mod extensions;
 pub(crate) mod handler;
 pub(crate) mod jwt;
+pub(crate) mod login_cache;
+#[cfg(any(test, feature = "memory-store"))]
+pub(crate) mod memory_repo;
 pub(crate) mod model;
+pub(crate) mod passkey_format;
+pub(crate) mod permissions;
 mod queries;
+pub(crate) mod recovery;
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
 pub(crate) mod repo;
 pub(crate) mod service;
+#[cfg(feature = "sqlx")]
+pub(crate) mod sqlx_repo;
+pub(crate) mod throttle;
 pub(crate) mod traits;
+pub(crate) mod verification;
 
+#[cfg(feature = "memory-store")]
+pub(crate) use memory_repo::MemoryRepository;
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
 pub(crate) use repo::Repository;
+#[cfg(feature = "sqlx")]
+pub(crate) use sqlx_repo::SqlxRepository;
+
+#[cfg(all(feature = "sqlx", feature = "memory-store"))]
+compile_error!("the `sqlx` and `memory-store` features select different auth backends; enable one");
+#[cfg(all(feature = "sqlx", feature = "fips"))]
+compile_error!("sqlx connects over ring-based TLS, which is outside the `fips` module");
+
+#[cfg(test)]
+mod tests;
@@ -1,14 +1,17 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use webauthn_rs::prelude::Passkey;
 
-use crate::utils::FromRow;
+use crate::{
+    app::{AppError, ErrorCode},
+    utils::{FromRow, MIGRATIONS},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
     pub username: String,
-    pub role: Option<String>,
     pub status: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -20,7 +23,6 @@ impl FromRow for User {
         Ok(User {
             id: row.try_get("id")?,
             username: row.try_get("username")?,
-            role: row.try_get("role")?,
             status: row.try_get("status")?,
             created_at: row.try_get("created_at")?,
             updated_at: row.try_get("updated_at")?,
@@ -29,6 +31,157 @@ impl FromRow for User {
     }
 }
 
+impl User {
+    pub fn is_suspended(&self) -> bool {
+        self.status == UserStatus::Suspended.as_str()
+    }
+}
+
+/// Where an account is in its lifecycle, as stored in `users.status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserStatus {
+    /// Registered, waiting for its first passkey or for activation.
+    Pending,
+    Active,
+    /// Cannot sign in or refresh until an administrator lifts it.
+    Suspended,
+    /// Soft-deleted; the row only stays for the audit trail.
+    Deleted,
+}
+
+impl UserStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UserStatus::Pending => "pending",
+            UserStatus::Active => "active",
+            UserStatus::Suspended => "suspended",
+            UserStatus::Deleted => "deleted",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(UserStatus::Pending),
+            "active" => Some(UserStatus::Active),
+            "suspended" => Some(UserStatus::Suspended),
+            "deleted" => Some(UserStatus::Deleted),
+            _ => None,
+        }
+    }
+
+    /// Pending accounts become active once enrolled, only active ones can
+    /// be suspended and lifting a suspension makes them active again.
+    /// Deletion is possible from anywhere and final.
+    pub fn can_become(self, next: UserStatus) -> bool {
+        matches!(
+            (self, next),
+            (UserStatus::Pending, UserStatus::Active)
+                | (UserStatus::Active, UserStatus::Suspended)
+                | (UserStatus::Suspended, UserStatus::Active)
+                | (
+                    UserStatus::Pending | UserStatus::Active | UserStatus::Suspended,
+                    UserStatus::Deleted
+                )
+        )
+    }
+}
+
+/// Refused sign-in or token for a suspended account.
+pub fn account_suspended() -> AppError {
+    AppError::Forbidden(String::from("Account suspended")).with_code(ErrorCode::AccountSuspended)
+}
+
+/// The roles held by a user and the permissions they add up to. Carried in
+/// access tokens, and reloaded from the database whenever one is issued.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grants {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
+}
+
+impl Grants {
+    pub fn allows(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+}
+
+impl FromRow for Grants {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, crate::app::AppError> {
+        Ok(Grants {
+            roles: row.try_get("roles")?,
+            permissions: row.try_get("permissions")?,
+        })
+    }
+}
+
+/// User-facing label and trust flag of a login session. Carried in the
+/// refresh token, so it follows the session across rotations.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionDevice {
+    #[serde(
+        rename = "device_name",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub trusted: bool,
+    /// The client application the session was opened by, kept across
+    /// refreshes so a session cannot move to another application.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azp: Option<String>,
+}
+
+/// An active user together with their recovery lockout, if any.
+#[derive(Debug, Clone)]
+pub struct RecoveryState {
+    pub user: User,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl FromRow for RecoveryState {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, crate::app::AppError> {
+        Ok(RecoveryState {
+            user: User::from_row(row)?,
+            locked_until: row.try_get("recovery_locked_until")?,
+        })
+    }
+}
+
+/// An invitation a registration spent, and the role it names, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedeemedInvitation {
+    pub id: Uuid,
+    pub role: Option<String>,
+}
+
+/// A passkey together with what `credentials` keeps beside it. `aaguid` is
+/// the authenticator model reported at registration, if any.
+#[derive(Debug, Clone)]
+pub struct StoredCredential {
+    pub passkey: Passkey,
+    pub aaguid: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Set while the passkey is locked as possibly cloned.
+    pub clone_suspected_at: Option<DateTime<Utc>>,
+}
+
+impl FromRow for StoredCredential {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, crate::app::AppError> {
+        let passkey: serde_json::Value = row.try_get("passkey")?;
+        Ok(StoredCredential {
+            passkey: serde_json::from_value(passkey)?,
+            aaguid: row.try_get("aaguid")?,
+            created_at: row.try_get("created_at")?,
+            last_used_at: row.try_get("last_used_at")?,
+            clone_suspected_at: row.try_get("clone_suspected_at")?,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebAuthnSession {
     pub id: Uuid,
@@ -51,3 +204,35 @@ impl FromRow for WebAuthnSession {
         })
     }
 }
+
+/// Which embedded migrations have run, judged by whether their table exists.
+/// This works whether the schema came from the Postgres init scripts or from
+/// the built-in runner, which is the only one keeping `schema_migrations`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub applied: Vec<&'static str>,
+    pub pending: Vec<&'static str>,
+}
+
+impl MigrationStatus {
+    pub fn from_missing_tables(missing: &[String]) -> Self {
+        let (pending, applied): (Vec<_>, Vec<_>) = MIGRATIONS
+            .iter()
+            .partition(|migration| missing.iter().any(|m| m == migration.table));
+
+        Self {
+            applied: applied
+                .into_iter()
+                .map(|migration| migration.name)
+                .collect(),
+            pending: pending
+                .into_iter()
+                .map(|migration| migration.name)
+                .collect(),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
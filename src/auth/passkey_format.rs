@@ -0,0 +1,131 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use tokio_postgres::Client;
+use webauthn_rs::prelude::{AuthenticationResult, Credential, Passkey, WebauthnError};
+
+use crate::{
+    app::{AppError, ErrorCode},
+    auth::queries,
+};
+
+/// `credentials.passkey_format` of rows written by serializing a `Passkey`.
+/// Format 1 rows may have been edited in place with `jsonb_set` by a login.
+/// Logins keep the format, so the `last_used_at` trigger can tell them from
+/// the rewrite.
+pub const PASSKEY_FORMAT: i16 = 2;
+
+const MIGRATION_BATCH: i64 = 500;
+
+/// Applies a login to a stored passkey through webauthn-rs, so the counter
+/// only moves forward and backup eligibility is only ever gained. A stale
+/// login finishing after a newer one cannot roll the counter back and hide
+/// a cloned authenticator.
+pub fn apply_authentication(
+    stored: serde_json::Value,
+    result: &AuthenticationResult,
+) -> Result<serde_json::Value, AppError> {
+    let mut passkey: Passkey = serde_json::from_value(stored)?;
+    if passkey.update_credential(result).is_none() {
+        return Err(AppError::InternalServer(String::from(
+            "Authentication result belongs to another credential",
+        )));
+    }
+    Ok(serde_json::to_value(&passkey)?)
+}
+
+/// Sets the stored counter back to zero once the owner confirmed a passkey
+/// locked as possibly cloned. The authenticator's own counter is behind the
+/// stored one, so without this the next login would lock it again.
+pub fn reset_counter(stored: serde_json::Value) -> Result<serde_json::Value, AppError> {
+    let passkey: Passkey = serde_json::from_value(stored)?;
+    let mut credential = Credential::from(passkey);
+    credential.counter = 0;
+    Ok(serde_json::to_value(Passkey::from(credential))?)
+}
+
+/// The passkeys a login may offer, leaving out those locked as possibly
+/// cloned. When every one is locked the user is told so, rather than that
+/// they have no credentials.
+pub fn unlocked_passkeys(
+    stored: impl IntoIterator<Item = (serde_json::Value, Option<DateTime<Utc>>)>,
+) -> Result<Vec<Passkey>, AppError> {
+    let mut locked = false;
+    let mut passkeys = Vec::new();
+    for (passkey, clone_suspected_at) in stored {
+        if clone_suspected_at.is_some() {
+            locked = true;
+        } else {
+            passkeys.push(serde_json::from_value(passkey)?);
+        }
+    }
+
+    if passkeys.is_empty() && locked {
+        return Err(AppError::Forbidden(String::from(
+            "Every passkey is locked pending confirmation",
+        ))
+        .with_code(ErrorCode::CredentialLocked));
+    }
+    Ok(passkeys)
+}
+
+/// WebAuthn turned down the answer to a ceremony that was loaded for the
+/// user. Unlike a malformed request, it is a failed sign-in attempt.
+pub fn passkey_rejected(error: WebauthnError) -> AppError {
+    AppError::Unauthorized(format!("Passkey rejected: {}", error))
+        .with_code(ErrorCode::PasskeyRejected)
+}
+
+pub fn credential_locked(cred_id: &[u8]) -> AppError {
+    AppError::Forbidden(String::from(
+        "Passkey locked: its signature counter went backwards",
+    ))
+    .with_code(ErrorCode::CredentialLocked)
+    .with_detail("credential_id", BASE64_URL_SAFE_NO_PAD.encode(cred_id))
+}
+
+/// Rewrites format 1 passkeys through `Passkey`, a batch per transaction.
+/// Rows webauthn-rs cannot read are logged and left as they are; logins
+/// with them fail either way. Returns how many rows were rewritten.
+pub async fn migrate_legacy_passkeys(client: &mut Client) -> Result<u64, AppError> {
+    let mut migrated = 0;
+    let mut after: Vec<u8> = Vec::new();
+
+    loop {
+        let transaction = client.transaction().await?;
+        let rows = transaction
+            .query(
+                queries::passkey_format::SELECT_LEGACY,
+                &[&PASSKEY_FORMAT, &after, &MIGRATION_BATCH],
+            )
+            .await?;
+        let Some(last) = rows.last() else {
+            return Ok(migrated);
+        };
+        after = last.get("id");
+
+        for row in &rows {
+            let id: Vec<u8> = row.get("id");
+            let passkey = match serde_json::from_value::<Passkey>(row.get("passkey")) {
+                Ok(passkey) => serde_json::to_value(&passkey)?,
+                Err(e) => {
+                    tracing::warn!(
+                        "Leaving unreadable passkey {} in the legacy format: {}",
+                        BASE64_URL_SAFE_NO_PAD.encode(&id),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            transaction
+                .execute(
+                    queries::passkey_format::UPDATE_FORMAT,
+                    &[&passkey, &PASSKEY_FORMAT, &id],
+                )
+                .await?;
+            migrated += 1;
+        }
+
+        transaction.commit().await?;
+    }
+}
@@ -0,0 +1,28 @@
+/// A `resource:action` scope a route can demand through
+/// `RequirePermission<P>`.
+pub trait Permission: Send + Sync + 'static {
+    const SCOPE: &'static str;
+}
+
+macro_rules! permissions {
+    ($($(#[$doc:meta])* $name:ident => $scope:literal,)*) => {
+        $(
+            $(#[$doc])*
+            pub struct $name;
+
+            impl Permission for $name {
+                const SCOPE: &'static str = $scope;
+            }
+        )*
+    };
+}
+
+permissions! {
+    /// Operational actions under `/admin/actions`.
+    AdminActions => "admin:actions",
+    AuditRead => "audit:read",
+    /// Setting or clearing the login banner.
+    BannerWrite => "banner:write",
+    EnrollmentRead => "enrollment:read",
+    TrafficRead => "traffic:read",
+}
@@ -1,45 +1,209 @@
-pub mod users {
-    pub const SELECT_BY_USERNAME: &str = "SELECT * FROM users WHERE username = $1";
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
+pub mod tenants {
+    pub const INSERT_MISSING: &str = "INSERT INTO tenants (id)
+         SELECT UNNEST($1::text[])
+         ON CONFLICT (id) DO NOTHING";
+}
 
-    pub const INSERT_WITH_ROLE: &str = "INSERT INTO users (username, role)
-         VALUES ($1, $2)
-         RETURNING *";
+/// Every query takes the tenant of the request as its last parameter, so
+/// rows of other tenants are never seen or changed.
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
+pub mod users {
+    pub const SELECT_BY_USERNAME: &str =
+        "SELECT * FROM users WHERE normalized_username = $1 AND tenant_id = $2";
 
-    pub const INSERT_WITHOUT_ROLE: &str = "INSERT INTO users (username)
-         VALUES ($1)
-         RETURNING *";
+    pub const SELECT_ACTIVE_BY_ID: &str =
+        "SELECT * FROM users WHERE id = $1 AND is_active AND tenant_id = $2";
 
-    pub const UPDATE_STATUS_ACTIVE: &str = "UPDATE users SET status = 'active' WHERE username = $1";
+    /// Only pending users, so a stale activation cannot lift a suspension.
+    pub const UPDATE_STATUS_ACTIVE: &str = "UPDATE users SET status = 'active'
+         WHERE normalized_username = $1 AND status = 'pending' AND tenant_id = $2";
 
-    pub const SELECT_WITH_SESSION: &str = "SELECT u.id, u.username, u.role, u.status,
+    pub const SELECT_WITH_SESSION: &str = "SELECT u.id, u.username, u.status,
                 u.created_at, u.updated_at, u.is_active,
                 ws.id as session_id, ws.user_id, ws.data, ws.purpose,
                 ws.created_at as session_created_at, ws.expires_at
          FROM users u
          INNER JOIN webauthn_sessions ws ON u.id = ws.user_id
-         WHERE u.username = $1 AND ws.id = $2 AND ws.purpose = $3";
+         WHERE u.normalized_username = $1 AND ws.id = $2 AND ws.purpose = $3
+           AND u.tenant_id = $4";
+
+    pub const SELECT_ACTIVE_BY_USERNAME: &str = "SELECT * FROM users WHERE normalized_username = $1 AND status = 'active' AND tenant_id = $2";
+
+    pub const RECORD_RECOVERY_FAILURE: &str = "UPDATE users
+         SET recovery_failed_attempts = CASE
+                 WHEN recovery_failed_attempts + 1 >= $2 THEN 0
+                 ELSE recovery_failed_attempts + 1
+             END,
+             recovery_locked_until = CASE
+                 WHEN recovery_failed_attempts + 1 >= $2 THEN $3
+                 ELSE recovery_locked_until
+             END
+         WHERE id = $1 AND tenant_id = $4
+         RETURNING recovery_locked_until IS NOT DISTINCT FROM $3 AS locked";
 
-    pub const SELECT_ACTIVE_WITH_CREDENTIALS: &str = "SELECT u.id, u.username, u.role, u.status,
+    pub const RESET_RECOVERY_FAILURES: &str = "UPDATE users
+         SET recovery_failed_attempts = 0, recovery_locked_until = NULL
+         WHERE id = $1 AND tenant_id = $2";
+
+    /// Suspended users are read too, so they are told why they cannot sign in.
+    pub const SELECT_ACTIVE_WITH_CREDENTIALS: &str = "SELECT u.id, u.username, u.status,
                 u.created_at, u.updated_at, u.is_active,
-                c.passkey
+                c.passkey, c.clone_suspected_at
          FROM users u
          INNER JOIN credentials c ON u.id = c.user_id
-         WHERE u.username = $1 AND u.status = 'active'";
+         WHERE u.normalized_username = $1 AND u.status IN ('active', 'suspended')
+           AND u.tenant_id = $2";
+
+    /// Keeps the row for the audit trail but frees the username; the roles
+    /// are removed separately, so nothing identifying or privileged is left.
+    pub const SOFT_DELETE: &str = "UPDATE users
+         SET is_active = FALSE,
+             status = 'deleted',
+             username = 'deleted-' || id::text,
+             normalized_username = 'deleted-' || id::text
+         WHERE id = $1 AND is_active AND tenant_id = $2";
 }
 
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
+pub mod user_roles {
+    /// Inserts nothing when the role does not exist.
+    pub const INSERT: &str = "INSERT INTO user_roles (user_id, role)
+         SELECT $1, name FROM roles WHERE name = $2";
+
+    pub const DELETE_BY_USER: &str = "DELETE FROM user_roles WHERE user_id = $1";
+
+    /// No row when the user belongs to another tenant.
+    pub const SELECT_GRANTS: &str = "SELECT u.status,
+             ARRAY(SELECT role FROM user_roles WHERE user_id = u.id ORDER BY role) AS roles,
+             ARRAY(SELECT DISTINCT rp.permission
+                   FROM user_roles ur
+                   INNER JOIN role_permissions rp ON rp.role = ur.role
+                   WHERE ur.user_id = u.id
+                   ORDER BY rp.permission) AS permissions
+         FROM users u
+         WHERE u.id = $1 AND u.tenant_id = $2";
+
+    pub const SELECT_AAGUID_POLICY: &str = "SELECT ra.role, ra.aaguid
+         FROM role_aaguids ra
+         INNER JOIN user_roles ur ON ur.role = ra.role
+         INNER JOIN users u ON u.id = ur.user_id
+         WHERE ur.user_id = $1 AND u.tenant_id = $2";
+}
+
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
 pub mod credentials {
-    pub const INSERT: &str = "INSERT INTO credentials (id, user_id, passkey)
-         VALUES ($1, $2, $3)";
+    pub const INSERT: &str = "INSERT INTO credentials (id, user_id, passkey, aaguid, tenant_id)
+         VALUES ($1, $2, $3, $4, $5)";
+
+    pub const SELECT_BY_USER: &str = "SELECT passkey, aaguid, created_at, last_used_at,
+                clone_suspected_at
+         FROM credentials
+         WHERE user_id = $1 AND tenant_id = $2
+         ORDER BY created_at";
+
+    pub const DELETE_BY_USER: &str =
+        "DELETE FROM credentials WHERE user_id = $1 AND tenant_id = $2";
+
+    pub const SELECT_PASSKEY_FOR_UPDATE: &str = "SELECT passkey, clone_suspected_at
+         FROM credentials
+         WHERE id = $1 AND tenant_id = $2
+         FOR UPDATE";
+
+    /// Leaves `passkey_format` alone, so the trigger records the use.
+    pub const UPDATE_PASSKEY: &str =
+        "UPDATE credentials SET passkey = $1 WHERE id = $2 AND tenant_id = $3";
+
+    /// Matches nothing when the passkey is already locked.
+    pub const LOCK_CLONE_SUSPECTED: &str = "UPDATE credentials
+         SET clone_suspected_at = NOW()
+         WHERE id = $1 AND user_id = $2 AND clone_suspected_at IS NULL AND tenant_id = $3";
+
+    pub const SELECT_LOCKED_FOR_UPDATE: &str = "SELECT passkey FROM credentials
+         WHERE id = $1 AND user_id = $2 AND clone_suspected_at IS NOT NULL AND tenant_id = $3
+         FOR UPDATE";
 
-    pub const UPDATE_COUNTER: &str = "UPDATE credentials
-         SET passkey = jsonb_set(passkey, '{counter}', $1::text::jsonb)
-         WHERE id = $2";
+    pub const UNLOCK: &str = "UPDATE credentials
+         SET passkey = $1, clone_suspected_at = NULL
+         WHERE id = $2 AND tenant_id = $3";
 }
 
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
+pub mod recovery_codes {
+    pub const INSERT_BATCH: &str = "INSERT INTO recovery_codes (user_id, code_hash)
+         SELECT $1, UNNEST($2::bytea[])";
+
+    pub const DELETE_BY_USER: &str = "DELETE FROM recovery_codes WHERE user_id = $1";
+
+    pub const CONSUME: &str = "UPDATE recovery_codes
+         SET used_at = NOW()
+         WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL";
+}
+
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
 pub mod webauthn_sessions {
-    pub const INSERT: &str = "INSERT INTO webauthn_sessions (user_id, data, purpose, expires_at)
-         VALUES ($1, $2, $3, $4)
+    pub const INSERT: &str = "INSERT INTO webauthn_sessions
+             (user_id, data, purpose, expires_at, tenant_id)
+         VALUES ($1, $2, $3, $4, $5)
          RETURNING id";
 
-    pub const DELETE_BY_ID: &str = "DELETE FROM webauthn_sessions WHERE id = $1";
+    pub const DELETE_BY_ID: &str = "DELETE FROM webauthn_sessions WHERE id = $1 AND tenant_id = $2";
+
+    pub const DELETE_BY_USER: &str =
+        "DELETE FROM webauthn_sessions WHERE user_id = $1 AND tenant_id = $2";
+}
+
+pub mod account_throttle {
+    /// Hash with the `failures` in a row, `last_failure_ms` and, while the
+    /// account is locked, `locked_until_ms`.
+    pub fn key(tenant: &str, username: &str) -> String {
+        format!("account_throttle:{}:{}", tenant, username)
+    }
+}
+
+pub mod login_cache {
+    /// JSON of the user and the passkeys a login may offer, by normalized
+    /// username.
+    pub fn key(tenant: &str, username: &str) -> String {
+        format!("login_cache:{}:{}", tenant, username)
+    }
+}
+
+pub mod ceremony_nonces {
+    pub fn key(nonce: &uuid::Uuid) -> String {
+        format!("ceremony_nonce:{}", nonce)
+    }
+}
+
+pub mod email_verifications {
+    /// Hash with the address, the token hash and the `verified` and
+    /// `enrolled` flags of a pending registration.
+    pub fn key(user_id: &uuid::Uuid) -> String {
+        format!("email_verification:{}", user_id)
+    }
+}
+
+/// Used by the startup rewrite in every build, across all tenants.
+pub mod passkey_format {
+    pub const SELECT_LEGACY: &str = "SELECT id, passkey
+         FROM credentials
+         WHERE passkey_format < $1 AND id > $2
+         ORDER BY id
+         LIMIT $3
+         FOR UPDATE SKIP LOCKED";
+
+    pub const UPDATE_FORMAT: &str = "UPDATE credentials
+         SET passkey = $1, passkey_format = $2
+         WHERE id = $3";
+}
+
+#[cfg(not(any(feature = "sqlx", feature = "memory-store")))]
+pub mod migrations {
+    /// Names with parentheses are function signatures.
+    pub const SELECT_MISSING_TABLES: &str = "SELECT name
+         FROM UNNEST($1::text[]) AS name
+         WHERE CASE WHEN name LIKE '%(%'
+                    THEN to_regprocedure(name) IS NULL
+                    ELSE to_regclass(name) IS NULL
+               END";
 }
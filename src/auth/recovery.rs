@@ -0,0 +1,61 @@
+use crate::utils::crypto::{random_bytes, sha256};
+
+// Crockford base32: no I, L, O or U, so codes survive being read aloud or handwritten.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const GROUPS: usize = 3;
+const GROUP_LEN: usize = 4;
+const SEPARATOR: char = '-';
+
+/// A one-time recovery code in its display form, e.g. `7KQ2-M9XD-4TRB`.
+/// Only its hash is ever persisted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryCode(String);
+
+impl RecoveryCode {
+    pub fn generate() -> Self {
+        let entropy = u128::from_be_bytes(random_bytes());
+
+        let mut code = String::with_capacity(GROUPS * (GROUP_LEN + 1));
+        for i in 0..GROUPS * GROUP_LEN {
+            if i > 0 && i % GROUP_LEN == 0 {
+                code.push(SEPARATOR);
+            }
+            let index = (entropy >> (i * 5)) & 0x1f;
+            code.push(ALPHABET[index as usize] as char);
+        }
+
+        Self(code)
+    }
+
+    pub fn generate_batch(count: usize) -> Vec<Self> {
+        (0..count).map(|_| Self::generate()).collect()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn hash(&self) -> Vec<u8> {
+        Self::hash_input(&self.0)
+    }
+
+    /// Hashes user input after normalization, so separators, spacing and
+    /// case do not matter when the code is typed back in.
+    pub fn hash_input(input: &str) -> Vec<u8> {
+        sha256(Self::normalize(input).as_bytes()).to_vec()
+    }
+
+    pub fn normalize(input: &str) -> String {
+        input
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    }
+}
+
+impl From<RecoveryCode> for String {
+    fn from(value: RecoveryCode) -> Self {
+        value.0
+    }
+}
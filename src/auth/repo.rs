@@ -1,22 +1,49 @@
-use std::sync::Arc;
+use std::{fmt::Debug, sync::Arc};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use deadpool_postgres::{Pool, Transaction};
+use serde::Serialize;
+use tokio_postgres::types::Json;
 use uuid::Uuid;
+use webauthn_rs::prelude::AuthenticationResult;
 
 use crate::{
-    app::AppError,
+    app::{
+        AppError, ErrorCode,
+        context::{current_region, current_tenant},
+    },
     auth::{
+        attestation::AaguidPolicy,
         dto::ServiceHealth,
-        model::{User, WebAuthnSession},
+        model::{
+            Grants, MigrationStatus, RecoveryState, StoredCredential, User, UserStatus,
+            WebAuthnSession, account_suspended,
+        },
+        passkey_format::{
+            apply_authentication, credential_locked, reset_counter, unlocked_passkeys,
+        },
         queries,
         traits::AuthRepository,
     },
     config::CircuitBreaker,
     db_delete, db_insert, db_select, db_update,
-    utils::{BaseRepository, FromRow, RepositoryMetrics},
+    utils::{
+        BaseRepository, FromRow, InsertBuilder, MIGRATIONS, QueryKind, ReadReplica, RegionDatabase,
+        RepositoryMetrics, normalize_username,
+    },
 };
 
+/// Queries run through the statement cache on the login and token paths,
+/// with the kind they are run as.
+const HOT_QUERIES: &[(QueryKind, &str)] = &[
+    (QueryKind::Read, queries::users::SELECT_BY_USERNAME),
+    (QueryKind::Write, queries::users::SELECT_ACTIVE_BY_ID),
+    (QueryKind::Write, queries::users::SELECT_ACTIVE_BY_USERNAME),
+    (QueryKind::Write, queries::user_roles::SELECT_GRANTS),
+    (QueryKind::Write, queries::user_roles::SELECT_AAGUID_POLICY),
+    (QueryKind::Write, queries::credentials::SELECT_BY_USER),
+];
+
 pub struct Repository {
     base: BaseRepository,
 }
@@ -28,10 +55,32 @@ impl Repository {
         }
     }
 
-    async fn activate_user(tx: &Transaction<'_>, username: &str) -> Result<(), AppError> {
+    /// Looks users up by username on `replica` when there is one.
+    pub fn with_replica(mut self, replica: Option<ReadReplica>) -> Self {
+        self.base = self.base.with_replica(replica);
+        self
+    }
+
+    /// Keeps the accounts of requests resolved to a region in that region's
+    /// database.
+    pub fn with_regions(mut self, regions: Vec<RegionDatabase>) -> Self {
+        self.base = self.base.with_regions(regions);
+        self
+    }
+
+    async fn activate_user(
+        tx: &Transaction<'_>,
+        username: &str,
+        tenant: &str,
+    ) -> Result<(), AppError> {
+        let normalized = normalize_username(username);
+
         db_update!("users", {
-            tx.execute(queries::users::UPDATE_STATUS_ACTIVE, &[&username])
-                .await
+            tx.execute(
+                queries::users::UPDATE_STATUS_ACTIVE,
+                &[&normalized, &tenant],
+            )
+            .await
         })?;
 
         Ok(())
@@ -41,13 +90,54 @@ impl Repository {
         tx: &Transaction<'_>,
         user_id: Uuid,
         passkey: &webauthn_rs::prelude::Passkey,
+        aaguid: Option<Uuid>,
+        tenant: &str,
     ) -> Result<(), AppError> {
         let passkey_json = serde_json::to_value(passkey)?;
 
         db_insert!("credentials", {
             tx.execute(
                 queries::credentials::INSERT,
-                &[&passkey.cred_id().as_slice(), &user_id, &passkey_json],
+                &[
+                    &passkey.cred_id().as_slice(),
+                    &user_id,
+                    &passkey_json,
+                    &aaguid,
+                    &tenant,
+                ],
+            )
+            .await
+        })?;
+
+        Ok(())
+    }
+
+    async fn assign_role(tx: &Transaction<'_>, user_id: Uuid, role: &str) -> Result<(), AppError> {
+        let inserted = db_insert!("user_roles", {
+            tx.execute(queries::user_roles::INSERT, &[&user_id, &role])
+                .await
+        })?;
+
+        if inserted == 0 {
+            return Err(AppError::BadRequest(format!("Unknown role: {}", role)));
+        }
+        Ok(())
+    }
+
+    async fn replace_recovery_codes(
+        tx: &Transaction<'_>,
+        user_id: Uuid,
+        code_hashes: &[Vec<u8>],
+    ) -> Result<(), AppError> {
+        db_delete!("recovery_codes", {
+            tx.execute(queries::recovery_codes::DELETE_BY_USER, &[&user_id])
+                .await
+        })?;
+
+        db_insert!("recovery_codes", {
+            tx.execute(
+                queries::recovery_codes::INSERT_BATCH,
+                &[&user_id, &code_hashes],
             )
             .await
         })?;
@@ -62,53 +152,118 @@ impl AuthRepository for Repository {
         self.base.check_database_health().await
     }
 
+    async fn check_migrations(&self) -> Result<MigrationStatus, AppError> {
+        let tables: Vec<&str> = MIGRATIONS.iter().map(|migration| migration.table).collect();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let client = db.get().await?;
+
+                let rows = db_select!("pg_class", {
+                    client
+                        .query(queries::migrations::SELECT_MISSING_TABLES, &[&tables])
+                        .await
+                })?;
+                let missing = rows
+                    .iter()
+                    .map(|row| row.try_get("name"))
+                    .collect::<Result<Vec<String>, _>>()?;
+
+                Ok(MigrationStatus::from_missing_tables(&missing))
+            })
+            .await
+    }
+
+    async fn warm_up(&self, connections: usize) -> Result<usize, AppError> {
+        self.base.warm_up(HOT_QUERIES, connections).await
+    }
+
+    async fn register_tenants(&self, tenants: &[Box<str>]) -> Result<(), AppError> {
+        let tenants: Vec<String> = tenants.iter().map(|tenant| tenant.to_string()).collect();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let client = db.get().await?;
+
+                db_insert!("tenants", {
+                    client
+                        .execute(queries::tenants::INSERT_MISSING, &[&tenants])
+                        .await
+                })?;
+
+                Ok(())
+            })
+            .await
+    }
+
     async fn create_user(&self, username: &str, role: Option<&str>) -> Result<User, AppError> {
-        match self.get_user_by_username(&username).await {
+        match self.get_user_by_username(username).await {
+            // A registration started again picks its pending user back up,
+            // unless a role is involved: taking over a pending user that
+            // holds one would grant it without the invitation or
+            // administrator that admitted the first registration.
             Ok(user) => {
-                if user.status == "active" {
-                    return Err(AppError::AlreadyExists(String::from(
-                        "Username already exists",
-                    )));
-                } else {
+                if user.status == UserStatus::Pending.as_str()
+                    && role.is_none()
+                    && self.get_grants(user.id).await?.roles.is_empty()
+                {
                     return Ok(user);
                 }
+                return Err(
+                    AppError::AlreadyExists(String::from("Username already exists"))
+                        .with_code(ErrorCode::UsernameTaken),
+                );
             }
             Err(AppError::NotFound(_)) => {}
             Err(e) => return Err(e),
         }
 
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+        let region = current_region();
+        let query = InsertBuilder::new()
+            .into("users")
+            .column("username", &username)
+            .column("normalized_username", &normalized)
+            .column("tenant_id", &tenant)
+            .column("region", &region)
+            .build_returning()?;
         let username = username.to_string();
-        let role = role.map(|s| s.to_string());
+        let role = role.map(str::to_owned);
 
         self.base
             .execute_with_circuit_breaker(move |db| async move {
-                let client = db.get().await?;
+                let mut client = db.get().await?;
+                let tx = client.transaction().await?;
 
-                let row = if let Some(role_val) = &role {
-                    db_insert!("users", {
-                        client
-                            .query_one(queries::users::INSERT_WITH_ROLE, &[&username, role_val])
-                            .await
-                    })?
-                } else {
-                    db_insert!("users", {
-                        client
-                            .query_one(queries::users::INSERT_WITHOUT_ROLE, &[&username])
-                            .await
-                    })?
-                };
-
-                User::from_row(&row)
+                let row = db_insert!("users", {
+                    tx.query_one(&query, &[&username, &normalized, &tenant, &region])
+                        .await
+                })?;
+                let user = User::from_row(&row)?;
+                if let Some(role) = &role {
+                    Repository::assign_role(&tx, user.id, role).await?;
+                }
+
+                tx.commit().await?;
+                Ok(user)
             })
             .await
     }
 
     async fn get_user_by_username(&self, username: &str) -> Result<User, AppError> {
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+
         match db_select!("users", {
             self.base
-                .execute_prepared_opt(
+                .execute_prepared_opt_on(
+                    QueryKind::Read,
                     queries::users::SELECT_BY_USERNAME,
-                    &[&username as &(dyn tokio_postgres::types::ToSql + Sync)],
+                    &[
+                        &normalized as &(dyn tokio_postgres::types::ToSql + Sync),
+                        &tenant,
+                    ],
                 )
                 .await
         })? {
@@ -117,14 +272,76 @@ impl AuthRepository for Repository {
         }
     }
 
+    async fn get_user_by_id(&self, user_id: Uuid) -> Result<User, AppError> {
+        let tenant = current_tenant();
+
+        match db_select!("users", {
+            self.base
+                .execute_prepared_opt(
+                    queries::users::SELECT_ACTIVE_BY_ID,
+                    &[
+                        &user_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                        &tenant,
+                    ],
+                )
+                .await
+        })? {
+            Some(row) => User::from_row(&row),
+            None => Err(AppError::NotFound("User not found".to_string())),
+        }
+    }
+
+    async fn get_grants(&self, user_id: Uuid) -> Result<Grants, AppError> {
+        let tenant = current_tenant();
+
+        match db_select!("user_roles", {
+            self.base
+                .execute_prepared_opt(
+                    queries::user_roles::SELECT_GRANTS,
+                    &[
+                        &user_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                        &tenant,
+                    ],
+                )
+                .await
+        })? {
+            Some(row) if row.try_get::<_, &str>("status")? == UserStatus::Suspended.as_str() => {
+                Err(account_suspended())
+            }
+            Some(row) => Grants::from_row(&row),
+            None => Err(AppError::NotFound("User not found".to_string())),
+        }
+    }
+
+    async fn get_aaguid_policy(&self, user_id: Uuid) -> Result<AaguidPolicy, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("role_aaguids", {
+            self.base
+                .execute_prepared(
+                    queries::user_roles::SELECT_AAGUID_POLICY,
+                    &[
+                        &user_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                        &tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        rows.iter()
+            .map(|row| Ok((row.try_get("role")?, row.try_get("aaguid")?)))
+            .collect()
+    }
+
     async fn get_user_and_session(
         &self,
         session_id: Uuid,
         username: &str,
         purpose: &str,
     ) -> Result<(User, WebAuthnSession), AppError> {
-        let username = username.to_string();
+        let normalized = normalize_username(username);
         let purpose = purpose.to_string();
+        let tenant = current_tenant();
 
         self.base
             .execute_with_circuit_breaker(move |db| async move {
@@ -134,7 +351,7 @@ impl AuthRepository for Repository {
                     client
                         .query_opt(
                             queries::users::SELECT_WITH_SESSION,
-                            &[&username, &session_id, &purpose],
+                            &[&normalized, &session_id, &purpose, &tenant],
                         )
                         .await
                 })? {
@@ -153,7 +370,8 @@ impl AuthRepository for Repository {
         &self,
         username: &str,
     ) -> Result<(User, Vec<webauthn_rs::prelude::Passkey>), AppError> {
-        let username = username.to_string();
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
 
         self.base
             .execute_with_circuit_breaker(move |db| async move {
@@ -161,7 +379,10 @@ impl AuthRepository for Repository {
 
                 let rows = db_select!("users", {
                     client
-                        .query(queries::users::SELECT_ACTIVE_WITH_CREDENTIALS, &[&username])
+                        .query(
+                            queries::users::SELECT_ACTIVE_WITH_CREDENTIALS,
+                            &[&normalized, &tenant],
+                        )
                         .await
                 })?;
 
@@ -172,29 +393,29 @@ impl AuthRepository for Repository {
                 }
 
                 let user = User::from_row(&rows[0])?;
+                if user.is_suspended() {
+                    return Err(account_suspended());
+                }
 
-                let passkeys = rows
+                let stored = rows
                     .iter()
-                    .map(|row| {
-                        let passkey_json: serde_json::Value = row.try_get("passkey")?;
-                        let passkey: webauthn_rs::prelude::Passkey =
-                            serde_json::from_value(passkey_json)?;
-                        Ok(passkey)
-                    })
+                    .map(|row| Ok((row.try_get("passkey")?, row.try_get("clone_suspected_at")?)))
                     .collect::<Result<Vec<_>, AppError>>()?;
+                let passkeys = unlocked_passkeys(stored)?;
 
                 Ok((user, passkeys))
             })
             .await
     }
 
-    async fn create_webauthn_session(
+    async fn create_webauthn_session<S: Serialize + Debug + Sync>(
         &self,
         user_id: Uuid,
-        data: serde_json::Value,
+        state: &S,
         purpose: &str,
     ) -> Result<Uuid, AppError> {
         let purpose = purpose.to_string();
+        let tenant = current_tenant();
 
         self.base
             .execute_with_circuit_breaker(move |db| async move {
@@ -205,7 +426,7 @@ impl AuthRepository for Repository {
                     client
                         .query_one(
                             queries::webauthn_sessions::INSERT,
-                            &[&user_id, &data, &purpose, &expire_at],
+                            &[&user_id, &Json(state), &purpose, &expire_at, &tenant],
                         )
                         .await
                 })?;
@@ -216,13 +437,15 @@ impl AuthRepository for Repository {
     }
 
     async fn delete_webauthn_session(&self, id: Uuid) -> Result<(), AppError> {
+        let tenant = current_tenant();
+
         self.base
             .execute_with_circuit_breaker(move |db| async move {
                 let client = db.get().await?;
 
                 let result = db_delete!("webauthn_sessions", {
                     client
-                        .execute(queries::webauthn_sessions::DELETE_BY_ID, &[&id])
+                        .execute(queries::webauthn_sessions::DELETE_BY_ID, &[&id, &tenant])
                         .await
                 })?;
 
@@ -235,47 +458,328 @@ impl AuthRepository for Repository {
             .await
     }
 
-    async fn update_credential(&self, cred_id: &[u8], new_counter: u32) -> Result<(), AppError> {
+    async fn update_credential(
+        &self,
+        _username: &str,
+        result: &AuthenticationResult,
+    ) -> Result<(), AppError> {
+        let cred_id = result.cred_id().as_slice().to_vec();
+        let result = result.clone();
+        let tenant = current_tenant();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let mut client = db.get().await?;
+                let tx = client.transaction().await?;
+
+                let row = db_select!("credentials", {
+                    tx.query_opt(
+                        queries::credentials::SELECT_PASSKEY_FOR_UPDATE,
+                        &[&cred_id, &tenant],
+                    )
+                    .await
+                })?
+                .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
+                let clone_suspected_at: Option<DateTime<Utc>> = row.get("clone_suspected_at");
+                if clone_suspected_at.is_some() {
+                    return Err(credential_locked(&cred_id));
+                }
+                let passkey = apply_authentication(row.get("passkey"), &result)?;
+
+                db_update!("credentials", {
+                    tx.execute(
+                        queries::credentials::UPDATE_PASSKEY,
+                        &[&passkey, &cred_id, &tenant],
+                    )
+                    .await
+                })?;
+
+                tx.commit().await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn lock_cloned_credential(
+        &self,
+        user_id: Uuid,
+        cred_id: &[u8],
+    ) -> Result<bool, AppError> {
         let cred_id = cred_id.to_vec();
+        let tenant = current_tenant();
 
         self.base
             .execute_with_circuit_breaker(move |db| async move {
                 let client = db.get().await?;
 
-                let result = db_update!("credentials", {
+                let locked = db_update!("credentials", {
                     client
                         .execute(
-                            queries::credentials::UPDATE_COUNTER,
-                            &[&(new_counter as i64), &cred_id.as_slice()],
+                            queries::credentials::LOCK_CLONE_SUSPECTED,
+                            &[&cred_id, &user_id, &tenant],
                         )
                         .await
                 })?;
 
-                if result == 0 {
-                    return Err(AppError::NotFound("Credential not found".to_string()));
-                }
+                Ok(locked > 0)
+            })
+            .await
+    }
+
+    async fn unlock_credential(&self, user_id: Uuid, cred_id: &[u8]) -> Result<(), AppError> {
+        let cred_id = cred_id.to_vec();
+        let tenant = current_tenant();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let mut client = db.get().await?;
+                let tx = client.transaction().await?;
 
+                let row = db_select!("credentials", {
+                    tx.query_opt(
+                        queries::credentials::SELECT_LOCKED_FOR_UPDATE,
+                        &[&cred_id, &user_id, &tenant],
+                    )
+                    .await
+                })?
+                .ok_or_else(|| AppError::NotFound("Locked credential not found".to_string()))?;
+                let passkey = reset_counter(row.get("passkey"))?;
+
+                db_update!("credentials", {
+                    tx.execute(queries::credentials::UNLOCK, &[&passkey, &cred_id, &tenant])
+                        .await
+                })?;
+
+                tx.commit().await?;
                 Ok(())
             })
             .await
     }
 
+    async fn list_credentials(&self, user_id: Uuid) -> Result<Vec<StoredCredential>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("credentials", {
+            self.base
+                .execute_prepared(
+                    queries::credentials::SELECT_BY_USER,
+                    &[
+                        &user_id as &(dyn tokio_postgres::types::ToSql + Sync),
+                        &tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        rows.iter().map(StoredCredential::from_row).collect()
+    }
+
     async fn complete_registration(
         &self,
         user_id: Uuid,
         username: &str,
         passkey: &webauthn_rs::prelude::Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+        activate: bool,
     ) -> Result<(), AppError> {
         let username = username.to_string();
         let passkey = passkey.clone();
+        let recovery_code_hashes = recovery_code_hashes.to_vec();
+        let tenant = current_tenant();
 
         self.base
             .execute_with_circuit_breaker(move |db| async move {
                 let mut client = db.get().await?;
                 let tx = client.transaction().await?;
 
-                Repository::create_credential(&tx, user_id, &passkey).await?;
-                Repository::activate_user(&tx, &username).await?;
+                Repository::create_credential(&tx, user_id, &passkey, aaguid, &tenant).await?;
+                if activate {
+                    Repository::activate_user(&tx, &username, &tenant).await?;
+                }
+                Repository::replace_recovery_codes(&tx, user_id, &recovery_code_hashes).await?;
+
+                tx.commit().await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn activate_pending_user(&self, username: &str) -> Result<(), AppError> {
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let client = db.get().await?;
+
+                db_update!("users", {
+                    client
+                        .execute(
+                            queries::users::UPDATE_STATUS_ACTIVE,
+                            &[&normalized, &tenant],
+                        )
+                        .await
+                })?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_recovery_state(&self, username: &str) -> Result<RecoveryState, AppError> {
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+
+        match db_select!("users", {
+            self.base
+                .execute_prepared_opt(
+                    queries::users::SELECT_ACTIVE_BY_USERNAME,
+                    &[
+                        &normalized as &(dyn tokio_postgres::types::ToSql + Sync),
+                        &tenant,
+                    ],
+                )
+                .await
+        })? {
+            Some(row) => RecoveryState::from_row(&row),
+            None => Err(AppError::NotFound("Username not found".to_string())),
+        }
+    }
+
+    async fn consume_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &[u8],
+    ) -> Result<bool, AppError> {
+        let code_hash = code_hash.to_vec();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let client = db.get().await?;
+
+                let result = db_update!("recovery_codes", {
+                    client
+                        .execute(queries::recovery_codes::CONSUME, &[&user_id, &code_hash])
+                        .await
+                })?;
+
+                Ok(result == 1)
+            })
+            .await
+    }
+
+    async fn record_recovery_failure(
+        &self,
+        user_id: Uuid,
+        max_attempts: i32,
+        lock_until: DateTime<Utc>,
+    ) -> Result<bool, AppError> {
+        let tenant = current_tenant();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let client = db.get().await?;
+
+                let row = db_update!("users", {
+                    client
+                        .query_one(
+                            queries::users::RECORD_RECOVERY_FAILURE,
+                            &[&user_id, &max_attempts, &lock_until, &tenant],
+                        )
+                        .await
+                })?;
+
+                Ok(row.try_get("locked")?)
+            })
+            .await
+    }
+
+    async fn reset_recovery_failures(&self, user_id: Uuid) -> Result<(), AppError> {
+        let tenant = current_tenant();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let client = db.get().await?;
+
+                db_update!("users", {
+                    client
+                        .execute(
+                            queries::users::RESET_RECOVERY_FAILURES,
+                            &[&user_id, &tenant],
+                        )
+                        .await
+                })?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    async fn complete_recovery(
+        &self,
+        user_id: Uuid,
+        passkey: &webauthn_rs::prelude::Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+    ) -> Result<(), AppError> {
+        let passkey = passkey.clone();
+        let recovery_code_hashes = recovery_code_hashes.to_vec();
+        let tenant = current_tenant();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let mut client = db.get().await?;
+                let tx = client.transaction().await?;
+
+                db_delete!("credentials", {
+                    tx.execute(queries::credentials::DELETE_BY_USER, &[&user_id, &tenant])
+                        .await
+                })?;
+                Repository::create_credential(&tx, user_id, &passkey, aaguid, &tenant).await?;
+                Repository::replace_recovery_codes(&tx, user_id, &recovery_code_hashes).await?;
+
+                tx.commit().await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn delete_account(&self, user_id: Uuid) -> Result<(), AppError> {
+        let tenant = current_tenant();
+
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let mut client = db.get().await?;
+                let tx = client.transaction().await?;
+
+                db_delete!("credentials", {
+                    tx.execute(queries::credentials::DELETE_BY_USER, &[&user_id, &tenant])
+                        .await
+                })?;
+                db_delete!("recovery_codes", {
+                    tx.execute(queries::recovery_codes::DELETE_BY_USER, &[&user_id])
+                        .await
+                })?;
+                db_delete!("webauthn_sessions", {
+                    tx.execute(
+                        queries::webauthn_sessions::DELETE_BY_USER,
+                        &[&user_id, &tenant],
+                    )
+                    .await
+                })?;
+                db_delete!("user_roles", {
+                    tx.execute(queries::user_roles::DELETE_BY_USER, &[&user_id])
+                        .await
+                })?;
+                let result = db_update!("users", {
+                    tx.execute(queries::users::SOFT_DELETE, &[&user_id, &tenant])
+                        .await
+                })?;
+
+                if result == 0 {
+                    return Err(AppError::NotFound("User not found".to_string()));
+                }
 
                 tx.commit().await?;
                 Ok(())
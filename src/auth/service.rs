@@ -1,176 +1,605 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, future::Future, sync::Arc};
 
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use futures_util::future::join_all;
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use tracing::Instrument;
 use uuid::Uuid;
-use webauthn_rs::{
-    Webauthn,
-    prelude::{
-        PasskeyAuthentication, PasskeyRegistration, PublicKeyCredential,
-        RegisterPublicKeyCredential,
-    },
+use webauthn_rs::prelude::{
+    AttestationCaList, Credential, CredentialID, Passkey, PasskeyAuthentication,
+    PublicKeyCredential, RegisterPublicKeyCredential, WebauthnError,
 };
 
 use crate::{
-    app::AppError,
+    app::{
+        AppError, ErrorCode,
+        context::scope_region,
+        middleware::metrics::{
+            track_clone_suspected, track_credential_payload, track_token_operation,
+        },
+    },
+    audit::{
+        model::{AuditContext, AuditEntry, AuditEvent},
+        traits::AuditLogger,
+    },
     auth::{
+        attestation::{EnrollmentState, StoredEnrollment, attested_aaguid, reported_aaguid},
+        ceremony::{CeremonyClock, CeremonySealer},
+        ceremony_limit::CeremonyLimiter,
         dto::{
-            BeginRequest, BeginResponse, FinishRequest, HealthChecks, HealthResponse, HealthStatus,
-            MessageResponse, TokenResponse,
+            BeginRequest, BeginResponse, CredentialInfo, CredentialListResponse, FinishRequest,
+            HealthChecks, HealthResponse, HealthStatus, IntrospectionResponse,
+            LoginOptionsResponse, LoginStep, MessageResponse, ProfileResponse, RecoveryRequest,
+            RegistrationResponse, ServiceHealth, StartupResponse, TokenResponse,
+            UpdateSessionRequest, VerifyEmailRequest,
+        },
+        extensions::{self, Extensions},
+        jwt::{
+            AccessTokenClaims, JwtService, RefreshToken, RefreshTokenClaims, RotatedPair,
+            TokenPair, claims::JwtClaims,
         },
-        jwt::{JwtService, claims::JwtClaims},
-        model::WebAuthnSession,
-        traits::AuthRepository,
+        model::{SessionDevice, User},
+        passkey_format::{credential_locked, passkey_rejected},
+        permissions::{AdminActions, Permission},
+        recovery::RecoveryCode,
+        throttle::{AccountThrottle, counts_as_failure},
+        traits::{AuthRepository, ChallengeNonces, InvitationRedeemer},
+        verification::EmailVerifier,
+    },
+    config::{
+        ClientAppConfig, LoginHints, RolePolicy,
+        webauthn::{ExtensionsConfig, RelyingParties},
     },
+    event_export::{model::DomainEventKind, traits::DomainEventPublisher},
+    events::{
+        model::{AuthEventKind, RevocationReason},
+        traits::EventPublisher,
+    },
+    login_history::traits::LoginRecorder,
+    notification::{
+        model::{Notification, NotificationEvent},
+        traits::NotificationDispatcher,
+    },
+    token_issuance::{
+        model::{GrantType, TokenIssuance},
+        traits::IssuanceRecorder,
+    },
+    utils::validate_new_username,
 };
 
-pub struct AuthService<R, J>
+pub const RECOVERY_CODE_COUNT: usize = 10;
+pub const MAX_RECOVERY_ATTEMPTS: i32 = 5;
+pub const RECOVERY_LOCKOUT_MINUTES: i64 = 15;
+
+pub struct AuthService<R, J, N, A, C>
 where
     R: AuthRepository + 'static,
     J: JwtService + 'static,
+    N: NotificationDispatcher + 'static,
+    A: AuditLogger + 'static,
+    C: ChallengeNonces + 'static,
 {
-    webauthn: Webauthn,
+    relying_parties: RelyingParties,
     auth_repo: Arc<R>,
     jwt_service: Arc<J>,
+    notifier: Arc<N>,
+    audit_logger: Arc<A>,
+    /// Set in stateless mode: ceremony state is sealed into the session id
+    /// instead of being stored in `webauthn_sessions`.
+    sealer: Option<CeremonySealer>,
+    nonces: Arc<C>,
+    /// Set when registration also needs a verified email.
+    verifier: Option<EmailVerifier>,
+    events: Option<Arc<dyn EventPublisher>>,
+    exporter: Option<Arc<dyn DomainEventPublisher>>,
+    login_history: Option<Arc<dyn LoginRecorder>>,
+    issuance_log: Option<Arc<dyn IssuanceRecorder>>,
+    /// Needed to enroll users whose roles restrict authenticator models.
+    attestation_cas: Option<AttestationCaList>,
+    extensions: ExtensionsConfig,
+    client_apps: Arc<ClientAppConfig>,
+    ceremony_limiter: Arc<CeremonyLimiter>,
+    /// Regions with a database of their own, each checked for health.
+    regions: Vec<Box<str>>,
+    login_hints: LoginHints,
+    /// Set when failed finish steps delay and eventually lock the account.
+    account_throttle: Option<Arc<AccountThrottle>>,
+    role_policy: RolePolicy,
+    /// Set when invitations can admit registrations and grant roles.
+    invitations: Option<Arc<dyn InvitationRedeemer>>,
 }
 
-impl<R, J> AuthService<R, J>
+impl<R, J, N, A, C> AuthService<R, J, N, A, C>
 where
     R: AuthRepository + 'static,
     J: JwtService + 'static,
+    N: NotificationDispatcher + 'static,
+    A: AuditLogger + 'static,
+    C: ChallengeNonces + 'static,
 {
-    pub fn new(webauthn: Webauthn, auth_repo: Arc<R>, jwt_service: Arc<J>) -> Self {
+    pub fn new(
+        relying_parties: RelyingParties,
+        auth_repo: Arc<R>,
+        jwt_service: Arc<J>,
+        notifier: Arc<N>,
+        audit_logger: Arc<A>,
+        sealer: Option<CeremonySealer>,
+        nonces: Arc<C>,
+    ) -> Self {
         Self {
-            webauthn,
+            relying_parties,
             auth_repo,
             jwt_service,
+            notifier,
+            audit_logger,
+            sealer,
+            nonces,
+            verifier: None,
+            events: None,
+            exporter: None,
+            login_history: None,
+            issuance_log: None,
+            attestation_cas: None,
+            extensions: ExtensionsConfig::default(),
+            client_apps: Arc::default(),
+            ceremony_limiter: Arc::default(),
+            regions: Vec::new(),
+            login_hints: LoginHints::default(),
+            account_throttle: None,
+            role_policy: RolePolicy::default(),
+            invitations: None,
         }
     }
 
-    pub async fn begin_register(&self, req: BeginRequest) -> Result<BeginResponse, AppError> {
+    /// Requires a verified email before a registration activates the user.
+    pub fn with_email_verification(mut self, verifier: Option<EmailVerifier>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Lets roles with an AAGUID allowlist enroll attested credentials.
+    pub fn with_attestation_cas(mut self, cas: Option<AttestationCaList>) -> Self {
+        self.attestation_cas = cas;
+        self
+    }
+
+    /// Passes PRF and largeBlob through the ceremonies.
+    pub fn with_extensions(mut self, extensions: ExtensionsConfig) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Streams logins, new credentials and revoked sessions to their owner.
+    pub fn with_events(mut self, events: Arc<dyn EventPublisher>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Sends registrations, logins, refreshes and revoked sessions to an
+    /// external broker for analytics.
+    pub fn with_exporter(mut self, exporter: Option<Arc<dyn DomainEventPublisher>>) -> Self {
+        self.exporter = exporter;
+        self
+    }
+
+    /// Keeps where each login came from and flags unfamiliar origins.
+    pub fn with_login_history(mut self, login_history: Arc<dyn LoginRecorder>) -> Self {
+        self.login_history = Some(login_history);
+        self
+    }
+
+    /// Keeps who every token pair went to and which key signed it.
+    pub fn with_issuance_log(mut self, issuance_log: Arc<dyn IssuanceRecorder>) -> Self {
+        self.issuance_log = Some(issuance_log);
+        self
+    }
+
+    /// Binds the tokens of a login to the registered application it came
+    /// from.
+    pub fn with_client_apps(mut self, client_apps: Arc<ClientAppConfig>) -> Self {
+        self.client_apps = client_apps;
+        self
+    }
+
+    /// Shares the finish step limit with the admin endpoint that adjusts it.
+    pub fn with_ceremony_limiter(mut self, ceremony_limiter: Arc<CeremonyLimiter>) -> Self {
+        self.ceremony_limiter = ceremony_limiter;
+        self
+    }
+
+    /// Reports the health of each region's database next to the home one.
+    pub fn with_regions(mut self, regions: Vec<Box<str>>) -> Self {
+        self.regions = regions;
+        self
+    }
+
+    /// Lets the login form ask what to offer for a username.
+    pub fn with_login_hints(mut self, login_hints: LoginHints) -> Self {
+        self.login_hints = login_hints;
+        self
+    }
+
+    /// Shares the failure counters with the admin endpoint that unlocks
+    /// accounts.
+    pub fn with_account_throttle(mut self, account_throttle: Option<Arc<AccountThrottle>>) -> Self {
+        self.account_throttle = account_throttle;
+        self
+    }
+
+    /// Limits who may register, and with which roles.
+    pub fn with_role_policy(mut self, role_policy: RolePolicy) -> Self {
+        self.role_policy = role_policy;
+        self
+    }
+
+    /// Lets an invitation stand in for an administrator when registering a
+    /// privileged role, or any user in invite-only mode.
+    pub fn with_invitations(mut self, invitations: Arc<dyn InvitationRedeemer>) -> Self {
+        self.invitations = Some(invitations);
+        self
+    }
+
+    /// `registrar` is the caller's access token, if it sent one: holding
+    /// `admin:actions` lets it register users with privileged roles, and
+    /// without an invitation in invite-only mode.
+    pub async fn begin_register(
+        &self,
+        req: BeginRequest,
+        registrar: Option<&AccessTokenClaims>,
+    ) -> Result<BeginResponse, AppError> {
+        self.check_throttle(&req.username).await?;
+        if self.verifier.is_some() && req.email.is_none() {
+            return Err(AppError::BadRequest(String::from("Email is required")));
+        }
+        validate_new_username(&req.username)?;
+        let extensions = extensions::registration_inputs(self.extensions, req.extensions.as_ref())?;
+        let (role, invitation) = self.admit(&req, registrar).await?;
+
         let user = self
             .auth_repo
-            .create_user(&req.username, req.role.as_deref())
+            .create_user(&req.username, role.as_deref())
             .await?;
 
-        let (ccr, passkey_registration) = self.webauthn.start_passkey_registration(
-            user.id,
-            &req.username,
-            &req.username,
-            None,
-        )?;
+        if let (Some(verifier), Some(email)) = (&self.verifier, &req.email) {
+            verifier.start(&user, email).await?;
+        }
 
-        let (session_data, opts) = self.prepare_session_data(passkey_registration, ccr).await?;
-        self.create_session_response(user.id, session_data, opts, "registration")
+        let registered = self.registered_credentials(user.id).await?;
+        self.start_enrollment(&user, "registration", extensions, registered, invitation)
             .await
     }
 
-    pub async fn finish_register(&self, req: FinishRequest) -> Result<MessageResponse, AppError> {
-        let (session_id, user, session) = self
-            .get_user_and_session(&req.session_id, &req.username, "registration")
-            .await?;
+    pub async fn finish_register(
+        &self,
+        req: FinishRequest,
+        ctx: &AuditContext,
+    ) -> Result<RegistrationResponse, AppError> {
+        let username = req.username.clone();
+        let result = self
+            .throttled(&username, "registration", self.complete_registration(req))
+            .await;
+        self.audit_logger.record(AuditEntry::new(
+            AuditEvent::Registration,
+            ctx,
+            Some(&username),
+            result.as_ref().map(|_| ()),
+        ));
+        result
+    }
 
-        let (passkey_registration, credentials) = tokio::join!(
-            async { serde_json::from_value::<PasskeyRegistration>(session.data) },
-            async { serde_json::from_value::<RegisterPublicKeyCredential>(req.credentials) }
-        );
-        let passkey_registration = passkey_registration?;
-        let credentials = credentials?;
+    /// Confirms the address given at registration. The account is activated
+    /// here when the passkey is already registered.
+    pub async fn verify_email(
+        &self,
+        req: VerifyEmailRequest,
+        ctx: &AuditContext,
+    ) -> Result<MessageResponse, AppError> {
+        let username = req.username.clone();
+        let result = self.confirm_email(req).await;
+        self.audit_logger.record(AuditEntry::new(
+            AuditEvent::EmailVerification,
+            ctx,
+            Some(&username),
+            result.as_ref().map(|_| ()),
+        ));
+        result
+    }
 
-        let passkey = self
-            .webauthn
-            .finish_passkey_registration(&credentials, &passkey_registration)?;
+    /// Consumes a recovery code and starts enrolling a replacement passkey.
+    /// The code is spent even if the ceremony is never finished, so a leaked
+    /// code cannot be replayed while the owner is mid-recovery.
+    pub async fn begin_recovery(
+        &self,
+        req: RecoveryRequest,
+        ctx: &AuditContext,
+    ) -> Result<BeginResponse, AppError> {
+        let username = req.username.clone();
+        let result = self.start_recovery(req).await;
+        self.audit_logger.record(AuditEntry::new(
+            AuditEvent::RecoveryAttempt,
+            ctx,
+            Some(&username),
+            result.as_ref().map(|_| ()),
+        ));
+        result
+    }
 
-        self.auth_repo
-            .complete_registration(user.id, &user.username, &passkey)
-            .await?;
-        self.cleanup_session(session_id);
+    /// Replaces every existing credential with the new passkey and issues a
+    /// fresh set of recovery codes, invalidating the remaining old ones.
+    pub async fn finish_recovery(
+        &self,
+        req: FinishRequest,
+        ctx: &AuditContext,
+    ) -> Result<RegistrationResponse, AppError> {
+        let username = req.username.clone();
+        let result = self.complete_recovery(req).await;
+        self.audit_logger.record(AuditEntry::new(
+            AuditEvent::Recovery,
+            ctx,
+            Some(&username),
+            result.as_ref().map(|_| ()),
+        ));
+        if result.is_ok() {
+            self.audit_logger.record(
+                AuditEntry::new(AuditEvent::CredentialDeleted, ctx, Some(&username), Ok(()))
+                    .with_details(serde_json::json!({ "reason": "account_recovery" })),
+            );
+        }
+        result
+    }
 
-        Ok(MessageResponse {
-            message: String::from("Registration completed successfully!"),
-        })
+    /// What the login form should offer for `username`, as far as
+    /// `LOGIN_HINTS` allows telling. A pending or deleted account looks
+    /// like no account at all, as it does to `begin_login`.
+    pub async fn login_options(&self, username: &str) -> Result<LoginOptionsResponse, AppError> {
+        match self.login_hints {
+            LoginHints::Off => Err(AppError::NotFound(String::from("Login hints are disabled"))),
+            LoginHints::Uniform => Ok(LoginOptionsResponse::login(true)),
+            LoginHints::Exact => match self
+                .auth_repo
+                .get_active_user_with_credential(username)
+                .await
+            {
+                Ok((_, passkeys)) => Ok(LoginOptionsResponse::login(
+                    passkeys
+                        .into_iter()
+                        .any(|passkey| Credential::from(passkey).backup_eligible),
+                )),
+                Err(e) if e.code() == ErrorCode::CredentialLocked => {
+                    Ok(LoginOptionsResponse::without_passkey(LoginStep::Recover))
+                }
+                Err(e) if matches!(e.kind(), AppError::NotFound(_)) => {
+                    Ok(LoginOptionsResponse::without_passkey(LoginStep::Register))
+                }
+                Err(e) => Err(e),
+            },
+        }
     }
 
     pub async fn begin_login(&self, req: BeginRequest) -> Result<BeginResponse, AppError> {
+        self.check_throttle(&req.username).await?;
+        let extensions =
+            extensions::authentication_inputs(self.extensions, req.extensions.as_ref())?;
         let (user, passkey) = self
             .auth_repo
             .get_active_user_with_credential(&req.username)
             .await?;
-        let (rcr, passkey_authentication) = self.webauthn.start_passkey_authentication(&passkey)?;
-
-        let (session_data, opts) = self
-            .prepare_session_data(passkey_authentication, rcr)
-            .await?;
+        let (rcr, passkey_authentication) = self
+            .relying_parties
+            .current()
+            .start_passkey_authentication(&passkey)?;
 
-        self.create_session_response(user.id, session_data, opts, "login")
-            .await
+        self.create_session_response(
+            user.id,
+            &passkey_authentication,
+            &rcr,
+            extensions,
+            None,
+            "login",
+        )
+        .await
     }
 
     pub async fn finish_login(
         &self,
         req: FinishRequest,
-    ) -> Result<(TokenResponse, String), AppError> {
-        let (session_id, user, session) = self
-            .get_user_and_session(&req.session_id, &req.username, "login")
-            .await?;
-
-        let (passkey_authentication, credentials) = tokio::join!(
-            async { serde_json::from_value::<PasskeyAuthentication>(session.data) },
-            async { serde_json::from_value::<PublicKeyCredential>(req.credentials) }
+        ctx: &AuditContext,
+    ) -> Result<(TokenResponse, RefreshToken), AppError> {
+        let username = req.username.clone();
+        let trusted = req.trusted;
+        let result = self
+            .throttled(&username, "login", self.complete_login(req, ctx))
+            .await;
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::Login,
+                ctx,
+                Some(&username),
+                result.as_ref().map(|_| ()),
+            )
+            .with_details(serde_json::json!({ "trusted": trusted })),
         );
-        let passkey_authentication = passkey_authentication?;
-        let credentials = credentials?;
+        result
+    }
 
-        let result = self
-            .webauthn
-            .finish_passkey_authentication(&credentials, &passkey_authentication)?;
+    pub async fn refresh(
+        &self,
+        refresh_token: &str,
+        ctx: &AuditContext,
+    ) -> Result<(TokenResponse, RefreshToken), AppError> {
+        let claims = match self.jwt_service.validate_refresh(refresh_token).await {
+            Ok(claims) => claims,
+            Err(e) => {
+                if e.code() == ErrorCode::AuthTokenRevoked
+                    && let Some(pair) = self.replay_rotation(refresh_token).await
+                {
+                    return Ok(self.replayed(pair, ctx));
+                }
+                self.audit_logger
+                    .record(AuditEntry::new(AuditEvent::Refresh, ctx, None, Err(&e)));
+                return Err(e);
+            }
+        };
 
-        if result.needs_update() {
-            self.auth_repo
-                .update_credential(result.cred_id(), result.counter())
-                .await?;
+        let result = self
+            .rotate_refresh_token(&claims, claims.device(), GrantType::Refresh, ctx)
+            .await;
+        if let Ok((response, new_token)) = &result {
+            self.export(DomainEventKind::TokenRefreshed {
+                user_id: *claims.sub(),
+                client_app: claims.device().azp.clone(),
+            });
+            let pair = RotatedPair {
+                user_id: *claims.sub(),
+                username: claims.username().to_owned(),
+                access_token: response.access_token.clone(),
+                refresh_token: new_token.value.clone(),
+                trusted: new_token.trusted,
+            };
+            if let Err(e) = self
+                .jwt_service
+                .remember_rotation(refresh_token, &pair)
+                .await
+            {
+                tracing::warn!("Refresh grace window not kept: {}", e);
+            }
         }
-
-        self.cleanup_session(session_id);
-
-        let token_pair =
-            self.jwt_service
-                .generate_token_pair(user.id, &user.username, user.role.as_deref());
-
-        Ok((
-            TokenResponse {
-                message: String::from("Login completed successfully!"),
-                access_token: token_pair.access_token,
-            },
-            token_pair.refresh_token,
-        ))
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::Refresh,
+                ctx,
+                Some(claims.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*claims.sub()),
+        );
+        result
     }
 
-    pub async fn refresh(&self, refresh_token: &str) -> Result<(TokenResponse, String), AppError> {
-        let claims = self.jwt_service.validate_refresh(refresh_token).await?;
+    /// What the refresh that rotated out `refresh_token` returned, while
+    /// its grace window lasts. A lookup failure only costs the grace.
+    async fn replay_rotation(&self, refresh_token: &str) -> Option<RotatedPair> {
         self.jwt_service
-            .blacklist(&claims.jti(), claims.exp())
-            .await?;
+            .take_rotation(refresh_token)
+            .await
+            .inspect_err(|e| tracing::warn!("Refresh grace window unavailable: {}", e))
+            .ok()
+            .flatten()
+    }
 
-        let token_pair = self.jwt_service.generate_token_pair(
-            claims.sub().to_owned(),
-            claims.username(),
-            claims.role(),
+    /// Hands the lost response back as is: nothing new is issued, so there
+    /// is no issuance to log or event to export.
+    fn replayed(&self, pair: RotatedPair, ctx: &AuditContext) -> (TokenResponse, RefreshToken) {
+        track_token_operation("refresh_grace", true);
+        self.audit_logger.record(
+            AuditEntry::new(AuditEvent::Refresh, ctx, Some(&pair.username), Ok(()))
+                .with_user_id(pair.user_id)
+                .with_details(serde_json::json!({ "grace": true })),
         );
-        Ok((
+        (
             TokenResponse {
                 message: String::from("Refresh completed successfully!"),
-                access_token: token_pair.access_token,
+                access_token: pair.access_token,
+                credential: None,
+                extensions: None,
             },
-            token_pair.refresh_token,
-        ))
+            RefreshToken {
+                value: pair.refresh_token,
+                trusted: pair.trusted,
+            },
+        )
     }
 
-    pub async fn logout(&self, refresh_token: &str) -> Result<MessageResponse, AppError> {
-        if !refresh_token.is_empty() {
-            if let Ok(claims) = self.jwt_service.validate_refresh(refresh_token).await {
-                if let Err(e) = self.jwt_service.blacklist(claims.jti(), claims.exp()).await {
-                    tracing::error!("Failed to blacklist token during logout: {}", e);
-                }
+    /// Renames the current session or withdraws its trust, rotating the
+    /// refresh token so the change travels with it.
+    pub async fn update_session(
+        &self,
+        refresh_token: &str,
+        req: UpdateSessionRequest,
+        ctx: &AuditContext,
+    ) -> Result<(TokenResponse, RefreshToken), AppError> {
+        let claims = match self.jwt_service.validate_refresh(refresh_token).await {
+            Ok(claims) => claims,
+            Err(e) => {
+                self.audit_logger.record(AuditEntry::new(
+                    AuditEvent::SessionUpdated,
+                    ctx,
+                    None,
+                    Err(&e),
+                ));
+                return Err(e);
             }
+        };
+
+        let device = SessionDevice {
+            name: req.device_name.or_else(|| claims.device().name.clone()),
+            trusted: req.trusted.unwrap_or(claims.device().trusted),
+            azp: claims.device().azp.clone(),
+        };
+
+        let result = self
+            .rotate_refresh_token(&claims, &device, GrantType::SessionUpdate, ctx)
+            .await
+            .map(|(mut response, refresh_token)| {
+                response.message = String::from("Session updated successfully!");
+                (response, refresh_token)
+            });
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::SessionUpdated,
+                ctx,
+                Some(claims.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*claims.sub())
+            .with_details(serde_json::json!({
+                "device_name": device.name,
+                "trusted": device.trusted,
+            })),
+        );
+        result
+    }
+
+    pub async fn logout(
+        &self,
+        refresh_token: &str,
+        ctx: &AuditContext,
+    ) -> Result<MessageResponse, AppError> {
+        let claims = if refresh_token.is_empty() {
+            None
+        } else {
+            self.jwt_service.validate_refresh(refresh_token).await.ok()
+        };
+
+        if let Some(claims) = &claims
+            && let Err(e) = self.jwt_service.blacklist(claims.jti(), claims.exp()).await
+        {
+            tracing::error!("Failed to blacklist token during logout: {}", e);
+        }
+
+        let entry = AuditEntry::new(
+            AuditEvent::Logout,
+            ctx,
+            claims.as_ref().map(|claims| claims.username()),
+            Ok(()),
+        );
+        self.audit_logger.record(match &claims {
+            Some(claims) => entry.with_user_id(*claims.sub()),
+            None => entry,
+        });
+        if let Some(claims) = &claims {
+            self.publish(
+                *claims.sub(),
+                AuthEventKind::SessionRevoked {
+                    reason: RevocationReason::Logout,
+                },
+            );
+            self.export(DomainEventKind::SessionRevoked {
+                user_id: *claims.sub(),
+                reason: RevocationReason::Logout,
+            });
         }
 
         Ok(MessageResponse {
@@ -178,13 +607,119 @@ where
         })
     }
 
+    pub async fn profile(&self, claims: &AccessTokenClaims) -> Result<ProfileResponse, AppError> {
+        let user_id = *claims.sub();
+        let (user, grants) = tokio::try_join!(
+            self.auth_repo.get_user_by_id(user_id),
+            self.auth_repo.get_grants(user_id)
+        )?;
+        Ok(ProfileResponse::new(user, grants))
+    }
+
+    pub async fn introspect(&self, token: &str) -> Result<IntrospectionResponse, AppError> {
+        let claims = self.jwt_service.introspect_access(token).await?;
+        Ok(IntrospectionResponse::new(claims))
+    }
+
+    pub async fn credentials(
+        &self,
+        claims: &AccessTokenClaims,
+    ) -> Result<CredentialListResponse, AppError> {
+        let credentials = self.auth_repo.list_credentials(*claims.sub()).await?;
+        Ok(CredentialListResponse::new(credentials))
+    }
+
+    /// Unlocks a passkey locked as possibly cloned, once its owner confirmed
+    /// from another session that the key is theirs. Its counter starts over.
+    pub async fn unlock_credential(
+        &self,
+        claims: &AccessTokenClaims,
+        credential_id: &str,
+        ctx: &AuditContext,
+    ) -> Result<MessageResponse, AppError> {
+        let user_id = *claims.sub();
+        let result = match BASE64_URL_SAFE_NO_PAD.decode(credential_id) {
+            Ok(cred_id) => self.auth_repo.unlock_credential(user_id, &cred_id).await,
+            Err(_) => Err(AppError::BadRequest(String::from(
+                "Credential id must be unpadded base64url",
+            ))),
+        };
+
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::CredentialUnlocked,
+                ctx,
+                Some(claims.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(user_id)
+            .with_details(serde_json::json!({ "credential_id": credential_id })),
+        );
+        result?;
+
+        Ok(MessageResponse {
+            message: String::from("Passkey unlocked successfully!"),
+        })
+    }
+
+    /// Refresh tokens are revoked before anything is deleted, so a failure
+    /// leaves the account intact rather than deleted with live sessions.
+    /// Signed access tokens already issued stay valid until they expire.
+    pub async fn delete_account(
+        &self,
+        claims: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<MessageResponse, AppError> {
+        let user_id = *claims.sub();
+        let result = match self.jwt_service.revoke_subject(user_id).await {
+            Ok(()) => self.auth_repo.delete_account(user_id).await,
+            Err(e) => Err(e),
+        };
+
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AccountDeleted,
+                ctx,
+                Some(claims.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(user_id),
+        );
+        result?;
+        self.publish(
+            user_id,
+            AuthEventKind::SessionRevoked {
+                reason: RevocationReason::AccountDeleted,
+            },
+        );
+        self.export(DomainEventKind::SessionRevoked {
+            user_id,
+            reason: RevocationReason::AccountDeleted,
+        });
+
+        Ok(MessageResponse {
+            message: String::from("Account deleted successfully!"),
+        })
+    }
+
     pub async fn check_health(&self) -> Result<HealthResponse, AppError> {
         let timestamp = chrono::Utc::now().to_rfc3339();
-        let (db_health, redis_health) =
-            tokio::join!(self.auth_repo.check_db(), self.jwt_service.check_redis(),);
+        let regions = join_all(self.regions.iter().map(|region| async move {
+            let health = scope_region(Some(region.to_string()), self.auth_repo.check_db()).await;
+            (region.to_string(), health)
+        }));
+        let (db_health, redis_health, regions) = tokio::join!(
+            scope_region(None, self.auth_repo.check_db()),
+            self.jwt_service.check_redis(),
+            regions
+        );
+        let regions: BTreeMap<String, ServiceHealth> = regions.into_iter().collect();
 
         if db_health.status == HealthStatus::Unhealthy
             || redis_health.status == HealthStatus::Unhealthy
+            || regions
+                .values()
+                .any(|health| health.status == HealthStatus::Unhealthy)
         {
             let mut error_details = Vec::new();
 
@@ -192,6 +727,12 @@ where
                 error_details.push(format!("Database: {}", db_health.message));
             }
 
+            for (region, health) in &regions {
+                if health.status == HealthStatus::Unhealthy {
+                    error_details.push(format!("Database {}: {}", region, health.message));
+                }
+            }
+
             if redis_health.status == HealthStatus::Unhealthy {
                 error_details.push(format!("Redis: {}", redis_health.message));
             }
@@ -207,64 +748,762 @@ where
             checks: HealthChecks {
                 database: db_health,
                 redis: redis_health,
+                regions,
             },
         })
     }
 
-    async fn prepare_session_data<T, U>(
+    pub async fn check_startup(&self) -> Result<StartupResponse, AppError> {
+        let migrations = self.auth_repo.check_migrations().await?;
+
+        if !migrations.is_complete() {
+            tracing::warn!(pending = ?migrations.pending, "Database migrations pending");
+        }
+
+        Ok(StartupResponse {
+            status: if migrations.is_complete() {
+                HealthStatus::Healthy
+            } else {
+                HealthStatus::Unhealthy
+            },
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            applied_migrations: migrations.applied.iter().map(|m| m.to_string()).collect(),
+            pending_migrations: migrations.pending.iter().map(|m| m.to_string()).collect(),
+        })
+    }
+
+    async fn complete_registration(
         &self,
-        session_obj: T,
-        options_obj: U,
-    ) -> Result<(serde_json::Value, serde_json::Value), AppError>
-    where
-        T: serde::Serialize + Send,
-        U: serde::Serialize + Send,
-    {
-        let (session_data, opts) =
-            tokio::join!(async { serde_json::to_value(session_obj) }, async {
-                serde_json::to_value(options_obj)
+        req: FinishRequest,
+    ) -> Result<RegistrationResponse, AppError> {
+        let extensions = extensions::client_outputs(self.extensions, &req.credentials)?;
+        let (session_id, user, passkey, aaguid, credential, clock, invitation) =
+            self.finish_passkey_enrollment(req, "registration").await?;
+        let recovery_codes = RecoveryCode::generate_batch(RECOVERY_CODE_COUNT);
+
+        if let Some(id) = invitation {
+            self.redeem_invitation(id, &user.username).await?;
+        }
+        if let Err(e) = self
+            .auth_repo
+            .complete_registration(
+                user.id,
+                &user.username,
+                &passkey,
+                aaguid,
+                &Self::hash_recovery_codes(&recovery_codes),
+                self.verifier.is_none(),
+            )
+            .await
+        {
+            self.release_invitation(invitation).await;
+            return Err(e);
+        }
+        self.cleanup_session(session_id);
+        self.notify_passkey_registered(&user, &passkey, false);
+        self.export(DomainEventKind::UserRegistered {
+            user_id: user.id,
+            username: user.username.clone(),
+        });
+
+        let verification_pending = match &self.verifier {
+            Some(verifier) => {
+                let progress = verifier.mark_enrolled(user.id).await?;
+                if progress.is_complete() {
+                    self.auth_repo.activate_pending_user(&user.username).await?;
+                }
+                !progress.is_complete()
+            }
+            None => false,
+        };
+
+        clock.succeed();
+        Ok(RegistrationResponse {
+            message: String::from(if verification_pending {
+                "Passkey registered, verify your email to activate the account."
+            } else {
+                "Registration completed successfully!"
+            }),
+            recovery_codes: recovery_codes.into_iter().map(String::from).collect(),
+            verification_pending,
+            credential,
+            extensions,
+        })
+    }
+
+    async fn confirm_email(&self, req: VerifyEmailRequest) -> Result<MessageResponse, AppError> {
+        let Some(verifier) = &self.verifier else {
+            return Err(AppError::NotFound(String::from(
+                "Email verification is not enabled",
+            )));
+        };
+
+        let user = self.auth_repo.get_user_by_username(&req.username).await?;
+        let progress = verifier.confirm(user.id, &req.token).await?;
+        if progress.is_complete() {
+            self.auth_repo.activate_pending_user(&user.username).await?;
+        }
+
+        Ok(MessageResponse {
+            message: String::from(if progress.is_complete() {
+                "Email verified, the account is now active!"
+            } else {
+                "Email verified, register a passkey to activate the account."
+            }),
+        })
+    }
+
+    async fn start_recovery(&self, req: RecoveryRequest) -> Result<BeginResponse, AppError> {
+        let state = self.auth_repo.get_recovery_state(&req.username).await?;
+        let user = state.user;
+
+        if let Some(locked_until) = state.locked_until {
+            let remaining = (locked_until - Utc::now()).num_seconds();
+            if remaining > 0 {
+                return Err(AppError::TooManyRequests(remaining as u64));
+            }
+        }
+
+        let code_hash = RecoveryCode::hash_input(&req.recovery_code);
+        if !self
+            .auth_repo
+            .consume_recovery_code(user.id, &code_hash)
+            .await?
+        {
+            let lock_until = Utc::now() + Duration::minutes(RECOVERY_LOCKOUT_MINUTES);
+            if self
+                .auth_repo
+                .record_recovery_failure(user.id, MAX_RECOVERY_ATTEMPTS, lock_until)
+                .await?
+            {
+                tracing::warn!(username = %user.username, "Account recovery locked");
+                return Err(AppError::TooManyRequests(
+                    (RECOVERY_LOCKOUT_MINUTES * 60) as u64,
+                ));
+            }
+            return Err(AppError::Unauthorized(String::from(
+                "Invalid recovery code",
+            )));
+        }
+
+        self.auth_repo.reset_recovery_failures(user.id).await?;
+
+        let extensions = extensions::registration_inputs(self.extensions, None)?;
+        // Recovery replaces every passkey, the lost authenticator's included.
+        self.start_enrollment(&user, "recovery", extensions, Vec::new(), None)
+            .await
+    }
+
+    async fn complete_recovery(
+        &self,
+        req: FinishRequest,
+    ) -> Result<RegistrationResponse, AppError> {
+        let extensions = extensions::client_outputs(self.extensions, &req.credentials)?;
+        let (session_id, user, passkey, aaguid, credential, clock, _) =
+            self.finish_passkey_enrollment(req, "recovery").await?;
+        let recovery_codes = RecoveryCode::generate_batch(RECOVERY_CODE_COUNT);
+
+        self.auth_repo
+            .complete_recovery(
+                user.id,
+                &passkey,
+                aaguid,
+                &Self::hash_recovery_codes(&recovery_codes),
+            )
+            .await?;
+        self.cleanup_session(session_id);
+        self.notify_passkey_registered(&user, &passkey, true);
+        clock.succeed();
+
+        Ok(RegistrationResponse {
+            message: String::from("Account recovery completed successfully!"),
+            recovery_codes: recovery_codes.into_iter().map(String::from).collect(),
+            verification_pending: false,
+            credential,
+            extensions,
+        })
+    }
+
+    async fn complete_login(
+        &self,
+        req: FinishRequest,
+        ctx: &AuditContext,
+    ) -> Result<(TokenResponse, RefreshToken), AppError> {
+        let (session_id, user, passkey_authentication, clock) = self
+            .load_ceremony::<PasskeyAuthentication>(&req.session_id, &req.username, "login")
+            .await?;
+        let credentials = parse_credentials::<PublicKeyCredential>(&req.credentials, "login")?;
+        let extensions = extensions::client_outputs(self.extensions, &req.credentials)?;
+
+        let permit = self.ceremony_limiter.acquire("login").await?;
+        let verified = self
+            .relying_parties
+            .current()
+            .finish_passkey_authentication(&credentials, &passkey_authentication);
+        drop(permit);
+        let result = match verified {
+            Err(WebauthnError::CredentialPossibleCompromise) => {
+                self.cleanup_session(session_id);
+                return Err(self
+                    .lock_cloned_credential(&user, credentials.raw_id.as_slice())
+                    .await);
+            }
+            result => result.map_err(passkey_rejected)?,
+        };
+
+        // Written back even when nothing changed, since it also records
+        // when the credential was last used.
+        self.auth_repo
+            .update_credential(&user.username, &result)
+            .await?;
+
+        self.cleanup_session(session_id);
+
+        let device = SessionDevice {
+            name: req.device_name,
+            trusted: req.trusted,
+            azp: self
+                .client_apps
+                .authorized_party(ctx.client_app.as_deref())
+                .map(str::to_owned),
+        };
+        let grants = self.auth_repo.get_grants(user.id).await?;
+        let token_pair = self
+            .jwt_service
+            .generate_token_pair(user.id, &user.username, grants, &device)
+            .await?;
+        self.log_issuance(user.id, &token_pair, GrantType::Login, None, ctx);
+        self.export(DomainEventKind::LoginSucceeded {
+            user_id: user.id,
+            username: user.username.clone(),
+            client_app: device.azp.clone(),
+            trusted: device.trusted,
+        });
+        self.publish(
+            user.id,
+            AuthEventKind::NewLogin {
+                device_name: device.name,
+                trusted: device.trusted,
+            },
+        );
+        if let Some(login_history) = &self.login_history {
+            login_history.record(user.id, &user.username, ctx);
+        }
+        clock.succeed();
+
+        Ok((
+            TokenResponse {
+                message: String::from("Login completed successfully!"),
+                access_token: token_pair.access_token,
+                credential: Some(CredentialInfo {
+                    discoverable: None,
+                    user_verified: result.user_verified(),
+                }),
+                extensions,
+            },
+            token_pair.refresh_token,
+        ))
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        claims: &RefreshTokenClaims,
+        device: &SessionDevice,
+        grant_type: GrantType,
+        ctx: &AuditContext,
+    ) -> Result<(TokenResponse, RefreshToken), AppError> {
+        // Reloaded so role changes reach the user by the next refresh.
+        let grants = self.auth_repo.get_grants(*claims.sub()).await?;
+        self.jwt_service
+            .blacklist(claims.jti(), claims.exp())
+            .await?;
+
+        let token_pair = self
+            .jwt_service
+            .generate_token_pair(claims.sub().to_owned(), claims.username(), grants, device)
+            .await?;
+        self.log_issuance(
+            *claims.sub(),
+            &token_pair,
+            grant_type,
+            Some(claims.jti()),
+            ctx,
+        );
+        Ok((
+            TokenResponse {
+                message: String::from("Refresh completed successfully!"),
+                access_token: token_pair.access_token,
+                credential: None,
+                extensions: None,
+            },
+            token_pair.refresh_token,
+        ))
+    }
+
+    /// The role a registration gets and the invitation admitting it, which
+    /// is only checked here and redeemed when the registration finishes. Roles
+    /// outside the policy are refused. Privileged roles, and every
+    /// registration in invite-only mode, need an administrator or an
+    /// invitation; only an invitation naming a privileged role grants it.
+    /// When the request names no role, the invitation's applies.
+    async fn admit(
+        &self,
+        req: &BeginRequest,
+        registrar: Option<&AccessTokenClaims>,
+    ) -> Result<(Option<String>, Option<Uuid>), AppError> {
+        if let Some(role) = req.role.as_deref() {
+            self.check_role(role)?;
+        }
+        let privileged = req
+            .role
+            .as_deref()
+            .filter(|role| self.role_policy.is_privileged(role));
+        if (privileged.is_none() && !self.role_policy.invite_only)
+            || registrar.is_some_and(|claims| claims.grants().allows(AdminActions::SCOPE))
+        {
+            return Ok((req.role.clone(), None));
+        }
+
+        let usable = match (&self.invitations, req.invitation.as_deref()) {
+            (Some(invitations), Some(code)) => invitations.check(code, req.role.as_deref()).await?,
+            _ => None,
+        };
+        let Some(invitation) = usable else {
+            return Err(match privileged {
+                Some(role) => role_not_allowed(role),
+                None => AppError::Forbidden(String::from("Registration needs an invitation"))
+                    .with_code(ErrorCode::InvitationRequired),
             });
-        Ok((session_data?, opts?))
+        };
+
+        let role = req.role.clone().or_else(|| invitation.role.clone());
+        match role.as_deref() {
+            Some(role)
+                if self.role_policy.is_privileged(role)
+                    && invitation.role.as_deref() != Some(role) =>
+            {
+                return Err(role_not_allowed(role));
+            }
+            Some(role) => self.check_role(role)?,
+            None => {}
+        }
+        Ok((role, Some(invitation.id)))
     }
 
-    async fn create_session_response(
+    fn check_role(&self, role: &str) -> Result<(), AppError> {
+        if self.role_policy.is_allowed(role) {
+            Ok(())
+        } else {
+            Err(AppError::BadRequest(format!("Unknown role: {}", role)))
+        }
+    }
+
+    /// Another registration may have spent a single-use invitation, or it
+    /// was revoked or expired, since the registration began.
+    async fn redeem_invitation(&self, id: Uuid, username: &str) -> Result<(), AppError> {
+        let redeemed = match &self.invitations {
+            Some(invitations) => invitations.redeem(id, username).await?,
+            None => false,
+        };
+        if !redeemed {
+            return Err(
+                AppError::Forbidden(String::from("The invitation is no longer usable"))
+                    .with_code(ErrorCode::InvitationRequired),
+            );
+        }
+        Ok(())
+    }
+
+    async fn release_invitation(&self, invitation: Option<Uuid>) {
+        if let (Some(invitations), Some(id)) = (&self.invitations, invitation)
+            && let Err(e) = invitations.release(id).await
+        {
+            tracing::error!("Failed to release invitation {}: {}", id, e);
+        }
+    }
+
+    async fn check_throttle(&self, username: &str) -> Result<(), AppError> {
+        match &self.account_throttle {
+            Some(throttle) => throttle.check(username).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Runs a finish step unless the account has to wait, counting its
+    /// failure against the account or forgetting the earlier ones.
+    async fn throttled<T>(
+        &self,
+        username: &str,
+        flow: &str,
+        attempt: impl Future<Output = Result<T, AppError>>,
+    ) -> Result<T, AppError> {
+        let Some(throttle) = &self.account_throttle else {
+            return attempt.await;
+        };
+        throttle.check(username).await?;
+
+        let result = attempt.await;
+        match &result {
+            Ok(_) => throttle.reset(username).await,
+            Err(e) if counts_as_failure(e) => throttle.record_failure(username, flow).await,
+            Err(_) => {}
+        }
+        result
+    }
+
+    fn log_issuance(
         &self,
         user_id: Uuid,
-        session_data: serde_json::Value,
-        opts: serde_json::Value,
+        token_pair: &TokenPair,
+        grant_type: GrantType,
+        rotated_from: Option<&str>,
+        ctx: &AuditContext,
+    ) {
+        if let Some(issuance_log) = &self.issuance_log {
+            let issuance = TokenIssuance::new(user_id, token_pair, grant_type, ctx);
+            issuance_log.record(match rotated_from {
+                Some(jti) => issuance.with_rotated_from(jti),
+                None => issuance,
+            });
+        }
+    }
+
+    /// Stores the ceremony state and returns the options for the client. Each
+    /// is serialized exactly once, with no intermediate `Value` unless
+    /// extensions have to be merged into the options.
+    async fn create_session_response<S, O>(
+        &self,
+        user_id: Uuid,
+        state: &S,
+        options: &O,
+        extensions: Extensions,
+        rp_icon: Option<&str>,
         session_type: &str,
-    ) -> Result<BeginResponse, AppError> {
-        let session_id = self
-            .auth_repo
-            .create_webauthn_session(user_id, session_data, session_type)
-            .await?;
+    ) -> Result<BeginResponse, AppError>
+    where
+        S: serde::Serialize + std::fmt::Debug + Sync,
+        O: serde::Serialize,
+    {
+        let options = extensions::options_with_extensions(options, extensions, rp_icon)?;
+        let session_id = match &self.sealer {
+            Some(sealer) => sealer.seal(user_id, session_type, state, Utc::now().timestamp())?,
+            None => self
+                .auth_repo
+                .create_webauthn_session(user_id, state, session_type)
+                .await?
+                .to_string(),
+        };
 
         Ok(BeginResponse {
-            options: opts,
-            session_id: String::from(session_id),
+            options,
+            session_id,
         })
     }
 
-    async fn get_user_and_session(
+    /// Loads the user and ceremony state for `session_id`. The id is `None`
+    /// for sealed state, which has no row to delete afterwards; its nonce is
+    /// spent here instead, so each sealed session can be finished once.
+    async fn load_ceremony<T: DeserializeOwned>(
         &self,
-        session_id_str: &str,
+        session_id: &str,
         username: &str,
         session_type: &str,
-    ) -> Result<(Uuid, crate::auth::model::User, WebAuthnSession), AppError> {
-        let session_id = Uuid::try_parse(session_id_str)?;
-        let (user, session) = self
+    ) -> Result<(Option<Uuid>, User, T, CeremonyClock), AppError> {
+        let Some(sealer) = &self.sealer else {
+            let session_id = Uuid::try_parse(session_id)?;
+            let (user, session) = self
+                .auth_repo
+                .get_user_and_session(session_id, username, session_type)
+                .await?;
+            let clock = CeremonyClock::new(session_type, session.created_at);
+            let state = serde_json::from_value(session.data)?;
+            return Ok((Some(session_id), user, state, clock));
+        };
+
+        let now = Utc::now().timestamp();
+        let ceremony = sealer.open::<T>(session_id, session_type, now)?;
+        let started_at =
+            DateTime::from_timestamp(sealer.started_at(&ceremony), 0).unwrap_or_else(Utc::now);
+        let clock = CeremonyClock::new(session_type, started_at);
+        let user = self.auth_repo.get_user_by_username(username).await?;
+        if user.id != ceremony.user_id {
+            return Err(AppError::NotFound(String::from(
+                "User or session not found",
+            )));
+        }
+
+        if !self
+            .nonces
+            .consume(ceremony.nonce, (ceremony.exp - now) as u64)
+            .await?
+        {
+            return Err(AppError::Unauthorized(String::from("Session already used")));
+        }
+        Ok((None, user, ceremony.state, clock))
+    }
+
+    /// The ids of the user's passkeys, for the authenticator to refuse
+    /// creating a second credential next to one of them.
+    async fn registered_credentials(&self, user_id: Uuid) -> Result<Vec<CredentialID>, AppError> {
+        Ok(self
             .auth_repo
-            .get_user_and_session(session_id, username, session_type)
+            .list_credentials(user_id)
+            .await?
+            .into_iter()
+            .map(|stored| stored.passkey.cred_id().clone())
+            .collect())
+    }
+
+    /// Users whose roles allowlist authenticator models must register with
+    /// attestation, checked against the configured vendor roots. `exclude`
+    /// lists the credentials the authenticator must not register again;
+    /// `invitation` is kept with the state, to be redeemed at the finish.
+    async fn start_enrollment(
+        &self,
+        user: &User,
+        session_type: &str,
+        extensions: Extensions,
+        exclude: Vec<CredentialID>,
+        invitation: Option<Uuid>,
+    ) -> Result<BeginResponse, AppError> {
+        let exclude = (!exclude.is_empty()).then_some(exclude);
+        let policy = self.auth_repo.get_aaguid_policy(user.id).await?;
+        if !policy.is_restricted() {
+            let (ccr, state) = self.relying_parties.current().start_passkey_registration(
+                user.id,
+                &user.username,
+                &user.username,
+                exclude,
+            )?;
+            return self
+                .create_session_response(
+                    user.id,
+                    &StoredEnrollment {
+                        state: EnrollmentState::Passkey(state),
+                        invitation,
+                    },
+                    &ccr,
+                    extensions,
+                    self.relying_parties.icon(),
+                    session_type,
+                )
+                .await;
+        }
+
+        let Some(cas) = &self.attestation_cas else {
+            return Err(AppError::InternalServer(String::from(
+                "WEBAUTHN_ATTESTATION_CA_FILE is required by roles with an AAGUID allowlist",
+            )));
+        };
+        let (ccr, state) = self
+            .relying_parties
+            .current()
+            .start_attested_passkey_registration(
+                user.id,
+                &user.username,
+                &user.username,
+                exclude,
+                cas.clone(),
+                None,
+            )?;
+        self.create_session_response(
+            user.id,
+            &StoredEnrollment {
+                state: EnrollmentState::Attested(state),
+                invitation,
+            },
+            &ccr,
+            extensions,
+            self.relying_parties.icon(),
+            session_type,
+        )
+        .await
+    }
+
+    /// The policy is read again rather than trusted from the begin step, so
+    /// an allowlist added in between still applies. Also returns the AAGUID
+    /// to label the credential with, attested or merely reported, and the
+    /// invitation the enrollment was started with.
+    async fn finish_passkey_enrollment(
+        &self,
+        req: FinishRequest,
+        session_type: &str,
+    ) -> Result<
+        (
+            Option<Uuid>,
+            User,
+            Passkey,
+            Option<Uuid>,
+            CredentialInfo,
+            CeremonyClock,
+            Option<Uuid>,
+        ),
+        AppError,
+    > {
+        let (session_id, user, stored, clock) = self
+            .load_ceremony::<StoredEnrollment>(&req.session_id, &req.username, session_type)
             .await?;
-        Ok((session_id, user, session))
+        let credentials =
+            parse_credentials::<RegisterPublicKeyCredential>(&req.credentials, session_type)?;
+        if session_type == "registration" {
+            self.check_not_registered(&user, credentials.raw_id.as_slice())
+                .await?;
+        }
+        let policy = self.auth_repo.get_aaguid_policy(user.id).await?;
+
+        let permit = self.ceremony_limiter.acquire(session_type).await?;
+        let (passkey, aaguid) = match stored.state {
+            EnrollmentState::Passkey(state) => {
+                let passkey = self
+                    .relying_parties
+                    .current()
+                    .finish_passkey_registration(&credentials, &state)
+                    .map_err(passkey_rejected)?;
+                policy.check(None)?;
+                (passkey, reported_aaguid(&credentials))
+            }
+            EnrollmentState::Attested(state) => {
+                let passkey = self
+                    .relying_parties
+                    .current()
+                    .finish_attested_passkey_registration(&credentials, &state)
+                    .map_err(passkey_rejected)?;
+                let aaguid = attested_aaguid(&passkey);
+                policy.check(aaguid)?;
+                (
+                    passkey.into(),
+                    aaguid.or_else(|| reported_aaguid(&credentials)),
+                )
+            }
+        };
+        drop(permit);
+
+        // Passkeys are registered with user verification required, so a
+        // finished ceremony always verified the user.
+        let credential = CredentialInfo {
+            discoverable: credentials.extensions.cred_props.and_then(|props| props.rk),
+            user_verified: true,
+        };
+
+        Ok((
+            session_id,
+            user,
+            passkey,
+            aaguid,
+            credential,
+            clock,
+            stored.invitation,
+        ))
+    }
+
+    /// An authenticator that ignored the exclude list answers with a
+    /// credential the user already has, which webauthn-rs only reports as an
+    /// algorithm mismatch.
+    async fn check_not_registered(&self, user: &User, raw_id: &[u8]) -> Result<(), AppError> {
+        let registered = self.registered_credentials(user.id).await?;
+        if registered.iter().any(|id| id.as_slice() == raw_id) {
+            return Err(AppError::AlreadyExists(String::from(
+                "This authenticator is already registered",
+            ))
+            .with_code(ErrorCode::CredentialAlreadyRegistered));
+        }
+        Ok(())
+    }
+
+    /// webauthn-rs refuses a login whose signature counter did not move past
+    /// the stored one, since two copies of the key may be signing. The
+    /// signature was verified first, so the credential id can be trusted.
+    async fn lock_cloned_credential(&self, user: &User, cred_id: &[u8]) -> AppError {
+        track_clone_suspected();
+        let credential_id = BASE64_URL_SAFE_NO_PAD.encode(cred_id);
+        tracing::warn!(
+            user_id = %user.id,
+            credential_id = %credential_id,
+            "Signature counter went backwards, locking possibly cloned passkey"
+        );
+
+        match self
+            .auth_repo
+            .lock_cloned_credential(user.id, cred_id)
+            .await
+        {
+            Ok(true) => self.publish(user.id, AuthEventKind::CloneSuspected { credential_id }),
+            Ok(false) => {}
+            Err(e) => {
+                tracing::error!("Failed to lock passkey {}: {}", credential_id, e);
+                return e;
+            }
+        }
+        credential_locked(cred_id)
+    }
+
+    fn hash_recovery_codes(codes: &[RecoveryCode]) -> Vec<Vec<u8>> {
+        codes.iter().map(RecoveryCode::hash).collect()
+    }
+
+    fn notify_passkey_registered(&self, user: &User, passkey: &Passkey, recovery: bool) {
+        let credential_id = BASE64_URL_SAFE_NO_PAD.encode(passkey.cred_id().as_slice());
+        self.notifier.dispatch(Notification::new(
+            NotificationEvent::PasskeyRegistered,
+            user.id,
+            &user.username,
+            serde_json::json!({
+                "credential_id": credential_id,
+                "recovery": recovery,
+            }),
+        ));
+        self.publish(
+            user.id,
+            AuthEventKind::CredentialAdded {
+                credential_id,
+                recovery,
+            },
+        );
+    }
+
+    fn publish(&self, user_id: Uuid, kind: AuthEventKind) {
+        if let Some(events) = &self.events {
+            events.publish(user_id, kind);
+        }
+    }
+
+    fn export(&self, kind: DomainEventKind) {
+        if let Some(exporter) = &self.exporter {
+            exporter.export(kind);
+        }
     }
 
-    fn cleanup_session(&self, session_id: Uuid) {
+    fn cleanup_session(&self, session_id: Option<Uuid>) {
+        let Some(session_id) = session_id else {
+            return;
+        };
         let auth_repo = Arc::clone(&self.auth_repo);
-        tokio::spawn(async move {
-            if let Err(e) = auth_repo.delete_webauthn_session(session_id).await {
-                tracing::error!("Failed to delete webauthn session {}: {}", session_id, e);
+        tokio::spawn(
+            async move {
+                if let Err(e) = auth_repo.delete_webauthn_session(session_id).await {
+                    tracing::error!("Failed to delete webauthn session {}: {}", session_id, e);
+                }
             }
-        });
+            .in_current_span(),
+        );
     }
 }
+
+fn role_not_allowed(role: &str) -> AppError {
+    AppError::Forbidden(format!(
+        "Registering as {} needs an administrator or an invitation",
+        role
+    ))
+    .with_code(ErrorCode::RoleNotAllowed)
+    .with_detail("role", role)
+}
+
+/// Parses the raw credential JSON straight into its WebAuthn type, the only
+/// time the payload is deserialized.
+fn parse_credentials<T: DeserializeOwned>(
+    credentials: &RawValue,
+    ceremony: &str,
+) -> Result<T, AppError> {
+    track_credential_payload(ceremony, credentials.get().len());
+    Ok(serde_json::from_str(credentials.get())?)
+}
@@ -0,0 +1,1016 @@
+use std::{fmt::Debug, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction, types::Json};
+use uuid::Uuid;
+use webauthn_rs::prelude::AuthenticationResult;
+
+use crate::{
+    app::{
+        AppError, ErrorCode,
+        context::{current_region, current_tenant},
+        deadline::check_deadline,
+        middleware::metrics::{
+            track_replica_read, update_db_pool_stats, update_db_region_pool_stats,
+        },
+    },
+    auth::{
+        attestation::AaguidPolicy,
+        dto::ServiceHealth,
+        model::{
+            Grants, MigrationStatus, RecoveryState, StoredCredential, User, UserStatus,
+            WebAuthnSession, account_suspended,
+        },
+        passkey_format::{
+            apply_authentication, credential_locked, reset_counter, unlocked_passkeys,
+        },
+        traits::AuthRepository,
+    },
+    config::CircuitBreaker,
+    db_delete, db_insert, db_select, db_update,
+    utils::{MIGRATIONS, check_database_health, normalize_username},
+};
+
+/// `AuthRepository` on sqlx, enabled by the `sqlx` feature. Every query is
+/// checked against `DATABASE_URL` at build time, or against the prepared
+/// metadata in `.sqlx/` when no database is reachable.
+pub struct SqlxRepository {
+    db: PgPool,
+    circuit_breaker: Arc<CircuitBreaker>,
+    replica: Option<(PgPool, Arc<CircuitBreaker>)>,
+    regions: Vec<(Box<str>, PgPool, Arc<CircuitBreaker>)>,
+}
+
+impl SqlxRepository {
+    pub fn new(db: PgPool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            db,
+            circuit_breaker,
+            replica: None,
+            regions: Vec::new(),
+        }
+    }
+
+    /// Looks users up by username on `replica` when there is one, as
+    /// `Repository::with_replica` does.
+    pub fn with_replica(mut self, replica: Option<(PgPool, Arc<CircuitBreaker>)>) -> Self {
+        self.replica = replica;
+        self
+    }
+
+    /// Routes requests resolved to a region to its database, as
+    /// `Repository::with_regions` does.
+    pub fn with_regions(mut self, regions: Vec<(Box<str>, PgPool, Arc<CircuitBreaker>)>) -> Self {
+        self.regions = regions;
+        self
+    }
+
+    /// The pool and breaker of the current request's region, the home ones
+    /// without a region or without any configured.
+    fn target(&self) -> Result<(Option<&str>, &PgPool, &Arc<CircuitBreaker>), AppError> {
+        let home = (None, &self.db, &self.circuit_breaker);
+        if self.regions.is_empty() {
+            return Ok(home);
+        }
+        let Some(name) = current_region() else {
+            return Ok(home);
+        };
+
+        self.regions
+            .iter()
+            .find(|(region, _, _)| **region == *name)
+            .map(|(region, db, circuit_breaker)| (Some(&**region), db, circuit_breaker))
+            .ok_or_else(|| AppError::InternalServer(format!("No database for region {}", name)))
+    }
+
+    async fn execute_with_circuit_breaker<F, Fut, T>(&self, operation: F) -> Result<T, AppError>
+    where
+        F: FnOnce(PgPool) -> Fut + Send,
+        Fut: std::future::Future<Output = Result<T, AppError>> + Send,
+        T: Send,
+    {
+        check_deadline("postgres")?;
+        let (_, db, circuit_breaker) = self.target()?;
+        let db = db.clone();
+
+        circuit_breaker
+            .call(|| async move { operation(db).await })
+            .await
+    }
+
+    async fn select_user_by_username(
+        db: &PgPool,
+        normalized: &str,
+        tenant: &str,
+    ) -> Result<Option<User>, AppError> {
+        Ok(db_select!("users", {
+            sqlx::query_as!(
+                User,
+                "SELECT id, username, status, created_at, updated_at, is_active
+                     FROM users WHERE normalized_username = $1 AND tenant_id = $2",
+                normalized,
+                tenant
+            )
+            .fetch_optional(db)
+            .await
+        })?)
+    }
+
+    async fn create_credential(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        passkey: &webauthn_rs::prelude::Passkey,
+        aaguid: Option<Uuid>,
+        tenant: &str,
+    ) -> Result<(), AppError> {
+        let passkey_json = serde_json::to_value(passkey)?;
+
+        db_insert!("credentials", {
+            sqlx::query!(
+                "INSERT INTO credentials (id, user_id, passkey, aaguid, tenant_id)
+                 VALUES ($1, $2, $3, $4, $5)",
+                passkey.cred_id().as_slice(),
+                user_id,
+                passkey_json,
+                aaguid as Option<Uuid>,
+                tenant
+            )
+            .execute(&mut **tx)
+            .await
+        })?;
+
+        Ok(())
+    }
+
+    async fn assign_role(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        role: &str,
+    ) -> Result<(), AppError> {
+        let result = db_insert!("user_roles", {
+            sqlx::query!(
+                "INSERT INTO user_roles (user_id, role)
+                 SELECT $1, name FROM roles WHERE name = $2",
+                user_id,
+                role
+            )
+            .execute(&mut **tx)
+            .await
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::BadRequest(format!("Unknown role: {}", role)));
+        }
+        Ok(())
+    }
+
+    async fn replace_recovery_codes(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        code_hashes: &[Vec<u8>],
+    ) -> Result<(), AppError> {
+        db_delete!("recovery_codes", {
+            sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1", user_id)
+                .execute(&mut **tx)
+                .await
+        })?;
+
+        db_insert!("recovery_codes", {
+            sqlx::query!(
+                "INSERT INTO recovery_codes (user_id, code_hash)
+                 SELECT $1, UNNEST($2::bytea[])",
+                user_id,
+                code_hashes
+            )
+            .execute(&mut **tx)
+            .await
+        })?;
+
+        Ok(())
+    }
+}
+
+impl AuthRepository for SqlxRepository {
+    async fn check_db(&self) -> ServiceHealth {
+        update_db_pool_stats(
+            self.db.size() as usize,
+            self.db.num_idle(),
+            self.db.options().get_max_connections() as usize,
+        );
+        for (region, db, _) in &self.regions {
+            update_db_region_pool_stats(
+                region,
+                db.size() as usize,
+                db.num_idle(),
+                db.options().get_max_connections() as usize,
+            );
+        }
+        check_database_health(|| {
+            self.execute_with_circuit_breaker(|db| async move {
+                sqlx::query!("SELECT 1 AS health_check")
+                    .fetch_one(&db)
+                    .await?;
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    async fn check_migrations(&self) -> Result<MigrationStatus, AppError> {
+        let tables: Vec<String> = MIGRATIONS
+            .iter()
+            .map(|migration| migration.table.to_owned())
+            .collect();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let missing = db_select!("pg_class", {
+                sqlx::query_scalar!(
+                    r#"SELECT name AS "name!"
+                       FROM UNNEST($1::text[]) AS name
+                       WHERE CASE WHEN name LIKE '%(%'
+                                  THEN to_regprocedure(name) IS NULL
+                                  ELSE to_regclass(name) IS NULL
+                             END"#,
+                    &tables
+                )
+                .fetch_all(&db)
+                .await
+            })?;
+
+            Ok(MigrationStatus::from_missing_tables(&missing))
+        })
+        .await
+    }
+
+    /// sqlx caches each statement on its connection the first time it runs.
+    async fn warm_up(&self, _connections: usize) -> Result<usize, AppError> {
+        Ok(0)
+    }
+
+    async fn register_tenants(&self, tenants: &[Box<str>]) -> Result<(), AppError> {
+        let tenants: Vec<String> = tenants.iter().map(|tenant| tenant.to_string()).collect();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            db_insert!("tenants", {
+                sqlx::query!(
+                    "INSERT INTO tenants (id)
+                     SELECT UNNEST($1::text[])
+                     ON CONFLICT (id) DO NOTHING",
+                    &tenants
+                )
+                .execute(&db)
+                .await
+            })?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn create_user(&self, username: &str, role: Option<&str>) -> Result<User, AppError> {
+        match self.get_user_by_username(username).await {
+            // A registration started again picks its pending user back up,
+            // unless a role is involved: taking over a pending user that
+            // holds one would grant it without the invitation or
+            // administrator that admitted the first registration.
+            Ok(user) => {
+                if user.status == UserStatus::Pending.as_str()
+                    && role.is_none()
+                    && self.get_grants(user.id).await?.roles.is_empty()
+                {
+                    return Ok(user);
+                }
+                return Err(
+                    AppError::AlreadyExists(String::from("Username already exists"))
+                        .with_code(ErrorCode::UsernameTaken),
+                );
+            }
+            Err(AppError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let normalized = normalize_username(username);
+        let username = username.to_string();
+        let role = role.map(str::to_owned);
+        let tenant = current_tenant();
+        let region = current_region();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let mut tx = db.begin().await?;
+
+            let user = db_insert!("users", {
+                sqlx::query_as!(
+                    User,
+                    "INSERT INTO users (username, normalized_username, tenant_id, region)
+                     VALUES ($1, $2, $3, $4)
+                     RETURNING id, username, status, created_at, updated_at, is_active",
+                    username,
+                    normalized,
+                    tenant,
+                    region
+                )
+                .fetch_one(&mut *tx)
+                .await
+            })?;
+
+            if let Some(role) = &role {
+                Self::assign_role(&mut tx, user.id, role).await?;
+            }
+
+            tx.commit().await?;
+            Ok(user)
+        })
+        .await
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<User, AppError> {
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+
+        // Regions have no replica.
+        if let Some((replica, circuit_breaker)) = self
+            .replica
+            .as_ref()
+            .filter(|_| matches!(self.target(), Ok((None, _, _))))
+        {
+            check_deadline("postgres")?;
+            let db = replica.clone();
+            let normalized = normalized.clone();
+            let tenant = tenant.clone();
+            match circuit_breaker
+                .call(|| async move {
+                    Self::select_user_by_username(&db, &normalized, &tenant).await
+                })
+                .await
+            {
+                Ok(Some(user)) => {
+                    track_replica_read("replica");
+                    return Ok(user);
+                }
+                Ok(None) => track_replica_read("replica"),
+                Err(e @ (AppError::InternalServer(_) | AppError::CircuitBreakerOpen(_))) => {
+                    tracing::debug!(error = %e, "Replica read failed, reading from the primary");
+                    track_replica_read("fallback");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            Self::select_user_by_username(&db, &normalized, &tenant).await
+        })
+        .await?
+        .ok_or_else(|| AppError::NotFound("Username not found".to_string()))
+    }
+
+    async fn get_user_by_id(&self, user_id: Uuid) -> Result<User, AppError> {
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            db_select!("users", {
+                sqlx::query_as!(
+                    User,
+                    "SELECT id, username, status, created_at, updated_at, is_active
+                     FROM users WHERE id = $1 AND is_active AND tenant_id = $2",
+                    user_id,
+                    tenant
+                )
+                .fetch_optional(&db)
+                .await
+            })?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+        })
+        .await
+    }
+
+    async fn get_grants(&self, user_id: Uuid) -> Result<Grants, AppError> {
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let row = db_select!("user_roles", {
+                sqlx::query!(
+                    r#"SELECT u.status,
+                         ARRAY(SELECT role FROM user_roles WHERE user_id = u.id ORDER BY role)
+                             AS "roles!",
+                         ARRAY(SELECT DISTINCT rp.permission
+                               FROM user_roles ur
+                               INNER JOIN role_permissions rp ON rp.role = ur.role
+                               WHERE ur.user_id = u.id
+                               ORDER BY rp.permission) AS "permissions!"
+                       FROM users u
+                       WHERE u.id = $1 AND u.tenant_id = $2"#,
+                    user_id,
+                    tenant
+                )
+                .fetch_optional(&db)
+                .await
+            })?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+            if row.status == UserStatus::Suspended.as_str() {
+                return Err(account_suspended());
+            }
+
+            Ok(Grants {
+                roles: row.roles,
+                permissions: row.permissions,
+            })
+        })
+        .await
+    }
+
+    async fn get_aaguid_policy(&self, user_id: Uuid) -> Result<AaguidPolicy, AppError> {
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let rows = db_select!("role_aaguids", {
+                sqlx::query!(
+                    "SELECT ra.role, ra.aaguid
+                     FROM role_aaguids ra
+                     INNER JOIN user_roles ur ON ur.role = ra.role
+                     INNER JOIN users u ON u.id = ur.user_id
+                     WHERE ur.user_id = $1 AND u.tenant_id = $2",
+                    user_id,
+                    tenant
+                )
+                .fetch_all(&db)
+                .await
+            })?;
+
+            Ok(rows.into_iter().map(|row| (row.role, row.aaguid)).collect())
+        })
+        .await
+    }
+
+    async fn get_user_and_session(
+        &self,
+        session_id: Uuid,
+        username: &str,
+        purpose: &str,
+    ) -> Result<(User, WebAuthnSession), AppError> {
+        let normalized = normalize_username(username);
+        let purpose = purpose.to_string();
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let row = db_select!("users", {
+                sqlx::query!(
+                    "SELECT u.id, u.username, u.status,
+                            u.created_at, u.updated_at, u.is_active,
+                            ws.id AS session_id, ws.user_id, ws.data, ws.purpose,
+                            ws.created_at AS session_created_at, ws.expires_at
+                     FROM users u
+                     INNER JOIN webauthn_sessions ws ON u.id = ws.user_id
+                     WHERE u.normalized_username = $1 AND ws.id = $2 AND ws.purpose = $3
+                       AND u.tenant_id = $4",
+                    normalized,
+                    session_id,
+                    purpose,
+                    tenant
+                )
+                .fetch_optional(&db)
+                .await
+            })?
+            .ok_or_else(|| AppError::NotFound("User or session not found".to_string()))?;
+
+            let user = User {
+                id: row.id,
+                username: row.username,
+                status: row.status,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+                is_active: row.is_active,
+            };
+            let session = WebAuthnSession {
+                id: row.session_id,
+                user_id: row.user_id,
+                data: row.data,
+                purpose: row.purpose,
+                created_at: row.session_created_at,
+                expires_at: row.expires_at,
+            };
+            Ok((user, session))
+        })
+        .await
+    }
+
+    async fn get_active_user_with_credential(
+        &self,
+        username: &str,
+    ) -> Result<(User, Vec<webauthn_rs::prelude::Passkey>), AppError> {
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let rows = db_select!("users", {
+                sqlx::query!(
+                    "SELECT u.id, u.username, u.status,
+                            u.created_at, u.updated_at, u.is_active,
+                            c.passkey, c.clone_suspected_at
+                     FROM users u
+                     INNER JOIN credentials c ON u.id = c.user_id
+                     WHERE u.normalized_username = $1 AND u.status IN ('active', 'suspended')
+                       AND u.tenant_id = $2",
+                    normalized,
+                    tenant
+                )
+                .fetch_all(&db)
+                .await
+            })?;
+
+            let Some(first) = rows.first() else {
+                return Err(AppError::NotFound(
+                    "User or credentials not found".to_string(),
+                ));
+            };
+
+            let user = User {
+                id: first.id,
+                username: first.username.clone(),
+                status: first.status.clone(),
+                created_at: first.created_at,
+                updated_at: first.updated_at,
+                is_active: first.is_active,
+            };
+            if user.is_suspended() {
+                return Err(account_suspended());
+            }
+
+            let passkeys = unlocked_passkeys(
+                rows.into_iter()
+                    .map(|row| (row.passkey, row.clone_suspected_at)),
+            )?;
+
+            Ok((user, passkeys))
+        })
+        .await
+    }
+
+    async fn create_webauthn_session<S: Serialize + Debug + Sync>(
+        &self,
+        user_id: Uuid,
+        state: &S,
+        purpose: &str,
+    ) -> Result<Uuid, AppError> {
+        let purpose = purpose.to_string();
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let expire_at = Utc::now() + chrono::Duration::minutes(30);
+
+            Ok(db_insert!("webauthn_sessions", {
+                sqlx::query_scalar!(
+                    "INSERT INTO webauthn_sessions (user_id, data, purpose, expires_at, tenant_id)
+                     VALUES ($1, $2, $3, $4, $5)
+                     RETURNING id",
+                    user_id,
+                    Json(state) as _,
+                    purpose,
+                    expire_at,
+                    tenant
+                )
+                .fetch_one(&db)
+                .await
+            })?)
+        })
+        .await
+    }
+
+    async fn delete_webauthn_session(&self, id: Uuid) -> Result<(), AppError> {
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let result = db_delete!("webauthn_sessions", {
+                sqlx::query!(
+                    "DELETE FROM webauthn_sessions WHERE id = $1 AND tenant_id = $2",
+                    id,
+                    tenant
+                )
+                .execute(&db)
+                .await
+            })?;
+
+            if result.rows_affected() == 0 {
+                return Err(AppError::NotFound("Session not found".to_string()));
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn update_credential(
+        &self,
+        _username: &str,
+        result: &AuthenticationResult,
+    ) -> Result<(), AppError> {
+        let cred_id = result.cred_id().as_slice().to_vec();
+        let result = result.clone();
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let mut tx = db.begin().await?;
+
+            let stored = db_select!("credentials", {
+                sqlx::query!(
+                    "SELECT passkey, clone_suspected_at
+                     FROM credentials
+                     WHERE id = $1 AND tenant_id = $2
+                     FOR UPDATE",
+                    cred_id,
+                    tenant
+                )
+                .fetch_optional(&mut *tx)
+                .await
+            })?
+            .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
+            if stored.clone_suspected_at.is_some() {
+                return Err(credential_locked(&cred_id));
+            }
+            let passkey = apply_authentication(stored.passkey, &result)?;
+
+            db_update!("credentials", {
+                sqlx::query!(
+                    "UPDATE credentials SET passkey = $1 WHERE id = $2 AND tenant_id = $3",
+                    passkey,
+                    cred_id,
+                    tenant
+                )
+                .execute(&mut *tx)
+                .await
+            })?;
+
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn lock_cloned_credential(
+        &self,
+        user_id: Uuid,
+        cred_id: &[u8],
+    ) -> Result<bool, AppError> {
+        let cred_id = cred_id.to_vec();
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let result = db_update!("credentials", {
+                sqlx::query!(
+                    "UPDATE credentials
+                     SET clone_suspected_at = NOW()
+                     WHERE id = $1 AND user_id = $2 AND clone_suspected_at IS NULL
+                       AND tenant_id = $3",
+                    cred_id,
+                    user_id,
+                    tenant
+                )
+                .execute(&db)
+                .await
+            })?;
+
+            Ok(result.rows_affected() > 0)
+        })
+        .await
+    }
+
+    async fn unlock_credential(&self, user_id: Uuid, cred_id: &[u8]) -> Result<(), AppError> {
+        let cred_id = cred_id.to_vec();
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let mut tx = db.begin().await?;
+
+            let stored = db_select!("credentials", {
+                sqlx::query_scalar!(
+                    "SELECT passkey FROM credentials
+                     WHERE id = $1 AND user_id = $2 AND clone_suspected_at IS NOT NULL
+                       AND tenant_id = $3
+                     FOR UPDATE",
+                    cred_id,
+                    user_id,
+                    tenant
+                )
+                .fetch_optional(&mut *tx)
+                .await
+            })?
+            .ok_or_else(|| AppError::NotFound("Locked credential not found".to_string()))?;
+            let passkey = reset_counter(stored)?;
+
+            db_update!("credentials", {
+                sqlx::query!(
+                    "UPDATE credentials
+                     SET passkey = $1, clone_suspected_at = NULL
+                     WHERE id = $2 AND tenant_id = $3",
+                    passkey,
+                    cred_id,
+                    tenant
+                )
+                .execute(&mut *tx)
+                .await
+            })?;
+
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_credentials(&self, user_id: Uuid) -> Result<Vec<StoredCredential>, AppError> {
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let rows = db_select!("credentials", {
+                sqlx::query!(
+                    "SELECT passkey, aaguid, created_at, last_used_at,
+                            clone_suspected_at
+                     FROM credentials
+                     WHERE user_id = $1 AND tenant_id = $2
+                     ORDER BY created_at",
+                    user_id,
+                    tenant
+                )
+                .fetch_all(&db)
+                .await
+            })?;
+
+            rows.into_iter()
+                .map(|row| {
+                    Ok(StoredCredential {
+                        passkey: serde_json::from_value(row.passkey)?,
+                        aaguid: row.aaguid,
+                        created_at: row.created_at,
+                        last_used_at: row.last_used_at,
+                        clone_suspected_at: row.clone_suspected_at,
+                    })
+                })
+                .collect()
+        })
+        .await
+    }
+
+    async fn complete_registration(
+        &self,
+        user_id: Uuid,
+        username: &str,
+        passkey: &webauthn_rs::prelude::Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+        activate: bool,
+    ) -> Result<(), AppError> {
+        let normalized = normalize_username(username);
+        let passkey = passkey.clone();
+        let recovery_code_hashes = recovery_code_hashes.to_vec();
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let mut tx = db.begin().await?;
+
+            SqlxRepository::create_credential(&mut tx, user_id, &passkey, aaguid, &tenant).await?;
+            if activate {
+                db_update!("users", {
+                    sqlx::query!(
+                        "UPDATE users SET status = 'active'
+                         WHERE normalized_username = $1 AND status = 'pending'
+                           AND tenant_id = $2",
+                        normalized,
+                        tenant
+                    )
+                    .execute(&mut *tx)
+                    .await
+                })?;
+            }
+            SqlxRepository::replace_recovery_codes(&mut tx, user_id, &recovery_code_hashes).await?;
+
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn activate_pending_user(&self, username: &str) -> Result<(), AppError> {
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            db_update!("users", {
+                sqlx::query!(
+                    "UPDATE users SET status = 'active'
+                     WHERE normalized_username = $1 AND status = 'pending' AND tenant_id = $2",
+                    normalized,
+                    tenant
+                )
+                .execute(&db)
+                .await
+            })?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_recovery_state(&self, username: &str) -> Result<RecoveryState, AppError> {
+        let normalized = normalize_username(username);
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let row = db_select!("users", {
+                sqlx::query!(
+                    "SELECT id, username, status, created_at, updated_at, is_active,
+                            recovery_locked_until
+                     FROM users
+                     WHERE normalized_username = $1 AND status = 'active' AND tenant_id = $2",
+                    normalized,
+                    tenant
+                )
+                .fetch_optional(&db)
+                .await
+            })?
+            .ok_or_else(|| AppError::NotFound("Username not found".to_string()))?;
+
+            Ok(RecoveryState {
+                user: User {
+                    id: row.id,
+                    username: row.username,
+                    status: row.status,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                    is_active: row.is_active,
+                },
+                locked_until: row.recovery_locked_until,
+            })
+        })
+        .await
+    }
+
+    async fn consume_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &[u8],
+    ) -> Result<bool, AppError> {
+        let code_hash = code_hash.to_vec();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let result = db_update!("recovery_codes", {
+                sqlx::query!(
+                    "UPDATE recovery_codes
+                     SET used_at = NOW()
+                     WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL",
+                    user_id,
+                    code_hash
+                )
+                .execute(&db)
+                .await
+            })?;
+
+            Ok(result.rows_affected() == 1)
+        })
+        .await
+    }
+
+    async fn record_recovery_failure(
+        &self,
+        user_id: Uuid,
+        max_attempts: i32,
+        lock_until: DateTime<Utc>,
+    ) -> Result<bool, AppError> {
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            Ok(db_update!("users", {
+                sqlx::query_scalar!(
+                    r#"UPDATE users
+                       SET recovery_failed_attempts = CASE
+                               WHEN recovery_failed_attempts + 1 >= $2 THEN 0
+                               ELSE recovery_failed_attempts + 1
+                           END,
+                           recovery_locked_until = CASE
+                               WHEN recovery_failed_attempts + 1 >= $2 THEN $3
+                               ELSE recovery_locked_until
+                           END
+                       WHERE id = $1 AND tenant_id = $4
+                       RETURNING recovery_locked_until IS NOT DISTINCT FROM $3 AS "locked!""#,
+                    user_id,
+                    max_attempts,
+                    lock_until,
+                    tenant
+                )
+                .fetch_one(&db)
+                .await
+            })?)
+        })
+        .await
+    }
+
+    async fn reset_recovery_failures(&self, user_id: Uuid) -> Result<(), AppError> {
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            db_update!("users", {
+                sqlx::query!(
+                    "UPDATE users
+                     SET recovery_failed_attempts = 0, recovery_locked_until = NULL
+                     WHERE id = $1 AND tenant_id = $2",
+                    user_id,
+                    tenant
+                )
+                .execute(&db)
+                .await
+            })?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn complete_recovery(
+        &self,
+        user_id: Uuid,
+        passkey: &webauthn_rs::prelude::Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+    ) -> Result<(), AppError> {
+        let passkey = passkey.clone();
+        let recovery_code_hashes = recovery_code_hashes.to_vec();
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let mut tx = db.begin().await?;
+
+            db_delete!("credentials", {
+                sqlx::query!(
+                    "DELETE FROM credentials WHERE user_id = $1 AND tenant_id = $2",
+                    user_id,
+                    tenant
+                )
+                .execute(&mut *tx)
+                .await
+            })?;
+            SqlxRepository::create_credential(&mut tx, user_id, &passkey, aaguid, &tenant).await?;
+            SqlxRepository::replace_recovery_codes(&mut tx, user_id, &recovery_code_hashes).await?;
+
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete_account(&self, user_id: Uuid) -> Result<(), AppError> {
+        let tenant = current_tenant();
+
+        self.execute_with_circuit_breaker(move |db| async move {
+            let mut tx = db.begin().await?;
+
+            db_delete!("credentials", {
+                sqlx::query!(
+                    "DELETE FROM credentials WHERE user_id = $1 AND tenant_id = $2",
+                    user_id,
+                    tenant
+                )
+                .execute(&mut *tx)
+                .await
+            })?;
+            db_delete!("recovery_codes", {
+                sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1", user_id)
+                    .execute(&mut *tx)
+                    .await
+            })?;
+            db_delete!("webauthn_sessions", {
+                sqlx::query!(
+                    "DELETE FROM webauthn_sessions WHERE user_id = $1 AND tenant_id = $2",
+                    user_id,
+                    tenant
+                )
+                .execute(&mut *tx)
+                .await
+            })?;
+            db_delete!("user_roles", {
+                sqlx::query!("DELETE FROM user_roles WHERE user_id = $1", user_id)
+                    .execute(&mut *tx)
+                    .await
+            })?;
+            let result = db_update!("users", {
+                sqlx::query!(
+                    "UPDATE users
+                     SET is_active = FALSE,
+                         status = 'deleted',
+                         username = 'deleted-' || id::text,
+                         normalized_username = 'deleted-' || id::text
+                     WHERE id = $1 AND is_active AND tenant_id = $2",
+                    user_id,
+                    tenant
+                )
+                .execute(&mut *tx)
+                .await
+            })?;
+
+            if result.rows_affected() == 0 {
+                return Err(AppError::NotFound("User not found".to_string()));
+            }
+
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+}
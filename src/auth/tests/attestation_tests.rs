@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use serde_cbor_2::Value;
+use url::Url;
+use uuid::Uuid;
+use webauthn_rs::{WebauthnBuilder, prelude::RegisterPublicKeyCredential};
+
+use crate::{
+    app::{AppError, ErrorCode},
+    auth::attestation::{AaguidPolicy, EnrollmentState, StoredEnrollment, reported_aaguid},
+};
+
+const YUBIKEY: Uuid = Uuid::from_u128(0xcb69481e_8ff7_4039_93ec_0a2729a154a8);
+const TPM: Uuid = Uuid::from_u128(0x08987058_cadc_4b81_b6e1_30de50dcbe96);
+
+/// A registration whose attestation object carries `aaguid`, with the
+/// attested credential data flag as given.
+fn registration(aaguid: Uuid, attested: bool) -> RegisterPublicKeyCredential {
+    let mut auth_data = vec![0u8; 32];
+    auth_data.push(if attested { 0x45 } else { 0x05 });
+    auth_data.extend_from_slice(&1u32.to_be_bytes());
+    auth_data.extend_from_slice(aaguid.as_bytes());
+
+    let attestation_object = serde_cbor_2::to_vec(&Value::Map(BTreeMap::from([
+        (Value::Text("fmt".into()), Value::Text("none".into())),
+        (Value::Text("attStmt".into()), Value::Map(BTreeMap::new())),
+        (Value::Text("authData".into()), Value::Bytes(auth_data)),
+    ])))
+    .unwrap();
+
+    serde_json::from_value(serde_json::json!({
+        "id": "AAAA",
+        "rawId": "AAAA",
+        "type": "public-key",
+        "response": {
+            "attestationObject": BASE64_URL_SAFE_NO_PAD.encode(attestation_object),
+            "clientDataJSON": BASE64_URL_SAFE_NO_PAD.encode(b"{}"),
+        },
+    }))
+    .unwrap()
+}
+
+fn policy(pairs: &[(&str, Uuid)]) -> AaguidPolicy {
+    pairs
+        .iter()
+        .map(|(role, aaguid)| (role.to_string(), *aaguid))
+        .collect()
+}
+
+#[test]
+fn test_unrestricted_policy_accepts_anything() {
+    let policy = AaguidPolicy::default();
+
+    assert!(!policy.is_restricted());
+    assert!(policy.check(None).is_ok());
+    assert!(policy.check(Some(YUBIKEY)).is_ok());
+}
+
+#[test]
+fn test_allowlisted_aaguid_is_accepted() {
+    let policy = policy(&[("ops", YUBIKEY), ("ops", TPM)]);
+
+    assert!(policy.is_restricted());
+    assert!(policy.check(Some(TPM)).is_ok());
+}
+
+#[test]
+fn test_other_aaguid_is_rejected() {
+    let policy = policy(&[("ops", YUBIKEY)]);
+
+    let error = policy.check(Some(TPM)).unwrap_err();
+
+    assert!(matches!(error, AppError::AuthenticatorNotAllowed(_)));
+    assert_eq!(error.code(), ErrorCode::AuthenticatorNotAllowed);
+    assert!(error.to_string().contains("ops"));
+}
+
+#[test]
+fn test_unattested_credential_is_rejected() {
+    let policy = policy(&[("ops", YUBIKEY)]);
+
+    assert!(matches!(
+        policy.check(None),
+        Err(AppError::AuthenticatorNotAllowed(_))
+    ));
+}
+
+#[test]
+fn test_every_restricted_role_must_accept() {
+    let policy = policy(&[("ops", YUBIKEY), ("finance", TPM)]);
+
+    assert!(policy.check(Some(YUBIKEY)).is_err());
+    assert!(policy.check(Some(TPM)).is_err());
+}
+
+#[test]
+fn test_plain_registration_state_loads_as_passkey() {
+    let origin = Url::parse("https://example.com").unwrap();
+    let webauthn = WebauthnBuilder::new("example.com", &origin)
+        .unwrap()
+        .build()
+        .unwrap();
+    let (_, state) = webauthn
+        .start_passkey_registration(Uuid::new_v4(), "alice", "alice", None)
+        .unwrap();
+
+    let stored = serde_json::to_value(&state).unwrap();
+    let wrapped = serde_json::to_value(EnrollmentState::Passkey(state)).unwrap();
+
+    assert_eq!(stored, wrapped);
+    assert!(matches!(
+        serde_json::from_value(stored).unwrap(),
+        EnrollmentState::Passkey(_)
+    ));
+}
+
+#[test]
+fn test_stored_enrollment_keeps_invitation_beside_state() {
+    let origin = Url::parse("https://example.com").unwrap();
+    let webauthn = WebauthnBuilder::new("example.com", &origin)
+        .unwrap()
+        .build()
+        .unwrap();
+    let (_, state) = webauthn
+        .start_passkey_registration(Uuid::new_v4(), "alice", "alice", None)
+        .unwrap();
+    let plain = serde_json::to_value(&state).unwrap();
+    let invitation = Uuid::new_v4();
+
+    let stored = serde_json::to_value(StoredEnrollment {
+        state: EnrollmentState::Passkey(state),
+        invitation: Some(invitation),
+    })
+    .unwrap();
+    let loaded: StoredEnrollment = serde_json::from_value(stored).unwrap();
+    assert!(matches!(loaded.state, EnrollmentState::Passkey(_)));
+    assert_eq!(loaded.invitation, Some(invitation));
+
+    // Stored before invitations were kept with the state.
+    let loaded: StoredEnrollment = serde_json::from_value(plain).unwrap();
+    assert!(matches!(loaded.state, EnrollmentState::Passkey(_)));
+    assert_eq!(loaded.invitation, None);
+}
+
+#[test]
+fn test_reported_aaguid_is_read_from_authenticator_data() {
+    assert_eq!(reported_aaguid(&registration(YUBIKEY, true)), Some(YUBIKEY));
+}
+
+#[test]
+fn test_zero_aaguid_is_not_reported() {
+    assert_eq!(reported_aaguid(&registration(Uuid::nil(), true)), None);
+}
+
+#[test]
+fn test_aaguid_needs_attested_credential_data() {
+    assert_eq!(reported_aaguid(&registration(YUBIKEY, false)), None);
+}
+
+#[test]
+fn test_malformed_attestation_object_reports_nothing() {
+    let mut credential = registration(YUBIKEY, true);
+    credential.response.attestation_object = vec![0xff, 0x00].into();
+
+    assert_eq!(reported_aaguid(&credential), None);
+}
@@ -0,0 +1,96 @@
+//! Tokens must stay valid across instances built with different crypto
+//! backends, so the aws-lc build is checked against ed25519-dalek, which
+//! the default build uses.
+
+use std::sync::Arc;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use ed25519_dalek::{Signature, Signer, SigningKey};
+use jsonwebtoken::{decode, jwk::AlgorithmParameters};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::jwt::keys::{AccessKeyring, AccessKeys},
+    utils::crypto::{Cipher, ed25519_public_key},
+};
+
+const SEED: [u8; 32] = *b"an-ed25519-seed-of-32-bytes-long";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    exp: i64,
+}
+
+fn claims() -> Claims {
+    Claims {
+        sub: String::from("alice"),
+        exp: chrono::Utc::now().timestamp() + 60,
+    }
+}
+
+#[test]
+fn test_public_keys_match_dalek() {
+    for seed in [SEED, [0; 32], [0xff; 32]] {
+        assert_eq!(
+            ed25519_public_key(&seed),
+            SigningKey::from_bytes(&seed).verifying_key().to_bytes()
+        );
+    }
+}
+
+#[test]
+fn test_published_key_matches_dalek() {
+    let keys = Arc::new(AccessKeys::derive_from_secret(&SEED).unwrap());
+    let jwk = AccessKeyring::new(keys).jwks().keys.remove(0);
+    let x = match jwk.algorithm {
+        AlgorithmParameters::OctetKeyPair(key) => key.x,
+        other => panic!("unexpected JWK {:?}", other),
+    };
+
+    assert_eq!(
+        BASE64_URL_SAFE_NO_PAD.decode(x).unwrap(),
+        SigningKey::from_bytes(&SEED).verifying_key().to_bytes()
+    );
+}
+
+#[tokio::test]
+async fn test_dalek_verifies_issued_tokens() {
+    let keys = AccessKeys::derive_from_secret(&SEED).unwrap();
+    let token = keys.sign(&claims()).await.unwrap();
+
+    let (message, signature) = token.rsplit_once('.').unwrap();
+    let signature =
+        Signature::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap();
+    SigningKey::from_bytes(&SEED)
+        .verifying_key()
+        .verify_strict(message.as_bytes(), &signature)
+        .unwrap();
+}
+
+#[test]
+fn test_tokens_signed_by_dalek_verify() {
+    let keys = AccessKeys::derive_from_secret(&SEED).unwrap();
+    let message = format!(
+        "{}.{}",
+        BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&keys.header()).unwrap()),
+        BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims()).unwrap())
+    );
+    let signature = SigningKey::from_bytes(&SEED).sign(message.as_bytes());
+    let token = format!(
+        "{}.{}",
+        message,
+        BASE64_URL_SAFE_NO_PAD.encode(signature.to_bytes())
+    );
+
+    let decoded = decode::<Claims>(&token, keys.decoding_key(), &keys.validation()).unwrap();
+    assert_eq!(decoded.claims.sub, "alice");
+}
+
+#[test]
+fn test_sealed_state_is_aes_gcm() {
+    // 12-byte nonce and 16-byte tag around the plaintext.
+    let sealed = Cipher::new(&SEED).unwrap().seal(b"state", b"").unwrap();
+
+    assert_eq!(sealed.len(), 12 + 5 + 16);
+}
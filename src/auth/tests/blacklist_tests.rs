@@ -0,0 +1,24 @@
+use crate::auth::jwt::revocation::blacklist_ttl;
+
+const NOW: i64 = 1_700_000_000;
+
+#[test]
+fn test_ttl_covers_remaining_lifetime_and_leeway() {
+    assert_eq!(blacklist_ttl(NOW + 3600, NOW, false), 3660);
+}
+
+#[test]
+fn test_ttl_drops_leeway_under_memory_pressure() {
+    assert_eq!(blacklist_ttl(NOW + 3600, NOW, true), 3600);
+}
+
+#[test]
+fn test_recently_expired_token_keeps_leeway() {
+    assert_eq!(blacklist_ttl(NOW - 30, NOW, false), 30);
+}
+
+#[test]
+fn test_expired_token_gets_minimal_ttl() {
+    assert_eq!(blacklist_ttl(NOW - 30, NOW, true), 1);
+    assert_eq!(blacklist_ttl(NOW - 3600, NOW, false), 1);
+}
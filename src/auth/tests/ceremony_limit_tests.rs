@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use tokio::time::timeout;
+
+use crate::auth::ceremony_limit::CeremonyLimiter;
+
+const WAIT: Duration = Duration::from_millis(50);
+
+#[tokio::test]
+async fn test_finish_steps_queue_at_the_limit() {
+    let limiter = CeremonyLimiter::new(1);
+
+    let held = limiter.acquire("login").await.unwrap();
+    assert_eq!(limiter.available(), 0);
+    assert!(timeout(WAIT, limiter.acquire("login")).await.is_err());
+
+    drop(held);
+    assert!(timeout(WAIT, limiter.acquire("login")).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_raising_the_limit_releases_waiters() {
+    let limiter = CeremonyLimiter::new(1);
+    let _held = limiter.acquire("login").await.unwrap();
+
+    assert_eq!(limiter.set_limit(2), 1);
+
+    assert_eq!(limiter.limit(), 2);
+    assert!(timeout(WAIT, limiter.acquire("login")).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_lowering_the_limit_waits_for_running_ceremonies() {
+    let limiter = CeremonyLimiter::new(2);
+    let first = limiter.acquire("registration").await.unwrap();
+    let second = limiter.acquire("registration").await.unwrap();
+
+    limiter.set_limit(1);
+    // Let the task reclaiming the held permit join the queue.
+    tokio::task::yield_now().await;
+    drop(first);
+    assert!(
+        timeout(WAIT, limiter.acquire("registration"))
+            .await
+            .is_err()
+    );
+
+    drop(second);
+    let third = timeout(WAIT, limiter.acquire("registration"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(limiter.available(), 0);
+    drop(third);
+    assert_eq!(limiter.available(), 1);
+}
+
+#[tokio::test]
+async fn test_zero_removes_the_limit() {
+    let limiter = CeremonyLimiter::new(1);
+    let _held = limiter.acquire("login").await.unwrap();
+
+    limiter.set_limit(0);
+    let _second = timeout(WAIT, limiter.acquire("login")).await.unwrap();
+
+    limiter.set_limit(2);
+    assert_eq!(limiter.available(), 0);
+}
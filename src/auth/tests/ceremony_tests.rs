@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    auth::ceremony::{CeremonySealer, SealedCeremony},
+    config::webauthn::StatelessChallengeConfig,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct State {
+    challenge: String,
+}
+
+fn sealer(key: u8) -> CeremonySealer {
+    CeremonySealer::new(&StatelessChallengeConfig {
+        key: [key; 32],
+        ttl: Duration::from_secs(300),
+    })
+}
+
+fn state() -> State {
+    State {
+        challenge: String::from("Y2hhbGxlbmdl"),
+    }
+}
+
+fn open(
+    sealer: &CeremonySealer,
+    token: &str,
+    purpose: &str,
+    now: i64,
+) -> Result<SealedCeremony<State>, AppError> {
+    sealer.open(token, purpose, now)
+}
+
+#[test]
+fn test_sealed_state_round_trips() {
+    let sealer = sealer(1);
+    let user_id = Uuid::new_v4();
+    let token = sealer.seal(user_id, "login", &state(), NOW).unwrap();
+
+    let ceremony = open(&sealer, &token, "login", NOW + 10).unwrap();
+
+    assert_eq!(ceremony.user_id, user_id);
+    assert_eq!(ceremony.state, state());
+    assert_eq!(ceremony.exp, NOW + 300);
+}
+
+#[test]
+fn test_start_is_recovered_from_expiry() {
+    let sealer = sealer(1);
+    let token = sealer.seal(Uuid::new_v4(), "login", &state(), NOW).unwrap();
+
+    let ceremony = open(&sealer, &token, "login", NOW + 42).unwrap();
+
+    assert_eq!(sealer.started_at(&ceremony), NOW);
+}
+
+#[test]
+fn test_state_is_not_readable_by_the_client() {
+    let token = sealer(1)
+        .seal(Uuid::new_v4(), "login", &state(), NOW)
+        .unwrap();
+
+    assert!(!token.contains("Y2hhbGxlbmdl"));
+}
+
+#[test]
+fn test_each_seal_gets_its_own_nonce() {
+    let sealer = sealer(1);
+    let user_id = Uuid::new_v4();
+    let first = sealer.seal(user_id, "login", &state(), NOW).unwrap();
+    let second = sealer.seal(user_id, "login", &state(), NOW).unwrap();
+
+    assert_ne!(first, second);
+    assert_ne!(
+        open(&sealer, &first, "login", NOW).unwrap().nonce,
+        open(&sealer, &second, "login", NOW).unwrap().nonce
+    );
+}
+
+#[test]
+fn test_rejects_other_purpose() {
+    let sealer = sealer(1);
+    let token = sealer.seal(Uuid::new_v4(), "login", &state(), NOW).unwrap();
+
+    assert!(matches!(
+        open(&sealer, &token, "registration", NOW),
+        Err(AppError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_rejects_other_key() {
+    let token = sealer(1)
+        .seal(Uuid::new_v4(), "login", &state(), NOW)
+        .unwrap();
+
+    assert!(matches!(
+        open(&sealer(2), &token, "login", NOW),
+        Err(AppError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_rejects_tampered_token() {
+    let sealer = sealer(1);
+    let mut token = sealer.seal(Uuid::new_v4(), "login", &state(), NOW).unwrap();
+    let last = token.pop().unwrap();
+    token.push(if last == 'A' { 'B' } else { 'A' });
+
+    assert!(matches!(
+        open(&sealer, &token, "login", NOW),
+        Err(AppError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_rejects_garbage() {
+    let sealer = sealer(1);
+
+    for token in [
+        "",
+        "not base64!",
+        "c2hvcnQ",
+        "550e8400-e29b-41d4-a716-446655440000",
+    ] {
+        assert!(matches!(
+            open(&sealer, token, "login", NOW),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
+
+#[test]
+fn test_rejects_expired_state() {
+    let sealer = sealer(1);
+    let token = sealer.seal(Uuid::new_v4(), "login", &state(), NOW).unwrap();
+
+    let error = open(&sealer, &token, "login", NOW + 300).unwrap_err();
+    assert!(matches!(error.kind(), AppError::Unauthorized(_)));
+    assert_eq!(error.code(), ErrorCode::AuthSessionExpired);
+}
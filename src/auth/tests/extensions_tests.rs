@@ -0,0 +1,208 @@
+use serde_json::{Value, json, value::RawValue};
+use url::Url;
+use uuid::Uuid;
+use webauthn_rs::WebauthnBuilder;
+
+use crate::{
+    app::AppError,
+    auth::{
+        dto::{ExtensionInputs, LargeBlobInput},
+        extensions::{
+            authentication_inputs, client_outputs, options_with_extensions, registration_inputs,
+        },
+    },
+    config::webauthn::ExtensionsConfig,
+};
+
+const SALT: &str = "c2FsdC1mb3ItdGhlLWVuY3J5cHRpb24ta2V5";
+
+const ALL: ExtensionsConfig = ExtensionsConfig {
+    prf: true,
+    large_blob: true,
+};
+
+fn inputs(prf_salt: Option<&str>, large_blob: Option<LargeBlobInput>) -> ExtensionInputs {
+    ExtensionInputs {
+        prf_salt: prf_salt.map(String::from),
+        large_blob,
+    }
+}
+
+fn raw(value: Value) -> Box<RawValue> {
+    serde_json::value::to_raw_value(&value).unwrap()
+}
+
+#[test]
+fn test_registration_requests_enabled_extensions() {
+    let extensions = registration_inputs(ALL, None).unwrap();
+
+    assert_eq!(extensions["prf"], json!({}));
+    assert_eq!(extensions["largeBlob"], json!({ "support": "preferred" }));
+    assert!(
+        registration_inputs(ExtensionsConfig::default(), None)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_registration_evaluates_prf_salt() {
+    let extensions = registration_inputs(ALL, Some(&inputs(Some(SALT), None))).unwrap();
+
+    assert_eq!(extensions["prf"], json!({ "eval": { "first": SALT } }));
+}
+
+#[test]
+fn test_registration_rejects_large_blob_access() {
+    let result = registration_inputs(ALL, Some(&inputs(None, Some(LargeBlobInput::Read))));
+
+    assert!(matches!(result, Err(AppError::BadRequest(_))));
+}
+
+#[test]
+fn test_login_only_carries_requested_extensions() {
+    assert!(authentication_inputs(ALL, None).unwrap().is_empty());
+
+    let extensions = authentication_inputs(
+        ALL,
+        Some(&inputs(
+            Some(SALT),
+            Some(LargeBlobInput::Write(String::from("AQID"))),
+        )),
+    )
+    .unwrap();
+
+    assert_eq!(extensions["prf"], json!({ "eval": { "first": SALT } }));
+    assert_eq!(extensions["largeBlob"], json!({ "write": "AQID" }));
+}
+
+#[test]
+fn test_disabled_extension_is_rejected() {
+    let prf_only = ExtensionsConfig {
+        prf: true,
+        large_blob: false,
+    };
+
+    match authentication_inputs(prf_only, Some(&inputs(None, Some(LargeBlobInput::Read)))) {
+        Err(AppError::BadRequest(msg)) => {
+            assert_eq!(msg, "The large_blob extension is not enabled")
+        }
+        _ => panic!("Expected BadRequest error"),
+    }
+    assert!(
+        registration_inputs(ExtensionsConfig::default(), Some(&inputs(Some(SALT), None))).is_err()
+    );
+}
+
+#[test]
+fn test_options_keep_webauthn_extensions() {
+    let origin = Url::parse("https://example.com").unwrap();
+    let webauthn = WebauthnBuilder::new("example.com", &origin)
+        .unwrap()
+        .build()
+        .unwrap();
+    let (options, _) = webauthn
+        .start_passkey_registration(Uuid::new_v4(), "alice", "alice", None)
+        .unwrap();
+
+    let plain = options_with_extensions(&options, Default::default(), None).unwrap();
+    let merged =
+        options_with_extensions(&options, registration_inputs(ALL, None).unwrap(), None).unwrap();
+
+    assert_eq!(
+        plain.get(),
+        serde_json::to_string(&options).unwrap(),
+        "no extensions must leave the options untouched"
+    );
+    let merged: Value = serde_json::from_str(merged.get()).unwrap();
+    let extensions = &merged["publicKey"]["extensions"];
+    assert_eq!(extensions["prf"], json!({}));
+    assert_eq!(extensions["credProps"], json!(true));
+}
+
+#[test]
+fn test_rp_icon_is_added_to_creation_options() {
+    let origin = Url::parse("https://example.com").unwrap();
+    let webauthn = WebauthnBuilder::new("example.com", &origin)
+        .unwrap()
+        .rp_name("Example")
+        .build()
+        .unwrap();
+    let (options, _) = webauthn
+        .start_passkey_registration(Uuid::new_v4(), "alice", "alice", None)
+        .unwrap();
+
+    let branded = options_with_extensions(
+        &options,
+        Default::default(),
+        Some("https://cdn.example.com/logo.png"),
+    )
+    .unwrap();
+
+    let branded: Value = serde_json::from_str(branded.get()).unwrap();
+    assert_eq!(
+        branded["publicKey"]["rp"],
+        json!({
+            "name": "Example",
+            "id": "example.com",
+            "icon": "https://cdn.example.com/logo.png",
+        })
+    );
+    assert_eq!(
+        branded["publicKey"]["extensions"],
+        serde_json::to_value(&options).unwrap()["publicKey"]["extensions"],
+        "the icon alone must not change the extensions"
+    );
+}
+
+#[test]
+fn test_outputs_of_enabled_extensions_are_returned() {
+    let credentials = raw(json!({
+        "id": "AQID",
+        "clientExtensionResults": {
+            "prf": { "enabled": true },
+            "largeBlob": { "supported": true },
+            "credProps": { "rk": true },
+        },
+    }));
+    let prf_only = ExtensionsConfig {
+        prf: true,
+        large_blob: false,
+    };
+
+    let outputs = client_outputs(prf_only, &credentials).unwrap().unwrap();
+
+    assert_eq!(outputs.prf, Some(json!({ "enabled": true })));
+    assert_eq!(outputs.large_blob, None);
+    assert!(
+        client_outputs(ExtensionsConfig::default(), &credentials)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_missing_outputs_are_omitted() {
+    let credentials = raw(json!({ "id": "AQID", "extensions": { "credProps": { "rk": true } } }));
+
+    assert!(client_outputs(ALL, &credentials).unwrap().is_none());
+    assert!(
+        client_outputs(ALL, &raw(json!({ "id": "AQID" })))
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_prf_results_are_not_echoed() {
+    let credentials = raw(json!({
+        "id": "AQID",
+        "clientExtensionResults": {
+            "prf": { "enabled": true, "results": { "first": "c2VjcmV0" } },
+        },
+    }));
+
+    let outputs = client_outputs(ALL, &credentials).unwrap().unwrap();
+
+    assert_eq!(outputs.prf, Some(json!({ "enabled": true })));
+}
@@ -0,0 +1,237 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{
+    Json, Router,
+    http::HeaderMap,
+    routing::{get, post},
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chrono::{TimeZone, Utc};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{
+    auth::jwt::{
+        kms::{AwsKmsSigner, GcpKmsSigner, SigV4Request, der_to_jose, sigv4_headers},
+        traits::TokenSigner,
+    },
+    config::{CircuitBreakerConfig, HttpClientConfig, jwt::AwsCredentials},
+    utils::HttpClientService,
+};
+
+fn credentials() -> AwsCredentials {
+    AwsCredentials {
+        access_key_id: String::from("AKIDEXAMPLE"),
+        secret_access_key: String::from("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"),
+        session_token: None,
+    }
+}
+
+fn http() -> Arc<HttpClientService> {
+    Arc::new(HttpClientService::new(HttpClientConfig {
+        timeout: Duration::from_secs(5),
+        connect_timeout: Duration::from_secs(1),
+        proxy: None,
+        no_proxy: None,
+        max_retries: 0,
+        retry_budget_percent: 20,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        ssrf_allowed_hosts: Vec::new(),
+    }))
+}
+
+/// r is one byte short and s needs a sign byte, the two cases DER changes.
+fn der_signature() -> Vec<u8> {
+    let mut der = vec![0x30, 0x44, 0x02, 0x1f];
+    der.extend([0x11; 31]);
+    der.extend([0x02, 0x21, 0x00]);
+    der.extend([0x80; 32]);
+    der
+}
+
+fn jose_signature() -> Vec<u8> {
+    let mut jose = vec![0x00];
+    jose.extend([0x11; 31]);
+    jose.extend([0x80; 32]);
+    jose
+}
+
+async fn serve(app: Router) -> Url {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url.parse().unwrap()
+}
+
+#[test]
+fn test_sigv4_matches_aws_example() {
+    // The IAM ListUsers example from the AWS Signature Version 4 documentation.
+    let headers = sigv4_headers(
+        &credentials(),
+        &SigV4Request {
+            region: "us-east-1",
+            service: "iam",
+            method: "GET",
+            host: "iam.amazonaws.com",
+            path: "/",
+            query: "Action=ListUsers&Version=2010-05-08",
+            headers: &[(
+                "Content-Type",
+                "application/x-www-form-urlencoded; charset=utf-8",
+            )],
+            body: b"",
+        },
+        Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap(),
+    );
+
+    assert_eq!(
+        headers,
+        vec![
+            (String::from("x-amz-date"), String::from("20150830T123600Z")),
+            (
+                String::from("authorization"),
+                String::from(
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+                     SignedHeaders=content-type;host;x-amz-date, \
+                     Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+                ),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_sigv4_signs_session_token() {
+    let mut credentials = credentials();
+    credentials.session_token = Some(String::from("token"));
+
+    let headers = sigv4_headers(
+        &credentials,
+        &SigV4Request {
+            region: "eu-west-1",
+            service: "kms",
+            method: "POST",
+            host: "kms.eu-west-1.amazonaws.com",
+            path: "/",
+            query: "",
+            headers: &[],
+            body: b"{}",
+        },
+        Utc::now(),
+    );
+
+    assert_eq!(
+        headers[1],
+        (String::from("x-amz-security-token"), String::from("token"))
+    );
+    assert!(
+        headers[2]
+            .1
+            .contains("SignedHeaders=host;x-amz-date;x-amz-security-token,")
+    );
+}
+
+#[test]
+fn test_der_signature_converted_to_jose() {
+    assert_eq!(der_to_jose(&der_signature()).unwrap(), jose_signature());
+}
+
+#[test]
+fn test_malformed_der_signature_rejected() {
+    let mut trailing = der_signature();
+    trailing[1] += 1;
+    trailing.push(0);
+
+    let mut oversized = vec![0x30, 0x46, 0x02, 0x21, 0x01];
+    oversized.extend([0x11; 32]);
+    oversized.extend([0x02, 0x21, 0x00]);
+    oversized.extend([0x80; 32]);
+
+    assert!(der_to_jose(&[]).is_err());
+    assert!(der_to_jose(&der_signature()[..40]).is_err());
+    assert!(der_to_jose(&trailing).is_err());
+    assert!(der_to_jose(&oversized).is_err());
+}
+
+#[tokio::test]
+async fn test_aws_signer_signs_digest() {
+    let app = Router::new().route(
+        "/",
+        post(|headers: HeaderMap, body: String| async move {
+            let request: Value = serde_json::from_str(&body).unwrap();
+            let digest = BASE64_STANDARD.encode(Sha256::digest(b"header.claims"));
+
+            assert_eq!(headers["x-amz-target"], "TrentService.Sign");
+            assert!(
+                headers["authorization"]
+                    .to_str()
+                    .unwrap()
+                    .starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/")
+            );
+            assert_eq!(request["KeyId"], "alias/tokens");
+            assert_eq!(request["MessageType"], "DIGEST");
+            assert_eq!(request["Message"], digest.as_str());
+
+            Json(json!({ "Signature": BASE64_STANDARD.encode(der_signature()) }))
+        }),
+    );
+    let endpoint = serve(app).await;
+    let signer = AwsKmsSigner::new(
+        http(),
+        endpoint,
+        String::from("eu-west-1"),
+        String::from("alias/tokens"),
+        credentials(),
+    );
+
+    let signature = signer.sign(b"header.claims").await.unwrap();
+
+    assert_eq!(signature, jose_signature());
+}
+
+#[tokio::test]
+async fn test_gcp_signer_reuses_access_token() {
+    let token_requests = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&token_requests);
+    let key_name = "projects/p/locations/global/keyRings/r/cryptoKeys/k/cryptoKeyVersions/1";
+
+    let app = Router::new()
+        .route(
+            "/token",
+            get(move |headers: HeaderMap| async move {
+                assert_eq!(headers["metadata-flavor"], "Google");
+                counter.fetch_add(1, Ordering::SeqCst);
+                Json(json!({ "access_token": "ya29.test", "expires_in": 3600 }))
+            }),
+        )
+        .route(
+            &format!("/v1/{}:asymmetricSign", key_name),
+            post(
+                |headers: HeaderMap, Json(request): Json<Value>| async move {
+                    let digest = BASE64_STANDARD.encode(Sha256::digest(b"header.claims"));
+
+                    assert_eq!(headers["authorization"], "Bearer ya29.test");
+                    assert_eq!(request["digest"]["sha256"], digest.as_str());
+                    Json(json!({ "signature": BASE64_STANDARD.encode(der_signature()) }))
+                },
+            ),
+        );
+    let endpoint = serve(app).await;
+    let signer =
+        GcpKmsSigner::new(http(), &endpoint, key_name, endpoint.join("token").unwrap()).unwrap();
+
+    for _ in 0..2 {
+        assert_eq!(
+            signer.sign(b"header.claims").await.unwrap(),
+            jose_signature()
+        );
+    }
+    assert_eq!(token_requests.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,237 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, ErrorCode, context::scope_tenant},
+    auth::{memory_repo::MemoryRepository, traits::AuthRepository},
+};
+
+const YUBIKEY: Uuid = Uuid::from_u128(0xcb69481e_8ff7_4039_93ec_0a2729a154a8);
+
+#[tokio::test]
+async fn test_new_user_is_pending() {
+    let repo = MemoryRepository::new();
+
+    let user = repo.create_user("alice", None).await.unwrap();
+
+    assert_eq!(user.status, "pending");
+    assert_eq!(
+        repo.get_user_by_username("alice").await.unwrap().id,
+        user.id
+    );
+}
+
+#[tokio::test]
+async fn test_pending_user_is_reused_and_active_user_conflicts() {
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", None).await.unwrap();
+
+    assert_eq!(repo.create_user("alice", None).await.unwrap().id, user.id);
+
+    repo.activate_pending_user("alice").await.unwrap();
+    let error = repo.create_user("alice", None).await.unwrap_err();
+    assert!(matches!(error.kind(), AppError::AlreadyExists(_)));
+    assert_eq!(error.code(), ErrorCode::UsernameTaken);
+}
+
+#[tokio::test]
+async fn test_pending_user_is_not_reused_when_a_role_is_involved() {
+    let repo = MemoryRepository::new();
+    repo.create_user("root", Some("admin")).await.unwrap();
+    repo.create_user("alice", None).await.unwrap();
+
+    for (username, role) in [
+        ("root", None),
+        ("root", Some("admin")),
+        ("alice", Some("admin")),
+    ] {
+        let error = repo.create_user(username, role).await.unwrap_err();
+        assert_eq!(error.code(), ErrorCode::UsernameTaken);
+    }
+    assert!(
+        repo.get_grants(repo.get_user_by_username("alice").await.unwrap().id)
+            .await
+            .unwrap()
+            .roles
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_usernames_are_unique_once_normalized() {
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("Alice", None).await.unwrap();
+    repo.activate_pending_user("alice").await.unwrap();
+
+    assert_eq!(
+        repo.get_user_by_username("ＡＬＩＣＥ").await.unwrap().id,
+        user.id
+    );
+    assert_eq!(
+        repo.create_user("aLiCe", None).await.unwrap_err().code(),
+        ErrorCode::UsernameTaken
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_role_creates_nothing() {
+    let repo = MemoryRepository::new();
+
+    assert!(matches!(
+        repo.create_user("alice", Some("ops")).await,
+        Err(AppError::BadRequest(_))
+    ));
+    assert!(matches!(
+        repo.get_user_by_username("alice").await,
+        Err(AppError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_grants_follow_roles() {
+    let repo = MemoryRepository::new().with_role("auditor", &["audit:read"]);
+    let admin = repo.create_user("root", Some("admin")).await.unwrap();
+    let auditor = repo.create_user("carol", Some("auditor")).await.unwrap();
+
+    let admin_grants = repo.get_grants(admin.id).await.unwrap();
+    let auditor_grants = repo.get_grants(auditor.id).await.unwrap();
+
+    assert_eq!(admin_grants.roles, vec!["admin"]);
+    assert!(admin_grants.allows("banner:write"));
+    assert_eq!(auditor_grants.permissions, vec!["audit:read"]);
+}
+
+#[tokio::test]
+async fn test_aaguid_policy_of_user_roles() {
+    let repo = MemoryRepository::new()
+        .with_role("ops", &[])
+        .with_aaguids("ops", &[YUBIKEY]);
+    let ops = repo.create_user("olga", Some("ops")).await.unwrap();
+    let plain = repo.create_user("paul", None).await.unwrap();
+
+    assert!(
+        repo.get_aaguid_policy(ops.id)
+            .await
+            .unwrap()
+            .is_restricted()
+    );
+    assert!(
+        !repo
+            .get_aaguid_policy(plain.id)
+            .await
+            .unwrap()
+            .is_restricted()
+    );
+}
+
+#[tokio::test]
+async fn test_session_matches_user_and_purpose() {
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", None).await.unwrap();
+    let id = repo
+        .create_webauthn_session(user.id, &serde_json::json!({ "c": 1 }), "registration")
+        .await
+        .unwrap();
+
+    let (found, session) = repo
+        .get_user_and_session(id, "alice", "registration")
+        .await
+        .unwrap();
+    assert_eq!(found.id, user.id);
+    assert_eq!(session.data["c"], 1);
+
+    assert!(
+        repo.get_user_and_session(id, "alice", "login")
+            .await
+            .is_err()
+    );
+    assert!(
+        repo.get_user_and_session(id, "mallory", "registration")
+            .await
+            .is_err()
+    );
+
+    repo.delete_webauthn_session(id).await.unwrap();
+    assert!(matches!(
+        repo.delete_webauthn_session(id).await,
+        Err(AppError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_recovery_lock_applies_at_max_attempts() {
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", None).await.unwrap();
+    let lock_until = Utc::now() + Duration::minutes(15);
+
+    assert!(
+        !repo
+            .record_recovery_failure(user.id, 2, lock_until)
+            .await
+            .unwrap()
+    );
+    assert!(
+        repo.record_recovery_failure(user.id, 2, lock_until)
+            .await
+            .unwrap()
+    );
+
+    repo.activate_pending_user("alice").await.unwrap();
+    let state = repo.get_recovery_state("alice").await.unwrap();
+    assert_eq!(state.locked_until, Some(lock_until));
+
+    repo.reset_recovery_failures(user.id).await.unwrap();
+    let state = repo.get_recovery_state("alice").await.unwrap();
+    assert_eq!(state.locked_until, None);
+}
+
+#[tokio::test]
+async fn test_delete_account_frees_username() {
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", Some("admin")).await.unwrap();
+
+    repo.delete_account(user.id).await.unwrap();
+
+    assert!(matches!(
+        repo.get_user_by_id(user.id).await,
+        Err(AppError::NotFound(_))
+    ));
+    assert!(repo.get_grants(user.id).await.unwrap().roles.is_empty());
+    assert_ne!(repo.create_user("alice", None).await.unwrap().id, user.id);
+    assert!(matches!(
+        repo.delete_account(user.id).await,
+        Err(AppError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_tenants_see_only_their_own_users() {
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", Some("admin")).await.unwrap();
+    repo.activate_pending_user("alice").await.unwrap();
+
+    scope_tenant(String::from("shop"), async {
+        let other = repo.create_user("alice", None).await.unwrap();
+        assert_ne!(other.id, user.id);
+        assert_eq!(other.status, "pending");
+
+        assert!(matches!(
+            repo.get_user_by_id(user.id).await,
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            repo.get_grants(user.id).await,
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            repo.delete_account(user.id).await,
+            Err(AppError::NotFound(_))
+        ));
+    })
+    .await;
+
+    assert_eq!(
+        repo.get_user_by_username("alice").await.unwrap().id,
+        user.id
+    );
+}
@@ -0,0 +1,18 @@
+use crate::{auth::model::MigrationStatus, utils::MIGRATIONS};
+
+#[test]
+fn test_all_tables_present_is_complete() {
+    let status = MigrationStatus::from_missing_tables(&[]);
+
+    assert!(status.is_complete());
+    assert_eq!(status.applied.len(), MIGRATIONS.len());
+}
+
+#[test]
+fn test_missing_table_marks_its_migration_pending() {
+    let status = MigrationStatus::from_missing_tables(&[String::from("audit_log")]);
+
+    assert!(!status.is_complete());
+    assert_eq!(status.pending, vec!["V7__Create_Audit_Log_Table"]);
+    assert_eq!(status.applied.len(), MIGRATIONS.len() - 1);
+}
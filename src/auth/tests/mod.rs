@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod attestation_tests;
+#[cfg(all(test, feature = "aws-lc"))]
+mod backend_interop_tests;
+#[cfg(test)]
+mod blacklist_tests;
+#[cfg(test)]
+mod ceremony_limit_tests;
+#[cfg(test)]
+mod ceremony_tests;
+#[cfg(test)]
+mod extensions_tests;
+#[cfg(test)]
+mod keys_tests;
+#[cfg(all(test, feature = "kms"))]
+mod kms_tests;
+#[cfg(test)]
+mod memory_repo_tests;
+#[cfg(test)]
+mod migration_tests;
+#[cfg(test)]
+mod opaque_tests;
+#[cfg(test)]
+mod permissions_tests;
+#[cfg(test)]
+mod recovery_tests;
+#[cfg(test)]
+mod revocation_tests;
+#[cfg(test)]
+mod service_tests;
+#[cfg(test)]
+mod session_tests;
+#[cfg(test)]
+mod throttle_tests;
+#[cfg(test)]
+mod verification_tests;
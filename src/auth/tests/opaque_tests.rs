@@ -0,0 +1,34 @@
+use crate::auth::jwt::opaque::OpaqueToken;
+
+#[test]
+fn test_generated_tokens_are_unique_and_url_safe() {
+    let first = OpaqueToken::generate();
+    let second = OpaqueToken::generate();
+
+    assert_ne!(first, second);
+    let token = first.into_string();
+    assert_eq!(token.len(), 43);
+    assert!(
+        token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    );
+}
+
+#[test]
+fn test_hash_is_stable_and_hides_the_token() {
+    let token = OpaqueToken::generate();
+    let hash = token.hash();
+    let value = token.into_string();
+
+    assert_eq!(OpaqueToken::hash_input(&value), hash);
+    assert_ne!(hash, value);
+}
+
+#[test]
+fn test_matches_tells_opaque_tokens_from_jwts() {
+    assert!(OpaqueToken::matches(&OpaqueToken::generate().into_string()));
+    assert!(!OpaqueToken::matches(
+        "eyJhbGciOiJFZERTQSJ9.eyJzdWIiOiIxIn0.c2ln"
+    ));
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::auth::{
+    jwt::AccessTokenClaims,
+    model::Grants,
+    permissions::{AdminActions, AuditRead, BannerWrite, EnrollmentRead, Permission, TrafficRead},
+};
+
+const ROLES_MIGRATION: &str = include_str!("../../../migrations/V10__Create_Roles_Tables.sql");
+
+fn grants(permissions: &[&str]) -> Grants {
+    Grants {
+        roles: vec![String::from("operator")],
+        permissions: permissions.iter().map(|p| p.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_grants_allow_only_listed_permissions() {
+    let grants = grants(&[AuditRead::SCOPE]);
+
+    assert!(grants.allows(AuditRead::SCOPE));
+    assert!(!grants.allows(TrafficRead::SCOPE));
+    assert!(!Grants::default().allows(AuditRead::SCOPE));
+}
+
+#[test]
+fn test_access_claims_carry_grants_flat() {
+    let claims = AccessTokenClaims::new(
+        Uuid::new_v4(),
+        String::from("alice"),
+        grants(&[BannerWrite::SCOPE]),
+        Duration::from_secs(60),
+    );
+    let json = serde_json::to_value(&claims).unwrap();
+
+    assert_eq!(json["roles"], serde_json::json!(["operator"]));
+    assert_eq!(json["permissions"], serde_json::json!(["banner:write"]));
+
+    let decoded: AccessTokenClaims = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded.grants(), claims.grants());
+}
+
+#[test]
+fn test_access_claims_without_grants_omit_them() {
+    let claims = AccessTokenClaims::new(
+        Uuid::new_v4(),
+        String::from("alice"),
+        Grants::default(),
+        Duration::from_secs(60),
+    );
+    let json = serde_json::to_value(&claims).unwrap();
+
+    assert!(json.get("roles").is_none());
+    assert!(json.get("permissions").is_none());
+}
+
+#[test]
+fn test_admin_role_is_seeded_with_every_scope() {
+    for scope in [
+        AdminActions::SCOPE,
+        AuditRead::SCOPE,
+        BannerWrite::SCOPE,
+        EnrollmentRead::SCOPE,
+        TrafficRead::SCOPE,
+    ] {
+        assert!(
+            ROLES_MIGRATION.contains(&format!("('admin', '{}')", scope)),
+            "{} is not granted to admin",
+            scope
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+use crate::auth::recovery::RecoveryCode;
+
+#[test]
+fn test_generated_code_format() {
+    let code = RecoveryCode::generate();
+    let groups: Vec<&str> = code.as_str().split('-').collect();
+
+    assert_eq!(groups.len(), 3);
+    assert!(groups.iter().all(|group| group.len() == 4));
+    assert!(
+        code.as_str()
+            .chars()
+            .filter(|c| *c != '-')
+            .all(|c| c.is_ascii_digit() || c.is_ascii_uppercase())
+    );
+}
+
+#[test]
+fn test_generated_code_avoids_ambiguous_characters() {
+    for code in RecoveryCode::generate_batch(50) {
+        assert!(!code.as_str().contains(['I', 'L', 'O', 'U']));
+    }
+}
+
+#[test]
+fn test_generate_batch_is_unique() {
+    let codes = RecoveryCode::generate_batch(10);
+    let unique: HashSet<&str> = codes.iter().map(RecoveryCode::as_str).collect();
+
+    assert_eq!(codes.len(), 10);
+    assert_eq!(unique.len(), 10);
+}
+
+#[test]
+fn test_normalize_ignores_case_and_separators() {
+    assert_eq!(RecoveryCode::normalize(" 7kq2-m9xd 4trb "), "7KQ2M9XD4TRB");
+}
+
+#[test]
+fn test_hash_matches_user_input() {
+    let code = RecoveryCode::generate();
+    let typed = code.as_str().to_lowercase().replace('-', " ");
+
+    assert_eq!(code.hash(), RecoveryCode::hash_input(&typed));
+}
+
+#[test]
+fn test_hash_differs_between_codes() {
+    let first = RecoveryCode::hash_input("AAAA-BBBB-CCCC");
+    let second = RecoveryCode::hash_input("AAAA-BBBB-CCCD");
+
+    assert_ne!(first, second);
+    assert_eq!(first.len(), 32);
+}
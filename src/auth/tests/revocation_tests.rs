@@ -0,0 +1,215 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+
+use crate::{
+    app::AppError,
+    auth::jwt::{
+        revocation::{LayeredRevocations, RecentRevocations},
+        traits::{FallbackRevocationStore, RevocationStore},
+    },
+    config::RevocationConfig,
+};
+
+#[derive(Default)]
+struct MockStore {
+    down: AtomicBool,
+    entries: Mutex<HashMap<String, i64>>,
+}
+
+impl MockStore {
+    fn set_down(&self, down: bool) {
+        self.down.store(down, Ordering::SeqCst);
+    }
+
+    fn check(&self) -> Result<(), AppError> {
+        if self.down.load(Ordering::SeqCst) {
+            Err(AppError::CircuitBreakerOpen(String::from("store down")))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn has(&self, jti: &str) -> bool {
+        self.entries.lock().unwrap().contains_key(jti)
+    }
+}
+
+impl RevocationStore for MockStore {
+    async fn revoke(&self, jti: &str, exp: i64) -> Result<(), AppError> {
+        self.check()?;
+        self.entries.lock().unwrap().insert(jti.to_owned(), exp);
+        Ok(())
+    }
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        self.check()?;
+        Ok(self.has(jti))
+    }
+}
+
+impl FallbackRevocationStore for MockStore {
+    async fn drain(&self) -> Result<Vec<(String, i64)>, AppError> {
+        self.check()?;
+        Ok(self.entries.lock().unwrap().drain().collect())
+    }
+}
+
+fn config(max_outage: Option<Duration>) -> RevocationConfig {
+    RevocationConfig {
+        fallback_max_outage: max_outage,
+        fallback_max_entries: 100,
+        memory_entries: 2,
+    }
+}
+
+fn layered(max_outage: Option<Duration>) -> LayeredRevocations<MockStore, MockStore> {
+    LayeredRevocations::new(
+        MockStore::default(),
+        Some(MockStore::default()),
+        &config(max_outage),
+    )
+}
+
+fn exp() -> i64 {
+    Utc::now().timestamp() + 3600
+}
+
+#[tokio::test]
+async fn test_revoke_goes_to_primary_when_healthy() {
+    let store = layered(Some(Duration::from_secs(60)));
+
+    store.revoke("a", exp()).await.unwrap();
+
+    assert!(store.primary().has("a"));
+    assert!(!store.fallback().unwrap().has("a"));
+}
+
+#[tokio::test]
+async fn test_revoke_falls_back_during_outage() {
+    let store = layered(Some(Duration::from_secs(60)));
+    store.primary().set_down(true);
+
+    store.revoke("a", exp()).await.unwrap();
+
+    assert!(store.fallback().unwrap().has("a"));
+}
+
+#[tokio::test]
+async fn test_check_uses_fallback_during_outage() {
+    let store = layered(Some(Duration::from_secs(60)));
+    store.primary().set_down(true);
+    store
+        .fallback()
+        .unwrap()
+        .revoke("other", exp())
+        .await
+        .unwrap();
+
+    assert!(store.is_revoked("other").await.unwrap());
+    assert!(!store.is_revoked("unknown").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_check_uses_memory_when_every_store_is_down() {
+    let store = layered(Some(Duration::from_secs(60)));
+    store.revoke("a", exp()).await.unwrap();
+    store.primary().set_down(true);
+    store.fallback().unwrap().set_down(true);
+
+    assert!(store.is_revoked("a").await.unwrap());
+    assert!(store.is_revoked("b").await.is_err());
+}
+
+#[tokio::test]
+async fn test_fails_closed_without_fallback() {
+    let store = layered(None);
+    store.primary().set_down(true);
+
+    assert!(store.revoke("a", exp()).await.is_err());
+    assert!(store.is_revoked("a").await.is_err());
+}
+
+#[tokio::test]
+async fn test_fails_closed_once_outage_exceeds_window() {
+    let store = layered(Some(Duration::from_millis(10)));
+    store.primary().set_down(true);
+    assert!(!store.is_revoked("a").await.unwrap());
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+
+    assert!(store.is_revoked("a").await.is_err());
+    assert!(store.revoke("a", exp()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_recovery_resets_the_outage_window() {
+    let store = layered(Some(Duration::from_millis(10)));
+    store.primary().set_down(true);
+    let _ = store.is_revoked("a").await;
+    tokio::time::sleep(Duration::from_millis(30)).await;
+
+    store.primary().set_down(false);
+    store.is_revoked("a").await.unwrap();
+    store.primary().set_down(true);
+
+    assert!(store.is_revoked("a").await.is_ok());
+}
+
+#[tokio::test]
+async fn test_fallback_entries_are_restored_to_primary() {
+    let store = layered(Some(Duration::from_secs(60)));
+    store.primary().set_down(true);
+    store.revoke("a", exp()).await.unwrap();
+
+    store.primary().set_down(false);
+    assert!(store.is_revoked("a").await.unwrap());
+
+    assert!(store.primary().has("a"));
+    assert!(!store.fallback().unwrap().has("a"));
+}
+
+#[tokio::test]
+async fn test_entries_left_by_other_instances_are_restored_on_start() {
+    let fallback = MockStore::default();
+    fallback.revoke("a", exp()).await.unwrap();
+    let store = LayeredRevocations::new(
+        MockStore::default(),
+        Some(fallback),
+        &config(Some(Duration::from_secs(60))),
+    );
+
+    assert!(store.is_revoked("a").await.unwrap());
+    assert!(store.primary().has("a"));
+}
+
+#[test]
+fn test_recent_revocations_evict_oldest() {
+    let recent = RecentRevocations::new(2);
+    let now = Utc::now().timestamp();
+
+    recent.insert("a", now + 60);
+    recent.insert("b", now + 60);
+    recent.insert("c", now + 60);
+
+    assert!(!recent.contains("a", now));
+    assert!(recent.contains("b", now));
+    assert!(recent.contains("c", now));
+}
+
+#[test]
+fn test_recent_revocations_ignore_expired_tokens() {
+    let recent = RecentRevocations::new(2);
+    let now = Utc::now().timestamp();
+
+    recent.insert("a", now - 3600);
+
+    assert!(!recent.contains("a", now));
+}
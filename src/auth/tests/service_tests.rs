@@ -0,0 +1,429 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use uuid::Uuid;
+use webauthn_rs::prelude::Passkey;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    audit::model::{AuditContext, AuditOutcome},
+    auth::{
+        dto::{BeginRequest, FinishRequest},
+        jwt::{AccessTokenClaims, JwtService},
+        login_cache::CachedRepository,
+        memory_repo::MemoryRepository,
+        model::{Grants, RedeemedInvitation, SessionDevice, User},
+        service::AuthService,
+        throttle::counts_as_failure,
+        traits::{AuthRepository, ChallengeNonces, InvitationRedeemer, SendFuture},
+    },
+    config::{
+        OriginConfig, RolePolicy, WebAuthnConfig, webauthn::RelyingParties, webauthn::RpBranding,
+    },
+    utils::mocks::{MockAuditLogger, MockDispatcher, MockJwt, MockLoginStore},
+};
+
+struct MockNonces;
+
+impl ChallengeNonces for MockNonces {
+    async fn consume(&self, _: Uuid, _: u64) -> Result<bool, AppError> {
+        Ok(true)
+    }
+}
+
+/// Admits every registration with one invitation for `admin`, and records
+/// what is redeemed.
+#[derive(Default)]
+struct MockInvitations {
+    id: Uuid,
+    redeemed: Mutex<Vec<(Uuid, String)>>,
+}
+
+impl InvitationRedeemer for MockInvitations {
+    fn check<'a>(
+        &'a self,
+        _: &'a str,
+        _: Option<&'a str>,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<RedeemedInvitation>, AppError>> + Send + 'a>>
+    {
+        Box::pin(async move {
+            Ok(Some(RedeemedInvitation {
+                id: self.id,
+                role: Some(String::from("admin")),
+            }))
+        })
+    }
+
+    fn redeem<'a>(
+        &'a self,
+        id: Uuid,
+        username: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, AppError>> + Send + 'a>> {
+        Box::pin(async move {
+            self.redeemed
+                .lock()
+                .unwrap()
+                .push((id, username.to_owned()));
+            Ok(true)
+        })
+    }
+
+    fn release(&self, _: Uuid) -> SendFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+type Service = AuthService<MemoryRepository, MockJwt, MockDispatcher, MockAuditLogger, MockNonces>;
+
+type CachedService = AuthService<
+    CachedRepository<MemoryRepository>,
+    MockJwt,
+    MockDispatcher,
+    MockAuditLogger,
+    MockNonces,
+>;
+
+/// A passkey `testing::SoftPasskey` enrolled, for logins that only begin.
+const PASSKEY: &str = r#"{"cred":{"cred_id":"4SPVvtzWQwy0wALDXYkIZw","cred":{"type_":"ES256","key":{"EC_EC2":{"curve":"SECP256R1","x":"d3-JkpcgJKwAkufe2nXcoPzlIk7tDxl2wil5DF4zfG4","y":"mvojzZtTULeLb2kIPqWfYWkv8S_8R4hKkO1MizWXtTY"}}},"counter":1,"transports":null,"user_verified":true,"backup_eligible":false,"backup_state":false,"registration_policy":"required","extensions":{"cred_protect":"Ignored","hmac_create_secret":"NotRequested","appid":"NotRequested","cred_props":{"Unsigned":{"rk":false}}},"attestation":{"data":"None","metadata":"None"},"attestation_format":"none"}}"#;
+
+struct Fixture {
+    service: Service,
+    jwt: Arc<MockJwt>,
+    audit: Arc<MockAuditLogger>,
+    /// A refresh token of a live session, not yet rotated.
+    refresh_token: String,
+}
+
+async fn fixture(refresh_grace: Duration) -> Fixture {
+    let repo = Arc::new(MemoryRepository::new());
+    let user = repo.create_user("alice", None).await.unwrap();
+    let jwt = Arc::new(MockJwt {
+        refresh_grace,
+        ..Default::default()
+    });
+    let pair = jwt
+        .generate_token_pair(
+            user.id,
+            &user.username,
+            Grants::default(),
+            &SessionDevice::default(),
+        )
+        .await
+        .unwrap();
+    let audit = Arc::new(MockAuditLogger::default());
+
+    Fixture {
+        service: AuthService::new(
+            relying_parties(),
+            repo,
+            jwt.clone(),
+            Arc::new(MockDispatcher::default()),
+            audit.clone(),
+            None,
+            Arc::new(MockNonces),
+        ),
+        jwt,
+        audit,
+        refresh_token: pair.refresh_token.value,
+    }
+}
+
+fn relying_parties() -> RelyingParties {
+    WebAuthnConfig {
+        rp: RpBranding::new("rs-server tests"),
+        timeout: Duration::from_secs(60),
+        stateless: None,
+        attestation_cas: None,
+        extensions: Default::default(),
+        max_concurrent_finishes: 0,
+    }
+    .create_webauthn(&OriginConfig::parse("http://localhost:3000", "localhost"))
+}
+
+/// Registrations are open, apart from the privileged `admin` role.
+fn registration_service(invitations: Arc<MockInvitations>) -> Service {
+    AuthService::new(
+        relying_parties(),
+        Arc::new(MemoryRepository::new()),
+        Arc::new(MockJwt::default()),
+        Arc::new(MockDispatcher::default()),
+        Arc::new(MockAuditLogger::default()),
+        None,
+        Arc::new(MockNonces),
+    )
+    .with_role_policy(RolePolicy::parse("", "admin"))
+    .with_invitations(invitations)
+}
+
+fn begin(username: &str, role: Option<&str>, invitation: Option<&str>) -> BeginRequest {
+    BeginRequest {
+        username: username.to_owned(),
+        role: role.map(str::to_owned),
+        invitation: invitation.map(str::to_owned),
+        email: None,
+        extensions: None,
+    }
+}
+
+struct CacheFixture {
+    service: CachedService,
+    repo: Arc<CachedRepository<MemoryRepository>>,
+    store: Arc<MockLoginStore>,
+    /// Active, with `PASSKEY`.
+    alice: User,
+}
+
+async fn cache_fixture() -> CacheFixture {
+    let store = Arc::new(MockLoginStore::default());
+    let repo = Arc::new(CachedRepository::new(
+        MemoryRepository::new(),
+        Some(store.clone() as _),
+    ));
+    let alice = repo.create_user("alice", None).await.unwrap();
+    let passkey: Passkey = serde_json::from_str(PASSKEY).unwrap();
+    repo.complete_registration(alice.id, "alice", &passkey, None, &[], true)
+        .await
+        .unwrap();
+
+    CacheFixture {
+        service: AuthService::new(
+            relying_parties(),
+            repo.clone(),
+            Arc::new(MockJwt::default()),
+            Arc::new(MockDispatcher::default()),
+            Arc::new(MockAuditLogger::default()),
+            None,
+            Arc::new(MockNonces),
+        ),
+        repo,
+        store,
+        alice,
+    }
+}
+
+fn is_revoked(result: Result<impl Sized, AppError>) -> bool {
+    matches!(result, Err(e) if e.code() == ErrorCode::AuthTokenRevoked)
+}
+
+#[tokio::test]
+async fn test_replay_inside_grace_window_returns_same_pair() {
+    let f = fixture(Duration::from_secs(10)).await;
+    let ctx = AuditContext::default();
+
+    let (rotated, rotated_token) = f.service.refresh(&f.refresh_token, &ctx).await.unwrap();
+    let (replayed, replayed_token) = f.service.refresh(&f.refresh_token, &ctx).await.unwrap();
+
+    assert_eq!(replayed.access_token, rotated.access_token);
+    assert_eq!(replayed_token.value, rotated_token.value);
+    // Only the rotation blacklisted anything.
+    assert_eq!(f.jwt.blacklisted.lock().unwrap().len(), 1);
+
+    let entries = f.audit.entries.lock().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].details["grace"], true);
+    assert!(entries.iter().all(|e| e.outcome == AuditOutcome::Success));
+}
+
+#[tokio::test]
+async fn test_second_replay_is_rejected() {
+    let f = fixture(Duration::from_secs(10)).await;
+    let ctx = AuditContext::default();
+
+    f.service.refresh(&f.refresh_token, &ctx).await.unwrap();
+    f.service.refresh(&f.refresh_token, &ctx).await.unwrap();
+
+    assert!(is_revoked(f.service.refresh(&f.refresh_token, &ctx).await));
+    let entries = f.audit.entries.lock().unwrap();
+    assert_ne!(entries.last().unwrap().outcome, AuditOutcome::Success);
+}
+
+#[tokio::test]
+async fn test_replay_after_grace_window_is_reuse() {
+    let f = fixture(Duration::from_millis(20)).await;
+    let ctx = AuditContext::default();
+
+    f.service.refresh(&f.refresh_token, &ctx).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(is_revoked(f.service.refresh(&f.refresh_token, &ctx).await));
+    let entries = f.audit.entries.lock().unwrap();
+    assert_ne!(entries.last().unwrap().outcome, AuditOutcome::Success);
+}
+
+#[tokio::test]
+async fn test_replay_after_new_token_is_used_is_reuse() {
+    let f = fixture(Duration::from_secs(10)).await;
+    let ctx = AuditContext::default();
+
+    let (_, rotated_token) = f.service.refresh(&f.refresh_token, &ctx).await.unwrap();
+    f.service.refresh(&rotated_token.value, &ctx).await.unwrap();
+
+    assert!(is_revoked(f.service.refresh(&f.refresh_token, &ctx).await));
+}
+
+#[tokio::test]
+async fn test_replay_without_grace_window_is_reuse() {
+    let f = fixture(Duration::ZERO).await;
+    let ctx = AuditContext::default();
+
+    f.service.refresh(&f.refresh_token, &ctx).await.unwrap();
+
+    assert!(is_revoked(f.service.refresh(&f.refresh_token, &ctx).await));
+}
+
+#[tokio::test]
+async fn test_invitation_is_not_redeemed_when_registration_begins() {
+    let invitations = Arc::new(MockInvitations::default());
+    let service = registration_service(invitations.clone());
+
+    service
+        .begin_register(begin("root", Some("admin"), Some("code")), None)
+        .await
+        .unwrap();
+
+    assert!(invitations.redeemed.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_pending_privileged_user_cannot_be_taken_over() {
+    let service = registration_service(Arc::new(MockInvitations::default()));
+    service
+        .begin_register(begin("root", Some("admin"), Some("code")), None)
+        .await
+        .unwrap();
+
+    for request in [
+        begin("root", None, None),
+        begin("root", Some("admin"), Some("code")),
+    ] {
+        let error = service.begin_register(request, None).await.unwrap_err();
+        assert_eq!(error.code(), ErrorCode::UsernameTaken);
+    }
+}
+
+#[tokio::test]
+async fn test_pending_user_without_role_is_picked_up_again() {
+    let service = registration_service(Arc::new(MockInvitations::default()));
+
+    for _ in 0..2 {
+        service
+            .begin_register(begin("alice", None, None), None)
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_login_cache_miss_is_filled_from_the_database() {
+    let f = cache_fixture().await;
+
+    f.service
+        .begin_login(begin("Alice", None, None))
+        .await
+        .unwrap();
+
+    let (user, passkeys) = f.store.entry("alice").unwrap();
+    assert_eq!(user.id, f.alice.id);
+    assert_eq!(passkeys.len(), 1);
+}
+
+#[tokio::test]
+async fn test_login_cache_hit_skips_the_database() {
+    let f = cache_fixture().await;
+    let (user, passkeys) = f
+        .repo
+        .get_active_user_with_credential("alice")
+        .await
+        .unwrap();
+    // Nobody registered bob: only the cache can begin his login.
+    f.store
+        .entries
+        .lock()
+        .unwrap()
+        .insert(String::from("bob"), (user, passkeys));
+
+    f.service
+        .begin_login(begin("bob", None, None))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_unknown_user_is_not_cached() {
+    let f = cache_fixture().await;
+
+    let error = f.service.begin_login(begin("bob", None, None)).await;
+
+    assert!(error.is_err());
+    assert!(f.store.entry("bob").is_none());
+}
+
+#[tokio::test]
+async fn test_account_deletion_invalidates_the_cached_login() {
+    let f = cache_fixture().await;
+    f.service
+        .begin_login(begin("alice", None, None))
+        .await
+        .unwrap();
+    let claims = AccessTokenClaims::new(
+        f.alice.id,
+        f.alice.username.clone(),
+        Grants::default(),
+        Duration::from_secs(60),
+    );
+
+    f.service
+        .delete_account(&claims, &AuditContext::default())
+        .await
+        .unwrap();
+
+    assert!(f.store.entry("alice").is_none());
+}
+
+#[tokio::test]
+async fn test_locking_a_passkey_invalidates_the_cached_login() {
+    let f = cache_fixture().await;
+    let (_, passkeys) = f
+        .repo
+        .get_active_user_with_credential("alice")
+        .await
+        .unwrap();
+    assert!(f.store.entry("alice").is_some());
+
+    f.repo
+        .lock_cloned_credential(f.alice.id, passkeys[0].cred_id())
+        .await
+        .unwrap();
+
+    assert!(f.store.entry("alice").is_none());
+    let error = f
+        .service
+        .begin_login(begin("alice", None, None))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::CredentialLocked);
+}
+
+#[tokio::test]
+async fn test_bad_or_unknown_session_id_is_not_a_failed_attempt() {
+    let f = cache_fixture().await;
+
+    for session_id in [String::from("not-a-session"), Uuid::new_v4().to_string()] {
+        let req = FinishRequest {
+            username: String::from("alice"),
+            session_id,
+            credentials: serde_json::value::RawValue::from_string(String::from("{}")).unwrap(),
+            device_name: None,
+            trusted: false,
+        };
+        let error = f
+            .service
+            .finish_login(req, &AuditContext::default())
+            .await
+            .unwrap_err();
+        assert!(!counts_as_failure(&error));
+    }
+}
@@ -0,0 +1,183 @@
+use std::time::Duration;
+
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Validation, decode};
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, ErrorCode, context::scope_tenant},
+    auth::{
+        jwt::{AccessTokenClaims, RefreshTokenClaims, claims::TokenScope},
+        model::{Grants, SessionDevice},
+    },
+    config::tenant::DEFAULT_TENANT,
+};
+
+fn claims(device: SessionDevice) -> RefreshTokenClaims {
+    RefreshTokenClaims::new(
+        Uuid::new_v4(),
+        String::from("alice"),
+        device,
+        Duration::from_secs(60),
+    )
+}
+
+#[test]
+fn test_device_round_trips_through_claims() {
+    let device = SessionDevice {
+        name: Some(String::from("Work laptop")),
+        trusted: true,
+        azp: Some(String::from("web")),
+    };
+
+    let json = serde_json::to_value(claims(device.clone())).unwrap();
+    let decoded: RefreshTokenClaims = serde_json::from_value(json.clone()).unwrap();
+
+    assert_eq!(json["device_name"], "Work laptop");
+    assert_eq!(json["trusted"], true);
+    assert_eq!(json["azp"], "web");
+    assert_eq!(decoded.device(), &device);
+}
+
+#[test]
+fn test_untrusted_unnamed_session_adds_no_claims() {
+    let json = serde_json::to_value(claims(SessionDevice::default())).unwrap();
+
+    assert!(json.get("device_name").is_none());
+    assert!(json.get("trusted").is_none());
+    assert!(json.get("azp").is_none());
+}
+
+#[test]
+fn test_tokens_without_device_claims_still_decode() {
+    let decoded: RefreshTokenClaims = serde_json::from_value(serde_json::json!({
+        "sub": Uuid::new_v4(),
+        "username": "alice",
+        "jti": "abc",
+        "iat": 1_700_000_000,
+        "exp": 1_700_086_400,
+    }))
+    .unwrap();
+
+    assert_eq!(decoded.device(), &SessionDevice::default());
+}
+
+fn access_claims(azp: Option<&str>) -> AccessTokenClaims {
+    AccessTokenClaims::new(
+        Uuid::new_v4(),
+        String::from("alice"),
+        Grants::default(),
+        Duration::from_secs(60),
+    )
+    .with_azp(azp.map(str::to_owned))
+}
+
+#[test]
+fn test_any_token_passes_without_expected_azp() {
+    assert!(access_claims(None).check_azp(None).is_ok());
+    assert!(access_claims(Some("web")).check_azp(None).is_ok());
+}
+
+#[test]
+fn test_expected_azp_refuses_other_and_unbound_tokens() {
+    assert!(
+        access_claims(Some("console"))
+            .check_azp(Some("console"))
+            .is_ok()
+    );
+
+    for claims in [access_claims(Some("web")), access_claims(None)] {
+        let error = claims.check_azp(Some("console")).unwrap_err();
+        assert!(matches!(error.kind(), AppError::Unauthorized(_)));
+        assert_eq!(error.code(), ErrorCode::AuthTokenWrongClient);
+    }
+}
+
+#[tokio::test]
+async fn test_token_of_another_tenant_is_refused() {
+    let claims = scope_tenant(String::from("shop"), async { access_claims(None) }).await;
+    assert_eq!(claims.tenant, "shop");
+    assert!(claims.check_tenant("shop").is_ok());
+
+    let error = claims.check_tenant(DEFAULT_TENANT).unwrap_err();
+    assert!(matches!(error.kind(), AppError::Unauthorized(_)));
+    assert_eq!(error.code(), ErrorCode::AuthTokenWrongTenant);
+}
+
+#[test]
+fn test_access_tokens_without_tenant_belong_to_default() {
+    let decoded: AccessTokenClaims = serde_json::from_value(serde_json::json!({
+        "sub": Uuid::new_v4(),
+        "username": "alice",
+        "iat": 1_700_000_000,
+        "exp": 1_700_000_900,
+    }))
+    .unwrap();
+
+    assert_eq!(decoded.tenant, DEFAULT_TENANT);
+    assert_eq!(
+        decoded.check_tenant("shop").unwrap_err().code(),
+        ErrorCode::AuthTokenWrongTenant
+    );
+}
+
+const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+fn scope(issuer: &str, audience: &str) -> TokenScope {
+    TokenScope {
+        issuer: Some(issuer.into()),
+        audience: Some(audience.into()),
+    }
+}
+
+fn decode_with(scope: &TokenScope, token: &str) -> Result<RefreshTokenClaims, AppError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    scope.apply(&mut validation);
+    Ok(decode::<RefreshTokenClaims>(token, &DecodingKey::from_secret(SECRET), &validation)?.claims)
+}
+
+#[test]
+fn test_scope_is_minted_into_claims() {
+    let scope = scope("https://auth.example.com", "example-api");
+    let json = serde_json::to_value(access_claims(None).with_scope(&scope)).unwrap();
+
+    assert_eq!(json["iss"], "https://auth.example.com");
+    assert_eq!(json["aud"], "example-api");
+
+    let unscoped = serde_json::to_value(access_claims(None)).unwrap();
+    assert!(unscoped.get("iss").is_none());
+    assert!(unscoped.get("aud").is_none());
+}
+
+#[test]
+fn test_token_of_same_deployment_validates() {
+    let scope = scope("https://auth.example.com", "example-api");
+    let token = claims(SessionDevice::default())
+        .with_scope(&scope)
+        .to_token(&EncodingKey::from_secret(SECRET));
+
+    let decoded = decode_with(&scope, &token).unwrap();
+
+    assert_eq!(decoded.iss.as_deref(), Some("https://auth.example.com"));
+}
+
+#[test]
+fn test_token_of_other_deployment_is_refused() {
+    let token = claims(SessionDevice::default())
+        .with_scope(&scope("https://staging.example.com", "example-api"))
+        .to_token(&EncodingKey::from_secret(SECRET));
+
+    assert!(decode_with(&scope("https://auth.example.com", "example-api"), &token).is_err());
+    assert!(decode_with(&scope("https://staging.example.com", "other-api"), &token).is_err());
+    assert!(
+        decode_with(&TokenScope::default(), &token).is_err(),
+        "an audience no one claims must be refused"
+    );
+}
+
+#[test]
+fn test_unscoped_token_is_refused_once_scope_is_set() {
+    let token = claims(SessionDevice::default()).to_token(&EncodingKey::from_secret(SECRET));
+
+    assert!(decode_with(&TokenScope::default(), &token).is_ok());
+    assert!(decode_with(&scope("https://auth.example.com", "example-api"), &token).is_err());
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use webauthn_rs::prelude::WebauthnError;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    auth::{
+        passkey_format::passkey_rejected,
+        throttle::{ThrottleState, Throttled, counts_as_failure, key},
+    },
+    config::AccountThrottleConfig,
+};
+
+const NOW_MS: i64 = 1_700_000_000_000;
+
+fn state(failures: u32, last_failure_ago_ms: i64) -> ThrottleState {
+    ThrottleState {
+        failures,
+        last_failure_ms: NOW_MS - last_failure_ago_ms,
+        locked_until_ms: None,
+    }
+}
+
+#[test]
+fn test_state_from_redis_fields() {
+    let fields = HashMap::from([
+        (String::from("failures"), String::from("4")),
+        (String::from("last_failure_ms"), NOW_MS.to_string()),
+    ]);
+
+    assert_eq!(ThrottleState::from_fields(&fields), state(4, 0));
+    assert_eq!(
+        ThrottleState::from_fields(&HashMap::new()),
+        ThrottleState::default()
+    );
+}
+
+#[test]
+fn test_free_attempts_are_not_delayed() {
+    let config = AccountThrottleConfig::default();
+    assert_eq!(state(3, 0).throttled(&config, NOW_MS), None);
+    assert_eq!(ThrottleState::default().throttled(&config, NOW_MS), None);
+}
+
+#[test]
+fn test_delay_runs_from_last_failure() {
+    let config = AccountThrottleConfig::default();
+
+    assert_eq!(
+        state(6, 1_500).throttled(&config, NOW_MS),
+        Some(Throttled {
+            retry_after: 3,
+            locked: false,
+        })
+    );
+    assert_eq!(state(6, 4_000).throttled(&config, NOW_MS), None);
+}
+
+#[test]
+fn test_lock_outlasts_delay_and_then_expires() {
+    let config = AccountThrottleConfig::default();
+    let locked = ThrottleState {
+        locked_until_ms: Some(NOW_MS + 600_000),
+        ..state(10, 300_000)
+    };
+
+    let throttled = locked.throttled(&config, NOW_MS).unwrap();
+    assert_eq!(throttled.retry_after, 600);
+    assert!(throttled.locked);
+    assert_eq!(locked.throttled(&config, NOW_MS + 600_000), None);
+}
+
+#[test]
+fn test_lock_answers_account_locked() {
+    let locked = Throttled {
+        retry_after: 60,
+        locked: true,
+    }
+    .into_error();
+    assert_eq!(locked.code(), ErrorCode::AccountLocked);
+    assert!(matches!(locked.kind(), AppError::TooManyRequests(60)));
+
+    let delayed = Throttled {
+        retry_after: 2,
+        locked: false,
+    }
+    .into_error();
+    assert_eq!(delayed.code(), ErrorCode::RateLimited);
+}
+
+#[test]
+fn test_only_rejected_passkeys_count() {
+    assert!(counts_as_failure(&passkey_rejected(
+        WebauthnError::UserNotVerified
+    )));
+    assert!(!counts_as_failure(&AppError::Unauthorized(String::new())));
+    assert!(!counts_as_failure(&AppError::BadRequest(String::new())));
+    assert!(!counts_as_failure(&AppError::NotFound(String::new())));
+    assert!(!counts_as_failure(&AppError::TooManyRequests(1)));
+    assert!(!counts_as_failure(&AppError::ServiceUnavailable(
+        String::new()
+    )));
+    assert!(!counts_as_failure(&AppError::InternalServer(String::new())));
+}
+
+#[test]
+fn test_username_variants_share_the_lock() {
+    assert_eq!(key("Alice"), key("alice"));
+    assert_eq!(key("ａｌｉｃｅ"), key("alice"));
+    assert_eq!(key("ＡＬＩＣＥ"), key("alice"));
+    assert_ne!(key("alice2"), key("alice"));
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::auth::verification::{VerificationProgress, VerificationToken};
+
+#[test]
+fn test_generated_tokens_are_unique_and_url_safe() {
+    let first = VerificationToken::generate();
+    let second = VerificationToken::generate();
+
+    assert_ne!(first, second);
+    assert_eq!(first.as_str().len(), 43);
+    assert!(
+        first
+            .as_str()
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    );
+}
+
+#[test]
+fn test_token_hash_matches_pasted_input() {
+    let token = VerificationToken::generate();
+
+    assert_eq!(
+        VerificationToken::hash_input(&format!(" {}\n", token.as_str())),
+        token.hash()
+    );
+    assert_ne!(
+        VerificationToken::hash_input(&token.as_str().to_lowercase()),
+        token.hash()
+    );
+}
+
+#[test]
+fn test_progress_completes_with_both_steps() {
+    let fields = |keys: &[&str]| -> HashMap<String, String> {
+        keys.iter()
+            .map(|key| (key.to_string(), String::from("1")))
+            .collect()
+    };
+
+    let verified = VerificationProgress::from_fields(&fields(&["email", "verified"]));
+    let enrolled = VerificationProgress::from_fields(&fields(&["enrolled"]));
+    let both = VerificationProgress::from_fields(&fields(&["verified", "enrolled"]));
+
+    assert!(verified.verified && !verified.enrolled);
+    assert!(!enrolled.is_complete());
+    assert!(both.is_complete());
+}
+
+#[cfg(feature = "notifications")]
+#[test]
+fn test_link_carries_username_and_token() {
+    use std::time::Duration;
+
+    use crate::config::EmailVerificationConfig;
+
+    let config = EmailVerificationConfig {
+        ttl: Duration::from_secs(3600),
+        link_base_url: Some(url::Url::parse("https://app.example/verify?lang=en").unwrap()),
+    };
+
+    assert_eq!(
+        config.link("john doe", "abc").as_deref(),
+        Some("https://app.example/verify?lang=en&username=john+doe&token=abc")
+    );
+    assert_eq!(
+        EmailVerificationConfig {
+            link_base_url: None,
+            ..config
+        }
+        .link("john_doe", "abc"),
+        None
+    );
+}
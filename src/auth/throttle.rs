@@ -0,0 +1,255 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+
+use crate::{
+    app::{
+        AppError, ErrorCode, context::current_tenant, middleware::metrics::track_account_lockout,
+    },
+    auth::queries,
+    config::{AccountThrottleConfig, CircuitBreaker},
+    redis_delete, redis_get, redis_pipeline, redis_set,
+    utils::{BaseRedisRepository, normalize_username},
+};
+
+const FAILURES: &str = "failures";
+const LAST_FAILURE_MS: &str = "last_failure_ms";
+const LOCKED_UNTIL_MS: &str = "locked_until_ms";
+
+/// The failed finish steps recorded against one account.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThrottleState {
+    pub failures: u32,
+    pub last_failure_ms: i64,
+    pub locked_until_ms: Option<i64>,
+}
+
+/// Why an attempt has to wait, and for how many seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttled {
+    pub retry_after: u64,
+    pub locked: bool,
+}
+
+impl ThrottleState {
+    pub fn from_fields(fields: &HashMap<String, String>) -> Self {
+        let field = |name: &str| fields.get(name).and_then(|value| value.parse().ok());
+        Self {
+            failures: field(FAILURES).map_or(0, |failures: i64| failures.max(0) as u32),
+            last_failure_ms: field(LAST_FAILURE_MS).unwrap_or(0),
+            locked_until_ms: field(LOCKED_UNTIL_MS),
+        }
+    }
+
+    /// `None` when the account may try again at `now_ms`.
+    pub fn throttled(&self, config: &AccountThrottleConfig, now_ms: i64) -> Option<Throttled> {
+        if let Some(locked_until_ms) = self.locked_until_ms
+            && locked_until_ms > now_ms
+        {
+            return Some(Throttled {
+                retry_after: seconds_until(locked_until_ms, now_ms),
+                locked: true,
+            });
+        }
+
+        let ready_at_ms = self.last_failure_ms + config.delay(self.failures).as_millis() as i64;
+        (ready_at_ms > now_ms).then(|| Throttled {
+            retry_after: seconds_until(ready_at_ms, now_ms),
+            locked: false,
+        })
+    }
+
+    pub fn locked_until(&self) -> Option<DateTime<Utc>> {
+        self.locked_until_ms
+            .and_then(DateTime::from_timestamp_millis)
+    }
+}
+
+impl Throttled {
+    pub fn into_error(self) -> AppError {
+        let error = AppError::TooManyRequests(self.retry_after);
+        if self.locked {
+            error.with_code(ErrorCode::AccountLocked)
+        } else {
+            error
+        }
+    }
+}
+
+/// Whether a failed finish step counts against the account: only once the
+/// ceremony of that user was loaded and WebAuthn rejected the answer. A
+/// junk or unknown session id names the account without trying it, and
+/// server-side failures are not the caller's doing.
+pub fn counts_as_failure(error: &AppError) -> bool {
+    error.code() == ErrorCode::PasskeyRejected
+}
+
+/// Counts failed login and registration finish steps per account in Redis.
+/// Past `free_attempts` each failure makes the next attempt wait twice as
+/// long, and at `lockout_threshold` the account is locked for `lockout`.
+/// Like the request limits, it fails open: with Redis down, nobody is
+/// locked out.
+pub struct AccountThrottle {
+    base: BaseRedisRepository,
+    config: AccountThrottleConfig,
+}
+
+impl AccountThrottle {
+    pub fn new(
+        conn_manager: ConnectionManager,
+        circuit_breaker: Arc<CircuitBreaker>,
+        config: AccountThrottleConfig,
+    ) -> Self {
+        Self {
+            base: BaseRedisRepository::new(conn_manager, circuit_breaker),
+            config,
+        }
+    }
+
+    /// Refuses the attempt while the account is locked or its delay runs.
+    pub async fn check(&self, username: &str) -> Result<(), AppError> {
+        let state = match self.state(username).await {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::warn!(error = %e, "Account throttle check skipped");
+                return Ok(());
+            }
+        };
+
+        match state.throttled(&self.config, Utc::now().timestamp_millis()) {
+            Some(throttled) => Err(throttled.into_error()),
+            None => Ok(()),
+        }
+    }
+
+    /// Records a failed `flow` finish step, locking the account once the
+    /// failures reach the threshold.
+    pub async fn record_failure(&self, username: &str, flow: &str) {
+        match self.increment(username).await {
+            Ok(failures) if failures >= self.config.lockout_threshold => {
+                match self.lock(username).await {
+                    Ok(()) => {
+                        track_account_lockout(flow);
+                        tracing::warn!(
+                            username = %username,
+                            flow = flow,
+                            failures = failures,
+                            "Account locked after repeated failures"
+                        );
+                    }
+                    Err(e) => tracing::error!("Failed to lock account {}: {}", username, e),
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to record failure of {}: {}", username, e),
+        }
+    }
+
+    /// Forgets the failures after a successful finish step.
+    pub async fn reset(&self, username: &str) {
+        if let Err(e) = self.clear(username).await {
+            tracing::error!("Failed to reset failures of {}: {}", username, e);
+        }
+    }
+
+    /// Lifts a lock and forgets the failures, returning what was recorded.
+    /// `None` when nothing was.
+    pub async fn unlock(&self, username: &str) -> Result<Option<ThrottleState>, AppError> {
+        let key = key(username);
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let (fields,): (HashMap<String, String>,) = redis_pipeline!({
+                    redis::pipe()
+                        .atomic()
+                        .hgetall(&key)
+                        .del(&key)
+                        .ignore()
+                        .query_async(&mut conn)
+                        .await
+                })?;
+                Ok((!fields.is_empty()).then(|| ThrottleState::from_fields(&fields)))
+            })
+            .await
+    }
+
+    async fn state(&self, username: &str) -> Result<ThrottleState, AppError> {
+        let key = key(username);
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let fields: HashMap<String, String> =
+                    redis_get!({ redis::cmd("HGETALL").arg(&key).query_async(&mut conn).await })?;
+                Ok(ThrottleState::from_fields(&fields))
+            })
+            .await
+    }
+
+    /// The key outlives both the window and a lock, whichever is longer.
+    async fn increment(&self, username: &str) -> Result<u32, AppError> {
+        let key = key(username);
+        let now_ms = Utc::now().timestamp_millis();
+        let ttl_ms = self.config.window.max(self.config.lockout).as_millis() as i64;
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let (failures,): (u32,) = redis_pipeline!({
+                    redis::pipe()
+                        .atomic()
+                        .hincr(&key, FAILURES, 1)
+                        .hset(&key, LAST_FAILURE_MS, now_ms)
+                        .ignore()
+                        .pexpire(&key, ttl_ms)
+                        .ignore()
+                        .query_async(&mut conn)
+                        .await
+                })?;
+                Ok(failures)
+            })
+            .await
+    }
+
+    async fn lock(&self, username: &str) -> Result<(), AppError> {
+        let key = key(username);
+        let locked_until_ms =
+            Utc::now().timestamp_millis() + self.config.lockout.as_millis() as i64;
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let () = redis_set!({
+                    redis::cmd("HSET")
+                        .arg(&key)
+                        .arg(LOCKED_UNTIL_MS)
+                        .arg(locked_until_ms)
+                        .query_async(&mut conn)
+                        .await
+                })?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn clear(&self, username: &str) -> Result<(), AppError> {
+        let key = key(username);
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let () =
+                    redis_delete!({ redis::cmd("DEL").arg(&key).query_async(&mut conn).await })?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+/// The throttle key of `username` in the current tenant. Usernames fold
+/// the way they are unique in, so a fullwidth or differently cased spelling
+/// shares the lock of the account it logs in to.
+pub fn key(username: &str) -> String {
+    queries::account_throttle::key(&current_tenant(), &normalize_username(username))
+}
+
+fn seconds_until(at_ms: i64, now_ms: i64) -> u64 {
+    ((at_ms - now_ms).max(0) as u64).div_ceil(1000).max(1)
+}
@@ -1,17 +1,37 @@
-use std::future::Future;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{fmt::Debug, future::Future, pin::Pin};
 use uuid::Uuid;
-use webauthn_rs::prelude::Passkey;
+use webauthn_rs::prelude::{AuthenticationResult, Passkey};
 
 use crate::{
     app::AppError,
     auth::{
+        attestation::AaguidPolicy,
         dto::ServiceHealth,
-        model::{User, WebAuthnSession},
+        model::{
+            Grants, MigrationStatus, RecoveryState, RedeemedInvitation, StoredCredential, User,
+            WebAuthnSession,
+        },
     },
 };
 
 pub trait AuthRepository: Send + Sync {
     fn check_db(&self) -> impl Future<Output = ServiceHealth> + Send;
+    fn check_migrations(&self) -> impl Future<Output = Result<MigrationStatus, AppError>> + Send;
+    /// Prepares the queries of the login and token paths on up to
+    /// `connections` pooled connections and returns how many statements
+    /// were prepared; 0 for stores without a statement cache of their own.
+    fn warm_up(&self, connections: usize) -> impl Future<Output = Result<usize, AppError>> + Send;
+    /// Adds the tenants this deployment serves, keeping the ones already
+    /// stored.
+    fn register_tenants(
+        &self,
+        tenants: &[Box<str>],
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// `role` must name an existing role; it is granted to the new user. A
+    /// pending user of that name is returned instead, unless it holds a role
+    /// or `role` is set, which is refused as `UsernameTaken`.
     fn create_user(
         &self,
         username: &str,
@@ -21,6 +41,13 @@ pub trait AuthRepository: Send + Sync {
         &self,
         username: &str,
     ) -> impl Future<Output = Result<User, AppError>> + Send;
+    /// Only users that have not deleted their account.
+    fn get_user_by_id(&self, user_id: Uuid) -> impl Future<Output = Result<User, AppError>> + Send;
+    fn get_grants(&self, user_id: Uuid) -> impl Future<Output = Result<Grants, AppError>> + Send;
+    fn get_aaguid_policy(
+        &self,
+        user_id: Uuid,
+    ) -> impl Future<Output = Result<AaguidPolicy, AppError>> + Send;
     fn get_user_and_session(
         &self,
         session_id: Uuid,
@@ -31,25 +58,149 @@ pub trait AuthRepository: Send + Sync {
         &self,
         username: &str,
     ) -> impl Future<Output = Result<(User, Vec<Passkey>), AppError>> + Send;
-    fn create_webauthn_session(
+    /// `state` is serialized straight into the JSONB parameter.
+    fn create_webauthn_session<S: Serialize + Debug + Sync>(
         &self,
         user_id: Uuid,
-        data: serde_json::Value,
+        state: &S,
         purpose: &str,
     ) -> impl Future<Output = Result<Uuid, AppError>> + Send;
     fn delete_webauthn_session(
         &self,
         id: Uuid,
     ) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Applies a login to the stored passkey with `apply_authentication`,
+    /// and marks the credential as used. Fails with `CREDENTIAL_LOCKED` if
+    /// the passkey was locked since the ceremony began. `username` is who
+    /// signed in, which caches of the login are keyed on.
     fn update_credential(
         &self,
+        username: &str,
+        result: &AuthenticationResult,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Locks the user's passkey as possibly cloned, leaving its stored
+    /// counter alone. Returns whether it was unlocked until now.
+    fn lock_cloned_credential(
+        &self,
+        user_id: Uuid,
+        cred_id: &[u8],
+    ) -> impl Future<Output = Result<bool, AppError>> + Send;
+    /// Lifts the lock on one of the user's passkeys and resets its counter
+    /// with `reset_counter`. Not found unless the passkey is locked.
+    fn unlock_credential(
+        &self,
+        user_id: Uuid,
         cred_id: &[u8],
-        new_counter: u32,
     ) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// The user's passkeys, oldest first.
+    fn list_credentials(
+        &self,
+        user_id: Uuid,
+    ) -> impl Future<Output = Result<Vec<StoredCredential>, AppError>> + Send;
+    /// Stores the passkey and recovery codes; the user is only activated
+    /// with `activate`, otherwise it waits for `activate_pending_user`.
     fn complete_registration(
         &self,
         user_id: Uuid,
         username: &str,
         passkey: &Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+        activate: bool,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+    fn activate_pending_user(
+        &self,
+        username: &str,
     ) -> impl Future<Output = Result<(), AppError>> + Send;
+    fn get_recovery_state(
+        &self,
+        username: &str,
+    ) -> impl Future<Output = Result<RecoveryState, AppError>> + Send;
+    fn consume_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &[u8],
+    ) -> impl Future<Output = Result<bool, AppError>> + Send;
+    /// Counts a failed attempt and, once `max_attempts` is reached, locks
+    /// recovery until `lock_until`. Returns whether this failure applied the lock.
+    fn record_recovery_failure(
+        &self,
+        user_id: Uuid,
+        max_attempts: i32,
+        lock_until: DateTime<Utc>,
+    ) -> impl Future<Output = Result<bool, AppError>> + Send;
+    fn reset_recovery_failures(
+        &self,
+        user_id: Uuid,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Replaces every credential and recovery code of the user with the
+    /// newly enrolled passkey and a fresh set of codes.
+    fn complete_recovery(
+        &self,
+        user_id: Uuid,
+        passkey: &Passkey,
+        aaguid: Option<Uuid>,
+        recovery_code_hashes: &[Vec<u8>],
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Removes the user's credentials, recovery codes, roles and pending
+    /// sessions and soft-deletes the user row, all in one transaction.
+    fn delete_account(&self, user_id: Uuid) -> impl Future<Output = Result<(), AppError>> + Send;
+}
+
+/// One-time use of sealed ceremony state, which carries no server-side record
+/// that finishing could delete.
+pub trait ChallengeNonces: Send + Sync {
+    /// Marks `nonce` as used for `ttl_secs`; `false` if it already was.
+    fn consume(
+        &self,
+        nonce: Uuid,
+        ttl_secs: u64,
+    ) -> impl Future<Output = Result<bool, AppError>> + Send;
+}
+
+pub type LoginStoreFuture<'a, T = ()> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Where `CachedRepository` keeps the user and passkeys a login begins
+/// with. Object safe, like `VerificationSender`. Failures are logged and
+/// read as a miss, since the database still has the answer.
+pub trait LoginStore: Send + Sync {
+    fn get<'a>(&'a self, username: &'a str) -> LoginStoreFuture<'a, Option<(User, Vec<Passkey>)>>;
+    fn put<'a>(
+        &'a self,
+        username: &'a str,
+        user: &'a User,
+        passkeys: &'a [Passkey],
+    ) -> LoginStoreFuture<'a>;
+    /// Drops the entry, so the next login reads the database again.
+    fn invalidate<'a>(&'a self, username: &'a str) -> LoginStoreFuture<'a>;
+}
+
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + 'a>>;
+
+/// Delivers email verification tokens. Object safe, so the transport is
+/// chosen at startup without another type parameter on `AuthService`.
+pub trait VerificationSender: Send + Sync {
+    fn send<'a>(&'a self, email: &'a str, user: &'a User, token: &'a str) -> SendFuture<'a>;
+}
+
+/// Spends the invitations that admit registrations in invite-only mode and
+/// let them take privileged roles. Object safe, like `VerificationSender`.
+pub trait InvitationRedeemer: Send + Sync {
+    /// The usable invitation with `code`. `None` when there is none, or it
+    /// names a role other than `role`. Nothing is spent: registrations
+    /// redeem it when they finish.
+    fn check<'a>(
+        &'a self,
+        code: &'a str,
+        role: Option<&'a str>,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<RedeemedInvitation>, AppError>> + Send + 'a>>;
+    /// Counts a use of the invitation `check` returned by `username`. False
+    /// when it was revoked, expired or spent since.
+    fn redeem<'a>(
+        &'a self,
+        id: Uuid,
+        username: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, AppError>> + Send + 'a>>;
+    /// Gives back the use, when the registration it was spent on failed.
+    fn release(&self, id: Uuid) -> SendFuture<'_>;
 }
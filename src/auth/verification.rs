@@ -0,0 +1,249 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
+use redis::aio::ConnectionManager;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    auth::{model::User, queries, traits::VerificationSender},
+    config::CircuitBreaker,
+    redis_get, redis_pipeline,
+    utils::{
+        BaseRedisRepository,
+        crypto::{random_bytes, sha256},
+    },
+};
+#[cfg(feature = "notifications")]
+use crate::{
+    auth::traits::SendFuture,
+    config::EmailVerificationConfig,
+    notification::{
+        channels::RelayNotifier,
+        model::{Channel, Notification, NotificationEvent},
+        template::TemplateRenderer,
+        traits::Notifier,
+    },
+};
+
+const EMAIL: &str = "email";
+const TOKEN_HASH: &str = "token_hash";
+const VERIFIED: &str = "verified";
+const ENROLLED: &str = "enrolled";
+
+/// A single-use email verification token. Only its hash is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationToken(String);
+
+impl VerificationToken {
+    pub fn generate() -> Self {
+        let entropy: [u8; 32] = random_bytes();
+        Self(BASE64_URL_SAFE_NO_PAD.encode(entropy))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn hash(&self) -> String {
+        Self::hash_input(&self.0)
+    }
+
+    pub fn hash_input(input: &str) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(sha256(input.trim().as_bytes()))
+    }
+}
+
+/// The two halves of a verified registration. The account is activated by
+/// whichever finishes second.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerificationProgress {
+    pub verified: bool,
+    pub enrolled: bool,
+}
+
+impl VerificationProgress {
+    pub fn from_fields(fields: &HashMap<String, String>) -> Self {
+        Self {
+            verified: fields.contains_key(VERIFIED),
+            enrolled: fields.contains_key(ENROLLED),
+        }
+    }
+
+    pub fn is_complete(self) -> bool {
+        self.verified && self.enrolled
+    }
+}
+
+/// Pending verifications live in Redis and expire with their token, so an
+/// abandoned registration leaves nothing behind but a pending user.
+pub struct EmailVerifier {
+    base: BaseRedisRepository,
+    sender: Arc<dyn VerificationSender>,
+    ttl_secs: u64,
+}
+
+impl EmailVerifier {
+    /// Only the email relay can send tokens, so verification needs the
+    /// `notifications` feature.
+    #[cfg_attr(not(feature = "notifications"), allow(dead_code))]
+    pub fn new(
+        conn_manager: ConnectionManager,
+        circuit_breaker: Arc<CircuitBreaker>,
+        sender: Arc<dyn VerificationSender>,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            base: BaseRedisRepository::new(conn_manager, circuit_breaker),
+            sender,
+            ttl_secs: ttl.as_secs(),
+        }
+    }
+
+    /// Replaces any earlier verification of `user`, including its progress,
+    /// and sends a fresh token to `email`.
+    pub async fn start(&self, user: &User, email: &str) -> Result<(), AppError> {
+        let token = VerificationToken::generate();
+        let key = queries::email_verifications::key(&user.id);
+        let fields = [(EMAIL, email.to_owned()), (TOKEN_HASH, token.hash())];
+        let ttl_secs = self.ttl_secs as i64;
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let () = redis_pipeline!({
+                    redis::pipe()
+                        .atomic()
+                        .del(&key)
+                        .ignore()
+                        .hset_multiple(&key, &fields)
+                        .ignore()
+                        .expire(&key, ttl_secs)
+                        .ignore()
+                        .query_async(&mut conn)
+                        .await
+                })?;
+                Ok(())
+            })
+            .await?;
+
+        self.sender.send(email, user, token.as_str()).await
+    }
+
+    pub async fn confirm(
+        &self,
+        user_id: Uuid,
+        token: &str,
+    ) -> Result<VerificationProgress, AppError> {
+        let key = queries::email_verifications::key(&user_id);
+
+        let stored: Option<String> = self
+            .base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                Ok(redis_get!({
+                    redis::cmd("HGET")
+                        .arg(&key)
+                        .arg(TOKEN_HASH)
+                        .query_async(&mut conn)
+                        .await
+                })?)
+            })
+            .await?;
+
+        if stored.as_deref() != Some(VerificationToken::hash_input(token).as_str()) {
+            return Err(AppError::Unauthorized(String::from(
+                "Invalid or expired verification token",
+            )));
+        }
+
+        self.record(user_id, VERIFIED).await
+    }
+
+    pub async fn mark_enrolled(&self, user_id: Uuid) -> Result<VerificationProgress, AppError> {
+        self.record(user_id, ENROLLED).await
+    }
+
+    /// Sets `step` and reads both flags in one transaction, so of two steps
+    /// finishing at once, at least one sees the other. `EXPIRE NX` keeps a
+    /// step recorded after expiry from living forever.
+    async fn record(
+        &self,
+        user_id: Uuid,
+        step: &'static str,
+    ) -> Result<VerificationProgress, AppError> {
+        let key = queries::email_verifications::key(&user_id);
+        let ttl_secs = self.ttl_secs;
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let (fields,): (HashMap<String, String>,) = redis_pipeline!({
+                    redis::pipe()
+                        .atomic()
+                        .hset(&key, step, 1)
+                        .ignore()
+                        .cmd("EXPIRE")
+                        .arg(&key)
+                        .arg(ttl_secs)
+                        .arg("NX")
+                        .ignore()
+                        .hgetall(&key)
+                        .query_async(&mut conn)
+                        .await
+                })?;
+                Ok(VerificationProgress::from_fields(&fields))
+            })
+            .await
+    }
+}
+
+/// Sends the `email_verification` template through the email relay, straight
+/// to the address being verified.
+#[cfg(feature = "notifications")]
+pub struct RelayVerificationSender {
+    notifier: RelayNotifier,
+    renderer: TemplateRenderer,
+    locale: Box<str>,
+    config: EmailVerificationConfig,
+}
+
+#[cfg(feature = "notifications")]
+impl RelayVerificationSender {
+    pub fn new(
+        notifier: RelayNotifier,
+        renderer: TemplateRenderer,
+        locale: Box<str>,
+        config: EmailVerificationConfig,
+    ) -> Self {
+        Self {
+            notifier,
+            renderer,
+            locale,
+            config,
+        }
+    }
+
+    pub fn notification(&self, user: &User, token: &str) -> Notification {
+        Notification::new(
+            NotificationEvent::EmailVerification,
+            user.id,
+            &user.username,
+            serde_json::json!({
+                "token": token,
+                "link": self.config.link(&user.username, token),
+                "expires_in_minutes": self.config.ttl.as_secs() / 60,
+            }),
+        )
+    }
+}
+
+#[cfg(feature = "notifications")]
+impl VerificationSender for RelayVerificationSender {
+    fn send<'a>(&'a self, email: &'a str, user: &'a User, token: &'a str) -> SendFuture<'a> {
+        Box::pin(async move {
+            let notification = self.notification(user, token);
+            let message =
+                self.renderer
+                    .render(&notification, Channel::Email, &self.locale, None)?;
+            self.notifier.send(email, &notification, &message).await
+        })
+    }
+}
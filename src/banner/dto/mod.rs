@@ -0,0 +1,5 @@
+pub(crate) mod request;
+pub(crate) mod response;
+
+pub(crate) use request::UpdateBannerRequest;
+pub(crate) use response::{BannerResponse, CurrentBannerResponse};
@@ -0,0 +1,46 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::AppError,
+    banner::model::BannerSeverity,
+    impl_validated_json_request,
+    utils::{Validatable, validate_text},
+};
+
+pub const MAX_BANNER_CHARS: usize = 500;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateBannerRequest {
+    #[schema(
+        example = "Scheduled maintenance on Saturday 02:00-04:00 UTC",
+        max_length = 500
+    )]
+    pub message: String,
+    /// `info`, `warning` or `critical`
+    #[schema(example = "warning")]
+    pub severity: String,
+}
+
+impl UpdateBannerRequest {
+    pub fn severity(&self) -> Result<BannerSeverity, AppError> {
+        BannerSeverity::try_from(self.severity.as_str())
+    }
+}
+
+impl Validatable for UpdateBannerRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        validate_text(&self.message, "Banner message")?;
+
+        if self.message.chars().count() > MAX_BANNER_CHARS {
+            return Err(AppError::BadRequest(format!(
+                "Banner message must be at most {} characters",
+                MAX_BANNER_CHARS
+            )));
+        }
+
+        self.severity().map(|_| ())
+    }
+}
+
+impl_validated_json_request!(UpdateBannerRequest);
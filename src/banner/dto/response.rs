@@ -0,0 +1,38 @@
+use axum::{Json, http::header, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::banner::{model::Banner, service::BANNER_CACHE_TTL};
+
+/// `banner` is null when no message is set.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CurrentBannerResponse {
+    pub banner: Option<BannerResponse>,
+}
+
+impl IntoResponse for CurrentBannerResponse {
+    fn into_response(self) -> axum::response::Response {
+        let cache_control = format!("public, max-age={}", BANNER_CACHE_TTL.as_secs());
+        ([(header::CACHE_CONTROL, cache_control)], Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BannerResponse {
+    #[schema(example = "Scheduled maintenance on Saturday 02:00-04:00 UTC")]
+    pub message: String,
+    #[schema(example = "warning")]
+    pub severity: String,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub updated_at: String,
+}
+
+impl From<Banner> for BannerResponse {
+    fn from(banner: Banner) -> Self {
+        Self {
+            message: banner.message,
+            severity: banner.severity.as_str().to_owned(),
+            updated_at: banner.updated_at.to_rfc3339(),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    audit::model::AuditContext,
+    auth::permissions::BannerWrite,
+    banner::dto::{CurrentBannerResponse, UpdateBannerRequest},
+};
+
+/// Login page banner
+///
+/// Returns the message frontends should display on the login page, such as a
+/// planned maintenance notice, or a null `banner` when none is set.
+#[utoipa::path(
+    get,
+    path = "/auth/banner",
+    tag = "Authentication",
+    responses(
+        (status = 200, description = "Current banner", body = CurrentBannerResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn current(
+    State(state): State<Arc<AppState>>,
+) -> Result<CurrentBannerResponse, AppError> {
+    state.banner_service.current().await
+}
+
+/// Set the login page banner
+///
+/// Replaces the banner shown on the login page. Other instances pick up the
+/// change within 30 seconds. Requires `banner:write`.
+#[utoipa::path(
+    put,
+    path = "/admin/banner",
+    tag = "Admin",
+    request_body = UpdateBannerRequest,
+    responses(
+        (status = 200, description = "Banner updated", body = CurrentBannerResponse),
+        (status = 400, description = "Invalid message or severity", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn update(
+    admin: RequirePermission<BannerWrite>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: UpdateBannerRequest,
+) -> Result<CurrentBannerResponse, AppError> {
+    state.banner_service.update(request, &admin, &ctx).await
+}
+
+/// Clear the login page banner
+///
+/// Removes the banner. Requires `banner:write`.
+#[utoipa::path(
+    delete,
+    path = "/admin/banner",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Banner cleared", body = CurrentBannerResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn clear(
+    admin: RequirePermission<BannerWrite>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+) -> Result<CurrentBannerResponse, AppError> {
+    state.banner_service.clear(&admin, &ctx).await
+}
@@ -0,0 +1,65 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+use crate::{app::AppError, utils::FromRow};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BannerSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl BannerSeverity {
+    pub const ALL: [BannerSeverity; 3] = [
+        BannerSeverity::Info,
+        BannerSeverity::Warning,
+        BannerSeverity::Critical,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BannerSeverity::Info => "info",
+            BannerSeverity::Warning => "warning",
+            BannerSeverity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for BannerSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<&str> for BannerSeverity {
+    type Error = AppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|severity| severity.as_str() == value)
+            .ok_or_else(|| AppError::BadRequest(format!("Unknown banner severity: {}", value)))
+    }
+}
+
+/// The message shown on the login page, e.g. a planned maintenance notice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Banner {
+    pub message: String,
+    pub severity: BannerSeverity,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FromRow for Banner {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        let severity: String = row.try_get("severity")?;
+
+        Ok(Banner {
+            message: row.try_get("message")?,
+            severity: BannerSeverity::try_from(severity.as_str())?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
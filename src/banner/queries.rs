@@ -0,0 +1,14 @@
+pub mod login_banner {
+    pub const SELECT: &str = "SELECT message, severity, updated_at FROM login_banner";
+
+    pub const UPSERT: &str = "INSERT INTO login_banner (message, severity, updated_by)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (id) DO UPDATE
+         SET message = EXCLUDED.message,
+             severity = EXCLUDED.severity,
+             updated_by = EXCLUDED.updated_by,
+             updated_at = NOW()
+         RETURNING message, severity, updated_at";
+
+    pub const DELETE: &str = "DELETE FROM login_banner";
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    banner::{
+        model::{Banner, BannerSeverity},
+        queries,
+        traits::BannerRepository,
+    },
+    config::CircuitBreaker,
+    db_delete, db_insert, db_select,
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl BannerRepository for Repository {
+    async fn get(&self) -> Result<Option<Banner>, AppError> {
+        let row = db_select!("login_banner", {
+            self.base
+                .execute_prepared_opt(queries::login_banner::SELECT, &[])
+                .await
+        })?;
+
+        row.as_ref().map(Banner::from_row).transpose()
+    }
+
+    async fn upsert(
+        &self,
+        message: &str,
+        severity: BannerSeverity,
+        updated_by: Uuid,
+    ) -> Result<Banner, AppError> {
+        let row = db_insert!("login_banner", {
+            self.base
+                .execute_prepared_one(
+                    queries::login_banner::UPSERT,
+                    &[
+                        &message as &(dyn ToSql + Sync),
+                        &severity.as_str(),
+                        &updated_by,
+                    ],
+                )
+                .await
+        })?;
+
+        Banner::from_row(&row)
+    }
+
+    async fn delete(&self) -> Result<bool, AppError> {
+        let deleted = db_delete!("login_banner", {
+            self.base
+                .execute_prepared_raw(queries::login_banner::DELETE, &[])
+                .await
+        })?;
+
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    app::AppError,
+    audit::{
+        model::{AuditContext, AuditEntry, AuditEvent},
+        traits::AuditLogger,
+    },
+    auth::jwt::{AccessTokenClaims, claims::JwtClaims},
+    banner::{
+        dto::{CurrentBannerResponse, UpdateBannerRequest},
+        model::Banner,
+        traits::BannerRepository,
+    },
+};
+
+/// How long an instance serves its cached banner before rereading it, which
+/// bounds how long other instances show a banner after it changes.
+pub const BANNER_CACHE_TTL: Duration = Duration::from_secs(30);
+
+struct CachedBanner {
+    banner: Option<Banner>,
+    loaded_at: Instant,
+}
+
+pub struct BannerService<R, A>
+where
+    R: BannerRepository + 'static,
+    A: AuditLogger + 'static,
+{
+    banner_repo: Arc<R>,
+    audit_logger: Arc<A>,
+    cache: RwLock<Option<CachedBanner>>,
+}
+
+impl<R, A> BannerService<R, A>
+where
+    R: BannerRepository + 'static,
+    A: AuditLogger + 'static,
+{
+    pub fn new(banner_repo: Arc<R>, audit_logger: Arc<A>) -> Self {
+        Self {
+            banner_repo,
+            audit_logger,
+            cache: RwLock::new(None),
+        }
+    }
+
+    /// Serves the cached banner while fresh. If the database cannot be
+    /// reached, a stale banner beats failing the login page.
+    pub async fn current(&self) -> Result<CurrentBannerResponse, AppError> {
+        if let Some(cached) = self.cache.read().unwrap().as_ref()
+            && cached.loaded_at.elapsed() < BANNER_CACHE_TTL
+        {
+            return Ok(Self::response(cached.banner.clone()));
+        }
+
+        match self.banner_repo.get().await {
+            Ok(banner) => {
+                self.store(banner.clone());
+                Ok(Self::response(banner))
+            }
+            Err(e) => match self.cache.read().unwrap().as_ref() {
+                Some(cached) => {
+                    tracing::warn!("Serving stale login banner: {}", e);
+                    Ok(Self::response(cached.banner.clone()))
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    pub async fn update(
+        &self,
+        req: UpdateBannerRequest,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<CurrentBannerResponse, AppError> {
+        let severity = req.severity()?;
+        let result = self
+            .banner_repo
+            .upsert(&req.message, severity, *actor.sub())
+            .await;
+
+        self.record(
+            actor,
+            ctx,
+            result.as_ref().map(|_| ()),
+            serde_json::json!({
+                "action": "update-banner",
+                "severity": severity.as_str(),
+                "message": req.message,
+            }),
+        );
+
+        let banner = result?;
+        self.store(Some(banner.clone()));
+        Ok(Self::response(Some(banner)))
+    }
+
+    pub async fn clear(
+        &self,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<CurrentBannerResponse, AppError> {
+        let result = self.banner_repo.delete().await;
+
+        self.record(
+            actor,
+            ctx,
+            result.as_ref().map(|_| ()),
+            serde_json::json!({ "action": "clear-banner" }),
+        );
+
+        result?;
+        self.store(None);
+        Ok(Self::response(None))
+    }
+
+    fn store(&self, banner: Option<Banner>) {
+        *self.cache.write().unwrap() = Some(CachedBanner {
+            banner,
+            loaded_at: Instant::now(),
+        });
+    }
+
+    fn record(
+        &self,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+        result: Result<(), &AppError>,
+        details: serde_json::Value,
+    ) {
+        self.audit_logger.record(
+            AuditEntry::new(AuditEvent::AdminAction, ctx, Some(actor.username()), result)
+                .with_user_id(*actor.sub())
+                .with_details(details),
+        );
+    }
+
+    fn response(banner: Option<Banner>) -> CurrentBannerResponse {
+        CurrentBannerResponse {
+            banner: banner.map(Into::into),
+        }
+    }
+}
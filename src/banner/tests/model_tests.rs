@@ -0,0 +1,34 @@
+use crate::{
+    banner::{dto::UpdateBannerRequest, model::BannerSeverity},
+    utils::Validatable,
+};
+
+fn request(message: &str, severity: &str) -> UpdateBannerRequest {
+    UpdateBannerRequest {
+        message: String::from(message),
+        severity: String::from(severity),
+    }
+}
+
+#[test]
+fn test_severity_round_trips() {
+    for severity in BannerSeverity::ALL {
+        assert_eq!(
+            BannerSeverity::try_from(severity.as_str()).unwrap(),
+            severity
+        );
+    }
+}
+
+#[test]
+fn test_unknown_severity_is_rejected() {
+    assert!(BannerSeverity::try_from("urgent").is_err());
+    assert!(request("Maintenance tonight", "urgent").validate().is_err());
+}
+
+#[test]
+fn test_banner_request_validation() {
+    assert!(request("Maintenance tonight", "warning").validate().is_ok());
+    assert!(request("   ", "info").validate().is_err());
+    assert!(request(&"x".repeat(501), "info").validate().is_err());
+}
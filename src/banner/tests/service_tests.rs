@@ -0,0 +1,167 @@
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::model::{AuditContext, AuditEvent},
+    banner::{
+        dto::UpdateBannerRequest,
+        model::{Banner, BannerSeverity},
+        service::BannerService,
+        traits::BannerRepository,
+    },
+    utils::mocks::{MockAuditLogger, admin_claims},
+};
+
+#[derive(Default)]
+struct MockRepository {
+    banner: Mutex<Option<Banner>>,
+    reads: AtomicUsize,
+    failing: AtomicBool,
+}
+
+impl BannerRepository for MockRepository {
+    async fn get(&self) -> Result<Option<Banner>, AppError> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        if self.failing.load(Ordering::Relaxed) {
+            return Err(AppError::ServiceUnavailable(String::from("database down")));
+        }
+        Ok(self.banner.lock().unwrap().clone())
+    }
+
+    async fn upsert(
+        &self,
+        message: &str,
+        severity: BannerSeverity,
+        _: Uuid,
+    ) -> Result<Banner, AppError> {
+        let banner = Banner {
+            message: message.to_owned(),
+            severity,
+            updated_at: Utc::now(),
+        };
+        *self.banner.lock().unwrap() = Some(banner.clone());
+        Ok(banner)
+    }
+
+    async fn delete(&self) -> Result<bool, AppError> {
+        Ok(self.banner.lock().unwrap().take().is_some())
+    }
+}
+
+struct Fixture {
+    service: BannerService<MockRepository, MockAuditLogger>,
+    repo: Arc<MockRepository>,
+    audit: Arc<MockAuditLogger>,
+}
+
+fn fixture() -> Fixture {
+    let repo = Arc::new(MockRepository::default());
+    let audit = Arc::new(MockAuditLogger::default());
+
+    Fixture {
+        service: BannerService::new(Arc::clone(&repo), Arc::clone(&audit)),
+        repo,
+        audit,
+    }
+}
+
+fn update(message: &str) -> UpdateBannerRequest {
+    UpdateBannerRequest {
+        message: String::from(message),
+        severity: String::from("warning"),
+    }
+}
+
+#[tokio::test]
+async fn test_current_is_cached() {
+    let f = fixture();
+
+    assert!(f.service.current().await.unwrap().banner.is_none());
+    assert!(f.service.current().await.unwrap().banner.is_none());
+
+    assert_eq!(f.repo.reads.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_update_is_visible_immediately() {
+    let f = fixture();
+    f.service.current().await.unwrap();
+
+    f.service
+        .update(
+            update("Maintenance tonight"),
+            &admin_claims(&["banner:write"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    let banner = f.service.current().await.unwrap().banner.unwrap();
+    assert_eq!(banner.message, "Maintenance tonight");
+    assert_eq!(banner.severity, "warning");
+    assert_eq!(f.repo.reads.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_clear_removes_banner() {
+    let f = fixture();
+    f.service
+        .update(
+            update("Maintenance tonight"),
+            &admin_claims(&["banner:write"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    f.service
+        .clear(&admin_claims(&["banner:write"]), &AuditContext::default())
+        .await
+        .unwrap();
+
+    assert!(f.service.current().await.unwrap().banner.is_none());
+}
+
+#[tokio::test]
+async fn test_changes_are_audited() {
+    let f = fixture();
+    let actor = admin_claims(&["banner:write"]);
+
+    f.service
+        .update(
+            update("Maintenance tonight"),
+            &actor,
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+    f.service
+        .clear(&actor, &AuditContext::default())
+        .await
+        .unwrap();
+
+    let entries = f.audit.entries.lock().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(
+        entries
+            .iter()
+            .all(|entry| entry.event == AuditEvent::AdminAction)
+    );
+    assert_eq!(entries[0].user_id, Some(actor.sub));
+    assert_eq!(entries[0].details["action"], "update-banner");
+    assert_eq!(entries[1].details["action"], "clear-banner");
+}
+
+#[tokio::test]
+async fn test_read_failure_without_cache_is_an_error() {
+    let f = fixture();
+    f.repo.failing.store(true, Ordering::Relaxed);
+
+    assert!(f.service.current().await.is_err());
+}
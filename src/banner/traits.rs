@@ -0,0 +1,20 @@
+use std::future::Future;
+
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    banner::model::{Banner, BannerSeverity},
+};
+
+pub trait BannerRepository: Send + Sync {
+    fn get(&self) -> impl Future<Output = Result<Option<Banner>, AppError>> + Send;
+    fn upsert(
+        &self,
+        message: &str,
+        severity: BannerSeverity,
+        updated_by: Uuid,
+    ) -> impl Future<Output = Result<Banner, AppError>> + Send;
+    /// Returns whether a banner was removed.
+    fn delete(&self) -> impl Future<Output = Result<bool, AppError>> + Send;
+}
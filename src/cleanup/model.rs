@@ -0,0 +1,14 @@
+/// Rows removed by one cleanup run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeReport {
+    pub expired_sessions: u64,
+    pub stale_users: u64,
+    /// Rows of partitions dropped past their table's retention.
+    pub expired_partition_rows: u64,
+}
+
+impl PurgeReport {
+    pub fn total(&self) -> u64 {
+        self.expired_sessions + self.stale_users + self.expired_partition_rows
+    }
+}
@@ -0,0 +1,31 @@
+pub mod webauthn_sessions {
+    pub const DELETE_EXPIRED: &str = "DELETE FROM webauthn_sessions
+         WHERE id IN (
+             SELECT id FROM webauthn_sessions
+             WHERE expires_at < NOW()
+             LIMIT $1
+         )";
+
+    pub const COUNT_PENDING: &str = "SELECT purpose, COUNT(*) AS pending
+         FROM webauthn_sessions
+         WHERE expires_at > NOW()
+         GROUP BY purpose";
+}
+
+pub mod partitions {
+    /// Creates the upcoming partitions of a table before dropping expired ones.
+    pub const MAINTAIN: &str = "SELECT maintain_partitions($1, $2, $3) AS dropped";
+}
+
+pub mod users {
+    /// Users that never finished registering. Anyone holding a credential is
+    /// kept, whatever their status says.
+    pub const DELETE_STALE_PENDING: &str = "DELETE FROM users
+         WHERE id IN (
+             SELECT u.id FROM users u
+             WHERE u.status = 'pending'
+               AND u.created_at < $1
+               AND NOT EXISTS (SELECT 1 FROM credentials c WHERE c.user_id = u.id)
+             LIMIT $2
+         )";
+}
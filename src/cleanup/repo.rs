@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+
+use crate::{
+    app::AppError,
+    cleanup::{queries, traits::CleanupRepository},
+    config::CircuitBreaker,
+    db_delete, db_select,
+    utils::BaseRepository,
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl CleanupRepository for Repository {
+    async fn delete_expired_sessions(&self, batch_size: i64) -> Result<u64, AppError> {
+        db_delete!("webauthn_sessions", {
+            self.base
+                .execute_prepared_raw(
+                    queries::webauthn_sessions::DELETE_EXPIRED,
+                    &[&batch_size as &(dyn tokio_postgres::types::ToSql + Sync)],
+                )
+                .await
+        })
+    }
+
+    async fn count_pending_sessions(&self) -> Result<Vec<(String, i64)>, AppError> {
+        let rows = db_select!("webauthn_sessions", {
+            self.base
+                .execute_prepared(queries::webauthn_sessions::COUNT_PENDING, &[])
+                .await
+        })?;
+
+        rows.iter()
+            .map(|row| Ok((row.try_get("purpose")?, row.try_get("pending")?)))
+            .collect()
+    }
+
+    async fn delete_stale_users(
+        &self,
+        created_before: DateTime<Utc>,
+        batch_size: i64,
+    ) -> Result<u64, AppError> {
+        db_delete!("users", {
+            self.base
+                .execute_prepared_raw(
+                    queries::users::DELETE_STALE_PENDING,
+                    &[&created_before, &batch_size],
+                )
+                .await
+        })
+    }
+
+    async fn maintain_partitions(
+        &self,
+        table: &str,
+        months_ahead: i32,
+        retention_days: i32,
+    ) -> Result<u64, AppError> {
+        let row = db_delete!(table, {
+            self.base
+                .execute_prepared_one(
+                    queries::partitions::MAINTAIN,
+                    &[
+                        &table as &(dyn tokio_postgres::types::ToSql + Sync),
+                        &months_ahead,
+                        &retention_days,
+                    ],
+                )
+                .await
+        })?;
+
+        Ok(row.try_get::<_, i64>("dropped")? as u64)
+    }
+}
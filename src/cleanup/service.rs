@@ -0,0 +1,140 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+
+use crate::{
+    app::{
+        AppError,
+        middleware::metrics::{track_cleanup_purge, update_pending_ceremonies},
+    },
+    cleanup::{model::PurgeReport, traits::CleanupRepository},
+    config::CleanupConfig,
+};
+
+/// How often the pending ceremony gauge is refreshed.
+const PENDING_SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Every session purpose, so one with nothing pending reads 0 rather than
+/// keeping its last count.
+const CEREMONIES: [&str; 3] = ["registration", "login", "recovery"];
+
+pub struct CleanupService<R>
+where
+    R: CleanupRepository + 'static,
+{
+    cleanup_repo: Arc<R>,
+    config: CleanupConfig,
+}
+
+impl<R> CleanupService<R>
+where
+    R: CleanupRepository + 'static,
+{
+    pub fn new(cleanup_repo: Arc<R>, config: CleanupConfig) -> Self {
+        Self {
+            cleanup_repo,
+            config,
+        }
+    }
+
+    /// Purges on a fixed interval for the lifetime of the process.
+    pub fn spawn_purge(self: &Arc<Self>) {
+        let Some(period) = self.config.interval else {
+            return;
+        };
+
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match service.run_purge().await {
+                    Ok(report) if report.total() == 0 => {}
+                    Ok(report) => tracing::info!(
+                        expired_sessions = report.expired_sessions,
+                        stale_users = report.stale_users,
+                        expired_partition_rows = report.expired_partition_rows,
+                        "Cleanup purged stale rows"
+                    ),
+                    Err(e) => tracing::error!("Cleanup job failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Refreshes `webauthn_pending_ceremonies` for the lifetime of the
+    /// process. Only stateful ceremonies have a session row to count.
+    pub fn spawn_pending_sampler(self: &Arc<Self>) {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PENDING_SAMPLE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.sample_pending_sessions().await {
+                    tracing::warn!("Failed to count pending ceremonies: {}", e);
+                }
+            }
+        });
+    }
+
+    pub async fn sample_pending_sessions(&self) -> Result<(), AppError> {
+        let pending = self.cleanup_repo.count_pending_sessions().await?;
+        for ceremony in CEREMONIES {
+            let count = pending
+                .iter()
+                .find(|(purpose, _)| purpose == ceremony)
+                .map_or(0, |(_, count)| *count);
+            update_pending_ceremonies(ceremony, count);
+        }
+        Ok(())
+    }
+
+    /// Deletes batches until nothing is left to purge, so a backlog drains in
+    /// one run without holding locks on a large delete.
+    pub async fn run_purge(&self) -> Result<PurgeReport, AppError> {
+        let batch_size = i64::from(self.config.batch_size);
+        let mut report = PurgeReport::default();
+
+        loop {
+            let deleted = self
+                .cleanup_repo
+                .delete_expired_sessions(batch_size)
+                .await?;
+            track_cleanup_purge("webauthn_sessions", deleted);
+            report.expired_sessions += deleted;
+            if deleted < batch_size as u64 {
+                break;
+            }
+        }
+
+        let months_ahead = i32::try_from(self.config.partition_months_ahead).unwrap_or(i32::MAX);
+        for policy in &self.config.partitions {
+            let retention_days = i32::try_from(policy.retention_days).unwrap_or(i32::MAX);
+            let dropped = self
+                .cleanup_repo
+                .maintain_partitions(policy.table, months_ahead, retention_days)
+                .await?;
+            track_cleanup_purge(policy.table, dropped);
+            report.expired_partition_rows += dropped;
+        }
+
+        let Some(ttl) = self.config.pending_user_ttl else {
+            return Ok(report);
+        };
+        let created_before = Utc::now() - ttl;
+
+        loop {
+            let deleted = self
+                .cleanup_repo
+                .delete_stale_users(created_before, batch_size)
+                .await?;
+            track_cleanup_purge("users", deleted);
+            report.stale_users += deleted;
+            if deleted < batch_size as u64 {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+}
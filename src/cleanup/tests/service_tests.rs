@@ -0,0 +1,165 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    app::{AppError, middleware::metrics::PENDING_CEREMONIES},
+    cleanup::{model::PurgeReport, service::CleanupService, traits::CleanupRepository},
+    config::{CleanupConfig, cleanup::PartitionPolicy},
+};
+
+/// Hands out the queued batch sizes in order, then zero.
+#[derive(Default)]
+struct MockRepository {
+    session_batches: Mutex<Vec<u64>>,
+    user_batches: Mutex<Vec<u64>>,
+    users_created_before: Mutex<Option<DateTime<Utc>>>,
+    maintained_partitions: Mutex<Vec<(String, i32, i32)>>,
+}
+
+impl MockRepository {
+    fn new(sessions: &[u64], users: &[u64]) -> Self {
+        Self {
+            session_batches: Mutex::new(sessions.iter().rev().copied().collect()),
+            user_batches: Mutex::new(users.iter().rev().copied().collect()),
+            users_created_before: Mutex::new(None),
+            maintained_partitions: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl CleanupRepository for MockRepository {
+    async fn delete_expired_sessions(&self, _: i64) -> Result<u64, AppError> {
+        Ok(self.session_batches.lock().unwrap().pop().unwrap_or(0))
+    }
+
+    async fn count_pending_sessions(&self) -> Result<Vec<(String, i64)>, AppError> {
+        Ok(vec![(String::from("login"), 3)])
+    }
+
+    async fn delete_stale_users(
+        &self,
+        created_before: DateTime<Utc>,
+        _: i64,
+    ) -> Result<u64, AppError> {
+        *self.users_created_before.lock().unwrap() = Some(created_before);
+        Ok(self.user_batches.lock().unwrap().pop().unwrap_or(0))
+    }
+
+    async fn maintain_partitions(
+        &self,
+        table: &str,
+        months_ahead: i32,
+        retention_days: i32,
+    ) -> Result<u64, AppError> {
+        self.maintained_partitions.lock().unwrap().push((
+            table.to_string(),
+            months_ahead,
+            retention_days,
+        ));
+        Ok(7)
+    }
+}
+
+fn service(
+    repo: &Arc<MockRepository>,
+    pending_user_ttl: Option<Duration>,
+) -> CleanupService<MockRepository> {
+    CleanupService::new(
+        Arc::clone(repo),
+        CleanupConfig {
+            interval: Some(Duration::from_secs(60)),
+            pending_user_ttl,
+            batch_size: 10,
+            partition_months_ahead: 2,
+            partitions: vec![
+                PartitionPolicy {
+                    table: "audit_log",
+                    retention_days: 0,
+                },
+                PartitionPolicy {
+                    table: "token_issuances",
+                    retention_days: 90,
+                },
+            ],
+        },
+    )
+}
+
+#[tokio::test]
+async fn test_purge_drains_full_batches() {
+    let repo = Arc::new(MockRepository::new(&[10, 10, 3], &[10, 4]));
+
+    let report = service(&repo, Some(Duration::from_secs(3600)))
+        .run_purge()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        report,
+        PurgeReport {
+            expired_sessions: 23,
+            stale_users: 14,
+            expired_partition_rows: 14,
+        }
+    );
+    assert!(repo.session_batches.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_users_older_than_ttl_are_targeted() {
+    let repo = Arc::new(MockRepository::new(&[], &[]));
+    let ttl = Duration::from_secs(7 * 24 * 3600);
+
+    service(&repo, Some(ttl)).run_purge().await.unwrap();
+
+    let cutoff = repo.users_created_before.lock().unwrap().unwrap();
+    let age = (Utc::now() - cutoff).to_std().unwrap();
+    assert!(age >= ttl && age < ttl + Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_users_kept_without_ttl() {
+    let repo = Arc::new(MockRepository::new(&[2], &[5]));
+
+    let report = service(&repo, None).run_purge().await.unwrap();
+
+    assert_eq!(report.expired_sessions, 2);
+    assert_eq!(report.stale_users, 0);
+    assert!(repo.users_created_before.lock().unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_every_partitioned_table_maintained_without_user_ttl() {
+    let repo = Arc::new(MockRepository::new(&[], &[]));
+
+    let report = service(&repo, None).run_purge().await.unwrap();
+
+    assert_eq!(report.expired_partition_rows, 14);
+    assert_eq!(
+        *repo.maintained_partitions.lock().unwrap(),
+        vec![
+            ("audit_log".to_string(), 2, 0),
+            ("token_issuances".to_string(), 2, 90),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_pending_sample_zeroes_ceremonies_without_sessions() {
+    let repo = Arc::new(MockRepository::new(&[], &[]));
+    PENDING_CEREMONIES.with_label_values(&["recovery"]).set(5.0);
+
+    service(&repo, None)
+        .sample_pending_sessions()
+        .await
+        .unwrap();
+
+    let pending = |ceremony| PENDING_CEREMONIES.with_label_values(&[ceremony]).get();
+    assert_eq!(pending("login"), 3.0);
+    assert_eq!(pending("registration"), 0.0);
+    assert_eq!(pending("recovery"), 0.0);
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use std::future::Future;
+
+use crate::app::AppError;
+
+pub trait CleanupRepository: Send + Sync {
+    /// Deletes up to `batch_size` expired WebAuthn sessions.
+    fn delete_expired_sessions(
+        &self,
+        batch_size: i64,
+    ) -> impl Future<Output = Result<u64, AppError>> + Send;
+    /// Unexpired WebAuthn sessions of every tenant, counted by purpose.
+    fn count_pending_sessions(
+        &self,
+    ) -> impl Future<Output = Result<Vec<(String, i64)>, AppError>> + Send;
+    /// Deletes up to `batch_size` pending users without credentials created
+    /// before `created_before`.
+    fn delete_stale_users(
+        &self,
+        created_before: DateTime<Utc>,
+        batch_size: i64,
+    ) -> impl Future<Output = Result<u64, AppError>> + Send;
+    /// Creates the monthly partitions of `table` up to `months_ahead` and
+    /// drops those past `retention_days`, returning the rows dropped.
+    fn maintain_partitions(
+        &self,
+        table: &str,
+        months_ahead: i32,
+        retention_days: i32,
+    ) -> impl Future<Output = Result<u64, AppError>> + Send;
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use crate::config::env::env_or;
+
+const DEFAULT_FREE_ATTEMPTS: u32 = 3;
+const DEFAULT_BASE_DELAY_SECS: u64 = 1;
+const DEFAULT_MAX_DELAY_SECS: u64 = 60;
+const DEFAULT_LOCKOUT_THRESHOLD: u32 = 10;
+const DEFAULT_LOCKOUT_SECS: u64 = 900;
+const DEFAULT_WINDOW_SECS: u64 = 3600;
+
+/// Per-account throttling of failed WebAuthn finish steps, on top of the
+/// per-IP and per-username request limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountThrottleConfig {
+    pub enabled: bool,
+    /// Failures allowed back to back before any delay applies.
+    pub free_attempts: u32,
+    /// Wait after the first delayed failure, doubled after each one after.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Failures that lock the account.
+    pub lockout_threshold: u32,
+    pub lockout: Duration,
+    /// Failures are forgotten this long after the last one.
+    pub window: Duration,
+}
+
+impl Default for AccountThrottleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            free_attempts: DEFAULT_FREE_ATTEMPTS,
+            base_delay: Duration::from_secs(DEFAULT_BASE_DELAY_SECS),
+            max_delay: Duration::from_secs(DEFAULT_MAX_DELAY_SECS),
+            lockout_threshold: DEFAULT_LOCKOUT_THRESHOLD,
+            lockout: Duration::from_secs(DEFAULT_LOCKOUT_SECS),
+            window: Duration::from_secs(DEFAULT_WINDOW_SECS),
+        }
+    }
+}
+
+impl AccountThrottleConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        let config = Self {
+            enabled: env_or("ACCOUNT_THROTTLE_ENABLED", defaults.enabled),
+            free_attempts: env_or("ACCOUNT_THROTTLE_FREE_ATTEMPTS", defaults.free_attempts),
+            base_delay: Duration::from_secs(env_or(
+                "ACCOUNT_THROTTLE_BASE_DELAY_SECS",
+                defaults.base_delay.as_secs(),
+            )),
+            max_delay: Duration::from_secs(env_or(
+                "ACCOUNT_THROTTLE_MAX_DELAY_SECS",
+                defaults.max_delay.as_secs(),
+            )),
+            lockout_threshold: env_or("ACCOUNT_LOCKOUT_THRESHOLD", defaults.lockout_threshold),
+            lockout: Duration::from_secs(env_or(
+                "ACCOUNT_LOCKOUT_SECS",
+                defaults.lockout.as_secs(),
+            )),
+            window: Duration::from_secs(env_or(
+                "ACCOUNT_THROTTLE_WINDOW_SECS",
+                defaults.window.as_secs(),
+            )),
+        };
+        config.validate();
+        config
+    }
+
+    pub fn validate(&self) {
+        if !self.enabled {
+            return;
+        }
+        if self.lockout_threshold <= self.free_attempts {
+            panic!("ACCOUNT_LOCKOUT_THRESHOLD must be greater than ACCOUNT_THROTTLE_FREE_ATTEMPTS");
+        }
+        if self.max_delay < self.base_delay {
+            panic!(
+                "ACCOUNT_THROTTLE_MAX_DELAY_SECS must be at least ACCOUNT_THROTTLE_BASE_DELAY_SECS"
+            );
+        }
+        if self.lockout.is_zero() || self.window.is_zero() {
+            panic!("ACCOUNT_LOCKOUT_SECS and ACCOUNT_THROTTLE_WINDOW_SECS must be greater than 0");
+        }
+    }
+
+    /// How long to wait after the `failures`th failure in a row.
+    pub fn delay(&self, failures: u32) -> Duration {
+        let Some(delayed) = failures.checked_sub(self.free_attempts + 1) else {
+            return Duration::ZERO;
+        };
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(delayed))
+            .min(self.max_delay)
+    }
+}
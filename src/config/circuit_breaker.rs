@@ -3,7 +3,10 @@ use failsafe::{
 };
 use std::{sync::Arc, time::Duration};
 
-use crate::app::{AppError, middleware::metrics::update_circuit_breaker_state};
+use crate::{
+    app::{AppError, middleware::metrics::update_circuit_breaker_state},
+    config::env::env_or,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum BreakerState {
@@ -17,7 +20,7 @@ impl BreakerState {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CircuitBreakerConfig {
     pub failure_threshold: u32,
     pub backoff_initial_secs: u64,
@@ -34,6 +37,57 @@ impl Default for CircuitBreakerConfig {
     }
 }
 
+impl CircuitBreakerConfig {
+    /// Reads `CB_{SERVICE}_FAILURE_THRESHOLD`, `CB_{SERVICE}_BACKOFF_INITIAL_SECS`
+    /// and `CB_{SERVICE}_BACKOFF_MAX_SECS`, so each dependency is tuned on its own.
+    pub fn from_env(service: &str) -> Self {
+        let defaults = Self::default();
+        let prefix = format!("CB_{}", service);
+
+        defaults
+            .with_failure_threshold(env_or(
+                &format!("{}_FAILURE_THRESHOLD", prefix),
+                defaults.failure_threshold,
+            ))
+            .with_backoff(
+                env_or(
+                    &format!("{}_BACKOFF_INITIAL_SECS", prefix),
+                    defaults.backoff_initial_secs,
+                ),
+                env_or(
+                    &format!("{}_BACKOFF_MAX_SECS", prefix),
+                    defaults.backoff_max_secs,
+                ),
+            )
+            .validated(&prefix)
+    }
+
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold;
+        self
+    }
+
+    pub fn with_backoff(mut self, initial_secs: u64, max_secs: u64) -> Self {
+        self.backoff_initial_secs = initial_secs;
+        self.backoff_max_secs = max_secs;
+        self
+    }
+
+    /// Panics on a breaker that could never open or whose backoff shrinks.
+    pub fn validated(self, prefix: &str) -> Self {
+        if self.failure_threshold == 0 {
+            panic!("{}_FAILURE_THRESHOLD must be greater than 0", prefix);
+        }
+        if self.backoff_initial_secs == 0 || self.backoff_initial_secs > self.backoff_max_secs {
+            panic!(
+                "{}_BACKOFF_INITIAL_SECS must be greater than 0 and at most {}_BACKOFF_MAX_SECS",
+                prefix, prefix
+            );
+        }
+        self
+    }
+}
+
 type BreakerImpl = StateMachine<
     failsafe::failure_policy::ConsecutiveFailures<failsafe::backoff::EqualJittered>,
     (),
@@ -43,6 +97,7 @@ type BreakerImpl = StateMachine<
 pub struct CircuitBreaker {
     breaker: Arc<BreakerImpl>,
     name: Box<str>,
+    config: CircuitBreakerConfig,
 }
 
 impl CircuitBreaker {
@@ -59,6 +114,7 @@ impl CircuitBreaker {
         let cb = Self {
             breaker: Arc::new(breaker),
             name: name.into(),
+            config,
         };
         cb.update_state(BreakerState::Closed);
         cb
@@ -90,6 +146,20 @@ impl CircuitBreaker {
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn config(&self) -> CircuitBreakerConfig {
+        self.config
+    }
+
+    /// Closes the breaker immediately instead of waiting for the backoff.
+    pub fn reset(&self) {
+        self.breaker.reset();
+        self.update_state(BreakerState::Closed);
+    }
+
     fn update_state(&self, state: BreakerState) {
         update_circuit_breaker_state(&self.name, state.as_metric_value());
 
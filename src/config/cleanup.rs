@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use crate::config::env::env_or;
+
+const DEFAULT_INTERVAL_SECS: u64 = 3600;
+const DEFAULT_PENDING_USER_TTL_HOURS: u64 = 30 * 24;
+const DEFAULT_BATCH_SIZE: u32 = 1000;
+const DEFAULT_PARTITION_MONTHS_AHEAD: u32 = 1;
+
+/// Tables partitioned by month, with the variable and default of their
+/// retention. Any range-partitioned table can be added here.
+const PARTITIONED_TABLES: [(&str, &str, u32); 2] = [
+    ("audit_log", "CLEANUP_AUDIT_LOG_RETENTION_DAYS", 0),
+    (
+        "token_issuances",
+        "CLEANUP_TOKEN_ISSUANCE_RETENTION_DAYS",
+        365,
+    ),
+];
+
+/// A table whose monthly partitions the cleanup job keeps ahead of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionPolicy {
+    pub table: &'static str,
+    /// Months that ended longer ago than this are dropped; 0 keeps them.
+    pub retention_days: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct CleanupConfig {
+    /// `None` disables the purge job.
+    pub interval: Option<Duration>,
+    /// How long a user may stay pending before being pruned; `None` keeps them.
+    pub pending_user_ttl: Option<Duration>,
+    pub batch_size: u32,
+    /// Partitions created beyond the current month.
+    pub partition_months_ahead: u32,
+    pub partitions: Vec<PartitionPolicy>,
+}
+
+impl CleanupConfig {
+    pub fn from_env() -> Self {
+        let interval_secs: u64 = env_or("CLEANUP_INTERVAL_SECS", DEFAULT_INTERVAL_SECS);
+        let ttl_hours: u64 = env_or(
+            "CLEANUP_PENDING_USER_TTL_HOURS",
+            DEFAULT_PENDING_USER_TTL_HOURS,
+        );
+        let batch_size = env_or("CLEANUP_BATCH_SIZE", DEFAULT_BATCH_SIZE);
+        let partition_months_ahead = env_or(
+            "CLEANUP_PARTITION_MONTHS_AHEAD",
+            DEFAULT_PARTITION_MONTHS_AHEAD,
+        );
+        let partitions = PARTITIONED_TABLES
+            .iter()
+            .map(|(table, key, default)| PartitionPolicy {
+                table,
+                retention_days: env_or(key, *default),
+            })
+            .collect();
+
+        if batch_size == 0 {
+            panic!("CLEANUP_BATCH_SIZE must be greater than 0");
+        }
+        // Rows written between the turn of the month and the next run would
+        // otherwise land in the default partition.
+        if partition_months_ahead == 0 {
+            panic!("CLEANUP_PARTITION_MONTHS_AHEAD must be greater than 0");
+        }
+
+        Self {
+            interval: (interval_secs > 0).then(|| Duration::from_secs(interval_secs)),
+            pending_user_ttl: (ttl_hours > 0).then(|| Duration::from_secs(ttl_hours * 3600)),
+            batch_size,
+            partition_months_ahead,
+            partitions,
+        }
+    }
+}
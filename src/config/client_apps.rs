@@ -0,0 +1,87 @@
+use crate::config::env::env_opt;
+
+/// The client registry: applications whose tokens are bound to them through
+/// the `azp` claim, and the API surface reserved to each. Empty by default,
+/// in which case tokens carry no `azp` and every route takes any token.
+#[derive(Debug, Clone, Default)]
+pub struct ClientAppConfig {
+    pub apps: Vec<Box<str>>,
+    /// Path prefixes and the only application whose tokens they accept.
+    pub resources: Vec<(Box<str>, Box<str>)>,
+}
+
+impl ClientAppConfig {
+    pub fn from_env() -> Self {
+        let apps = env_opt("CLIENT_APPS")
+            .map(|value| parse_apps(&value))
+            .unwrap_or_default();
+        let resources = env_opt("CLIENT_APP_RESOURCES")
+            .map(|value| parse_resources(&value))
+            .unwrap_or_default();
+
+        for (prefix, app) in &resources {
+            if !apps.contains(app) {
+                panic!(
+                    "CLIENT_APP_RESOURCES binds {} to {}, which is not in CLIENT_APPS",
+                    prefix, app
+                );
+            }
+        }
+
+        Self { apps, resources }
+    }
+
+    /// The registered application behind an `X-Client-App` value, which may
+    /// carry a version after a dash: `ios-2.3.1` belongs to `ios`. Unknown
+    /// names get no `azp`, so a client cannot bind tokens to whatever it
+    /// claims to be.
+    pub fn authorized_party(&self, client_app: Option<&str>) -> Option<&str> {
+        let client_app = client_app?;
+        self.apps
+            .iter()
+            .filter(|app| {
+                client_app
+                    .strip_prefix(app.as_ref())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+            })
+            .max_by_key(|app| app.len())
+            .map(AsRef::as_ref)
+    }
+
+    /// The `azp` tokens must carry to reach `path`, by the longest prefix.
+    pub fn expected_party(&self, path: &str) -> Option<&str> {
+        self.resources
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_ref()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, app)| app.as_ref())
+    }
+}
+
+pub fn parse_apps(value: &str) -> Vec<Box<str>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|app| !app.is_empty())
+        .map(Box::from)
+        .collect()
+}
+
+/// `/admin/=console,/billing/=web`.
+pub fn parse_resources(value: &str) -> Vec<(Box<str>, Box<str>)> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (prefix, app) = entry.split_once('=').unwrap_or_else(|| {
+                panic!("CLIENT_APP_RESOURCES entry is not prefix=app: {}", entry)
+            });
+            let (prefix, app) = (prefix.trim(), app.trim());
+            if !prefix.starts_with('/') || app.is_empty() {
+                panic!("CLIENT_APP_RESOURCES entry is not prefix=app: {}", entry);
+            }
+            (Box::from(prefix), Box::from(app))
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use crate::config::env::{env_opt, env_or};
+
+const DEFAULT_PATH: &str = "/auth";
+
+#[derive(Debug, Clone)]
+pub struct CookieConfig {
+    pub path: String,
+    pub max_age: Duration,
+    pub trusted_max_age: Duration,
+}
+
+impl CookieConfig {
+    /// The refresh token durations are the default max ages, so the cookie
+    /// lives exactly as long as the token inside it.
+    pub fn from_env(
+        refresh_token_duration: Duration,
+        trusted_refresh_token_duration: Duration,
+    ) -> Self {
+        let config = Self {
+            path: env_opt("COOKIE_PATH").unwrap_or_else(|| String::from(DEFAULT_PATH)),
+            max_age: Duration::from_secs(env_or(
+                "COOKIE_MAX_AGE_SECS",
+                refresh_token_duration.as_secs(),
+            )),
+            trusted_max_age: Duration::from_secs(env_or(
+                "COOKIE_TRUSTED_MAX_AGE_SECS",
+                trusted_refresh_token_duration.as_secs(),
+            )),
+        };
+
+        if !config.path.starts_with('/') {
+            panic!("COOKIE_PATH must start with '/'");
+        }
+
+        if config.max_age.is_zero() {
+            panic!("COOKIE_MAX_AGE_SECS must be greater than 0");
+        }
+
+        if config.trusted_max_age < config.max_age {
+            panic!("COOKIE_TRUSTED_MAX_AGE_SECS must be at least COOKIE_MAX_AGE_SECS");
+        }
+
+        config
+    }
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self {
+            path: String::from(DEFAULT_PATH),
+            max_age: Duration::from_secs(24 * 60 * 60),
+            trusted_max_age: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use url::Url;
+
+use crate::{
+    config::env::{env_opt, env_or},
+    enrollment::model::ReminderLinks,
+};
+
+const DEFAULT_INTERVAL_SECS: u64 = 3600;
+const DEFAULT_RESEND_AFTER_HOURS: u64 = 72;
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_BATCH_SIZE: u32 = 100;
+
+#[derive(Debug, Clone)]
+pub struct EnrollmentConfig {
+    pub reminders_enabled: bool,
+    pub interval: Duration,
+    pub resend_after: Duration,
+    pub max_attempts: u32,
+    pub batch_size: u32,
+    pub links: Option<ReminderLinks>,
+}
+
+impl EnrollmentConfig {
+    pub fn from_env() -> Self {
+        let enrollment_url = env_opt("ENROLLMENT_URL").map(|value| Url::parse(&value).unwrap());
+        let tracking_base_url =
+            env_opt("ENROLLMENT_TRACKING_BASE_URL").map(|value| Url::parse(&value).unwrap());
+
+        let config = Self {
+            reminders_enabled: env_or("ENROLLMENT_REMINDERS_ENABLED", false),
+            interval: Duration::from_secs(env_or(
+                "ENROLLMENT_REMINDER_INTERVAL_SECS",
+                DEFAULT_INTERVAL_SECS,
+            )),
+            resend_after: Duration::from_secs(
+                env_or(
+                    "ENROLLMENT_REMINDER_RESEND_HOURS",
+                    DEFAULT_RESEND_AFTER_HOURS,
+                ) * 3600,
+            ),
+            max_attempts: env_or("ENROLLMENT_REMINDER_MAX_ATTEMPTS", DEFAULT_MAX_ATTEMPTS),
+            batch_size: env_or("ENROLLMENT_REMINDER_BATCH_SIZE", DEFAULT_BATCH_SIZE),
+            links: enrollment_url.map(|enrollment_url| ReminderLinks {
+                enrollment_url,
+                tracking_base_url,
+            }),
+        };
+
+        if config.reminders_enabled {
+            if config.links.is_none() {
+                panic!("ENROLLMENT_URL must be set when ENROLLMENT_REMINDERS_ENABLED is true");
+            }
+
+            if config.interval.is_zero() || config.max_attempts == 0 || config.batch_size == 0 {
+                panic!(
+                    "ENROLLMENT_REMINDER_INTERVAL_SECS, _MAX_ATTEMPTS and _BATCH_SIZE must be greater than 0"
+                );
+            }
+        }
+
+        config
+    }
+}
@@ -0,0 +1,17 @@
+use std::{env, str::FromStr};
+
+/// Reads an optional variable, falling back to `default` when unset or empty.
+/// A variable that is set but unparsable is a configuration error and panics.
+pub(crate) fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    match env_opt(key) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} has an invalid value: {}", key, value)),
+        None => default,
+    }
+}
+
+/// Reads an optional variable, treating an empty value as unset.
+pub(crate) fn env_opt(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|value| !value.trim().is_empty())
+}
@@ -0,0 +1,75 @@
+use crate::config::env::{env_opt, env_or};
+
+const DEFAULT_TOPIC: &str = "auth.events";
+const DEFAULT_QUEUE_CAPACITY: usize = 10_000;
+
+/// Where domain events are exported, chosen by the scheme of
+/// `EVENT_EXPORT_URL`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    not(all(feature = "event-export-nats", feature = "event-export-kafka")),
+    allow(dead_code)
+)]
+pub enum EventExportBackend {
+    /// `nats://host:port`; each event goes to `<topic>.<event type>`.
+    Nats { url: Box<str> },
+    /// `kafka://broker:port[,broker:port...]`; events go to `<topic>`,
+    /// keyed by user so each user's events stay in order.
+    Kafka { brokers: Box<str> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventExportConfig {
+    pub backend: EventExportBackend,
+    /// NATS subject prefix or Kafka topic.
+    pub topic: Box<str>,
+    /// Events waiting to be exported before new ones are dropped.
+    pub queue_capacity: usize,
+}
+
+impl EventExportConfig {
+    /// `None` without `EVENT_EXPORT_URL`: nothing is exported.
+    pub fn from_env() -> Option<Self> {
+        let url = env_opt("EVENT_EXPORT_URL")?;
+        Some(Self::parse(
+            &url,
+            env_opt("EVENT_EXPORT_TOPIC")
+                .as_deref()
+                .unwrap_or(DEFAULT_TOPIC),
+            env_or("EVENT_EXPORT_QUEUE_CAPACITY", DEFAULT_QUEUE_CAPACITY),
+        ))
+    }
+
+    pub fn parse(url: &str, topic: &str, queue_capacity: usize) -> Self {
+        let url = url.trim();
+        let backend = match url.split_once("://") {
+            Some(("nats" | "tls", _)) => EventExportBackend::Nats { url: url.into() },
+            Some(("kafka", brokers)) if !brokers.trim().is_empty() => EventExportBackend::Kafka {
+                brokers: brokers.trim().into(),
+            },
+            _ => panic!(
+                "EVENT_EXPORT_URL must be nats://<host>:<port> or kafka://<broker>[,<broker>]: {}",
+                url
+            ),
+        };
+
+        let topic = topic.trim();
+        if topic.is_empty()
+            || !topic
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        {
+            panic!("EVENT_EXPORT_TOPIC has an invalid topic {}", topic);
+        }
+
+        if queue_capacity == 0 {
+            panic!("EVENT_EXPORT_QUEUE_CAPACITY must be greater than 0");
+        }
+
+        Self {
+            backend,
+            topic: topic.into(),
+            queue_capacity,
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use sha2::{Digest, Sha256};
+
+use crate::{
+    app::middleware::metrics::track_feature_flag_attempt, config::env::env_opt,
+    utils::normalize_username,
+};
+
+// Percentages resolve to hundredths, so 0.5 reaches one user in 200.
+const BUCKETS: u32 = 10_000;
+
+/// Flags that shadow-launch new flows, from `FEATURE_FLAGS`. Each user lands
+/// in a stable bucket per flag, so raising the percentage only adds users and
+/// every instance agrees on who is in. Empty by default, every flag off.
+#[derive(Debug, Clone, Default)]
+pub struct FeatureFlags {
+    pub flags: Vec<FeatureFlag>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureFlag {
+    pub name: Box<str>,
+    /// Share of users in the treatment, in hundredths of a percent.
+    pub rollout: u32,
+    /// Usernames always in the treatment, whatever the percentage, normalized.
+    pub usernames: Vec<Box<str>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// The current flow.
+    Control,
+    /// The flow being rolled out.
+    Treatment,
+}
+
+impl Variant {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Control => "control",
+            Self::Treatment => "treatment",
+        }
+    }
+}
+
+impl FeatureFlags {
+    pub fn from_env() -> Self {
+        Self::parse(env_opt("FEATURE_FLAGS").as_deref().unwrap_or(""))
+    }
+
+    /// `discoverable_login=10,dpop=0.5:alice|bob`: each flag's percentage,
+    /// optionally followed by the usernames let in regardless.
+    pub fn parse(value: &str) -> Self {
+        let mut flags: Vec<FeatureFlag> = Vec::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let flag = FeatureFlag::parse(entry);
+            if flags.iter().any(|f| f.name == flag.name) {
+                panic!("FEATURE_FLAGS lists {} twice", flag.name);
+            }
+            flags.push(flag);
+        }
+        Self { flags }
+    }
+
+    /// Counts an attempt at `flow` under the user's variant of every flag,
+    /// so both sides' error rates can be compared before a full rollout.
+    pub fn track(&self, flow: &str, username: &str, success: bool) {
+        for flag in &self.flags {
+            track_feature_flag_attempt(&flag.name, flag.variant(username).as_str(), flow, success);
+        }
+    }
+}
+
+impl FeatureFlag {
+    fn parse(entry: &str) -> Self {
+        let invalid = || -> ! {
+            panic!(
+                "FEATURE_FLAGS entry is not name=percent[:user|user]: {}",
+                entry
+            )
+        };
+        let (name, rule) = entry.split_once('=').unwrap_or_else(|| invalid());
+        let name = name.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            invalid();
+        }
+
+        let (percent, usernames) = rule.split_once(':').unwrap_or((rule, ""));
+        let percent: f64 = percent.trim().parse().unwrap_or_else(|_| invalid());
+        if !(0.0..=100.0).contains(&percent) {
+            panic!("FEATURE_FLAGS percentage of {} must be 0-100", name);
+        }
+
+        Self {
+            name: name.into(),
+            rollout: (percent * f64::from(BUCKETS) / 100.0).round() as u32,
+            usernames: usernames
+                .split('|')
+                .map(str::trim)
+                .filter(|u| !u.is_empty())
+                .map(|u| normalize_username(u).into())
+                .collect(),
+        }
+    }
+
+    /// Usernames are normalized first, so `Alice` and `alice` share a
+    /// variant like they share an account.
+    pub fn variant(&self, username: &str) -> Variant {
+        let username = normalize_username(username);
+        if self.usernames.iter().any(|u| **u == *username)
+            || bucket(&self.name, &username) < self.rollout
+        {
+            Variant::Treatment
+        } else {
+            Variant::Control
+        }
+    }
+}
+
+/// The user's bucket for `flag`, in `0..10000`. Salted with the flag so the
+/// first 10% of one rollout are not the first 10% of every other, and
+/// hashed with SHA-256 so it stays the same across instances and releases.
+pub fn bucket(flag: &str, username: &str) -> u32 {
+    let digest = Sha256::new()
+        .chain_update(flag)
+        .chain_update([0])
+        .chain_update(username)
+        .finalize();
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % BUCKETS
+}
@@ -0,0 +1,39 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use crate::config::env::env_opt;
+
+/// Where the gRPC interface listens. It has no rate limiting or CORS, so it
+/// belongs on a port only reachable by internal services.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrpcConfig {
+    pub addr: SocketAddr,
+}
+
+impl GrpcConfig {
+    /// `None` without `GRPC_PORT`: the gRPC interface is not served.
+    pub fn from_env() -> Option<Self> {
+        let port = env_opt("GRPC_PORT")?;
+        Some(Self::parse(env_opt("GRPC_HOST").as_deref(), &port))
+    }
+
+    /// Binds every interface unless `host` is given.
+    pub fn parse(host: Option<&str>, port: &str) -> Self {
+        let ip = match host {
+            Some(host) => host
+                .trim()
+                .parse::<IpAddr>()
+                .unwrap_or_else(|_| panic!("GRPC_HOST must be an IP address: {}", host)),
+            None => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        };
+        let port = port
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|port| *port != 0)
+            .unwrap_or_else(|| panic!("GRPC_PORT must be a port number: {}", port));
+
+        Self {
+            addr: SocketAddr::new(ip, port),
+        }
+    }
+}
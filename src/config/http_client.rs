@@ -0,0 +1,125 @@
+use std::{sync::Arc, time::Duration};
+
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy};
+use url::Url;
+
+use crate::{
+    config::{
+        CircuitBreakerConfig,
+        env::{env_opt, env_or},
+    },
+    utils::http::{GuardedResolver, SsrfGuard},
+};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 3;
+const DEFAULT_MAX_RETRIES: u32 = 2;
+const DEFAULT_RETRY_BUDGET_PERCENT: u32 = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpClientConfig {
+    /// Whole-request timeout, unless a caller sets its own.
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Every outbound request goes through it, except hosts in `no_proxy`.
+    pub proxy: Option<Url>,
+    pub no_proxy: Option<String>,
+    /// Retries of one request, on top of the first attempt.
+    pub max_retries: u32,
+    /// Retries a destination may spend per 100 requests, beyond a small reserve.
+    pub retry_budget_percent: u32,
+    /// Shared settings of the per-destination breakers.
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Hosts that URLs from admins or users may point at even when they
+    /// resolve to a private address.
+    pub ssrf_allowed_hosts: Vec<String>,
+}
+
+impl HttpClientConfig {
+    pub fn from_env() -> Self {
+        let config = Self {
+            timeout: Duration::from_secs(env_or("HTTP_CLIENT_TIMEOUT_SECS", DEFAULT_TIMEOUT_SECS)),
+            connect_timeout: Duration::from_secs(env_or(
+                "HTTP_CLIENT_CONNECT_TIMEOUT_SECS",
+                DEFAULT_CONNECT_TIMEOUT_SECS,
+            )),
+            proxy: env_opt("HTTP_CLIENT_PROXY").map(|value| {
+                Url::parse(&value)
+                    .unwrap_or_else(|_| panic!("HTTP_CLIENT_PROXY has an invalid value: {}", value))
+            }),
+            no_proxy: env_opt("HTTP_CLIENT_NO_PROXY"),
+            max_retries: env_or("HTTP_CLIENT_MAX_RETRIES", DEFAULT_MAX_RETRIES),
+            retry_budget_percent: env_or(
+                "HTTP_CLIENT_RETRY_BUDGET_PERCENT",
+                DEFAULT_RETRY_BUDGET_PERCENT,
+            ),
+            circuit_breaker: CircuitBreakerConfig::from_env("HTTP"),
+            ssrf_allowed_hosts: env_opt("HTTP_CLIENT_SSRF_ALLOWED_HOSTS")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|host| !host.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+
+        config.validated()
+    }
+
+    /// Panics on timeouts that would fail every request.
+    pub fn validated(self) -> Self {
+        if self.timeout.is_zero() || self.connect_timeout.is_zero() {
+            panic!(
+                "HTTP_CLIENT_TIMEOUT_SECS and HTTP_CLIENT_CONNECT_TIMEOUT_SECS must be greater than 0"
+            );
+        }
+        if self.retry_budget_percent > 100 {
+            panic!("HTTP_CLIENT_RETRY_BUDGET_PERCENT must be at most 100");
+        }
+        self
+    }
+
+    pub fn create_client(&self) -> Client {
+        self.builder().build().unwrap()
+    }
+
+    /// A client for URLs from admins or users: names resolving to private
+    /// addresses and redirects to them are refused. The proxy host is
+    /// allowed, since the client has to reach it.
+    pub fn create_guarded_client(&self) -> (Client, Arc<SsrfGuard>) {
+        let mut allowed_hosts = self.ssrf_allowed_hosts.clone();
+        allowed_hosts.extend(
+            self.proxy
+                .as_ref()
+                .and_then(|url| url.host_str())
+                .map(String::from),
+        );
+        let guard = Arc::new(SsrfGuard::new(allowed_hosts));
+
+        let client = self
+            .builder()
+            .dns_resolver(Arc::new(GuardedResolver::new(Arc::clone(&guard))))
+            .redirect(guard.redirect_policy())
+            .build()
+            .unwrap();
+        (client, guard)
+    }
+
+    fn builder(&self) -> ClientBuilder {
+        let builder = Client::builder()
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout);
+
+        match &self.proxy {
+            Some(url) => builder.proxy(
+                Proxy::all(url.as_str())
+                    .unwrap()
+                    .no_proxy(self.no_proxy.as_deref().and_then(NoProxy::from_string)),
+            ),
+            None => builder,
+        }
+    }
+}
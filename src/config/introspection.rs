@@ -0,0 +1,125 @@
+use std::fmt;
+
+use axum::http::{HeaderMap, HeaderName, header::AUTHORIZATION};
+use base64::{Engine, prelude::BASE64_STANDARD};
+
+use crate::{config::env::env_opt, utils::crypto::sha256};
+
+const BASIC_PREFIX: &str = "Basic ";
+
+/// Who may call `/auth/introspect`. With neither a client nor mTLS set up,
+/// every call is rejected.
+#[derive(Debug, Clone, Default)]
+pub struct IntrospectionConfig {
+    pub client: Option<IntrospectionClient>,
+    pub mtls: Option<MtlsConfig>,
+}
+
+/// Credentials sent with HTTP Basic, as RFC 7662 suggests.
+#[derive(Clone)]
+pub struct IntrospectionClient {
+    pub id: Box<str>,
+    pub secret: Box<str>,
+}
+
+impl fmt::Debug for IntrospectionClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntrospectionClient")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Client certificates are checked by the TLS terminating proxy, which
+/// passes the verified subject in `header`. The proxy must overwrite the
+/// header on every request, or callers could send it themselves.
+#[derive(Debug, Clone)]
+pub struct MtlsConfig {
+    pub header: HeaderName,
+    pub subjects: Vec<Box<str>>,
+}
+
+impl IntrospectionConfig {
+    pub fn from_env() -> Self {
+        let client = match (
+            env_opt("INTROSPECTION_CLIENT_ID"),
+            env_opt("INTROSPECTION_CLIENT_SECRET"),
+        ) {
+            (Some(id), Some(secret)) => {
+                if id.contains(':') {
+                    panic!("INTROSPECTION_CLIENT_ID cannot contain ':'");
+                }
+                Some(IntrospectionClient {
+                    id: id.into_boxed_str(),
+                    secret: secret.into_boxed_str(),
+                })
+            }
+            (None, None) => None,
+            _ => panic!("INTROSPECTION_CLIENT_ID and INTROSPECTION_CLIENT_SECRET go together"),
+        };
+
+        let mtls = match (
+            env_opt("INTROSPECTION_MTLS_HEADER"),
+            env_opt("INTROSPECTION_MTLS_SUBJECTS"),
+        ) {
+            (Some(header), Some(subjects)) => Some(MtlsConfig {
+                header: header.trim().parse().unwrap_or_else(|_| {
+                    panic!("INTROSPECTION_MTLS_HEADER is not a header name: {}", header)
+                }),
+                subjects: parse_subjects(&subjects),
+            }),
+            (None, None) => None,
+            _ => panic!("INTROSPECTION_MTLS_HEADER and INTROSPECTION_MTLS_SUBJECTS go together"),
+        };
+
+        Self { client, mtls }
+    }
+
+    /// Whether `headers` carry the client's credentials or an allowed
+    /// certificate subject.
+    pub fn authorize(&self, headers: &HeaderMap) -> bool {
+        let by_secret = self.client.as_ref().is_some_and(|client| {
+            basic_credentials(headers).is_some_and(|(id, secret)| client.matches(&id, &secret))
+        });
+        let by_certificate = self.mtls.as_ref().is_some_and(|mtls| {
+            headers
+                .get(&mtls.header)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|subject| mtls.subjects.iter().any(|s| s.as_ref() == subject.trim()))
+        });
+
+        by_secret || by_certificate
+    }
+}
+
+impl IntrospectionClient {
+    /// Compares digests, so the time taken says nothing about how much of
+    /// the secret was right.
+    fn matches(&self, id: &str, secret: &str) -> bool {
+        let id_matches = sha256(id.as_bytes()) == sha256(self.id.as_bytes());
+        let secret_matches = sha256(secret.as_bytes()) == sha256(self.secret.as_bytes());
+        id_matches & secret_matches
+    }
+}
+
+/// The id and secret of an HTTP Basic `Authorization` header.
+pub fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let encoded = headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix(BASIC_PREFIX)?;
+    let decoded = String::from_utf8(BASE64_STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (id, secret) = decoded.split_once(':')?;
+    Some((id.to_owned(), secret.to_owned()))
+}
+
+/// Semicolon separated, since subjects are themselves comma separated DNs.
+pub fn parse_subjects(value: &str) -> Vec<Box<str>> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|subject| !subject.is_empty())
+        .map(Box::from)
+        .collect()
+}
@@ -1,8 +1,75 @@
-use std::env;
+use std::{env, fs, time::Duration};
+#[cfg(feature = "kms")]
+use std::{fmt, sync::Arc};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use jsonwebtoken::Algorithm;
+#[cfg(feature = "kms")]
+use url::Url;
+
+use crate::{
+    auth::jwt::claims::TokenScope,
+    config::env::{env_opt, env_or},
+};
+#[cfg(feature = "kms")]
+use crate::{
+    auth::jwt::{
+        kms::{AwsKmsSigner, GCP_METADATA_TOKEN_URL, GcpKmsSigner},
+        traits::TokenSigner,
+    },
+    utils::HttpClientService,
+};
+
+const DEFAULT_ACCESS_TOKEN_TTL_SECS: u64 = 5 * 60;
+const DEFAULT_REFRESH_TOKEN_TTL_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_REFRESH_GRACE_SECS: u64 = 10;
+const MAX_REFRESH_GRACE_SECS: u64 = 60;
 
 #[derive(Debug)]
 pub struct JwtConfig {
     secret_key: Box<str>,
+    access_key: Option<AccessKeyConfig>,
+    #[cfg(feature = "kms")]
+    kms: Option<KmsConfig>,
+    key_rotation_interval: Option<Duration>,
+    key_encryption_key: Option<[u8; 32]>,
+    access_token_format: AccessTokenFormat,
+    access_token_duration: Duration,
+    refresh_token_duration: Duration,
+    trusted_refresh_token_duration: Duration,
+    refresh_grace: Duration,
+    token_scope: TokenScope,
+}
+
+/// What access tokens are issued as. Either kind is accepted whichever is
+/// configured, so switching does not sign anyone out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessTokenFormat {
+    /// Self-contained signed tokens, verifiable through the JWKS.
+    Jwt,
+    /// Random strings whose claims live in Redis, so deleting them revokes
+    /// the token at once.
+    Opaque,
+}
+
+impl AccessTokenFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "jwt" => Some(Self::Jwt),
+            "opaque" => Some(Self::Opaque),
+            _ => None,
+        }
+    }
+}
+
+/// PEM keypair used to sign access tokens. When absent the signing key is
+/// derived from `JWT_SECRET_KEY`, which only exists for backwards compatibility.
+#[derive(Debug)]
+pub struct AccessKeyConfig {
+    pub algorithm: Algorithm,
+    pub private_pem: Vec<u8>,
+    pub public_pem: Vec<u8>,
+    pub key_id: Option<String>,
 }
 
 impl JwtConfig {
@@ -13,10 +80,353 @@ impl JwtConfig {
             panic!("JWT_SECRET_KEY must be at least 32 characters");
         }
 
-        Self { secret_key }
+        let rotation_hours: u64 = env_or("JWT_KEY_ROTATION_HOURS", 0);
+        let key_encryption_key = env_opt("JWT_KEY_ENCRYPTION_KEY").map(|value| {
+            BASE64_STANDARD
+                .decode(value)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .unwrap_or_else(|| panic!("JWT_KEY_ENCRYPTION_KEY must be 32 base64-encoded bytes"))
+        });
+        if rotation_hours > 0 && key_encryption_key.is_none() {
+            panic!("JWT_KEY_ROTATION_HOURS needs JWT_KEY_ENCRYPTION_KEY to seal the rotated keys");
+        }
+        let access_token_format =
+            env_opt("JWT_ACCESS_TOKEN_FORMAT").map_or(AccessTokenFormat::Jwt, |value| {
+                AccessTokenFormat::parse(&value).unwrap_or_else(|| {
+                    panic!(
+                        "JWT_ACCESS_TOKEN_FORMAT must be jwt or opaque, got {}",
+                        value
+                    )
+                })
+            });
+        let access_token_duration = Duration::from_secs(env_or(
+            "JWT_ACCESS_TOKEN_TTL_SECS",
+            DEFAULT_ACCESS_TOKEN_TTL_SECS,
+        ));
+        let refresh_token_duration = Duration::from_secs(env_or(
+            "JWT_REFRESH_TOKEN_TTL_SECS",
+            DEFAULT_REFRESH_TOKEN_TTL_SECS,
+        ));
+
+        let trusted_refresh_token_duration = Duration::from_secs(env_or(
+            "JWT_TRUSTED_REFRESH_TOKEN_TTL_SECS",
+            refresh_token_duration.as_secs(),
+        ));
+
+        if access_token_duration.is_zero() {
+            panic!("JWT_ACCESS_TOKEN_TTL_SECS must be greater than 0");
+        }
+
+        // A refresh token that dies first would make the refresh flow useless.
+        if refresh_token_duration <= access_token_duration {
+            panic!("JWT_REFRESH_TOKEN_TTL_SECS must be greater than JWT_ACCESS_TOKEN_TTL_SECS");
+        }
+
+        if trusted_refresh_token_duration < refresh_token_duration {
+            panic!(
+                "JWT_TRUSTED_REFRESH_TOKEN_TTL_SECS must be at least JWT_REFRESH_TOKEN_TTL_SECS"
+            );
+        }
+
+        let refresh_grace =
+            Self::parse_refresh_grace(env_or("JWT_REFRESH_GRACE_SECS", DEFAULT_REFRESH_GRACE_SECS));
+
+        // With a KMS the private key is never configured, only the public one.
+        #[cfg(feature = "kms")]
+        let kms = KmsConfig::from_env();
+        #[cfg(feature = "kms")]
+        let access_key = match kms {
+            Some(_) => None,
+            None => AccessKeyConfig::from_env(),
+        };
+        #[cfg(not(feature = "kms"))]
+        let access_key = AccessKeyConfig::from_env();
+
+        Self {
+            secret_key,
+            access_key,
+            #[cfg(feature = "kms")]
+            kms,
+            key_rotation_interval: (rotation_hours > 0)
+                .then(|| Duration::from_secs(rotation_hours * 60 * 60)),
+            key_encryption_key,
+            access_token_format,
+            access_token_duration,
+            refresh_token_duration,
+            trusted_refresh_token_duration,
+            refresh_grace,
+            token_scope: TokenScope {
+                issuer: env_opt("JWT_ISSUER").map(Into::into),
+                audience: env_opt("JWT_AUDIENCE").map(Into::into),
+            },
+        }
+    }
+
+    /// A long window would let a stolen, rotated-out token be replayed, so it
+    /// is capped at a minute. Zero turns the window off.
+    pub fn parse_refresh_grace(secs: u64) -> Duration {
+        if secs > MAX_REFRESH_GRACE_SECS {
+            panic!(
+                "JWT_REFRESH_GRACE_SECS must be at most {}",
+                MAX_REFRESH_GRACE_SECS
+            );
+        }
+        Duration::from_secs(secs)
+    }
+
+    /// Default lifetimes, no access keypair and no scheduled rotation.
+    #[cfg(feature = "test-support")]
+    pub fn with_secret(secret_key: &str) -> Self {
+        let refresh_token_duration = Duration::from_secs(DEFAULT_REFRESH_TOKEN_TTL_SECS);
+
+        Self {
+            secret_key: secret_key.into(),
+            access_key: None,
+            #[cfg(feature = "kms")]
+            kms: None,
+            key_rotation_interval: None,
+            key_encryption_key: None,
+            access_token_format: AccessTokenFormat::Jwt,
+            access_token_duration: Duration::from_secs(DEFAULT_ACCESS_TOKEN_TTL_SECS),
+            refresh_token_duration,
+            trusted_refresh_token_duration: refresh_token_duration,
+            refresh_grace: Duration::from_secs(DEFAULT_REFRESH_GRACE_SECS),
+            token_scope: TokenScope::default(),
+        }
     }
 
     pub fn as_bytes(&self) -> &[u8] {
         self.secret_key.as_bytes()
     }
+
+    pub fn access_key(&self) -> Option<&AccessKeyConfig> {
+        self.access_key.as_ref()
+    }
+
+    #[cfg(feature = "kms")]
+    pub fn kms(&self) -> Option<&KmsConfig> {
+        self.kms.as_ref()
+    }
+
+    /// Whether access tokens are signed with keys held by the server, which
+    /// are the only ones it can rotate.
+    pub fn signs_locally(&self) -> bool {
+        #[cfg(feature = "kms")]
+        return self.kms.is_none();
+        #[cfg(not(feature = "kms"))]
+        return true;
+    }
+
+    pub fn access_token_format(&self) -> AccessTokenFormat {
+        self.access_token_format
+    }
+
+    pub fn access_token_duration(&self) -> Duration {
+        self.access_token_duration
+    }
+
+    pub fn refresh_token_duration(&self) -> Duration {
+        self.refresh_token_duration
+    }
+
+    /// Refresh token lifetime for sessions the user marked as trusted.
+    pub fn trusted_refresh_token_duration(&self) -> Duration {
+        self.trusted_refresh_token_duration
+    }
+
+    /// How long the refresh token a refresh rotated out still gets the same
+    /// new pair once, for clients that lost the response.
+    pub fn refresh_grace(&self) -> Duration {
+        self.refresh_grace
+    }
+
+    pub fn token_scope(&self) -> TokenScope {
+        self.token_scope.clone()
+    }
+
+    /// Seals the keys rotation stores in Redis. Without it rotation is
+    /// refused.
+    pub fn key_encryption_key(&self) -> Option<&[u8; 32]> {
+        self.key_encryption_key.as_ref()
+    }
+
+    /// `None` leaves rotation to the admin action.
+    pub fn key_rotation_interval(&self) -> Option<Duration> {
+        self.key_rotation_interval
+    }
+}
+
+impl AccessKeyConfig {
+    fn from_env() -> Option<Self> {
+        let private_pem = read_pem("JWT_PRIVATE_KEY");
+        let public_pem = read_pem("JWT_PUBLIC_KEY");
+
+        let (private_pem, public_pem) = match (private_pem, public_pem) {
+            (Some(private_pem), Some(public_pem)) => (private_pem, public_pem),
+            (None, None) => return None,
+            _ => panic!("JWT_PRIVATE_KEY and JWT_PUBLIC_KEY must be configured together"),
+        };
+
+        let algorithm = match env_opt("JWT_ALGORITHM").as_deref() {
+            None | Some("EdDSA") => Algorithm::EdDSA,
+            Some("ES256") => Algorithm::ES256,
+            Some(other) => panic!("JWT_ALGORITHM must be EdDSA or ES256, got {}", other),
+        };
+
+        Some(Self {
+            algorithm,
+            private_pem,
+            public_pem,
+            key_id: env_opt("JWT_KEY_ID"),
+        })
+    }
+}
+
+/// An access token key kept in a cloud KMS. Tokens are signed by calling the
+/// KMS, and validated with `JWT_PUBLIC_KEY` as usual.
+#[cfg(feature = "kms")]
+#[derive(Debug)]
+pub struct KmsConfig {
+    pub provider: KmsProvider,
+    /// AWS key id, ARN or alias, or the Cloud KMS key version resource name.
+    pub key_id: String,
+    pub endpoint: Url,
+    pub public_pem: Vec<u8>,
+    pub kid: Option<String>,
+}
+
+#[cfg(feature = "kms")]
+#[derive(Debug)]
+pub enum KmsProvider {
+    Aws {
+        region: String,
+        credentials: AwsCredentials,
+    },
+    /// Authenticates as the instance's service account.
+    Gcp,
+}
+
+/// Static credentials from the standard AWS variables.
+#[cfg(feature = "kms")]
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+#[cfg(feature = "kms")]
+impl fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "kms")]
+impl KmsConfig {
+    fn from_env() -> Option<Self> {
+        let provider = env_opt("JWT_KMS_PROVIDER")?;
+
+        let key_id = env_opt("JWT_KMS_KEY_ID")
+            .unwrap_or_else(|| panic!("JWT_KMS_KEY_ID is required with JWT_KMS_PROVIDER"));
+        let public_pem = read_pem("JWT_PUBLIC_KEY")
+            .unwrap_or_else(|| panic!("JWT_PUBLIC_KEY is required with JWT_KMS_PROVIDER"));
+        if read_pem("JWT_PRIVATE_KEY").is_some() {
+            panic!("JWT_PRIVATE_KEY cannot be combined with JWT_KMS_PROVIDER");
+        }
+        if env_or("JWT_KEY_ROTATION_HOURS", 0u64) > 0 {
+            panic!("JWT_KEY_ROTATION_HOURS cannot be combined with JWT_KMS_PROVIDER");
+        }
+        match env_opt("JWT_ALGORITHM").as_deref() {
+            None | Some("ES256") => {}
+            Some(other) => panic!(
+                "JWT_ALGORITHM must be ES256 with JWT_KMS_PROVIDER, got {}",
+                other
+            ),
+        }
+
+        let (provider, default_endpoint) = match provider.as_str() {
+            "aws" => {
+                let region = env_opt("JWT_KMS_REGION")
+                    .or_else(|| env_opt("AWS_REGION"))
+                    .unwrap_or_else(|| {
+                        panic!("JWT_KMS_REGION or AWS_REGION is required for AWS KMS")
+                    });
+                let endpoint = format!("https://kms.{}.amazonaws.com/", region);
+                let credentials = AwsCredentials {
+                    access_key_id: env_opt("AWS_ACCESS_KEY_ID")
+                        .unwrap_or_else(|| panic!("AWS_ACCESS_KEY_ID is required for AWS KMS")),
+                    secret_access_key: env_opt("AWS_SECRET_ACCESS_KEY")
+                        .unwrap_or_else(|| panic!("AWS_SECRET_ACCESS_KEY is required for AWS KMS")),
+                    session_token: env_opt("AWS_SESSION_TOKEN"),
+                };
+                (
+                    KmsProvider::Aws {
+                        region,
+                        credentials,
+                    },
+                    endpoint,
+                )
+            }
+            "gcp" => (
+                KmsProvider::Gcp,
+                String::from("https://cloudkms.googleapis.com/"),
+            ),
+            other => panic!("JWT_KMS_PROVIDER must be aws or gcp, got {}", other),
+        };
+
+        let endpoint = env_opt("JWT_KMS_ENDPOINT").unwrap_or(default_endpoint);
+        Some(Self {
+            provider,
+            key_id,
+            endpoint: endpoint
+                .parse()
+                .unwrap_or_else(|e| panic!("JWT_KMS_ENDPOINT is not a valid URL: {}", e)),
+            public_pem,
+            kid: env_opt("JWT_KEY_ID"),
+        })
+    }
+
+    pub fn create_signer(&self, http: Arc<HttpClientService>) -> Arc<dyn TokenSigner> {
+        match &self.provider {
+            KmsProvider::Aws {
+                region,
+                credentials,
+            } => Arc::new(AwsKmsSigner::new(
+                http,
+                self.endpoint.clone(),
+                region.clone(),
+                self.key_id.clone(),
+                credentials.clone(),
+            )),
+            KmsProvider::Gcp => Arc::new(
+                GcpKmsSigner::new(
+                    http,
+                    &self.endpoint,
+                    &self.key_id,
+                    Url::parse(GCP_METADATA_TOKEN_URL).unwrap(),
+                )
+                .unwrap_or_else(|e| panic!("{}", e)),
+            ),
+        }
+    }
+}
+
+/// Reads a PEM from `{key}` or from the file named by `{key}_FILE`. Inline
+/// values may use literal `\n`, since most env files cannot hold newlines.
+fn read_pem(key: &str) -> Option<Vec<u8>> {
+    let file_key = format!("{}_FILE", key);
+
+    match (env_opt(key), env_opt(&file_key)) {
+        (Some(_), Some(_)) => panic!("Set only one of {} and {}", key, file_key),
+        (Some(value), None) => Some(value.replace("\\n", "\n").into_bytes()),
+        (None, Some(path)) => Some(
+            fs::read(&path)
+                .unwrap_or_else(|e| panic!("{} cannot be read ({}): {}", file_key, path, e)),
+        ),
+        (None, None) => None,
+    }
 }
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use crate::config::env::env_or;
+
+const DEFAULT_TTL_SECS: u64 = 60;
+
+/// Caching in Redis of the user and passkeys `begin_login` reads. Entries
+/// are dropped whenever the credentials or the account change, and the TTL
+/// bounds how long one missed invalidation can last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginCacheConfig {
+    pub enabled: bool,
+    pub ttl: Duration,
+}
+
+impl Default for LoginCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl: Duration::from_secs(DEFAULT_TTL_SECS),
+        }
+    }
+}
+
+impl LoginCacheConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        let config = Self {
+            enabled: env_or("LOGIN_CACHE_ENABLED", defaults.enabled),
+            ttl: Duration::from_secs(env_or("LOGIN_CACHE_TTL_SECS", defaults.ttl.as_secs())),
+        };
+        config.validate();
+        config
+    }
+
+    pub fn validate(&self) {
+        if self.enabled && self.ttl.is_zero() {
+            panic!("LOGIN_CACHE_TTL_SECS must be greater than 0");
+        }
+    }
+}
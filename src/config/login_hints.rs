@@ -0,0 +1,34 @@
+use crate::config::env::env_opt;
+
+/// How much `GET /auth/login/options` tells about a username.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoginHints {
+    /// The endpoint answers 404, as if it did not exist.
+    #[default]
+    Off,
+    /// Every username gets the same answer, a passkey login, so the
+    /// endpoint cannot be used to find out which accounts exist.
+    Uniform,
+    /// The answer for the account behind the username, which reveals
+    /// whether it exists.
+    Exact,
+}
+
+impl LoginHints {
+    pub fn from_env() -> Self {
+        env_opt("LOGIN_HINTS").map_or(Self::Off, |value| {
+            Self::parse(&value).unwrap_or_else(|| {
+                panic!("LOGIN_HINTS must be off, uniform or exact, got {}", value)
+            })
+        })
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(Self::Off),
+            "uniform" => Some(Self::Uniform),
+            "exact" => Some(Self::Exact),
+            _ => None,
+        }
+    }
+}
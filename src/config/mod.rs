@@ -1,13 +1,78 @@
+pub(crate) mod account_throttle;
 pub(crate) mod circuit_breaker;
+pub(crate) mod cleanup;
+pub(crate) mod client_apps;
+pub(crate) mod cookie;
+#[cfg(feature = "enrollment-reminders")]
+pub(crate) mod enrollment;
+pub(crate) mod env;
+pub(crate) mod event_export;
+pub(crate) mod flags;
+#[cfg(feature = "grpc")]
+pub(crate) mod grpc;
+#[cfg(feature = "http-client")]
+pub(crate) mod http_client;
+pub(crate) mod introspection;
 pub(crate) mod jwt;
+pub(crate) mod login_cache;
+pub(crate) mod login_hints;
+#[cfg(feature = "notifications")]
+pub(crate) mod notification;
 pub(crate) mod origin;
 pub(crate) mod postgres;
+pub(crate) mod postgres_tls;
+pub(crate) mod rate_limit;
 pub(crate) mod redis;
+pub(crate) mod region;
+pub(crate) mod request_policy;
+pub(crate) mod revocation;
+pub(crate) mod roles;
+pub(crate) mod slo;
+pub(crate) mod startup;
+#[cfg(feature = "otel")]
+pub(crate) mod telemetry;
+pub(crate) mod tenant;
+pub(crate) mod username;
+#[cfg(feature = "notifications")]
+pub(crate) mod verification;
 pub(crate) mod webauthn;
 
+pub(crate) use account_throttle::AccountThrottleConfig;
 pub(crate) use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
-pub(crate) use jwt::JwtConfig;
-pub(crate) use origin::OriginConfig;
-pub(crate) use postgres::DbConfig;
-pub(crate) use redis::RedisConfig;
+pub(crate) use cleanup::CleanupConfig;
+pub(crate) use client_apps::ClientAppConfig;
+pub(crate) use cookie::CookieConfig;
+#[cfg(feature = "enrollment-reminders")]
+pub(crate) use enrollment::EnrollmentConfig;
+pub(crate) use event_export::EventExportConfig;
+pub(crate) use flags::FeatureFlags;
+#[cfg(feature = "grpc")]
+pub(crate) use grpc::GrpcConfig;
+#[cfg(feature = "http-client")]
+pub(crate) use http_client::HttpClientConfig;
+pub(crate) use introspection::IntrospectionConfig;
+pub(crate) use jwt::{AccessTokenFormat, JwtConfig};
+pub(crate) use login_cache::LoginCacheConfig;
+pub(crate) use login_hints::LoginHints;
+#[cfg(feature = "notifications")]
+pub(crate) use notification::{NotificationConfig, NotificationStreamConfig};
+pub(crate) use origin::{CorsConfig, OriginConfig};
+pub(crate) use postgres::{DbConfig, DbListenConfig, StatementCacheConfig};
+pub(crate) use rate_limit::RateLimitConfig;
+pub(crate) use redis::{RedisConfig, RedisMemoryConfig};
+pub(crate) use region::RegionConfig;
+pub(crate) use request_policy::RequestPolicyConfig;
+pub(crate) use revocation::RevocationConfig;
+pub(crate) use roles::RolePolicy;
+pub(crate) use slo::SloConfig;
+pub(crate) use startup::StartupConfig;
+#[cfg(feature = "otel")]
+pub(crate) use telemetry::TelemetryConfig;
+pub(crate) use tenant::TenantConfig;
+pub(crate) use username::UsernamePolicy;
+#[cfg(feature = "notifications")]
+pub(crate) use verification::EmailVerificationConfig;
 pub(crate) use webauthn::WebAuthnConfig;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,174 @@
+use std::{env, path::PathBuf, sync::Arc, time::Duration};
+
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    auth::verification::RelayVerificationSender,
+    config::{
+        EmailVerificationConfig,
+        env::{env_opt, env_or},
+    },
+    notification::{
+        channels::{RelayNotifier, WebhookNotifier},
+        model::{Branding, Channel},
+        template::TemplateRenderer,
+        traits::Notifier,
+    },
+    utils::HttpClientService,
+};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_LOCALE: &str = "en";
+const DEFAULT_STREAM_MAX_LEN: u64 = 10_000;
+const DEFAULT_STREAM_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_STREAM_CLAIM_IDLE_SECS: u64 = 60;
+
+/// Queueing of notifications on a Redis stream, read by a consumer group
+/// with one consumer per instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationStreamConfig {
+    /// Unique per instance, so entries left pending by a dead one can be told apart.
+    pub consumer: Box<str>,
+    /// Approximate number of entries the stream keeps, delivered or not.
+    pub max_len: u64,
+    /// Deliveries of one entry before it is dropped as undeliverable.
+    pub max_attempts: u32,
+    /// How long an entry stays unacknowledged before another consumer retries it.
+    pub claim_idle: Duration,
+}
+
+impl NotificationStreamConfig {
+    /// `None` when `NOTIFY_STREAM_ENABLED=false`: notifications are then
+    /// delivered in-process and lost if the instance stops midway.
+    pub fn from_env() -> Option<Self> {
+        if !env_or("NOTIFY_STREAM_ENABLED", true) {
+            return None;
+        }
+
+        let consumer = env_opt("NOTIFY_STREAM_CONSUMER")
+            .or_else(|| env_opt("HOSTNAME"))
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let max_attempts = env_or("NOTIFY_STREAM_MAX_ATTEMPTS", DEFAULT_STREAM_MAX_ATTEMPTS);
+        let claim_idle_secs = env_or(
+            "NOTIFY_STREAM_CLAIM_IDLE_SECS",
+            DEFAULT_STREAM_CLAIM_IDLE_SECS,
+        );
+
+        if max_attempts == 0 {
+            panic!("NOTIFY_STREAM_MAX_ATTEMPTS must be greater than 0");
+        }
+        // Shorter than a slow delivery, and entries would be retried while
+        // still in flight.
+        if claim_idle_secs == 0 {
+            panic!("NOTIFY_STREAM_CLAIM_IDLE_SECS must be greater than 0");
+        }
+
+        Some(Self {
+            consumer: consumer.into_boxed_str(),
+            max_len: env_or("NOTIFY_STREAM_MAX_LEN", DEFAULT_STREAM_MAX_LEN),
+            max_attempts,
+            claim_idle: Duration::from_secs(claim_idle_secs),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct NotificationConfig {
+    pub email_relay_url: Option<Url>,
+    pub sms_relay_url: Option<Url>,
+    pub push_relay_url: Option<Url>,
+    pub relay_api_key: Option<Box<str>>,
+    pub timeout: Duration,
+    pub template_dir: Option<PathBuf>,
+    pub default_locale: Box<str>,
+    pub branding: Branding,
+    pub stream: Option<NotificationStreamConfig>,
+}
+
+impl NotificationConfig {
+    pub fn from_env() -> Self {
+        Self {
+            email_relay_url: optional_url("NOTIFY_EMAIL_RELAY_URL"),
+            sms_relay_url: optional_url("NOTIFY_SMS_RELAY_URL"),
+            push_relay_url: optional_url("NOTIFY_PUSH_RELAY_URL"),
+            relay_api_key: env_opt("NOTIFY_RELAY_API_KEY").map(String::into_boxed_str),
+            timeout: Duration::from_secs(env_or("NOTIFY_TIMEOUT_SECS", DEFAULT_TIMEOUT_SECS)),
+            template_dir: env_opt("NOTIFY_TEMPLATE_DIR").map(PathBuf::from),
+            default_locale: env_or("NOTIFY_DEFAULT_LOCALE", String::from(DEFAULT_LOCALE))
+                .into_boxed_str(),
+            branding: Branding {
+                name: env_opt("NOTIFY_BRAND_NAME")
+                    .unwrap_or_else(|| env::var("WEBAUTHN_RP_NAME").unwrap()),
+                url: env_opt("NOTIFY_BRAND_URL"),
+                logo_url: env_opt("NOTIFY_BRAND_LOGO_URL"),
+                support_contact: env_opt("NOTIFY_BRAND_SUPPORT_CONTACT"),
+            },
+            stream: NotificationStreamConfig::from_env(),
+        }
+    }
+
+    pub fn create_renderer(&self) -> TemplateRenderer {
+        TemplateRenderer::new(
+            self.template_dir.clone(),
+            self.branding.clone(),
+            &self.default_locale,
+        )
+    }
+
+    /// Webhooks are always available; relay channels only when their URL is set.
+    pub fn create_notifiers(&self, http: &Arc<HttpClientService>) -> Vec<Arc<dyn Notifier>> {
+        let mut notifiers: Vec<Arc<dyn Notifier>> = vec![Arc::new(WebhookNotifier::new(
+            Arc::clone(http),
+            self.timeout,
+        ))];
+
+        let relays = [
+            (Channel::Email, &self.email_relay_url),
+            (Channel::Sms, &self.sms_relay_url),
+            (Channel::Push, &self.push_relay_url),
+        ];
+
+        for (channel, url) in relays {
+            if let Some(url) = url {
+                notifiers.push(Arc::new(RelayNotifier::new(
+                    channel,
+                    url.clone(),
+                    self.relay_api_key.clone(),
+                    Arc::clone(http),
+                    self.timeout,
+                )));
+            }
+        }
+
+        notifiers
+    }
+
+    /// Verification emails need the email relay; enabling verification
+    /// without it is a configuration error.
+    pub fn create_verification_sender(
+        &self,
+        config: &EmailVerificationConfig,
+        http: &Arc<HttpClientService>,
+    ) -> RelayVerificationSender {
+        let endpoint = self.email_relay_url.clone().unwrap_or_else(|| {
+            panic!("EMAIL_VERIFICATION_ENABLED requires NOTIFY_EMAIL_RELAY_URL")
+        });
+        RelayVerificationSender::new(
+            RelayNotifier::new(
+                Channel::Email,
+                endpoint,
+                self.relay_api_key.clone(),
+                Arc::clone(http),
+                self.timeout,
+            ),
+            self.create_renderer(),
+            self.default_locale.clone(),
+            config.clone(),
+        )
+    }
+}
+
+fn optional_url(key: &str) -> Option<Url> {
+    env_opt(key).map(|value| Url::parse(&value).unwrap())
+}
@@ -1,55 +1,201 @@
-use std::env;
+use std::{env, sync::Arc, time::Duration};
 
-use axum::http::{self, HeaderValue, Method};
-use tower_http::cors::CorsLayer;
+use axum::http::{self, HeaderName, HeaderValue, Method, request::Parts};
+use tower_http::cors::{AllowOrigin, CorsLayer, MaxAge};
 use url::Url;
 
-const ALLOWED_METHODS: [Method; 3] = [Method::GET, Method::POST, Method::OPTIONS];
-const ALLOWED_HEADERS: [http::HeaderName; 2] =
-    [http::header::CONTENT_TYPE, http::header::AUTHORIZATION];
+use crate::{
+    app::{
+        context::{CLIENT_APP_HEADER, REQUEST_ID_HEADER},
+        deadline::REQUEST_TIMEOUT_HEADER,
+    },
+    config::env::{env_opt, env_or},
+};
+
+const ALLOWED_METHODS: [Method; 6] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+    Method::OPTIONS,
+];
+const ALLOWED_HEADERS: [http::HeaderName; 4] = [
+    http::header::CONTENT_TYPE,
+    http::header::AUTHORIZATION,
+    CLIENT_APP_HEADER,
+    REQUEST_TIMEOUT_HEADER,
+];
+/// Always readable by the frontend, on top of `CORS_EXPOSE_HEADERS`.
+const EXPOSED_HEADERS: [http::HeaderName; 2] = [REQUEST_ID_HEADER, http::header::RETRY_AFTER];
 const ALLOW_CREDENTIALS: bool = true;
-const MAX_AGE: std::time::Duration = std::time::Duration::from_secs(86400);
+const DEFAULT_MAX_AGE_SECS: u64 = 86400;
+const DEFAULT_ADMIN_MAX_AGE_SECS: u64 = 600;
 const VARY_HEADERS: [http::HeaderName; 1] = [http::header::ORIGIN];
+const ADMIN_PATH_PREFIX: &str = "/admin/";
+
+/// A frontend allowed to run ceremonies and hold the refresh cookie, with
+/// the RP ID its passkeys are scoped to.
+#[derive(Debug, Clone)]
+pub struct RelyingParty {
+    /// As browsers send it in `Origin`: scheme, host and port only.
+    pub origin: Box<str>,
+    pub url: Url,
+    pub rp_id: Box<str>,
+}
+
+impl RelyingParty {
+    pub fn new(origin: &str, rp_id: &str) -> Self {
+        let url = Url::parse(origin)
+            .unwrap_or_else(|e| panic!("ORIGIN_FRONTEND has an invalid origin {}: {}", origin, e));
+        if url.host_str().is_none() {
+            panic!("ORIGIN_FRONTEND has an origin without a host: {}", origin);
+        }
+
+        Self {
+            origin: url.origin().ascii_serialization().into(),
+            url,
+            rp_id: rp_id.into(),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct OriginConfig {
-    pub frontend_origin: Box<str>,
-    pub frontend_url: Url,
-    pub backend_domain: Box<str>,
+    /// Never empty. The first serves requests whose `Origin` is missing or
+    /// not listed, as the only frontend did before.
+    pub relying_parties: Vec<RelyingParty>,
 }
 
 impl OriginConfig {
     pub fn from_env() -> Self {
-        let frontend_origin = env::var("ORIGIN_FRONTEND").unwrap().into_boxed_str();
-        let frontend_url = Url::parse(&frontend_origin).unwrap();
-
         let backend_url = env::var("URL_BACKEND").unwrap();
         let parsed_backend = Url::parse(&backend_url).unwrap();
-        let backend_domain = parsed_backend.host_str().unwrap().into();
+        let backend_domain = parsed_backend.host_str().unwrap();
 
-        Self {
-            frontend_origin,
-            frontend_url,
-            backend_domain,
-        }
+        Self::parse(&env::var("ORIGIN_FRONTEND").unwrap(), backend_domain)
     }
 
-    pub fn rp_id(&self) -> &str {
-        &self.backend_domain
-    }
+    /// Reads `ORIGIN_FRONTEND`: comma separated origins, each optionally
+    /// followed by `=<rp id>`. Origins without one use `default_rp_id`.
+    pub fn parse(value: &str, default_rp_id: &str) -> Self {
+        let mut relying_parties: Vec<RelyingParty> = Vec::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (origin, rp_id) = match entry.split_once('=') {
+                Some((origin, rp_id)) => (origin.trim(), rp_id.trim()),
+                None => (entry, default_rp_id),
+            };
+            let party = RelyingParty::new(origin, rp_id);
+            if relying_parties.iter().any(|p| p.origin == party.origin) {
+                panic!("ORIGIN_FRONTEND lists {} twice", party.origin);
+            }
+            relying_parties.push(party);
+        }
+        if relying_parties.is_empty() {
+            panic!("ORIGIN_FRONTEND must list at least one origin");
+        }
 
-    pub fn rp_origin(&self) -> &Url {
-        &self.frontend_url
+        Self { relying_parties }
     }
 
-    pub fn create_cors_layer(&self) -> CorsLayer {
-        let origin = self.frontend_origin.parse::<HeaderValue>().unwrap();
+    /// One layer for every route, outermost so that errors raised by other
+    /// layers still carry CORS headers. `/admin/*` only answers the admin
+    /// origins, and browsers cache its preflights for less time.
+    pub fn create_cors_layer(&self, cors: &CorsConfig) -> CorsLayer {
+        let origins: Arc<Vec<HeaderValue>> = Arc::new(
+            self.relying_parties
+                .iter()
+                .map(|party| party.origin.parse().unwrap())
+                .collect(),
+        );
+        let admin_origins = Arc::new(
+            cors.admin_origins
+                .clone()
+                .unwrap_or_else(|| origins.to_vec()),
+        );
+        let (max_age, admin_max_age) = (cors.max_age, cors.admin_max_age);
+
         CorsLayer::new()
-            .allow_origin(origin)
+            .allow_origin(AllowOrigin::predicate(move |request_origin, parts| {
+                if is_admin_route(parts) {
+                    admin_origins.contains(request_origin)
+                } else {
+                    origins.contains(request_origin)
+                }
+            }))
             .allow_methods(ALLOWED_METHODS)
             .allow_headers(ALLOWED_HEADERS)
+            .expose_headers(
+                EXPOSED_HEADERS
+                    .into_iter()
+                    .chain(cors.expose_headers.iter().cloned())
+                    .collect::<Vec<_>>(),
+            )
             .allow_credentials(ALLOW_CREDENTIALS)
-            .max_age(MAX_AGE)
+            .max_age(MaxAge::dynamic(move |_, parts| {
+                if is_admin_route(parts) {
+                    admin_max_age
+                } else {
+                    max_age
+                }
+            }))
             .vary(VARY_HEADERS)
     }
 }
+
+fn is_admin_route(parts: &Parts) -> bool {
+    parts.uri.path().starts_with(ADMIN_PATH_PREFIX)
+}
+
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Response headers scripts may read besides the defaults, such as
+    /// rate limit or deprecation headers added by a gateway.
+    pub expose_headers: Vec<HeaderName>,
+    /// How long browsers may cache a preflight.
+    pub max_age: Duration,
+    /// Origins allowed on `/admin/*`; `None` keeps the frontend origins.
+    pub admin_origins: Option<Vec<HeaderValue>>,
+    pub admin_max_age: Duration,
+}
+
+impl CorsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            expose_headers: env_opt("CORS_EXPOSE_HEADERS")
+                .map(|value| parse_list("CORS_EXPOSE_HEADERS", &value))
+                .unwrap_or_default(),
+            max_age: Duration::from_secs(env_or("CORS_MAX_AGE_SECS", DEFAULT_MAX_AGE_SECS)),
+            admin_origins: env_opt("CORS_ADMIN_ORIGINS")
+                .map(|value| parse_list("CORS_ADMIN_ORIGINS", &value)),
+            admin_max_age: Duration::from_secs(env_or(
+                "CORS_ADMIN_MAX_AGE_SECS",
+                DEFAULT_ADMIN_MAX_AGE_SECS,
+            )),
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            expose_headers: Vec::new(),
+            max_age: Duration::from_secs(DEFAULT_MAX_AGE_SECS),
+            admin_origins: None,
+            admin_max_age: Duration::from_secs(DEFAULT_ADMIN_MAX_AGE_SECS),
+        }
+    }
+}
+
+/// Comma separated header names or origins; panics on one that is invalid.
+pub fn parse_list<T: std::str::FromStr>(key: &str, value: &str) -> Vec<T> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            item.parse()
+                .unwrap_or_else(|_| panic!("{} has an invalid entry: {}", key, item))
+        })
+        .collect()
+}
@@ -1,14 +1,32 @@
 use std::{env, time::Duration};
 
-use deadpool_postgres::{Config, ManagerConfig, Pool, Runtime};
-use tokio_postgres::NoTls;
+use deadpool_postgres::{Config, ManagerConfig, Pool, Runtime, SslMode};
+use tokio_postgres::{
+    Client, NoTls, Socket,
+    tls::{MakeTlsConnect, TlsConnect},
+};
+
+#[cfg(feature = "sqlx")]
+use crate::config::postgres_tls::SslMode as TlsMode;
+use crate::{
+    config::{
+        env::{env_opt, env_or},
+        postgres_tls::DbTlsConfig,
+        tenant::is_valid_tenant,
+    },
+    utils::PgListener,
+};
 
 const DB_MAX_SIZE: usize = 10;
 const DB_CONNECTION_TIMEOUT_SECS: u64 = 10;
 const DB_WAIT_TIMEOUT_SECS: u64 = 30;
 const DB_RECYCLE_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_LISTEN_RECONNECT_SECS: u64 = 5;
+const DEFAULT_CACHE_CHANNEL: &str = "prepared_cache_flush";
+const DEFAULT_STATEMENT_CACHE_SIZE: usize = 512;
+const DEFAULT_STATEMENT_CACHE_TTL_SECS: u64 = 3600;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DbConfig {
     pub host: Box<str>,
     pub port: u16,
@@ -19,6 +37,32 @@ pub struct DbConfig {
     pub connection_timeout: Duration,
     pub wait_timeout: Duration,
     pub recycle_timeout: Duration,
+    pub run_migrations: bool,
+    pub migration_user: Box<str>,
+    pub migration_password: Box<str>,
+    pub tls: Option<DbTlsConfig>,
+    pub statement_cache: StatementCacheConfig,
+    pub replica: Option<DbReplicaConfig>,
+    /// Databases that keep the accounts of one region each; the primary is
+    /// the home database of everyone else.
+    pub regions: Vec<DbRegionConfig>,
+}
+
+/// A streaming replica of the primary, reached with the same role,
+/// database and TLS settings.
+#[derive(Debug, Clone)]
+pub struct DbReplicaConfig {
+    pub host: Box<str>,
+    pub port: u16,
+}
+
+/// The database of a data residency region, reached with the same role,
+/// database name and TLS settings as the primary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbRegionConfig {
+    pub name: Box<str>,
+    pub host: Box<str>,
+    pub port: u16,
 }
 
 impl DbConfig {
@@ -29,6 +73,15 @@ impl DbConfig {
         let password = env::var("POSTGRES_PASSWORD").unwrap().into_boxed_str();
         let dbname = env::var("POSTGRES_DB").unwrap().into_boxed_str();
 
+        // The application role only has DML grants, so schema changes usually
+        // need the role that owns the schema.
+        let migration_user = env_opt("DB_MIGRATION_USER")
+            .map(String::into_boxed_str)
+            .unwrap_or_else(|| user.clone());
+        let migration_password = env_opt("DB_MIGRATION_PASSWORD")
+            .map(String::into_boxed_str)
+            .unwrap_or_else(|| password.clone());
+
         Self {
             host,
             port,
@@ -39,6 +92,43 @@ impl DbConfig {
             connection_timeout: Duration::from_secs(DB_CONNECTION_TIMEOUT_SECS),
             wait_timeout: Duration::from_secs(DB_WAIT_TIMEOUT_SECS),
             recycle_timeout: Duration::from_secs(DB_RECYCLE_TIMEOUT_SECS),
+            run_migrations: env_or("DB_RUN_MIGRATIONS", false),
+            migration_user,
+            migration_password,
+            tls: DbTlsConfig::from_env(),
+            statement_cache: StatementCacheConfig::from_env(),
+            replica: env_opt("DB_REPLICA_HOST").map(|host| DbReplicaConfig {
+                host: host.into_boxed_str(),
+                port: env_or("DB_REPLICA_PORT", port),
+            }),
+            regions: parse_regions(env_opt("DB_REGIONS").as_deref().unwrap_or(""), port),
+        }
+    }
+
+    /// These settings pointed at the replica, for pools that only serve
+    /// reads. `None` without `DB_REPLICA_HOST`.
+    pub fn for_replica(&self) -> Option<Self> {
+        let replica = self.replica.as_ref()?;
+
+        Some(Self {
+            host: replica.host.clone(),
+            port: replica.port,
+            run_migrations: false,
+            replica: None,
+            regions: Vec::new(),
+            ..self.clone()
+        })
+    }
+
+    /// These settings pointed at a region's database, which has the full
+    /// schema and is migrated along with the primary.
+    pub fn for_region(&self, region: &DbRegionConfig) -> Self {
+        Self {
+            host: region.host.clone(),
+            port: region.port,
+            replica: None,
+            regions: Vec::new(),
+            ..self.clone()
         }
     }
 
@@ -49,6 +139,9 @@ impl DbConfig {
         cfg.user = Some(self.user.to_string());
         cfg.password = Some(self.password.to_string());
         cfg.dbname = Some(self.dbname.to_string());
+        if self.tls.is_some() {
+            cfg.ssl_mode = Some(SslMode::Require);
+        }
 
         let mut pool_config = deadpool_postgres::PoolConfig::new(self.max_size);
         pool_config.timeouts.wait = Some(self.wait_timeout);
@@ -63,6 +156,239 @@ impl DbConfig {
 
     pub fn create_pool(&self) -> Pool {
         let config = self.to_deadpool_config();
-        config.create_pool(Some(Runtime::Tokio1), NoTls).unwrap()
+        match &self.tls {
+            Some(tls) => config.create_pool(Some(Runtime::Tokio1), tls.connector()),
+            None => config.create_pool(Some(Runtime::Tokio1), NoTls),
+        }
+        .unwrap()
+    }
+
+    /// Pool for the sqlx-backed auth repository, with the same limits and TLS
+    /// settings as the deadpool one. Connects on first use.
+    #[cfg(feature = "sqlx")]
+    pub fn create_sqlx_pool(&self) -> sqlx::PgPool {
+        use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+
+        let mut options = PgConnectOptions::new()
+            .host(&self.host)
+            .port(self.port)
+            .username(&self.user)
+            .password(&self.password)
+            .database(&self.dbname);
+
+        options = match &self.tls {
+            Some(tls) => {
+                let mut options = options.ssl_mode(match tls.mode {
+                    TlsMode::VerifyFull => PgSslMode::VerifyFull,
+                    TlsMode::VerifyCa => PgSslMode::VerifyCa,
+                    TlsMode::Require | TlsMode::Disable => PgSslMode::Require,
+                });
+                if let Some(root_cert) = &tls.root_cert {
+                    options = options.ssl_root_cert(root_cert);
+                }
+                if let Some((cert, key)) = &tls.client_cert {
+                    options = options.ssl_client_cert(cert).ssl_client_key(key);
+                }
+                options
+            }
+            None => options.ssl_mode(PgSslMode::Disable),
+        };
+
+        PgPoolOptions::new()
+            .max_connections(self.max_size as u32)
+            .acquire_timeout(self.wait_timeout)
+            .idle_timeout(self.recycle_timeout)
+            .connect_lazy_with(options)
+    }
+
+    /// Dedicated connection for the migration runner, outside the pool so it
+    /// can use the migration credentials.
+    pub async fn connect_migrator(&self) -> Client {
+        let config = self.connection_config(&self.migration_user, &self.migration_password);
+
+        match &self.tls {
+            Some(tls) => connect(&config, tls.connector()).await,
+            None => connect(&config, NoTls).await,
+        }
+    }
+
+    /// Listener on a dedicated connection of the application role. Handlers
+    /// are registered before it is spawned.
+    pub fn create_listener(&self, listen: &DbListenConfig) -> PgListener {
+        PgListener::new(
+            self.connection_config(&self.user, &self.password),
+            self.tls.as_ref().map(DbTlsConfig::connector),
+            listen.reconnect_delay,
+        )
+    }
+
+    fn connection_config(&self, user: &str, password: &str) -> tokio_postgres::Config {
+        let mut config = tokio_postgres::Config::new();
+        config
+            .host(&*self.host)
+            .port(self.port)
+            .user(user)
+            .password(password)
+            .dbname(&*self.dbname)
+            .connect_timeout(self.connection_timeout);
+        if self.tls.is_some() {
+            config.ssl_mode(tokio_postgres::config::SslMode::Require);
+        }
+        config
+    }
+}
+
+/// Limits of each repository's prepared statement cache.
+#[derive(Debug, Clone)]
+pub struct StatementCacheConfig {
+    /// Statements kept across all connections of the pool.
+    pub max_size: usize,
+    /// How long a statement is reused before being prepared again.
+    pub ttl: Option<Duration>,
+    /// Pooled connections the hot queries are prepared on at startup; 0
+    /// leaves every statement to be prepared on first use.
+    pub warmup_connections: usize,
+}
+
+impl StatementCacheConfig {
+    pub fn from_env() -> Self {
+        let max_size = env_or("DB_STATEMENT_CACHE_SIZE", DEFAULT_STATEMENT_CACHE_SIZE);
+        if max_size == 0 {
+            panic!("DB_STATEMENT_CACHE_SIZE must be greater than 0");
+        }
+        let ttl_secs: u64 = env_or(
+            "DB_STATEMENT_CACHE_TTL_SECS",
+            DEFAULT_STATEMENT_CACHE_TTL_SECS,
+        );
+
+        Self {
+            max_size,
+            ttl: (ttl_secs > 0).then(|| Duration::from_secs(ttl_secs)),
+            warmup_connections: env_or("DB_STATEMENT_WARMUP_CONNECTIONS", 0),
+        }
+    }
+}
+
+impl Default for StatementCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_STATEMENT_CACHE_SIZE,
+            ttl: Some(Duration::from_secs(DEFAULT_STATEMENT_CACHE_TTL_SECS)),
+            warmup_connections: 0,
+        }
     }
 }
+
+/// LISTEN/NOTIFY between instances sharing the database.
+#[derive(Debug, Clone)]
+pub struct DbListenConfig {
+    /// Wait before opening the listener connection again after it drops.
+    pub reconnect_delay: Duration,
+    /// Channel on which a prepared statement cache flush reaches every instance.
+    pub cache_channel: Box<str>,
+}
+
+impl DbListenConfig {
+    /// `None` when `DB_LISTEN_ENABLED` is false: each instance then only acts
+    /// on its own changes.
+    pub fn from_env() -> Option<Self> {
+        if !env_or("DB_LISTEN_ENABLED", true) {
+            return None;
+        }
+
+        let reconnect_secs: u64 = env_or("DB_LISTEN_RECONNECT_SECS", DEFAULT_LISTEN_RECONNECT_SECS);
+        if reconnect_secs == 0 {
+            panic!("DB_LISTEN_RECONNECT_SECS must be greater than 0");
+        }
+
+        Some(Self {
+            reconnect_delay: Duration::from_secs(reconnect_secs),
+            cache_channel: channel_name(
+                "DB_LISTEN_CACHE_CHANNEL",
+                env_opt("DB_LISTEN_CACHE_CHANNEL")
+                    .unwrap_or_else(|| String::from(DEFAULT_CACHE_CHANNEL)),
+            ),
+        })
+    }
+}
+
+/// Channels are interpolated into LISTEN, which takes no parameters, so
+/// only plain identifiers are accepted.
+pub fn channel_name(key: &str, value: String) -> Box<str> {
+    let valid = value.len() <= 63
+        && value.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+
+    if !valid {
+        panic!(
+            "{} must be a lowercase identifier of at most 63 characters: {}",
+            key, value
+        );
+    }
+    value.into_boxed_str()
+}
+
+async fn connect<T>(config: &tokio_postgres::Config, tls: T) -> Client
+where
+    T: MakeTlsConnect<Socket>,
+    T::Stream: Send + 'static,
+    T::TlsConnect: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    let (client, connection) = config
+        .connect(tls)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to connect to Postgres for migrations: {}", e));
+
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            tracing::error!("Migration connection error: {}", e);
+        }
+    });
+
+    client
+}
+
+/// Reads `DB_REGIONS`, comma separated `<region>=<host>[:<port>]` entries;
+/// the port defaults to the primary's. Region names follow the tenant id
+/// rule.
+pub fn parse_regions(value: &str, default_port: u16) -> Vec<DbRegionConfig> {
+    let mut regions: Vec<DbRegionConfig> = Vec::new();
+
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((name, address)) = entry.split_once('=') else {
+            panic!(
+                "DB_REGIONS entries must be <region>=<host>[:<port>]: {}",
+                entry
+            );
+        };
+        let name = name.trim();
+        if !is_valid_tenant(name) {
+            panic!("DB_REGIONS has an invalid region {}", name);
+        }
+        if regions.iter().any(|region| *region.name == *name) {
+            panic!("DB_REGIONS lists {} twice", name);
+        }
+        let (host, port) = match address.trim().rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .unwrap_or_else(|_| panic!("DB_REGIONS has an invalid port for {}", name)),
+            ),
+            None => (address.trim(), default_port),
+        };
+        if host.is_empty() {
+            panic!("DB_REGIONS has no host for {}", name);
+        }
+
+        regions.push(DbRegionConfig {
+            name: name.into(),
+            host: host.into(),
+            port,
+        });
+    }
+
+    regions
+}
@@ -0,0 +1,221 @@
+use std::{str::FromStr, sync::Arc};
+
+use rustls::{
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    client::{
+        WebPkiServerVerifier,
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    },
+    crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+};
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject};
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+use crate::{config::env::env_opt, utils::crypto::tls_provider};
+
+/// Mirrors libpq's `sslmode`, minus the modes that fall back to plaintext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SslMode {
+    Disable,
+    /// Encrypt without checking the server certificate.
+    Require,
+    /// Check the certificate chain but not the host name.
+    VerifyCa,
+    VerifyFull,
+}
+
+impl FromStr for SslMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "disable" => Ok(SslMode::Disable),
+            "require" => Ok(SslMode::Require),
+            "verify-ca" => Ok(SslMode::VerifyCa),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            other => Err(format!(
+                "DB_SSLMODE must be disable, require, verify-ca or verify-full, got {}",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DbTlsConfig {
+    pub mode: SslMode,
+    /// CA bundle for the server certificate; the Mozilla roots when unset.
+    pub root_cert: Option<String>,
+    pub client_cert: Option<(String, String)>,
+}
+
+impl DbTlsConfig {
+    /// `None` when `DB_SSLMODE` is unset or `disable`.
+    pub fn from_env() -> Option<Self> {
+        let mode = env_opt("DB_SSLMODE")
+            .map(|value| value.parse::<SslMode>().unwrap_or_else(|e| panic!("{}", e)))
+            .unwrap_or(SslMode::Disable);
+
+        let client_cert = match (env_opt("DB_SSLCERT"), env_opt("DB_SSLKEY")) {
+            (Some(cert), Some(key)) => Some((cert, key)),
+            (None, None) => None,
+            _ => panic!("DB_SSLCERT and DB_SSLKEY must be configured together"),
+        };
+
+        if mode == SslMode::Disable {
+            return None;
+        }
+
+        Some(Self {
+            mode,
+            root_cert: env_opt("DB_SSLROOTCERT"),
+            client_cert,
+        })
+    }
+
+    /// Builds the connector, panicking on unreadable certificates so a bad
+    /// path fails at startup rather than on the first query.
+    pub fn connector(&self) -> MakeRustlsConnect {
+        let provider = tls_provider();
+        let builder = ClientConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()
+            .unwrap();
+
+        let builder = match self.mode {
+            SslMode::VerifyFull => builder.with_root_certificates(self.root_store()),
+            SslMode::VerifyCa => {
+                let verifier = WebPkiServerVerifier::builder_with_provider(
+                    self.root_store(),
+                    Arc::clone(&provider),
+                )
+                .build()
+                .unwrap();
+                builder
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(RelaxedVerifier {
+                        chain: Some(verifier),
+                        provider,
+                    }))
+            }
+            SslMode::Require | SslMode::Disable => {
+                tracing::warn!(
+                    "DB_SSLMODE=require does not verify the Postgres server certificate"
+                );
+                builder
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(RelaxedVerifier {
+                        chain: None,
+                        provider,
+                    }))
+            }
+        };
+
+        let config = match &self.client_cert {
+            Some((cert, key)) => builder
+                .with_client_auth_cert(read_certs(cert), read_key(key))
+                .unwrap_or_else(|e| panic!("Invalid Postgres client certificate: {}", e)),
+            None => builder.with_no_client_auth(),
+        };
+
+        MakeRustlsConnect::new(config)
+    }
+
+    fn root_store(&self) -> Arc<RootCertStore> {
+        let mut roots = RootCertStore::empty();
+
+        match &self.root_cert {
+            Some(path) => {
+                for cert in read_certs(path) {
+                    roots
+                        .add(cert)
+                        .unwrap_or_else(|e| panic!("Invalid certificate in {}: {}", path, e));
+                }
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+
+        Arc::new(roots)
+    }
+}
+
+fn read_certs(path: &str) -> Vec<CertificateDer<'static>> {
+    let certs: Vec<_> = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<_, _>>())
+        .unwrap_or_else(|e| panic!("Failed to read certificates from {}: {}", path, e));
+
+    if certs.is_empty() {
+        panic!("No certificates found in {}", path);
+    }
+
+    certs
+}
+
+fn read_key(path: &str) -> PrivateKeyDer<'static> {
+    PrivateKeyDer::from_pem_file(path)
+        .unwrap_or_else(|e| panic!("Failed to read private key from {}: {}", path, e))
+}
+
+/// Verifier for `verify-ca` (chain only) and `require` (nothing). Handshake
+/// signatures are still checked, so the peer must hold the certificate's key.
+#[derive(Debug)]
+struct RelaxedVerifier {
+    chain: Option<Arc<WebPkiServerVerifier>>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for RelaxedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let Some(chain) = &self.chain else {
+            return Ok(ServerCertVerified::assertion());
+        };
+
+        match chain.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now) {
+            Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::NotValidForName
+                | rustls::CertificateError::NotValidForNameContext { .. },
+            )) => Ok(ServerCertVerified::assertion()),
+            result => result,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use crate::config::env::env_or;
+
+const DEFAULT_WINDOW_SECS: u64 = 60;
+const DEFAULT_IP_MAX_REQUESTS: u64 = 20;
+const DEFAULT_USERNAME_MAX_REQUESTS: u64 = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    pub window: Duration,
+    pub ip_max_requests: u64,
+    pub username_max_requests: u64,
+    pub trust_proxy: bool,
+    /// Evaluate limits and record would-be rejections without returning 429.
+    pub shadow_mode: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(DEFAULT_WINDOW_SECS),
+            ip_max_requests: DEFAULT_IP_MAX_REQUESTS,
+            username_max_requests: DEFAULT_USERNAME_MAX_REQUESTS,
+            trust_proxy: false,
+            shadow_mode: false,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        let config = Self {
+            window: Duration::from_secs(env_or(
+                "RATE_LIMIT_WINDOW_SECS",
+                defaults.window.as_secs(),
+            )),
+            ip_max_requests: env_or("RATE_LIMIT_IP_MAX_REQUESTS", defaults.ip_max_requests),
+            username_max_requests: env_or(
+                "RATE_LIMIT_USERNAME_MAX_REQUESTS",
+                defaults.username_max_requests,
+            ),
+            trust_proxy: env_or("RATE_LIMIT_TRUST_PROXY", defaults.trust_proxy),
+            shadow_mode: env_or("RATE_LIMIT_SHADOW_MODE", defaults.shadow_mode),
+        };
+
+        if config.window.is_zero() {
+            panic!("RATE_LIMIT_WINDOW_SECS must be greater than 0");
+        }
+
+        if config.ip_max_requests == 0 || config.username_max_requests == 0 {
+            panic!("RATE_LIMIT_*_MAX_REQUESTS must be greater than 0");
+        }
+
+        config
+    }
+}
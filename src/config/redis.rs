@@ -1,27 +1,208 @@
-use std::env;
+use std::{env, time::Duration};
 
-use redis::{Client, aio::ConnectionManager};
+use chrono::Utc;
+use redis::{AsyncCommands, Client, RedisResult, aio::ConnectionManager};
+
+use crate::{
+    config::{
+        StartupConfig,
+        env::{env_opt, env_or},
+    },
+    utils::redis::shard::{RING_KEY, RingRecord, plan_ring},
+};
 
 #[derive(Debug)]
 pub struct RedisConfig {
     pub url: Box<str>,
+    /// `host:port` of the primary, which holds the blacklist when there are
+    /// no shards.
+    pub primary: Box<str>,
+    /// `(host:port, url)` for each blacklist shard from `REDIS_SHARDS`.
+    pub shards: Vec<(Box<str>, Box<str>)>,
+    /// The shards before `REDIS_SHARDS` last changed, from
+    /// `REDIS_SHARDS_PREVIOUS`, read until their entries have expired.
+    pub previous_shards: Option<Vec<(Box<str>, Box<str>)>>,
 }
 
 impl RedisConfig {
     pub fn from_env() -> Self {
+        let password = env::var("REDIS_PASSWORD").unwrap();
+        let primary = format!(
+            "{}:{}",
+            env::var("REDIS_HOST").unwrap(),
+            env::var("REDIS_PORT").unwrap()
+        );
+        let with_urls = |names: Vec<Box<str>>| -> Vec<(Box<str>, Box<str>)> {
+            names
+                .into_iter()
+                .map(|name| {
+                    let url = format!("redis://:{}@{}", password, name).into_boxed_str();
+                    (name, url)
+                })
+                .collect()
+        };
+
+        Self {
+            url: format!("redis://:{}@{}", password, primary).into_boxed_str(),
+            shards: with_urls(parse_shards(
+                env_opt("REDIS_SHARDS").as_deref().unwrap_or(""),
+            )),
+            previous_shards: env_opt("REDIS_SHARDS_PREVIOUS")
+                .map(|previous| with_urls(parse_shards(&previous))),
+            primary: primary.into_boxed_str(),
+        }
+    }
+
+    /// Waits for Redis to accept a connection, retrying as `startup` says.
+    pub async fn create_conn_manager(&self, startup: &StartupConfig) -> ConnectionManager {
+        startup.retry("Redis", || connect(&self.url)).await
+    }
+
+    /// For connections a `ConnectionManager` cannot multiplex, such as
+    /// pub/sub subscriptions.
+    pub fn create_client(&self) -> Client {
+        Client::open(&*self.url).unwrap()
+    }
+
+    /// Names of the shards the blacklist is spread over: the primary alone
+    /// when `REDIS_SHARDS` is empty.
+    pub fn ring(&self) -> Vec<Box<str>> {
+        if self.shards.is_empty() {
+            return vec![self.primary.clone()];
+        }
+        self.shards.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    pub fn previous_ring(&self) -> Option<Vec<Box<str>>> {
+        self.previous_shards
+            .as_ref()
+            .map(|shards| shards.iter().map(|(name, _)| name.clone()).collect())
+    }
+
+    /// A connection to every shard of `ring` and `previous_ring`. Empty when
+    /// the blacklist stays on the primary, with no migration under way.
+    pub async fn create_shard_managers(
+        &self,
+        startup: &StartupConfig,
+    ) -> Vec<(Box<str>, ConnectionManager)> {
+        if self.shards.is_empty() && self.previous_shards.is_none() {
+            return Vec::new();
+        }
+
+        let primary = (self.primary.clone(), self.url.clone());
+        let current = if self.shards.is_empty() {
+            std::slice::from_ref(&primary)
+        } else {
+            self.shards.as_slice()
+        };
+        let previous = self.previous_shards.as_deref().unwrap_or_default();
+
+        let mut managers: Vec<(Box<str>, ConnectionManager)> = Vec::new();
+        for (name, url) in current.iter().chain(previous) {
+            if managers.iter().any(|(connected, _)| connected == name) {
+                continue;
+            }
+            let target = format!("Redis shard {}", name);
+            managers.push((name.clone(), startup.retry(&target, || connect(url)).await));
+        }
+        managers
+    }
+
+    /// Records the ring in the primary, and refuses to start when it changed
+    /// without `REDIS_SHARDS_PREVIOUS`, or that is dropped while entries
+    /// written under the previous ring may still be live. `retention` is
+    /// how long a blacklist entry can live. Skipped when Redis is down.
+    pub async fn check_shard_ring(&self, conn: &ConnectionManager, retention: Duration) {
+        let mut conn = conn.clone();
+        let recorded: Option<String> = match conn.get(RING_KEY).await {
+            Ok(recorded) => recorded,
+            Err(e) => {
+                tracing::warn!("Skipping the Redis shard ring check: {}", e);
+                return;
+            }
+        };
+        let recorded =
+            recorded.and_then(|value| match serde_json::from_str::<RingRecord>(&value) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    tracing::warn!("Replacing malformed Redis shard ring record: {}", e);
+                    None
+                }
+            });
+
+        let plan = plan_ring(
+            recorded.as_ref(),
+            &self.ring(),
+            self.previous_ring().as_deref(),
+            Utc::now().timestamp(),
+            retention.as_secs() as i64,
+        );
+        match plan {
+            Ok(Some(record)) => {
+                let value = serde_json::to_string(&record).unwrap();
+                if let Err(e) = conn.set::<_, _, ()>(RING_KEY, value).await {
+                    tracing::warn!("Failed to record the Redis shard ring: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(reason) => panic!("{}", reason),
+        }
+    }
+}
+
+async fn connect(url: &str) -> RedisResult<ConnectionManager> {
+    let client = Client::open(url).unwrap();
+    ConnectionManager::new(client).await
+}
+
+/// Parses a comma-separated `host:port` list. Names identify shards on the
+/// hash ring, so duplicates are rejected rather than silently merged.
+pub fn parse_shards(value: &str) -> Vec<Box<str>> {
+    let mut shards: Vec<Box<str>> = Vec::new();
+
+    for shard in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match shard.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+            _ => panic!("REDIS_SHARDS entries must be host:port, got {}", shard),
+        }
+        if shards.iter().any(|existing| &**existing == shard) {
+            panic!("REDIS_SHARDS lists {} more than once", shard);
+        }
+        shards.push(shard.into());
+    }
+
+    shards
+}
+
+/// When Redis memory counts as under pressure. `threshold_bytes` wins over
+/// `threshold_percent`, which is relative to `maxmemory` and so does nothing
+/// on an instance without one.
+#[derive(Debug, Clone, Copy)]
+pub struct RedisMemoryConfig {
+    pub check_interval: Option<Duration>,
+    pub threshold_percent: u8,
+    pub threshold_bytes: Option<u64>,
+}
+
+impl RedisMemoryConfig {
+    pub fn from_env() -> Self {
+        let interval_secs: u64 = env_or("REDIS_MEMORY_CHECK_INTERVAL_SECS", 30);
+        let threshold_percent = env_or("REDIS_MEMORY_THRESHOLD_PERCENT", 85);
+
+        if !(1..=100).contains(&threshold_percent) {
+            panic!("REDIS_MEMORY_THRESHOLD_PERCENT must be between 1 and 100");
+        }
+
         Self {
-            url: format!(
-                "redis://:{}@{}:{}",
-                env::var("REDIS_PASSWORD").unwrap(),
-                env::var("REDIS_HOST").unwrap(),
-                env::var("REDIS_PORT").unwrap()
-            )
-            .into_boxed_str(),
+            check_interval: (interval_secs > 0).then(|| Duration::from_secs(interval_secs)),
+            threshold_percent,
+            threshold_bytes: Some(env_or("REDIS_MEMORY_THRESHOLD_BYTES", 0)).filter(|b| *b > 0),
         }
     }
 
-    pub async fn create_conn_manager(&self) -> ConnectionManager {
-        let client = Client::open(&*self.url).unwrap();
-        ConnectionManager::new(client).await.unwrap()
+    pub fn threshold(&self, maxmemory: u64) -> Option<u64> {
+        self.threshold_bytes.or_else(|| {
+            (maxmemory > 0).then(|| maxmemory / 100 * u64::from(self.threshold_percent))
+        })
     }
 }
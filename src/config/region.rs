@@ -0,0 +1,107 @@
+use url::Url;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    config::{env::env_opt, origin::OriginConfig, postgres::DbRegionConfig, tenant::entries},
+};
+
+/// Which region's database a request's accounts live in. A frontend origin
+/// may be pinned to a region; other callers may name one with
+/// `X-Data-Region` through a trusted proxy. Everything else stays in the
+/// home database.
+#[derive(Debug, Clone, Default)]
+pub struct RegionConfig {
+    /// Frontend origins, as in `ORIGIN_FRONTEND`, and their region.
+    origins: Vec<(Box<str>, Box<str>)>,
+    /// The regions in `DB_REGIONS`.
+    regions: Vec<Box<str>>,
+}
+
+impl RegionConfig {
+    pub fn from_env(origin_config: &OriginConfig, regions: &[DbRegionConfig]) -> Self {
+        Self::parse(
+            env_opt("REGION_ORIGINS").as_deref().unwrap_or(""),
+            origin_config,
+            regions,
+        )
+    }
+
+    /// Reads `REGION_ORIGINS`, comma separated `<origin>=<region>` pairs.
+    /// Origins must appear in `ORIGIN_FRONTEND` and regions in `DB_REGIONS`.
+    pub fn parse(origins: &str, origin_config: &OriginConfig, regions: &[DbRegionConfig]) -> Self {
+        let mut config = Self {
+            origins: Vec::new(),
+            regions: regions.iter().map(|region| region.name.clone()).collect(),
+        };
+
+        for entry in entries(origins) {
+            let Some((origin, region)) = entry.split_once('=') else {
+                panic!(
+                    "REGION_ORIGINS entries must be <origin>=<region>: {}",
+                    entry
+                );
+            };
+            let region = region.trim();
+            let origin = Url::parse(origin.trim())
+                .map(|url| url.origin().ascii_serialization())
+                .unwrap_or_else(|e| {
+                    panic!("REGION_ORIGINS has an invalid origin {}: {}", origin, e)
+                });
+            if !origin_config
+                .relying_parties
+                .iter()
+                .any(|party| *party.origin == origin)
+            {
+                panic!(
+                    "REGION_ORIGINS maps {}, which is not in ORIGIN_FRONTEND",
+                    origin
+                );
+            }
+            if !config.regions.iter().any(|r| **r == *region) {
+                panic!(
+                    "REGION_ORIGINS maps {} to {}, which is not in DB_REGIONS",
+                    origin, region
+                );
+            }
+            if config.origins.iter().any(|(o, _)| **o == *origin) {
+                panic!("REGION_ORIGINS lists {} twice", origin);
+            }
+            config.origins.push((origin.into(), region.into()));
+        }
+
+        config
+    }
+
+    /// The regions with a database of their own.
+    pub fn regions(&self) -> &[Box<str>] {
+        &self.regions
+    }
+
+    /// The region of a request with this `Origin` and trusted
+    /// `X-Data-Region`, `None` for the home database. A mapped origin wins
+    /// over the header, as for tenants.
+    pub fn resolve(
+        &self,
+        origin: Option<&str>,
+        header: Option<&str>,
+    ) -> Result<Option<&str>, AppError> {
+        if let Some((_, region)) =
+            origin.and_then(|origin| self.origins.iter().find(|(o, _)| **o == *origin))
+        {
+            return Ok(Some(region));
+        }
+
+        match header {
+            Some(header) => self
+                .regions
+                .iter()
+                .find(|region| ***region == *header)
+                .map(|region| Some(&**region))
+                .ok_or_else(|| {
+                    AppError::BadRequest(format!("Unknown region: {}", header))
+                        .with_code(ErrorCode::UnknownRegion)
+                }),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use crate::{app::router::MAX_BODY_BYTES, config::env::env_or};
+
+const AUTH_TIMEOUT_MS: u64 = 5_000;
+const ADMIN_TIMEOUT_MS: u64 = 10_000;
+const HEALTH_TIMEOUT_MS: u64 = 1_000;
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+const MIN_REQUESTED_TIMEOUT_MS: u64 = 100;
+
+// WebAuthn credentials stay well below 16 KiB; the rest is headroom.
+const AUTH_BODY_LIMIT_BYTES: usize = 64 * 1024;
+const HEALTH_BODY_LIMIT_BYTES: usize = 1024;
+
+/// Deadline and body size allowed for one class of routes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoutePolicy {
+    /// `None` lets the request run for as long as it takes.
+    pub timeout: Option<Duration>,
+    pub max_body_bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RequestPolicyConfig {
+    pub auth: RoutePolicy,
+    pub admin: RoutePolicy,
+    pub health: RoutePolicy,
+    pub default: RoutePolicy,
+    /// Floor for `X-Request-Timeout`, so callers cannot ask for budgets no
+    /// request could meet.
+    pub min_requested_timeout: Duration,
+}
+
+impl Default for RequestPolicyConfig {
+    fn default() -> Self {
+        Self {
+            auth: RoutePolicy {
+                timeout: Some(Duration::from_millis(AUTH_TIMEOUT_MS)),
+                max_body_bytes: AUTH_BODY_LIMIT_BYTES,
+            },
+            admin: RoutePolicy {
+                timeout: Some(Duration::from_millis(ADMIN_TIMEOUT_MS)),
+                max_body_bytes: MAX_BODY_BYTES,
+            },
+            health: RoutePolicy {
+                timeout: Some(Duration::from_millis(HEALTH_TIMEOUT_MS)),
+                max_body_bytes: HEALTH_BODY_LIMIT_BYTES,
+            },
+            default: RoutePolicy {
+                timeout: Some(Duration::from_millis(DEFAULT_TIMEOUT_MS)),
+                max_body_bytes: MAX_BODY_BYTES,
+            },
+            min_requested_timeout: Duration::from_millis(MIN_REQUESTED_TIMEOUT_MS),
+        }
+    }
+}
+
+impl RequestPolicyConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        Self {
+            auth: route_policy_from_env("AUTH", defaults.auth),
+            admin: route_policy_from_env("ADMIN", defaults.admin),
+            health: route_policy_from_env("HEALTH", defaults.health),
+            default: route_policy_from_env("DEFAULT", defaults.default),
+            min_requested_timeout: Duration::from_millis(env_or(
+                "REQUEST_TIMEOUT_MIN_REQUESTED_MS",
+                MIN_REQUESTED_TIMEOUT_MS,
+            )),
+        }
+    }
+
+    /// How long a request under `policy` may run when the caller asked for
+    /// `requested`: never longer than the route allows, nor shorter than
+    /// `min_requested_timeout`.
+    pub fn budget(&self, policy: &RoutePolicy, requested: Option<Duration>) -> Option<Duration> {
+        let Some(requested) = requested else {
+            return policy.timeout;
+        };
+
+        let requested = requested.max(self.min_requested_timeout);
+        Some(
+            policy
+                .timeout
+                .map_or(requested, |timeout| timeout.min(requested)),
+        )
+    }
+
+    pub fn for_path(&self, path: &str) -> RoutePolicy {
+        if path.starts_with("/auth/") {
+            self.auth
+        } else if path.starts_with("/admin/") {
+            self.admin
+        } else if matches!(path, "/livez" | "/readyz" | "/healthz" | "/startupz") {
+            self.health
+        } else {
+            self.default
+        }
+    }
+}
+
+fn route_policy_from_env(class: &str, defaults: RoutePolicy) -> RoutePolicy {
+    let timeout_key = format!("REQUEST_TIMEOUT_{}_MS", class);
+    let body_key = format!("REQUEST_BODY_LIMIT_{}_BYTES", class);
+
+    let timeout_ms: u64 = env_or(
+        &timeout_key,
+        defaults
+            .timeout
+            .map_or(0, |timeout| timeout.as_millis() as u64),
+    );
+    let max_body_bytes = env_or(&body_key, defaults.max_body_bytes);
+
+    if max_body_bytes == 0 || max_body_bytes > MAX_BODY_BYTES {
+        panic!("{} must be between 1 and {}", body_key, MAX_BODY_BYTES);
+    }
+
+    RoutePolicy {
+        timeout: (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms)),
+        max_body_bytes,
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use crate::config::env::env_or;
+
+const DEFAULT_FALLBACK_MAX_OUTAGE_SECS: u64 = 5 * 60;
+const DEFAULT_FALLBACK_MAX_ENTRIES: i64 = 100_000;
+const DEFAULT_MEMORY_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct RevocationConfig {
+    /// How long refresh keeps working on the fallback stores once Redis fails;
+    /// `None` fails closed straight away, as before the fallback existed.
+    pub fallback_max_outage: Option<Duration>,
+    /// Cap on rows in the Postgres fallback table.
+    pub fallback_max_entries: i64,
+    /// Revocations this instance remembers in memory, newest first.
+    pub memory_entries: usize,
+}
+
+impl RevocationConfig {
+    pub fn from_env() -> Self {
+        let max_outage_secs: u64 = env_or(
+            "REVOCATION_FALLBACK_MAX_OUTAGE_SECS",
+            DEFAULT_FALLBACK_MAX_OUTAGE_SECS,
+        );
+        let fallback_max_entries = env_or(
+            "REVOCATION_FALLBACK_MAX_ENTRIES",
+            DEFAULT_FALLBACK_MAX_ENTRIES,
+        );
+        let memory_entries = env_or("REVOCATION_MEMORY_ENTRIES", DEFAULT_MEMORY_ENTRIES);
+
+        if fallback_max_entries <= 0 {
+            panic!("REVOCATION_FALLBACK_MAX_ENTRIES must be greater than 0");
+        }
+
+        if memory_entries == 0 {
+            panic!("REVOCATION_MEMORY_ENTRIES must be greater than 0");
+        }
+
+        Self {
+            fallback_max_outage: (max_outage_secs > 0)
+                .then(|| Duration::from_secs(max_outage_secs)),
+            fallback_max_entries,
+            memory_entries,
+        }
+    }
+}
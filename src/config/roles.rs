@@ -0,0 +1,62 @@
+use crate::config::{
+    env::{env_opt, env_or},
+    tenant::entries,
+};
+
+/// Who may register, and with which roles. Privileged roles also need the
+/// caller to hold `admin:actions` or an invitation issued for the role;
+/// anything unlisted is refused.
+#[derive(Debug, Clone)]
+pub struct RolePolicy {
+    /// Anyone may register with these.
+    open: Vec<Box<str>>,
+    privileged: Vec<Box<str>>,
+    /// Every registration needs `admin:actions` or an invitation.
+    pub invite_only: bool,
+}
+
+impl RolePolicy {
+    pub fn from_env() -> Self {
+        let mut policy = Self::parse(
+            env_opt("REGISTRATION_ROLES").as_deref().unwrap_or(""),
+            env_opt("PRIVILEGED_ROLES").as_deref().unwrap_or("admin"),
+        );
+        policy.invite_only = env_or("REGISTRATION_INVITE_ONLY", false);
+        policy
+    }
+
+    /// Reads `REGISTRATION_ROLES` and `PRIVILEGED_ROLES`, both comma
+    /// separated role names. A role cannot be both.
+    pub fn parse(open: &str, privileged: &str) -> Self {
+        let open: Vec<Box<str>> = entries(open).map(Into::into).collect();
+        let privileged: Vec<Box<str>> = entries(privileged).map(Into::into).collect();
+
+        if let Some(role) = open.iter().find(|role| privileged.contains(role)) {
+            panic!(
+                "{} is in both REGISTRATION_ROLES and PRIVILEGED_ROLES",
+                role
+            );
+        }
+
+        Self {
+            open,
+            privileged,
+            invite_only: false,
+        }
+    }
+
+    pub fn is_allowed(&self, role: &str) -> bool {
+        self.open.iter().any(|r| **r == *role) || self.is_privileged(role)
+    }
+
+    pub fn is_privileged(&self, role: &str) -> bool {
+        self.privileged.iter().any(|r| **r == *role)
+    }
+}
+
+impl Default for RolePolicy {
+    /// Open registration, with `admin` only for administrators or invitees.
+    fn default() -> Self {
+        Self::parse("", "admin")
+    }
+}
@@ -0,0 +1,114 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::config::env::env_opt;
+
+/// A request is fast enough when it finishes within `threshold`, and the
+/// route promises that `target` of them are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyObjective {
+    pub threshold: Duration,
+    pub target: f64,
+}
+
+/// Objectives of one route. Targets are ratios, `0.999` for 99.9%.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RouteObjectives {
+    pub availability: Option<f64>,
+    pub latency: Option<LatencyObjective>,
+}
+
+/// Routes are axum route templates, such as `/admin/actions/{name}`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SloConfig {
+    pub routes: BTreeMap<String, RouteObjectives>,
+}
+
+impl SloConfig {
+    pub fn from_env() -> Self {
+        Self::new(
+            env_opt("SLO_AVAILABILITY").map_or_else(Vec::new, |value| parse_availability(&value)),
+            env_opt("SLO_LATENCY").map_or_else(Vec::new, |value| parse_latency(&value)),
+        )
+    }
+
+    pub fn new(availability: Vec<(String, f64)>, latency: Vec<(String, LatencyObjective)>) -> Self {
+        let mut routes: BTreeMap<String, RouteObjectives> = BTreeMap::new();
+        for (route, target) in availability {
+            routes.entry(route).or_default().availability = Some(target);
+        }
+        for (route, objective) in latency {
+            routes.entry(route).or_default().latency = Some(objective);
+        }
+        Self { routes }
+    }
+}
+
+/// Comma separated `route=percent`, e.g. `/auth/login/finish=99.9`.
+pub fn parse_availability(value: &str) -> Vec<(String, f64)> {
+    entries("SLO_AVAILABILITY", value)
+        .map(|(route, objective)| (route, parse_target("SLO_AVAILABILITY", objective)))
+        .collect()
+}
+
+/// Comma separated `route=milliseconds@percent`, e.g.
+/// `/auth/login/finish=500@99` for 99% of requests within 500 ms.
+pub fn parse_latency(value: &str) -> Vec<(String, LatencyObjective)> {
+    entries("SLO_LATENCY", value)
+        .map(|(route, objective)| {
+            let Some((threshold, target)) = objective.split_once('@') else {
+                panic!("SLO_LATENCY must map {} to milliseconds@percent", route);
+            };
+            let threshold_ms = threshold
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .filter(|ms| *ms > 0)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "SLO_LATENCY threshold of {} must be a positive number of milliseconds",
+                        route
+                    )
+                });
+
+            (
+                route,
+                LatencyObjective {
+                    threshold: Duration::from_millis(threshold_ms),
+                    target: parse_target("SLO_LATENCY", target),
+                },
+            )
+        })
+        .collect()
+}
+
+fn entries<'a>(key: &'a str, value: &'a str) -> impl Iterator<Item = (String, &'a str)> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(move |entry| match entry.split_once('=') {
+            Some((route, objective)) if route.trim().starts_with('/') => {
+                (route.trim().to_string(), objective)
+            }
+            _ => panic!(
+                "{} entries must look like /route=objective, got {}",
+                key, entry
+            ),
+        })
+}
+
+/// At 100% there is no error budget to burn, so it is refused.
+fn parse_target(key: &str, percent: &str) -> f64 {
+    percent
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|percent| *percent > 0.0 && *percent < 100.0)
+        .map(|percent| percent / 100.0)
+        .unwrap_or_else(|| {
+            panic!(
+                "{} targets must be percentages between 0 and 100, got {}",
+                key, percent
+            )
+        })
+}
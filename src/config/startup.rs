@@ -0,0 +1,92 @@
+use std::{fmt::Display, future::Future, time::Duration};
+
+use crate::config::env::env_or;
+
+const DEFAULT_RETRY_INTERVAL_SECS: u64 = 1;
+const DEFAULT_MAX_RETRY_INTERVAL_SECS: u64 = 30;
+const DEFAULT_FAIL_AFTER_SECS: u64 = 5 * 60;
+
+/// How startup waits for Postgres and Redis. The server already answers the
+/// probes meanwhile, not ready, so a dependency that comes up a little late
+/// no longer takes the process down with it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StartupConfig {
+    /// Wait before the second attempt, doubled after each failure.
+    pub retry_interval: Duration,
+    pub max_retry_interval: Duration,
+    /// Exit if the connections are not up by then; `None` waits forever.
+    pub fail_after: Option<Duration>,
+}
+
+impl StartupConfig {
+    pub fn from_env() -> Self {
+        let retry_secs: u64 = env_or("STARTUP_RETRY_INTERVAL_SECS", DEFAULT_RETRY_INTERVAL_SECS);
+        let max_retry_secs: u64 = env_or(
+            "STARTUP_MAX_RETRY_INTERVAL_SECS",
+            DEFAULT_MAX_RETRY_INTERVAL_SECS,
+        );
+        let fail_after_secs: u64 = env_or("STARTUP_FAIL_AFTER_SECS", DEFAULT_FAIL_AFTER_SECS);
+
+        if retry_secs == 0 {
+            panic!("STARTUP_RETRY_INTERVAL_SECS must be greater than 0");
+        }
+        if max_retry_secs < retry_secs {
+            panic!("STARTUP_MAX_RETRY_INTERVAL_SECS must not be below STARTUP_RETRY_INTERVAL_SECS");
+        }
+
+        Self {
+            retry_interval: Duration::from_secs(retry_secs),
+            max_retry_interval: Duration::from_secs(max_retry_secs),
+            fail_after: (fail_after_secs > 0).then(|| Duration::from_secs(fail_after_secs)),
+        }
+    }
+
+    /// Wait after the `attempt`th failure, counting from 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.retry_interval
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_retry_interval)
+    }
+
+    /// Runs `connect` until it succeeds, logging each failure. Only the
+    /// `fail_after` deadline around the whole startup ends the wait.
+    pub async fn retry<T, E, F, Fut>(&self, target: &str, mut connect: F) -> T
+    where
+        E: Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match connect().await {
+                Ok(value) => {
+                    if attempt > 0 {
+                        tracing::info!("Connected to {} after {} retries", target, attempt);
+                    }
+                    return value;
+                }
+                Err(e) => {
+                    attempt += 1;
+                    let wait = self.backoff(attempt);
+                    tracing::warn!(
+                        "{} unavailable, retrying in {}s: {}",
+                        target,
+                        wait.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            retry_interval: Duration::from_secs(DEFAULT_RETRY_INTERVAL_SECS),
+            max_retry_interval: Duration::from_secs(DEFAULT_MAX_RETRY_INTERVAL_SECS),
+            fail_after: Some(Duration::from_secs(DEFAULT_FAIL_AFTER_SECS)),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::config::env::{env_opt, env_or};
+
+const DEFAULT_SERVICE_NAME: &str = "rs-server";
+const DEFAULT_SAMPLING_RATIO: f64 = 1.0;
+
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP collector base URL, e.g. `http://localhost:4318`; `None`
+    /// keeps tracing local to the log output.
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    /// Share of new traces that are recorded. Requests continuing a sampled
+    /// upstream trace always are.
+    pub sampling_ratio: f64,
+}
+
+impl TelemetryConfig {
+    pub fn from_env() -> Self {
+        let otlp_endpoint =
+            env_opt("OTEL_EXPORTER_OTLP_ENDPOINT").map(|url| url.trim_end_matches('/').to_owned());
+        let service_name = env_or("OTEL_SERVICE_NAME", String::from(DEFAULT_SERVICE_NAME));
+        let sampling_ratio = env_or("OTEL_TRACES_SAMPLER_ARG", DEFAULT_SAMPLING_RATIO);
+
+        if !(0.0..=1.0).contains(&sampling_ratio) {
+            panic!("OTEL_TRACES_SAMPLER_ARG must be between 0 and 1");
+        }
+
+        Self {
+            otlp_endpoint,
+            service_name,
+            sampling_ratio,
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use url::Url;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    config::{env::env_opt, origin::OriginConfig},
+};
+
+/// Owns every account that existed before tenants, and every request that
+/// names no other tenant.
+pub const DEFAULT_TENANT: &str = "default";
+const MAX_TENANT_LEN: usize = 63;
+
+/// Which tenant a request belongs to. A frontend origin always selects its
+/// own tenant; other callers may name one with `X-Tenant-Id` through a
+/// trusted proxy.
+#[derive(Debug, Clone)]
+pub struct TenantConfig {
+    /// Frontend origins, as in `ORIGIN_FRONTEND`, and the tenant they serve.
+    origins: Vec<(Box<str>, Box<str>)>,
+    /// Every tenant this deployment serves, `default` first.
+    tenants: Vec<Box<str>>,
+}
+
+impl TenantConfig {
+    pub fn from_env(origin_config: &OriginConfig) -> Self {
+        Self::parse(
+            env_opt("TENANT_ORIGINS").as_deref().unwrap_or(""),
+            env_opt("TENANT_IDS").as_deref().unwrap_or(""),
+            origin_config,
+        )
+    }
+
+    /// Reads `TENANT_ORIGINS`, comma separated `<origin>=<tenant>` pairs,
+    /// and `TENANT_IDS`, tenants served without a frontend of their own.
+    /// Origins must appear in `ORIGIN_FRONTEND`; unmapped ones stay with
+    /// the default tenant.
+    pub fn parse(origins: &str, extra_tenants: &str, origin_config: &OriginConfig) -> Self {
+        let mut config = Self::default();
+
+        for entry in entries(origins) {
+            let Some((origin, tenant)) = entry.split_once('=') else {
+                panic!(
+                    "TENANT_ORIGINS entries must be <origin>=<tenant>: {}",
+                    entry
+                );
+            };
+            let tenant = tenant.trim();
+            let origin = Url::parse(origin.trim())
+                .map(|url| url.origin().ascii_serialization())
+                .unwrap_or_else(|e| {
+                    panic!("TENANT_ORIGINS has an invalid origin {}: {}", origin, e)
+                });
+            if !origin_config
+                .relying_parties
+                .iter()
+                .any(|party| *party.origin == origin)
+            {
+                panic!(
+                    "TENANT_ORIGINS maps {}, which is not in ORIGIN_FRONTEND",
+                    origin
+                );
+            }
+            if config.origins.iter().any(|(o, _)| **o == *origin) {
+                panic!("TENANT_ORIGINS lists {} twice", origin);
+            }
+            config.origins.push((origin.into(), tenant.into()));
+            config.add_tenant("TENANT_ORIGINS", tenant);
+        }
+        for tenant in entries(extra_tenants) {
+            config.add_tenant("TENANT_IDS", tenant);
+        }
+
+        config
+    }
+
+    /// Every tenant this deployment serves, `default` first.
+    pub fn tenants(&self) -> &[Box<str>] {
+        &self.tenants
+    }
+
+    /// The tenant of a request with this `Origin` and trusted
+    /// `X-Tenant-Id`. A mapped origin wins over the header, so a page of one
+    /// tenant can never act for another.
+    pub fn resolve(&self, origin: Option<&str>, header: Option<&str>) -> Result<&str, AppError> {
+        if let Some((_, tenant)) =
+            origin.and_then(|origin| self.origins.iter().find(|(o, _)| **o == *origin))
+        {
+            return Ok(tenant);
+        }
+
+        match header {
+            Some(header) => self
+                .tenants
+                .iter()
+                .find(|tenant| ***tenant == *header)
+                .map(|tenant| &**tenant)
+                .ok_or_else(|| {
+                    AppError::BadRequest(format!("Unknown tenant: {}", header))
+                        .with_code(ErrorCode::UnknownTenant)
+                }),
+            None => Ok(DEFAULT_TENANT),
+        }
+    }
+
+    fn add_tenant(&mut self, key: &str, tenant: &str) {
+        if !is_valid_tenant(tenant) {
+            panic!(
+                "{} has an invalid tenant {}: use up to {} lowercase letters, digits, - and _",
+                key, tenant, MAX_TENANT_LEN
+            );
+        }
+        if !self.tenants.iter().any(|t| **t == *tenant) {
+            self.tenants.push(tenant.into());
+        }
+    }
+}
+
+impl Default for TenantConfig {
+    /// Everything belongs to the default tenant.
+    fn default() -> Self {
+        Self {
+            origins: Vec::new(),
+            tenants: vec![DEFAULT_TENANT.into()],
+        }
+    }
+}
+
+/// Same rule as the `tenants.id` check constraint.
+pub fn is_valid_tenant(tenant: &str) -> bool {
+    let mut chars = tenant.chars();
+    tenant.len() <= MAX_TENANT_LEN
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'))
+}
+
+pub(crate) fn entries(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|e| !e.is_empty())
+}
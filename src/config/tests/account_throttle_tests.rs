@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use crate::config::AccountThrottleConfig;
+
+#[test]
+fn test_delay_doubles_past_free_attempts_up_to_max() {
+    let config = AccountThrottleConfig::default();
+    let delays: Vec<u64> = (0..=12).map(|n| config.delay(n).as_secs()).collect();
+
+    assert_eq!(delays, [0, 0, 0, 0, 1, 2, 4, 8, 16, 32, 60, 60, 60]);
+}
+
+#[test]
+fn test_delay_does_not_overflow() {
+    let config = AccountThrottleConfig::default();
+    assert_eq!(config.delay(u32::MAX), Duration::from_secs(60));
+}
+
+#[test]
+#[should_panic(expected = "ACCOUNT_LOCKOUT_THRESHOLD must be greater than")]
+fn test_lockout_within_free_attempts_is_rejected() {
+    AccountThrottleConfig {
+        lockout_threshold: 3,
+        ..AccountThrottleConfig::default()
+    }
+    .validate();
+}
+
+#[test]
+fn test_disabled_config_is_not_validated() {
+    AccountThrottleConfig {
+        enabled: false,
+        lockout_threshold: 0,
+        ..AccountThrottleConfig::default()
+    }
+    .validate();
+}
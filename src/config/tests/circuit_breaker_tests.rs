@@ -0,0 +1,36 @@
+use crate::config::{CircuitBreaker, CircuitBreakerConfig};
+
+#[test]
+fn test_builder_overrides_defaults() {
+    let config = CircuitBreakerConfig::default()
+        .with_failure_threshold(3)
+        .with_backoff(2, 30);
+
+    assert_eq!(config.failure_threshold, 3);
+    assert_eq!(config.backoff_initial_secs, 2);
+    assert_eq!(config.backoff_max_secs, 30);
+    assert_eq!(config.validated("CB_DB"), config);
+}
+
+#[test]
+fn test_breaker_keeps_its_config() {
+    let config = CircuitBreakerConfig::default().with_failure_threshold(9);
+
+    assert_eq!(CircuitBreaker::new("redis", config).config(), config);
+}
+
+#[test]
+#[should_panic(expected = "CB_REDIS_FAILURE_THRESHOLD must be greater than 0")]
+fn test_zero_threshold_is_rejected() {
+    CircuitBreakerConfig::default()
+        .with_failure_threshold(0)
+        .validated("CB_REDIS");
+}
+
+#[test]
+#[should_panic(expected = "at most CB_DB_BACKOFF_MAX_SECS")]
+fn test_initial_backoff_above_max_is_rejected() {
+    CircuitBreakerConfig::default()
+        .with_backoff(120, 60)
+        .validated("CB_DB");
+}
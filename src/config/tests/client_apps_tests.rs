@@ -0,0 +1,48 @@
+use crate::config::client_apps::{ClientAppConfig, parse_apps, parse_resources};
+
+fn config() -> ClientAppConfig {
+    ClientAppConfig {
+        apps: parse_apps("web, ios, ios-beta, console"),
+        resources: parse_resources("/admin/=console, /admin/reports/=web"),
+    }
+}
+
+#[test]
+fn test_authorized_party_matches_registered_apps_and_versions() {
+    let config = config();
+
+    assert_eq!(config.authorized_party(Some("web")), Some("web"));
+    assert_eq!(config.authorized_party(Some("ios-2.3.1")), Some("ios"));
+    assert_eq!(
+        config.authorized_party(Some("ios-beta-0.9")),
+        Some("ios-beta")
+    );
+}
+
+#[test]
+fn test_authorized_party_ignores_unknown_apps() {
+    let config = config();
+
+    assert_eq!(config.authorized_party(None), None);
+    assert_eq!(config.authorized_party(Some("webby")), None);
+    assert_eq!(config.authorized_party(Some("android-1.0")), None);
+    assert_eq!(
+        ClientAppConfig::default().authorized_party(Some("web")),
+        None
+    );
+}
+
+#[test]
+fn test_expected_party_uses_longest_prefix() {
+    let config = config();
+
+    assert_eq!(config.expected_party("/admin/actions"), Some("console"));
+    assert_eq!(config.expected_party("/admin/reports/traffic"), Some("web"));
+    assert_eq!(config.expected_party("/auth/profile"), None);
+}
+
+#[test]
+#[should_panic(expected = "not prefix=app")]
+fn test_resources_need_a_path_prefix() {
+    parse_resources("admin=console");
+}
@@ -0,0 +1,39 @@
+use crate::config::{EventExportConfig, event_export::EventExportBackend};
+
+#[test]
+fn test_parse_selects_backend_by_scheme() {
+    let nats = EventExportConfig::parse("nats://nats:4222", "auth.events", 100);
+    let kafka = EventExportConfig::parse("kafka://k1:9092,k2:9092", "auth-events", 100);
+
+    assert_eq!(
+        nats.backend,
+        EventExportBackend::Nats {
+            url: "nats://nats:4222".into()
+        }
+    );
+    assert_eq!(
+        kafka.backend,
+        EventExportBackend::Kafka {
+            brokers: "k1:9092,k2:9092".into()
+        }
+    );
+    assert_eq!(&*kafka.topic, "auth-events");
+}
+
+#[test]
+#[should_panic(expected = "EVENT_EXPORT_URL must be nats://<host>:<port> or kafka://<broker>")]
+fn test_parse_rejects_unknown_scheme() {
+    EventExportConfig::parse("amqp://rabbit:5672", "auth.events", 100);
+}
+
+#[test]
+#[should_panic(expected = "EVENT_EXPORT_TOPIC has an invalid topic auth events")]
+fn test_parse_rejects_invalid_topic() {
+    EventExportConfig::parse("nats://nats:4222", "auth events", 100);
+}
+
+#[test]
+#[should_panic(expected = "EVENT_EXPORT_QUEUE_CAPACITY must be greater than 0")]
+fn test_parse_rejects_empty_queue() {
+    EventExportConfig::parse("nats://nats:4222", "auth.events", 0);
+}
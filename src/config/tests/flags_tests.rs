@@ -0,0 +1,100 @@
+use crate::config::flags::{FeatureFlag, FeatureFlags, Variant, bucket};
+
+fn users() -> impl Iterator<Item = String> {
+    (0..2_000).map(|i| format!("user{}", i))
+}
+
+#[test]
+fn test_parse_percentages_and_usernames() {
+    let flags = FeatureFlags::parse("discoverable_login=10, dpop=0.5:alice|bob ,rotation=100");
+
+    assert_eq!(
+        flags.flags,
+        vec![
+            FeatureFlag {
+                name: "discoverable_login".into(),
+                rollout: 1_000,
+                usernames: vec![],
+            },
+            FeatureFlag {
+                name: "dpop".into(),
+                rollout: 50,
+                usernames: vec!["alice".into(), "bob".into()],
+            },
+            FeatureFlag {
+                name: "rotation".into(),
+                rollout: 10_000,
+                usernames: vec![],
+            },
+        ]
+    );
+    assert!(FeatureFlags::parse("").flags.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "0-100")]
+fn test_percentage_above_100() {
+    FeatureFlags::parse("dpop=150");
+}
+
+#[test]
+#[should_panic(expected = "name=percent")]
+fn test_invalid_flag_name() {
+    FeatureFlags::parse("New-Login=10");
+}
+
+#[test]
+#[should_panic(expected = "twice")]
+fn test_flag_listed_twice() {
+    FeatureFlags::parse("dpop=10,dpop=20");
+}
+
+#[test]
+fn test_listed_usernames_always_get_the_treatment() {
+    let flags = FeatureFlags::parse("dpop=0:alice");
+
+    assert_eq!(flags.flags[0].variant("alice"), Variant::Treatment);
+    assert!(users().all(|user| flags.flags[0].variant(&user) == Variant::Control));
+}
+
+#[test]
+fn test_usernames_are_normalized() {
+    let flags = FeatureFlags::parse("dpop=0:Alice,rotation=50");
+
+    assert_eq!(flags.flags[0].usernames, vec![Box::from("alice")]);
+    assert_eq!(flags.flags[0].variant("ALICE"), Variant::Treatment);
+    assert!(
+        users()
+            .all(|user| flags.flags[1].variant(&user)
+                == flags.flags[1].variant(&user.to_uppercase()))
+    );
+}
+
+#[test]
+fn test_rollout_reaches_about_the_percentage() {
+    let flags = FeatureFlags::parse("discoverable_login=25");
+    let enabled = users()
+        .filter(|user| flags.flags[0].variant(user) == Variant::Treatment)
+        .count();
+
+    assert!((400..600).contains(&enabled), "{} of 2000 enabled", enabled);
+}
+
+#[test]
+fn test_raising_the_percentage_only_adds_users() {
+    let ten = FeatureFlags::parse("dpop=10");
+    let fifty = FeatureFlags::parse("dpop=50");
+
+    assert!(
+        users()
+            .filter(|user| ten.flags[0].variant(user) == Variant::Treatment)
+            .all(|user| fifty.flags[0].variant(&user) == Variant::Treatment)
+    );
+}
+
+#[test]
+fn test_buckets_are_stable_and_salted_per_flag() {
+    assert_eq!(bucket("dpop", "alice"), bucket("dpop", "alice"));
+    assert!(bucket("dpop", "alice") < 10_000);
+    assert!(users().any(|user| bucket("dpop", &user) != bucket("rotation", &user)));
+}
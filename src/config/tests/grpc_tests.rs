@@ -0,0 +1,29 @@
+use std::net::SocketAddr;
+
+use crate::config::GrpcConfig;
+
+#[test]
+fn test_binds_every_interface_by_default() {
+    let config = GrpcConfig::parse(None, "50051");
+
+    assert_eq!(config.addr, "0.0.0.0:50051".parse::<SocketAddr>().unwrap());
+}
+
+#[test]
+fn test_binds_the_given_host() {
+    let config = GrpcConfig::parse(Some(" ::1 "), " 50051 ");
+
+    assert_eq!(config.addr, "[::1]:50051".parse::<SocketAddr>().unwrap());
+}
+
+#[test]
+#[should_panic(expected = "GRPC_PORT must be a port number")]
+fn test_rejects_port_zero() {
+    GrpcConfig::parse(None, "0");
+}
+
+#[test]
+#[should_panic(expected = "GRPC_HOST must be an IP address")]
+fn test_rejects_hostnames() {
+    GrpcConfig::parse(Some("localhost"), "50051");
+}
@@ -0,0 +1,91 @@
+use axum::http::{HeaderMap, HeaderValue, header::AUTHORIZATION};
+use base64::{Engine, prelude::BASE64_STANDARD};
+
+use crate::config::introspection::{
+    IntrospectionClient, IntrospectionConfig, MtlsConfig, parse_subjects,
+};
+
+const SUBJECT: &str = "CN=orders,O=Example";
+
+fn config() -> IntrospectionConfig {
+    IntrospectionConfig {
+        client: Some(IntrospectionClient {
+            id: "orders".into(),
+            secret: "s3cret".into(),
+        }),
+        mtls: Some(MtlsConfig {
+            header: "x-client-cert-subject".parse().unwrap(),
+            subjects: vec![SUBJECT.into()],
+        }),
+    }
+}
+
+fn basic(id: &str, secret: &str) -> HeaderMap {
+    let encoded = BASE64_STANDARD.encode(format!("{}:{}", id, secret));
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Basic {}", encoded)).unwrap(),
+    );
+    headers
+}
+
+fn subject(value: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-client-cert-subject",
+        HeaderValue::from_str(value).unwrap(),
+    );
+    headers
+}
+
+#[test]
+fn test_authorize_accepts_client_credentials() {
+    assert!(config().authorize(&basic("orders", "s3cret")));
+}
+
+#[test]
+fn test_authorize_rejects_wrong_credentials() {
+    let config = config();
+
+    assert!(!config.authorize(&basic("orders", "wrong")));
+    assert!(!config.authorize(&basic("billing", "s3cret")));
+    assert!(!config.authorize(&HeaderMap::new()));
+}
+
+#[test]
+fn test_authorize_rejects_bearer_tokens() {
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer s3cret"));
+
+    assert!(!config().authorize(&headers));
+}
+
+#[test]
+fn test_authorize_accepts_allowed_certificate_subject() {
+    let config = config();
+
+    assert!(config.authorize(&subject(SUBJECT)));
+    assert!(!config.authorize(&subject("CN=billing,O=Example")));
+}
+
+#[test]
+fn test_authorize_rejects_everything_when_unconfigured() {
+    let config = IntrospectionConfig::default();
+
+    assert!(!config.authorize(&basic("orders", "s3cret")));
+    assert!(!config.authorize(&subject(SUBJECT)));
+}
+
+#[test]
+fn test_parse_subjects_splits_on_semicolons() {
+    let subjects = parse_subjects("CN=orders,O=Example; CN=billing,O=Example;");
+
+    assert_eq!(
+        subjects,
+        vec![
+            Box::from("CN=orders,O=Example"),
+            Box::from("CN=billing,O=Example")
+        ]
+    );
+}
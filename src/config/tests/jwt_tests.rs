@@ -0,0 +1,29 @@
+use crate::config::{AccessTokenFormat, JwtConfig};
+
+#[test]
+fn test_access_token_format_parses_any_case() {
+    assert_eq!(
+        AccessTokenFormat::parse("jwt"),
+        Some(AccessTokenFormat::Jwt)
+    );
+    assert_eq!(
+        AccessTokenFormat::parse(" Opaque "),
+        Some(AccessTokenFormat::Opaque)
+    );
+    assert_eq!(AccessTokenFormat::parse("paseto"), None);
+}
+
+#[test]
+fn test_refresh_grace_may_be_off() {
+    assert!(JwtConfig::parse_refresh_grace(0).is_zero());
+    assert_eq!(
+        JwtConfig::parse_refresh_grace(60),
+        std::time::Duration::from_secs(60)
+    );
+}
+
+#[test]
+#[should_panic(expected = "JWT_REFRESH_GRACE_SECS must be at most 60")]
+fn test_refresh_grace_is_capped() {
+    JwtConfig::parse_refresh_grace(61);
+}
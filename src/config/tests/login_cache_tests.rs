@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use crate::config::LoginCacheConfig;
+
+#[test]
+fn test_login_cache_is_off_by_default() {
+    let config = LoginCacheConfig::default();
+    assert!(!config.enabled);
+    assert_eq!(config.ttl, Duration::from_secs(60));
+}
+
+#[test]
+#[should_panic(expected = "LOGIN_CACHE_TTL_SECS must be greater than 0")]
+fn test_enabled_cache_needs_a_ttl() {
+    LoginCacheConfig {
+        enabled: true,
+        ttl: Duration::ZERO,
+    }
+    .validate();
+}
+
+#[test]
+fn test_disabled_cache_ignores_the_ttl() {
+    LoginCacheConfig {
+        enabled: false,
+        ttl: Duration::ZERO,
+    }
+    .validate();
+}
@@ -0,0 +1,14 @@
+use crate::config::LoginHints;
+
+#[test]
+fn test_login_hints_parse_any_case() {
+    assert_eq!(LoginHints::parse("off"), Some(LoginHints::Off));
+    assert_eq!(LoginHints::parse(" Uniform "), Some(LoginHints::Uniform));
+    assert_eq!(LoginHints::parse("EXACT"), Some(LoginHints::Exact));
+    assert_eq!(LoginHints::parse("on"), None);
+}
+
+#[test]
+fn test_login_hints_are_off_by_default() {
+    assert_eq!(LoginHints::default(), LoginHints::Off);
+}
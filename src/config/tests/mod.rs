@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod account_throttle_tests;
+#[cfg(test)]
+mod circuit_breaker_tests;
+#[cfg(test)]
+mod client_apps_tests;
+#[cfg(test)]
+mod event_export_tests;
+#[cfg(test)]
+mod flags_tests;
+#[cfg(all(test, feature = "grpc"))]
+mod grpc_tests;
+#[cfg(test)]
+mod introspection_tests;
+#[cfg(test)]
+mod jwt_tests;
+#[cfg(test)]
+mod login_cache_tests;
+#[cfg(test)]
+mod login_hints_tests;
+#[cfg(test)]
+mod origin_tests;
+#[cfg(test)]
+mod postgres_tests;
+#[cfg(test)]
+mod postgres_tls_tests;
+#[cfg(test)]
+mod region_tests;
+#[cfg(test)]
+mod request_policy_tests;
+#[cfg(test)]
+mod roles_tests;
+#[cfg(test)]
+mod slo_tests;
+#[cfg(test)]
+mod startup_tests;
+#[cfg(test)]
+mod tenant_tests;
+#[cfg(test)]
+mod username_tests;
+#[cfg(test)]
+mod webauthn_tests;
@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderValue, Method, Request, header},
+    response::Response,
+    routing::get,
+};
+use tower::ServiceExt;
+
+use crate::config::{CorsConfig, OriginConfig, origin::parse_list};
+
+const FRONTEND: &str = "https://app.example.com";
+const CONSOLE: &str = "https://console.example.com";
+
+fn origin_config() -> OriginConfig {
+    OriginConfig::parse(FRONTEND, "api.example.com")
+}
+
+fn router(cors: CorsConfig) -> Router {
+    Router::new()
+        .route("/auth/me", get(|| async { "ok" }))
+        .route("/admin/audit", get(|| async { "ok" }))
+        .layer(origin_config().create_cors_layer(&cors))
+}
+
+async fn preflight(cors: CorsConfig, path: &str, origin: &str) -> Response {
+    router(cors)
+        .oneshot(
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri(path)
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+fn header_value(response: &Response, name: header::HeaderName) -> Option<&str> {
+    response.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+fn console_only() -> CorsConfig {
+    CorsConfig {
+        admin_origins: Some(vec![HeaderValue::from_static(CONSOLE)]),
+        ..CorsConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn test_preflight_allows_frontend_with_long_max_age() {
+    let response = preflight(CorsConfig::default(), "/auth/me", FRONTEND).await;
+
+    assert_eq!(
+        header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        Some(FRONTEND)
+    );
+    assert_eq!(
+        header_value(&response, header::ACCESS_CONTROL_MAX_AGE),
+        Some("86400")
+    );
+}
+
+#[tokio::test]
+async fn test_admin_preflight_has_shorter_max_age() {
+    let response = preflight(CorsConfig::default(), "/admin/audit", FRONTEND).await;
+
+    assert_eq!(
+        header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        Some(FRONTEND)
+    );
+    assert_eq!(
+        header_value(&response, header::ACCESS_CONTROL_MAX_AGE),
+        Some("600")
+    );
+}
+
+#[tokio::test]
+async fn test_admin_origins_replace_frontend_on_admin_routes() {
+    let from_frontend = preflight(console_only(), "/admin/audit", FRONTEND).await;
+    let from_console = preflight(console_only(), "/admin/audit", CONSOLE).await;
+    let console_elsewhere = preflight(console_only(), "/auth/me", CONSOLE).await;
+
+    assert!(header_value(&from_frontend, header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    assert_eq!(
+        header_value(&from_console, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        Some(CONSOLE)
+    );
+    assert!(header_value(&console_elsewhere, header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+}
+
+#[tokio::test]
+async fn test_configured_max_age_and_exposed_headers_apply() {
+    let cors = CorsConfig {
+        expose_headers: parse_list("KEY", "RateLimit-Remaining, Deprecation"),
+        max_age: Duration::from_secs(120),
+        ..CorsConfig::default()
+    };
+
+    let response = router(cors.clone())
+        .oneshot(
+            Request::builder()
+                .uri("/auth/me")
+                .header(header::ORIGIN, FRONTEND)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let exposed = header_value(&response, header::ACCESS_CONTROL_EXPOSE_HEADERS).unwrap();
+
+    assert!(exposed.contains("x-request-id"));
+    assert!(exposed.contains("retry-after"));
+    assert!(exposed.contains("ratelimit-remaining"));
+    assert!(exposed.contains("deprecation"));
+    assert_eq!(
+        header_value(
+            &preflight(cors, "/auth/me", FRONTEND).await,
+            header::ACCESS_CONTROL_MAX_AGE
+        ),
+        Some("120")
+    );
+}
+
+#[test]
+#[should_panic(expected = "KEY has an invalid entry: bad header")]
+fn test_parse_list_rejects_invalid_entries() {
+    parse_list::<header::HeaderName>("KEY", "x-ok, bad header");
+}
+
+#[test]
+fn test_parse_pairs_origins_with_their_rp_ids() {
+    let config = OriginConfig::parse(
+        "https://app.example.com/, https://admin.example.com=admin.example.com",
+        "example.com",
+    );
+    let parties: Vec<(&str, &str)> = config
+        .relying_parties
+        .iter()
+        .map(|party| (&*party.origin, &*party.rp_id))
+        .collect();
+
+    assert_eq!(
+        parties,
+        vec![
+            ("https://app.example.com", "example.com"),
+            ("https://admin.example.com", "admin.example.com"),
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "ORIGIN_FRONTEND lists https://app.example.com twice")]
+fn test_parse_rejects_duplicate_origins() {
+    OriginConfig::parse(
+        "https://app.example.com,https://app.example.com=app.example.com",
+        "example.com",
+    );
+}
+
+#[tokio::test]
+async fn test_every_frontend_passes_cors() {
+    let config = OriginConfig::parse(&format!("{},{}", FRONTEND, CONSOLE), "example.com");
+    let router = Router::new()
+        .route("/auth/me", get(|| async { "ok" }))
+        .route("/admin/audit", get(|| async { "ok" }))
+        .layer(config.create_cors_layer(&CorsConfig::default()));
+
+    for path in ["/auth/me", "/admin/audit"] {
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(path)
+                    .header(header::ORIGIN, CONSOLE)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(CONSOLE)
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use crate::config::{
+    DbConfig, StatementCacheConfig,
+    postgres::{DbRegionConfig, DbReplicaConfig, channel_name, parse_regions},
+};
+
+fn db_config(replica: Option<DbReplicaConfig>) -> DbConfig {
+    DbConfig {
+        host: "primary".into(),
+        port: 5432,
+        user: "app".into(),
+        password: "secret".into(),
+        dbname: "server_db".into(),
+        max_size: 4,
+        connection_timeout: Duration::from_secs(5),
+        wait_timeout: Duration::from_secs(5),
+        recycle_timeout: Duration::from_secs(5),
+        run_migrations: true,
+        migration_user: "owner".into(),
+        migration_password: "owner".into(),
+        tls: None,
+        statement_cache: StatementCacheConfig::default(),
+        replica,
+        regions: Vec::new(),
+    }
+}
+
+#[test]
+fn test_channel_name_accepts_identifiers() {
+    assert_eq!(
+        &*channel_name("KEY", String::from("prepared_cache_flush")),
+        "prepared_cache_flush"
+    );
+    assert_eq!(&*channel_name("KEY", String::from("_v2")), "_v2");
+}
+
+#[test]
+#[should_panic(expected = "KEY must be a lowercase identifier")]
+fn test_channel_name_rejects_sql() {
+    channel_name("KEY", String::from("flush; DROP TABLE users"));
+}
+
+#[test]
+#[should_panic(expected = "KEY must be a lowercase identifier")]
+fn test_channel_name_rejects_leading_digit() {
+    channel_name("KEY", String::from("1cache"));
+}
+
+#[test]
+#[should_panic(expected = "KEY must be a lowercase identifier")]
+fn test_channel_name_rejects_long_names() {
+    channel_name("KEY", "a".repeat(64));
+}
+
+#[test]
+fn test_for_replica_is_none_without_replica() {
+    assert!(db_config(None).for_replica().is_none());
+}
+
+#[test]
+fn test_for_replica_points_at_replica() {
+    let config = db_config(Some(DbReplicaConfig {
+        host: "replica".into(),
+        port: 5433,
+    }));
+
+    let replica = config.for_replica().unwrap();
+
+    assert_eq!(&*replica.host, "replica");
+    assert_eq!(replica.port, 5433);
+    assert_eq!(&*replica.user, "app");
+    assert_eq!(&*replica.dbname, "server_db");
+    assert!(!replica.run_migrations);
+    assert!(replica.replica.is_none());
+}
+
+#[test]
+fn test_parse_regions_defaults_to_primary_port() {
+    let regions = parse_regions("eu=db.eu.internal, us=db.us.internal:6432", 5432);
+
+    assert_eq!(
+        regions,
+        vec![
+            DbRegionConfig {
+                name: "eu".into(),
+                host: "db.eu.internal".into(),
+                port: 5432,
+            },
+            DbRegionConfig {
+                name: "us".into(),
+                host: "db.us.internal".into(),
+                port: 6432,
+            },
+        ]
+    );
+    assert!(parse_regions("", 5432).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "DB_REGIONS lists eu twice")]
+fn test_parse_regions_rejects_duplicates() {
+    parse_regions("eu=db1,eu=db2", 5432);
+}
+
+#[test]
+#[should_panic(expected = "DB_REGIONS has an invalid port for eu")]
+fn test_parse_regions_rejects_invalid_port() {
+    parse_regions("eu=db:port", 5432);
+}
+
+#[test]
+fn test_for_region_keeps_credentials_and_migrations() {
+    let mut config = db_config(Some(DbReplicaConfig {
+        host: "replica".into(),
+        port: 5433,
+    }));
+    config.regions = parse_regions("eu=db.eu.internal:6432", 5432);
+
+    let region = config.for_region(&config.regions[0]);
+
+    assert_eq!(&*region.host, "db.eu.internal");
+    assert_eq!(region.port, 6432);
+    assert_eq!(&*region.user, "app");
+    assert!(region.run_migrations);
+    assert!(region.replica.is_none());
+    assert!(region.regions.is_empty());
+}
@@ -0,0 +1,37 @@
+use crate::config::postgres_tls::{DbTlsConfig, SslMode};
+
+fn tls(mode: SslMode, root_cert: Option<&str>) -> DbTlsConfig {
+    DbTlsConfig {
+        mode,
+        root_cert: root_cert.map(str::to_owned),
+        client_cert: None,
+    }
+}
+
+#[test]
+fn test_ssl_modes_parse() {
+    assert_eq!("disable".parse(), Ok(SslMode::Disable));
+    assert_eq!("require".parse(), Ok(SslMode::Require));
+    assert_eq!("verify-ca".parse(), Ok(SslMode::VerifyCa));
+    assert_eq!("verify-full".parse(), Ok(SslMode::VerifyFull));
+}
+
+#[test]
+fn test_fallback_modes_are_rejected() {
+    // `prefer` and `allow` silently drop to plaintext, which defeats the point.
+    assert!("prefer".parse::<SslMode>().is_err());
+    assert!("allow".parse::<SslMode>().is_err());
+}
+
+#[test]
+fn test_connectors_build_with_default_roots() {
+    tls(SslMode::Require, None).connector();
+    tls(SslMode::VerifyCa, None).connector();
+    tls(SslMode::VerifyFull, None).connector();
+}
+
+#[test]
+#[should_panic(expected = "Failed to read certificates")]
+fn test_missing_root_cert_fails_fast() {
+    tls(SslMode::VerifyFull, Some("/nonexistent/ca.pem")).connector();
+}
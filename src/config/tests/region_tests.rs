@@ -0,0 +1,57 @@
+use crate::{
+    app::{AppError, ErrorCode},
+    config::{OriginConfig, RegionConfig, postgres::parse_regions},
+};
+
+const SHOP: &str = "https://shop.example.com";
+const BLOG: &str = "https://blog.example.com";
+
+fn region_config() -> RegionConfig {
+    RegionConfig::parse(
+        "https://shop.example.com/=eu",
+        &OriginConfig::parse(&format!("{},{}", SHOP, BLOG), "example.com"),
+        &parse_regions("eu=db.eu.internal,us=db.us.internal", 5432),
+    )
+}
+
+#[test]
+fn test_mapped_origin_wins_over_header() {
+    let config = region_config();
+
+    assert_eq!(config.resolve(Some(SHOP), Some("us")).unwrap(), Some("eu"));
+    assert_eq!(config.resolve(Some(BLOG), Some("us")).unwrap(), Some("us"));
+    assert_eq!(config.resolve(Some(BLOG), None).unwrap(), None);
+    assert_eq!(config.resolve(None, None).unwrap(), None);
+}
+
+#[test]
+fn test_unknown_region_header_is_rejected() {
+    let error = region_config().resolve(None, Some("apac")).unwrap_err();
+
+    assert!(matches!(error.kind(), AppError::BadRequest(_)));
+    assert_eq!(error.code(), ErrorCode::UnknownRegion);
+}
+
+#[test]
+#[should_panic(
+    expected = "REGION_ORIGINS maps https://shop.example.com to apac, which is not in DB_REGIONS"
+)]
+fn test_parse_rejects_region_without_database() {
+    RegionConfig::parse(
+        "https://shop.example.com=apac",
+        &OriginConfig::parse(SHOP, "example.com"),
+        &parse_regions("eu=db.eu.internal", 5432),
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "REGION_ORIGINS maps https://other.example.com, which is not in ORIGIN_FRONTEND"
+)]
+fn test_parse_rejects_origin_outside_frontends() {
+    RegionConfig::parse(
+        "https://other.example.com=eu",
+        &OriginConfig::parse(SHOP, "example.com"),
+        &parse_regions("eu=db.eu.internal", 5432),
+    );
+}
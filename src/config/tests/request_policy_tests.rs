@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use crate::config::request_policy::RequestPolicyConfig;
+
+#[test]
+fn test_routes_get_their_class_policy() {
+    let config = RequestPolicyConfig::default();
+
+    assert_eq!(config.for_path("/auth/login/begin"), config.auth);
+    assert_eq!(config.for_path("/admin/audit"), config.admin);
+    assert_eq!(config.for_path("/healthz"), config.health);
+    assert_eq!(config.for_path("/readyz"), config.health);
+    assert_eq!(config.for_path("/.well-known/jwks.json"), config.default);
+}
+
+#[test]
+fn test_health_prefix_does_not_match_other_routes() {
+    let config = RequestPolicyConfig::default();
+
+    assert_eq!(config.for_path("/healthz-extra"), config.default);
+    assert_eq!(config.for_path("/authz"), config.default);
+}
+
+#[test]
+fn test_default_policies() {
+    let config = RequestPolicyConfig::default();
+
+    assert_eq!(config.auth.timeout, Some(Duration::from_secs(5)));
+    assert_eq!(config.health.timeout, Some(Duration::from_secs(1)));
+    assert!(config.auth.max_body_bytes < config.default.max_body_bytes);
+}
+
+#[test]
+fn test_requested_timeout_only_shortens_the_budget() {
+    let config = RequestPolicyConfig::default();
+    let auth = config.auth;
+
+    assert_eq!(config.budget(&auth, None), auth.timeout);
+    assert_eq!(
+        config.budget(&auth, Some(Duration::from_secs(2))),
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(
+        config.budget(&auth, Some(Duration::from_secs(60))),
+        auth.timeout
+    );
+}
+
+#[test]
+fn test_requested_timeout_has_a_floor() {
+    let config = RequestPolicyConfig::default();
+
+    assert_eq!(
+        config.budget(&config.auth, Some(Duration::ZERO)),
+        Some(config.min_requested_timeout)
+    );
+}
+
+#[test]
+fn test_requested_timeout_bounds_routes_without_one() {
+    let config = RequestPolicyConfig::default();
+    let mut unbounded = config.default;
+    unbounded.timeout = None;
+
+    assert_eq!(config.budget(&unbounded, None), None);
+    assert_eq!(
+        config.budget(&unbounded, Some(Duration::from_secs(3))),
+        Some(Duration::from_secs(3))
+    );
+}
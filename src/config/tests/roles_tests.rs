@@ -0,0 +1,27 @@
+use crate::config::RolePolicy;
+
+#[test]
+fn test_default_allows_only_admin_as_privileged() {
+    let policy = RolePolicy::default();
+
+    assert!(policy.is_allowed("admin"));
+    assert!(policy.is_privileged("admin"));
+    assert!(!policy.is_allowed("auditor"));
+    assert!(!policy.invite_only);
+}
+
+#[test]
+fn test_open_roles_are_allowed_but_not_privileged() {
+    let policy = RolePolicy::parse("member, auditor", "admin,ops");
+
+    assert!(policy.is_allowed("auditor"));
+    assert!(!policy.is_privileged("auditor"));
+    assert!(policy.is_privileged("ops"));
+    assert!(!policy.is_allowed("root"));
+}
+
+#[test]
+#[should_panic(expected = "both REGISTRATION_ROLES and PRIVILEGED_ROLES")]
+fn test_role_listed_twice_panics() {
+    RolePolicy::parse("admin", "admin");
+}
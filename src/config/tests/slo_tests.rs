@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use crate::config::slo::{LatencyObjective, SloConfig, parse_availability, parse_latency};
+
+#[test]
+fn test_availability_targets_become_ratios() {
+    let parsed = parse_availability("/auth/login/finish=99.9, /auth/refresh=99.95,");
+
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].0, "/auth/login/finish");
+    assert!((parsed[0].1 - 0.999).abs() < 1e-9);
+    assert_eq!(parsed[1].0, "/auth/refresh");
+    assert!((parsed[1].1 - 0.9995).abs() < 1e-9);
+}
+
+#[test]
+fn test_latency_has_threshold_and_target() {
+    let parsed = parse_latency("/admin/actions/{name}=500@99");
+
+    assert_eq!(
+        parsed,
+        vec![(
+            String::from("/admin/actions/{name}"),
+            LatencyObjective {
+                threshold: Duration::from_millis(500),
+                target: 0.99,
+            }
+        )]
+    );
+}
+
+#[test]
+fn test_objectives_of_a_route_are_merged() {
+    let config = SloConfig::new(
+        parse_availability("/auth/refresh=99.9"),
+        parse_latency("/auth/refresh=200@99,/auth/me=100@95"),
+    );
+
+    assert_eq!(config.routes.len(), 2);
+    assert!(
+        config.routes["/auth/refresh"]
+            .availability
+            .is_some_and(|target| (target - 0.999).abs() < 1e-9)
+    );
+    assert!(config.routes["/auth/refresh"].latency.is_some());
+    assert_eq!(config.routes["/auth/me"].availability, None);
+}
+
+#[test]
+#[should_panic(expected = "between 0 and 100")]
+fn test_full_availability_leaves_no_budget() {
+    parse_availability("/auth/refresh=100");
+}
+
+#[test]
+#[should_panic(expected = "/route=objective")]
+fn test_entry_needs_a_route() {
+    parse_availability("auth=99");
+}
+
+#[test]
+#[should_panic(expected = "milliseconds@percent")]
+fn test_latency_needs_a_target() {
+    parse_latency("/auth/refresh=200");
+}
@@ -0,0 +1,54 @@
+use std::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+
+use crate::config::StartupConfig;
+
+fn config(retry_ms: u64, max_retry_ms: u64) -> StartupConfig {
+    StartupConfig {
+        retry_interval: Duration::from_millis(retry_ms),
+        max_retry_interval: Duration::from_millis(max_retry_ms),
+        fail_after: None,
+    }
+}
+
+#[test]
+fn test_backoff_doubles_up_to_the_cap() {
+    let config = config(1000, 10_000);
+
+    assert_eq!(config.backoff(1), Duration::from_secs(1));
+    assert_eq!(config.backoff(2), Duration::from_secs(2));
+    assert_eq!(config.backoff(4), Duration::from_secs(8));
+    assert_eq!(config.backoff(5), Duration::from_secs(10));
+    assert_eq!(config.backoff(u32::MAX), Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn test_retry_until_connected() {
+    let attempts = AtomicU32::new(0);
+
+    let value = config(1, 5)
+        .retry("Redis", || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0..=2 => Err("connection refused"),
+                _ => Ok(42),
+            }
+        })
+        .await;
+
+    assert_eq!(value, 42);
+    assert_eq!(attempts.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn test_deadline_ends_the_wait() {
+    let config = config(1, 5);
+    let wait = config.retry("Postgres", || async { Err::<(), _>("connection refused") });
+
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), wait)
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,78 @@
+use crate::{
+    app::{AppError, ErrorCode},
+    config::{
+        OriginConfig, TenantConfig,
+        tenant::{DEFAULT_TENANT, is_valid_tenant},
+    },
+};
+
+const SHOP: &str = "https://shop.example.com";
+const BLOG: &str = "https://blog.example.com";
+
+fn tenant_config() -> TenantConfig {
+    TenantConfig::parse(
+        "https://shop.example.com/=shop",
+        "internal, shop",
+        &OriginConfig::parse(&format!("{},{}", SHOP, BLOG), "example.com"),
+    )
+}
+
+#[test]
+fn test_parse_lists_every_tenant_once() {
+    let config = tenant_config();
+    let tenants: Vec<&str> = config.tenants().iter().map(|t| &**t).collect();
+
+    assert_eq!(tenants, vec![DEFAULT_TENANT, "shop", "internal"]);
+}
+
+#[test]
+fn test_mapped_origin_wins_over_header() {
+    let config = tenant_config();
+
+    assert_eq!(
+        config.resolve(Some(SHOP), Some("internal")).unwrap(),
+        "shop"
+    );
+    assert_eq!(config.resolve(Some(BLOG), None).unwrap(), DEFAULT_TENANT);
+    assert_eq!(
+        config.resolve(Some(BLOG), Some("internal")).unwrap(),
+        "internal"
+    );
+    assert_eq!(config.resolve(None, None).unwrap(), DEFAULT_TENANT);
+}
+
+#[test]
+fn test_unknown_tenant_header_is_rejected() {
+    let error = tenant_config().resolve(None, Some("nobody")).unwrap_err();
+
+    assert!(matches!(error.kind(), AppError::BadRequest(_)));
+    assert_eq!(error.code(), ErrorCode::UnknownTenant);
+}
+
+#[test]
+#[should_panic(
+    expected = "TENANT_ORIGINS maps https://other.example.com, which is not in ORIGIN_FRONTEND"
+)]
+fn test_parse_rejects_origin_outside_frontends() {
+    TenantConfig::parse(
+        "https://other.example.com=other",
+        "",
+        &OriginConfig::parse(SHOP, "example.com"),
+    );
+}
+
+#[test]
+#[should_panic(expected = "TENANT_IDS has an invalid tenant Shop")]
+fn test_parse_rejects_invalid_tenant() {
+    TenantConfig::parse("", "Shop", &OriginConfig::parse(SHOP, "example.com"));
+}
+
+#[test]
+fn test_tenant_ids_follow_the_column_check() {
+    assert!(is_valid_tenant("shop"));
+    assert!(is_valid_tenant("0-shop_eu"));
+    assert!(!is_valid_tenant(""));
+    assert!(!is_valid_tenant("-shop"));
+    assert!(!is_valid_tenant("shop.eu"));
+    assert!(!is_valid_tenant(&"a".repeat(64)));
+}
@@ -0,0 +1,43 @@
+use crate::config::username::{UsernamePolicy, parse_reserved};
+
+#[test]
+fn test_reserved_names_are_normalized() {
+    assert_eq!(
+        parse_reserved(" Admin, ｒｏｏｔ,,Support "),
+        vec!["admin", "root", "support"]
+    );
+}
+
+#[test]
+fn test_reserved_matches_any_spelling() {
+    let policy = UsernamePolicy {
+        reserved: parse_reserved("admin,root"),
+        ..UsernamePolicy::default()
+    };
+
+    assert!(policy.is_reserved("ADMIN"));
+    assert!(policy.is_reserved("ｒｏｏｔ"));
+    assert!(!policy.is_reserved("administrator"));
+}
+
+#[test]
+fn test_default_policy_reserves_nothing() {
+    assert!(!UsernamePolicy::default().is_reserved("admin"));
+}
+
+#[test]
+fn test_reserved_matches_look_alikes() {
+    let policy = UsernamePolicy {
+        reserved: parse_reserved("admin"),
+        ..UsernamePolicy::default()
+    };
+
+    // Cyrillic "а" in place of the Latin one.
+    assert!(policy.is_reserved("\u{430}dmin"));
+
+    let lenient = UsernamePolicy {
+        reject_confusables: false,
+        ..policy
+    };
+    assert!(!lenient.is_reserved("\u{430}dmin"));
+}
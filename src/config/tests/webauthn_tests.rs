@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::config::{
+    OriginConfig,
+    webauthn::{
+        ExtensionsConfig, RpBranding, WebAuthnConfig, ceremony_timeout, parse_extensions,
+        parse_rp_names, read_attestation_cas, rp_icon, rp_name, split_pem_certs,
+    },
+};
+
+fn config(rp: RpBranding) -> WebAuthnConfig {
+    WebAuthnConfig {
+        rp,
+        timeout: Duration::from_secs(60),
+        stateless: None,
+        attestation_cas: None,
+        extensions: ExtensionsConfig::default(),
+        max_concurrent_finishes: 0,
+    }
+}
+
+#[test]
+fn test_timeout_within_bounds() {
+    assert_eq!(ceremony_timeout(300_000, None), Duration::from_secs(300));
+    assert_eq!(ceremony_timeout(30_000, None), Duration::from_secs(30));
+    assert_eq!(ceremony_timeout(1_800_000, None), Duration::from_secs(1800));
+}
+
+#[test]
+#[should_panic(expected = "between")]
+fn test_timeout_too_short() {
+    ceremony_timeout(5_000, None);
+}
+
+#[test]
+#[should_panic(expected = "between")]
+fn test_timeout_too_long() {
+    ceremony_timeout(3_600_000, None);
+}
+
+#[test]
+fn test_timeout_up_to_challenge_ttl() {
+    let ttl = Duration::from_secs(600);
+
+    assert_eq!(ceremony_timeout(600_000, Some(ttl)), ttl);
+}
+
+#[test]
+#[should_panic(expected = "WEBAUTHN_CHALLENGE_TTL_SECS")]
+fn test_timeout_longer_than_challenge_ttl() {
+    ceremony_timeout(600_000, Some(Duration::from_secs(300)));
+}
+
+#[test]
+fn test_split_pem_certs() {
+    let bundle = "# Vendor A\n-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\
+                  -----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\ntrailing";
+
+    let certs = split_pem_certs(bundle);
+
+    assert_eq!(certs.len(), 2);
+    assert!(certs[0].contains("AAAA"));
+    assert!(certs[1].starts_with("-----BEGIN CERTIFICATE-----"));
+    assert!(certs[1].ends_with("-----END CERTIFICATE-----"));
+}
+
+#[test]
+#[should_panic(expected = "Failed to read attestation CAs")]
+fn test_missing_attestation_ca_file() {
+    read_attestation_cas("/nonexistent/attestation-cas.pem");
+}
+
+#[test]
+fn test_extensions_parsed_from_list() {
+    assert_eq!(parse_extensions(""), ExtensionsConfig::default());
+    assert_eq!(
+        parse_extensions("prf"),
+        ExtensionsConfig {
+            prf: true,
+            large_blob: false,
+        }
+    );
+    assert_eq!(
+        parse_extensions(" large_blob , prf "),
+        ExtensionsConfig {
+            prf: true,
+            large_blob: true,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "WEBAUTHN_EXTENSIONS")]
+fn test_unknown_extension() {
+    parse_extensions("prf,cred_blob");
+}
+
+#[test]
+fn test_relying_party_follows_the_origin() {
+    let origins = OriginConfig::parse(
+        "https://app.example.com,https://admin.example.com=admin.example.com",
+        "example.com",
+    );
+    let parties = config(RpBranding::new("test")).create_webauthn(&origins);
+    let origin_of = |origin| parties.for_origin(origin).get_allowed_origins()[0].to_string();
+
+    assert_eq!(
+        origin_of(Some("https://admin.example.com")),
+        "https://admin.example.com/"
+    );
+    assert_eq!(
+        origin_of(Some("https://app.example.com")),
+        "https://app.example.com/"
+    );
+    assert_eq!(
+        origin_of(Some("https://evil.example")),
+        "https://app.example.com/"
+    );
+    assert_eq!(origin_of(None), "https://app.example.com/");
+}
+
+#[test]
+fn test_rp_names_are_keyed_by_normalized_origin() {
+    let names = parse_rp_names(
+        " https://Admin.Example.com:443/ = Example Admin ,https://app.example.com=Example",
+    );
+
+    assert_eq!(
+        names,
+        vec![
+            ("https://admin.example.com".into(), "Example Admin".into()),
+            ("https://app.example.com".into(), "Example".into()),
+        ]
+    );
+    assert!(parse_rp_names("").is_empty());
+}
+
+#[test]
+#[should_panic(expected = "origin=name")]
+fn test_rp_name_without_origin() {
+    parse_rp_names("Example");
+}
+
+#[test]
+#[should_panic(expected = "twice")]
+fn test_rp_name_listed_twice() {
+    parse_rp_names("https://app.example.com=A,https://app.example.com/=B");
+}
+
+#[test]
+#[should_panic(expected = "must not be blank")]
+fn test_blank_rp_name() {
+    rp_name("  ", "WEBAUTHN_RP_NAME");
+}
+
+#[test]
+fn test_rp_icon_must_be_https_or_inline_image() {
+    assert_eq!(
+        &*rp_icon("https://cdn.example.com/logo.png"),
+        "https://cdn.example.com/logo.png"
+    );
+    assert_eq!(
+        &*rp_icon("data:image/png;base64,AAAA"),
+        "data:image/png;base64,AAAA"
+    );
+
+    for invalid in [
+        "http://cdn.example.com/logo.png",
+        "logo.png",
+        "data:text/html,x",
+    ] {
+        assert!(
+            std::panic::catch_unwind(|| rp_icon(invalid)).is_err(),
+            "{} must be rejected",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_creation_options_carry_the_origin_rp_name() {
+    let origins = OriginConfig::parse(
+        "https://app.example.com,https://admin.example.com=admin.example.com",
+        "example.com",
+    );
+    let mut rp = RpBranding::new("Example");
+    rp.names = parse_rp_names("https://admin.example.com=Example Admin");
+    let parties = config(rp).create_webauthn(&origins);
+    let rp_name_of = |origin| {
+        let (options, _) = parties
+            .for_origin(Some(origin))
+            .start_passkey_registration(Uuid::new_v4(), "alice", "alice", None)
+            .unwrap();
+        options.public_key.rp.name
+    };
+
+    assert_eq!(rp_name_of("https://admin.example.com"), "Example Admin");
+    assert_eq!(rp_name_of("https://app.example.com"), "Example");
+}
+
+#[test]
+#[should_panic(expected = "not in ORIGIN_FRONTEND")]
+fn test_rp_name_for_unknown_origin() {
+    let origins = OriginConfig::parse("https://app.example.com", "example.com");
+    let mut rp = RpBranding::new("Example");
+    rp.names = parse_rp_names("https://other.example.com=Other");
+    config(rp).create_webauthn(&origins);
+}
@@ -0,0 +1,94 @@
+use regex::Regex;
+
+use crate::{
+    config::env::{env_opt, env_or},
+    utils::{normalize_username, username_skeleton},
+};
+
+// Enforced by the CHECK on users.username as well.
+pub const MIN_USERNAME_CHARS: usize = 3;
+const DEFAULT_MAX_USERNAME_CHARS: usize = 64;
+// Letters and digits of any script, combining marks, and a few separators.
+const DEFAULT_USERNAME_PATTERN: &str = r"^[\p{L}\p{M}\p{N}._@-]+$";
+
+/// What a username may look like. Everything is checked against the
+/// normalized form, the one kept unique in the database, so width and case
+/// variants cannot slip past the pattern or the reserved list.
+#[derive(Debug, Clone)]
+pub struct UsernamePolicy {
+    pub max_chars: usize,
+    pub pattern: Regex,
+    /// Normalized names that cannot be registered.
+    pub reserved: Vec<String>,
+    /// Rejects names mixing scripts, such as Latin with Cyrillic.
+    pub reject_mixed_script: bool,
+    /// Rejects names in another script that read as Latin, and look-alikes
+    /// of reserved names.
+    pub reject_confusables: bool,
+}
+
+impl Default for UsernamePolicy {
+    fn default() -> Self {
+        Self {
+            max_chars: DEFAULT_MAX_USERNAME_CHARS,
+            pattern: Regex::new(DEFAULT_USERNAME_PATTERN).unwrap(),
+            reserved: Vec::new(),
+            reject_mixed_script: true,
+            reject_confusables: true,
+        }
+    }
+}
+
+impl UsernamePolicy {
+    pub fn from_env() -> Self {
+        let max_chars = env_or("USERNAME_MAX_LENGTH", DEFAULT_MAX_USERNAME_CHARS);
+        if max_chars < MIN_USERNAME_CHARS {
+            panic!(
+                "USERNAME_MAX_LENGTH must be at least {}",
+                MIN_USERNAME_CHARS
+            );
+        }
+
+        let pattern = env_opt("USERNAME_PATTERN").map_or_else(
+            || Regex::new(DEFAULT_USERNAME_PATTERN).unwrap(),
+            |pattern| {
+                Regex::new(&pattern)
+                    .unwrap_or_else(|e| panic!("USERNAME_PATTERN is not a valid regex: {}", e))
+            },
+        );
+
+        Self {
+            max_chars,
+            pattern,
+            reserved: env_opt("USERNAME_RESERVED")
+                .map(|value| parse_reserved(&value))
+                .unwrap_or_default(),
+            reject_mixed_script: env_or("USERNAME_REJECT_MIXED_SCRIPT", true),
+            reject_confusables: env_or("USERNAME_REJECT_CONFUSABLES", true),
+        }
+    }
+
+    pub fn is_reserved(&self, username: &str) -> bool {
+        let normalized = normalize_username(username);
+        if self.reserved.contains(&normalized) {
+            return true;
+        }
+
+        self.reject_confusables && {
+            let skeleton = username_skeleton(&normalized);
+            self.reserved
+                .iter()
+                .any(|reserved| username_skeleton(reserved) == skeleton)
+        }
+    }
+}
+
+/// Comma separated, normalized so they match however they are spelled.
+pub fn parse_reserved(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(normalize_username)
+        .collect()
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use url::Url;
+
+use crate::config::env::{env_opt, env_or};
+
+const DEFAULT_TTL_MINUTES: u64 = 24 * 60;
+
+/// Set when registration also needs a verified email. The account stays
+/// pending until both the passkey and the address are confirmed.
+#[derive(Debug, Clone)]
+pub struct EmailVerificationConfig {
+    pub ttl: Duration,
+    /// Frontend page receiving `?username=..&token=..`; without it the email
+    /// carries the bare token.
+    pub link_base_url: Option<Url>,
+}
+
+impl EmailVerificationConfig {
+    pub fn from_env() -> Option<Self> {
+        if !env_or("EMAIL_VERIFICATION_ENABLED", false) {
+            return None;
+        }
+
+        let ttl_minutes: u64 = env_or("EMAIL_VERIFICATION_TTL_MINUTES", DEFAULT_TTL_MINUTES);
+        if ttl_minutes == 0 {
+            panic!("EMAIL_VERIFICATION_TTL_MINUTES must be greater than 0");
+        }
+
+        Some(Self {
+            ttl: Duration::from_secs(ttl_minutes * 60),
+            link_base_url: env_opt("EMAIL_VERIFICATION_LINK_URL").map(|value| {
+                Url::parse(&value)
+                    .unwrap_or_else(|_| panic!("EMAIL_VERIFICATION_LINK_URL is not a valid URL"))
+            }),
+        })
+    }
+
+    pub fn link(&self, username: &str, token: &str) -> Option<String> {
+        let mut url = self.link_base_url.clone()?;
+        url.query_pairs_mut()
+            .append_pair("username", username)
+            .append_pair("token", token);
+        Some(url.into())
+    }
+}
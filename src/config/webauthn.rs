@@ -1,24 +1,330 @@
-use std::env;
+use std::{env, fs, time::Duration};
 
-use webauthn_rs::{Webauthn, WebauthnBuilder};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use url::Url;
+use webauthn_rs::{Webauthn, WebauthnBuilder, prelude::AttestationCaList};
 
-use crate::config::origin::OriginConfig;
+use crate::{
+    app::context::current_origin,
+    config::{
+        env::{env_opt, env_or},
+        origin::OriginConfig,
+    },
+};
+
+const DEFAULT_CHALLENGE_TTL_SECS: u64 = 5 * 60;
+const DEFAULT_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+const MIN_TIMEOUT_MS: u64 = 30 * 1000;
+// Stored sessions expire after 30 minutes; a longer prompt could never finish.
+const MAX_TIMEOUT_MS: u64 = 30 * 60 * 1000;
+const PEM_CERT_END: &str = "-----END CERTIFICATE-----";
+// Authenticators may truncate longer names, CTAP2 keeps at least 64 bytes.
+const MAX_RP_NAME_BYTES: usize = 64;
 
 pub struct WebAuthnConfig {
-    pub rp_name: Box<str>,
+    pub rp: RpBranding,
+    /// How long the browser keeps the authenticator prompt open, sent to
+    /// clients as `timeout` in the creation and request options.
+    pub timeout: Duration,
+    /// Set when ceremony state travels with the client instead of Postgres.
+    pub stateless: Option<StatelessChallengeConfig>,
+    /// Roots that attested credentials must chain to, needed by roles with
+    /// an AAGUID allowlist.
+    pub attestation_cas: Option<AttestationCaList>,
+    pub extensions: ExtensionsConfig,
+    /// Finish steps allowed to verify at once, 0 for no limit. Admins can
+    /// change it at runtime.
+    pub max_concurrent_finishes: u32,
+}
+
+/// How the relying party introduces itself in the OS passkey prompts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpBranding {
+    /// `rp.name`, from `WEBAUTHN_RP_NAME`.
+    pub name: Box<str>,
+    /// Frontends shown under another name, from `WEBAUTHN_RP_NAMES` as
+    /// `origin=name` pairs.
+    pub names: Vec<(Box<str>, Box<str>)>,
+    /// `rp.icon`, from `WEBAUTHN_RP_ICON_URL`. Dropped from the current spec
+    /// and unknown to webauthn-rs, so it is added to the creation options
+    /// as is; browsers that no longer show it ignore it.
+    pub icon: Option<Box<str>>,
+}
+
+/// Extensions clients may request, from `WEBAUTHN_EXTENSIONS`. webauthn-rs
+/// models neither, so the server only forwards their inputs and outputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtensionsConfig {
+    /// `prf`: the authenticator derives secrets from a caller salt, which
+    /// clients use as encryption keys.
+    pub prf: bool,
+    /// `large_blob`: opaque data stored on the authenticator with the
+    /// credential, read or written at login.
+    pub large_blob: bool,
+}
+
+/// Key and lifetime for sealed ceremony state. Every instance must share the
+/// key, or a ceremony started on one cannot finish on another.
+pub struct StatelessChallengeConfig {
+    pub key: [u8; 32],
+    pub ttl: Duration,
 }
 
 impl WebAuthnConfig {
     pub fn from_env() -> Self {
-        let rp_name = env::var("WEBAUTHN_RP_NAME").unwrap().into_boxed_str();
+        let stateless = StatelessChallengeConfig::from_env();
+        let timeout = ceremony_timeout(
+            env_or("WEBAUTHN_TIMEOUT_MS", DEFAULT_TIMEOUT_MS),
+            stateless.as_ref().map(|config| config.ttl),
+        );
+
+        Self {
+            rp: RpBranding::from_env(),
+            timeout,
+            stateless,
+            attestation_cas: env_opt("WEBAUTHN_ATTESTATION_CA_FILE")
+                .map(|path| read_attestation_cas(&path)),
+            extensions: parse_extensions(env_opt("WEBAUTHN_EXTENSIONS").as_deref().unwrap_or("")),
+            max_concurrent_finishes: env_or("WEBAUTHN_MAX_CONCURRENT_FINISHES", 0),
+        }
+    }
+
+    pub fn create_webauthn(&self, origin_config: &OriginConfig) -> RelyingParties {
+        let parties = origin_config
+            .relying_parties
+            .iter()
+            .map(|party| {
+                let webauthn = WebauthnBuilder::new(&party.rp_id, &party.url)
+                    .and_then(|builder| {
+                        builder
+                            .rp_name(self.rp.name_for(&party.origin))
+                            .timeout(self.timeout)
+                            .build()
+                    })
+                    .unwrap_or_else(|e| {
+                        panic!(
+                            "Invalid RP ID {} for origin {}: {}",
+                            party.rp_id, party.origin, e
+                        )
+                    });
+                (party.origin.clone(), webauthn)
+            })
+            .collect();
+
+        if let Some((origin, _)) = self.rp.names.iter().find(|(origin, _)| {
+            !origin_config
+                .relying_parties
+                .iter()
+                .any(|party| party.origin == *origin)
+        }) {
+            panic!(
+                "WEBAUTHN_RP_NAMES names {} which is not in ORIGIN_FRONTEND",
+                origin
+            );
+        }
+
+        RelyingParties {
+            parties,
+            icon: self.rp.icon.clone(),
+        }
+    }
+}
+
+impl RpBranding {
+    /// A single name for every frontend, without an icon.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: rp_name(name, "WEBAUTHN_RP_NAME"),
+            names: Vec::new(),
+            icon: None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let mut branding = Self::new(&env::var("WEBAUTHN_RP_NAME").unwrap());
+        branding.names = parse_rp_names(env_opt("WEBAUTHN_RP_NAMES").as_deref().unwrap_or(""));
+        branding.icon = env_opt("WEBAUTHN_RP_ICON_URL").map(|url| rp_icon(&url));
+        branding
+    }
+
+    /// The name shown for passkeys created from `origin`.
+    pub fn name_for(&self, origin: &str) -> &str {
+        self.names
+            .iter()
+            .find(|(o, _)| **o == *origin)
+            .map_or(&self.name, |(_, name)| name)
+    }
+}
+
+/// A `Webauthn` per frontend origin. Passkeys are scoped to the RP ID, so a
+/// ceremony must start and finish with the same one.
+pub struct RelyingParties {
+    parties: Vec<(Box<str>, Webauthn)>,
+    icon: Option<Box<str>>,
+}
+
+impl RelyingParties {
+    /// The relying party of `origin`, or the first one.
+    pub fn for_origin(&self, origin: Option<&str>) -> &Webauthn {
+        origin
+            .and_then(|origin| self.parties.iter().find(|(o, _)| **o == *origin))
+            .map_or(&self.parties[0].1, |(_, webauthn)| webauthn)
+    }
+
+    /// The relying party of the request being handled.
+    pub fn current(&self) -> &Webauthn {
+        self.for_origin(current_origin().as_deref())
+    }
+
+    /// Icon to add to the creation options.
+    pub fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+}
+
+/// Validates `WEBAUTHN_TIMEOUT_MS`. In stateless mode the prompt must also
+/// close before the sealed challenge expires.
+pub fn ceremony_timeout(timeout_ms: u64, challenge_ttl: Option<Duration>) -> Duration {
+    if !(MIN_TIMEOUT_MS..=MAX_TIMEOUT_MS).contains(&timeout_ms) {
+        panic!(
+            "WEBAUTHN_TIMEOUT_MS must be between {} and {}",
+            MIN_TIMEOUT_MS, MAX_TIMEOUT_MS
+        );
+    }
+
+    let timeout = Duration::from_millis(timeout_ms);
+    if challenge_ttl.is_some_and(|ttl| timeout > ttl) {
+        panic!("WEBAUTHN_TIMEOUT_MS must not exceed WEBAUTHN_CHALLENGE_TTL_SECS");
+    }
+
+    timeout
+}
+
+/// Reads a PEM bundle of authenticator vendor roots. Each root vouches for
+/// any model it signed; the per-role allowlists narrow that down.
+pub fn read_attestation_cas(path: &str) -> AttestationCaList {
+    let bundle = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Failed to read attestation CAs from {}: {}", path, e));
+
+    let mut cas = AttestationCaList::default();
+    for pem in split_pem_certs(&bundle) {
+        let ca = AttestationCaList::try_from(pem.as_bytes())
+            .unwrap_or_else(|e| panic!("Invalid attestation CA in {}: {}", path, e));
+        cas.union(&ca);
+    }
+
+    if cas.is_empty() {
+        panic!("No certificates found in {}", path);
+    }
+
+    cas
+}
+
+/// Parses `WEBAUTHN_RP_NAMES`: comma separated `origin=name` pairs, the
+/// origins normalized the way `ORIGIN_FRONTEND` stores them.
+pub fn parse_rp_names(value: &str) -> Vec<(Box<str>, Box<str>)> {
+    let mut names: Vec<(Box<str>, Box<str>)> = Vec::new();
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (origin, name) = entry.split_once('=').unwrap_or_else(|| {
+            panic!(
+                "WEBAUTHN_RP_NAMES entries must be origin=name, got {}",
+                entry
+            )
+        });
+        let origin: Box<str> = Url::parse(origin.trim())
+            .map(|url| url.origin().ascii_serialization().into())
+            .unwrap_or_else(|e| {
+                panic!("WEBAUTHN_RP_NAMES has an invalid origin {}: {}", origin, e)
+            });
+        if names.iter().any(|(o, _)| *o == origin) {
+            panic!("WEBAUTHN_RP_NAMES lists {} twice", origin);
+        }
+        let name = rp_name(name, "WEBAUTHN_RP_NAMES");
+        names.push((origin, name));
+    }
+    names
+}
+
+/// Validates an RP display name, warning when authenticators may cut it.
+pub fn rp_name(name: &str, var: &str) -> Box<str> {
+    let name = name.trim();
+    if name.is_empty() {
+        panic!("{} must not be blank", var);
+    }
+    if name.chars().any(char::is_control) {
+        panic!("{} must not contain control characters", var);
+    }
+    if name.len() > MAX_RP_NAME_BYTES {
+        tracing::warn!(
+            "{} is longer than {} bytes and may be truncated by authenticators",
+            var,
+            MAX_RP_NAME_BYTES
+        );
+    }
+    name.into()
+}
 
-        Self { rp_name }
+/// Validates `WEBAUTHN_RP_ICON_URL`: an https URL or an inline image.
+pub fn rp_icon(value: &str) -> Box<str> {
+    let value = value.trim();
+    let valid = value.starts_with("data:image/")
+        || Url::parse(value).is_ok_and(|url| url.scheme() == "https" && url.has_host());
+    if !valid {
+        panic!("WEBAUTHN_RP_ICON_URL must be an https URL or a data:image URL");
     }
+    value.into()
+}
+
+/// Parses a comma-separated list of `prf` and `large_blob`.
+pub fn parse_extensions(value: &str) -> ExtensionsConfig {
+    let mut config = ExtensionsConfig::default();
+    for name in value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        match name {
+            "prf" => config.prf = true,
+            "large_blob" => config.large_blob = true,
+            other => panic!(
+                "WEBAUTHN_EXTENSIONS must list prf or large_blob, got {}",
+                other
+            ),
+        }
+    }
+    config
+}
+
+/// Splits a PEM bundle into one string per certificate.
+pub fn split_pem_certs(bundle: &str) -> Vec<String> {
+    bundle
+        .split_inclusive(PEM_CERT_END)
+        .filter(|block| block.ends_with(PEM_CERT_END))
+        .map(|block| block.trim().to_owned())
+        .collect()
+}
+
+impl StatelessChallengeConfig {
+    fn from_env() -> Option<Self> {
+        if !env_or("WEBAUTHN_STATELESS_CHALLENGES", false) {
+            return None;
+        }
+
+        let key = env_opt("WEBAUTHN_CHALLENGE_KEY")
+            .and_then(|value| BASE64_STANDARD.decode(value).ok())
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .unwrap_or_else(|| {
+                panic!("WEBAUTHN_CHALLENGE_KEY must be 32 base64-encoded bytes in stateless mode")
+            });
+        let ttl_secs: u64 = env_or("WEBAUTHN_CHALLENGE_TTL_SECS", DEFAULT_CHALLENGE_TTL_SECS);
 
-    pub fn create_webauthn(&self, origin_config: &OriginConfig) -> Webauthn {
-        let builder =
-            WebauthnBuilder::new(origin_config.rp_id(), origin_config.rp_origin()).unwrap();
+        if ttl_secs == 0 {
+            panic!("WEBAUTHN_CHALLENGE_TTL_SECS must be greater than 0");
+        }
 
-        builder.rp_name(&self.rp_name).build().unwrap()
+        Some(Self {
+            key,
+            ttl: Duration::from_secs(ttl_secs),
+        })
     }
 }
@@ -0,0 +1,50 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    impl_validated_json_request,
+    utils::{Validatable, validate_text},
+};
+
+pub const MAX_REASON_CHARS: usize = 200;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RevokeByAaguidRequest {
+    /// Authenticator model whose passkeys are revoked
+    #[schema(example = "cb69481e-8ff7-4039-93ec-0a2729a154a8")]
+    pub aaguid: Uuid,
+    /// Why, passed on to the affected users, such as an advisory id
+    #[schema(example = "CVE-2024-12345")]
+    pub reason: Option<String>,
+    /// Count the matching passkeys without revoking them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl Validatable for RevokeByAaguidRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        // Authenticators that do not disclose their model all report the
+        // nil AAGUID, so it names no model in particular.
+        if self.aaguid.is_nil() {
+            return Err(AppError::BadRequest(String::from(
+                "The nil AAGUID does not identify an authenticator model",
+            )));
+        }
+
+        if let Some(reason) = &self.reason {
+            validate_text(reason, "Reason")?;
+            if reason.chars().count() > MAX_REASON_CHARS {
+                return Err(AppError::BadRequest(format!(
+                    "Reason must be at most {} characters",
+                    MAX_REASON_CHARS
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_json_request!(RevokeByAaguidRequest);
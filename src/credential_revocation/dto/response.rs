@@ -0,0 +1,31 @@
+use axum::{Json, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct RevokeByAaguidResponse {
+    pub aaguid: Uuid,
+    pub dry_run: bool,
+    /// Passkeys of the model in this tenant.
+    #[schema(example = 340)]
+    pub matched: u64,
+    #[schema(example = 340)]
+    pub revoked: u64,
+    /// Users who held at least one of the passkeys.
+    #[schema(example = 310)]
+    pub users: u64,
+    /// Users left without a passkey, who must recover their account to
+    /// sign in again.
+    #[schema(example = 12)]
+    pub users_without_passkey: u64,
+    /// Users a notification was dispatched to.
+    #[schema(example = 310)]
+    pub notified: u64,
+}
+
+impl IntoResponse for RevokeByAaguidResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    audit::model::AuditContext,
+    auth::permissions::AdminActions,
+    credential_revocation::dto::{RevokeByAaguidRequest, RevokeByAaguidResponse},
+};
+
+/// Revoke the passkeys of an authenticator model
+///
+/// Deletes every passkey registered with the AAGUID, in batches, and notifies
+/// each owner. With `dry_run` only counts them. Requires `admin:actions`.
+#[utoipa::path(
+    post,
+    path = "/admin/credentials/revoke-by-aaguid",
+    tag = "Admin",
+    request_body = RevokeByAaguidRequest,
+    responses(
+        (status = 200, description = "Passkeys revoked or counted", body = RevokeByAaguidResponse),
+        (status = 400, description = "Nil AAGUID or invalid reason", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn revoke_by_aaguid(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: RevokeByAaguidRequest,
+) -> Result<RevokeByAaguidResponse, AppError> {
+    state
+        .credential_revocation_service
+        .revoke_by_aaguid(request, &admin, &ctx)
+        .await
+}
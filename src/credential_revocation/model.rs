@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
+use uuid::Uuid;
+
+use crate::{app::AppError, utils::FromRow};
+
+/// How many credentials and owners an AAGUID matches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AaguidMatches {
+    pub credentials: i64,
+    pub users: i64,
+}
+
+impl FromRow for AaguidMatches {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(AaguidMatches {
+            credentials: row.try_get("credentials")?,
+            users: row.try_get("users")?,
+        })
+    }
+}
+
+/// A deleted credential and the account it belonged to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevokedCredential {
+    pub credential_id: Vec<u8>,
+    pub user_id: Uuid,
+    pub username: String,
+}
+
+impl FromRow for RevokedCredential {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(RevokedCredential {
+            credential_id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            username: row.try_get("username")?,
+        })
+    }
+}
+
+/// The credentials one user lost, as their notification lists them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AffectedUser {
+    pub user_id: Uuid,
+    pub username: String,
+    pub credential_ids: Vec<String>,
+}
+
+/// Groups the revoked credentials by owner, so each user is told once
+/// however many batches their passkeys were deleted in.
+pub fn group_by_user(revoked: Vec<RevokedCredential>) -> Vec<AffectedUser> {
+    let mut users: BTreeMap<Uuid, AffectedUser> = BTreeMap::new();
+    for credential in revoked {
+        users
+            .entry(credential.user_id)
+            .or_insert_with(|| AffectedUser {
+                user_id: credential.user_id,
+                username: credential.username,
+                credential_ids: Vec::new(),
+            })
+            .credential_ids
+            .push(BASE64_URL_SAFE_NO_PAD.encode(&credential.credential_id));
+    }
+    users.into_values().collect()
+}
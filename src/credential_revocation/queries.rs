@@ -0,0 +1,31 @@
+/// Credentials only ever match within the current tenant, the last
+/// parameter of every query.
+pub mod credentials {
+    pub const COUNT_BY_AAGUID: &str = "SELECT COUNT(*) AS credentials,
+                COUNT(DISTINCT c.user_id) AS users
+         FROM credentials c
+         WHERE c.aaguid = $1 AND c.tenant_id = $2";
+
+    /// One batch at a time, so a popular model does not lock every
+    /// matching row in a single statement.
+    pub const DELETE_BATCH_BY_AAGUID: &str = "WITH revoked AS (
+             DELETE FROM credentials
+             WHERE id IN (
+                 SELECT id FROM credentials
+                 WHERE aaguid = $1 AND tenant_id = $3
+                 LIMIT $2
+             )
+             RETURNING id, user_id
+         )
+         SELECT r.id, r.user_id, u.username
+         FROM revoked r
+         INNER JOIN users u ON u.id = r.user_id";
+}
+
+pub mod users {
+    pub const SELECT_WITHOUT_CREDENTIALS: &str = "SELECT u.id
+         FROM users u
+         WHERE u.id = ANY($1)
+           AND NOT EXISTS (SELECT 1 FROM credentials c WHERE c.user_id = u.id)
+           AND u.tenant_id = $2";
+}
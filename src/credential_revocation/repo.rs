@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    config::CircuitBreaker,
+    credential_revocation::{
+        model::{AaguidMatches, RevokedCredential},
+        queries,
+        traits::CredentialRevocationRepository,
+    },
+    db_delete, db_select,
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl CredentialRevocationRepository for Repository {
+    async fn count_by_aaguid(&self, aaguid: Uuid) -> Result<AaguidMatches, AppError> {
+        let tenant = current_tenant();
+
+        let row = db_select!("credentials", {
+            self.base
+                .execute_prepared_one(
+                    queries::credentials::COUNT_BY_AAGUID,
+                    &[&aaguid as &(dyn ToSql + Sync), &tenant],
+                )
+                .await
+        })?;
+
+        AaguidMatches::from_row(&row)
+    }
+
+    async fn delete_batch_by_aaguid(
+        &self,
+        aaguid: Uuid,
+        batch_size: i64,
+    ) -> Result<Vec<RevokedCredential>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_delete!("credentials", {
+            self.base
+                .execute_prepared(
+                    queries::credentials::DELETE_BATCH_BY_AAGUID,
+                    &[&aaguid as &(dyn ToSql + Sync), &batch_size, &tenant],
+                )
+                .await
+        })?;
+
+        rows.iter().map(RevokedCredential::from_row).collect()
+    }
+
+    async fn users_without_credentials(&self, user_ids: &[Uuid]) -> Result<Vec<Uuid>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("users", {
+            self.base
+                .execute_prepared(
+                    queries::users::SELECT_WITHOUT_CREDENTIALS,
+                    &[&user_ids as &(dyn ToSql + Sync), &tenant],
+                )
+                .await
+        })?;
+
+        Ok(rows.iter().map(|row| row.get("id")).collect())
+    }
+}
@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::{
+        model::{AuditContext, AuditEntry, AuditEvent},
+        traits::AuditLogger,
+    },
+    auth::jwt::{AccessTokenClaims, claims::JwtClaims},
+    credential_revocation::{
+        dto::{RevokeByAaguidRequest, RevokeByAaguidResponse},
+        model::group_by_user,
+        traits::CredentialRevocationRepository,
+    },
+    notification::{
+        model::{Notification, NotificationEvent},
+        traits::NotificationDispatcher,
+    },
+};
+
+const BATCH_SIZE: i64 = 500;
+
+pub struct CredentialRevocationService<R, N, A>
+where
+    R: CredentialRevocationRepository + 'static,
+    N: NotificationDispatcher + 'static,
+    A: AuditLogger + 'static,
+{
+    revocation_repo: Arc<R>,
+    notifier: Arc<N>,
+    audit_logger: Arc<A>,
+}
+
+impl<R, N, A> CredentialRevocationService<R, N, A>
+where
+    R: CredentialRevocationRepository + 'static,
+    N: NotificationDispatcher + 'static,
+    A: AuditLogger + 'static,
+{
+    pub fn new(revocation_repo: Arc<R>, notifier: Arc<N>, audit_logger: Arc<A>) -> Self {
+        Self {
+            revocation_repo,
+            notifier,
+            audit_logger,
+        }
+    }
+
+    /// Deletes every passkey of the authenticator model and tells each owner
+    /// which ones went. Audited like the operational actions, dry runs
+    /// included.
+    pub async fn revoke_by_aaguid(
+        &self,
+        req: RevokeByAaguidRequest,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<RevokeByAaguidResponse, AppError> {
+        let result = if req.dry_run {
+            self.count(req.aaguid).await
+        } else {
+            self.apply(req.aaguid, req.reason.as_deref()).await
+        };
+
+        let mut details = serde_json::json!({
+            "action": "revoke-credentials-by-aaguid",
+            "aaguid": req.aaguid,
+            "reason": req.reason,
+            "dry_run": req.dry_run,
+        });
+        if let Ok(response) = &result {
+            details["matched"] = serde_json::Value::from(response.matched);
+            details["revoked"] = serde_json::Value::from(response.revoked);
+            details["users"] = serde_json::Value::from(response.users);
+            details["users_without_passkey"] =
+                serde_json::Value::from(response.users_without_passkey);
+        }
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AdminAction,
+                ctx,
+                Some(actor.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*actor.sub())
+            .with_details(details),
+        );
+
+        result
+    }
+
+    async fn count(&self, aaguid: Uuid) -> Result<RevokeByAaguidResponse, AppError> {
+        let matches = self.revocation_repo.count_by_aaguid(aaguid).await?;
+
+        Ok(RevokeByAaguidResponse {
+            aaguid,
+            dry_run: true,
+            matched: matches.credentials.max(0) as u64,
+            users: matches.users.max(0) as u64,
+            ..Default::default()
+        })
+    }
+
+    /// Passkeys registered while this runs are revoked too, as long as a
+    /// batch still finds them.
+    async fn apply(
+        &self,
+        aaguid: Uuid,
+        reason: Option<&str>,
+    ) -> Result<RevokeByAaguidResponse, AppError> {
+        let mut revoked = Vec::new();
+        loop {
+            let batch = self
+                .revocation_repo
+                .delete_batch_by_aaguid(aaguid, BATCH_SIZE)
+                .await?;
+            let full = batch.len() as i64 == BATCH_SIZE;
+            revoked.extend(batch);
+            if !full {
+                break;
+            }
+        }
+
+        let count = revoked.len() as u64;
+        let affected = group_by_user(revoked);
+        let user_ids: Vec<Uuid> = affected.iter().map(|user| user.user_id).collect();
+        let stranded = if user_ids.is_empty() {
+            Vec::new()
+        } else {
+            self.revocation_repo
+                .users_without_credentials(&user_ids)
+                .await?
+        };
+
+        for user in &affected {
+            self.notifier.dispatch(Notification::new(
+                NotificationEvent::CredentialsRevoked,
+                user.user_id,
+                &user.username,
+                serde_json::json!({
+                    "aaguid": aaguid,
+                    "reason": reason,
+                    "credential_ids": user.credential_ids,
+                    "recovery_required": stranded.contains(&user.user_id),
+                }),
+            ));
+        }
+        if count > 0 {
+            tracing::warn!(
+                %aaguid,
+                revoked = count,
+                users = affected.len(),
+                "Revoked passkeys of a compromised authenticator model"
+            );
+        }
+
+        Ok(RevokeByAaguidResponse {
+            aaguid,
+            dry_run: false,
+            matched: count,
+            revoked: count,
+            users: affected.len() as u64,
+            users_without_passkey: stranded.len() as u64,
+            notified: affected.len() as u64,
+        })
+    }
+}
@@ -0,0 +1,34 @@
+use uuid::Uuid;
+
+use crate::credential_revocation::model::{RevokedCredential, group_by_user};
+
+fn revoked(user_id: Uuid, username: &str, credential_id: &[u8]) -> RevokedCredential {
+    RevokedCredential {
+        credential_id: credential_id.to_vec(),
+        user_id,
+        username: username.to_owned(),
+    }
+}
+
+#[test]
+fn test_group_by_user_collects_each_owners_passkeys() {
+    let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let mut users = group_by_user(vec![
+        revoked(alice, "alice", b"a1"),
+        revoked(bob, "bob", b"b1"),
+        revoked(alice, "alice", b"a2"),
+    ]);
+    users.sort_by(|a, b| a.username.cmp(&b.username));
+
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[0].user_id, alice);
+    assert_eq!(users[0].credential_ids, ["YTE", "YTI"]);
+    assert_eq!(users[1].username, "bob");
+    assert_eq!(users[1].credential_ids, ["YjE"]);
+}
+
+#[test]
+fn test_group_by_user_of_nothing() {
+    assert!(group_by_user(Vec::new()).is_empty());
+}
@@ -0,0 +1,40 @@
+use uuid::Uuid;
+
+use crate::{app::AppError, credential_revocation::dto::RevokeByAaguidRequest, utils::Validatable};
+
+fn request(aaguid: Uuid, reason: Option<&str>) -> RevokeByAaguidRequest {
+    RevokeByAaguidRequest {
+        aaguid,
+        reason: reason.map(str::to_owned),
+        dry_run: false,
+    }
+}
+
+#[test]
+fn test_valid_request() {
+    assert!(request(Uuid::new_v4(), None).validate().is_ok());
+    assert!(
+        request(Uuid::new_v4(), Some("CVE-2024-12345"))
+            .validate()
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_nil_aaguid_rejected() {
+    assert!(matches!(
+        request(Uuid::nil(), None).validate(),
+        Err(AppError::BadRequest(_))
+    ));
+}
+
+#[test]
+fn test_blank_or_long_reason_rejected() {
+    let long = "x".repeat(201);
+    for reason in ["  ", long.as_str()] {
+        assert!(matches!(
+            request(Uuid::new_v4(), Some(reason)).validate(),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
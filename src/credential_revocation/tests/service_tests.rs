@@ -0,0 +1,224 @@
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::model::{AuditContext, AuditOutcome},
+    credential_revocation::{
+        dto::RevokeByAaguidRequest,
+        model::{AaguidMatches, RevokedCredential},
+        service::CredentialRevocationService,
+        traits::CredentialRevocationRepository,
+    },
+    notification::model::NotificationEvent,
+    utils::mocks::{MockAuditLogger, MockDispatcher, admin_claims},
+};
+
+/// Credentials by AAGUID, with the owners that keep other passkeys.
+#[derive(Default)]
+struct MockRepository {
+    credentials: Mutex<Vec<(Uuid, RevokedCredential)>>,
+    batch_sizes: Mutex<Vec<usize>>,
+    with_other_passkeys: Vec<Uuid>,
+}
+
+impl CredentialRevocationRepository for MockRepository {
+    async fn count_by_aaguid(&self, aaguid: Uuid) -> Result<AaguidMatches, AppError> {
+        let credentials = self.credentials.lock().unwrap();
+        let mut users: Vec<Uuid> = credentials
+            .iter()
+            .filter(|(model, _)| *model == aaguid)
+            .map(|(_, credential)| credential.user_id)
+            .collect();
+        let matched = users.len() as i64;
+        users.sort();
+        users.dedup();
+
+        Ok(AaguidMatches {
+            credentials: matched,
+            users: users.len() as i64,
+        })
+    }
+
+    async fn delete_batch_by_aaguid(
+        &self,
+        aaguid: Uuid,
+        batch_size: i64,
+    ) -> Result<Vec<RevokedCredential>, AppError> {
+        let mut credentials = self.credentials.lock().unwrap();
+        let mut batch = Vec::new();
+        credentials.retain(|(model, credential)| {
+            if *model == aaguid && (batch.len() as i64) < batch_size {
+                batch.push(credential.clone());
+                false
+            } else {
+                true
+            }
+        });
+        self.batch_sizes.lock().unwrap().push(batch.len());
+        Ok(batch)
+    }
+
+    async fn users_without_credentials(&self, user_ids: &[Uuid]) -> Result<Vec<Uuid>, AppError> {
+        Ok(user_ids
+            .iter()
+            .filter(|id| !self.with_other_passkeys.contains(id))
+            .copied()
+            .collect())
+    }
+}
+
+struct Fixture {
+    service: CredentialRevocationService<MockRepository, MockDispatcher, MockAuditLogger>,
+    repo: Arc<MockRepository>,
+    notifier: Arc<MockDispatcher>,
+    audit: Arc<MockAuditLogger>,
+}
+
+fn fixture(repo: MockRepository) -> Fixture {
+    let repo = Arc::new(repo);
+    let notifier = Arc::new(MockDispatcher::default());
+    let audit = Arc::new(MockAuditLogger::default());
+
+    Fixture {
+        service: CredentialRevocationService::new(
+            Arc::clone(&repo),
+            Arc::clone(&notifier),
+            Arc::clone(&audit),
+        ),
+        repo,
+        notifier,
+        audit,
+    }
+}
+
+fn credentials(aaguid: Uuid, user_id: Uuid, count: usize) -> Vec<(Uuid, RevokedCredential)> {
+    (0..count)
+        .map(|i| {
+            (
+                aaguid,
+                RevokedCredential {
+                    credential_id: i.to_be_bytes().to_vec(),
+                    user_id,
+                    username: user_id.to_string(),
+                },
+            )
+        })
+        .collect()
+}
+
+fn request(aaguid: Uuid, dry_run: bool) -> RevokeByAaguidRequest {
+    RevokeByAaguidRequest {
+        aaguid,
+        reason: Some(String::from("CVE-2024-12345")),
+        dry_run,
+    }
+}
+
+#[tokio::test]
+async fn test_revoke_deletes_in_batches_and_notifies_each_owner_once() {
+    let (compromised, other) = (Uuid::new_v4(), Uuid::new_v4());
+    let (heavy, stranded) = (Uuid::new_v4(), Uuid::new_v4());
+    let mut stored = credentials(compromised, heavy, 600);
+    stored.extend(credentials(compromised, stranded, 1));
+    stored.extend(credentials(other, heavy, 1));
+    let f = fixture(MockRepository {
+        credentials: Mutex::new(stored),
+        with_other_passkeys: vec![heavy],
+        ..Default::default()
+    });
+
+    let response = f
+        .service
+        .revoke_by_aaguid(
+            request(compromised, false),
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!((response.matched, response.revoked), (601, 601));
+    assert_eq!(
+        (
+            response.users,
+            response.users_without_passkey,
+            response.notified
+        ),
+        (2, 1, 2)
+    );
+    assert_eq!(*f.repo.batch_sizes.lock().unwrap(), [500, 101]);
+    assert_eq!(f.repo.credentials.lock().unwrap().len(), 1);
+
+    let sent = f.notifier.sent.lock().unwrap();
+    assert_eq!(sent.len(), 2);
+    assert!(
+        sent.iter()
+            .all(|n| n.event == NotificationEvent::CredentialsRevoked)
+    );
+    let notice = |user_id| {
+        sent.iter()
+            .find(|n| n.user_id == user_id)
+            .map(|n| n.details.clone())
+            .unwrap()
+    };
+    assert_eq!(
+        notice(heavy)["credential_ids"].as_array().unwrap().len(),
+        600
+    );
+    assert_eq!(notice(heavy)["recovery_required"], false);
+    assert_eq!(notice(stranded)["recovery_required"], true);
+    assert_eq!(notice(stranded)["reason"], "CVE-2024-12345");
+
+    let entries = f.audit.entries.lock().unwrap();
+    assert_eq!(entries[0].outcome, AuditOutcome::Success);
+    assert_eq!(entries[0].details["revoked"], 601);
+}
+
+#[tokio::test]
+async fn test_dry_run_only_counts() {
+    let aaguid = Uuid::new_v4();
+    let user = Uuid::new_v4();
+    let f = fixture(MockRepository {
+        credentials: Mutex::new(credentials(aaguid, user, 3)),
+        ..Default::default()
+    });
+
+    let response = f
+        .service
+        .revoke_by_aaguid(
+            request(aaguid, true),
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert!(response.dry_run);
+    assert_eq!(
+        (response.matched, response.users, response.revoked),
+        (3, 1, 0)
+    );
+    assert_eq!(f.repo.credentials.lock().unwrap().len(), 3);
+    assert!(f.notifier.sent.lock().unwrap().is_empty());
+    assert_eq!(f.audit.entries.lock().unwrap()[0].details["dry_run"], true);
+}
+
+#[tokio::test]
+async fn test_unknown_model_revokes_nothing() {
+    let f = fixture(MockRepository::default());
+
+    let response = f
+        .service
+        .revoke_by_aaguid(
+            request(Uuid::new_v4(), false),
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!((response.revoked, response.users), (0, 0));
+    assert!(f.notifier.sent.lock().unwrap().is_empty());
+}
@@ -0,0 +1,26 @@
+use std::future::Future;
+
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    credential_revocation::model::{AaguidMatches, RevokedCredential},
+};
+
+pub trait CredentialRevocationRepository: Send + Sync {
+    fn count_by_aaguid(
+        &self,
+        aaguid: Uuid,
+    ) -> impl Future<Output = Result<AaguidMatches, AppError>> + Send;
+    /// Deletes up to `batch_size` credentials of the model and returns them.
+    fn delete_batch_by_aaguid(
+        &self,
+        aaguid: Uuid,
+        batch_size: i64,
+    ) -> impl Future<Output = Result<Vec<RevokedCredential>, AppError>> + Send;
+    /// Those of `user_ids` left without a single credential.
+    fn users_without_credentials(
+        &self,
+        user_ids: &[Uuid],
+    ) -> impl Future<Output = Result<Vec<Uuid>, AppError>> + Send;
+}
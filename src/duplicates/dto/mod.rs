@@ -0,0 +1,7 @@
+pub(crate) mod request;
+pub(crate) mod response;
+
+pub(crate) use request::MergeDuplicatesRequest;
+pub(crate) use response::{
+    DuplicateGroupEntry, DuplicateReportResponse, DuplicateUserEntry, MergeResponse,
+};
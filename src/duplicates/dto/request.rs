@@ -0,0 +1,50 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{app::AppError, impl_validated_json_request, utils::Validatable};
+
+pub const MAX_MERGED_USERS: usize = 50;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MergeDuplicatesRequest {
+    /// The account that survives under the canonical username
+    pub keep: Uuid,
+    /// Accounts to remove; each must share the canonical username of `keep`.
+    /// Empty only rewrites the stored form of `keep`.
+    #[serde(default)]
+    pub merge: Vec<Uuid>,
+    /// Hand the removed accounts' passkeys to `keep` instead of deleting them
+    #[serde(default)]
+    pub move_credentials: bool,
+}
+
+impl Validatable for MergeDuplicatesRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.merge.len() > MAX_MERGED_USERS {
+            return Err(AppError::BadRequest(format!(
+                "At most {} accounts can be merged at once",
+                MAX_MERGED_USERS
+            )));
+        }
+
+        if self.merge.contains(&self.keep) {
+            return Err(AppError::BadRequest(String::from(
+                "The kept account cannot also be merged",
+            )));
+        }
+
+        let mut ids = self.merge.clone();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() != self.merge.len() {
+            return Err(AppError::BadRequest(String::from(
+                "Accounts to merge must be distinct",
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_json_request!(MergeDuplicatesRequest);
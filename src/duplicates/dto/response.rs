@@ -0,0 +1,80 @@
+use axum::{Json, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::duplicates::model::{DuplicateGroup, StoredIdentity};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DuplicateReportResponse {
+    pub groups: Vec<DuplicateGroupEntry>,
+}
+
+impl IntoResponse for DuplicateReportResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DuplicateGroupEntry {
+    /// The username every account of the group normalizes to.
+    #[schema(example = "john_doe")]
+    pub canonical: String,
+    /// Oldest account first.
+    pub users: Vec<DuplicateUserEntry>,
+}
+
+impl From<DuplicateGroup> for DuplicateGroupEntry {
+    fn from(group: DuplicateGroup) -> Self {
+        Self {
+            canonical: group.canonical,
+            users: group.users.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DuplicateUserEntry {
+    pub id: Uuid,
+    #[schema(example = "John_Doe")]
+    pub username: String,
+    #[schema(example = "active")]
+    pub status: String,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub created_at: String,
+    #[schema(example = 1)]
+    pub credentials: i64,
+    /// The stored normalized form differs from the canonical one, so logins
+    /// cannot find this account.
+    pub stale: bool,
+}
+
+impl From<StoredIdentity> for DuplicateUserEntry {
+    fn from(user: StoredIdentity) -> Self {
+        Self {
+            stale: user.is_stale(),
+            id: user.id,
+            username: user.username,
+            status: user.status,
+            created_at: user.created_at.to_rfc3339(),
+            credentials: user.credentials,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MergeResponse {
+    pub kept: Uuid,
+    #[schema(example = "john_doe")]
+    pub canonical: String,
+    pub merged: Vec<Uuid>,
+    #[schema(example = 1)]
+    pub credentials_moved: u64,
+}
+
+impl IntoResponse for MergeResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
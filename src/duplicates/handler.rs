@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    audit::model::AuditContext,
+    auth::permissions::AdminActions,
+    duplicates::dto::{DuplicateReportResponse, MergeDuplicatesRequest, MergeResponse},
+};
+
+/// List near-duplicate usernames
+///
+/// Groups active accounts whose usernames normalize to the same canonical
+/// form, and accounts whose stored form no longer matches it. Requires
+/// `admin:actions`.
+#[utoipa::path(
+    get,
+    path = "/admin/users/duplicates",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Conflicting accounts", body = DuplicateReportResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn list(
+    _admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+) -> Result<DuplicateReportResponse, AppError> {
+    state.duplicate_service.report().await
+}
+
+/// Merge near-duplicate accounts
+///
+/// Keeps one account under the canonical username and removes the others,
+/// revoking their sessions and optionally moving their passkeys over.
+/// Requires `admin:actions`.
+#[utoipa::path(
+    post,
+    path = "/admin/users/duplicates/merge",
+    tag = "Admin",
+    request_body = MergeDuplicatesRequest,
+    responses(
+        (status = 200, description = "Accounts merged", body = MergeResponse),
+        (status = 400, description = "Accounts do not share a username", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "Unknown or inactive account", body = crate::app::error::ErrorResponse),
+        (status = 409, description = "Another account holds the canonical username", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn merge(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: MergeDuplicatesRequest,
+) -> Result<MergeResponse, AppError> {
+    state.duplicate_service.merge(request, &admin, &ctx).await
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    utils::{FromRow, normalize_username},
+};
+
+/// An active account as stored, with what the application makes of its
+/// username today.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredIdentity {
+    pub id: Uuid,
+    pub username: String,
+    pub normalized_username: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub credentials: i64,
+}
+
+impl StoredIdentity {
+    pub fn canonical(&self) -> String {
+        normalize_username(&self.username)
+    }
+
+    /// Backfilled by the database with a normalization that may not match
+    /// the application's, so lookups by the canonical form miss the row.
+    pub fn is_stale(&self) -> bool {
+        self.normalized_username != self.canonical()
+    }
+}
+
+impl FromRow for StoredIdentity {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(StoredIdentity {
+            id: row.try_get("id")?,
+            username: row.try_get("username")?,
+            normalized_username: row.try_get("normalized_username")?,
+            status: row.try_get("status")?,
+            created_at: row.try_get("created_at")?,
+            credentials: row.try_get("credentials")?,
+        })
+    }
+}
+
+/// Accounts whose usernames share a canonical form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    pub canonical: String,
+    pub users: Vec<StoredIdentity>,
+}
+
+/// Groups that need an admin: more than one account, or a single account
+/// whose stored form is stale. Oldest account first within a group.
+pub fn find_conflicts(users: Vec<StoredIdentity>) -> Vec<DuplicateGroup> {
+    let mut groups: BTreeMap<String, Vec<StoredIdentity>> = BTreeMap::new();
+    for user in users {
+        groups.entry(user.canonical()).or_default().push(user);
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, users)| users.len() > 1 || users.iter().any(StoredIdentity::is_stale))
+        .map(|(canonical, mut users)| {
+            users.sort_by_key(|user| user.created_at);
+            DuplicateGroup { canonical, users }
+        })
+        .collect()
+}
+
+/// One account survives under the canonical form; the others are removed,
+/// handing over their passkeys when asked to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergePlan {
+    pub keep: Uuid,
+    pub canonical: String,
+    pub merge: Vec<Uuid>,
+    pub move_credentials: bool,
+}
@@ -0,0 +1,54 @@
+/// Duplicates only ever share a tenant; the tenant is the last parameter of
+/// every user query.
+pub mod users {
+    pub const SELECT_ACTIVE: &str = "SELECT u.id, u.username, u.normalized_username, u.status,
+                u.created_at,
+                (SELECT COUNT(*) FROM credentials c WHERE c.user_id = u.id) AS credentials
+         FROM users u
+         WHERE u.is_active AND u.tenant_id = $1";
+
+    pub const SELECT_BY_IDS: &str = "SELECT u.id, u.username, u.normalized_username, u.status,
+                u.created_at,
+                (SELECT COUNT(*) FROM credentials c WHERE c.user_id = u.id) AS credentials
+         FROM users u
+         WHERE u.is_active AND u.id = ANY($1) AND u.tenant_id = $2";
+
+    pub const SELECT_HOLDER: &str =
+        "SELECT id FROM users WHERE normalized_username = $1 AND tenant_id = $2";
+
+    /// Same as the account deletion, for every merged account at once.
+    pub const SOFT_DELETE: &str = "UPDATE users
+         SET is_active = FALSE,
+             status = 'deleted',
+             username = 'deleted-' || id::text,
+             normalized_username = 'deleted-' || id::text
+         WHERE id = ANY($1) AND is_active AND tenant_id = $2";
+
+    /// A pending account that received passkeys is enrolled now.
+    pub const SET_CANONICAL: &str = "UPDATE users
+         SET normalized_username = $2,
+             status = CASE
+                 WHEN status = 'pending'
+                  AND EXISTS (SELECT 1 FROM credentials c WHERE c.user_id = users.id)
+                 THEN 'active'
+                 ELSE status
+             END,
+             updated_at = NOW()
+         WHERE id = $1 AND is_active AND tenant_id = $3";
+}
+
+pub mod credentials {
+    /// `trigger_last_used` stamps the moved passkeys, as for any update.
+    pub const MOVE: &str = "UPDATE credentials SET user_id = $1 WHERE user_id = ANY($2)";
+
+    pub const DELETE: &str = "DELETE FROM credentials WHERE user_id = ANY($1)";
+}
+
+pub mod related {
+    pub const DELETE_RECOVERY_CODES: &str = "DELETE FROM recovery_codes WHERE user_id = ANY($1)";
+
+    pub const DELETE_WEBAUTHN_SESSIONS: &str =
+        "DELETE FROM webauthn_sessions WHERE user_id = ANY($1)";
+
+    pub const DELETE_USER_ROLES: &str = "DELETE FROM user_roles WHERE user_id = ANY($1)";
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    config::CircuitBreaker,
+    db_delete, db_select, db_update,
+    duplicates::{
+        model::{MergePlan, StoredIdentity},
+        queries,
+        traits::DuplicateRepository,
+    },
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl DuplicateRepository for Repository {
+    async fn active_users(&self) -> Result<Vec<StoredIdentity>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("users", {
+            self.base
+                .execute_prepared(queries::users::SELECT_ACTIVE, &[&tenant])
+                .await
+        })?;
+
+        rows.iter().map(StoredIdentity::from_row).collect()
+    }
+
+    async fn users_by_id(&self, ids: &[Uuid]) -> Result<Vec<StoredIdentity>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("users", {
+            self.base
+                .execute_prepared(queries::users::SELECT_BY_IDS, &[&ids, &tenant])
+                .await
+        })?;
+
+        rows.iter().map(StoredIdentity::from_row).collect()
+    }
+
+    async fn canonical_holder(&self, canonical: &str) -> Result<Option<Uuid>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("users", {
+            self.base
+                .execute_prepared(queries::users::SELECT_HOLDER, &[&canonical, &tenant])
+                .await
+        })?;
+
+        Ok(rows.first().map(|row| row.get("id")))
+    }
+
+    /// The merged accounts give up their usernames before the kept one takes
+    /// the canonical form, which one of them may have held.
+    async fn merge(&self, plan: &MergePlan) -> Result<u64, AppError> {
+        let plan = plan.clone();
+        let tenant = current_tenant();
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let mut client = db.get().await?;
+                let tx = client.transaction().await?;
+                let merged = plan.merge.as_slice();
+
+                let moved = if plan.move_credentials {
+                    db_update!("credentials", {
+                        tx.execute(queries::credentials::MOVE, &[&plan.keep, &merged])
+                            .await
+                    })?
+                } else {
+                    db_delete!("credentials", {
+                        tx.execute(queries::credentials::DELETE, &[&merged]).await
+                    })?;
+                    0
+                };
+                db_delete!("recovery_codes", {
+                    tx.execute(queries::related::DELETE_RECOVERY_CODES, &[&merged])
+                        .await
+                })?;
+                db_delete!("webauthn_sessions", {
+                    tx.execute(queries::related::DELETE_WEBAUTHN_SESSIONS, &[&merged])
+                        .await
+                })?;
+                db_delete!("user_roles", {
+                    tx.execute(queries::related::DELETE_USER_ROLES, &[&merged])
+                        .await
+                })?;
+                db_update!("users", {
+                    tx.execute(queries::users::SOFT_DELETE, &[&merged, &tenant])
+                        .await
+                })?;
+                let updated = db_update!("users", {
+                    tx.execute(
+                        queries::users::SET_CANONICAL,
+                        &[&plan.keep, &plan.canonical, &tenant],
+                    )
+                    .await
+                })?;
+
+                if updated == 0 {
+                    return Err(AppError::NotFound("User not found".to_string()));
+                }
+
+                tx.commit().await?;
+                Ok(moved)
+            })
+            .await
+    }
+}
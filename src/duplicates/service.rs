@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    audit::{
+        model::{AuditContext, AuditEntry, AuditEvent},
+        traits::AuditLogger,
+    },
+    auth::jwt::{AccessTokenClaims, JwtService, claims::JwtClaims},
+    duplicates::{
+        dto::{DuplicateReportResponse, MergeDuplicatesRequest, MergeResponse},
+        model::{MergePlan, find_conflicts},
+        traits::DuplicateRepository,
+    },
+};
+
+pub struct DuplicateService<R, J, A>
+where
+    R: DuplicateRepository + 'static,
+    J: JwtService + 'static,
+    A: AuditLogger + 'static,
+{
+    duplicate_repo: Arc<R>,
+    jwt_service: Arc<J>,
+    audit_logger: Arc<A>,
+}
+
+impl<R, J, A> DuplicateService<R, J, A>
+where
+    R: DuplicateRepository + 'static,
+    J: JwtService + 'static,
+    A: AuditLogger + 'static,
+{
+    pub fn new(duplicate_repo: Arc<R>, jwt_service: Arc<J>, audit_logger: Arc<A>) -> Self {
+        Self {
+            duplicate_repo,
+            jwt_service,
+            audit_logger,
+        }
+    }
+
+    pub async fn report(&self) -> Result<DuplicateReportResponse, AppError> {
+        let users = self.duplicate_repo.active_users().await?;
+
+        Ok(DuplicateReportResponse {
+            groups: find_conflicts(users).into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// Audited like the operational actions, whether it succeeds or not.
+    pub async fn merge(
+        &self,
+        req: MergeDuplicatesRequest,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<MergeResponse, AppError> {
+        let result = self.apply(&req).await;
+
+        let mut details = serde_json::json!({
+            "action": "merge-duplicates",
+            "keep": req.keep,
+            "merge": req.merge,
+            "move_credentials": req.move_credentials,
+        });
+        if let Ok(response) = &result {
+            details["credentials_moved"] = serde_json::Value::from(response.credentials_moved);
+        }
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AdminAction,
+                ctx,
+                Some(actor.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*actor.sub())
+            .with_details(details),
+        );
+
+        result
+    }
+
+    /// Only accounts that really share the kept username can be merged, so
+    /// a mistyped id cannot fold an unrelated user into another.
+    async fn apply(&self, req: &MergeDuplicatesRequest) -> Result<MergeResponse, AppError> {
+        let mut ids = req.merge.clone();
+        ids.push(req.keep);
+        let users = self.duplicate_repo.users_by_id(&ids).await?;
+
+        let kept = users
+            .iter()
+            .find(|user| user.id == req.keep)
+            .ok_or_else(|| AppError::NotFound(format!("User {} not found", req.keep)))?;
+        let canonical = kept.canonical();
+
+        for id in &req.merge {
+            let user = users
+                .iter()
+                .find(|user| user.id == *id)
+                .ok_or_else(|| AppError::NotFound(format!("User {} not found", id)))?;
+            if user.canonical() != canonical {
+                return Err(AppError::BadRequest(format!(
+                    "User {} does not share the username {}",
+                    id, canonical
+                )));
+            }
+        }
+
+        if let Some(holder) = self.duplicate_repo.canonical_holder(&canonical).await?
+            && !ids.contains(&holder)
+        {
+            return Err(AppError::AlreadyExists(format!(
+                "The username {} belongs to user {}; include it in the merge",
+                canonical, holder
+            ))
+            .with_code(ErrorCode::UsernameTaken)
+            .with_detail("holder", holder.to_string()));
+        }
+
+        // Sessions go first, as for an account deletion: a failure leaves
+        // the accounts intact rather than removed with live sessions.
+        for id in &req.merge {
+            self.jwt_service.revoke_subject(*id).await?;
+        }
+
+        let plan = MergePlan {
+            keep: req.keep,
+            canonical,
+            merge: req.merge.clone(),
+            move_credentials: req.move_credentials,
+        };
+        let credentials_moved = self.duplicate_repo.merge(&plan).await?;
+
+        Ok(MergeResponse {
+            kept: plan.keep,
+            canonical: plan.canonical,
+            merged: plan.merge,
+            credentials_moved,
+        })
+    }
+}
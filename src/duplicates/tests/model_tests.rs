@@ -0,0 +1,45 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::duplicates::model::{StoredIdentity, find_conflicts};
+
+fn user(username: &str, normalized: &str, age_days: i64) -> StoredIdentity {
+    StoredIdentity {
+        id: Uuid::new_v4(),
+        username: username.to_string(),
+        normalized_username: normalized.to_string(),
+        status: String::from("active"),
+        created_at: Utc::now() - Duration::days(age_days),
+        credentials: 1,
+    }
+}
+
+#[test]
+fn test_groups_case_and_width_variants() {
+    let groups = find_conflicts(vec![
+        user("john_doe", "john_doe", 1),
+        user("John_Doe", "John_Doe", 5),
+        user("ｊｏｈｎ_ｄｏｅ", "ｊｏｈｎ_ｄｏｅ", 3),
+        user("alice", "alice", 2),
+    ]);
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].canonical, "john_doe");
+    let names: Vec<&str> = groups[0]
+        .users
+        .iter()
+        .map(|u| u.username.as_str())
+        .collect();
+    assert_eq!(names, ["John_Doe", "ｊｏｈｎ_ｄｏｅ", "john_doe"]);
+}
+
+#[test]
+fn test_single_stale_account_is_reported() {
+    let stale = user("Ärger", "Ärger", 1);
+    assert!(stale.is_stale());
+
+    let groups = find_conflicts(vec![stale, user("bob", "bob", 1)]);
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].canonical, "ärger");
+}
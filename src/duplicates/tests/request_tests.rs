@@ -0,0 +1,32 @@
+use uuid::Uuid;
+
+use crate::{app::AppError, duplicates::dto::MergeDuplicatesRequest, utils::Validatable};
+
+fn request(keep: Uuid, merge: Vec<Uuid>) -> MergeDuplicatesRequest {
+    MergeDuplicatesRequest {
+        keep,
+        merge,
+        move_credentials: false,
+    }
+}
+
+#[test]
+fn test_keep_cannot_be_merged() {
+    let keep = Uuid::new_v4();
+
+    assert!(matches!(
+        request(keep, vec![keep]).validate(),
+        Err(AppError::BadRequest(_))
+    ));
+}
+
+#[test]
+fn test_merged_ids_must_be_distinct() {
+    let other = Uuid::new_v4();
+
+    assert!(matches!(
+        request(Uuid::new_v4(), vec![other, other]).validate(),
+        Err(AppError::BadRequest(_))
+    ));
+    assert!(request(Uuid::new_v4(), vec![other]).validate().is_ok());
+}
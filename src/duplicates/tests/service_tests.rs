@@ -0,0 +1,200 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    audit::model::{AuditContext, AuditOutcome},
+    duplicates::{
+        dto::MergeDuplicatesRequest,
+        model::{MergePlan, StoredIdentity},
+        service::DuplicateService,
+        traits::DuplicateRepository,
+    },
+    utils::mocks::{MockAuditLogger, MockJwt, admin_claims},
+};
+
+#[derive(Default)]
+struct MockRepository {
+    users: Vec<StoredIdentity>,
+    merged: Mutex<Vec<MergePlan>>,
+}
+
+impl DuplicateRepository for MockRepository {
+    async fn active_users(&self) -> Result<Vec<StoredIdentity>, AppError> {
+        Ok(self.users.clone())
+    }
+
+    async fn users_by_id(&self, ids: &[Uuid]) -> Result<Vec<StoredIdentity>, AppError> {
+        Ok(self
+            .users
+            .iter()
+            .filter(|user| ids.contains(&user.id))
+            .cloned()
+            .collect())
+    }
+
+    async fn canonical_holder(&self, canonical: &str) -> Result<Option<Uuid>, AppError> {
+        Ok(self
+            .users
+            .iter()
+            .find(|user| user.normalized_username == canonical)
+            .map(|user| user.id))
+    }
+
+    async fn merge(&self, plan: &MergePlan) -> Result<u64, AppError> {
+        self.merged.lock().unwrap().push(plan.clone());
+        Ok(if plan.move_credentials { 2 } else { 0 })
+    }
+}
+
+struct Fixture {
+    service: DuplicateService<MockRepository, MockJwt, MockAuditLogger>,
+    repo: Arc<MockRepository>,
+    jwt: Arc<MockJwt>,
+    audit: Arc<MockAuditLogger>,
+}
+
+fn user(username: &str, normalized: &str) -> StoredIdentity {
+    StoredIdentity {
+        id: Uuid::new_v4(),
+        username: username.to_string(),
+        normalized_username: normalized.to_string(),
+        status: String::from("active"),
+        created_at: Utc::now(),
+        credentials: 1,
+    }
+}
+
+fn fixture(users: Vec<StoredIdentity>) -> Fixture {
+    let repo = Arc::new(MockRepository {
+        users,
+        ..Default::default()
+    });
+    let jwt = Arc::new(MockJwt::default());
+    let audit = Arc::new(MockAuditLogger::default());
+
+    Fixture {
+        service: DuplicateService::new(Arc::clone(&repo), Arc::clone(&jwt), Arc::clone(&audit)),
+        repo,
+        jwt,
+        audit,
+    }
+}
+
+#[tokio::test]
+async fn test_report_lists_conflicts() {
+    let f = fixture(vec![
+        user("john_doe", "john_doe"),
+        user("JOHN_DOE", "JOHN_DOE"),
+        user("alice", "alice"),
+    ]);
+
+    let report = f.service.report().await.unwrap();
+
+    assert_eq!(report.groups.len(), 1);
+    assert_eq!(report.groups[0].users.len(), 2);
+    assert_eq!(
+        report.groups[0]
+            .users
+            .iter()
+            .filter(|user| user.stale)
+            .count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_merge_revokes_and_moves_passkeys() {
+    let keep = user("John_Doe", "John_Doe");
+    let duplicate = user("john_doe", "john_doe");
+    let (keep_id, duplicate_id) = (keep.id, duplicate.id);
+    let f = fixture(vec![keep, duplicate]);
+
+    let response = f
+        .service
+        .merge(
+            MergeDuplicatesRequest {
+                keep: keep_id,
+                merge: vec![duplicate_id],
+                move_credentials: true,
+            },
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.canonical, "john_doe");
+    assert_eq!(response.credentials_moved, 2);
+    assert_eq!(*f.jwt.revoked.lock().unwrap(), [duplicate_id]);
+    assert_eq!(
+        f.repo.merged.lock().unwrap()[0],
+        MergePlan {
+            keep: keep_id,
+            canonical: String::from("john_doe"),
+            merge: vec![duplicate_id],
+            move_credentials: true,
+        }
+    );
+    let entries = f.audit.entries.lock().unwrap();
+    assert_eq!(entries[0].outcome, AuditOutcome::Success);
+    assert_eq!(entries[0].details["action"], "merge-duplicates");
+}
+
+#[tokio::test]
+async fn test_merge_rejects_unrelated_account() {
+    let keep = user("john_doe", "john_doe");
+    let other = user("jane_doe", "jane_doe");
+    let (keep_id, other_id) = (keep.id, other.id);
+    let f = fixture(vec![keep, other]);
+
+    let result = f
+        .service
+        .merge(
+            MergeDuplicatesRequest {
+                keep: keep_id,
+                merge: vec![other_id],
+                move_credentials: true,
+            },
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await;
+
+    assert!(matches!(result, Err(AppError::BadRequest(_))));
+    assert!(f.jwt.revoked.lock().unwrap().is_empty());
+    assert!(f.repo.merged.lock().unwrap().is_empty());
+    assert_eq!(
+        f.audit.entries.lock().unwrap()[0].outcome,
+        AuditOutcome::Failure
+    );
+}
+
+#[tokio::test]
+async fn test_merge_requires_the_current_holder() {
+    let keep = user("John_Doe", "John_Doe");
+    let holder = user("john_doe", "john_doe");
+    let keep_id = keep.id;
+    let f = fixture(vec![keep, holder]);
+
+    let result = f
+        .service
+        .merge(
+            MergeDuplicatesRequest {
+                keep: keep_id,
+                merge: Vec::new(),
+                move_credentials: false,
+            },
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await;
+
+    let error = result.unwrap_err();
+    assert!(matches!(error.kind(), AppError::AlreadyExists(_)));
+    assert_eq!(error.code(), ErrorCode::UsernameTaken);
+    assert!(error.details().unwrap().contains_key("holder"));
+    assert!(f.repo.merged.lock().unwrap().is_empty());
+}
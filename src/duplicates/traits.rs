@@ -0,0 +1,24 @@
+use std::future::Future;
+
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    duplicates::model::{MergePlan, StoredIdentity},
+};
+
+pub trait DuplicateRepository: Send + Sync {
+    fn active_users(&self) -> impl Future<Output = Result<Vec<StoredIdentity>, AppError>> + Send;
+    /// Inactive or unknown ids are left out.
+    fn users_by_id(
+        &self,
+        ids: &[Uuid],
+    ) -> impl Future<Output = Result<Vec<StoredIdentity>, AppError>> + Send;
+    /// The account, active or not, already storing this normalized form.
+    fn canonical_holder(
+        &self,
+        canonical: &str,
+    ) -> impl Future<Output = Result<Option<Uuid>, AppError>> + Send;
+    /// Applies the plan in one transaction; returns the passkeys moved.
+    fn merge(&self, plan: &MergePlan) -> impl Future<Output = Result<u64, AppError>> + Send;
+}
@@ -0,0 +1,3 @@
+pub(crate) mod response;
+
+pub(crate) use response::ReminderStatsResponse;
@@ -0,0 +1,35 @@
+use axum::{Json, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReminderStatsResponse {
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub generated_at: String,
+    #[schema(example = 3)]
+    pub max_attempts: u32,
+    /// Reminders dispatched across all attempts
+    #[schema(example = 240)]
+    pub sent: i64,
+    /// Distinct users that received at least one reminder
+    #[schema(example = 120)]
+    pub recipients: i64,
+    #[schema(example = 96)]
+    pub opened: i64,
+    /// Reminded users that have since registered a passkey
+    #[schema(example = 60)]
+    pub completed: i64,
+    /// Still pending users that reached the attempt limit
+    #[schema(example = 20)]
+    pub exhausted: i64,
+    #[schema(example = 0.4)]
+    pub open_rate: f64,
+    #[schema(example = 0.5)]
+    pub completion_rate: f64,
+}
+
+impl IntoResponse for ReminderStatsResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
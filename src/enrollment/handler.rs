@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    response::Redirect,
+};
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    auth::permissions::EnrollmentRead,
+    enrollment::dto::ReminderStatsResponse,
+};
+
+/// Follow an enrollment reminder
+///
+/// Records that the reminder was opened and redirects to the enrollment page.
+#[utoipa::path(
+    get,
+    path = "/enrollment/reminders/{token}",
+    tag = "Enrollment",
+    params(("token" = String, Path, description = "Reminder tracking token")),
+    responses(
+        (status = 303, description = "Redirect to the enrollment page"),
+        (status = 400, description = "Malformed token", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "Reminder not found", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn open_reminder(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Redirect, AppError> {
+    let location = state.enrollment_service.open_reminder(&token).await?;
+    Ok(Redirect::to(&location))
+}
+
+/// Enrollment reminder campaign statistics
+///
+/// Reports how many reminders were sent, opened and led to a registered
+/// passkey. Requires `enrollment:read`.
+#[utoipa::path(
+    get,
+    path = "/admin/enrollment/reminders",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Campaign statistics", body = ReminderStatsResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn reminder_stats(
+    _admin: RequirePermission<EnrollmentRead>,
+    State(state): State<Arc<AppState>>,
+) -> Result<ReminderStatsResponse, AppError> {
+    state.enrollment_service.stats().await
+}
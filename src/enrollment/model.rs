@@ -0,0 +1,80 @@
+use url::Url;
+use uuid::Uuid;
+
+use crate::{app::AppError, utils::FromRow};
+
+/// A reminder claimed by the campaign; `token` identifies it in the tracking link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledReminder {
+    pub token: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub attempt: i32,
+}
+
+impl FromRow for ScheduledReminder {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(ScheduledReminder {
+            token: row.try_get("token")?,
+            user_id: row.try_get("user_id")?,
+            username: row.try_get("username")?,
+            attempt: row.try_get("attempt")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReminderCounters {
+    pub sent: i64,
+    pub recipients: i64,
+    pub opened: i64,
+    pub completed: i64,
+    pub exhausted: i64,
+}
+
+impl FromRow for ReminderCounters {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(ReminderCounters {
+            sent: row.try_get("sent")?,
+            recipients: row.try_get("recipients")?,
+            opened: row.try_get("opened")?,
+            completed: row.try_get("completed")?,
+            exhausted: row.try_get("exhausted")?,
+        })
+    }
+}
+
+/// Builds the links placed in reminders. Without a tracking base the link
+/// points straight at the enrollment page and opens are not counted.
+#[derive(Debug, Clone)]
+pub struct ReminderLinks {
+    pub enrollment_url: Url,
+    pub tracking_base_url: Option<Url>,
+}
+
+impl ReminderLinks {
+    pub fn reminder_link(&self, token: Uuid, username: &str) -> String {
+        match &self.tracking_base_url {
+            Some(base) => {
+                let mut url = base.clone();
+                url.path_segments_mut()
+                    .map(|mut segments| {
+                        segments.pop_if_empty().extend([
+                            "enrollment",
+                            "reminders",
+                            &token.to_string(),
+                        ]);
+                    })
+                    .ok();
+                url.to_string()
+            }
+            None => self.enrollment_link(username),
+        }
+    }
+
+    pub fn enrollment_link(&self, username: &str) -> String {
+        let mut url = self.enrollment_url.clone();
+        url.query_pairs_mut().append_pair("username", username);
+        url.to_string()
+    }
+}
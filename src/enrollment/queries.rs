@@ -0,0 +1,47 @@
+pub mod enrollment_reminders {
+    /// Held for the claiming transaction so concurrent instances never
+    /// remind the same user twice.
+    pub const CAMPAIGN_LOCK_KEY: i64 = 0x656e_726f_6c6c;
+
+    pub const TRY_LOCK_CAMPAIGN: &str = "SELECT pg_try_advisory_xact_lock($1) AS acquired";
+
+    pub const CLAIM_DUE: &str = "WITH due AS (
+             SELECT u.id, u.username, COUNT(r.id)::INT AS attempts
+             FROM users u
+             LEFT JOIN enrollment_reminders r ON r.user_id = u.id
+             WHERE u.status = 'pending' AND u.is_active
+             GROUP BY u.id
+             HAVING COUNT(r.id) < $1 AND COALESCE(MAX(r.sent_at), u.created_at) < $2
+             ORDER BY u.created_at
+             LIMIT $3
+         ),
+         inserted AS (
+             INSERT INTO enrollment_reminders (user_id, attempt)
+             SELECT id, attempts + 1 FROM due
+             RETURNING id, user_id, attempt
+         )
+         SELECT i.id AS token, i.user_id, d.username, i.attempt
+         FROM inserted i
+         INNER JOIN due d ON d.id = i.user_id";
+
+    pub const MARK_OPENED: &str = "UPDATE enrollment_reminders r
+         SET opened_at = COALESCE(r.opened_at, NOW())
+         FROM users u
+         WHERE r.id = $1 AND u.id = r.user_id
+         RETURNING u.username";
+
+    pub const SELECT_COUNTERS: &str = "SELECT COUNT(*) AS sent,
+                COUNT(DISTINCT r.user_id) AS recipients,
+                COUNT(*) FILTER (WHERE r.opened_at IS NOT NULL) AS opened,
+                COUNT(DISTINCT r.user_id) FILTER (WHERE u.status = 'active') AS completed,
+                (SELECT COUNT(*) FROM (
+                     SELECT er.user_id
+                     FROM enrollment_reminders er
+                     INNER JOIN users eu ON eu.id = er.user_id
+                     WHERE eu.status = 'pending'
+                     GROUP BY er.user_id
+                     HAVING COUNT(*) >= $1
+                 ) exhausted_users) AS exhausted
+         FROM enrollment_reminders r
+         INNER JOIN users u ON u.id = r.user_id";
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    config::CircuitBreaker,
+    db_insert, db_select, db_update,
+    enrollment::{
+        model::{ReminderCounters, ScheduledReminder},
+        queries,
+        traits::EnrollmentRepository,
+    },
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl EnrollmentRepository for Repository {
+    async fn claim_due_reminders(
+        &self,
+        max_attempts: i64,
+        resend_before: DateTime<Utc>,
+        batch_size: i64,
+    ) -> Result<Vec<ScheduledReminder>, AppError> {
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let mut client = db.get().await?;
+                let tx = client.transaction().await?;
+
+                let lock = tx
+                    .query_one(
+                        queries::enrollment_reminders::TRY_LOCK_CAMPAIGN,
+                        &[&queries::enrollment_reminders::CAMPAIGN_LOCK_KEY],
+                    )
+                    .await?;
+                if !lock.try_get::<_, bool>("acquired")? {
+                    return Ok(Vec::new());
+                }
+
+                let rows = db_insert!("enrollment_reminders", {
+                    tx.query(
+                        queries::enrollment_reminders::CLAIM_DUE,
+                        &[&max_attempts, &resend_before, &batch_size],
+                    )
+                    .await
+                })?;
+
+                tx.commit().await?;
+                rows.iter().map(ScheduledReminder::from_row).collect()
+            })
+            .await
+    }
+
+    async fn mark_opened(&self, token: Uuid) -> Result<String, AppError> {
+        self.base
+            .execute_with_circuit_breaker(move |db| async move {
+                let client = db.get().await?;
+
+                let row = db_update!("enrollment_reminders", {
+                    client
+                        .query_opt(queries::enrollment_reminders::MARK_OPENED, &[&token])
+                        .await
+                })?;
+
+                match row {
+                    Some(row) => Ok(row.try_get("username")?),
+                    None => Err(AppError::NotFound(String::from("Reminder not found"))),
+                }
+            })
+            .await
+    }
+
+    async fn counters(&self, max_attempts: i64) -> Result<ReminderCounters, AppError> {
+        let row = db_select!("enrollment_reminders", {
+            self.base
+                .execute_prepared_one(
+                    queries::enrollment_reminders::SELECT_COUNTERS,
+                    &[&max_attempts as &(dyn tokio_postgres::types::ToSql + Sync)],
+                )
+                .await
+        })?;
+
+        ReminderCounters::from_row(&row)
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    config::EnrollmentConfig,
+    enrollment::{dto::ReminderStatsResponse, traits::EnrollmentRepository},
+    notification::{
+        model::{Notification, NotificationEvent},
+        traits::NotificationDispatcher,
+    },
+};
+
+pub struct EnrollmentService<R, N>
+where
+    R: EnrollmentRepository + 'static,
+    N: NotificationDispatcher + 'static,
+{
+    enrollment_repo: Arc<R>,
+    notifier: Arc<N>,
+    config: EnrollmentConfig,
+}
+
+impl<R, N> EnrollmentService<R, N>
+where
+    R: EnrollmentRepository + 'static,
+    N: NotificationDispatcher + 'static,
+{
+    pub fn new(enrollment_repo: Arc<R>, notifier: Arc<N>, config: EnrollmentConfig) -> Self {
+        Self {
+            enrollment_repo,
+            notifier,
+            config,
+        }
+    }
+
+    /// Runs the campaign on a fixed interval for the lifetime of the process.
+    pub fn spawn_campaign(self: &Arc<Self>) {
+        if !self.config.reminders_enabled {
+            return;
+        }
+
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(service.config.interval);
+            loop {
+                interval.tick().await;
+                match service.run_campaign().await {
+                    Ok(0) => {}
+                    Ok(sent) => tracing::info!(sent, "Enrollment reminders dispatched"),
+                    Err(e) => tracing::error!("Enrollment reminder campaign failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Sends one round of reminders and returns how many were dispatched.
+    pub async fn run_campaign(&self) -> Result<usize, AppError> {
+        let Some(links) = &self.config.links else {
+            return Ok(0);
+        };
+
+        let resend_before = Utc::now() - self.config.resend_after;
+        let reminders = self
+            .enrollment_repo
+            .claim_due_reminders(
+                i64::from(self.config.max_attempts),
+                resend_before,
+                i64::from(self.config.batch_size),
+            )
+            .await?;
+
+        for reminder in &reminders {
+            self.notifier.dispatch(Notification::new(
+                NotificationEvent::EnrollmentReminder,
+                reminder.user_id,
+                &reminder.username,
+                serde_json::json!({
+                    "enrollment_link": links.reminder_link(reminder.token, &reminder.username),
+                    "attempt": reminder.attempt,
+                    "max_attempts": self.config.max_attempts,
+                }),
+            ));
+        }
+
+        Ok(reminders.len())
+    }
+
+    /// Records that a reminder link was followed and returns where to send the user.
+    pub async fn open_reminder(&self, token: &str) -> Result<String, AppError> {
+        let Some(links) = &self.config.links else {
+            return Err(AppError::NotFound(String::from(
+                "Enrollment is not configured",
+            )));
+        };
+
+        let token = Uuid::try_parse(token)?;
+        let username = self.enrollment_repo.mark_opened(token).await?;
+
+        Ok(links.enrollment_link(&username))
+    }
+
+    pub async fn stats(&self) -> Result<ReminderStatsResponse, AppError> {
+        let counters = self
+            .enrollment_repo
+            .counters(i64::from(self.config.max_attempts))
+            .await?;
+
+        Ok(ReminderStatsResponse {
+            generated_at: Utc::now().to_rfc3339(),
+            max_attempts: self.config.max_attempts,
+            sent: counters.sent,
+            recipients: counters.recipients,
+            opened: counters.opened,
+            completed: counters.completed,
+            exhausted: counters.exhausted,
+            open_rate: rate(counters.opened, counters.sent),
+            completion_rate: rate(counters.completed, counters.recipients),
+        })
+    }
+}
+
+fn rate(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
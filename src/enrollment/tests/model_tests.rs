@@ -0,0 +1,48 @@
+use url::Url;
+use uuid::Uuid;
+
+use crate::enrollment::model::ReminderLinks;
+
+fn links(tracking_base_url: Option<&str>) -> ReminderLinks {
+    ReminderLinks {
+        enrollment_url: Url::parse("https://app.example/enroll").unwrap(),
+        tracking_base_url: tracking_base_url.map(|url| Url::parse(url).unwrap()),
+    }
+}
+
+#[test]
+fn test_enrollment_link_encodes_username() {
+    let link = links(None).enrollment_link("john doe");
+
+    assert_eq!(link, "https://app.example/enroll?username=john+doe");
+}
+
+#[test]
+fn test_reminder_link_uses_tracking_base() {
+    let link = links(Some("https://api.example/")).reminder_link(Uuid::nil(), "john_doe");
+
+    assert_eq!(
+        link,
+        format!("https://api.example/enrollment/reminders/{}", Uuid::nil())
+    );
+}
+
+#[test]
+fn test_reminder_link_keeps_tracking_base_path() {
+    let link = links(Some("https://example.com/api")).reminder_link(Uuid::nil(), "john_doe");
+
+    assert_eq!(
+        link,
+        format!(
+            "https://example.com/api/enrollment/reminders/{}",
+            Uuid::nil()
+        )
+    );
+}
+
+#[test]
+fn test_reminder_link_without_tracking_points_to_enrollment() {
+    let link = links(None).reminder_link(Uuid::nil(), "john_doe");
+
+    assert_eq!(link, "https://app.example/enroll?username=john_doe");
+}
@@ -0,0 +1,195 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    config::EnrollmentConfig,
+    enrollment::{
+        model::{ReminderCounters, ReminderLinks, ScheduledReminder},
+        service::EnrollmentService,
+        traits::EnrollmentRepository,
+    },
+    notification::model::NotificationEvent,
+    utils::mocks::MockDispatcher,
+};
+
+#[derive(Default)]
+struct MockRepository {
+    due: Vec<ScheduledReminder>,
+    counters: ReminderCounters,
+    claimed_with: Mutex<Option<(i64, i64)>>,
+}
+
+impl EnrollmentRepository for MockRepository {
+    async fn claim_due_reminders(
+        &self,
+        max_attempts: i64,
+        _: DateTime<Utc>,
+        batch_size: i64,
+    ) -> Result<Vec<ScheduledReminder>, AppError> {
+        *self.claimed_with.lock().unwrap() = Some((max_attempts, batch_size));
+        Ok(self.due.clone())
+    }
+
+    async fn mark_opened(&self, token: Uuid) -> Result<String, AppError> {
+        self.due
+            .iter()
+            .find(|reminder| reminder.token == token)
+            .map(|reminder| reminder.username.clone())
+            .ok_or_else(|| AppError::NotFound(String::from("Reminder not found")))
+    }
+
+    async fn counters(&self, _: i64) -> Result<ReminderCounters, AppError> {
+        Ok(self.counters)
+    }
+}
+
+fn config(links: bool) -> EnrollmentConfig {
+    EnrollmentConfig {
+        reminders_enabled: true,
+        interval: Duration::from_secs(60),
+        resend_after: Duration::from_secs(3600),
+        max_attempts: 3,
+        batch_size: 50,
+        links: links.then(|| ReminderLinks {
+            enrollment_url: Url::parse("https://app.example/enroll").unwrap(),
+            tracking_base_url: Some(Url::parse("https://api.example").unwrap()),
+        }),
+    }
+}
+
+fn reminder(username: &str, attempt: i32) -> ScheduledReminder {
+    ScheduledReminder {
+        token: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        username: username.to_owned(),
+        attempt,
+    }
+}
+
+fn service(
+    repo: MockRepository,
+    links: bool,
+) -> (
+    EnrollmentService<MockRepository, MockDispatcher>,
+    Arc<MockRepository>,
+    Arc<MockDispatcher>,
+) {
+    let repo = Arc::new(repo);
+    let dispatcher = Arc::new(MockDispatcher::default());
+    let service = EnrollmentService::new(Arc::clone(&repo), Arc::clone(&dispatcher), config(links));
+    (service, repo, dispatcher)
+}
+
+#[tokio::test]
+async fn test_run_campaign_dispatches_reminder_per_user() {
+    let due = vec![reminder("alice", 1), reminder("bob", 3)];
+    let token = due[0].token;
+    let (service, repo, dispatcher) = service(
+        MockRepository {
+            due,
+            ..Default::default()
+        },
+        true,
+    );
+
+    let sent = service.run_campaign().await.unwrap();
+
+    assert_eq!(sent, 2);
+    assert_eq!(*repo.claimed_with.lock().unwrap(), Some((3, 50)));
+
+    let notifications = dispatcher.sent.lock().unwrap();
+    assert_eq!(notifications.len(), 2);
+    assert_eq!(
+        notifications[0].event,
+        NotificationEvent::EnrollmentReminder
+    );
+    assert_eq!(notifications[0].username, "alice");
+    assert_eq!(
+        notifications[0].details["enrollment_link"],
+        format!("https://api.example/enrollment/reminders/{}", token)
+    );
+    assert_eq!(notifications[1].details["attempt"], 3);
+    assert_eq!(notifications[1].details["max_attempts"], 3);
+}
+
+#[tokio::test]
+async fn test_run_campaign_without_links_sends_nothing() {
+    let (service, repo, dispatcher) = service(
+        MockRepository {
+            due: vec![reminder("alice", 1)],
+            ..Default::default()
+        },
+        false,
+    );
+
+    assert_eq!(service.run_campaign().await.unwrap(), 0);
+    assert!(repo.claimed_with.lock().unwrap().is_none());
+    assert!(dispatcher.sent.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_open_reminder_redirects_to_enrollment() {
+    let due = vec![reminder("alice", 1)];
+    let token = due[0].token;
+    let (service, _, _) = service(
+        MockRepository {
+            due,
+            ..Default::default()
+        },
+        true,
+    );
+
+    let location = service.open_reminder(&token.to_string()).await.unwrap();
+
+    assert_eq!(location, "https://app.example/enroll?username=alice");
+}
+
+#[tokio::test]
+async fn test_open_reminder_rejects_malformed_token() {
+    let (service, _, _) = service(MockRepository::default(), true);
+
+    let result = service.open_reminder("not-a-token").await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_stats_rates() {
+    let (service, _, _) = service(
+        MockRepository {
+            counters: ReminderCounters {
+                sent: 40,
+                recipients: 20,
+                opened: 10,
+                completed: 5,
+                exhausted: 2,
+            },
+            ..Default::default()
+        },
+        true,
+    );
+
+    let stats = service.stats().await.unwrap();
+
+    assert_eq!(stats.open_rate, 0.25);
+    assert_eq!(stats.completion_rate, 0.25);
+    assert_eq!(stats.exhausted, 2);
+    assert_eq!(stats.max_attempts, 3);
+}
+
+#[tokio::test]
+async fn test_stats_without_reminders() {
+    let (service, _, _) = service(MockRepository::default(), true);
+
+    let stats = service.stats().await.unwrap();
+
+    assert_eq!(stats.open_rate, 0.0);
+    assert_eq!(stats.completion_rate, 0.0);
+}
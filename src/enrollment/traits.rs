@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    enrollment::model::{ReminderCounters, ScheduledReminder},
+};
+
+pub trait EnrollmentRepository: Send + Sync {
+    /// Records the next reminder for up to `batch_size` pending users that
+    /// have had fewer than `max_attempts` reminders and none since
+    /// `resend_before`. Returns nothing when another instance holds the campaign.
+    fn claim_due_reminders(
+        &self,
+        max_attempts: i64,
+        resend_before: DateTime<Utc>,
+        batch_size: i64,
+    ) -> impl Future<Output = Result<Vec<ScheduledReminder>, AppError>> + Send;
+    /// Marks the reminder as opened and returns the username it was sent to.
+    fn mark_opened(&self, token: Uuid) -> impl Future<Output = Result<String, AppError>> + Send;
+    fn counters(
+        &self,
+        max_attempts: i64,
+    ) -> impl Future<Output = Result<ReminderCounters, AppError>> + Send;
+}
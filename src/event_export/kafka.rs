@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use rdkafka::{
+    ClientConfig,
+    producer::{FutureProducer, FutureRecord},
+};
+
+use crate::{
+    app::AppError,
+    event_export::{
+        model::DomainEvent,
+        traits::{EventSink, ExportFuture},
+    },
+};
+
+/// How long librdkafka keeps retrying a message before giving up on it.
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Produces every event to one topic, keyed by user id so a user's events
+/// land on one partition in the order they happened.
+pub struct KafkaSink {
+    producer: FutureProducer,
+    topic: Box<str>,
+}
+
+impl KafkaSink {
+    /// The producer connects lazily; unreachable brokers only show up as
+    /// failed sends.
+    pub fn new(brokers: &str, topic: &str) -> Self {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set(
+                "message.timeout.ms",
+                MESSAGE_TIMEOUT.as_millis().to_string(),
+            )
+            .set("enable.idempotence", "true")
+            .create()
+            .unwrap_or_else(|e| panic!("Invalid Kafka producer config for {}: {}", brokers, e));
+
+        Self {
+            producer,
+            topic: topic.into(),
+        }
+    }
+}
+
+impl EventSink for KafkaSink {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    fn send<'a>(&'a self, event: &'a DomainEvent, payload: Vec<u8>) -> ExportFuture<'a> {
+        Box::pin(async move {
+            let key = event.kind.user_id().to_string();
+            let record = FutureRecord::to(&self.topic).key(&key).payload(&payload);
+            self.producer
+                .send(record, MESSAGE_TIMEOUT)
+                .await
+                .map(|_| ())
+                .map_err(|(e, _)| {
+                    AppError::ServiceUnavailable(format!("Kafka produce failed: {}", e))
+                })
+        })
+    }
+}
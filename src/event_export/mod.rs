@@ -0,0 +1,42 @@
+#[cfg(feature = "event-export-kafka")]
+pub(crate) mod kafka;
+pub(crate) mod model;
+#[cfg(feature = "event-export-nats")]
+pub(crate) mod nats;
+pub(crate) mod service;
+pub(crate) mod traits;
+
+use std::sync::Arc;
+
+pub(crate) use service::EventExporter;
+
+use crate::{
+    config::{EventExportConfig, event_export::EventExportBackend},
+    event_export::traits::EventSink,
+};
+
+/// The sink for the configured backend. Panics when the server was built
+/// without that backend's feature.
+pub async fn connect_sink(config: &EventExportConfig) -> Arc<dyn EventSink> {
+    match &config.backend {
+        #[cfg(feature = "event-export-nats")]
+        EventExportBackend::Nats { url } => {
+            Arc::new(nats::NatsSink::connect(url, &config.topic).await)
+        }
+        #[cfg(not(feature = "event-export-nats"))]
+        EventExportBackend::Nats { .. } => {
+            panic!("EVENT_EXPORT_URL is a NATS URL, but the event-export-nats feature is off")
+        }
+        #[cfg(feature = "event-export-kafka")]
+        EventExportBackend::Kafka { brokers } => {
+            Arc::new(kafka::KafkaSink::new(brokers, &config.topic))
+        }
+        #[cfg(not(feature = "event-export-kafka"))]
+        EventExportBackend::Kafka { .. } => {
+            panic!("EVENT_EXPORT_URL is a Kafka URL, but the event-export-kafka feature is off")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::events::model::RevocationReason;
+
+/// Something downstream systems may want to count or react to. Unlike
+/// `AuthEventKind`, these leave the deployment, so they name the user but
+/// carry no IP address or device details.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEventKind {
+    UserRegistered {
+        user_id: Uuid,
+        username: String,
+    },
+    LoginSucceeded {
+        user_id: Uuid,
+        username: String,
+        client_app: Option<String>,
+        trusted: bool,
+    },
+    TokenRefreshed {
+        user_id: Uuid,
+        client_app: Option<String>,
+    },
+    SessionRevoked {
+        user_id: Uuid,
+        reason: RevocationReason,
+    },
+}
+
+impl DomainEventKind {
+    /// The `type` field of the payload, also the last NATS subject token.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEventKind::UserRegistered { .. } => "user_registered",
+            DomainEventKind::LoginSucceeded { .. } => "login_succeeded",
+            DomainEventKind::TokenRefreshed { .. } => "token_refreshed",
+            DomainEventKind::SessionRevoked { .. } => "session_revoked",
+        }
+    }
+
+    /// The Kafka message key.
+    #[cfg_attr(not(feature = "event-export-kafka"), allow(dead_code))]
+    pub fn user_id(&self) -> Uuid {
+        match self {
+            DomainEventKind::UserRegistered { user_id, .. }
+            | DomainEventKind::LoginSucceeded { user_id, .. }
+            | DomainEventKind::TokenRefreshed { user_id, .. }
+            | DomainEventKind::SessionRevoked { user_id, .. } => *user_id,
+        }
+    }
+}
+
+/// One exported event. `id` lets consumers drop the duplicates an
+/// at-least-once broker may deliver.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainEvent {
+    pub id: Uuid,
+    pub tenant: String,
+    #[serde(flatten)]
+    pub kind: DomainEventKind,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl DomainEvent {
+    pub fn new(tenant: String, kind: DomainEventKind) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            tenant,
+            kind,
+            occurred_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{
+    app::AppError,
+    event_export::{
+        model::DomainEvent,
+        traits::{EventSink, ExportFuture},
+    },
+};
+
+/// Publishes each event on `<prefix>.<event type>`, so consumers can
+/// subscribe to one type or to `<prefix>.>`. Core NATS does not persist
+/// messages; put a JetStream stream on the subjects to keep them.
+pub struct NatsSink {
+    client: async_nats::Client,
+    prefix: Box<str>,
+}
+
+impl NatsSink {
+    /// Returns straight away and keeps connecting in the background, so a
+    /// broker outage at startup only costs the events published meanwhile.
+    pub async fn connect(url: &str, prefix: &str) -> Self {
+        let client = async_nats::ConnectOptions::new()
+            .retry_on_initial_connect()
+            .connect(url)
+            .await
+            .unwrap_or_else(|e| panic!("Invalid EVENT_EXPORT_URL {}: {}", url, e));
+
+        Self {
+            client,
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl EventSink for NatsSink {
+    fn name(&self) -> &'static str {
+        "nats"
+    }
+
+    fn send<'a>(&'a self, event: &'a DomainEvent, payload: Vec<u8>) -> ExportFuture<'a> {
+        Box::pin(async move {
+            let subject = format!("{}.{}", self.prefix, event.kind.name());
+            self.client
+                .publish(subject, payload.into())
+                .await
+                .map_err(|e| AppError::ServiceUnavailable(format!("NATS publish failed: {}", e)))
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::{
+    app::{context::current_tenant, middleware::metrics::track_event_export},
+    event_export::{
+        model::{DomainEvent, DomainEventKind},
+        traits::{DomainEventPublisher, EventSink},
+    },
+};
+
+/// Queues events in memory and hands them to the sink one at a time, in
+/// order. A full queue drops new events rather than slowing requests down,
+/// and events still queued when the instance stops are lost.
+pub struct EventExporter {
+    queue: mpsc::Sender<DomainEvent>,
+}
+
+impl EventExporter {
+    pub fn new(sink: Arc<dyn EventSink>, capacity: usize) -> Self {
+        let (queue, mut receiver) = mpsc::channel::<DomainEvent>(capacity);
+
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                let name = event.kind.name();
+                let payload = match serde_json::to_vec(&event) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        tracing::error!(event = name, "Failed to serialize domain event: {}", e);
+                        track_event_export(name, "failed");
+                        continue;
+                    }
+                };
+                match sink.send(&event, payload).await {
+                    Ok(()) => track_event_export(name, "exported"),
+                    Err(e) => {
+                        tracing::error!(
+                            event = name,
+                            sink = sink.name(),
+                            "Failed to export domain event: {}",
+                            e
+                        );
+                        track_event_export(name, "failed");
+                    }
+                }
+            }
+        });
+
+        Self { queue }
+    }
+}
+
+impl DomainEventPublisher for EventExporter {
+    fn export(&self, kind: DomainEventKind) {
+        let name = kind.name();
+        match self
+            .queue
+            .try_send(DomainEvent::new(current_tenant(), kind))
+        {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!(event = name, "Event export queue full, dropping event");
+                track_event_export(name, "dropped");
+            }
+            Err(TrySendError::Closed(_)) => track_event_export(name, "dropped"),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::{
+    event_export::model::{DomainEvent, DomainEventKind},
+    events::model::RevocationReason,
+};
+
+#[test]
+fn test_event_payload_is_flat() {
+    let user_id = Uuid::new_v4();
+    let event = DomainEvent {
+        id: Uuid::nil(),
+        tenant: String::from("shop"),
+        kind: DomainEventKind::SessionRevoked {
+            user_id,
+            reason: RevocationReason::Logout,
+        },
+        occurred_at: Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap(),
+    };
+
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        serde_json::json!({
+            "id": Uuid::nil(),
+            "tenant": "shop",
+            "type": "session_revoked",
+            "user_id": user_id,
+            "reason": "logout",
+            "occurred_at": "2026-01-02T03:04:05Z",
+        })
+    );
+}
+
+#[test]
+fn test_name_matches_payload_type() {
+    let user_id = Uuid::new_v4();
+    let kinds = [
+        DomainEventKind::UserRegistered {
+            user_id,
+            username: String::from("alice"),
+        },
+        DomainEventKind::LoginSucceeded {
+            user_id,
+            username: String::from("alice"),
+            client_app: Some(String::from("web")),
+            trusted: true,
+        },
+        DomainEventKind::TokenRefreshed {
+            user_id,
+            client_app: None,
+        },
+        DomainEventKind::SessionRevoked {
+            user_id,
+            reason: RevocationReason::AccountDeleted,
+        },
+    ];
+
+    for kind in kinds {
+        assert_eq!(kind.user_id(), user_id);
+        let name = kind.name();
+        let value = serde_json::to_value(DomainEvent::new(String::from("default"), kind)).unwrap();
+        assert_eq!(value["type"], name);
+    }
+}
@@ -0,0 +1,62 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    app::context::scope_tenant,
+    event_export::{
+        EventExporter,
+        model::{DomainEvent, DomainEventKind},
+        traits::{DomainEventPublisher, EventSink, ExportFuture},
+    },
+    events::model::RevocationReason,
+};
+
+struct ChannelSink(mpsc::UnboundedSender<(DomainEvent, Vec<u8>)>);
+
+impl EventSink for ChannelSink {
+    fn name(&self) -> &'static str {
+        "channel"
+    }
+
+    fn send<'a>(&'a self, event: &'a DomainEvent, payload: Vec<u8>) -> ExportFuture<'a> {
+        let _ = self.0.send((event.clone(), payload));
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::test]
+async fn test_exports_in_order_with_request_tenant() {
+    let (sender, mut received) = mpsc::unbounded_channel();
+    let exporter = EventExporter::new(Arc::new(ChannelSink(sender)), 16);
+    let user_id = Uuid::new_v4();
+
+    scope_tenant(String::from("shop"), async {
+        exporter.export(DomainEventKind::TokenRefreshed {
+            user_id,
+            client_app: None,
+        });
+        exporter.export(DomainEventKind::SessionRevoked {
+            user_id,
+            reason: RevocationReason::Logout,
+        });
+    })
+    .await;
+
+    let mut names = Vec::new();
+    for _ in 0..2 {
+        let (event, payload) = tokio::time::timeout(Duration::from_secs(1), received.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.tenant, "shop");
+        assert_eq!(
+            serde_json::from_slice::<DomainEvent>(&payload).unwrap(),
+            event
+        );
+        names.push(event.kind.name());
+    }
+
+    assert_eq!(names, vec!["token_refreshed", "session_revoked"]);
+}
@@ -0,0 +1,22 @@
+use std::{future::Future, pin::Pin};
+
+use crate::{
+    app::AppError,
+    event_export::model::{DomainEvent, DomainEventKind},
+};
+
+pub type ExportFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + 'a>>;
+
+/// A broker events are exported to. Chosen at startup from the config, so
+/// the trait is object safe and returns a boxed future.
+pub trait EventSink: Send + Sync {
+    /// Label used in logs and metrics.
+    fn name(&self) -> &'static str;
+    fn send<'a>(&'a self, event: &'a DomainEvent, payload: Vec<u8>) -> ExportFuture<'a>;
+}
+
+/// Entry point used by other features. Like auditing, exporting never
+/// blocks or fails the request that caused the event.
+pub trait DomainEventPublisher: Send + Sync {
+    fn export(&self, kind: DomainEventKind);
+}
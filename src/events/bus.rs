@@ -0,0 +1,146 @@
+use std::{sync::Arc, time::Duration};
+
+use futures_util::{Stream, StreamExt, stream};
+use redis::{Client, aio::ConnectionManager};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    config::CircuitBreaker,
+    events::{
+        model::{AuthEvent, AuthEventKind, UserEvent},
+        queries,
+        traits::EventPublisher,
+    },
+    redis_publish,
+    utils::BaseRedisRepository,
+};
+
+/// Events buffered per instance before a slow subscriber starts missing them.
+const LOCAL_CAPACITY: usize = 1024;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Fans auth events out to every replica over Redis pub/sub. Each instance
+/// holds one subscription and hands messages to its own streams, which keep
+/// only their user's events. An event published while Redis is unreachable
+/// is dropped, not queued.
+pub struct EventBus {
+    base: Arc<BaseRedisRepository>,
+    client: Client,
+    local: broadcast::Sender<Arc<UserEvent>>,
+}
+
+impl EventBus {
+    pub fn new(
+        client: Client,
+        conn_manager: ConnectionManager,
+        circuit_breaker: Arc<CircuitBreaker>,
+    ) -> Self {
+        Self {
+            base: Arc::new(BaseRedisRepository::new(conn_manager, circuit_breaker)),
+            client,
+            local: broadcast::channel(LOCAL_CAPACITY).0,
+        }
+    }
+
+    /// Events for `user_id` published from now on, on any instance.
+    pub fn subscribe(&self, user_id: Uuid) -> impl Stream<Item = AuthEvent> + Send + use<> {
+        stream::unfold(self.local.subscribe(), move |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(message) if message.user_id == user_id => {
+                        return Some((message.event.clone(), receiver));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(%user_id, skipped, "Event stream fell behind");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    /// Keeps this instance subscribed to the Redis channel, reconnecting
+    /// after a failure. Events published in the meantime are lost.
+    pub fn spawn_listener(&self) {
+        let client = self.client.clone();
+        let local = self.local.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = listen(&client, &local).await {
+                    tracing::error!("Auth event subscription lost: {}", e);
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+}
+
+async fn listen(
+    client: &Client,
+    local: &broadcast::Sender<Arc<UserEvent>>,
+) -> Result<(), redis::RedisError> {
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.subscribe(queries::channels::AUTH_EVENTS).await?;
+    tracing::info!("Subscribed to auth events");
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let payload: String = match message.get_payload() {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!("Unreadable auth event: {}", e);
+                continue;
+            }
+        };
+        match serde_json::from_str::<UserEvent>(&payload) {
+            // No receivers only means nobody is listening on this instance.
+            Ok(event) => {
+                let _ = local.send(Arc::new(event));
+            }
+            Err(e) => tracing::warn!("Malformed auth event: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+impl EventPublisher for EventBus {
+    fn publish(&self, user_id: Uuid, kind: AuthEventKind) {
+        let name = kind.name();
+        let message = UserEvent {
+            user_id,
+            event: AuthEvent::new(kind),
+        };
+        let base = Arc::clone(&self.base);
+
+        tokio::spawn(
+            async move {
+                if let Err(e) = send(&base, &message).await {
+                    tracing::error!(event = name, "Failed to publish auth event: {}", e);
+                }
+            }
+            .in_current_span(),
+        );
+    }
+}
+
+async fn send(base: &BaseRedisRepository, message: &UserEvent) -> Result<(), AppError> {
+    let payload = serde_json::to_string(message)?;
+
+    base.execute_with_circuit_breaker(move |mut conn| async move {
+        let _: i64 = redis_publish!({
+            redis::cmd("PUBLISH")
+                .arg(queries::channels::AUTH_EVENTS)
+                .arg(&payload)
+                .query_async(&mut conn)
+                .await
+        })?;
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,43 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use chrono::Utc;
+use futures_util::{Stream, StreamExt};
+
+use crate::{
+    app::AppState,
+    auth::jwt::{AccessTokenClaims, claims::JwtClaims},
+};
+
+/// Stream account events
+///
+/// Server-sent events for the authenticated user, on whichever instance they
+/// happen: `new_login`, `login_anomaly`, `credential_added`,
+/// `clone_suspected` and `session_revoked`. Each event's data is a JSON object with the same `type`
+/// and an `at` timestamp. The stream ends when the access token expires;
+/// reconnect with a fresh one.
+#[utoipa::path(
+    get,
+    path = "/auth/events",
+    tag = "Authentication",
+    responses(
+        (status = 200, description = "Event stream", content_type = "text/event-stream", body = String),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn stream(
+    claims: AccessTokenClaims,
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let remaining = (claims.exp() - Utc::now().timestamp()).max(0) as u64;
+    let events = state
+        .event_bus
+        .subscribe(*claims.sub())
+        .map(|event| Event::default().event(event.kind.name()).json_data(&event))
+        .take_until(tokio::time::sleep(Duration::from_secs(remaining)));
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
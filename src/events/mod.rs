@@ -0,0 +1,10 @@
+pub(crate) mod bus;
+pub(crate) mod handler;
+pub(crate) mod model;
+mod queries;
+pub(crate) mod traits;
+
+pub(crate) use bus::EventBus;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::login_history::model::LoginAnomaly;
+
+/// Something that happened to an account, streamed to its owner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthEventKind {
+    NewLogin {
+        device_name: Option<String>,
+        trusted: bool,
+    },
+    CredentialAdded {
+        credential_id: String,
+        recovery: bool,
+    },
+    SessionRevoked {
+        reason: RevocationReason,
+    },
+    /// A login's signature counter went backwards, so the passkey may have
+    /// been cloned; it is locked until confirmed.
+    CloneSuspected {
+        credential_id: String,
+    },
+    /// A login from a country, network or device new to the account.
+    LoginAnomaly {
+        reasons: Vec<LoginAnomaly>,
+        ip: Option<String>,
+        country: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationReason {
+    Logout,
+    AccountDeleted,
+}
+
+impl AuthEventKind {
+    /// The SSE `event:` name, matching the `type` field of the payload.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuthEventKind::NewLogin { .. } => "new_login",
+            AuthEventKind::CredentialAdded { .. } => "credential_added",
+            AuthEventKind::SessionRevoked { .. } => "session_revoked",
+            AuthEventKind::CloneSuspected { .. } => "clone_suspected",
+            AuthEventKind::LoginAnomaly { .. } => "login_anomaly",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthEvent {
+    #[serde(flatten)]
+    pub kind: AuthEventKind,
+    pub at: DateTime<Utc>,
+}
+
+impl AuthEvent {
+    pub fn new(kind: AuthEventKind) -> Self {
+        Self {
+            kind,
+            at: Utc::now(),
+        }
+    }
+}
+
+/// What travels over Redis: the event and the user it is addressed to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserEvent {
+    pub user_id: Uuid,
+    pub event: AuthEvent,
+}
@@ -0,0 +1,4 @@
+pub mod channels {
+    /// Every replica publishes and subscribes here; subscribers filter by user.
+    pub const AUTH_EVENTS: &str = "auth_events";
+}
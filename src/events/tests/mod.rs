@@ -0,0 +1,2 @@
+#[cfg(test)]
+mod model_tests;
@@ -0,0 +1,79 @@
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::{
+    events::model::{AuthEvent, AuthEventKind, RevocationReason, UserEvent},
+    login_history::model::LoginAnomaly,
+};
+
+fn event(kind: AuthEventKind) -> AuthEvent {
+    AuthEvent {
+        kind,
+        at: Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap(),
+    }
+}
+
+#[test]
+fn test_event_payload_is_flat() {
+    let value = serde_json::to_value(event(AuthEventKind::NewLogin {
+        device_name: Some(String::from("Laptop")),
+        trusted: true,
+    }))
+    .unwrap();
+
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "type": "new_login",
+            "device_name": "Laptop",
+            "trusted": true,
+            "at": "2026-01-02T03:04:05Z",
+        })
+    );
+}
+
+#[test]
+fn test_name_matches_payload_type() {
+    let kinds = [
+        AuthEventKind::NewLogin {
+            device_name: None,
+            trusted: false,
+        },
+        AuthEventKind::CredentialAdded {
+            credential_id: String::from("abc"),
+            recovery: false,
+        },
+        AuthEventKind::SessionRevoked {
+            reason: RevocationReason::Logout,
+        },
+        AuthEventKind::LoginAnomaly {
+            reasons: vec![LoginAnomaly::Country],
+            ip: None,
+            country: Some(String::from("DE")),
+        },
+    ];
+
+    for kind in kinds {
+        let name = kind.name();
+        let value = serde_json::to_value(event(kind)).unwrap();
+        assert_eq!(value["type"], name);
+    }
+}
+
+#[test]
+fn test_user_event_round_trips() {
+    let message = UserEvent {
+        user_id: Uuid::new_v4(),
+        event: event(AuthEventKind::SessionRevoked {
+            reason: RevocationReason::AccountDeleted,
+        }),
+    };
+
+    let payload = serde_json::to_string(&message).unwrap();
+
+    assert!(payload.contains("\"reason\":\"account_deleted\""));
+    assert_eq!(
+        serde_json::from_str::<UserEvent>(&payload).unwrap(),
+        message
+    );
+}
@@ -0,0 +1,9 @@
+use uuid::Uuid;
+
+use crate::events::model::AuthEventKind;
+
+/// Entry point used by other features. Like auditing, publishing never
+/// blocks or fails the request that caused the event.
+pub trait EventPublisher: Send + Sync {
+    fn publish(&self, user_id: Uuid, kind: AuthEventKind);
+}
@@ -0,0 +1,159 @@
+use serde::Serialize;
+use serde_json::value::RawValue;
+use tonic::{Code, Status, metadata::MetadataValue};
+
+use crate::{
+    app::AppError,
+    auth::{
+        dto::{
+            BeginRequest, BeginResponse, FinishRequest, IntrospectionRequest,
+            IntrospectionResponse, RegistrationResponse, TokenResponse,
+        },
+        jwt::RefreshToken,
+    },
+    grpc::proto,
+    utils::Validatable,
+};
+
+/// Same status semantics as the REST API. The error code rides along in the
+/// `error-code` metadata, so callers branch on it as REST clients do.
+pub fn status(error: AppError) -> Status {
+    let code = match error.status().as_u16() {
+        400 | 413 => Code::InvalidArgument,
+        401 => Code::Unauthenticated,
+        403 => Code::PermissionDenied,
+        404 => Code::NotFound,
+        408 => Code::DeadlineExceeded,
+        409 => Code::AlreadyExists,
+        429 => Code::ResourceExhausted,
+        503 => Code::Unavailable,
+        _ => Code::Internal,
+    };
+    let mut status = Status::new(code, error.to_string());
+    if let Ok(serde_json::Value::String(error_code)) = serde_json::to_value(error.code())
+        && let Ok(value) = error_code.parse()
+    {
+        status.metadata_mut().insert("error-code", value);
+    }
+    if let AppError::TooManyRequests(retry_after) = error.kind() {
+        status
+            .metadata_mut()
+            .insert("retry-after", MetadataValue::from(*retry_after));
+    }
+    status
+}
+
+impl TryFrom<proto::BeginRequest> for BeginRequest {
+    type Error = AppError;
+
+    fn try_from(request: proto::BeginRequest) -> Result<Self, AppError> {
+        let extensions = request
+            .extensions_json
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|_| AppError::BadRequest(String::from("Invalid extensions JSON")))
+            })
+            .transpose()?;
+        let request = Self {
+            username: request.username,
+            role: request.role,
+            invitation: request.invitation,
+            email: request.email,
+            extensions,
+        };
+        request.validate()?;
+        Ok(request)
+    }
+}
+
+impl TryFrom<proto::FinishRequest> for FinishRequest {
+    type Error = AppError;
+
+    fn try_from(request: proto::FinishRequest) -> Result<Self, AppError> {
+        let credentials = RawValue::from_string(request.credentials_json)
+            .map_err(|_| AppError::BadRequest(String::from("Invalid credentials JSON")))?;
+        let request = Self {
+            username: request.username,
+            session_id: request.session_id,
+            credentials,
+            device_name: request.device_name,
+            trusted: request.trusted,
+        };
+        request.validate()?;
+        Ok(request)
+    }
+}
+
+impl TryFrom<proto::IntrospectRequest> for IntrospectionRequest {
+    type Error = AppError;
+
+    fn try_from(request: proto::IntrospectRequest) -> Result<Self, AppError> {
+        let request = Self {
+            token: request.token,
+            token_type_hint: None,
+        };
+        request.validate()?;
+        Ok(request)
+    }
+}
+
+impl From<BeginResponse> for proto::BeginReply {
+    fn from(response: BeginResponse) -> Self {
+        Self {
+            options_json: response.options.get().to_owned(),
+            session_id: response.session_id,
+        }
+    }
+}
+
+impl TryFrom<RegistrationResponse> for proto::RegistrationReply {
+    type Error = AppError;
+
+    fn try_from(response: RegistrationResponse) -> Result<Self, AppError> {
+        Ok(Self {
+            message: response.message,
+            recovery_codes: response.recovery_codes,
+            verification_pending: response.verification_pending,
+            credential_json: to_json(&response.credential)?,
+            extensions_json: response.extensions.as_ref().map(to_json).transpose()?,
+        })
+    }
+}
+
+impl TryFrom<(TokenResponse, RefreshToken)> for proto::TokenReply {
+    type Error = AppError;
+
+    fn try_from(
+        (response, refresh_token): (TokenResponse, RefreshToken),
+    ) -> Result<Self, AppError> {
+        Ok(Self {
+            message: response.message,
+            access_token: response.access_token,
+            refresh_token: refresh_token.value,
+            credential_json: response.credential.as_ref().map(to_json).transpose()?,
+            extensions_json: response.extensions.as_ref().map(to_json).transpose()?,
+        })
+    }
+}
+
+impl From<IntrospectionResponse> for proto::IntrospectReply {
+    fn from(response: IntrospectionResponse) -> Self {
+        Self {
+            active: response.active,
+            sub: response.sub.map(|sub| sub.to_string()),
+            username: response.username,
+            roles: response.roles.unwrap_or_default(),
+            scope: response.scope,
+            iat: response.iat,
+            exp: response.exp,
+            token_type: response.token_type,
+            client_id: response.client_id,
+            iss: response.iss,
+            aud: response.aud,
+        }
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string(value).map_err(|e| AppError::InternalServer(e.to_string()))
+}
@@ -0,0 +1,32 @@
+pub(crate) mod convert;
+pub(crate) mod service;
+
+use std::sync::Arc;
+
+pub(crate) use service::AuthGrpc;
+
+use crate::{
+    app::{AppState, server::shutdown_signal},
+    config::GrpcConfig,
+};
+
+/// Generated from `proto/auth.proto`.
+pub(crate) mod proto {
+    tonic::include_proto!("auth.v1");
+}
+
+/// Serves the gRPC interface until the shutdown signal, next to the REST
+/// API and sharing its state.
+pub async fn serve(
+    state: Arc<AppState>,
+    config: GrpcConfig,
+) -> Result<(), tonic::transport::Error> {
+    tracing::info!("gRPC interface listening on {}", config.addr);
+    tonic::transport::Server::builder()
+        .add_service(proto::auth_server::AuthServer::new(AuthGrpc::new(state)))
+        .serve_with_shutdown(config.addr, shutdown_signal())
+        .await
+}
+
+#[cfg(test)]
+mod tests;
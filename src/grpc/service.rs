@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use tonic::{Request, Response, Status};
+
+use crate::{
+    app::{
+        AppError, AppState, RequestContext,
+        context::{scope_context, scope_request_id},
+        middleware::{auth::UNAUTHORIZED_MESSAGE, context::resolve_context, metrics},
+    },
+    auth::dto::{BeginRequest, FinishRequest, IntrospectionRequest},
+    grpc::{convert::status, proto},
+};
+
+/// The auth operations of the REST handlers, over gRPC. Metadata is read
+/// like request headers, so the tenant, region, origin and request id are
+/// resolved the same way. Maintenance mode and the rate limits of the REST
+/// routes apply too.
+pub struct AuthGrpc {
+    state: Arc<AppState>,
+}
+
+impl AuthGrpc {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Runs `handle` with the context of `request` scoped, as
+    /// `attach_context` does for the router, unless maintenance mode refuses
+    /// `method`.
+    async fn call<T, R, F>(
+        &self,
+        method: &str,
+        request: Request<T>,
+        handle: F,
+    ) -> Result<Response<R>, Status>
+    where
+        F: AsyncFnOnce(&AppState, RequestContext, T) -> Result<R, AppError>,
+    {
+        self.state.maintenance.check(method).map_err(status)?;
+        let headers = request.metadata().clone().into_headers();
+        let ip = request.remote_addr().map(|addr| addr.ip());
+        let context = resolve_context(&self.state, &headers, ip).map_err(status)?;
+        let message = request.into_inner();
+
+        let request_id = context.request_id.clone();
+        let scoped = context.clone();
+        let result = scope_request_id(
+            request_id,
+            scope_context(&scoped, handle(&self.state, context, message)),
+        )
+        .await;
+        result.map(Response::new).map_err(status)
+    }
+}
+
+#[tonic::async_trait]
+impl proto::auth_server::Auth for AuthGrpc {
+    async fn begin_register(
+        &self,
+        request: Request<proto::BeginRequest>,
+    ) -> Result<Response<proto::BeginReply>, Status> {
+        self.call(
+            "/auth.v1.Auth/BeginRegister",
+            request,
+            async |state, context, message| {
+                let request = BeginRequest::try_from(message)?;
+                let username = request.username.clone();
+                state
+                    .rate_limiter
+                    .check_request("/auth/register/begin", context.ip, Some(&username))
+                    .await?;
+                let response = state.auth_service.begin_register(request, None).await;
+                state.track_registration(&username, response.is_ok());
+                response.map(Into::into)
+            },
+        )
+        .await
+    }
+
+    async fn finish_register(
+        &self,
+        request: Request<proto::FinishRequest>,
+    ) -> Result<Response<proto::RegistrationReply>, Status> {
+        self.call(
+            "/auth.v1.Auth/FinishRegister",
+            request,
+            async |state, context, message| {
+                let request = FinishRequest::try_from(message)?;
+                let username = request.username.clone();
+                let response = state
+                    .auth_service
+                    .finish_register(request, &context.audit())
+                    .await;
+                state.track_registration(&username, response.is_ok());
+                response?.try_into()
+            },
+        )
+        .await
+    }
+
+    async fn begin_login(
+        &self,
+        request: Request<proto::BeginRequest>,
+    ) -> Result<Response<proto::BeginReply>, Status> {
+        self.call(
+            "/auth.v1.Auth/BeginLogin",
+            request,
+            async |state, context, message| {
+                let request = BeginRequest::try_from(message)?;
+                let username = request.username.clone();
+                state
+                    .rate_limiter
+                    .check_request("/auth/login/begin", context.ip, Some(&username))
+                    .await?;
+                let response = state.auth_service.begin_login(request).await;
+                state.track_login(&username, response.is_ok());
+                response.map(Into::into)
+            },
+        )
+        .await
+    }
+
+    async fn finish_login(
+        &self,
+        request: Request<proto::FinishRequest>,
+    ) -> Result<Response<proto::TokenReply>, Status> {
+        self.call(
+            "/auth.v1.Auth/FinishLogin",
+            request,
+            async |state, context, message| {
+                let request = FinishRequest::try_from(message)?;
+                let username = request.username.clone();
+                let result = state
+                    .auth_service
+                    .finish_login(request, &context.audit())
+                    .await;
+                state.track_login(&username, result.is_ok());
+                result?.try_into()
+            },
+        )
+        .await
+    }
+
+    async fn refresh(
+        &self,
+        request: Request<proto::RefreshRequest>,
+    ) -> Result<Response<proto::TokenReply>, Status> {
+        self.call(
+            "/auth.v1.Auth/Refresh",
+            request,
+            async |state, context, message| {
+                let result = state
+                    .auth_service
+                    .refresh(&message.refresh_token, &context.audit())
+                    .await;
+                metrics::track_token_operation("refresh", result.is_ok());
+                result?.try_into()
+            },
+        )
+        .await
+    }
+
+    async fn introspect(
+        &self,
+        request: Request<proto::IntrospectRequest>,
+    ) -> Result<Response<proto::IntrospectReply>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        if !self.state.introspection.authorize(&headers) {
+            return Err(status(AppError::Unauthorized(
+                UNAUTHORIZED_MESSAGE.to_string(),
+            )));
+        }
+
+        self.call(
+            "/auth.v1.Auth/Introspect",
+            request,
+            async |state, _, message| {
+                let request = IntrospectionRequest::try_from(message)?;
+                let response = state.auth_service.introspect(&request.token).await;
+                metrics::track_token_operation("introspect", response.is_ok());
+                response.map(Into::into)
+            },
+        )
+        .await
+    }
+}
@@ -0,0 +1,136 @@
+use tonic::Code;
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    auth::dto::{BeginRequest, FinishRequest, IntrospectionResponse},
+    grpc::{convert::status, proto},
+};
+
+fn finish_request(credentials_json: &str) -> proto::FinishRequest {
+    proto::FinishRequest {
+        username: String::from("john_doe"),
+        session_id: String::from("550e8400-e29b-41d4-a716-446655440000"),
+        credentials_json: credentials_json.to_owned(),
+        device_name: Some(String::from("Work laptop")),
+        trusted: true,
+    }
+}
+
+#[test]
+fn test_status_follows_the_http_status() {
+    let cases = [
+        (AppError::BadRequest(String::new()), Code::InvalidArgument),
+        (AppError::Unauthorized(String::new()), Code::Unauthenticated),
+        (AppError::Forbidden(String::new()), Code::PermissionDenied),
+        (AppError::NotFound(String::new()), Code::NotFound),
+        (AppError::AlreadyExists(String::new()), Code::AlreadyExists),
+        (AppError::TooManyRequests(30), Code::ResourceExhausted),
+        (
+            AppError::CircuitBreakerOpen(String::new()),
+            Code::Unavailable,
+        ),
+        (AppError::InternalServer(String::new()), Code::Internal),
+    ];
+
+    for (error, code) in cases {
+        assert_eq!(status(error).code(), code);
+    }
+}
+
+#[test]
+fn test_status_carries_the_error_code() {
+    let error = AppError::Unauthorized(String::from("Refresh token expired"))
+        .with_code(ErrorCode::AuthRefreshTokenMissing);
+
+    let status = status(error);
+
+    assert_eq!(status.message(), "unauthorized: Refresh token expired");
+    assert_eq!(
+        status.metadata().get("error-code").unwrap(),
+        "AUTH_REFRESH_TOKEN_MISSING"
+    );
+}
+
+#[test]
+fn test_rate_limited_status_carries_retry_after() {
+    let status = status(AppError::TooManyRequests(30));
+
+    assert_eq!(status.metadata().get("retry-after").unwrap(), "30");
+}
+
+#[test]
+fn test_begin_request_is_validated() {
+    let request = proto::BeginRequest {
+        username: String::from("x"),
+        role: None,
+        invitation: None,
+        email: None,
+        extensions_json: None,
+    };
+
+    let error = BeginRequest::try_from(request).unwrap_err();
+
+    assert!(matches!(error.kind(), AppError::BadRequest(_)));
+}
+
+#[test]
+fn test_begin_request_rejects_invalid_extensions() {
+    let request = proto::BeginRequest {
+        username: String::from("john_doe"),
+        role: None,
+        invitation: None,
+        email: None,
+        extensions_json: Some(String::from("{not json")),
+    };
+
+    let error = BeginRequest::try_from(request).unwrap_err();
+
+    assert!(matches!(error.kind(), AppError::BadRequest(_)));
+}
+
+#[test]
+fn test_finish_request_keeps_the_credentials_json() {
+    let json =
+        r#"{"id":"AQIDBAUGBwgJCgsMDQ4PEA","rawId":"AQIDBAUGBwgJCgsMDQ4PEA","type":"public-key"}"#;
+
+    let request = FinishRequest::try_from(finish_request(json)).unwrap();
+
+    assert_eq!(request.credentials.get(), json);
+    assert_eq!(request.device_name.as_deref(), Some("Work laptop"));
+    assert!(request.trusted);
+}
+
+#[test]
+fn test_finish_request_rejects_invalid_credentials_json() {
+    let error = FinishRequest::try_from(finish_request("{not json")).unwrap_err();
+
+    assert!(matches!(error.kind(), AppError::BadRequest(_)));
+}
+
+#[test]
+fn test_inactive_introspection_is_empty() {
+    let reply = proto::IntrospectReply::from(IntrospectionResponse::new(None));
+
+    assert!(!reply.active);
+    assert_eq!(reply.sub, None);
+    assert!(reply.roles.is_empty());
+}
+
+#[test]
+fn test_active_introspection_keeps_the_subject() {
+    let sub = Uuid::new_v4();
+    let response = IntrospectionResponse {
+        active: true,
+        sub: Some(sub),
+        username: Some(String::from("alice")),
+        roles: Some(vec![String::from("admin")]),
+        ..IntrospectionResponse::new(None)
+    };
+
+    let reply = proto::IntrospectReply::from(response);
+
+    assert!(reply.active);
+    assert_eq!(reply.sub, Some(sub.to_string()));
+    assert_eq!(reply.roles, vec![String::from("admin")]);
+}
@@ -0,0 +1,5 @@
+pub(crate) mod request;
+pub(crate) mod response;
+
+pub(crate) use request::CreateInvitationRequest;
+pub(crate) use response::{CreatedInvitationResponse, InvitationResponse};
@@ -0,0 +1,48 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::AppError,
+    impl_validated_json_request,
+    utils::{Validatable, validate_text},
+};
+
+pub const DEFAULT_EXPIRY_HOURS: u32 = 72;
+pub const MAX_EXPIRY_HOURS: u32 = 720;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateInvitationRequest {
+    /// Role granted to invitees that ask for none, and the only privileged
+    /// role they may ask for; must be in `REGISTRATION_ROLES` or
+    /// `PRIVILEGED_ROLES`. Without one, invitees pick their role as anyone
+    /// may.
+    #[schema(example = "admin")]
+    pub role: Option<String>,
+    /// Hours until the code expires, 72 by default
+    #[schema(example = 72, minimum = 1, maximum = 720)]
+    pub expires_in_hours: Option<u32>,
+    /// Whether the code admits one registration only, the default, or any
+    /// number until it expires or is revoked
+    #[schema(example = true)]
+    pub single_use: Option<bool>,
+}
+
+impl Validatable for CreateInvitationRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if let Some(role) = &self.role {
+            validate_text(role, "Role")?;
+        }
+        if let Some(hours) = self.expires_in_hours
+            && !(1..=MAX_EXPIRY_HOURS).contains(&hours)
+        {
+            return Err(AppError::BadRequest(format!(
+                "expires_in_hours must be between 1 and {}",
+                MAX_EXPIRY_HOURS
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_json_request!(CreateInvitationRequest);
@@ -0,0 +1,65 @@
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::invitations::model::Invitation;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvitationResponse {
+    pub invitation_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "admin")]
+    pub role: Option<String>,
+    pub single_use: bool,
+    /// Registrations the code has admitted so far.
+    #[schema(example = 0)]
+    pub uses: i32,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub created_at: String,
+    #[schema(example = "2024-01-04T12:00:00Z")]
+    pub expires_at: String,
+}
+
+impl From<Invitation> for InvitationResponse {
+    fn from(invitation: Invitation) -> Self {
+        Self {
+            invitation_id: invitation.id,
+            role: invitation.role,
+            single_use: invitation.single_use,
+            uses: invitation.uses,
+            created_at: invitation.created_at.to_rfc3339(),
+            expires_at: invitation.expires_at.to_rfc3339(),
+        }
+    }
+}
+
+impl IntoResponse for InvitationResponse {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+/// A new invitation with its code, which is never shown again.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedInvitationResponse {
+    #[serde(flatten)]
+    pub invitation: InvitationResponse,
+    #[schema(example = "q8Kx1yH7V0m3Tf2b9WcR4nLs6dPzAe5jUgYoBi0kXhE")]
+    pub code: String,
+}
+
+impl IntoResponse for CreatedInvitationResponse {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::CREATED,
+            [(header::CACHE_CONTROL, "no-store")],
+            Json(self),
+        )
+            .into_response()
+    }
+}
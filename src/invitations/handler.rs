@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    audit::model::AuditContext,
+    auth::permissions::AdminActions,
+    invitations::dto::{CreateInvitationRequest, CreatedInvitationResponse, InvitationResponse},
+};
+
+/// Create an invitation
+///
+/// Issues a code that registrations pass as `invitation` to
+/// `POST /auth/register/begin`. It admits them when registration is
+/// invite-only, and grants `role`, privileged or not, without an
+/// administrator's access token. It serves one registration, or any number
+/// with `single_use: false`, until it expires. The code is shown only in
+/// this response; only its hash is stored. Requires `admin:actions`.
+#[utoipa::path(
+    post,
+    path = "/admin/invites",
+    tag = "Admin",
+    request_body = CreateInvitationRequest,
+    responses(
+        (status = 201, description = "Invitation created", body = CreatedInvitationResponse),
+        (status = 400, description = "Unknown role or invalid expiry", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn create(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: CreateInvitationRequest,
+) -> Result<CreatedInvitationResponse, AppError> {
+    state.invitation_service.create(request, &admin, &ctx).await
+}
+
+/// Revoke an invitation
+///
+/// Its code stops working at once; the invitation is kept with its uses. A
+/// single-use invitation already spent cannot be revoked. Requires
+/// `admin:actions`.
+#[utoipa::path(
+    delete,
+    path = "/admin/invites/{invitation_id}",
+    tag = "Admin",
+    params(("invitation_id" = Uuid, Path, description = "Invitation to revoke")),
+    responses(
+        (status = 200, description = "Invitation revoked", body = InvitationResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "No usable invitation with this id", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn revoke(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Path(invitation_id): Path<Uuid>,
+) -> Result<InvitationResponse, AppError> {
+    state
+        .invitation_service
+        .revoke(invitation_id, &admin, &ctx)
+        .await
+}
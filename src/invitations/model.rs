@@ -0,0 +1,61 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    utils::{
+        FromRow,
+        crypto::{random_bytes, sha256},
+    },
+};
+
+/// Admits registrations while registration is invite-only, and grants
+/// its role, privileged or not, to those that name none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invitation {
+    pub id: Uuid,
+    /// `None` leaves the role to the registration.
+    pub role: Option<String>,
+    pub single_use: bool,
+    pub uses: i32,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl FromRow for Invitation {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            role: row.try_get("role")?,
+            single_use: row.try_get("single_use")?,
+            uses: row.try_get("uses")?,
+            created_at: row.try_get("created_at")?,
+            expires_at: row.try_get("expires_at")?,
+        })
+    }
+}
+
+/// An invitation code in the form handed out once at creation. Like a
+/// client secret, only its hash is ever persisted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvitationCode(String);
+
+impl InvitationCode {
+    pub fn generate() -> Self {
+        let entropy: [u8; 32] = random_bytes();
+        Self(BASE64_URL_SAFE_NO_PAD.encode(entropy))
+    }
+
+    pub fn hash(&self) -> Vec<u8> {
+        Self::hash_input(&self.0)
+    }
+
+    pub fn hash_input(input: &str) -> Vec<u8> {
+        sha256(input.as_bytes()).to_vec()
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
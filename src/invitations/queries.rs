@@ -0,0 +1,39 @@
+/// Every query takes the tenant of the request as its last parameter.
+pub mod invitations {
+    /// Inserts nothing when the role does not exist.
+    pub const INSERT: &str = "INSERT INTO invitations
+             (id, tenant_id, code_hash, role, created_by, expires_at, single_use)
+         SELECT $1, $7, $2, $3, $4, $5, $6
+         WHERE $3::TEXT IS NULL OR EXISTS (SELECT 1 FROM roles WHERE name = $3)
+         RETURNING id, role, single_use, uses, created_at, expires_at";
+
+    /// Matches on the code's hash. Spends nothing: registrations redeem the
+    /// invitation when they finish.
+    pub const SELECT_USABLE: &str = "SELECT id, role FROM invitations
+         WHERE code_hash = $1 AND tenant_id = $3
+           AND ($2::TEXT IS NULL OR role IS NULL OR role = $2)
+           AND (NOT single_use OR uses = 0)
+           AND revoked_at IS NULL AND expires_at > NOW()";
+
+    /// The conditions of `SELECT_USABLE` again, which make redemption
+    /// atomic: of two registrations racing for a single-use code, only one
+    /// updates the row.
+    pub const REDEEM: &str = "UPDATE invitations
+         SET uses = uses + 1, redeemed_at = NOW(), redeemed_by = $2
+         WHERE id = $1 AND tenant_id = $3
+           AND (NOT single_use OR uses = 0)
+           AND revoked_at IS NULL AND expires_at > NOW()";
+
+    pub const RELEASE: &str = "UPDATE invitations
+         SET uses = GREATEST(uses - 1, 0),
+             redeemed_at = CASE WHEN uses > 1 THEN redeemed_at END,
+             redeemed_by = CASE WHEN uses > 1 THEN redeemed_by END
+         WHERE id = $1 AND tenant_id = $2";
+
+    /// A used single-use invitation is spent already, and stays as it is.
+    pub const REVOKE: &str = "UPDATE invitations
+         SET revoked_at = NOW()
+         WHERE id = $1 AND tenant_id = $2
+           AND revoked_at IS NULL AND (NOT single_use OR uses = 0)
+         RETURNING id, role, single_use, uses, created_at, expires_at";
+}
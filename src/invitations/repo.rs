@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    auth::model::RedeemedInvitation,
+    config::CircuitBreaker,
+    db_insert, db_select, db_update,
+    invitations::{model::Invitation, queries, traits::InvitationRepository},
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl InvitationRepository for Repository {
+    async fn create(
+        &self,
+        id: Uuid,
+        code_hash: &[u8],
+        role: Option<&str>,
+        created_by: Uuid,
+        expires_at: DateTime<Utc>,
+        single_use: bool,
+    ) -> Result<Option<Invitation>, AppError> {
+        let tenant = current_tenant();
+
+        db_insert!("invitations", {
+            self.base
+                .execute_prepared_opt(
+                    queries::invitations::INSERT,
+                    &[
+                        &id as &(dyn ToSql + Sync),
+                        &code_hash,
+                        &role,
+                        &created_by,
+                        &expires_at,
+                        &single_use,
+                        &tenant,
+                    ],
+                )
+                .await
+        })?
+        .as_ref()
+        .map(Invitation::from_row)
+        .transpose()
+    }
+
+    async fn find_usable(
+        &self,
+        code_hash: &[u8],
+        role: Option<&str>,
+    ) -> Result<Option<RedeemedInvitation>, AppError> {
+        let tenant = current_tenant();
+
+        db_select!("invitations", {
+            self.base
+                .execute_prepared_opt(
+                    queries::invitations::SELECT_USABLE,
+                    &[&code_hash as &(dyn ToSql + Sync), &role, &tenant],
+                )
+                .await
+        })?
+        .map(|row| {
+            Ok(RedeemedInvitation {
+                id: row.try_get("id")?,
+                role: row.try_get("role")?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn redeem(&self, id: Uuid, username: &str) -> Result<bool, AppError> {
+        let tenant = current_tenant();
+
+        let updated = db_update!("invitations", {
+            self.base
+                .execute_prepared_raw(
+                    queries::invitations::REDEEM,
+                    &[&id as &(dyn ToSql + Sync), &username, &tenant],
+                )
+                .await
+        })?;
+        Ok(updated > 0)
+    }
+
+    async fn release(&self, id: Uuid) -> Result<(), AppError> {
+        let tenant = current_tenant();
+
+        db_update!("invitations", {
+            self.base
+                .execute_prepared_raw(
+                    queries::invitations::RELEASE,
+                    &[&id as &(dyn ToSql + Sync), &tenant],
+                )
+                .await
+        })?;
+        Ok(())
+    }
+
+    async fn revoke(&self, id: Uuid) -> Result<Option<Invitation>, AppError> {
+        let tenant = current_tenant();
+
+        db_update!("invitations", {
+            self.base
+                .execute_prepared_opt(
+                    queries::invitations::REVOKE,
+                    &[&id as &(dyn ToSql + Sync), &tenant],
+                )
+                .await
+        })?
+        .as_ref()
+        .map(Invitation::from_row)
+        .transpose()
+    }
+}
@@ -0,0 +1,183 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::{
+        model::{AuditContext, AuditEntry, AuditEvent},
+        traits::AuditLogger,
+    },
+    auth::{
+        jwt::{AccessTokenClaims, claims::JwtClaims},
+        model::RedeemedInvitation,
+        traits::{InvitationRedeemer, SendFuture},
+    },
+    config::RolePolicy,
+    invitations::{
+        dto::{
+            CreateInvitationRequest, CreatedInvitationResponse, InvitationResponse,
+            request::DEFAULT_EXPIRY_HOURS,
+        },
+        model::{Invitation, InvitationCode},
+        traits::InvitationRepository,
+    },
+};
+
+pub struct InvitationService<R, A>
+where
+    R: InvitationRepository + 'static,
+    A: AuditLogger + 'static,
+{
+    invitation_repo: Arc<R>,
+    audit_logger: Arc<A>,
+    role_policy: RolePolicy,
+}
+
+impl<R, A> InvitationService<R, A>
+where
+    R: InvitationRepository + 'static,
+    A: AuditLogger + 'static,
+{
+    pub fn new(invitation_repo: Arc<R>, audit_logger: Arc<A>, role_policy: RolePolicy) -> Self {
+        Self {
+            invitation_repo,
+            audit_logger,
+            role_policy,
+        }
+    }
+
+    /// Issues an invitation under a fresh code, returned this once.
+    pub async fn create(
+        &self,
+        req: CreateInvitationRequest,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<CreatedInvitationResponse, AppError> {
+        let code = InvitationCode::generate();
+        let result = self.insert(&req, &code, actor).await;
+        self.audit(
+            "create-invitation",
+            req.role.as_deref(),
+            &result,
+            actor,
+            ctx,
+        );
+
+        Ok(CreatedInvitationResponse {
+            invitation: result?.into(),
+            code: code.into_string(),
+        })
+    }
+
+    /// The code stops working; the invitation is kept with its uses. A
+    /// single-use invitation already spent cannot be revoked.
+    pub async fn revoke(
+        &self,
+        invitation_id: Uuid,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<InvitationResponse, AppError> {
+        let result = self
+            .invitation_repo
+            .revoke(invitation_id)
+            .await
+            .and_then(|invitation| {
+                invitation.ok_or_else(|| {
+                    AppError::NotFound(String::from("No usable invitation with this id"))
+                })
+            });
+        let role = result
+            .as_ref()
+            .ok()
+            .and_then(|invitation| invitation.role.as_deref());
+        self.audit("revoke-invitation", role, &result, actor, ctx);
+        result.map(Into::into)
+    }
+
+    async fn insert(
+        &self,
+        req: &CreateInvitationRequest,
+        code: &InvitationCode,
+        actor: &AccessTokenClaims,
+    ) -> Result<Invitation, AppError> {
+        let role = req.role.as_deref();
+        let unknown = |role: &str| AppError::BadRequest(format!("Unknown role: {}", role));
+        // Registrations refuse anything else, so the code could never be used.
+        if let Some(role) = role
+            && !self.role_policy.is_allowed(role)
+        {
+            return Err(unknown(role));
+        }
+
+        let hours = req.expires_in_hours.unwrap_or(DEFAULT_EXPIRY_HOURS);
+        let expires_at = Utc::now() + Duration::hours(i64::from(hours));
+        self.invitation_repo
+            .create(
+                Uuid::new_v4(),
+                &code.hash(),
+                role,
+                *actor.sub(),
+                expires_at,
+                req.single_use.unwrap_or(true),
+            )
+            .await?
+            .ok_or_else(|| unknown(role.unwrap_or_default()))
+    }
+
+    fn audit(
+        &self,
+        action: &str,
+        role: Option<&str>,
+        result: &Result<Invitation, AppError>,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) {
+        let mut details = serde_json::json!({ "action": action, "role": role });
+        if let Ok(invitation) = result {
+            details["invitation_id"] = serde_json::Value::from(invitation.id.to_string());
+        }
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AdminAction,
+                ctx,
+                Some(actor.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*actor.sub())
+            .with_details(details),
+        );
+    }
+}
+
+impl<R, A> InvitationRedeemer for InvitationService<R, A>
+where
+    R: InvitationRepository + 'static,
+    A: AuditLogger + 'static,
+{
+    fn check<'a>(
+        &'a self,
+        code: &'a str,
+        role: Option<&'a str>,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<RedeemedInvitation>, AppError>> + Send + 'a>>
+    {
+        Box::pin(async move {
+            self.invitation_repo
+                .find_usable(&InvitationCode::hash_input(code), role)
+                .await
+        })
+    }
+
+    fn redeem<'a>(
+        &'a self,
+        id: Uuid,
+        username: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, AppError>> + Send + 'a>> {
+        Box::pin(self.invitation_repo.redeem(id, username))
+    }
+
+    fn release(&self, id: Uuid) -> SendFuture<'_> {
+        Box::pin(self.invitation_repo.release(id))
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{app::AppError, invitations::dto::CreateInvitationRequest, utils::Validatable};
+
+fn request(role: &str, expires_in_hours: Option<u32>) -> CreateInvitationRequest {
+    CreateInvitationRequest {
+        role: Some(role.to_owned()),
+        expires_in_hours,
+        single_use: None,
+    }
+}
+
+#[test]
+fn test_expiry_must_be_within_bounds() {
+    assert!(request("admin", None).validate().is_ok());
+    assert!(request("admin", Some(1)).validate().is_ok());
+    assert!(request("admin", Some(720)).validate().is_ok());
+
+    for hours in [0, 721] {
+        assert!(matches!(
+            request("admin", Some(hours)).validate(),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
+
+#[test]
+fn test_blank_role_rejected() {
+    match request(" ", None).validate() {
+        Err(AppError::BadRequest(msg)) => assert_eq!(msg, "Role cannot be empty"),
+        _ => panic!("Expected BadRequest error"),
+    }
+}
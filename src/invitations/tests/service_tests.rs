@@ -0,0 +1,373 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::model::{AuditContext, AuditOutcome},
+    auth::{model::RedeemedInvitation, traits::InvitationRedeemer},
+    config::RolePolicy,
+    invitations::{
+        dto::CreateInvitationRequest,
+        model::{Invitation, InvitationCode},
+        service::InvitationService,
+        traits::InvitationRepository,
+    },
+    utils::mocks::{MockAuditLogger, admin_claims},
+};
+
+const ROLES: [&str; 3] = ["admin", "member", "superuser"];
+
+struct StoredInvitation {
+    invitation: Invitation,
+    code_hash: Vec<u8>,
+    redeemed_by: Option<String>,
+    revoked: bool,
+}
+
+#[derive(Default)]
+struct MockRepository {
+    invitations: Mutex<Vec<StoredInvitation>>,
+}
+
+impl MockRepository {
+    fn usable(stored: &StoredInvitation) -> bool {
+        let invitation = &stored.invitation;
+        (!invitation.single_use || invitation.uses == 0)
+            && !stored.revoked
+            && invitation.expires_at > Utc::now()
+    }
+}
+
+impl InvitationRepository for MockRepository {
+    async fn create(
+        &self,
+        id: Uuid,
+        code_hash: &[u8],
+        role: Option<&str>,
+        _: Uuid,
+        expires_at: DateTime<Utc>,
+        single_use: bool,
+    ) -> Result<Option<Invitation>, AppError> {
+        if role.is_some_and(|role| !ROLES.contains(&role)) {
+            return Ok(None);
+        }
+        let invitation = Invitation {
+            id,
+            role: role.map(str::to_owned),
+            single_use,
+            uses: 0,
+            created_at: Utc::now(),
+            expires_at,
+        };
+        self.invitations.lock().unwrap().push(StoredInvitation {
+            invitation: invitation.clone(),
+            code_hash: code_hash.to_vec(),
+            redeemed_by: None,
+            revoked: false,
+        });
+        Ok(Some(invitation))
+    }
+
+    async fn find_usable(
+        &self,
+        code_hash: &[u8],
+        role: Option<&str>,
+    ) -> Result<Option<RedeemedInvitation>, AppError> {
+        let invitations = self.invitations.lock().unwrap();
+        Ok(invitations
+            .iter()
+            .find(|i| {
+                i.code_hash == code_hash
+                    && (role.is_none()
+                        || i.invitation.role.is_none()
+                        || i.invitation.role.as_deref() == role)
+                    && Self::usable(i)
+            })
+            .map(|i| RedeemedInvitation {
+                id: i.invitation.id,
+                role: i.invitation.role.clone(),
+            }))
+    }
+
+    async fn redeem(&self, id: Uuid, username: &str) -> Result<bool, AppError> {
+        let mut invitations = self.invitations.lock().unwrap();
+        let Some(stored) = invitations
+            .iter_mut()
+            .find(|i| i.invitation.id == id && Self::usable(i))
+        else {
+            return Ok(false);
+        };
+        stored.invitation.uses += 1;
+        stored.redeemed_by = Some(username.to_owned());
+        Ok(true)
+    }
+
+    async fn release(&self, id: Uuid) -> Result<(), AppError> {
+        let mut invitations = self.invitations.lock().unwrap();
+        if let Some(stored) = invitations.iter_mut().find(|i| i.invitation.id == id) {
+            stored.invitation.uses = (stored.invitation.uses - 1).max(0);
+            if stored.invitation.uses == 0 {
+                stored.redeemed_by = None;
+            }
+        }
+        Ok(())
+    }
+
+    async fn revoke(&self, id: Uuid) -> Result<Option<Invitation>, AppError> {
+        let mut invitations = self.invitations.lock().unwrap();
+        let Some(stored) = invitations.iter_mut().find(|i| {
+            i.invitation.id == id
+                && !i.revoked
+                && (!i.invitation.single_use || i.invitation.uses == 0)
+        }) else {
+            return Ok(None);
+        };
+        stored.revoked = true;
+        Ok(Some(stored.invitation.clone()))
+    }
+}
+
+type Service = InvitationService<MockRepository, MockAuditLogger>;
+
+fn service() -> (Service, Arc<MockAuditLogger>) {
+    let audit = Arc::new(MockAuditLogger::default());
+    (
+        InvitationService::new(
+            Arc::new(MockRepository::default()),
+            Arc::clone(&audit),
+            RolePolicy::parse("member", "admin"),
+        ),
+        audit,
+    )
+}
+
+/// Checks `code` as beginning a registration does, then redeems it as
+/// finishing one does.
+async fn redeem(
+    service: &Service,
+    code: &str,
+    role: Option<&str>,
+    username: &str,
+) -> Option<RedeemedInvitation> {
+    let invitation = service.check(code, role).await.unwrap()?;
+    service
+        .redeem(invitation.id, username)
+        .await
+        .unwrap()
+        .then_some(invitation)
+}
+
+fn request(role: Option<&str>, single_use: Option<bool>) -> CreateInvitationRequest {
+    CreateInvitationRequest {
+        role: role.map(str::to_owned),
+        expires_in_hours: None,
+        single_use,
+    }
+}
+
+#[test]
+fn test_codes_are_unique_and_hashed() {
+    let (a, b) = (InvitationCode::generate(), InvitationCode::generate());
+    assert_ne!(a, b);
+    assert_eq!(
+        a.hash(),
+        InvitationCode::hash_input(&a.clone().into_string())
+    );
+    assert_ne!(a.hash(), b.hash());
+}
+
+#[tokio::test]
+async fn test_single_use_invitation_admits_one_user_for_its_role() {
+    let (service, audit) = service();
+    let created = service
+        .create(
+            request(Some("admin"), None),
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+    assert!(created.invitation.single_use);
+    let lifetime = DateTime::parse_from_rfc3339(&created.invitation.expires_at).unwrap()
+        - DateTime::parse_from_rfc3339(&created.invitation.created_at).unwrap();
+    assert!((71..=72).contains(&lifetime.num_hours()));
+
+    let id = created.invitation.invitation_id;
+    let redeemed = Some(RedeemedInvitation {
+        id,
+        role: Some(String::from("admin")),
+    });
+    assert_eq!(
+        redeem(&service, &created.code, Some("member"), "alice").await,
+        None
+    );
+    assert_eq!(
+        redeem(&service, &created.code, None, "alice").await,
+        redeemed
+    );
+    assert_eq!(redeem(&service, &created.code, None, "bob").await, None);
+
+    let entries = audit.entries.lock().unwrap();
+    assert_eq!(entries[0].details["action"], "create-invitation");
+    assert_eq!(entries[0].details["role"], "admin");
+    assert_eq!(entries[0].outcome, AuditOutcome::Success);
+}
+
+#[tokio::test]
+async fn test_reusable_invitation_without_role_admits_many_users() {
+    let (service, _) = service();
+    let ctx = AuditContext::default();
+    let created = service
+        .create(
+            request(None, Some(false)),
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.invitation.role, None);
+
+    for (username, role) in [
+        ("alice", None),
+        ("bob", Some("member")),
+        ("carol", Some("admin")),
+    ] {
+        let redeemed = redeem(&service, &created.code, role, username)
+            .await
+            .unwrap();
+        assert_eq!(redeemed.role, None);
+    }
+
+    let revoked = service
+        .revoke(
+            created.invitation.invitation_id,
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(revoked.uses, 3);
+    assert_eq!(redeem(&service, &created.code, None, "dave").await, None);
+}
+
+#[tokio::test]
+async fn test_released_invitation_can_be_redeemed_again() {
+    let (service, _) = service();
+    let created = service
+        .create(
+            request(Some("admin"), None),
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    let redeemed = redeem(&service, &created.code, Some("admin"), "alice")
+        .await
+        .unwrap();
+    service.release(redeemed.id).await.unwrap();
+
+    assert_eq!(
+        redeem(&service, &created.code, Some("admin"), "bob").await,
+        Some(redeemed)
+    );
+}
+
+#[tokio::test]
+async fn test_checking_spends_nothing_and_first_finish_wins() {
+    let (service, _) = service();
+    let created = service
+        .create(
+            request(Some("admin"), None),
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    let alice = service.check(&created.code, None).await.unwrap().unwrap();
+    let bob = service.check(&created.code, None).await.unwrap().unwrap();
+    assert_eq!(alice, bob);
+
+    assert!(service.redeem(bob.id, "bob").await.unwrap());
+    assert!(!service.redeem(alice.id, "alice").await.unwrap());
+    assert_eq!(service.check(&created.code, None).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_roles_outside_policy_rejected() {
+    let (service, audit) = service();
+    let ctx = AuditContext::default();
+
+    for role in ["superuser", "nobody"] {
+        assert!(matches!(
+            service
+                .create(
+                    request(Some(role), None),
+                    &admin_claims(&["admin:actions"]),
+                    &ctx
+                )
+                .await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+    assert!(
+        audit
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|e| e.outcome != AuditOutcome::Success)
+    );
+}
+
+#[tokio::test]
+async fn test_spent_single_use_invitation_cannot_be_revoked() {
+    let (service, _) = service();
+    let ctx = AuditContext::default();
+    let unused = service
+        .create(
+            request(Some("member"), None),
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    let used = service
+        .create(
+            request(Some("admin"), None),
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    redeem(&service, &used.code, Some("admin"), "alice")
+        .await
+        .unwrap();
+
+    let revoked = service
+        .revoke(
+            unused.invitation.invitation_id,
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(revoked.role.as_deref(), Some("member"));
+    assert_eq!(
+        redeem(&service, &unused.code, Some("member"), "bob").await,
+        None
+    );
+    assert!(matches!(
+        service
+            .revoke(
+                used.invitation.invitation_id,
+                &admin_claims(&["admin:actions"]),
+                &ctx
+            )
+            .await,
+        Err(AppError::NotFound(_))
+    ));
+}
@@ -0,0 +1,37 @@
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{app::AppError, auth::model::RedeemedInvitation, invitations::model::Invitation};
+
+pub trait InvitationRepository: Send + Sync {
+    /// `None` when the role does not exist.
+    fn create(
+        &self,
+        id: Uuid,
+        code_hash: &[u8],
+        role: Option<&str>,
+        created_by: Uuid,
+        expires_at: DateTime<Utc>,
+        single_use: bool,
+    ) -> impl Future<Output = Result<Option<Invitation>, AppError>> + Send;
+    /// The usable invitation with this hash, unless it names a role other
+    /// than `role`.
+    fn find_usable(
+        &self,
+        code_hash: &[u8],
+        role: Option<&str>,
+    ) -> impl Future<Output = Result<Option<RedeemedInvitation>, AppError>> + Send;
+    /// Counts a use by `username`, unless the invitation is no longer usable.
+    fn redeem(
+        &self,
+        id: Uuid,
+        username: &str,
+    ) -> impl Future<Output = Result<bool, AppError>> + Send;
+    fn release(&self, id: Uuid) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Stops the code working, keeping the invitation. `None` when there is
+    /// no such invitation, or it was revoked or spent.
+    fn revoke(&self, id: Uuid)
+    -> impl Future<Output = Result<Option<Invitation>, AppError>> + Send;
+}
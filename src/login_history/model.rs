@@ -0,0 +1,95 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{app::AppError, audit::model::AuditContext, utils::FromRow};
+
+/// What about a login the account has not seen before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoginAnomaly {
+    #[serde(rename = "new_country")]
+    Country,
+    #[serde(rename = "new_asn")]
+    Asn,
+    #[serde(rename = "new_device")]
+    Device,
+}
+
+impl LoginAnomaly {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LoginAnomaly::Country => "new_country",
+            LoginAnomaly::Asn => "new_asn",
+            LoginAnomaly::Device => "new_device",
+        }
+    }
+}
+
+/// A successful login, as far as the transport can tell. The user agent
+/// stands in for the device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginRecord {
+    pub user_id: Uuid,
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub country: Option<String>,
+    pub asn: Option<u32>,
+}
+
+impl LoginRecord {
+    pub fn new(user_id: Uuid, ctx: &AuditContext) -> Self {
+        Self {
+            user_id,
+            ip: ctx.ip,
+            user_agent: ctx.user_agent.clone(),
+            country: ctx.country.clone(),
+            asn: ctx.asn,
+        }
+    }
+}
+
+/// Whether earlier logins of the account share each attribute of a new one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KnownOrigins {
+    pub logins: i64,
+    pub country_seen: bool,
+    pub asn_seen: bool,
+    pub device_seen: bool,
+}
+
+impl KnownOrigins {
+    /// The first login only sets the baseline, and an attribute the
+    /// transport could not tell is never reported.
+    pub fn anomalies(&self, record: &LoginRecord) -> Vec<LoginAnomaly> {
+        if self.logins == 0 {
+            return Vec::new();
+        }
+
+        [
+            (
+                record.country.is_some() && !self.country_seen,
+                LoginAnomaly::Country,
+            ),
+            (record.asn.is_some() && !self.asn_seen, LoginAnomaly::Asn),
+            (
+                record.user_agent.is_some() && !self.device_seen,
+                LoginAnomaly::Device,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(new, anomaly)| new.then_some(anomaly))
+        .collect()
+    }
+}
+
+impl FromRow for KnownOrigins {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(KnownOrigins {
+            logins: row.try_get("logins")?,
+            country_seen: row.try_get("country_seen")?,
+            asn_seen: row.try_get("asn_seen")?,
+            device_seen: row.try_get("device_seen")?,
+        })
+    }
+}
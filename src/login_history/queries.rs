@@ -0,0 +1,12 @@
+pub mod login_history {
+    pub const KNOWN_ORIGINS: &str = "SELECT COUNT(*) AS logins,
+                COALESCE(BOOL_OR(country = $2), false) AS country_seen,
+                COALESCE(BOOL_OR(asn = $3), false) AS asn_seen,
+                COALESCE(BOOL_OR(user_agent = $4), false) AS device_seen
+         FROM login_history
+         WHERE user_id = $1";
+
+    pub const INSERT: &str = "INSERT INTO login_history
+             (user_id, ip, user_agent, country, asn, anomalies)
+         VALUES ($1, $2, $3, $4, $5, $6)";
+}
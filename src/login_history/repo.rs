@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    app::AppError,
+    config::CircuitBreaker,
+    db_insert, db_select,
+    login_history::{
+        model::{KnownOrigins, LoginAnomaly, LoginRecord},
+        queries,
+        traits::LoginHistoryRepository,
+    },
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl LoginHistoryRepository for Repository {
+    async fn known_origins(&self, record: &LoginRecord) -> Result<KnownOrigins, AppError> {
+        let row = db_select!("login_history", {
+            self.base
+                .execute_prepared_one(
+                    queries::login_history::KNOWN_ORIGINS,
+                    &[
+                        &record.user_id as &(dyn ToSql + Sync),
+                        &record.country,
+                        &record.asn.map(i64::from),
+                        &record.user_agent,
+                    ],
+                )
+                .await
+        })?;
+
+        KnownOrigins::from_row(&row)
+    }
+
+    async fn insert(
+        &self,
+        record: &LoginRecord,
+        anomalies: &[LoginAnomaly],
+    ) -> Result<(), AppError> {
+        let anomalies: Vec<&str> = anomalies.iter().map(|anomaly| anomaly.as_str()).collect();
+
+        db_insert!("login_history", {
+            self.base
+                .execute_prepared_raw(
+                    queries::login_history::INSERT,
+                    &[
+                        &record.user_id as &(dyn ToSql + Sync),
+                        &record.ip,
+                        &record.user_agent,
+                        &record.country,
+                        &record.asn.map(i64::from),
+                        &anomalies,
+                    ],
+                )
+                .await
+        })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, middleware::metrics::track_login_anomaly},
+    audit::model::AuditContext,
+    events::{model::AuthEventKind, traits::EventPublisher},
+    login_history::{
+        model::{LoginAnomaly, LoginRecord},
+        traits::{LoginHistoryRepository, LoginRecorder},
+    },
+};
+
+pub struct LoginHistoryService<R>
+where
+    R: LoginHistoryRepository + 'static,
+{
+    history_repo: Arc<R>,
+    events: Option<Arc<dyn EventPublisher>>,
+}
+
+impl<R> LoginHistoryService<R>
+where
+    R: LoginHistoryRepository + 'static,
+{
+    pub fn new(history_repo: Arc<R>) -> Self {
+        Self {
+            history_repo,
+            events: None,
+        }
+    }
+
+    /// Warns the account owner about anomalous logins.
+    pub fn with_events(mut self, events: Arc<dyn EventPublisher>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Compares the login with the earlier ones before storing it, so it
+    /// never counts as its own precedent.
+    pub async fn check_and_store(
+        &self,
+        record: &LoginRecord,
+        username: &str,
+    ) -> Result<Vec<LoginAnomaly>, AppError> {
+        let anomalies = self
+            .history_repo
+            .known_origins(record)
+            .await?
+            .anomalies(record);
+        self.history_repo.insert(record, &anomalies).await?;
+
+        if anomalies.is_empty() {
+            return Ok(anomalies);
+        }
+
+        let reasons: Vec<&str> = anomalies.iter().map(|anomaly| anomaly.as_str()).collect();
+        tracing::warn!(
+            target: "security",
+            user_id = %record.user_id,
+            username,
+            ip = record.ip.map(|ip| ip.to_string()),
+            country = record.country.as_deref(),
+            asn = record.asn,
+            reasons = ?reasons,
+            "Login from an unfamiliar origin"
+        );
+        for reason in &reasons {
+            track_login_anomaly(reason);
+        }
+        if let Some(events) = &self.events {
+            events.publish(
+                record.user_id,
+                AuthEventKind::LoginAnomaly {
+                    reasons: anomalies.clone(),
+                    ip: record.ip.map(|ip| ip.to_string()),
+                    country: record.country.clone(),
+                },
+            );
+        }
+
+        Ok(anomalies)
+    }
+}
+
+impl<R> Clone for LoginHistoryService<R>
+where
+    R: LoginHistoryRepository + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            history_repo: Arc::clone(&self.history_repo),
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl<R> LoginRecorder for LoginHistoryService<R>
+where
+    R: LoginHistoryRepository + 'static,
+{
+    fn record(&self, user_id: Uuid, username: &str, ctx: &AuditContext) {
+        let service = self.clone();
+        let record = LoginRecord::new(user_id, ctx);
+        let username = username.to_string();
+        tokio::spawn(
+            async move {
+                if let Err(e) = service.check_and_store(&record, &username).await {
+                    tracing::error!(%user_id, "Failed to record login history: {}", e);
+                }
+            }
+            .in_current_span(),
+        );
+    }
+}
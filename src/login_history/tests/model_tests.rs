@@ -0,0 +1,49 @@
+use uuid::Uuid;
+
+use crate::login_history::model::{KnownOrigins, LoginAnomaly, LoginRecord};
+
+fn record() -> LoginRecord {
+    LoginRecord {
+        user_id: Uuid::new_v4(),
+        ip: None,
+        user_agent: Some(String::from("Firefox")),
+        country: Some(String::from("DE")),
+        asn: Some(3320),
+    }
+}
+
+#[test]
+fn test_first_login_is_never_anomalous() {
+    assert!(KnownOrigins::default().anomalies(&record()).is_empty());
+}
+
+#[test]
+fn test_reports_each_unseen_attribute() {
+    let known = KnownOrigins {
+        logins: 3,
+        country_seen: false,
+        asn_seen: true,
+        device_seen: false,
+    };
+
+    assert_eq!(
+        known.anomalies(&record()),
+        [LoginAnomaly::Country, LoginAnomaly::Device]
+    );
+}
+
+#[test]
+fn test_unknown_attributes_are_not_reported() {
+    let known = KnownOrigins {
+        logins: 1,
+        ..Default::default()
+    };
+    let record = LoginRecord {
+        user_agent: None,
+        country: None,
+        asn: None,
+        ..record()
+    };
+
+    assert!(known.anomalies(&record).is_empty());
+}
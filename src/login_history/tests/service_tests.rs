@@ -0,0 +1,106 @@
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    events::{model::AuthEventKind, traits::EventPublisher},
+    login_history::{
+        model::{KnownOrigins, LoginAnomaly, LoginRecord},
+        service::LoginHistoryService,
+        traits::LoginHistoryRepository,
+    },
+};
+
+#[derive(Default)]
+struct MockRepository {
+    known: KnownOrigins,
+    inserted: Mutex<Vec<(LoginRecord, Vec<LoginAnomaly>)>>,
+}
+
+impl LoginHistoryRepository for MockRepository {
+    async fn known_origins(&self, _record: &LoginRecord) -> Result<KnownOrigins, AppError> {
+        Ok(self.known)
+    }
+
+    async fn insert(
+        &self,
+        record: &LoginRecord,
+        anomalies: &[LoginAnomaly],
+    ) -> Result<(), AppError> {
+        self.inserted
+            .lock()
+            .unwrap()
+            .push((record.clone(), anomalies.to_vec()));
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct MockEvents {
+    published: Mutex<Vec<(Uuid, AuthEventKind)>>,
+}
+
+impl EventPublisher for MockEvents {
+    fn publish(&self, user_id: Uuid, kind: AuthEventKind) {
+        self.published.lock().unwrap().push((user_id, kind));
+    }
+}
+
+fn record() -> LoginRecord {
+    LoginRecord {
+        user_id: Uuid::new_v4(),
+        ip: None,
+        user_agent: Some(String::from("Firefox")),
+        country: Some(String::from("FR")),
+        asn: None,
+    }
+}
+
+#[tokio::test]
+async fn test_anomaly_is_stored_and_published() {
+    let repo = Arc::new(MockRepository {
+        known: KnownOrigins {
+            logins: 2,
+            country_seen: false,
+            asn_seen: false,
+            device_seen: true,
+        },
+        ..Default::default()
+    });
+    let events = Arc::new(MockEvents::default());
+    let service = LoginHistoryService::new(Arc::clone(&repo)).with_events(Arc::clone(&events) as _);
+    let record = record();
+
+    let anomalies = service.check_and_store(&record, "alice").await.unwrap();
+
+    assert_eq!(anomalies, [LoginAnomaly::Country]);
+    assert_eq!(
+        repo.inserted.lock().unwrap()[0],
+        (record.clone(), vec![LoginAnomaly::Country])
+    );
+    assert_eq!(
+        events.published.lock().unwrap()[0],
+        (
+            record.user_id,
+            AuthEventKind::LoginAnomaly {
+                reasons: vec![LoginAnomaly::Country],
+                ip: None,
+                country: Some(String::from("FR")),
+            }
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_familiar_login_is_stored_quietly() {
+    let repo = Arc::new(MockRepository::default());
+    let events = Arc::new(MockEvents::default());
+    let service = LoginHistoryService::new(Arc::clone(&repo)).with_events(Arc::clone(&events) as _);
+
+    let anomalies = service.check_and_store(&record(), "alice").await.unwrap();
+
+    assert!(anomalies.is_empty());
+    assert_eq!(repo.inserted.lock().unwrap().len(), 1);
+    assert!(events.published.lock().unwrap().is_empty());
+}
@@ -0,0 +1,28 @@
+use std::future::Future;
+
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::model::AuditContext,
+    login_history::model::{KnownOrigins, LoginAnomaly, LoginRecord},
+};
+
+pub trait LoginHistoryRepository: Send + Sync {
+    /// Compared against every earlier login of the account.
+    fn known_origins(
+        &self,
+        record: &LoginRecord,
+    ) -> impl Future<Output = Result<KnownOrigins, AppError>> + Send;
+    fn insert(
+        &self,
+        record: &LoginRecord,
+        anomalies: &[LoginAnomaly],
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+}
+
+/// Entry point used by the login flow. Like auditing, recording never blocks
+/// or fails the login.
+pub trait LoginRecorder: Send + Sync {
+    fn record(&self, user_id: Uuid, username: &str, ctx: &AuditContext);
+}
@@ -0,0 +1,78 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::AppError,
+    impl_validated_form_request, impl_validated_json_request,
+    utils::{Validatable, validate_text},
+};
+
+pub const MAX_NAME_LEN: usize = 63;
+pub const CLIENT_CREDENTIALS: &str = "client_credentials";
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateClientRequest {
+    /// Unique within the tenant: lowercase letters, digits, `-` and `_`
+    #[schema(example = "nightly-report", max_length = 63)]
+    pub name: String,
+    /// Role whose permissions the client's tokens carry, `service` by default
+    #[schema(example = "service")]
+    pub role: Option<String>,
+}
+
+impl Validatable for CreateClientRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if !is_valid_client_name(&self.name) {
+            return Err(AppError::BadRequest(format!(
+                "Name must be 1-{} lowercase letters, digits, '-' or '_', starting with a letter or digit",
+                MAX_NAME_LEN
+            )));
+        }
+        if let Some(role) = &self.role {
+            validate_text(role, "Role")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Same rule as the `machine_clients.name` check constraint.
+pub fn is_valid_client_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.len() <= MAX_NAME_LEN
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'))
+}
+
+/// An RFC 6749 token request. The client authenticates with HTTP Basic or
+/// with `client_id` and `client_secret` in the body, not both.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct ClientTokenRequest {
+    #[schema(example = "client_credentials")]
+    pub grant_type: String,
+    #[schema(example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Accepted for compatibility; tokens carry every permission of the
+    /// client's role.
+    #[cfg_attr(not(feature = "strict"), allow(dead_code))]
+    pub scope: Option<String>,
+}
+
+impl Validatable for ClientTokenRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.grant_type != CLIENT_CREDENTIALS {
+            return Err(AppError::BadRequest(format!(
+                "Unsupported grant_type, only {} is",
+                CLIENT_CREDENTIALS
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_json_request!(CreateClientRequest);
+impl_validated_form_request!(ClientTokenRequest);
@@ -0,0 +1,81 @@
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::machine_clients::model::MachineClient;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MachineClientResponse {
+    pub client_id: Uuid,
+    #[schema(example = "nightly-report")]
+    pub name: String,
+    #[schema(example = "service")]
+    pub role: String,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "2024-01-02T03:00:00Z")]
+    pub last_used_at: Option<String>,
+}
+
+impl From<MachineClient> for MachineClientResponse {
+    fn from(client: MachineClient) -> Self {
+        Self {
+            client_id: client.id,
+            name: client.name,
+            role: client.role,
+            created_at: client.created_at.to_rfc3339(),
+            last_used_at: client.last_used_at.map(|at| at.to_rfc3339()),
+        }
+    }
+}
+
+impl IntoResponse for MachineClientResponse {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+/// A new client with its secret, which is never shown again.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedClientResponse {
+    #[serde(flatten)]
+    pub client: MachineClientResponse,
+    #[schema(example = "q8Kx1yH7V0m3Tf2b9WcR4nLs6dPzAe5jUgYoBi0kXhE")]
+    pub client_secret: String,
+}
+
+impl IntoResponse for CreatedClientResponse {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::CREATED,
+            [(header::CACHE_CONTROL, "no-store")],
+            Json(self),
+        )
+            .into_response()
+    }
+}
+
+/// An RFC 6749 token response. There is no refresh token: the client
+/// requests a new access token with its credentials instead.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClientTokenResponse {
+    #[schema(example = "eyJhbGciOiJFZERTQSIsInR5cCI6IkpXVCJ9...")]
+    pub access_token: String,
+    #[schema(example = "Bearer")]
+    pub token_type: &'static str,
+    /// Seconds until the access token expires.
+    #[schema(example = 300)]
+    pub expires_in: i64,
+}
+
+impl IntoResponse for ClientTokenResponse {
+    fn into_response(self) -> Response {
+        ([(header::CACHE_CONTROL, "no-store")], Json(self)).into_response()
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+};
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission, middleware::metrics},
+    audit::model::AuditContext,
+    auth::permissions::AdminActions,
+    config::introspection::basic_credentials,
+    machine_clients::dto::{
+        ClientTokenRequest, ClientTokenResponse, CreateClientRequest, CreatedClientResponse,
+        MachineClientResponse,
+    },
+};
+
+/// Create a machine client
+///
+/// Registers a cron job or internal service that gets access tokens from
+/// `POST /auth/token` with the returned `client_id` and `client_secret`. The
+/// secret is shown only in this response; only its hash is stored. Tokens
+/// carry the permissions of `role`, `service` by default. Requires
+/// `admin:actions`.
+#[utoipa::path(
+    post,
+    path = "/admin/clients",
+    tag = "Admin",
+    request_body = CreateClientRequest,
+    responses(
+        (status = 201, description = "Client created", body = CreatedClientResponse),
+        (status = 400, description = "Invalid name or unknown role", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 409, description = "Name already taken", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn create(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: CreateClientRequest,
+) -> Result<CreatedClientResponse, AppError> {
+    state
+        .machine_client_service
+        .create(request, &admin, &ctx)
+        .await
+}
+
+/// Delete a machine client
+///
+/// Its credentials stop working at once; access tokens already issued stay
+/// valid until they expire. Requires `admin:actions`.
+#[utoipa::path(
+    delete,
+    path = "/admin/clients/{client_id}",
+    tag = "Admin",
+    params(("client_id" = Uuid, Path, description = "Client to delete")),
+    responses(
+        (status = 200, description = "Client deleted", body = MachineClientResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "Client not found", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn delete(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Path(client_id): Path<Uuid>,
+) -> Result<MachineClientResponse, AppError> {
+    state
+        .machine_client_service
+        .delete(client_id, &admin, &ctx)
+        .await
+}
+
+/// Client credentials token
+///
+/// Issues an access token to a machine client, per the RFC 6749
+/// `client_credentials` grant. The client authenticates with HTTP Basic or
+/// with `client_id` and `client_secret` in the form, not both. No refresh
+/// token is issued; the client asks again once the token expires.
+#[utoipa::path(
+    post,
+    path = "/auth/token",
+    tag = "Authentication",
+    request_body(content = ClientTokenRequest, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Access token issued", body = ClientTokenResponse),
+        (status = 400, description = "Unsupported grant type or several authentication methods", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid client credentials", body = crate::app::error::ErrorResponse),
+        (status = 429, description = "Too many requests", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn token(
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    headers: HeaderMap,
+    request: ClientTokenRequest,
+) -> Result<ClientTokenResponse, AppError> {
+    let (client_id, client_secret) = client_credentials(&headers, request)?;
+    let response = state
+        .machine_client_service
+        .token(&client_id, &client_secret, &ctx)
+        .await;
+    metrics::track_token_operation("client_credentials", response.is_ok());
+    response
+}
+
+/// Where the client put its credentials: RFC 6749 forbids using more than
+/// one method in a request.
+pub fn client_credentials(
+    headers: &HeaderMap,
+    request: ClientTokenRequest,
+) -> Result<(String, String), AppError> {
+    match (
+        basic_credentials(headers),
+        request.client_id,
+        request.client_secret,
+    ) {
+        (Some(_), Some(_), _) | (Some(_), _, Some(_)) => Err(AppError::BadRequest(String::from(
+            "Use either HTTP Basic or client_id and client_secret, not both",
+        ))),
+        (Some(credentials), None, None) => Ok(credentials),
+        (None, Some(id), Some(secret)) => Ok((id, secret)),
+        _ => Err(AppError::Unauthorized(String::from(
+            "Missing client credentials",
+        ))),
+    }
+}
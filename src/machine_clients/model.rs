@@ -0,0 +1,61 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    utils::{
+        FromRow,
+        crypto::{random_bytes, sha256},
+    },
+};
+
+/// Role machine clients get when the administrator picks none.
+pub const DEFAULT_ROLE: &str = "service";
+
+/// A cron job or internal service that authenticates with its id and a
+/// secret instead of a passkey.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MachineClient {
+    pub id: Uuid,
+    pub name: String,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl FromRow for MachineClient {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            role: row.try_get("role")?,
+            created_at: row.try_get("created_at")?,
+            last_used_at: row.try_get("last_used_at")?,
+        })
+    }
+}
+
+/// A client secret in the form handed out once at creation. Only its hash
+/// is ever persisted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSecret(String);
+
+impl ClientSecret {
+    pub fn generate() -> Self {
+        let entropy: [u8; 32] = random_bytes();
+        Self(BASE64_URL_SAFE_NO_PAD.encode(entropy))
+    }
+
+    pub fn hash(&self) -> Vec<u8> {
+        Self::hash_input(&self.0)
+    }
+
+    pub fn hash_input(input: &str) -> Vec<u8> {
+        sha256(input.as_bytes()).to_vec()
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
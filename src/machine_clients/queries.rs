@@ -0,0 +1,28 @@
+/// Every query takes the tenant of the request as its last parameter.
+pub mod machine_clients {
+    /// Inserts nothing when the role does not exist or the tenant already
+    /// has a client of that name.
+    pub const INSERT: &str =
+        "INSERT INTO machine_clients (id, tenant_id, name, secret_hash, role, created_by)
+         SELECT $1, $6, $2, $3, r.name, $5 FROM roles r WHERE r.name = $4
+         ON CONFLICT (tenant_id, name) DO NOTHING
+         RETURNING id, name, role, created_at, last_used_at";
+
+    /// Matches on the secret's hash, and returns the role's permissions of
+    /// the moment, so granting the role a permission needs no new secret.
+    pub const AUTHENTICATE: &str = "UPDATE machine_clients c
+         SET last_used_at = NOW()
+         WHERE c.id = $1 AND c.secret_hash = $2 AND c.tenant_id = $3
+         RETURNING c.id, c.name, c.role, c.created_at, c.last_used_at,
+                   ARRAY(SELECT p.permission FROM role_permissions p
+                         WHERE p.role = c.role ORDER BY p.permission) AS permissions";
+
+    pub const DELETE: &str = "DELETE FROM machine_clients
+         WHERE id = $1 AND tenant_id = $2
+         RETURNING id, name, role, created_at, last_used_at";
+}
+
+pub mod roles {
+    /// Roles are shared by every tenant.
+    pub const EXISTS: &str = "SELECT EXISTS (SELECT 1 FROM roles WHERE name = $1)";
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    config::CircuitBreaker,
+    db_delete, db_insert, db_select, db_update,
+    machine_clients::{model::MachineClient, queries, traits::MachineClientRepository},
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl MachineClientRepository for Repository {
+    async fn create(
+        &self,
+        id: Uuid,
+        name: &str,
+        secret_hash: &[u8],
+        role: &str,
+        created_by: Uuid,
+    ) -> Result<Option<MachineClient>, AppError> {
+        let tenant = current_tenant();
+
+        db_insert!("machine_clients", {
+            self.base
+                .execute_prepared_opt(
+                    queries::machine_clients::INSERT,
+                    &[
+                        &id as &(dyn ToSql + Sync),
+                        &name,
+                        &secret_hash,
+                        &role,
+                        &created_by,
+                        &tenant,
+                    ],
+                )
+                .await
+        })?
+        .as_ref()
+        .map(MachineClient::from_row)
+        .transpose()
+    }
+
+    async fn role_exists(&self, role: &str) -> Result<bool, AppError> {
+        let row = db_select!("roles", {
+            self.base
+                .execute_prepared_opt(queries::roles::EXISTS, &[&role as &(dyn ToSql + Sync)])
+                .await
+        })?;
+        Ok(match row {
+            Some(row) => row.try_get(0)?,
+            None => false,
+        })
+    }
+
+    async fn authenticate(
+        &self,
+        id: Uuid,
+        secret_hash: &[u8],
+    ) -> Result<Option<(MachineClient, Vec<String>)>, AppError> {
+        let tenant = current_tenant();
+
+        db_update!("machine_clients", {
+            self.base
+                .execute_prepared_opt(
+                    queries::machine_clients::AUTHENTICATE,
+                    &[&id as &(dyn ToSql + Sync), &secret_hash, &tenant],
+                )
+                .await
+        })?
+        .as_ref()
+        .map(|row| Ok((MachineClient::from_row(row)?, row.try_get("permissions")?)))
+        .transpose()
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<Option<MachineClient>, AppError> {
+        let tenant = current_tenant();
+
+        db_delete!("machine_clients", {
+            self.base
+                .execute_prepared_opt(
+                    queries::machine_clients::DELETE,
+                    &[&id as &(dyn ToSql + Sync), &tenant],
+                )
+                .await
+        })?
+        .as_ref()
+        .map(MachineClient::from_row)
+        .transpose()
+    }
+}
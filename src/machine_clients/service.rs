@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::{
+        model::{AuditContext, AuditEntry, AuditEvent},
+        traits::AuditLogger,
+    },
+    auth::{
+        jwt::{AccessTokenClaims, JwtService, claims::JwtClaims},
+        model::Grants,
+    },
+    machine_clients::{
+        dto::{
+            ClientTokenResponse, CreateClientRequest, CreatedClientResponse, MachineClientResponse,
+        },
+        model::{ClientSecret, DEFAULT_ROLE, MachineClient},
+        traits::MachineClientRepository,
+    },
+};
+
+const INVALID_CLIENT: &str = "Invalid client credentials";
+
+pub struct MachineClientService<R, J, A>
+where
+    R: MachineClientRepository + 'static,
+    J: JwtService + 'static,
+    A: AuditLogger + 'static,
+{
+    client_repo: Arc<R>,
+    jwt_service: Arc<J>,
+    audit_logger: Arc<A>,
+}
+
+impl<R, J, A> MachineClientService<R, J, A>
+where
+    R: MachineClientRepository + 'static,
+    J: JwtService + 'static,
+    A: AuditLogger + 'static,
+{
+    pub fn new(client_repo: Arc<R>, jwt_service: Arc<J>, audit_logger: Arc<A>) -> Self {
+        Self {
+            client_repo,
+            jwt_service,
+            audit_logger,
+        }
+    }
+
+    /// Registers a client under a fresh secret, returned this once.
+    pub async fn create(
+        &self,
+        req: CreateClientRequest,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<CreatedClientResponse, AppError> {
+        let role = req.role.as_deref().unwrap_or(DEFAULT_ROLE);
+        let secret = ClientSecret::generate();
+        let result = self.insert(&req.name, role, &secret, actor).await;
+        self.audit_admin("create-machine-client", &req.name, &result, actor, ctx);
+
+        Ok(CreatedClientResponse {
+            client: result?.into(),
+            client_secret: secret.into_string(),
+        })
+    }
+
+    /// Tokens already issued to the client stay valid until they expire.
+    pub async fn delete(
+        &self,
+        client_id: Uuid,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<MachineClientResponse, AppError> {
+        let result = self.client_repo.delete(client_id).await.and_then(|client| {
+            client.ok_or_else(|| AppError::NotFound(String::from("Client not found")))
+        });
+        let name = result.as_ref().map_or("", |client| client.name.as_str());
+        self.audit_admin("delete-machine-client", name, &result, actor, ctx);
+        result.map(Into::into)
+    }
+
+    /// Exchanges client credentials for an access token carrying the
+    /// permissions of the client's role.
+    pub async fn token(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        ctx: &AuditContext,
+    ) -> Result<ClientTokenResponse, AppError> {
+        let result = self.issue(client_id, client_secret).await;
+
+        let details = match &result {
+            Ok((client, _)) => serde_json::json!({ "client_id": client_id, "client": client.name }),
+            Err(_) => serde_json::json!({ "client_id": client_id }),
+        };
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::ClientCredentials,
+                ctx,
+                None,
+                result.as_ref().map(|_| ()),
+            )
+            .with_details(details),
+        );
+
+        result.map(|(_, response)| response)
+    }
+
+    async fn insert(
+        &self,
+        name: &str,
+        role: &str,
+        secret: &ClientSecret,
+        actor: &AccessTokenClaims,
+    ) -> Result<MachineClient, AppError> {
+        if let Some(client) = self
+            .client_repo
+            .create(Uuid::new_v4(), name, &secret.hash(), role, *actor.sub())
+            .await?
+        {
+            return Ok(client);
+        }
+
+        if self.client_repo.role_exists(role).await? {
+            Err(AppError::AlreadyExists(format!(
+                "A client named {} already exists",
+                name
+            )))
+        } else {
+            Err(AppError::BadRequest(format!("Unknown role: {}", role)))
+        }
+    }
+
+    async fn issue(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<(MachineClient, ClientTokenResponse), AppError> {
+        let invalid = || AppError::Unauthorized(String::from(INVALID_CLIENT));
+        let id = Uuid::try_parse(client_id).map_err(|_| invalid())?;
+        let (client, permissions) = self
+            .client_repo
+            .authenticate(id, &ClientSecret::hash_input(client_secret))
+            .await?
+            .ok_or_else(invalid)?;
+
+        let grants = Grants {
+            roles: vec![client.role.clone()],
+            permissions,
+        };
+        let (access_token, claims) = self
+            .jwt_service
+            .issue_access_token(client.id, &client.name, grants)
+            .await?;
+
+        Ok((
+            client,
+            ClientTokenResponse {
+                access_token,
+                token_type: "Bearer",
+                expires_in: (claims.exp - Utc::now().timestamp()).max(0),
+            },
+        ))
+    }
+
+    fn audit_admin(
+        &self,
+        action: &str,
+        name: &str,
+        result: &Result<MachineClient, AppError>,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) {
+        let mut details = serde_json::json!({ "action": action, "client": name });
+        if let Ok(client) = result {
+            details["client_id"] = serde_json::Value::from(client.id.to_string());
+        }
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AdminAction,
+                ctx,
+                Some(actor.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*actor.sub())
+            .with_details(details),
+        );
+    }
+}
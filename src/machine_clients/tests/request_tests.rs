@@ -0,0 +1,90 @@
+use axum::http::{HeaderMap, HeaderValue, header::AUTHORIZATION};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::{
+    app::AppError,
+    machine_clients::{
+        dto::{ClientTokenRequest, CreateClientRequest, request::is_valid_client_name},
+        handler::client_credentials,
+    },
+    utils::Validatable,
+};
+
+fn token_request(client_id: Option<&str>, client_secret: Option<&str>) -> ClientTokenRequest {
+    ClientTokenRequest {
+        grant_type: String::from("client_credentials"),
+        client_id: client_id.map(str::to_owned),
+        client_secret: client_secret.map(str::to_owned),
+        scope: None,
+    }
+}
+
+fn basic(id: &str, secret: &str) -> HeaderMap {
+    let value = format!("Basic {}", STANDARD.encode(format!("{}:{}", id, secret)));
+    HeaderMap::from_iter([(AUTHORIZATION, HeaderValue::from_str(&value).unwrap())])
+}
+
+#[test]
+fn test_client_names() {
+    for name in ["nightly-report", "billing_sync", "0cron", "a"] {
+        assert!(is_valid_client_name(name), "{}", name);
+    }
+    let long = "a".repeat(64);
+    for name in [
+        "",
+        "-cron",
+        "Nightly",
+        "nightly report",
+        "café",
+        long.as_str(),
+    ] {
+        assert!(!is_valid_client_name(name), "{}", name);
+    }
+
+    let request = CreateClientRequest {
+        name: String::from("Nightly"),
+        role: None,
+    };
+    assert!(matches!(request.validate(), Err(AppError::BadRequest(_))));
+}
+
+#[test]
+fn test_only_client_credentials_grant_accepted() {
+    assert!(token_request(None, None).validate().is_ok());
+
+    let request = ClientTokenRequest {
+        grant_type: String::from("password"),
+        ..token_request(None, None)
+    };
+    assert!(matches!(request.validate(), Err(AppError::BadRequest(_))));
+}
+
+#[test]
+fn test_credentials_from_basic_or_body() {
+    let (id, secret) =
+        client_credentials(&basic("client", "s3cret"), token_request(None, None)).unwrap();
+    assert_eq!((id.as_str(), secret.as_str()), ("client", "s3cret"));
+
+    let (id, secret) = client_credentials(
+        &HeaderMap::new(),
+        token_request(Some("client"), Some("s3cret")),
+    )
+    .unwrap();
+    assert_eq!((id.as_str(), secret.as_str()), ("client", "s3cret"));
+}
+
+#[test]
+fn test_credentials_rejected_when_missing_or_doubled() {
+    assert!(matches!(
+        client_credentials(&basic("a", "b"), token_request(Some("a"), None)),
+        Err(AppError::BadRequest(_))
+    ));
+    assert!(matches!(
+        client_credentials(&HeaderMap::new(), token_request(Some("a"), None)),
+        Err(AppError::Unauthorized(_))
+    ));
+    assert!(matches!(
+        client_credentials(&HeaderMap::new(), token_request(None, None)),
+        Err(AppError::Unauthorized(_))
+    ));
+}
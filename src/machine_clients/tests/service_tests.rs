@@ -0,0 +1,265 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::model::{AuditContext, AuditEvent, AuditOutcome},
+    machine_clients::{
+        dto::CreateClientRequest,
+        model::{ClientSecret, MachineClient},
+        service::MachineClientService,
+        traits::MachineClientRepository,
+    },
+    utils::mocks::{MockAuditLogger, MockJwt, admin_claims},
+};
+
+const ROLES: [(&str, &[&str]); 2] = [
+    ("service", &["users:read"]),
+    ("admin", &["admin:actions", "users:read"]),
+];
+
+struct StoredClient {
+    client: MachineClient,
+    secret_hash: Vec<u8>,
+}
+
+#[derive(Default)]
+struct MockRepository {
+    clients: Mutex<Vec<StoredClient>>,
+}
+
+impl MachineClientRepository for MockRepository {
+    async fn create(
+        &self,
+        id: Uuid,
+        name: &str,
+        secret_hash: &[u8],
+        role: &str,
+        _: Uuid,
+    ) -> Result<Option<MachineClient>, AppError> {
+        let role_exists = self.role_exists(role).await?;
+        let mut clients = self.clients.lock().unwrap();
+        if !role_exists || clients.iter().any(|c| c.client.name == name) {
+            return Ok(None);
+        }
+        let client = MachineClient {
+            id,
+            name: name.to_owned(),
+            role: role.to_owned(),
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+        clients.push(StoredClient {
+            client: client.clone(),
+            secret_hash: secret_hash.to_vec(),
+        });
+        Ok(Some(client))
+    }
+
+    async fn role_exists(&self, role: &str) -> Result<bool, AppError> {
+        Ok(ROLES.iter().any(|(name, _)| *name == role))
+    }
+
+    async fn authenticate(
+        &self,
+        id: Uuid,
+        secret_hash: &[u8],
+    ) -> Result<Option<(MachineClient, Vec<String>)>, AppError> {
+        let mut clients = self.clients.lock().unwrap();
+        let Some(stored) = clients
+            .iter_mut()
+            .find(|c| c.client.id == id && c.secret_hash == secret_hash)
+        else {
+            return Ok(None);
+        };
+        stored.client.last_used_at = Some(Utc::now());
+        let permissions = ROLES
+            .iter()
+            .find(|(name, _)| *name == stored.client.role)
+            .map(|(_, permissions)| permissions.iter().map(|p| p.to_string()).collect())
+            .unwrap_or_default();
+        Ok(Some((stored.client.clone(), permissions)))
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<Option<MachineClient>, AppError> {
+        let mut clients = self.clients.lock().unwrap();
+        let index = clients.iter().position(|c| c.client.id == id);
+        Ok(index.map(|i| clients.remove(i).client))
+    }
+}
+
+type Service = MachineClientService<MockRepository, MockJwt, MockAuditLogger>;
+
+fn service() -> (Service, Arc<MockAuditLogger>) {
+    let audit = Arc::new(MockAuditLogger::default());
+    (
+        MachineClientService::new(
+            Arc::new(MockRepository::default()),
+            Arc::new(MockJwt::default()),
+            Arc::clone(&audit),
+        ),
+        audit,
+    )
+}
+
+fn request(name: &str, role: Option<&str>) -> CreateClientRequest {
+    CreateClientRequest {
+        name: name.to_owned(),
+        role: role.map(str::to_owned),
+    }
+}
+
+#[test]
+fn test_secrets_are_unique_and_hashed() {
+    let (a, b) = (ClientSecret::generate(), ClientSecret::generate());
+    assert_ne!(a, b);
+    assert_eq!(a.clone().into_string().len(), 43);
+    assert_eq!(a.hash(), ClientSecret::hash_input(&a.clone().into_string()));
+    assert_ne!(a.hash(), b.hash());
+}
+
+#[tokio::test]
+async fn test_created_client_gets_service_role_token() {
+    let (service, audit) = service();
+    let ctx = AuditContext::default();
+
+    let created = service
+        .create(
+            request("nightly-report", None),
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.client.role, "service");
+
+    let token = service
+        .token(
+            &created.client.client_id.to_string(),
+            &created.client_secret,
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(token.access_token, "token-for-nightly-report");
+    assert_eq!(token.token_type, "Bearer");
+    assert!((899..=900).contains(&token.expires_in));
+
+    let entries = audit.entries.lock().unwrap();
+    assert_eq!(entries[0].details["action"], "create-machine-client");
+    assert_eq!(entries[1].event, AuditEvent::ClientCredentials);
+    assert_eq!(entries[1].details["client"], "nightly-report");
+    assert_eq!(
+        entries[1].details["client_id"],
+        created.client.client_id.to_string()
+    );
+    assert!(entries.iter().all(|e| e.outcome == AuditOutcome::Success));
+}
+
+#[tokio::test]
+async fn test_wrong_secret_or_id_rejected() {
+    let (service, audit) = service();
+    let ctx = AuditContext::default();
+    let created = service
+        .create(
+            request("cron", None),
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    let client_id = created.client.client_id.to_string();
+
+    for (id, secret) in [
+        (client_id.as_str(), "wrong"),
+        ("not-a-uuid", created.client_secret.as_str()),
+        (&Uuid::new_v4().to_string(), created.client_secret.as_str()),
+    ] {
+        assert!(matches!(
+            service.token(id, secret, &ctx).await,
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    let entries = audit.entries.lock().unwrap();
+    assert!(
+        entries[1..]
+            .iter()
+            .all(|e| e.event == AuditEvent::ClientCredentials
+                && e.outcome != AuditOutcome::Success)
+    );
+    assert_eq!(entries[1].details["client_id"], client_id);
+}
+
+#[tokio::test]
+async fn test_duplicate_name_and_unknown_role_rejected() {
+    let (service, _) = service();
+    let ctx = AuditContext::default();
+    service
+        .create(
+            request("cron", None),
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        service
+            .create(
+                request("cron", None),
+                &admin_claims(&["admin:actions"]),
+                &ctx
+            )
+            .await,
+        Err(AppError::AlreadyExists(_))
+    ));
+    assert!(matches!(
+        service
+            .create(
+                request("other", Some("superuser")),
+                &admin_claims(&["admin:actions"]),
+                &ctx
+            )
+            .await,
+        Err(AppError::BadRequest(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_deleted_client_cannot_get_tokens() {
+    let (service, _) = service();
+    let ctx = AuditContext::default();
+    let created = service
+        .create(
+            request("cron", Some("admin")),
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    let id = created.client.client_id;
+
+    assert_eq!(
+        service
+            .delete(id, &admin_claims(&["admin:actions"]), &ctx)
+            .await
+            .unwrap()
+            .name,
+        "cron"
+    );
+    assert!(matches!(
+        service
+            .token(&id.to_string(), &created.client_secret, &ctx)
+            .await,
+        Err(AppError::Unauthorized(_))
+    ));
+    assert!(matches!(
+        service
+            .delete(id, &admin_claims(&["admin:actions"]), &ctx)
+            .await,
+        Err(AppError::NotFound(_))
+    ));
+}
@@ -0,0 +1,29 @@
+use std::future::Future;
+
+use uuid::Uuid;
+
+use crate::{app::AppError, machine_clients::model::MachineClient};
+
+pub trait MachineClientRepository: Send + Sync {
+    /// `None` when the role does not exist or the name is taken.
+    fn create(
+        &self,
+        id: Uuid,
+        name: &str,
+        secret_hash: &[u8],
+        role: &str,
+        created_by: Uuid,
+    ) -> impl Future<Output = Result<Option<MachineClient>, AppError>> + Send;
+    fn role_exists(&self, role: &str) -> impl Future<Output = Result<bool, AppError>> + Send;
+    /// The client and its role's permissions when the secret matches,
+    /// recording the use.
+    fn authenticate(
+        &self,
+        id: Uuid,
+        secret_hash: &[u8],
+    ) -> impl Future<Output = Result<Option<(MachineClient, Vec<String>)>, AppError>> + Send;
+    fn delete(
+        &self,
+        id: Uuid,
+    ) -> impl Future<Output = Result<Option<MachineClient>, AppError>> + Send;
+}
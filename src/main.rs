@@ -1,20 +1,112 @@
-use crate::app::{AppConfig, AppState, ServerConfig, create_router, init_tracing, start_server};
+use std::{io, pin::pin, process::ExitCode, time::Duration};
 
+use axum::Router;
+
+use crate::app::{
+    AppConfig, AppState, ServerConfig, StartupGate, create_router, init_tracing, start_server,
+};
+
+mod admin;
 mod app;
+mod audit;
 mod auth;
+mod banner;
+mod cleanup;
 mod config;
+mod credential_revocation;
+mod duplicates;
+#[cfg(feature = "enrollment-reminders")]
+mod enrollment;
+mod event_export;
+mod events;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod invitations;
+mod login_history;
+mod machine_clients;
+mod notification;
+mod reports;
+mod sessions;
+mod slo;
+mod suspensions;
+#[cfg(feature = "test-support")]
+#[cfg_attr(not(test), allow(dead_code))]
+mod testing;
+mod token_issuance;
+mod traffic;
+mod user_search;
 mod utils;
 
 #[tokio::main]
-async fn main() {
-    init_tracing();
+async fn main() -> ExitCode {
+    utils::crypto::install_tls_provider();
+    let _telemetry = init_tracing();
+    tracing::info!("Using the {} crypto backend", utils::crypto::backend_name());
+
+    let server_config = match ServerConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("Invalid server configuration: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let gate = StartupGate::new();
+    let server = start_server(gate.router(), &server_config);
+    let mut server = pin!(server);
+    let startup = config::StartupConfig::from_env();
 
-    let params = AppConfig::from_env().await;
-    let cors_layer = params.origin_config.create_cors_layer();
+    let app = tokio::select! {
+        result = &mut server => return exit_code(result, &server_config),
+        app = connect(startup.fail_after) => app,
+    };
+    match app {
+        Some(app) => {
+            gate.open(app);
+            tracing::info!("Connected to Postgres and Redis, accepting requests");
+        }
+        None => {
+            tracing::error!(
+                "Postgres or Redis still unavailable after {}s, giving up",
+                startup.fail_after.unwrap_or_default().as_secs()
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+
+    exit_code(server.await, &server_config)
+}
+
+/// Builds the application once its dependencies are up, or `None` if they
+/// are not within `fail_after`.
+async fn connect(fail_after: Option<Duration>) -> Option<Router> {
+    let params = match fail_after {
+        Some(fail_after) => tokio::time::timeout(fail_after, AppConfig::from_env())
+            .await
+            .ok()?,
+        None => AppConfig::from_env().await,
+    };
+    let cors_layer = params.origin_config.create_cors_layer(&params.cors_config);
 
     let state = AppState::new(params);
-    let app = create_router(state).layer(cors_layer);
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_config) = config::GrpcConfig::from_env() {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = grpc::serve(state, grpc_config).await {
+                tracing::error!("gRPC interface failed: {}", e);
+            }
+        });
+    }
+    Some(create_router(state).layer(cors_layer))
+}
 
-    let server_config = ServerConfig::default();
-    start_server(app, &server_config.bind_addr).await
+fn exit_code(result: io::Result<()>, server_config: &ServerConfig) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!("Server failed on {}: {}", server_config.bind_addr, e);
+            ExitCode::FAILURE
+        }
+    }
 }
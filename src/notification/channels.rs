@@ -0,0 +1,127 @@
+use std::{sync::Arc, time::Duration};
+
+use serde::Serialize;
+use url::Url;
+
+use crate::{
+    notification::{
+        model::{Channel, Notification, RenderedMessage},
+        traits::{Notifier, NotifyFuture},
+    },
+    utils::HttpClientService,
+};
+
+/// Posts the rendered payload straight to the URL stored in the routing rule.
+pub struct WebhookNotifier {
+    http: Arc<HttpClientService>,
+    timeout: Duration,
+}
+
+impl WebhookNotifier {
+    pub fn new(http: Arc<HttpClientService>, timeout: Duration) -> Self {
+        Self { http, timeout }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn channel(&self) -> Channel {
+        Channel::Webhook
+    }
+
+    fn send<'a>(
+        &'a self,
+        target: &'a str,
+        _notification: &'a Notification,
+        message: &'a RenderedMessage,
+    ) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            let request = self
+                .http
+                .client()
+                .post(target)
+                .timeout(self.timeout)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(message.body.clone());
+
+            self.http
+                .execute_guarded(request)
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct RelayPayload<'a> {
+    to: &'a str,
+    channel: Channel,
+    event: &'a str,
+    subject: Option<&'a str>,
+    body: &'a str,
+}
+
+/// Email, SMS and push are delivered through a provider-facing HTTP relay:
+/// the routing rule target is the recipient (address, phone number, device
+/// token) and the relay owns the provider integration.
+pub struct RelayNotifier {
+    channel: Channel,
+    endpoint: Url,
+    api_key: Option<Box<str>>,
+    http: Arc<HttpClientService>,
+    timeout: Duration,
+}
+
+impl RelayNotifier {
+    pub fn new(
+        channel: Channel,
+        endpoint: Url,
+        api_key: Option<Box<str>>,
+        http: Arc<HttpClientService>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            channel,
+            endpoint,
+            api_key,
+            http,
+            timeout,
+        }
+    }
+}
+
+impl Notifier for RelayNotifier {
+    fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    fn send<'a>(
+        &'a self,
+        target: &'a str,
+        notification: &'a Notification,
+        message: &'a RenderedMessage,
+    ) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            let payload = RelayPayload {
+                to: target,
+                channel: self.channel,
+                event: notification.event.as_str(),
+                subject: message.subject.as_deref(),
+                body: &message.body,
+            };
+
+            let mut request = self
+                .http
+                .client()
+                .post(self.endpoint.clone())
+                .timeout(self.timeout)
+                .json(&payload);
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+
+            self.http.execute(request).await?.error_for_status()?;
+            Ok(())
+        })
+    }
+}
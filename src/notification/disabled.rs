@@ -0,0 +1,14 @@
+use crate::notification::{model::Notification, traits::NotificationDispatcher};
+
+/// Stands in for `NotificationService` when the `notifications` feature is
+/// off, so callers keep dispatching without knowing it was compiled out.
+pub struct DisabledNotifications;
+
+impl NotificationDispatcher for DisabledNotifications {
+    fn dispatch(&self, notification: Notification) {
+        tracing::debug!(
+            event = notification.event.as_str(),
+            "Notifications are disabled, dropping event"
+        );
+    }
+}
@@ -0,0 +1,22 @@
+#[cfg(feature = "notifications")]
+pub(crate) mod channels;
+#[cfg(not(feature = "notifications"))]
+pub(crate) mod disabled;
+pub(crate) mod model;
+#[cfg(feature = "notifications")]
+mod queries;
+#[cfg(feature = "notifications")]
+pub(crate) mod repo;
+#[cfg(feature = "notifications")]
+pub(crate) mod service;
+#[cfg(feature = "notifications")]
+pub(crate) mod stream;
+#[cfg(feature = "notifications")]
+pub(crate) mod template;
+pub(crate) mod traits;
+
+#[cfg(feature = "notifications")]
+pub(crate) use repo::Repository;
+
+#[cfg(all(test, feature = "notifications"))]
+mod tests;
@@ -0,0 +1,175 @@
+#[cfg(feature = "notifications")]
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "notifications")]
+use crate::{app::AppError, utils::FromRow};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    PasskeyRegistered,
+    /// Passkeys of a compromised authenticator model were deleted by an admin.
+    CredentialsRevoked,
+    /// Sent straight to the address being verified, never through routing.
+    #[cfg(feature = "notifications")]
+    EmailVerification,
+    #[cfg(feature = "enrollment-reminders")]
+    EnrollmentReminder,
+}
+
+impl NotificationEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationEvent::PasskeyRegistered => "passkey_registered",
+            NotificationEvent::CredentialsRevoked => "credentials_revoked",
+            #[cfg(feature = "notifications")]
+            NotificationEvent::EmailVerification => "email_verification",
+            #[cfg(feature = "enrollment-reminders")]
+            NotificationEvent::EnrollmentReminder => "enrollment_reminder",
+        }
+    }
+}
+
+#[cfg(feature = "notifications")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Email,
+    Webhook,
+    Sms,
+    Push,
+}
+
+#[cfg(feature = "notifications")]
+impl Channel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Channel::Email => "email",
+            Channel::Webhook => "webhook",
+            Channel::Sms => "sms",
+            Channel::Push => "push",
+        }
+    }
+}
+
+#[cfg(feature = "notifications")]
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "notifications")]
+impl TryFrom<&str> for Channel {
+    type Error = AppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "email" => Ok(Channel::Email),
+            "webhook" => Ok(Channel::Webhook),
+            "sms" => Ok(Channel::Sms),
+            "push" => Ok(Channel::Push),
+            other => Err(AppError::InternalServer(format!(
+                "unknown notification channel: {}",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub user_id: Uuid,
+    pub username: String,
+    pub occurred_at: DateTime<Utc>,
+    pub details: serde_json::Value,
+}
+
+impl Notification {
+    pub fn new(
+        event: NotificationEvent,
+        user_id: Uuid,
+        username: &str,
+        details: serde_json::Value,
+    ) -> Self {
+        Self {
+            event,
+            user_id,
+            username: username.to_owned(),
+            occurred_at: Utc::now(),
+            details,
+        }
+    }
+}
+
+#[cfg(feature = "notifications")]
+/// A delivery rule: notifications for `event` go to `target` through `channel`,
+/// rendered in `locale`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingRule {
+    pub channel: Channel,
+    pub target: String,
+    pub locale: String,
+}
+
+#[cfg(feature = "notifications")]
+impl FromRow for RoutingRule {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        let channel: String = row.try_get("channel")?;
+
+        Ok(RoutingRule {
+            channel: Channel::try_from(channel.as_str())?,
+            target: row.try_get("target")?,
+            locale: row.try_get("locale")?,
+        })
+    }
+}
+
+#[cfg(feature = "notifications")]
+/// Template sources stored in the database, overriding files and built-ins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredTemplate {
+    pub subject: Option<String>,
+    pub body: String,
+}
+
+#[cfg(feature = "notifications")]
+impl FromRow for StoredTemplate {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(StoredTemplate {
+            subject: row.try_get("subject")?,
+            body: row.try_get("body")?,
+        })
+    }
+}
+
+#[cfg(feature = "notifications")]
+/// Sends that succeeded and failed for one notification. Unconfigured
+/// channels and unrenderable templates count as neither: retrying them
+/// cannot help.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeliveryOutcome {
+    pub delivered: usize,
+    pub failed: usize,
+}
+
+#[cfg(feature = "notifications")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedMessage {
+    pub subject: Option<String>,
+    pub body: String,
+}
+
+#[cfg(feature = "notifications")]
+#[derive(Debug, Clone, Serialize)]
+pub struct Branding {
+    pub name: String,
+    pub url: Option<String>,
+    pub logo_url: Option<String>,
+    pub support_contact: Option<String>,
+}
@@ -0,0 +1,19 @@
+pub mod notification_routes {
+    pub const SELECT_ENABLED_BY_EVENT: &str = "SELECT channel, target, locale
+         FROM notification_routes
+         WHERE event = $1 AND enabled = TRUE";
+}
+
+pub mod stream {
+    /// Shared by every instance; each reads through the same group.
+    pub const KEY: &str = "notifications:stream";
+    pub const GROUP: &str = "notification_workers";
+    /// Entry field holding the JSON-encoded notification.
+    pub const FIELD: &str = "notification";
+}
+
+pub mod notification_templates {
+    pub const SELECT_BY_KEY: &str = "SELECT subject, body
+         FROM notification_templates
+         WHERE event = $1 AND channel = $2 AND locale = $3";
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+
+use crate::{
+    app::AppError,
+    config::CircuitBreaker,
+    db_select,
+    notification::{
+        model::{Channel, NotificationEvent, RoutingRule, StoredTemplate},
+        queries,
+        traits::NotificationRepository,
+    },
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl NotificationRepository for Repository {
+    async fn routing_rules(&self, event: NotificationEvent) -> Result<Vec<RoutingRule>, AppError> {
+        let rows = db_select!("notification_routes", {
+            self.base
+                .execute_prepared(
+                    queries::notification_routes::SELECT_ENABLED_BY_EVENT,
+                    &[&event.as_str() as &(dyn tokio_postgres::types::ToSql + Sync)],
+                )
+                .await
+        })?;
+
+        rows.iter().map(RoutingRule::from_row).collect()
+    }
+
+    async fn stored_template(
+        &self,
+        event: NotificationEvent,
+        channel: Channel,
+        locale: &str,
+    ) -> Result<Option<StoredTemplate>, AppError> {
+        match db_select!("notification_templates", {
+            self.base
+                .execute_prepared_opt(
+                    queries::notification_templates::SELECT_BY_KEY,
+                    &[
+                        &event.as_str() as &(dyn tokio_postgres::types::ToSql + Sync),
+                        &channel.as_str(),
+                        &locale,
+                    ],
+                )
+                .await
+        })? {
+            Some(row) => Ok(Some(StoredTemplate::from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use redis::streams::StreamId;
+use tracing::Instrument;
+
+use crate::{
+    app::{
+        AppError,
+        middleware::metrics::{track_notification_processed, update_notification_stream},
+    },
+    notification::{
+        model::{Channel, DeliveryOutcome, Notification, RenderedMessage, RoutingRule},
+        stream::{NotificationStream, StreamConsumer, decode},
+        template::TemplateRenderer,
+        traits::{NotificationDispatcher, NotificationRepository, Notifier},
+    },
+};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+pub struct NotificationService<R>
+where
+    R: NotificationRepository + 'static,
+{
+    notification_repo: Arc<R>,
+    notifiers: Arc<HashMap<Channel, Arc<dyn Notifier>>>,
+    renderer: Arc<TemplateRenderer>,
+    stream: Option<Arc<NotificationStream>>,
+}
+
+impl<R> NotificationService<R>
+where
+    R: NotificationRepository + 'static,
+{
+    pub fn new(
+        notification_repo: Arc<R>,
+        notifiers: Vec<Arc<dyn Notifier>>,
+        renderer: Arc<TemplateRenderer>,
+    ) -> Self {
+        let notifiers = notifiers
+            .into_iter()
+            .map(|notifier| (notifier.channel(), notifier))
+            .collect();
+
+        Self {
+            notification_repo,
+            notifiers: Arc::new(notifiers),
+            renderer,
+            stream: None,
+        }
+    }
+
+    /// Queues dispatched notifications on the stream instead of delivering
+    /// them in-process; `spawn_worker` then consumes it.
+    pub fn with_stream(mut self, stream: Option<Arc<NotificationStream>>) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    /// Delivers to every enabled route for the event and returns how many
+    /// deliveries succeeded. A failing route never prevents the others.
+    pub async fn deliver(&self, notification: &Notification) -> usize {
+        match self.try_deliver(notification).await {
+            Ok(outcome) => outcome.delivered,
+            Err(e) => {
+                let event = notification.event.as_str();
+                tracing::error!(event, "Failed to load notification routes: {}", e);
+                0
+            }
+        }
+    }
+
+    /// Like `deliver`, but fails when the routes cannot be loaded and counts
+    /// failed sends, so the stream worker knows whether to retry.
+    pub async fn try_deliver(
+        &self,
+        notification: &Notification,
+    ) -> Result<DeliveryOutcome, AppError> {
+        let event = notification.event.as_str();
+        let rules = self
+            .notification_repo
+            .routing_rules(notification.event)
+            .await?;
+
+        let mut outcome = DeliveryOutcome::default();
+        for rule in rules {
+            let Some(notifier) = self.notifiers.get(&rule.channel) else {
+                tracing::warn!(event, channel = %rule.channel, "No notifier configured for channel");
+                continue;
+            };
+
+            let Some(message) = self.render(notification, &rule).await else {
+                continue;
+            };
+
+            match notifier.send(&rule.target, notification, &message).await {
+                Ok(()) => outcome.delivered += 1,
+                Err(e) => {
+                    outcome.failed += 1;
+                    tracing::error!(event, channel = %rule.channel, "Notification delivery failed: {}", e)
+                }
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Keeps this instance consuming the stream, reconnecting after a
+    /// failure. Does nothing when notifications are delivered in-process.
+    pub fn spawn_worker(self: &Arc<Self>) {
+        let Some(stream) = self.stream.clone() else {
+            return;
+        };
+        let service = Arc::clone(self);
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = service.consume(&stream).await {
+                    tracing::error!("Notification stream consumer lost: {}", e);
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+
+    async fn consume(&self, stream: &NotificationStream) -> Result<(), AppError> {
+        let mut consumer = stream.connect().await?;
+        let max_attempts = stream.config().max_attempts;
+        tracing::info!(consumer = %stream.config().consumer, "Consuming notification stream");
+
+        loop {
+            let stale = consumer.claim_stale().await?;
+            for id in &stale.exhausted {
+                tracing::error!(entry = %id, max_attempts, "Dropping undeliverable notification");
+                track_notification_processed("dropped");
+            }
+            consumer.ack(&stale.exhausted).await?;
+            self.process(&mut consumer, &stale.retried).await?;
+
+            let entries = consumer.read_new().await?;
+            self.process(&mut consumer, &entries).await?;
+
+            let (length, pending) = consumer.stats().await?;
+            update_notification_stream(length, pending);
+        }
+    }
+
+    /// Acknowledges the entries that need no retry. The others stay pending
+    /// until a consumer claims them again.
+    async fn process(
+        &self,
+        consumer: &mut StreamConsumer,
+        entries: &[StreamId],
+    ) -> Result<(), AppError> {
+        let mut done = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            let notification = match decode(entry) {
+                Ok(notification) => notification,
+                Err(e) => {
+                    tracing::warn!(entry = %entry.id, "Malformed notification stream entry: {}", e);
+                    track_notification_processed("dropped");
+                    done.push(entry.id.clone());
+                    continue;
+                }
+            };
+
+            match self.try_deliver(&notification).await {
+                Ok(outcome) if outcome.failed == 0 => {
+                    track_notification_processed("delivered");
+                    done.push(entry.id.clone());
+                }
+                Ok(_) => track_notification_processed("retried"),
+                Err(e) => {
+                    let event = notification.event.as_str();
+                    tracing::error!(event, "Failed to load notification routes: {}", e);
+                    track_notification_processed("retried");
+                }
+            }
+        }
+
+        consumer.ack(&done).await?;
+        Ok(())
+    }
+
+    async fn render(
+        &self,
+        notification: &Notification,
+        rule: &RoutingRule,
+    ) -> Option<RenderedMessage> {
+        let event = notification.event.as_str();
+
+        let stored = self
+            .notification_repo
+            .stored_template(notification.event, rule.channel, &rule.locale)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(event, "Falling back to file templates: {}", e);
+                None
+            });
+
+        self.renderer
+            .render(notification, rule.channel, &rule.locale, stored.as_ref())
+            .inspect_err(|e| {
+                tracing::error!(event, channel = %rule.channel, "Failed to render notification: {}", e)
+            })
+            .ok()
+    }
+}
+
+impl<R> NotificationDispatcher for NotificationService<R>
+where
+    R: NotificationRepository + 'static,
+{
+    fn dispatch(&self, notification: Notification) {
+        let service = Self {
+            notification_repo: Arc::clone(&self.notification_repo),
+            notifiers: Arc::clone(&self.notifiers),
+            renderer: Arc::clone(&self.renderer),
+            stream: self.stream.clone(),
+        };
+
+        tokio::spawn(
+            async move {
+                if let Some(stream) = &service.stream {
+                    match stream.enqueue(&notification).await {
+                        Ok(()) => return,
+                        Err(e) => tracing::warn!(
+                            event = notification.event.as_str(),
+                            "Notification stream unavailable, delivering in-process: {}",
+                            e
+                        ),
+                    }
+                }
+                service.deliver(&notification).await;
+            }
+            .in_current_span(),
+        );
+    }
+}
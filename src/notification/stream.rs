@@ -0,0 +1,225 @@
+use std::{sync::Arc, time::Duration};
+
+use redis::{
+    AsyncCommands, AsyncConnectionConfig, Client, RedisError,
+    aio::{ConnectionManager, MultiplexedConnection},
+    streams::{
+        StreamClaimReply, StreamId, StreamPendingCountReply, StreamPendingReply, StreamReadOptions,
+        StreamReadReply,
+    },
+};
+
+use crate::{
+    app::AppError,
+    config::{CircuitBreaker, NotificationStreamConfig},
+    notification::{model::Notification, queries::stream},
+    redis_stream_read, redis_stream_write,
+    utils::BaseRedisRepository,
+};
+
+/// Entries read or claimed per round trip.
+const BATCH_SIZE: usize = 32;
+/// How long a read waits for new entries before stale ones are checked again.
+const BLOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Notifications queued on a Redis stream and consumed through a consumer
+/// group, so each is delivered by one instance and retried by another if
+/// that instance stops before acknowledging it.
+pub struct NotificationStream {
+    base: BaseRedisRepository,
+    client: Client,
+    config: NotificationStreamConfig,
+}
+
+impl NotificationStream {
+    pub fn new(
+        client: Client,
+        conn_manager: ConnectionManager,
+        circuit_breaker: Arc<CircuitBreaker>,
+        config: NotificationStreamConfig,
+    ) -> Self {
+        Self {
+            base: BaseRedisRepository::new(conn_manager, circuit_breaker),
+            client,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &NotificationStreamConfig {
+        &self.config
+    }
+
+    pub async fn enqueue(&self, notification: &Notification) -> Result<(), AppError> {
+        let payload = serde_json::to_string(notification)?;
+        let max_len = self.config.max_len;
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let _: String = redis_stream_write!({
+                    redis::cmd("XADD")
+                        .arg(stream::KEY)
+                        .arg("MAXLEN")
+                        .arg("~")
+                        .arg(max_len)
+                        .arg("*")
+                        .arg(stream::FIELD)
+                        .arg(&payload)
+                        .query_async(&mut conn)
+                        .await
+                })?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Opens a connection of its own, since a blocking read would stall
+    /// every other command sharing it, and joins the consumer group.
+    pub async fn connect(&self) -> Result<StreamConsumer, RedisError> {
+        let connection_config = AsyncConnectionConfig::new()
+            .set_response_timeout(Some(BLOCK_TIMEOUT + Duration::from_secs(5)));
+        let conn = self
+            .client
+            .get_multiplexed_async_connection_with_config(&connection_config)
+            .await?;
+
+        let mut consumer = StreamConsumer {
+            conn,
+            config: self.config.clone(),
+        };
+        consumer.join_group().await?;
+        Ok(consumer)
+    }
+}
+
+/// Entries claimed back from consumers that did not acknowledge them in time.
+pub struct StaleEntries {
+    pub retried: Vec<StreamId>,
+    /// Ids delivered `max_attempts` times already, to be dropped.
+    pub exhausted: Vec<String>,
+}
+
+pub struct StreamConsumer {
+    conn: MultiplexedConnection,
+    config: NotificationStreamConfig,
+}
+
+impl StreamConsumer {
+    /// Creates the group on first use, starting from the oldest entry so
+    /// notifications queued before any worker ran are not skipped.
+    async fn join_group(&mut self) -> Result<(), RedisError> {
+        let created: Result<(), RedisError> = redis_stream_write!({
+            redis::cmd("XGROUP")
+                .arg("CREATE")
+                .arg(stream::KEY)
+                .arg(stream::GROUP)
+                .arg("0")
+                .arg("MKSTREAM")
+                .query_async(&mut self.conn)
+                .await
+        });
+
+        match created {
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            other => other,
+        }
+    }
+
+    /// Entries never delivered to the group, waiting up to `BLOCK_TIMEOUT`.
+    pub async fn read_new(&mut self) -> Result<Vec<StreamId>, RedisError> {
+        let options = StreamReadOptions::default()
+            .group(stream::GROUP, self.config.consumer.as_ref())
+            .count(BATCH_SIZE)
+            .block(BLOCK_TIMEOUT.as_millis() as usize);
+
+        let reply: Option<StreamReadReply> = redis_stream_read!({
+            self.conn
+                .xread_options(&[stream::KEY], &[">"], &options)
+                .await
+        })?;
+
+        Ok(reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .collect())
+    }
+
+    /// Takes over entries left unacknowledged for `claim_idle`, whichever
+    /// consumer read them, except those out of attempts.
+    pub async fn claim_stale(&mut self) -> Result<StaleEntries, RedisError> {
+        let min_idle_ms = self.config.claim_idle.as_millis() as u64;
+
+        let pending: StreamPendingCountReply = redis_stream_read!({
+            redis::cmd("XPENDING")
+                .arg(stream::KEY)
+                .arg(stream::GROUP)
+                .arg("IDLE")
+                .arg(min_idle_ms)
+                .arg("-")
+                .arg("+")
+                .arg(BATCH_SIZE)
+                .query_async(&mut self.conn)
+                .await
+        })?;
+
+        let (exhausted, stale): (Vec<_>, Vec<_>) = pending
+            .ids
+            .into_iter()
+            .partition(|entry| entry.times_delivered >= self.config.max_attempts as usize);
+        let exhausted = exhausted.into_iter().map(|entry| entry.id).collect();
+        let stale: Vec<String> = stale.into_iter().map(|entry| entry.id).collect();
+
+        if stale.is_empty() {
+            return Ok(StaleEntries {
+                retried: Vec::new(),
+                exhausted,
+            });
+        }
+
+        let claimed: StreamClaimReply = redis_stream_write!({
+            self.conn
+                .xclaim(
+                    stream::KEY,
+                    stream::GROUP,
+                    self.config.consumer.as_ref(),
+                    min_idle_ms,
+                    &stale,
+                )
+                .await
+        })?;
+
+        Ok(StaleEntries {
+            retried: claimed.ids,
+            exhausted,
+        })
+    }
+
+    pub async fn ack(&mut self, ids: &[String]) -> Result<(), RedisError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let _: usize =
+            redis_stream_write!({ self.conn.xack(stream::KEY, stream::GROUP, ids).await })?;
+        Ok(())
+    }
+
+    /// The stream length and the entries read but not yet acknowledged.
+    pub async fn stats(&mut self) -> Result<(u64, u64), RedisError> {
+        let length: u64 = redis_stream_read!({ self.conn.xlen(stream::KEY).await })?;
+        let pending: StreamPendingReply =
+            redis_stream_read!({ self.conn.xpending(stream::KEY, stream::GROUP).await })?;
+
+        Ok((length, pending.count() as u64))
+    }
+}
+
+/// The notification carried by an entry. A malformed entry never becomes
+/// deliverable, so callers acknowledge it instead of retrying.
+pub fn decode(entry: &StreamId) -> Result<Notification, AppError> {
+    let payload: String = entry.get(stream::FIELD).ok_or_else(|| {
+        AppError::InternalServer(format!("stream entry {} has no notification", entry.id))
+    })?;
+
+    Ok(serde_json::from_str(&payload)?)
+}
@@ -0,0 +1,203 @@
+use std::path::PathBuf;
+
+use minijinja::Environment;
+use serde::Serialize;
+
+use crate::{
+    app::AppError,
+    notification::model::{Branding, Channel, Notification, RenderedMessage, StoredTemplate},
+};
+
+const SUBJECT: &str = "subject";
+const BODY: &str = "body";
+
+/// Templates shipped with the binary, keyed by `{event}/{channel}.{part}.{locale}.j2`.
+const BUILT_IN: &[(&str, &str)] = &[
+    (
+        "passkey_registered/email.subject.en.j2",
+        include_str!("templates/passkey_registered/email.subject.en.j2"),
+    ),
+    (
+        "passkey_registered/email.body.en.j2",
+        include_str!("templates/passkey_registered/email.body.en.j2"),
+    ),
+    (
+        "passkey_registered/sms.body.en.j2",
+        include_str!("templates/passkey_registered/sms.body.en.j2"),
+    ),
+    (
+        "passkey_registered/push.subject.en.j2",
+        include_str!("templates/passkey_registered/push.subject.en.j2"),
+    ),
+    (
+        "passkey_registered/push.body.en.j2",
+        include_str!("templates/passkey_registered/push.body.en.j2"),
+    ),
+    (
+        "passkey_registered/webhook.body.en.j2",
+        include_str!("templates/passkey_registered/webhook.body.en.j2"),
+    ),
+    (
+        "credentials_revoked/email.subject.en.j2",
+        include_str!("templates/credentials_revoked/email.subject.en.j2"),
+    ),
+    (
+        "credentials_revoked/email.body.en.j2",
+        include_str!("templates/credentials_revoked/email.body.en.j2"),
+    ),
+    (
+        "credentials_revoked/sms.body.en.j2",
+        include_str!("templates/credentials_revoked/sms.body.en.j2"),
+    ),
+    (
+        "credentials_revoked/push.subject.en.j2",
+        include_str!("templates/credentials_revoked/push.subject.en.j2"),
+    ),
+    (
+        "credentials_revoked/push.body.en.j2",
+        include_str!("templates/credentials_revoked/push.body.en.j2"),
+    ),
+    (
+        "credentials_revoked/webhook.body.en.j2",
+        include_str!("templates/credentials_revoked/webhook.body.en.j2"),
+    ),
+    (
+        "email_verification/email.subject.en.j2",
+        include_str!("templates/email_verification/email.subject.en.j2"),
+    ),
+    (
+        "email_verification/email.body.en.j2",
+        include_str!("templates/email_verification/email.body.en.j2"),
+    ),
+    (
+        "enrollment_reminder/email.subject.en.j2",
+        include_str!("templates/enrollment_reminder/email.subject.en.j2"),
+    ),
+    (
+        "enrollment_reminder/email.body.en.j2",
+        include_str!("templates/enrollment_reminder/email.body.en.j2"),
+    ),
+    (
+        "enrollment_reminder/sms.body.en.j2",
+        include_str!("templates/enrollment_reminder/sms.body.en.j2"),
+    ),
+    (
+        "enrollment_reminder/push.subject.en.j2",
+        include_str!("templates/enrollment_reminder/push.subject.en.j2"),
+    ),
+    (
+        "enrollment_reminder/push.body.en.j2",
+        include_str!("templates/enrollment_reminder/push.body.en.j2"),
+    ),
+    (
+        "enrollment_reminder/webhook.body.en.j2",
+        include_str!("templates/enrollment_reminder/webhook.body.en.j2"),
+    ),
+];
+
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    notification: &'a Notification,
+    brand: &'a Branding,
+    locale: &'a str,
+}
+
+/// Renders notifications with a lookup order of database override, then a
+/// file in the template directory, then the built-in template. Missing
+/// locales fall back to the default locale.
+pub struct TemplateRenderer {
+    env: Environment<'static>,
+    branding: Branding,
+    default_locale: Box<str>,
+}
+
+impl TemplateRenderer {
+    pub fn new(template_dir: Option<PathBuf>, branding: Branding, default_locale: &str) -> Self {
+        let mut env = Environment::new();
+        env.set_loader(move |name| {
+            if let Some(dir) = &template_dir {
+                match std::fs::read_to_string(dir.join(name)) {
+                    Ok(source) => return Ok(Some(source)),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => {
+                        return Err(minijinja::Error::new(
+                            minijinja::ErrorKind::InvalidOperation,
+                            format!("failed to read template {}: {}", name, e),
+                        ));
+                    }
+                }
+            }
+
+            Ok(BUILT_IN
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, source)| source.to_string()))
+        });
+
+        Self {
+            env,
+            branding,
+            default_locale: default_locale.into(),
+        }
+    }
+
+    pub fn render(
+        &self,
+        notification: &Notification,
+        channel: Channel,
+        locale: &str,
+        stored: Option<&StoredTemplate>,
+    ) -> Result<RenderedMessage, AppError> {
+        let ctx = TemplateContext {
+            notification,
+            brand: &self.branding,
+            locale,
+        };
+
+        if let Some(stored) = stored {
+            return Ok(RenderedMessage {
+                subject: stored
+                    .subject
+                    .as_deref()
+                    .map(|source| self.env.render_str(source, &ctx))
+                    .transpose()?,
+                body: self.env.render_str(&stored.body, &ctx)?,
+            });
+        }
+
+        let event = notification.event.as_str();
+        let body = self
+            .render_part(event, channel, BODY, locale, &ctx)?
+            .ok_or_else(|| {
+                AppError::InternalServer(format!(
+                    "no {} template for event {} in locale {}",
+                    channel, event, locale
+                ))
+            })?;
+
+        Ok(RenderedMessage {
+            subject: self.render_part(event, channel, SUBJECT, locale, &ctx)?,
+            body,
+        })
+    }
+
+    fn render_part(
+        &self,
+        event: &str,
+        channel: Channel,
+        part: &str,
+        locale: &str,
+        ctx: &TemplateContext<'_>,
+    ) -> Result<Option<String>, AppError> {
+        for candidate in [locale, &*self.default_locale] {
+            let name = format!("{}/{}.{}.{}.j2", event, channel, part, candidate);
+            match self.env.get_template(&name) {
+                Ok(template) => return Ok(Some(template.render(ctx)?)),
+                Err(e) if e.kind() == minijinja::ErrorKind::TemplateNotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(None)
+    }
+}
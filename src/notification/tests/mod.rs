@@ -0,0 +1,6 @@
+#[cfg(test)]
+mod service_tests;
+#[cfg(test)]
+mod stream_tests;
+#[cfg(test)]
+mod template_tests;
@@ -0,0 +1,240 @@
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    notification::{
+        model::{
+            Branding, Channel, DeliveryOutcome, Notification, NotificationEvent, RenderedMessage,
+            RoutingRule, StoredTemplate,
+        },
+        service::NotificationService,
+        template::TemplateRenderer,
+        traits::{NotificationRepository, Notifier, NotifyFuture},
+    },
+};
+
+struct MockRepository {
+    rules: Result<Vec<RoutingRule>, ()>,
+}
+
+impl NotificationRepository for MockRepository {
+    async fn routing_rules(&self, _: NotificationEvent) -> Result<Vec<RoutingRule>, AppError> {
+        self.rules
+            .clone()
+            .map_err(|_| AppError::InternalServer(String::from("db down")))
+    }
+
+    async fn stored_template(
+        &self,
+        _: NotificationEvent,
+        _: Channel,
+        _: &str,
+    ) -> Result<Option<StoredTemplate>, AppError> {
+        Ok(None)
+    }
+}
+
+struct MockNotifier {
+    channel: Channel,
+    fail: bool,
+    sent: Arc<Mutex<Vec<String>>>,
+}
+
+impl Notifier for MockNotifier {
+    fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    fn send<'a>(
+        &'a self,
+        target: &'a str,
+        _: &'a Notification,
+        _: &'a RenderedMessage,
+    ) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            if self.fail {
+                return Err(AppError::ServiceUnavailable(String::from("provider down")));
+            }
+            self.sent.lock().unwrap().push(target.to_owned());
+            Ok(())
+        })
+    }
+}
+
+fn rule(channel: Channel, target: &str) -> RoutingRule {
+    RoutingRule {
+        channel,
+        target: target.to_owned(),
+        locale: String::from("en"),
+    }
+}
+
+fn renderer() -> Arc<TemplateRenderer> {
+    let branding = Branding {
+        name: String::from("Example"),
+        url: None,
+        logo_url: None,
+        support_contact: None,
+    };
+    Arc::new(TemplateRenderer::new(None, branding, "en"))
+}
+
+fn notifier(channel: Channel, fail: bool, sent: &Arc<Mutex<Vec<String>>>) -> Arc<dyn Notifier> {
+    Arc::new(MockNotifier {
+        channel,
+        fail,
+        sent: Arc::clone(sent),
+    })
+}
+
+fn notification() -> Notification {
+    Notification::new(
+        NotificationEvent::PasskeyRegistered,
+        Uuid::new_v4(),
+        "john_doe",
+        serde_json::Value::Null,
+    )
+}
+
+#[tokio::test]
+async fn test_deliver_routes_to_configured_channels() {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let repo = MockRepository {
+        rules: Ok(vec![
+            rule(Channel::Webhook, "https://hooks.example.com/a"),
+            rule(Channel::Email, "security@example.com"),
+        ]),
+    };
+    let service = NotificationService::new(
+        Arc::new(repo),
+        vec![
+            notifier(Channel::Webhook, false, &sent),
+            notifier(Channel::Email, false, &sent),
+        ],
+        renderer(),
+    );
+
+    let delivered = service.deliver(&notification()).await;
+
+    assert_eq!(delivered, 2);
+    assert_eq!(
+        *sent.lock().unwrap(),
+        vec!["https://hooks.example.com/a", "security@example.com"]
+    );
+}
+
+#[tokio::test]
+async fn test_deliver_skips_unconfigured_channel() {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let repo = MockRepository {
+        rules: Ok(vec![
+            rule(Channel::Sms, "+390000000"),
+            rule(Channel::Webhook, "https://hooks.example.com/a"),
+        ]),
+    };
+    let service = NotificationService::new(
+        Arc::new(repo),
+        vec![notifier(Channel::Webhook, false, &sent)],
+        renderer(),
+    );
+
+    let delivered = service.deliver(&notification()).await;
+
+    assert_eq!(delivered, 1);
+}
+
+#[tokio::test]
+async fn test_deliver_continues_after_failure() {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let repo = MockRepository {
+        rules: Ok(vec![
+            rule(Channel::Push, "device-token"),
+            rule(Channel::Webhook, "https://hooks.example.com/a"),
+        ]),
+    };
+    let service = NotificationService::new(
+        Arc::new(repo),
+        vec![
+            notifier(Channel::Push, true, &sent),
+            notifier(Channel::Webhook, false, &sent),
+        ],
+        renderer(),
+    );
+
+    let delivered = service.deliver(&notification()).await;
+
+    assert_eq!(delivered, 1);
+    assert_eq!(*sent.lock().unwrap(), vec!["https://hooks.example.com/a"]);
+}
+
+#[tokio::test]
+async fn test_deliver_without_routes() {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let service = NotificationService::new(
+        Arc::new(MockRepository { rules: Err(()) }),
+        vec![notifier(Channel::Webhook, false, &sent)],
+        renderer(),
+    );
+
+    let delivered = service.deliver(&notification()).await;
+
+    assert_eq!(delivered, 0);
+    assert!(sent.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_channel_round_trip() {
+    for channel in [
+        Channel::Email,
+        Channel::Webhook,
+        Channel::Sms,
+        Channel::Push,
+    ] {
+        assert_eq!(Channel::try_from(channel.as_str()).unwrap(), channel);
+    }
+    assert!(Channel::try_from("fax").is_err());
+}
+
+#[tokio::test]
+async fn test_try_deliver_counts_failed_sends() {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let repo = MockRepository {
+        rules: Ok(vec![
+            rule(Channel::Push, "device-token"),
+            rule(Channel::Sms, "+390000000"),
+            rule(Channel::Webhook, "https://hooks.example.com/a"),
+        ]),
+    };
+    let service = NotificationService::new(
+        Arc::new(repo),
+        vec![
+            notifier(Channel::Push, true, &sent),
+            notifier(Channel::Webhook, false, &sent),
+        ],
+        renderer(),
+    );
+
+    let outcome = service.try_deliver(&notification()).await.unwrap();
+
+    assert_eq!(
+        outcome,
+        DeliveryOutcome {
+            delivered: 1,
+            failed: 1,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_try_deliver_fails_without_routes() {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let service = NotificationService::new(
+        Arc::new(MockRepository { rules: Err(()) }),
+        vec![notifier(Channel::Webhook, false, &sent)],
+        renderer(),
+    );
+
+    assert!(service.try_deliver(&notification()).await.is_err());
+}
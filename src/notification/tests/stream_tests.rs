@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use redis::{Value, streams::StreamId};
+use uuid::Uuid;
+
+use crate::notification::{
+    model::{Notification, NotificationEvent},
+    stream::decode,
+};
+
+fn entry(fields: &[(&str, &str)]) -> StreamId {
+    StreamId {
+        id: String::from("1700000000000-0"),
+        map: fields
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.to_string(),
+                    Value::BulkString(value.as_bytes().to_vec()),
+                )
+            })
+            .collect::<HashMap<_, _>>(),
+        ..StreamId::default()
+    }
+}
+
+#[test]
+fn test_decode_round_trips_notification() {
+    let notification = Notification::new(
+        NotificationEvent::PasskeyRegistered,
+        Uuid::new_v4(),
+        "john_doe",
+        serde_json::json!({ "device": "Laptop" }),
+    );
+    let payload = serde_json::to_string(&notification).unwrap();
+
+    let decoded = decode(&entry(&[("notification", &payload)])).unwrap();
+
+    assert_eq!(decoded.event, notification.event);
+    assert_eq!(decoded.user_id, notification.user_id);
+    assert_eq!(decoded.username, "john_doe");
+    assert_eq!(decoded.occurred_at, notification.occurred_at);
+    assert_eq!(decoded.details, notification.details);
+}
+
+#[test]
+fn test_decode_rejects_malformed_entries() {
+    assert!(decode(&entry(&[])).is_err());
+    assert!(decode(&entry(&[("notification", "{not json")])).is_err());
+    assert!(decode(&entry(&[("notification", r#"{"event":"unknown"}"#)])).is_err());
+}
@@ -0,0 +1,186 @@
+use uuid::Uuid;
+
+use crate::notification::{
+    model::{Branding, Channel, Notification, NotificationEvent, StoredTemplate},
+    template::TemplateRenderer,
+};
+
+fn branding() -> Branding {
+    Branding {
+        name: String::from("Acme"),
+        url: Some(String::from("https://acme.example")),
+        logo_url: None,
+        support_contact: Some(String::from("help@acme.example")),
+    }
+}
+
+fn notification() -> Notification {
+    Notification::new(
+        NotificationEvent::PasskeyRegistered,
+        Uuid::nil(),
+        "john_doe",
+        serde_json::json!({ "credential_id": "abc" }),
+    )
+}
+
+#[test]
+fn test_render_built_in_email() {
+    let renderer = TemplateRenderer::new(None, branding(), "en");
+
+    let message = renderer
+        .render(&notification(), Channel::Email, "en", None)
+        .unwrap();
+
+    assert_eq!(
+        message.subject.as_deref(),
+        Some("[Acme] New passkey registered")
+    );
+    assert!(message.body.contains("Hello john_doe"));
+    assert!(message.body.contains("help@acme.example"));
+}
+
+#[test]
+fn test_render_webhook_payload_is_json() {
+    let renderer = TemplateRenderer::new(None, branding(), "en");
+
+    let message = renderer
+        .render(&notification(), Channel::Webhook, "en", None)
+        .unwrap();
+    let payload: serde_json::Value = serde_json::from_str(&message.body).unwrap();
+
+    assert_eq!(message.subject, None);
+    assert_eq!(payload["event"], "passkey_registered");
+    assert_eq!(payload["brand"], "Acme");
+    assert_eq!(payload["details"]["credential_id"], "abc");
+}
+
+#[test]
+fn test_render_unknown_locale_falls_back_to_default() {
+    let renderer = TemplateRenderer::new(None, branding(), "en");
+
+    let message = renderer
+        .render(&notification(), Channel::Sms, "fr", None)
+        .unwrap();
+
+    assert!(message.body.starts_with("Acme:"));
+}
+
+#[test]
+fn test_render_stored_template_overrides_built_in() {
+    let renderer = TemplateRenderer::new(None, branding(), "en");
+    let stored = StoredTemplate {
+        subject: Some(String::from("Nuova passkey su {{ brand.name }}")),
+        body: String::from("Ciao {{ notification.username }} ({{ locale }})"),
+    };
+
+    let message = renderer
+        .render(&notification(), Channel::Email, "it", Some(&stored))
+        .unwrap();
+
+    assert_eq!(message.subject.as_deref(), Some("Nuova passkey su Acme"));
+    assert_eq!(message.body, "Ciao john_doe (it)");
+}
+
+#[test]
+fn test_render_template_directory_overrides_built_in() {
+    let dir = std::env::temp_dir().join(format!("rs-server-templates-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(dir.join("passkey_registered")).unwrap();
+    std::fs::write(
+        dir.join("passkey_registered/push.body.en.j2"),
+        "Custom push for {{ notification.username }}",
+    )
+    .unwrap();
+    let renderer = TemplateRenderer::new(Some(dir.clone()), branding(), "en");
+
+    let message = renderer
+        .render(&notification(), Channel::Push, "en", None)
+        .unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+
+    assert_eq!(message.body, "Custom push for john_doe");
+    assert_eq!(message.subject.as_deref(), Some("New passkey registered"));
+}
+
+#[cfg(feature = "enrollment-reminders")]
+#[test]
+fn test_render_enrollment_reminder_includes_link() {
+    let renderer = TemplateRenderer::new(None, branding(), "en");
+    let notification = Notification::new(
+        NotificationEvent::EnrollmentReminder,
+        Uuid::nil(),
+        "john_doe",
+        serde_json::json!({ "enrollment_link": "https://app.example/enroll?username=john_doe" }),
+    );
+
+    let message = renderer
+        .render(&notification, Channel::Sms, "en", None)
+        .unwrap();
+
+    assert!(
+        message
+            .body
+            .contains("https://app.example/enroll?username=john_doe")
+    );
+}
+
+#[test]
+fn test_render_email_verification_prefers_link_over_token() {
+    let renderer = TemplateRenderer::new(None, branding(), "en");
+    let verification = |link: Option<&str>| {
+        Notification::new(
+            NotificationEvent::EmailVerification,
+            Uuid::nil(),
+            "john_doe",
+            serde_json::json!({ "token": "abc123", "link": link, "expires_in_minutes": 60 }),
+        )
+    };
+
+    let with_link = renderer
+        .render(
+            &verification(Some("https://app.example/verify?token=abc123")),
+            Channel::Email,
+            "en",
+            None,
+        )
+        .unwrap();
+    let without_link = renderer
+        .render(&verification(None), Channel::Email, "en", None)
+        .unwrap();
+
+    assert_eq!(
+        with_link.subject.as_deref(),
+        Some("[Acme] Verify your email address")
+    );
+    assert!(
+        with_link
+            .body
+            .contains("https://app.example/verify?token=abc123")
+    );
+    assert!(!with_link.body.contains("Verification code"));
+    assert!(without_link.body.contains("Verification code: abc123"));
+    assert!(without_link.body.contains("expires in 60 minutes"));
+}
+
+#[test]
+fn test_render_credentials_revoked_points_to_recovery() {
+    let renderer = TemplateRenderer::new(None, branding(), "en");
+    let notification = Notification::new(
+        NotificationEvent::CredentialsRevoked,
+        Uuid::nil(),
+        "john_doe",
+        serde_json::json!({
+            "aaguid": Uuid::nil(),
+            "reason": "CVE-2024-12345",
+            "credential_ids": ["abc", "def"],
+            "recovery_required": true,
+        }),
+    );
+
+    let message = renderer
+        .render(&notification, Channel::Email, "en", None)
+        .unwrap();
+
+    assert!(message.body.contains("revoked 2 passkey(s)"));
+    assert!(message.body.contains("Reason: CVE-2024-12345"));
+    assert!(message.body.contains("recovery codes"));
+}
@@ -0,0 +1,47 @@
+#[cfg(feature = "notifications")]
+use std::{future::Future, pin::Pin};
+
+use crate::notification::model::Notification;
+#[cfg(feature = "notifications")]
+use crate::{
+    app::AppError,
+    notification::model::{
+        Channel, NotificationEvent, RenderedMessage, RoutingRule, StoredTemplate,
+    },
+};
+
+#[cfg(feature = "notifications")]
+pub type NotifyFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + 'a>>;
+
+#[cfg(feature = "notifications")]
+/// A delivery mechanism. Channels are stored side by side in a registry,
+/// so the trait is object safe and returns a boxed future.
+pub trait Notifier: Send + Sync {
+    fn channel(&self) -> Channel;
+    fn send<'a>(
+        &'a self,
+        target: &'a str,
+        notification: &'a Notification,
+        message: &'a RenderedMessage,
+    ) -> NotifyFuture<'a>;
+}
+
+#[cfg(feature = "notifications")]
+pub trait NotificationRepository: Send + Sync {
+    fn routing_rules(
+        &self,
+        event: NotificationEvent,
+    ) -> impl Future<Output = Result<Vec<RoutingRule>, AppError>> + Send;
+    fn stored_template(
+        &self,
+        event: NotificationEvent,
+        channel: Channel,
+        locale: &str,
+    ) -> impl Future<Output = Result<Option<StoredTemplate>, AppError>> + Send;
+}
+
+/// Entry point used by other features: fire and forget, delivery never
+/// blocks or fails the calling request.
+pub trait NotificationDispatcher: Send + Sync {
+    fn dispatch(&self, notification: Notification);
+}
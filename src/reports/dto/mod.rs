@@ -0,0 +1,7 @@
+pub(crate) mod request;
+pub(crate) mod response;
+
+pub(crate) use request::{ReportFormat, UnenrolledQuery};
+pub(crate) use response::{
+    AgeBucketCounts, CsvResponse, UnenrolledReportResponse, UnenrolledUserEntry,
+};
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    app::AppError, auth::model::UserStatus, impl_validated_query_request,
+    reports::model::UnenrolledFilter, utils::Validatable,
+};
+
+pub const DEFAULT_UNENROLLED_LIMIT: u32 = 100;
+pub const MAX_UNENROLLED_LIMIT: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    /// A spreadsheet-safe download with one row per user.
+    Csv,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UnenrolledQuery {
+    /// `pending`, `active` or `suspended`; all of them when omitted
+    #[param(example = "pending")]
+    pub status: Option<String>,
+    /// Number of users to list; the bucket counts always cover all of them
+    #[param(example = 100, minimum = 1, maximum = 10000)]
+    pub limit: Option<u32>,
+    /// `json` (default) or `csv`
+    #[param(inline)]
+    pub format: Option<ReportFormat>,
+}
+
+impl UnenrolledQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_UNENROLLED_LIMIT)
+    }
+
+    pub fn format(&self) -> ReportFormat {
+        self.format.unwrap_or_default()
+    }
+
+    pub fn to_filter(&self, now: DateTime<Utc>) -> UnenrolledFilter {
+        UnenrolledFilter {
+            status: self.status.clone(),
+            now,
+            limit: i64::from(self.limit()),
+        }
+    }
+}
+
+impl Validatable for UnenrolledQuery {
+    fn validate(&self) -> Result<(), AppError> {
+        if !(1..=MAX_UNENROLLED_LIMIT).contains(&self.limit()) {
+            return Err(AppError::BadRequest(format!(
+                "limit must be between 1 and {}",
+                MAX_UNENROLLED_LIMIT
+            )));
+        }
+
+        if let Some(status) = &self.status
+            && !matches!(
+                UserStatus::parse(status),
+                Some(UserStatus::Pending | UserStatus::Active | UserStatus::Suspended)
+            )
+        {
+            return Err(AppError::BadRequest(format!(
+                "Unknown user status: {}",
+                status
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_query_request!(UnenrolledQuery);
@@ -0,0 +1,165 @@
+use axum::{
+    Json,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::reports::model::{AgeBucket, AgeCounts, UnenrolledUser};
+
+const CSV_HEADER: &str = "id,username,status,created_at,age_days,age_bucket";
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UnenrolledReportResponse {
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub generated_at: String,
+    /// Every matching user, including those past `limit`.
+    #[schema(example = 42)]
+    pub total: i64,
+    pub age_buckets: AgeBucketCounts,
+    /// Oldest sign-ups first.
+    pub users: Vec<UnenrolledUserEntry>,
+}
+
+impl UnenrolledReportResponse {
+    pub fn new(now: DateTime<Utc>, counts: AgeCounts, users: Vec<UnenrolledUser>) -> Self {
+        Self {
+            generated_at: now.to_rfc3339(),
+            total: counts.total(),
+            age_buckets: counts.into(),
+            users: users
+                .into_iter()
+                .map(|user| UnenrolledUserEntry::new(user, now))
+                .collect(),
+        }
+    }
+
+    /// The listed users, without the summary.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push_str("\r\n");
+
+        for user in &self.users {
+            let fields = [
+                user.id.to_string(),
+                csv_field(&user.username),
+                user.status.clone(),
+                user.created_at.clone(),
+                user.age_days.to_string(),
+                user.age_bucket.to_string(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+
+        csv
+    }
+}
+
+impl IntoResponse for UnenrolledReportResponse {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq, Eq)]
+pub struct AgeBucketCounts {
+    /// Signed up within the last day.
+    #[schema(example = 3)]
+    pub day: i64,
+    /// One to seven days ago.
+    #[schema(example = 10)]
+    pub week: i64,
+    /// Seven to thirty days ago.
+    #[schema(example = 20)]
+    pub month: i64,
+    /// More than thirty days ago.
+    #[schema(example = 9)]
+    pub older: i64,
+}
+
+impl From<AgeCounts> for AgeBucketCounts {
+    fn from(counts: AgeCounts) -> Self {
+        Self {
+            day: counts.day,
+            week: counts.week,
+            month: counts.month,
+            older: counts.older,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UnenrolledUserEntry {
+    pub id: Uuid,
+    #[schema(example = "john_doe")]
+    pub username: String,
+    #[schema(example = "pending")]
+    pub status: String,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub created_at: String,
+    /// Whole days since sign-up.
+    #[schema(example = 12)]
+    pub age_days: i64,
+    #[schema(example = "month")]
+    pub age_bucket: &'static str,
+}
+
+impl UnenrolledUserEntry {
+    fn new(user: UnenrolledUser, now: DateTime<Utc>) -> Self {
+        let age = now - user.created_at;
+
+        Self {
+            id: user.id,
+            username: user.username,
+            status: user.status,
+            created_at: user.created_at.to_rfc3339(),
+            age_days: age.num_days(),
+            age_bucket: AgeBucket::for_age(age).as_str(),
+        }
+    }
+}
+
+/// A CSV body offered as a file download.
+pub struct CsvResponse {
+    pub filename: &'static str,
+    pub body: String,
+}
+
+impl IntoResponse for CsvResponse {
+    fn into_response(self) -> Response {
+        (
+            [
+                (
+                    header::CONTENT_TYPE,
+                    String::from("text/csv; charset=utf-8"),
+                ),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", self.filename),
+                ),
+            ],
+            self.body,
+        )
+            .into_response()
+    }
+}
+
+/// Quotes what would break the row and defuses values a spreadsheet would
+/// run as a formula.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    auth::permissions::EnrollmentRead,
+    reports::dto::{CsvResponse, ReportFormat, UnenrolledQuery, UnenrolledReportResponse},
+};
+
+const UNENROLLED_CSV_FILENAME: &str = "unenrolled-users.csv";
+
+/// Users without a passkey
+///
+/// Lists active and pending users that have no credential, oldest sign-up
+/// first, with counts by how long ago they signed up. `format=csv` downloads
+/// the list instead. Requires `enrollment:read`.
+#[utoipa::path(
+    get,
+    path = "/admin/reports/unenrolled",
+    tag = "Admin",
+    params(UnenrolledQuery),
+    responses(
+        (status = 200, description = "Unenrolled users", content(
+            (UnenrolledReportResponse = "application/json"),
+            (String = "text/csv")
+        )),
+        (status = 400, description = "Invalid query parameters", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn unenrolled(
+    _admin: RequirePermission<EnrollmentRead>,
+    State(state): State<Arc<AppState>>,
+    query: UnenrolledQuery,
+) -> Result<Response, AppError> {
+    let report = state.report_service.unenrolled(&query).await?;
+
+    Ok(match query.format() {
+        ReportFormat::Json => report.into_response(),
+        ReportFormat::Csv => CsvResponse {
+            filename: UNENROLLED_CSV_FILENAME,
+            body: report.to_csv(),
+        }
+        .into_response(),
+    })
+}
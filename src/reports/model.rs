@@ -0,0 +1,90 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::{app::AppError, utils::FromRow};
+
+/// How long ago a user signed up, in the steps the report groups them by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeBucket {
+    Day,
+    Week,
+    Month,
+    Older,
+}
+
+impl AgeBucket {
+    /// Same boundaries as `COUNT_BY_AGE`.
+    pub fn for_age(age: Duration) -> Self {
+        if age < Duration::days(1) {
+            AgeBucket::Day
+        } else if age < Duration::days(7) {
+            AgeBucket::Week
+        } else if age < Duration::days(30) {
+            AgeBucket::Month
+        } else {
+            AgeBucket::Older
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AgeBucket::Day => "day",
+            AgeBucket::Week => "week",
+            AgeBucket::Month => "month",
+            AgeBucket::Older => "older",
+        }
+    }
+}
+
+/// Which users the report covers. Ages are measured against `now`, so the
+/// listed users and the bucket counts agree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnenrolledFilter {
+    pub status: Option<String>,
+    pub now: DateTime<Utc>,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnenrolledUser {
+    pub id: Uuid,
+    pub username: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FromRow for UnenrolledUser {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(UnenrolledUser {
+            id: row.try_get("id")?,
+            username: row.try_get("username")?,
+            status: row.try_get("status")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AgeCounts {
+    pub day: i64,
+    pub week: i64,
+    pub month: i64,
+    pub older: i64,
+}
+
+impl AgeCounts {
+    pub fn total(&self) -> i64 {
+        self.day + self.week + self.month + self.older
+    }
+}
+
+impl FromRow for AgeCounts {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(AgeCounts {
+            day: row.try_get("day")?,
+            week: row.try_get("week")?,
+            month: row.try_get("month")?,
+            older: row.try_get("older")?,
+        })
+    }
+}
@@ -0,0 +1,25 @@
+pub mod unenrolled {
+    /// Active or pending accounts without a passkey. Removed credentials are
+    /// deleted rather than flagged, so users who lost every passkey match too.
+    pub const SELECT: &str = "SELECT u.id, u.username, u.status, u.created_at
+         FROM users u
+         WHERE u.is_active
+           AND NOT EXISTS (SELECT 1 FROM credentials c WHERE c.user_id = u.id)
+           AND ($1::text IS NULL OR u.status = $1)
+           AND u.tenant_id = $3
+         ORDER BY u.created_at
+         LIMIT $2";
+
+    pub const COUNT_BY_AGE: &str = "SELECT
+             COUNT(*) FILTER (WHERE u.created_at > $2::timestamptz - INTERVAL '1 day') AS day,
+             COUNT(*) FILTER (WHERE u.created_at <= $2::timestamptz - INTERVAL '1 day'
+                                AND u.created_at > $2::timestamptz - INTERVAL '7 days') AS week,
+             COUNT(*) FILTER (WHERE u.created_at <= $2::timestamptz - INTERVAL '7 days'
+                                AND u.created_at > $2::timestamptz - INTERVAL '30 days') AS month,
+             COUNT(*) FILTER (WHERE u.created_at <= $2::timestamptz - INTERVAL '30 days') AS older
+         FROM users u
+         WHERE u.is_active
+           AND NOT EXISTS (SELECT 1 FROM credentials c WHERE c.user_id = u.id)
+           AND ($1::text IS NULL OR u.status = $1)
+           AND u.tenant_id = $3";
+}
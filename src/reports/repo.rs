@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    config::CircuitBreaker,
+    db_select,
+    reports::{
+        model::{AgeCounts, UnenrolledFilter, UnenrolledUser},
+        queries,
+        traits::ReportRepository,
+    },
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl ReportRepository for Repository {
+    async fn unenrolled_users(
+        &self,
+        filter: &UnenrolledFilter,
+    ) -> Result<Vec<UnenrolledUser>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("users", {
+            self.base
+                .execute_prepared(
+                    queries::unenrolled::SELECT,
+                    &[
+                        &filter.status as &(dyn ToSql + Sync),
+                        &filter.limit,
+                        &tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        rows.iter().map(UnenrolledUser::from_row).collect()
+    }
+
+    async fn unenrolled_by_age(&self, filter: &UnenrolledFilter) -> Result<AgeCounts, AppError> {
+        let tenant = current_tenant();
+
+        let row = db_select!("users", {
+            self.base
+                .execute_prepared_one(
+                    queries::unenrolled::COUNT_BY_AGE,
+                    &[&filter.status as &(dyn ToSql + Sync), &filter.now, &tenant],
+                )
+                .await
+        })?;
+
+        AgeCounts::from_row(&row)
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::{
+    app::AppError,
+    reports::{
+        dto::{UnenrolledQuery, UnenrolledReportResponse},
+        traits::ReportRepository,
+    },
+};
+
+pub struct ReportService<R>
+where
+    R: ReportRepository + 'static,
+{
+    report_repo: Arc<R>,
+}
+
+impl<R> ReportService<R>
+where
+    R: ReportRepository + 'static,
+{
+    pub fn new(report_repo: Arc<R>) -> Self {
+        Self { report_repo }
+    }
+
+    /// Users who never finished enrolling a passkey, or lost every one.
+    pub async fn unenrolled(
+        &self,
+        query: &UnenrolledQuery,
+    ) -> Result<UnenrolledReportResponse, AppError> {
+        let filter = query.to_filter(Utc::now());
+        let counts = self.report_repo.unenrolled_by_age(&filter).await?;
+        let users = self.report_repo.unenrolled_users(&filter).await?;
+
+        Ok(UnenrolledReportResponse::new(filter.now, counts, users))
+    }
+}
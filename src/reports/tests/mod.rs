@@ -0,0 +1,6 @@
+#[cfg(test)]
+mod model_tests;
+#[cfg(test)]
+mod query_tests;
+#[cfg(test)]
+mod service_tests;
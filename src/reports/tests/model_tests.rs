@@ -0,0 +1,32 @@
+use chrono::Duration;
+
+use crate::reports::model::{AgeBucket, AgeCounts};
+
+#[test]
+fn test_age_bucket_boundaries() {
+    let cases = [
+        (Duration::zero(), AgeBucket::Day),
+        (Duration::hours(23), AgeBucket::Day),
+        (Duration::days(1), AgeBucket::Week),
+        (Duration::days(7) - Duration::seconds(1), AgeBucket::Week),
+        (Duration::days(7), AgeBucket::Month),
+        (Duration::days(30), AgeBucket::Older),
+        (Duration::days(400), AgeBucket::Older),
+    ];
+
+    for (age, bucket) in cases {
+        assert_eq!(AgeBucket::for_age(age), bucket, "age {}", age);
+    }
+}
+
+#[test]
+fn test_age_counts_total() {
+    let counts = AgeCounts {
+        day: 1,
+        week: 2,
+        month: 3,
+        older: 4,
+    };
+
+    assert_eq!(counts.total(), 10);
+}
@@ -0,0 +1,55 @@
+use chrono::Utc;
+
+use crate::{
+    app::AppError,
+    reports::dto::{ReportFormat, UnenrolledQuery},
+    utils::Validatable,
+};
+
+#[test]
+fn test_default_query_is_valid() {
+    let query = UnenrolledQuery::default();
+    let now = Utc::now();
+    let filter = query.to_filter(now);
+
+    assert!(query.validate().is_ok());
+    assert_eq!(filter.limit, 100);
+    assert_eq!(filter.status, None);
+    assert_eq!(filter.now, now);
+    assert_eq!(query.format(), ReportFormat::Json);
+}
+
+#[test]
+fn test_limit_out_of_range() {
+    for limit in [0, 10_001] {
+        let query = UnenrolledQuery {
+            limit: Some(limit),
+            ..Default::default()
+        };
+        assert!(matches!(query.validate(), Err(AppError::BadRequest(_))));
+    }
+}
+
+#[test]
+fn test_status_must_be_pending_or_active() {
+    for status in ["pending", "active"] {
+        let query = UnenrolledQuery {
+            status: Some(status.to_string()),
+            ..Default::default()
+        };
+        assert!(query.validate().is_ok(), "{}", status);
+    }
+
+    let query = UnenrolledQuery {
+        status: Some(String::from("deleted")),
+        ..Default::default()
+    };
+    assert!(matches!(query.validate(), Err(AppError::BadRequest(_))));
+}
+
+#[test]
+fn test_format_parses_lowercase() {
+    let query: UnenrolledQuery = serde_json::from_str(r#"{"format":"csv"}"#).unwrap();
+
+    assert_eq!(query.format(), ReportFormat::Csv);
+}
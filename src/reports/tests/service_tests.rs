@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    reports::{
+        dto::UnenrolledQuery,
+        model::{AgeCounts, UnenrolledFilter, UnenrolledUser},
+        service::ReportService,
+        traits::ReportRepository,
+    },
+};
+
+#[derive(Default)]
+struct MockRepository {
+    users: Vec<UnenrolledUser>,
+    counts: AgeCounts,
+    filters: Mutex<Vec<UnenrolledFilter>>,
+}
+
+impl ReportRepository for MockRepository {
+    async fn unenrolled_users(
+        &self,
+        filter: &UnenrolledFilter,
+    ) -> Result<Vec<UnenrolledUser>, AppError> {
+        self.filters.lock().unwrap().push(filter.clone());
+        Ok(self.users.clone())
+    }
+
+    async fn unenrolled_by_age(&self, filter: &UnenrolledFilter) -> Result<AgeCounts, AppError> {
+        self.filters.lock().unwrap().push(filter.clone());
+        Ok(self.counts)
+    }
+}
+
+fn user(username: &str, age: Duration) -> UnenrolledUser {
+    UnenrolledUser {
+        id: Uuid::new_v4(),
+        username: username.to_string(),
+        status: String::from("pending"),
+        created_at: Utc::now() - age,
+    }
+}
+
+#[tokio::test]
+async fn test_unenrolled_builds_report() {
+    let repo = Arc::new(MockRepository {
+        users: vec![user("old_user", Duration::days(45))],
+        counts: AgeCounts {
+            day: 2,
+            week: 0,
+            month: 1,
+            older: 1,
+        },
+        ..Default::default()
+    });
+    let service = ReportService::new(Arc::clone(&repo));
+    let query = UnenrolledQuery {
+        status: Some(String::from("pending")),
+        limit: Some(1),
+        ..Default::default()
+    };
+
+    let report = service.unenrolled(&query).await.unwrap();
+
+    assert_eq!(report.total, 4);
+    assert_eq!(report.age_buckets.day, 2);
+    assert_eq!(report.users.len(), 1);
+    assert_eq!(report.users[0].age_days, 45);
+    assert_eq!(report.users[0].age_bucket, "older");
+
+    // Both queries measure ages from the same instant.
+    let filters = repo.filters.lock().unwrap();
+    assert_eq!(filters.len(), 2);
+    assert_eq!(filters[0], filters[1]);
+    assert_eq!(filters[0].status.as_deref(), Some("pending"));
+    assert_eq!(filters[0].limit, 1);
+}
+
+#[tokio::test]
+async fn test_csv_escapes_usernames() {
+    let repo = Arc::new(MockRepository {
+        users: vec![
+            user("plain", Duration::hours(1)),
+            user("a,\"b\"", Duration::hours(1)),
+            user("=HYPERLINK(1)", Duration::hours(1)),
+        ],
+        ..Default::default()
+    });
+    let service = ReportService::new(repo);
+
+    let csv = service
+        .unenrolled(&UnenrolledQuery::default())
+        .await
+        .unwrap()
+        .to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(
+        lines[0],
+        "id,username,status,created_at,age_days,age_bucket"
+    );
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].contains(",plain,pending,"));
+    assert!(lines[2].contains(",\"a,\"\"b\"\"\",pending,"));
+    assert!(lines[3].contains(",'=HYPERLINK(1),pending,"));
+    assert!(lines[1].ends_with(",0,day"));
+}
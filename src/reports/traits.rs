@@ -0,0 +1,19 @@
+use std::future::Future;
+
+use crate::{
+    app::AppError,
+    reports::model::{AgeCounts, UnenrolledFilter, UnenrolledUser},
+};
+
+pub trait ReportRepository: Send + Sync {
+    /// Oldest sign-ups first, up to `filter.limit`.
+    fn unenrolled_users(
+        &self,
+        filter: &UnenrolledFilter,
+    ) -> impl Future<Output = Result<Vec<UnenrolledUser>, AppError>> + Send;
+    /// Counts every matching user, regardless of the limit.
+    fn unenrolled_by_age(
+        &self,
+        filter: &UnenrolledFilter,
+    ) -> impl Future<Output = Result<AgeCounts, AppError>> + Send;
+}
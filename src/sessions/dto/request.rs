@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    impl_validated_json_request,
+    sessions::model::{IpNetwork, SessionCriteria},
+    utils::{Validatable, validate_text},
+};
+
+pub const MAX_REVOKED_USERS: usize = 1000;
+
+/// Criteria narrowing which sessions are revoked. At least one is required,
+/// so an empty body cannot sign out everyone.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RevokeSessionsRequest {
+    /// Only sessions of these users
+    #[serde(default)]
+    pub user_ids: Vec<Uuid>,
+    /// Only sessions issued before this instant (RFC 3339)
+    pub issued_before: Option<DateTime<Utc>>,
+    /// Only sessions issued to clients in this CIDR range
+    #[schema(example = "203.0.113.0/24")]
+    pub ip_range: Option<String>,
+    /// Only sessions of users holding this role
+    #[schema(example = "admin")]
+    pub role: Option<String>,
+    /// Count the matching sessions without revoking them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl RevokeSessionsRequest {
+    pub fn to_criteria(&self) -> SessionCriteria {
+        SessionCriteria {
+            user_ids: (!self.user_ids.is_empty()).then(|| self.user_ids.clone()),
+            issued_before: self.issued_before,
+            network: self
+                .ip_range
+                .as_deref()
+                .and_then(|range| range.parse().ok()),
+            role: self.role.as_deref().map(|role| role.trim().to_owned()),
+        }
+    }
+}
+
+impl Validatable for RevokeSessionsRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.user_ids.is_empty()
+            && self.issued_before.is_none()
+            && self.ip_range.is_none()
+            && self.role.is_none()
+        {
+            return Err(AppError::BadRequest(String::from(
+                "At least one of user_ids, issued_before, ip_range and role is required",
+            )));
+        }
+
+        if self.user_ids.len() > MAX_REVOKED_USERS {
+            return Err(AppError::BadRequest(format!(
+                "At most {} users can be given at once",
+                MAX_REVOKED_USERS
+            )));
+        }
+
+        if let Some(range) = &self.ip_range {
+            range.parse::<IpNetwork>()?;
+        }
+
+        if let Some(role) = &self.role {
+            validate_text(role, "Role")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_json_request!(RevokeSessionsRequest);
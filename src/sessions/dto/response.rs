@@ -0,0 +1,24 @@
+use axum::{Json, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct RevokeSessionsResponse {
+    pub dry_run: bool,
+    /// Unexpired refresh tokens matching the criteria, including ones
+    /// already rotated or revoked.
+    #[schema(example = 1280)]
+    pub matched: u64,
+    #[schema(example = 1280)]
+    pub revoked: u64,
+    /// Tokens that could not be blacklisted; running the request again
+    /// retries them.
+    #[schema(example = 0)]
+    pub failed: u64,
+}
+
+impl IntoResponse for RevokeSessionsResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
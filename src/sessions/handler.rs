@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    audit::model::AuditContext,
+    auth::permissions::AdminActions,
+    sessions::dto::{RevokeSessionsRequest, RevokeSessionsResponse},
+};
+
+/// Revoke sessions in bulk
+///
+/// Blacklists the unexpired refresh tokens matching every given criterion,
+/// found through the token issuance log, so their holders must log in
+/// again. With `dry_run` only counts them. Requires `admin:actions`.
+#[utoipa::path(
+    post,
+    path = "/admin/sessions/revoke",
+    tag = "Admin",
+    request_body = RevokeSessionsRequest,
+    responses(
+        (status = 200, description = "Sessions revoked or counted", body = RevokeSessionsResponse),
+        (status = 400, description = "No criteria or an invalid one", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn revoke(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    request: RevokeSessionsRequest,
+) -> Result<RevokeSessionsResponse, AppError> {
+    state.session_service.revoke(request, &admin, &ctx).await
+}
@@ -0,0 +1,108 @@
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{app::AppError, utils::FromRow};
+
+/// Which live refresh tokens a bulk revocation targets. Every criterion set
+/// narrows the match; `role` is the user's role today, not at issuance.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionCriteria {
+    pub user_ids: Option<Vec<Uuid>>,
+    pub issued_before: Option<DateTime<Utc>>,
+    pub network: Option<IpNetwork>,
+    pub role: Option<String>,
+}
+
+/// The newest refresh token of a session in the issuance log, not expired
+/// yet. It may already have been revoked; blacklisting it again is harmless.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveSession {
+    pub user_id: Uuid,
+    pub jti: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl FromRow for LiveSession {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(LiveSession {
+            user_id: row.try_get("user_id")?,
+            jti: row.try_get("jti")?,
+            issued_at: row.try_get("issued_at")?,
+            expires_at: row.try_get("expires_at")?,
+        })
+    }
+}
+
+/// Where the previous page ended: sessions sort newest first, then by jti.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionCursor {
+    pub issued_at: DateTime<Utc>,
+    pub jti: String,
+}
+
+impl From<&LiveSession> for SessionCursor {
+    fn from(session: &LiveSession) -> Self {
+        Self {
+            issued_at: session.issued_at,
+            jti: session.jti.clone(),
+        }
+    }
+}
+
+/// An address range in CIDR notation. A bare address is a range of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl FromStr for IpNetwork {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || AppError::BadRequest(format!("Invalid IP range: {}", value));
+
+        let (address, prefix) = match value.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (value.trim(), None),
+        };
+        let address: IpAddr = address.parse().map_err(|_| invalid())?;
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .ok_or_else(invalid)?,
+            None => max_prefix,
+        };
+
+        // Host bits are dropped, so `10.1.2.3/8` means `10.0.0.0/8`.
+        let network = match address {
+            IpAddr::V4(ip) => IpAddr::V4(Ipv4Addr::from(mask_v4(ip, prefix))),
+            IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(mask_v6(ip, prefix))),
+        };
+        Ok(Self { network, prefix })
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+fn mask_v4(ip: Ipv4Addr, prefix: u8) -> u32 {
+    u32::from(ip) & u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0)
+}
+
+fn mask_v6(ip: Ipv6Addr, prefix: u8) -> u128 {
+    u128::from(ip) & u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0)
+}
@@ -0,0 +1,40 @@
+pub mod token_issuances {
+    /// A page of unexpired refresh tokens matching the criteria, newest
+    /// first, after the cursor in `$5`/`$6`. The issuance log is the only
+    /// index of a user's sessions; a token a refresh rotated out is left
+    /// out, so each session shows once, as its newest token. The role is
+    /// checked as granted today. Only sessions of the tenant in `$8` are
+    /// visible.
+    pub const SELECT_LIVE: &str = "SELECT user_id, jti, issued_at, expires_at
+         FROM token_issuances ti
+         WHERE expires_at > NOW()
+           AND ($1::uuid[] IS NULL OR user_id = ANY($1))
+           AND ($2::timestamptz IS NULL OR issued_at < $2)
+           AND ($3::text IS NULL OR ip <<= $3::text::inet)
+           AND ($4::text IS NULL OR EXISTS (
+                 SELECT 1 FROM user_roles ur WHERE ur.user_id = ti.user_id AND ur.role = $4
+               ))
+           AND NOT EXISTS (
+                 SELECT 1 FROM token_issuances next
+                 WHERE next.rotated_from = ti.jti AND next.tenant_id = ti.tenant_id
+               )
+           AND ($5::timestamptz IS NULL OR (issued_at, jti) < ($5, $6::text))
+           AND tenant_id = $8
+         ORDER BY issued_at DESC, jti DESC
+         LIMIT $7";
+
+    pub const COUNT_LIVE: &str = "SELECT COUNT(*) AS sessions
+         FROM token_issuances ti
+         WHERE expires_at > NOW()
+           AND ($1::uuid[] IS NULL OR user_id = ANY($1))
+           AND ($2::timestamptz IS NULL OR issued_at < $2)
+           AND ($3::text IS NULL OR ip <<= $3::text::inet)
+           AND ($4::text IS NULL OR EXISTS (
+                 SELECT 1 FROM user_roles ur WHERE ur.user_id = ti.user_id AND ur.role = $4
+               ))
+           AND NOT EXISTS (
+                 SELECT 1 FROM token_issuances next
+                 WHERE next.rotated_from = ti.jti AND next.tenant_id = ti.tenant_id
+               )
+           AND tenant_id = $5";
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    config::CircuitBreaker,
+    db_select,
+    sessions::{
+        model::{LiveSession, SessionCriteria, SessionCursor},
+        queries,
+        traits::SessionRepository,
+    },
+    utils::{BaseRepository, FromRow},
+};
+
+/// Reads the primary: a replica lagging behind would miss the newest
+/// sessions, which are the ones an incident is about.
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl SessionRepository for Repository {
+    async fn live_sessions(
+        &self,
+        criteria: &SessionCriteria,
+        cursor: Option<&SessionCursor>,
+        limit: i64,
+    ) -> Result<Vec<LiveSession>, AppError> {
+        let network = criteria.network.map(|network| network.to_string());
+        let tenant = current_tenant();
+        let cursor_issued_at = cursor.map(|cursor| cursor.issued_at);
+        let cursor_jti = cursor.map(|cursor| cursor.jti.as_str());
+
+        let rows = db_select!("token_issuances", {
+            self.base
+                .execute_prepared(
+                    queries::token_issuances::SELECT_LIVE,
+                    &[
+                        &criteria.user_ids as &(dyn ToSql + Sync),
+                        &criteria.issued_before,
+                        &network,
+                        &criteria.role,
+                        &cursor_issued_at,
+                        &cursor_jti,
+                        &limit,
+                        &tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        rows.iter().map(LiveSession::from_row).collect()
+    }
+
+    async fn count_live_sessions(&self, criteria: &SessionCriteria) -> Result<i64, AppError> {
+        let network = criteria.network.map(|network| network.to_string());
+        let tenant = current_tenant();
+
+        let row = db_select!("token_issuances", {
+            self.base
+                .execute_prepared_one(
+                    queries::token_issuances::COUNT_LIVE,
+                    &[
+                        &criteria.user_ids as &(dyn ToSql + Sync),
+                        &criteria.issued_before,
+                        &network,
+                        &criteria.role,
+                        &tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        Ok(row.try_get("sessions")?)
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use futures_util::{StreamExt, stream};
+
+use crate::{
+    app::AppError,
+    audit::{
+        model::{AuditContext, AuditEntry, AuditEvent},
+        traits::AuditLogger,
+    },
+    auth::jwt::{AccessTokenClaims, JwtService, claims::JwtClaims},
+    sessions::{
+        dto::{RevokeSessionsRequest, RevokeSessionsResponse},
+        model::{SessionCriteria, SessionCursor},
+        traits::SessionRepository,
+    },
+};
+
+const PAGE_SIZE: i64 = 1000;
+const CONCURRENT_REVOCATIONS: usize = 32;
+
+pub struct SessionService<R, J, A>
+where
+    R: SessionRepository + 'static,
+    J: JwtService + 'static,
+    A: AuditLogger + 'static,
+{
+    session_repo: Arc<R>,
+    jwt_service: Arc<J>,
+    audit_logger: Arc<A>,
+}
+
+impl<R, J, A> SessionService<R, J, A>
+where
+    R: SessionRepository + 'static,
+    J: JwtService + 'static,
+    A: AuditLogger + 'static,
+{
+    pub fn new(session_repo: Arc<R>, jwt_service: Arc<J>, audit_logger: Arc<A>) -> Self {
+        Self {
+            session_repo,
+            jwt_service,
+            audit_logger,
+        }
+    }
+
+    /// Blacklists every matching refresh token until it would have expired.
+    /// Access tokens already issued stay valid until they expire. Audited
+    /// like the operational actions, dry runs included.
+    pub async fn revoke(
+        &self,
+        req: RevokeSessionsRequest,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<RevokeSessionsResponse, AppError> {
+        let criteria = req.to_criteria();
+        let result = if req.dry_run {
+            self.count(&criteria).await
+        } else {
+            self.apply(&criteria).await
+        };
+
+        let mut details = serde_json::json!({
+            "action": "revoke-sessions",
+            "user_ids": req.user_ids,
+            "issued_before": req.issued_before.map(|at| at.to_rfc3339()),
+            "ip_range": criteria.network.map(|network| network.to_string()),
+            "role": criteria.role,
+            "dry_run": req.dry_run,
+        });
+        if let Ok(response) = &result {
+            details["matched"] = serde_json::Value::from(response.matched);
+            details["revoked"] = serde_json::Value::from(response.revoked);
+            details["failed"] = serde_json::Value::from(response.failed);
+        }
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AdminAction,
+                ctx,
+                Some(actor.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*actor.sub())
+            .with_details(details),
+        );
+
+        result
+    }
+
+    async fn count(&self, criteria: &SessionCriteria) -> Result<RevokeSessionsResponse, AppError> {
+        let matched = self.session_repo.count_live_sessions(criteria).await?;
+
+        Ok(RevokeSessionsResponse {
+            dry_run: true,
+            matched: matched.max(0) as u64,
+            ..Default::default()
+        })
+    }
+
+    /// Pages from the newest session down, so sessions opened while this
+    /// runs are left alone and the walk ends.
+    async fn apply(&self, criteria: &SessionCriteria) -> Result<RevokeSessionsResponse, AppError> {
+        let mut response = RevokeSessionsResponse::default();
+        let mut cursor: Option<SessionCursor> = None;
+
+        loop {
+            let page = self
+                .session_repo
+                .live_sessions(criteria, cursor.as_ref(), PAGE_SIZE)
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            cursor = Some(SessionCursor::from(last));
+            let full = page.len() as i64 == PAGE_SIZE;
+
+            let revocations: Vec<_> = page
+                .iter()
+                .map(|session| {
+                    let jwt_service = Arc::clone(&self.jwt_service);
+                    let (jti, exp) = (session.jti.clone(), session.expires_at.timestamp());
+                    async move { jwt_service.blacklist(&jti, exp).await }
+                })
+                .collect();
+            let results: Vec<_> = stream::iter(revocations)
+                .buffer_unordered(CONCURRENT_REVOCATIONS)
+                .collect()
+                .await;
+
+            response.matched += page.len() as u64;
+            for result in results {
+                match result {
+                    Ok(()) => response.revoked += 1,
+                    Err(e) => {
+                        if response.failed == 0 {
+                            tracing::warn!("Failed to revoke a session: {}", e);
+                        }
+                        response.failed += 1;
+                    }
+                }
+            }
+
+            if !full {
+                break;
+            }
+        }
+
+        Ok(response)
+    }
+}
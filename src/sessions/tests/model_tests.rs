@@ -0,0 +1,37 @@
+use crate::sessions::model::IpNetwork;
+
+fn network(value: &str) -> String {
+    value.parse::<IpNetwork>().unwrap().to_string()
+}
+
+#[test]
+fn test_network_drops_host_bits() {
+    assert_eq!(network("203.0.113.77/24"), "203.0.113.0/24");
+    assert_eq!(network("10.1.2.3/8"), "10.0.0.0/8");
+    assert_eq!(network("2001:db8::1/32"), "2001:db8::/32");
+}
+
+#[test]
+fn test_bare_address_is_a_single_host() {
+    assert_eq!(network("198.51.100.7"), "198.51.100.7/32");
+    assert_eq!(network("::1"), "::1/128");
+}
+
+#[test]
+fn test_zero_prefix_matches_everything() {
+    assert_eq!(network("192.0.2.1/0"), "0.0.0.0/0");
+}
+
+#[test]
+fn test_invalid_networks_are_rejected() {
+    for invalid in [
+        "",
+        "10.0.0.0/33",
+        "::/129",
+        "10.0.0/8",
+        "10.0.0.0/x",
+        "host/8",
+    ] {
+        assert!(invalid.parse::<IpNetwork>().is_err(), "{:?}", invalid);
+    }
+}
@@ -0,0 +1,58 @@
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    sessions::dto::{RevokeSessionsRequest, request::MAX_REVOKED_USERS},
+    utils::Validatable,
+};
+
+#[test]
+fn test_request_needs_a_criterion() {
+    let request = RevokeSessionsRequest {
+        dry_run: true,
+        ..Default::default()
+    };
+
+    assert!(matches!(request.validate(), Err(AppError::BadRequest(_))));
+}
+
+#[test]
+fn test_request_rejects_invalid_range_and_role() {
+    let bad_range = RevokeSessionsRequest {
+        ip_range: Some(String::from("10.0.0.0/40")),
+        ..Default::default()
+    };
+    let blank_role = RevokeSessionsRequest {
+        role: Some(String::from("  ")),
+        ..Default::default()
+    };
+
+    assert!(bad_range.validate().is_err());
+    assert!(blank_role.validate().is_err());
+}
+
+#[test]
+fn test_request_caps_user_ids() {
+    let request = RevokeSessionsRequest {
+        user_ids: (0..=MAX_REVOKED_USERS).map(|_| Uuid::new_v4()).collect(),
+        ..Default::default()
+    };
+
+    assert!(request.validate().is_err());
+}
+
+#[test]
+fn test_criteria_leave_out_what_was_not_given() {
+    let request = RevokeSessionsRequest {
+        ip_range: Some(String::from("203.0.113.9/24")),
+        role: Some(String::from(" support ")),
+        ..Default::default()
+    };
+    assert!(request.validate().is_ok());
+
+    let criteria = request.to_criteria();
+    assert_eq!(criteria.user_ids, None);
+    assert_eq!(criteria.issued_before, None);
+    assert_eq!(criteria.network.unwrap().to_string(), "203.0.113.0/24");
+    assert_eq!(criteria.role.as_deref(), Some("support"));
+}
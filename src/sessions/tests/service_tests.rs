@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::model::{AuditContext, AuditOutcome},
+    sessions::{
+        dto::RevokeSessionsRequest,
+        model::{LiveSession, SessionCriteria, SessionCursor},
+        service::SessionService,
+        traits::SessionRepository,
+    },
+    utils::mocks::{MockAuditLogger, MockJwt, admin_claims},
+};
+
+#[derive(Default)]
+struct MockRepository {
+    sessions: Vec<LiveSession>,
+}
+
+impl MockRepository {
+    fn matching(&self, criteria: &SessionCriteria) -> Vec<LiveSession> {
+        let mut sessions: Vec<LiveSession> = self
+            .sessions
+            .iter()
+            .filter(|session| {
+                criteria
+                    .user_ids
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&session.user_id))
+                    && criteria
+                        .issued_before
+                        .is_none_or(|before| session.issued_at < before)
+            })
+            .cloned()
+            .collect();
+        sessions.sort_by(|a, b| (b.issued_at, &b.jti).cmp(&(a.issued_at, &a.jti)));
+        sessions
+    }
+}
+
+impl SessionRepository for MockRepository {
+    async fn live_sessions(
+        &self,
+        criteria: &SessionCriteria,
+        cursor: Option<&SessionCursor>,
+        limit: i64,
+    ) -> Result<Vec<LiveSession>, AppError> {
+        Ok(self
+            .matching(criteria)
+            .into_iter()
+            .filter(|session| {
+                cursor.is_none_or(|cursor| {
+                    (session.issued_at, &session.jti) < (cursor.issued_at, &cursor.jti)
+                })
+            })
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn count_live_sessions(&self, criteria: &SessionCriteria) -> Result<i64, AppError> {
+        Ok(self.matching(criteria).len() as i64)
+    }
+}
+
+struct Fixture {
+    service: SessionService<MockRepository, MockJwt, MockAuditLogger>,
+    jwt: Arc<MockJwt>,
+    audit: Arc<MockAuditLogger>,
+}
+
+fn session(user_id: Uuid, jti: &str, issued_at: DateTime<Utc>) -> LiveSession {
+    LiveSession {
+        user_id,
+        jti: jti.to_owned(),
+        issued_at,
+        expires_at: issued_at + TimeDelta::days(1),
+    }
+}
+
+fn fixture(sessions: Vec<LiveSession>, jwt: MockJwt) -> Fixture {
+    let jwt = Arc::new(jwt);
+    let audit = Arc::new(MockAuditLogger::default());
+
+    Fixture {
+        service: SessionService::new(
+            Arc::new(MockRepository { sessions }),
+            Arc::clone(&jwt),
+            Arc::clone(&audit),
+        ),
+        jwt,
+        audit,
+    }
+}
+
+#[tokio::test]
+async fn test_revoke_blacklists_matching_sessions_until_expiry() {
+    let (target, other) = (Uuid::new_v4(), Uuid::new_v4());
+    let now = Utc::now();
+    let f = fixture(
+        vec![
+            session(target, "a", now),
+            session(target, "b", now - TimeDelta::hours(1)),
+            session(other, "c", now),
+        ],
+        MockJwt::default(),
+    );
+
+    let response = f
+        .service
+        .revoke(
+            RevokeSessionsRequest {
+                user_ids: vec![target],
+                ..Default::default()
+            },
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        (response.matched, response.revoked, response.failed),
+        (2, 2, 0)
+    );
+    let mut blacklisted = f.jwt.blacklisted.lock().unwrap().clone();
+    blacklisted.sort();
+    assert_eq!(
+        blacklisted,
+        [
+            (String::from("a"), (now + TimeDelta::days(1)).timestamp()),
+            (
+                String::from("b"),
+                (now - TimeDelta::hours(1) + TimeDelta::days(1)).timestamp()
+            ),
+        ]
+    );
+
+    let entries = f.audit.entries.lock().unwrap();
+    assert_eq!(entries[0].outcome, AuditOutcome::Success);
+    assert_eq!(entries[0].details["action"], "revoke-sessions");
+    assert_eq!(entries[0].details["revoked"], 2);
+}
+
+#[tokio::test]
+async fn test_dry_run_only_counts() {
+    let now = Utc::now();
+    let f = fixture(
+        vec![
+            session(Uuid::new_v4(), "a", now - TimeDelta::hours(2)),
+            session(Uuid::new_v4(), "b", now),
+        ],
+        MockJwt::default(),
+    );
+
+    let response = f
+        .service
+        .revoke(
+            RevokeSessionsRequest {
+                issued_before: Some(now - TimeDelta::hours(1)),
+                dry_run: true,
+                ..Default::default()
+            },
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert!(response.dry_run);
+    assert_eq!((response.matched, response.revoked), (1, 0));
+    assert!(f.jwt.blacklisted.lock().unwrap().is_empty());
+    assert_eq!(f.audit.entries.lock().unwrap()[0].details["dry_run"], true);
+}
+
+#[tokio::test]
+async fn test_revoke_walks_every_page() {
+    let user_id = Uuid::new_v4();
+    let now = Utc::now();
+    // Pairs share an issue time, so the cursor has to break ties by jti.
+    let sessions = (0..2500)
+        .map(|i| {
+            session(
+                user_id,
+                &format!("jti-{:04}", i),
+                now - TimeDelta::seconds(i / 2),
+            )
+        })
+        .collect();
+    let f = fixture(sessions, MockJwt::default());
+
+    let response = f
+        .service
+        .revoke(
+            RevokeSessionsRequest {
+                user_ids: vec![user_id],
+                ..Default::default()
+            },
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.revoked, 2500);
+    let mut jtis: Vec<String> = f
+        .jwt
+        .blacklisted
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(jti, _)| jti.clone())
+        .collect();
+    jtis.sort();
+    jtis.dedup();
+    assert_eq!(jtis.len(), 2500);
+}
+
+#[tokio::test]
+async fn test_failed_revocations_are_counted() {
+    let user_id = Uuid::new_v4();
+    let now = Utc::now();
+    let f = fixture(
+        vec![session(user_id, "a", now), session(user_id, "b", now)],
+        MockJwt {
+            failing_jti: Some(String::from("b")),
+            ..Default::default()
+        },
+    );
+
+    let response = f
+        .service
+        .revoke(
+            RevokeSessionsRequest {
+                user_ids: vec![user_id],
+                ..Default::default()
+            },
+            &admin_claims(&["admin:actions"]),
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        (response.matched, response.revoked, response.failed),
+        (2, 1, 1)
+    );
+    assert_eq!(f.audit.entries.lock().unwrap()[0].details["failed"], 1);
+}
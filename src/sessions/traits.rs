@@ -0,0 +1,20 @@
+use std::future::Future;
+
+use crate::{
+    app::AppError,
+    sessions::model::{LiveSession, SessionCriteria, SessionCursor},
+};
+
+pub trait SessionRepository: Send + Sync {
+    /// Up to `limit` matching sessions, newest first, after `cursor`.
+    fn live_sessions(
+        &self,
+        criteria: &SessionCriteria,
+        cursor: Option<&SessionCursor>,
+        limit: i64,
+    ) -> impl Future<Output = Result<Vec<LiveSession>, AppError>> + Send;
+    fn count_live_sessions(
+        &self,
+        criteria: &SessionCriteria,
+    ) -> impl Future<Output = Result<i64, AppError>> + Send;
+}
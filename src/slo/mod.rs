@@ -0,0 +1,7 @@
+pub(crate) mod model;
+pub(crate) mod service;
+
+pub(crate) use service::SloTracker;
+
+#[cfg(test)]
+mod tests;
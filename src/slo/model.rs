@@ -0,0 +1,118 @@
+use axum::http::StatusCode;
+
+/// Windows of the multiwindow burn-rate alerts: a fast pair (5m, 1h), a
+/// slower pair (30m, 6h) and the day-scale ones (2h, 1d, 3d).
+pub const WINDOWS: [BurnWindow; 7] = [
+    BurnWindow::new("5m", 5),
+    BurnWindow::new("30m", 30),
+    BurnWindow::new("1h", 60),
+    BurnWindow::new("2h", 120),
+    BurnWindow::new("6h", 360),
+    BurnWindow::new("1d", 1_440),
+    BurnWindow::new("3d", 4_320),
+];
+
+/// How long per-minute buckets are kept: the widest window.
+pub const RETENTION_MINUTES: i64 = 4_320;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurnWindow {
+    pub label: &'static str,
+    pub minutes: i64,
+}
+
+impl BurnWindow {
+    const fn new(label: &'static str, minutes: i64) -> Self {
+        Self { label, minutes }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Sli {
+    Availability,
+    Latency,
+}
+
+impl Sli {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sli::Availability => "availability",
+            Sli::Latency => "latency",
+        }
+    }
+}
+
+/// Server errors and requests dropped at their deadline spend the
+/// availability budget; client errors are the caller's doing.
+pub fn is_available(status: StatusCode) -> bool {
+    !(status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub good: u64,
+    pub bad: u64,
+}
+
+impl Counts {
+    pub fn total(&self) -> u64 {
+        self.good + self.bad
+    }
+
+    /// How many times faster than allowed the error budget is being spent:
+    /// 1 exhausts it exactly at the end of the SLO period. No traffic burns
+    /// nothing.
+    pub fn burn_rate(&self, target: f64) -> f64 {
+        if self.total() == 0 {
+            return 0.0;
+        }
+        let error_ratio = self.bad as f64 / self.total() as f64;
+        error_ratio / (1.0 - target)
+    }
+
+    fn add(&mut self, other: Counts) {
+        self.good += other.good;
+        self.bad += other.bad;
+    }
+}
+
+/// Good and bad events of one SLI, bucketed by minute over the widest
+/// window. Slots are reused as minutes come round again, so the memory is
+/// fixed no matter the traffic.
+#[derive(Debug, Clone)]
+pub struct MinuteBuckets {
+    slots: Vec<(i64, Counts)>,
+}
+
+impl Default for MinuteBuckets {
+    fn default() -> Self {
+        Self {
+            slots: vec![(i64::MIN, Counts::default()); RETENTION_MINUTES as usize],
+        }
+    }
+}
+
+impl MinuteBuckets {
+    pub fn record(&mut self, minute: i64, good: bool) {
+        let slot = &mut self.slots[minute.rem_euclid(RETENTION_MINUTES) as usize];
+        if slot.0 != minute {
+            *slot = (minute, Counts::default());
+        }
+        if good {
+            slot.1.good += 1;
+        } else {
+            slot.1.bad += 1;
+        }
+    }
+
+    /// Events of the `minutes` up to and including `now`.
+    pub fn window(&self, now: i64, minutes: i64) -> Counts {
+        let mut counts = Counts::default();
+        for (minute, slot) in &self.slots {
+            if *minute <= now && *minute > now - minutes {
+                counts.add(*slot);
+            }
+        }
+        counts
+    }
+}
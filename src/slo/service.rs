@@ -0,0 +1,240 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use axum::http::StatusCode;
+use chrono::Utc;
+use prometheus::{
+    GaugeVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+    core::{Collector, Desc},
+    proto::MetricFamily,
+};
+
+use crate::{
+    config::slo::{RouteObjectives, SloConfig},
+    slo::model::{Counts, MinuteBuckets, Sli, WINDOWS, is_available},
+};
+
+/// One SLI of one route over every burn-rate window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SliSnapshot {
+    pub route: String,
+    pub sli: Sli,
+    pub target: f64,
+    pub windows: Vec<(&'static str, Counts)>,
+}
+
+/// Counts requests to routes with an objective against it, in process, and
+/// exports the windowed counts and burn rates so alerting rules only need to
+/// compare them with a threshold.
+pub struct SloTracker {
+    objectives: BTreeMap<String, RouteObjectives>,
+    series: Mutex<BTreeMap<(String, Sli), MinuteBuckets>>,
+    requests: IntCounterVec,
+    window_requests: IntGaugeVec,
+    burn_rate: GaugeVec,
+    objective: GaugeVec,
+}
+
+impl SloTracker {
+    pub fn new(config: SloConfig) -> Self {
+        let mut series = BTreeMap::new();
+        for (route, objectives) in &config.routes {
+            if objectives.availability.is_some() {
+                series.insert((route.clone(), Sli::Availability), MinuteBuckets::default());
+            }
+            if objectives.latency.is_some() {
+                series.insert((route.clone(), Sli::Latency), MinuteBuckets::default());
+            }
+        }
+
+        Self {
+            objectives: config.routes,
+            series: Mutex::new(series),
+            requests: IntCounterVec::new(
+                Opts::new(
+                    "slo_requests_total",
+                    "Total number of requests judged against a route SLO",
+                ),
+                &["route", "sli", "outcome"],
+            )
+            .unwrap(),
+            window_requests: IntGaugeVec::new(
+                Opts::new(
+                    "slo_window_requests",
+                    "Requests judged against a route SLO within the trailing window",
+                ),
+                &["route", "sli", "window", "outcome"],
+            )
+            .unwrap(),
+            burn_rate: GaugeVec::new(
+                Opts::new(
+                    "slo_burn_rate",
+                    "Error budget burn rate of a route SLO over the trailing window (1 = on budget)",
+                ),
+                &["route", "sli", "window"],
+            )
+            .unwrap(),
+            objective: GaugeVec::new(
+                Opts::new("slo_objective_ratio", "Target good ratio of a route SLO"),
+                &["route", "sli"],
+            )
+            .unwrap(),
+        }
+    }
+
+    /// Exports the tracker through the default registry.
+    pub fn register(self: &Arc<Self>) {
+        self.register_in(prometheus::default_registry());
+    }
+
+    /// Nothing is registered without objectives, so there is nothing to
+    /// collide with.
+    pub fn register_in(self: &Arc<Self>, registry: &Registry) {
+        if self.objectives.is_empty() {
+            return;
+        }
+        if let Err(e) = registry.register(Box::new(SloCollector(Arc::clone(self)))) {
+            tracing::warn!("Failed to register SLO metrics: {}", e);
+        }
+    }
+
+    pub fn is_tracked(&self, route: &str) -> bool {
+        self.objectives.contains_key(route)
+    }
+
+    pub fn record(&self, route: &str, status: StatusCode, elapsed: Duration) {
+        self.record_at(current_minute(), route, status, elapsed);
+    }
+
+    /// Latency is only judged on requests that were served, so an outage
+    /// is not counted twice.
+    pub fn record_at(&self, minute: i64, route: &str, status: StatusCode, elapsed: Duration) {
+        let Some(objectives) = self.objectives.get(route) else {
+            return;
+        };
+        let available = is_available(status);
+
+        let mut series = self.lock();
+        if objectives.availability.is_some() {
+            self.count(&mut series, minute, route, Sli::Availability, available);
+        }
+        if let Some(latency) = objectives.latency
+            && available
+        {
+            let fast = elapsed <= latency.threshold;
+            self.count(&mut series, minute, route, Sli::Latency, fast);
+        }
+    }
+
+    pub fn snapshot_at(&self, minute: i64) -> Vec<SliSnapshot> {
+        self.lock()
+            .iter()
+            .filter_map(|((route, sli), buckets)| {
+                let target = self.target(route, *sli)?;
+                Some(SliSnapshot {
+                    route: route.clone(),
+                    sli: *sli,
+                    target,
+                    windows: WINDOWS
+                        .iter()
+                        .map(|window| (window.label, buckets.window(minute, window.minutes)))
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn count(
+        &self,
+        series: &mut BTreeMap<(String, Sli), MinuteBuckets>,
+        minute: i64,
+        route: &str,
+        sli: Sli,
+        good: bool,
+    ) {
+        if let Some(buckets) = series.get_mut(&(route.to_string(), sli)) {
+            buckets.record(minute, good);
+        }
+        self.requests
+            .with_label_values(&[route, sli.as_str(), outcome(good)])
+            .inc();
+    }
+
+    fn target(&self, route: &str, sli: Sli) -> Option<f64> {
+        let objectives = self.objectives.get(route)?;
+        match sli {
+            Sli::Availability => objectives.availability,
+            Sli::Latency => objectives.latency.map(|latency| latency.target),
+        }
+    }
+
+    /// Windowed values are rebuilt at every scrape, so they decay with time
+    /// even when the route gets no traffic.
+    fn refresh_gauges(&self) {
+        for snapshot in self.snapshot_at(current_minute()) {
+            let route = snapshot.route.as_str();
+            let sli = snapshot.sli.as_str();
+            self.objective
+                .with_label_values(&[route, sli])
+                .set(snapshot.target);
+
+            for (window, counts) in &snapshot.windows {
+                self.window_requests
+                    .with_label_values(&[route, sli, window, "good"])
+                    .set(counts.good as i64);
+                self.window_requests
+                    .with_label_values(&[route, sli, window, "bad"])
+                    .set(counts.bad as i64);
+                self.burn_rate
+                    .with_label_values(&[route, sli, window])
+                    .set(counts.burn_rate(snapshot.target));
+            }
+        }
+    }
+
+    /// A panic while holding the lock leaves at most one bucket short a
+    /// count, so poisoning is ignored.
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<(String, Sli), MinuteBuckets>> {
+        self.series
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+struct SloCollector(Arc<SloTracker>);
+
+impl Collector for SloCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        let tracker = &self.0;
+        [
+            tracker.requests.desc(),
+            tracker.window_requests.desc(),
+            tracker.burn_rate.desc(),
+            tracker.objective.desc(),
+        ]
+        .concat()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let tracker = &self.0;
+        tracker.refresh_gauges();
+        [
+            tracker.requests.collect(),
+            tracker.window_requests.collect(),
+            tracker.burn_rate.collect(),
+            tracker.objective.collect(),
+        ]
+        .concat()
+    }
+}
+
+fn outcome(good: bool) -> &'static str {
+    if good { "good" } else { "bad" }
+}
+
+fn current_minute() -> i64 {
+    Utc::now().timestamp() / 60
+}
@@ -0,0 +1,44 @@
+use axum::http::StatusCode;
+
+use crate::slo::model::{Counts, MinuteBuckets, RETENTION_MINUTES, is_available};
+
+#[test]
+fn test_server_errors_and_timeouts_are_unavailable() {
+    assert!(is_available(StatusCode::OK));
+    assert!(is_available(StatusCode::UNAUTHORIZED));
+    assert!(!is_available(StatusCode::REQUEST_TIMEOUT));
+    assert!(!is_available(StatusCode::SERVICE_UNAVAILABLE));
+}
+
+#[test]
+fn test_burn_rate_is_error_ratio_over_budget() {
+    let counts = Counts { good: 990, bad: 10 };
+
+    assert!((counts.burn_rate(0.999) - 10.0).abs() < 1e-9);
+    assert_eq!(Counts::default().burn_rate(0.999), 0.0);
+}
+
+#[test]
+fn test_window_covers_trailing_minutes_only() {
+    let mut buckets = MinuteBuckets::default();
+    buckets.record(100, true);
+    buckets.record(104, false);
+    buckets.record(105, true);
+    buckets.record(106, true);
+
+    assert_eq!(buckets.window(105, 5), Counts { good: 1, bad: 1 });
+    assert_eq!(buckets.window(105, 6), Counts { good: 2, bad: 1 });
+}
+
+#[test]
+fn test_reused_slot_forgets_the_old_minute() {
+    let mut buckets = MinuteBuckets::default();
+    buckets.record(10, false);
+    buckets.record(10 + RETENTION_MINUTES, true);
+
+    let now = 10 + RETENTION_MINUTES;
+    assert_eq!(
+        buckets.window(now, RETENTION_MINUTES),
+        Counts { good: 1, bad: 0 }
+    );
+}
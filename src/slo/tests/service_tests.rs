@@ -0,0 +1,115 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::http::StatusCode;
+use prometheus::{Registry, TextEncoder};
+
+use crate::{
+    config::slo::{SloConfig, parse_availability, parse_latency},
+    slo::{
+        SloTracker,
+        model::{Counts, Sli},
+    },
+};
+
+const ROUTE: &str = "/auth/login/finish";
+
+fn tracker() -> SloTracker {
+    SloTracker::new(SloConfig::new(
+        parse_availability("/auth/login/finish=99"),
+        parse_latency("/auth/login/finish=500@90"),
+    ))
+}
+
+fn window(tracker: &SloTracker, minute: i64, sli: Sli, label: &str) -> Counts {
+    tracker
+        .snapshot_at(minute)
+        .into_iter()
+        .find(|snapshot| snapshot.sli == sli)
+        .unwrap()
+        .windows
+        .into_iter()
+        .find(|(window, _)| *window == label)
+        .unwrap()
+        .1
+}
+
+#[test]
+fn test_requests_count_against_both_objectives() {
+    let tracker = tracker();
+    tracker.record_at(1, ROUTE, StatusCode::OK, Duration::from_millis(100));
+    tracker.record_at(1, ROUTE, StatusCode::OK, Duration::from_millis(900));
+    tracker.record_at(1, ROUTE, StatusCode::BAD_REQUEST, Duration::from_millis(50));
+
+    assert_eq!(
+        window(&tracker, 1, Sli::Availability, "5m"),
+        Counts { good: 3, bad: 0 }
+    );
+    assert_eq!(
+        window(&tracker, 1, Sli::Latency, "5m"),
+        Counts { good: 2, bad: 1 }
+    );
+}
+
+#[test]
+fn test_failed_requests_are_not_judged_on_latency() {
+    let tracker = tracker();
+    tracker.record_at(
+        1,
+        ROUTE,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Duration::from_secs(5),
+    );
+
+    assert_eq!(
+        window(&tracker, 1, Sli::Availability, "5m"),
+        Counts { good: 0, bad: 1 }
+    );
+    assert_eq!(window(&tracker, 1, Sli::Latency, "5m"), Counts::default());
+}
+
+#[test]
+fn test_short_window_forgets_before_long_one() {
+    let tracker = tracker();
+    tracker.record_at(0, ROUTE, StatusCode::BAD_GATEWAY, Duration::ZERO);
+
+    assert_eq!(window(&tracker, 10, Sli::Availability, "5m").bad, 0);
+    assert_eq!(window(&tracker, 10, Sli::Availability, "1h").bad, 1);
+}
+
+#[test]
+fn test_untracked_routes_are_ignored() {
+    let tracker = tracker();
+    tracker.record_at(1, "/auth/me", StatusCode::OK, Duration::ZERO);
+
+    assert!(!tracker.is_tracked("/auth/me"));
+    assert_eq!(
+        window(&tracker, 1, Sli::Availability, "3d"),
+        Counts::default()
+    );
+}
+
+#[test]
+fn test_scrape_exports_windows_and_burn_rates() {
+    let tracker = Arc::new(tracker());
+    let registry = Registry::new();
+    tracker.register_in(&registry);
+    tracker.record(ROUTE, StatusCode::OK, Duration::from_millis(10));
+    tracker.record(ROUTE, StatusCode::SERVICE_UNAVAILABLE, Duration::ZERO);
+
+    let text = TextEncoder::new()
+        .encode_to_string(&registry.gather())
+        .unwrap();
+
+    assert!(text.contains(
+        r#"slo_requests_total{outcome="bad",route="/auth/login/finish",sli="availability"} 1"#
+    ));
+    assert!(text.contains(
+        r#"slo_window_requests{outcome="bad",route="/auth/login/finish",sli="availability",window="5m"} 1"#
+    ));
+    assert!(
+        text.contains(
+            r#"slo_burn_rate{route="/auth/login/finish",sli="availability",window="5m"} "#
+        )
+    );
+    assert!(text.contains(r#"slo_objective_ratio{route="/auth/login/finish",sli="latency"} 0.9"#));
+}
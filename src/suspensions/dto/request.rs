@@ -0,0 +1,35 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::AppError,
+    impl_validated_json_request,
+    utils::{Validatable, validate_text},
+};
+
+pub const MAX_REASON_CHARS: usize = 200;
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct SuspendUserRequest {
+    /// Why, kept with the suspension until it is lifted
+    #[schema(example = "Chargeback under investigation")]
+    pub reason: Option<String>,
+}
+
+impl Validatable for SuspendUserRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if let Some(reason) = &self.reason {
+            validate_text(reason, "Reason")?;
+            if reason.chars().count() > MAX_REASON_CHARS {
+                return Err(AppError::BadRequest(format!(
+                    "Reason must be at most {} characters",
+                    MAX_REASON_CHARS
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_json_request!(SuspendUserRequest);
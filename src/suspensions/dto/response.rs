@@ -0,0 +1,42 @@
+use axum::{
+    Json,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::suspensions::model::AccountState;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountStateResponse {
+    pub user_id: Uuid,
+    #[schema(example = "john_doe")]
+    pub username: String,
+    #[schema(example = "suspended")]
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub suspended_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Chargeback under investigation")]
+    pub suspension_reason: Option<String>,
+}
+
+impl From<AccountState> for AccountStateResponse {
+    fn from(state: AccountState) -> Self {
+        Self {
+            user_id: state.id,
+            username: state.username,
+            status: state.status,
+            suspended_at: state.suspended_at.map(|at| at.to_rfc3339()),
+            suspension_reason: state.suspension_reason,
+        }
+    }
+}
+
+impl IntoResponse for AccountStateResponse {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
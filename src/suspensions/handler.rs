@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    audit::model::AuditContext,
+    auth::permissions::AdminActions,
+    suspensions::dto::{AccountStateResponse, SuspendUserRequest},
+};
+
+/// Suspend a user
+///
+/// Refuses the user's sign-ins and refreshes until the suspension is lifted.
+/// Access tokens already issued keep working until they expire. Only active
+/// users can be suspended. Requires `admin:actions`.
+#[utoipa::path(
+    put,
+    path = "/admin/users/{user_id}/suspension",
+    tag = "Admin",
+    params(("user_id" = Uuid, Path, description = "User to suspend")),
+    request_body = SuspendUserRequest,
+    responses(
+        (status = 200, description = "User suspended", body = AccountStateResponse),
+        (status = 400, description = "User not active or invalid reason", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "User not found", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn suspend(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Path(user_id): Path<Uuid>,
+    request: SuspendUserRequest,
+) -> Result<AccountStateResponse, AppError> {
+    state
+        .suspension_service
+        .suspend(user_id, request, &admin, &ctx)
+        .await
+}
+
+/// Lift a suspension
+///
+/// Makes a suspended user active again. Requires `admin:actions`.
+#[utoipa::path(
+    delete,
+    path = "/admin/users/{user_id}/suspension",
+    tag = "Admin",
+    params(("user_id" = Uuid, Path, description = "Suspended user")),
+    responses(
+        (status = 200, description = "User active again", body = AccountStateResponse),
+        (status = 400, description = "User not suspended", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 404, description = "User not found", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn reinstate(
+    admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Path(user_id): Path<Uuid>,
+) -> Result<AccountStateResponse, AppError> {
+    state
+        .suspension_service
+        .reinstate(user_id, &admin, &ctx)
+        .await
+}
@@ -0,0 +1,12 @@
+pub(crate) mod dto;
+pub(crate) mod handler;
+pub(crate) mod model;
+mod queries;
+pub(crate) mod repo;
+pub(crate) mod service;
+pub(crate) mod traits;
+
+pub(crate) use repo::Repository;
+
+#[cfg(test)]
+mod tests;
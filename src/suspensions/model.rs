@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{app::AppError, auth::model::UserStatus, utils::FromRow};
+
+/// A user's place in the account lifecycle, with the suspension if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountState {
+    pub id: Uuid,
+    pub username: String,
+    pub status: String,
+    pub suspended_at: Option<DateTime<Utc>>,
+    pub suspension_reason: Option<String>,
+}
+
+impl AccountState {
+    /// `None` for a status this build does not know.
+    pub fn status(&self) -> Option<UserStatus> {
+        UserStatus::parse(&self.status)
+    }
+}
+
+impl FromRow for AccountState {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            username: row.try_get("username")?,
+            status: row.try_get("status")?,
+            suspended_at: row.try_get("suspended_at")?,
+            suspension_reason: row.try_get("suspension_reason")?,
+        })
+    }
+}
@@ -0,0 +1,18 @@
+/// Every query takes the tenant of the request as its last parameter. The
+/// updates only match the status they move away from, so two administrators
+/// acting at once cannot both succeed.
+pub mod users {
+    pub const SELECT: &str = "SELECT id, username, status, suspended_at, suspension_reason
+         FROM users
+         WHERE id = $1 AND is_active AND tenant_id = $2";
+
+    pub const SUSPEND: &str = "UPDATE users
+         SET status = 'suspended', suspended_at = NOW(), suspension_reason = $2
+         WHERE id = $1 AND status = 'active' AND tenant_id = $3
+         RETURNING id, username, status, suspended_at, suspension_reason";
+
+    pub const REINSTATE: &str = "UPDATE users
+         SET status = 'active', suspended_at = NULL, suspension_reason = NULL
+         WHERE id = $1 AND status = 'suspended' AND tenant_id = $2
+         RETURNING id, username, status, suspended_at, suspension_reason";
+}
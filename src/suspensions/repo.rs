@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    config::CircuitBreaker,
+    db_select, db_update,
+    suspensions::{model::AccountState, queries, traits::SuspensionRepository},
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl SuspensionRepository for Repository {
+    async fn find(&self, user_id: Uuid) -> Result<Option<AccountState>, AppError> {
+        let tenant = current_tenant();
+
+        db_select!("users", {
+            self.base
+                .execute_prepared_opt(
+                    queries::users::SELECT,
+                    &[&user_id as &(dyn ToSql + Sync), &tenant],
+                )
+                .await
+        })?
+        .as_ref()
+        .map(AccountState::from_row)
+        .transpose()
+    }
+
+    async fn suspend(
+        &self,
+        user_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<Option<AccountState>, AppError> {
+        let tenant = current_tenant();
+
+        db_update!("users", {
+            self.base
+                .execute_prepared_opt(
+                    queries::users::SUSPEND,
+                    &[&user_id as &(dyn ToSql + Sync), &reason, &tenant],
+                )
+                .await
+        })?
+        .as_ref()
+        .map(AccountState::from_row)
+        .transpose()
+    }
+
+    async fn reinstate(&self, user_id: Uuid) -> Result<Option<AccountState>, AppError> {
+        let tenant = current_tenant();
+
+        db_update!("users", {
+            self.base
+                .execute_prepared_opt(
+                    queries::users::REINSTATE,
+                    &[&user_id as &(dyn ToSql + Sync), &tenant],
+                )
+                .await
+        })?
+        .as_ref()
+        .map(AccountState::from_row)
+        .transpose()
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::{
+        model::{AuditContext, AuditEntry, AuditEvent},
+        traits::AuditLogger,
+    },
+    auth::{
+        jwt::{AccessTokenClaims, claims::JwtClaims},
+        model::UserStatus,
+    },
+    suspensions::{
+        dto::{AccountStateResponse, SuspendUserRequest},
+        model::AccountState,
+        traits::SuspensionRepository,
+    },
+};
+
+pub struct SuspensionService<R, A>
+where
+    R: SuspensionRepository + 'static,
+    A: AuditLogger + 'static,
+{
+    suspension_repo: Arc<R>,
+    audit_logger: Arc<A>,
+}
+
+impl<R, A> SuspensionService<R, A>
+where
+    R: SuspensionRepository + 'static,
+    A: AuditLogger + 'static,
+{
+    pub fn new(suspension_repo: Arc<R>, audit_logger: Arc<A>) -> Self {
+        Self {
+            suspension_repo,
+            audit_logger,
+        }
+    }
+
+    /// Stops an active user from signing in or refreshing. Access tokens
+    /// already issued stay valid until they expire.
+    pub async fn suspend(
+        &self,
+        user_id: Uuid,
+        req: SuspendUserRequest,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<AccountStateResponse, AppError> {
+        let result = self
+            .transition(user_id, UserStatus::Suspended, req.reason.as_deref())
+            .await;
+        self.audit(
+            "suspend-user",
+            user_id,
+            req.reason.as_deref(),
+            &result,
+            actor,
+            ctx,
+        );
+        result.map(Into::into)
+    }
+
+    pub async fn reinstate(
+        &self,
+        user_id: Uuid,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) -> Result<AccountStateResponse, AppError> {
+        let result = self.transition(user_id, UserStatus::Active, None).await;
+        self.audit("reinstate-user", user_id, None, &result, actor, ctx);
+        result.map(Into::into)
+    }
+
+    async fn transition(
+        &self,
+        user_id: Uuid,
+        target: UserStatus,
+        reason: Option<&str>,
+    ) -> Result<AccountState, AppError> {
+        let current = self
+            .suspension_repo
+            .find(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(String::from("User not found")))?;
+        if !current
+            .status()
+            .is_some_and(|status| status.can_become(target))
+        {
+            return Err(invalid_transition(&current.status, target));
+        }
+
+        let updated = match target {
+            UserStatus::Suspended => self.suspension_repo.suspend(user_id, reason).await?,
+            _ => self.suspension_repo.reinstate(user_id).await?,
+        };
+        // Another administrator moved the user since it was read.
+        let updated = updated.ok_or_else(|| invalid_transition(&current.status, target))?;
+        Ok(updated)
+    }
+
+    fn audit(
+        &self,
+        action: &str,
+        user_id: Uuid,
+        reason: Option<&str>,
+        result: &Result<AccountState, AppError>,
+        actor: &AccessTokenClaims,
+        ctx: &AuditContext,
+    ) {
+        let mut details = serde_json::json!({
+            "action": action,
+            "user_id": user_id,
+            "reason": reason,
+        });
+        if let Ok(state) = result {
+            details["username"] = serde_json::Value::from(state.username.as_str());
+        }
+        self.audit_logger.record(
+            AuditEntry::new(
+                AuditEvent::AdminAction,
+                ctx,
+                Some(actor.username()),
+                result.as_ref().map(|_| ()),
+            )
+            .with_user_id(*actor.sub())
+            .with_details(details),
+        );
+    }
+}
+
+fn invalid_transition(current: &str, target: UserStatus) -> AppError {
+    match target {
+        UserStatus::Suspended => AppError::BadRequest(format!(
+            "Only active users can be suspended, not {}",
+            current
+        )),
+        _ => AppError::BadRequest(String::from("User is not suspended")),
+    }
+}
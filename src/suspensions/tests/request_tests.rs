@@ -0,0 +1,24 @@
+use crate::{app::AppError, suspensions::dto::SuspendUserRequest, utils::Validatable};
+
+fn request(reason: Option<&str>) -> SuspendUserRequest {
+    SuspendUserRequest {
+        reason: reason.map(str::to_owned),
+    }
+}
+
+#[test]
+fn test_reason_is_optional() {
+    assert!(request(None).validate().is_ok());
+    assert!(request(Some("Chargeback")).validate().is_ok());
+}
+
+#[test]
+fn test_blank_or_long_reason_rejected() {
+    let long = "x".repeat(201);
+    for reason in ["  ", long.as_str()] {
+        assert!(matches!(
+            request(Some(reason)).validate(),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::model::{AuditContext, AuditOutcome},
+    auth::model::UserStatus,
+    suspensions::{
+        dto::SuspendUserRequest, model::AccountState, service::SuspensionService,
+        traits::SuspensionRepository,
+    },
+    utils::mocks::{MockAuditLogger, admin_claims},
+};
+
+#[derive(Default)]
+struct MockRepository {
+    users: Mutex<HashMap<Uuid, AccountState>>,
+}
+
+impl MockRepository {
+    fn with(user: AccountState) -> Self {
+        Self {
+            users: Mutex::new(HashMap::from([(user.id, user)])),
+        }
+    }
+
+    fn set(
+        &self,
+        user_id: Uuid,
+        from: &str,
+        apply: impl FnOnce(&mut AccountState),
+    ) -> Option<AccountState> {
+        let mut users = self.users.lock().unwrap();
+        let user = users.get_mut(&user_id).filter(|user| user.status == from)?;
+        apply(user);
+        Some(user.clone())
+    }
+}
+
+impl SuspensionRepository for MockRepository {
+    async fn find(&self, user_id: Uuid) -> Result<Option<AccountState>, AppError> {
+        Ok(self.users.lock().unwrap().get(&user_id).cloned())
+    }
+
+    async fn suspend(
+        &self,
+        user_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<Option<AccountState>, AppError> {
+        Ok(self.set(user_id, "active", |user| {
+            user.status = String::from("suspended");
+            user.suspended_at = Some(Utc::now());
+            user.suspension_reason = reason.map(str::to_owned);
+        }))
+    }
+
+    async fn reinstate(&self, user_id: Uuid) -> Result<Option<AccountState>, AppError> {
+        Ok(self.set(user_id, "suspended", |user| {
+            user.status = String::from("active");
+            user.suspended_at = None;
+            user.suspension_reason = None;
+        }))
+    }
+}
+
+fn user(status: UserStatus) -> AccountState {
+    AccountState {
+        id: Uuid::new_v4(),
+        username: String::from("alice"),
+        status: status.as_str().to_owned(),
+        suspended_at: None,
+        suspension_reason: None,
+    }
+}
+
+fn service(
+    repo: MockRepository,
+) -> (
+    SuspensionService<MockRepository, MockAuditLogger>,
+    Arc<MockAuditLogger>,
+) {
+    let audit = Arc::new(MockAuditLogger::default());
+    (
+        SuspensionService::new(Arc::new(repo), Arc::clone(&audit)),
+        audit,
+    )
+}
+
+fn reason(text: &str) -> SuspendUserRequest {
+    SuspendUserRequest {
+        reason: Some(text.to_owned()),
+    }
+}
+
+#[test]
+fn test_lifecycle_transitions() {
+    use UserStatus::*;
+
+    assert!(Pending.can_become(Active));
+    assert!(Active.can_become(Suspended));
+    assert!(Suspended.can_become(Active));
+    assert!(
+        [Pending, Active, Suspended]
+            .iter()
+            .all(|s| s.can_become(Deleted))
+    );
+
+    assert!(!Pending.can_become(Suspended));
+    assert!(!Suspended.can_become(Suspended));
+    assert!(!Deleted.can_become(Active));
+    assert_eq!(UserStatus::parse("suspended"), Some(Suspended));
+    assert_eq!(UserStatus::parse("banned"), None);
+}
+
+#[tokio::test]
+async fn test_suspend_and_reinstate_active_user() {
+    let alice = user(UserStatus::Active);
+    let (service, audit) = service(MockRepository::with(alice.clone()));
+    let ctx = AuditContext::default();
+
+    let suspended = service
+        .suspend(
+            alice.id,
+            reason("Chargeback"),
+            &admin_claims(&["admin:actions"]),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(suspended.status, "suspended");
+    assert_eq!(suspended.suspension_reason.as_deref(), Some("Chargeback"));
+    assert!(suspended.suspended_at.is_some());
+
+    let reinstated = service
+        .reinstate(alice.id, &admin_claims(&["admin:actions"]), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(reinstated.status, "active");
+    assert!(reinstated.suspended_at.is_none());
+
+    let entries = audit.entries.lock().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].details["action"], "suspend-user");
+    assert_eq!(entries[0].details["reason"], "Chargeback");
+    assert_eq!(entries[1].details["action"], "reinstate-user");
+    assert!(entries.iter().all(|e| e.outcome == AuditOutcome::Success));
+}
+
+#[tokio::test]
+async fn test_only_active_users_can_be_suspended() {
+    for status in [UserStatus::Pending, UserStatus::Suspended] {
+        let target = user(status);
+        let (service, audit) = service(MockRepository::with(target.clone()));
+
+        let result = service
+            .suspend(
+                target.id,
+                reason("x"),
+                &admin_claims(&["admin:actions"]),
+                &AuditContext::default(),
+            )
+            .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert_ne!(
+            audit.entries.lock().unwrap()[0].outcome,
+            AuditOutcome::Success
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_reinstate_requires_a_suspension() {
+    let alice = user(UserStatus::Active);
+    let (service, _) = service(MockRepository::with(alice.clone()));
+
+    assert!(matches!(
+        service
+            .reinstate(
+                alice.id,
+                &admin_claims(&["admin:actions"]),
+                &AuditContext::default()
+            )
+            .await,
+        Err(AppError::BadRequest(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_unknown_user_not_found() {
+    let (service, _) = service(MockRepository::default());
+
+    assert!(matches!(
+        service
+            .suspend(
+                Uuid::new_v4(),
+                reason("x"),
+                &admin_claims(&["admin:actions"]),
+                &AuditContext::default()
+            )
+            .await,
+        Err(AppError::NotFound(_))
+    ));
+}
@@ -0,0 +1,24 @@
+use std::future::Future;
+
+use uuid::Uuid;
+
+use crate::{app::AppError, suspensions::model::AccountState};
+
+pub trait SuspensionRepository: Send + Sync {
+    /// `None` for unknown and deleted users.
+    fn find(
+        &self,
+        user_id: Uuid,
+    ) -> impl Future<Output = Result<Option<AccountState>, AppError>> + Send;
+    /// `None` unless the user was active.
+    fn suspend(
+        &self,
+        user_id: Uuid,
+        reason: Option<&str>,
+    ) -> impl Future<Output = Result<Option<AccountState>, AppError>> + Send;
+    /// `None` unless the user was suspended.
+    fn reinstate(
+        &self,
+        user_id: Uuid,
+    ) -> impl Future<Output = Result<Option<AccountState>, AppError>> + Send;
+}
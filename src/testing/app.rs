@@ -0,0 +1,331 @@
+use std::{net::SocketAddr, time::Duration};
+
+use axum::{
+    Router,
+    body::Body,
+    extract::ConnectInfo,
+    http::{HeaderMap, Method, Request, StatusCode, header},
+};
+use deadpool_postgres::Pool;
+use http_body_util::BodyExt;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use testcontainers::{ContainerAsync, ImageExt, runners::AsyncRunner};
+use testcontainers_modules::{postgres::Postgres, redis::Redis};
+use tower::ServiceExt;
+use url::Url;
+use webauthn_rs::prelude::{CreationChallengeResponse, RequestChallengeResponse};
+
+use crate::{
+    app::{AppConfig, AppState, create_router},
+    config::{
+        DbConfig, JwtConfig, OriginConfig, RedisConfig, RolePolicy, StatementCacheConfig,
+        WebAuthnConfig, webauthn::RpBranding,
+    },
+    testing::SoftPasskey,
+    utils::cookie::REFRESH_TOKEN_COOKIE_NAME,
+};
+
+const POSTGRES_TAG: &str = "17-alpine";
+const REDIS_TAG: &str = "7-alpine";
+const SUPERUSER: &str = "postgres";
+const DB_NAME: &str = "server_db";
+// Created by V0__Create_Application_Role.sql
+const APP_USER: &str = "server_app";
+const APP_PASSWORD: &str = "changeme_app_password";
+const REDIS_PASSWORD: &str = "test_redis_password";
+const JWT_SECRET: &str = "test-support-secret-key-of-32-bytes!";
+const FRONTEND_ORIGIN: &str = "http://localhost:3000";
+const BACKEND_URL: &str = "http://localhost:8080";
+const CLIENT_ADDR: ([u8; 4], u16) = ([127, 0, 0, 1], 40000);
+
+/// The full router over throwaway Postgres and Redis containers, migrated to
+/// the current schema. Every app gets its own containers, removed on drop.
+/// Needs a Docker daemon; settings with defaults still come from the
+/// environment, as they do in production.
+pub struct TestApp {
+    router: Router,
+    #[cfg_attr(feature = "memory-store", allow(dead_code))]
+    db: Pool,
+    _postgres: ContainerAsync<Postgres>,
+    _redis: ContainerAsync<Redis>,
+}
+
+/// A response with its body parsed as JSON, or `Null` when empty.
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+/// A passkey enrolled through `/auth/register`, with the codes it returned.
+pub struct TestUser {
+    pub username: String,
+    pub passkey: SoftPasskey,
+    pub recovery_codes: Vec<String>,
+    /// What finish reported about the passkey.
+    pub credential: Value,
+}
+
+/// Tokens issued by a login or refresh.
+pub struct TestSession {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+impl TestApp {
+    pub async fn spawn() -> Self {
+        let postgres = Postgres::default()
+            .with_db_name(DB_NAME)
+            .with_user(SUPERUSER)
+            .with_password(SUPERUSER)
+            .with_tag(POSTGRES_TAG)
+            .start()
+            .await
+            .expect("Failed to start Postgres, is Docker running?");
+        let redis = Redis::default()
+            .with_tag(REDIS_TAG)
+            .with_cmd(["redis-server", "--requirepass", REDIS_PASSWORD])
+            .start()
+            .await
+            .expect("Failed to start Redis, is Docker running?");
+
+        let db_config = DbConfig {
+            host: postgres.get_host().await.unwrap().to_string().into(),
+            port: postgres.get_host_port_ipv4(5432).await.unwrap(),
+            user: APP_USER.into(),
+            password: APP_PASSWORD.into(),
+            dbname: DB_NAME.into(),
+            max_size: 4,
+            connection_timeout: Duration::from_secs(10),
+            wait_timeout: Duration::from_secs(10),
+            recycle_timeout: Duration::from_secs(10),
+            run_migrations: true,
+            migration_user: SUPERUSER.into(),
+            migration_password: SUPERUSER.into(),
+            tls: None,
+            statement_cache: StatementCacheConfig::default(),
+            replica: None,
+            regions: Vec::new(),
+        };
+        let redis_config = RedisConfig {
+            url: format!(
+                "redis://:{}@{}:{}",
+                REDIS_PASSWORD,
+                redis.get_host().await.unwrap(),
+                redis.get_host_port_ipv4(6379).await.unwrap()
+            )
+            .into(),
+            primary: format!(
+                "{}:{}",
+                redis.get_host().await.unwrap(),
+                redis.get_host_port_ipv4(6379).await.unwrap()
+            )
+            .into(),
+            shards: Vec::new(),
+            previous_shards: None,
+        };
+        let origin_config = OriginConfig::parse(
+            FRONTEND_ORIGIN,
+            Url::parse(BACKEND_URL).unwrap().host_str().unwrap(),
+        );
+        let webauthn_config = WebAuthnConfig {
+            rp: RpBranding::new("rs-server tests"),
+            timeout: Duration::from_secs(60),
+            stateless: None,
+            attestation_cas: None,
+            extensions: Default::default(),
+            max_concurrent_finishes: 0,
+        };
+
+        let db = db_config.create_pool();
+        let mut config = AppConfig::connect(
+            db_config,
+            redis_config,
+            origin_config,
+            webauthn_config,
+            JwtConfig::with_secret(JWT_SECRET),
+        )
+        .await;
+        // The flow tests seed an `auditor` role and register with it.
+        config.role_policy = RolePolicy::parse("auditor", "admin");
+        Self {
+            router: create_router(AppState::new(config)),
+            db,
+            _postgres: postgres,
+            _redis: redis,
+        }
+    }
+
+    /// Application-role pool, for seeding rows the API cannot create.
+    #[cfg_attr(feature = "memory-store", allow(dead_code))]
+    pub fn db(&self) -> &Pool {
+        &self.db
+    }
+
+    pub fn passkey(&self) -> SoftPasskey {
+        SoftPasskey::new(FRONTEND_ORIGIN)
+    }
+
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+        headers: &[(header::HeaderName, String)],
+    ) -> TestResponse {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(path)
+            .extension(ConnectInfo(SocketAddr::from(CLIENT_ADDR)));
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        let request = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = body.collect().await.unwrap().to_bytes();
+
+        TestResponse {
+            status: parts.status,
+            headers: parts.headers,
+            body: if bytes.is_empty() {
+                Value::Null
+            } else {
+                serde_json::from_slice(&bytes)
+                    .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into()))
+            },
+        }
+    }
+
+    pub async fn post(&self, path: &str, body: Value) -> TestResponse {
+        self.request(Method::POST, path, Some(body), &[]).await
+    }
+
+    /// Sends the access token as a bearer credential.
+    pub async fn get_as(&self, path: &str, session: &TestSession) -> TestResponse {
+        self.request(Method::GET, path, None, &[session.bearer()])
+            .await
+    }
+
+    /// Registers `username` with a new passkey, panicking on any failure.
+    pub async fn register(&self, username: &str, role: Option<&str>) -> TestUser {
+        let mut passkey = self.passkey();
+        let begin = self
+            .post(
+                "/auth/register/begin",
+                json!({ "username": username, "role": role }),
+            )
+            .await
+            .expect_ok();
+        let options: CreationChallengeResponse = begin.field("options");
+
+        let finish = self
+            .post(
+                "/auth/register/finish",
+                json!({
+                    "username": username,
+                    "session_id": begin.body["session_id"],
+                    "credentials": passkey.register(&options),
+                }),
+            )
+            .await
+            .expect_ok();
+
+        TestUser {
+            username: username.to_string(),
+            passkey,
+            recovery_codes: finish.field("recovery_codes"),
+            credential: finish.body["credential"].clone(),
+        }
+    }
+
+    /// Logs `user` in with its passkey, panicking on any failure.
+    pub async fn login(&self, user: &mut TestUser) -> TestSession {
+        let begin = self
+            .post("/auth/login/begin", json!({ "username": user.username }))
+            .await
+            .expect_ok();
+        let options: RequestChallengeResponse = begin.field("options");
+
+        self.post(
+            "/auth/login/finish",
+            json!({
+                "username": user.username,
+                "session_id": begin.body["session_id"],
+                "credentials": user.passkey.authenticate(&options),
+            }),
+        )
+        .await
+        .expect_ok()
+        .session()
+    }
+
+    /// Exchanges the refresh token for a new pair, panicking on any failure.
+    pub async fn refresh(&self, session: &TestSession) -> TestSession {
+        self.request(Method::POST, "/auth/refresh", None, &[session.cookie()])
+            .await
+            .expect_ok()
+            .session()
+    }
+}
+
+impl TestResponse {
+    pub fn expect_ok(self) -> Self {
+        assert_eq!(
+            self.status,
+            StatusCode::OK,
+            "unexpected response: {}",
+            self.body
+        );
+        self
+    }
+
+    pub fn field<T: DeserializeOwned>(&self, name: &str) -> T {
+        serde_json::from_value(self.body[name].clone())
+            .unwrap_or_else(|e| panic!("Invalid {} in {}: {}", name, self.body, e))
+    }
+
+    /// The refresh token set by this response, if any.
+    pub fn refresh_token(&self) -> Option<String> {
+        let prefix = format!("{}=", REFRESH_TOKEN_COOKIE_NAME);
+        self.headers
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(|cookie| cookie.strip_prefix(&prefix))
+            .map(|rest| rest.split(';').next().unwrap_or_default().to_string())
+    }
+
+    fn session(&self) -> TestSession {
+        TestSession {
+            access_token: self.field("access_token"),
+            refresh_token: self
+                .refresh_token()
+                .expect("response did not set a refresh token"),
+        }
+    }
+}
+
+impl TestSession {
+    pub fn bearer(&self) -> (header::HeaderName, String) {
+        (
+            header::AUTHORIZATION,
+            format!("Bearer {}", self.access_token),
+        )
+    }
+
+    pub fn cookie(&self) -> (header::HeaderName, String) {
+        (
+            header::COOKIE,
+            format!("{}={}", REFRESH_TOKEN_COOKIE_NAME, self.refresh_token),
+        )
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use ring::{
+    rand::SystemRandom,
+    signature::{ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair, KeyPair},
+};
+use serde_cbor_2::Value;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use webauthn_rs::prelude::{CreationChallengeResponse, RequestChallengeResponse};
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+const COSE_KTY_EC2: i128 = 2;
+const COSE_ALG_ES256: i128 = -7;
+const COSE_CRV_P256: i128 = 1;
+
+/// A software passkey holding one ES256 credential, for driving ceremonies
+/// without a browser. It answers with `none` attestation and the zero AAGUID,
+/// so it cannot enroll into a role with an authenticator allowlist.
+pub struct SoftPasskey {
+    origin: String,
+    credential_id: Vec<u8>,
+    key: EcdsaKeyPair,
+    rng: SystemRandom,
+    counter: u32,
+}
+
+impl SoftPasskey {
+    /// `origin` is what a browser would put in the client data, the
+    /// frontend origin the relying party expects.
+    pub fn new(origin: &str) -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+
+        Self {
+            origin: origin.trim_end_matches('/').to_string(),
+            credential_id: Uuid::new_v4().as_bytes().to_vec(),
+            key,
+            rng,
+            counter: 0,
+        }
+    }
+
+    pub fn credential_id(&self) -> &[u8] {
+        &self.credential_id
+    }
+
+    /// Answers `navigator.credentials.create()` for the given options, in
+    /// the JSON shape a browser client posts to `/finish`.
+    pub fn register(&mut self, options: &CreationChallengeResponse) -> serde_json::Value {
+        let options = &options.public_key;
+        let client_data = self.client_data("webauthn.create", options.challenge.as_slice());
+
+        let mut auth_data = self.auth_data(
+            &options.rp.id,
+            FLAG_USER_PRESENT | FLAG_USER_VERIFIED | FLAG_ATTESTED_CREDENTIAL,
+        );
+        auth_data.extend_from_slice(Uuid::nil().as_bytes());
+        auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+        auth_data.extend_from_slice(&self.credential_id);
+        auth_data.extend(self.cose_key());
+
+        let attestation_object = cbor_map([
+            (Value::Text("fmt".into()), Value::Text("none".into())),
+            (Value::Text("attStmt".into()), Value::Map(BTreeMap::new())),
+            (Value::Text("authData".into()), Value::Bytes(auth_data)),
+        ]);
+
+        let id = BASE64_URL_SAFE_NO_PAD.encode(&self.credential_id);
+        json!({
+            "id": id,
+            "rawId": id,
+            "type": "public-key",
+            "response": {
+                "attestationObject": BASE64_URL_SAFE_NO_PAD.encode(attestation_object),
+                "clientDataJSON": BASE64_URL_SAFE_NO_PAD.encode(client_data),
+            },
+            // The key lives here rather than on the authenticator.
+            "clientExtensionResults": { "credProps": { "rk": false } },
+        })
+    }
+
+    /// Sets the signature counter back, as a cloned authenticator would
+    /// report it.
+    pub fn rewind_counter(&mut self, counter: u32) {
+        self.counter = counter;
+    }
+
+    /// Answers `navigator.credentials.get()` for the given options.
+    pub fn authenticate(&mut self, options: &RequestChallengeResponse) -> serde_json::Value {
+        let options = &options.public_key;
+        let client_data = self.client_data("webauthn.get", options.challenge.as_slice());
+        let auth_data = self.auth_data(&options.rp_id, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+
+        let mut signed = auth_data.clone();
+        signed.extend_from_slice(&Sha256::digest(&client_data));
+        let signature = self.key.sign(&self.rng, &signed).unwrap();
+
+        let id = BASE64_URL_SAFE_NO_PAD.encode(&self.credential_id);
+        json!({
+            "id": id,
+            "rawId": id,
+            "type": "public-key",
+            "response": {
+                "authenticatorData": BASE64_URL_SAFE_NO_PAD.encode(auth_data),
+                "clientDataJSON": BASE64_URL_SAFE_NO_PAD.encode(client_data),
+                "signature": BASE64_URL_SAFE_NO_PAD.encode(signature),
+            },
+        })
+    }
+
+    fn client_data(&self, kind: &str, challenge: &[u8]) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "type": kind,
+            "challenge": BASE64_URL_SAFE_NO_PAD.encode(challenge),
+            "origin": self.origin,
+            "crossOrigin": false,
+        }))
+        .unwrap()
+    }
+
+    /// RP ID hash, flags and a signature counter that grows with every use.
+    fn auth_data(&mut self, rp_id: &str, flags: u8) -> Vec<u8> {
+        self.counter += 1;
+
+        let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend_from_slice(&self.counter.to_be_bytes());
+        data
+    }
+
+    fn cose_key(&self) -> Vec<u8> {
+        // Uncompressed SEC1 point: 0x04 || x || y
+        let point = self.key.public_key().as_ref();
+
+        cbor_map([
+            (Value::Integer(1), Value::Integer(COSE_KTY_EC2)),
+            (Value::Integer(3), Value::Integer(COSE_ALG_ES256)),
+            (Value::Integer(-1), Value::Integer(COSE_CRV_P256)),
+            (Value::Integer(-2), Value::Bytes(point[1..33].to_vec())),
+            (Value::Integer(-3), Value::Bytes(point[33..65].to_vec())),
+        ])
+    }
+}
+
+fn cbor_map<const N: usize>(entries: [(Value, Value); N]) -> Vec<u8> {
+    serde_cbor_2::to_vec(&Value::Map(BTreeMap::from(entries))).unwrap()
+}
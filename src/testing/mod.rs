@@ -0,0 +1,12 @@
+//! End-to-end harness: the real router over throwaway containers, plus a
+//! software passkey to drive the WebAuthn ceremonies against it.
+
+pub(crate) mod app;
+pub(crate) mod authenticator;
+
+#[cfg_attr(not(test), allow(unused_imports))]
+pub(crate) use app::TestApp;
+pub(crate) use authenticator::SoftPasskey;
+
+#[cfg(test)]
+mod tests;
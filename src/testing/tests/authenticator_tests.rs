@@ -0,0 +1,306 @@
+use std::sync::Arc;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use url::Url;
+use uuid::Uuid;
+use webauthn_rs::{
+    Webauthn, WebauthnBuilder,
+    prelude::{
+        AuthenticationResult, Credential, Passkey, PublicKeyCredential, RegisterPublicKeyCredential,
+    },
+};
+
+use crate::{
+    app::{AppError, ErrorCode},
+    auth::{
+        dto::CredentialEntry, login_cache::CachedRepository, memory_repo::MemoryRepository,
+        model::StoredCredential, traits::AuthRepository,
+    },
+    testing::SoftPasskey,
+    utils::mocks::MockLoginStore,
+};
+
+const ORIGIN: &str = "http://localhost:3000";
+
+fn webauthn() -> Webauthn {
+    WebauthnBuilder::new("localhost", &Url::parse(ORIGIN).unwrap())
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+/// Runs a login against `passkey` as the server currently stores it.
+fn login(
+    webauthn: &Webauthn,
+    authenticator: &mut SoftPasskey,
+    passkey: &Passkey,
+) -> Result<AuthenticationResult, webauthn_rs::prelude::WebauthnError> {
+    let (options, state) = webauthn
+        .start_passkey_authentication(std::slice::from_ref(passkey))
+        .unwrap();
+    let credential: PublicKeyCredential =
+        serde_json::from_value(authenticator.authenticate(&options)).unwrap();
+    webauthn.finish_passkey_authentication(&credential, &state)
+}
+
+async fn stored_passkey(repo: &MemoryRepository, user_id: Uuid) -> Passkey {
+    repo.list_credentials(user_id).await.unwrap()[0]
+        .passkey
+        .clone()
+}
+
+fn enroll(webauthn: &Webauthn, authenticator: &mut SoftPasskey) -> Passkey {
+    let (options, state) = webauthn
+        .start_passkey_registration(Uuid::new_v4(), "alice", "alice", None)
+        .unwrap();
+    let credential: RegisterPublicKeyCredential =
+        serde_json::from_value(authenticator.register(&options)).unwrap();
+
+    webauthn
+        .finish_passkey_registration(&credential, &state)
+        .unwrap()
+}
+
+#[test]
+fn test_registration_is_accepted() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new(ORIGIN);
+
+    let passkey = enroll(&webauthn, &mut authenticator);
+
+    assert_eq!(passkey.cred_id().as_slice(), authenticator.credential_id());
+}
+
+#[test]
+fn test_assertions_verify_with_growing_counter() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new(ORIGIN);
+    let passkey = enroll(&webauthn, &mut authenticator);
+
+    for expected in 2..4 {
+        let (options, state) = webauthn
+            .start_passkey_authentication(std::slice::from_ref(&passkey))
+            .unwrap();
+        let credential: PublicKeyCredential =
+            serde_json::from_value(authenticator.authenticate(&options)).unwrap();
+
+        let result = webauthn
+            .finish_passkey_authentication(&credential, &state)
+            .unwrap();
+        assert!(result.user_verified());
+        assert_eq!(result.counter(), expected);
+    }
+}
+
+#[test]
+fn test_other_origin_is_rejected() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new("https://evil.example");
+    let (options, state) = webauthn
+        .start_passkey_registration(Uuid::new_v4(), "alice", "alice", None)
+        .unwrap();
+    let credential: RegisterPublicKeyCredential =
+        serde_json::from_value(authenticator.register(&options)).unwrap();
+
+    assert!(
+        webauthn
+            .finish_passkey_registration(&credential, &state)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_enrolled_passkey_lists_its_metadata() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new(ORIGIN);
+    let passkey = enroll(&webauthn, &mut authenticator);
+
+    let entry = CredentialEntry::from(StoredCredential {
+        passkey,
+        aaguid: None,
+        created_at: chrono::Utc::now(),
+        last_used_at: None,
+        clone_suspected_at: None,
+    });
+
+    assert_eq!(
+        entry.id,
+        BASE64_URL_SAFE_NO_PAD.encode(authenticator.credential_id())
+    );
+    assert!(!entry.backup_eligible);
+    assert!(!entry.backup_state);
+    assert!(entry.transports.is_empty());
+}
+
+#[tokio::test]
+async fn test_login_writes_back_counter_and_last_use() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new(ORIGIN);
+    let passkey = enroll(&webauthn, &mut authenticator);
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", None).await.unwrap();
+    repo.complete_registration(user.id, "alice", &passkey, None, &[], true)
+        .await
+        .unwrap();
+
+    let (options, state) = webauthn
+        .start_passkey_authentication(std::slice::from_ref(&passkey))
+        .unwrap();
+    let credential: PublicKeyCredential =
+        serde_json::from_value(authenticator.authenticate(&options)).unwrap();
+    let result = webauthn
+        .finish_passkey_authentication(&credential, &state)
+        .unwrap();
+    repo.update_credential("alice", &result).await.unwrap();
+
+    let stored = repo.list_credentials(user.id).await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert!(stored[0].last_used_at.is_some());
+    assert_eq!(Credential::from(stored[0].passkey.clone()).counter, 2);
+}
+
+#[tokio::test]
+async fn test_stale_login_does_not_roll_back_counter() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new(ORIGIN);
+    let passkey = enroll(&webauthn, &mut authenticator);
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", None).await.unwrap();
+    repo.complete_registration(user.id, "alice", &passkey, None, &[], true)
+        .await
+        .unwrap();
+
+    // Two logins started from the same stored state; the older one is
+    // written back last.
+    let older = login(&webauthn, &mut authenticator, &passkey).unwrap();
+    let newer = login(&webauthn, &mut authenticator, &passkey).unwrap();
+    repo.update_credential("alice", &newer).await.unwrap();
+    repo.update_credential("alice", &older).await.unwrap();
+
+    let stored = stored_passkey(&repo, user.id).await;
+    assert_eq!(Credential::from(stored).counter, 3);
+}
+
+#[tokio::test]
+async fn test_cloned_authenticator_is_refused_after_stale_login() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new(ORIGIN);
+    let passkey = enroll(&webauthn, &mut authenticator);
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", None).await.unwrap();
+    repo.complete_registration(user.id, "alice", &passkey, None, &[], true)
+        .await
+        .unwrap();
+
+    let older = login(&webauthn, &mut authenticator, &passkey).unwrap();
+    let newer = login(&webauthn, &mut authenticator, &passkey).unwrap();
+    repo.update_credential("alice", &newer).await.unwrap();
+    repo.update_credential("alice", &older).await.unwrap();
+
+    // A copy of the key that has signed up to 2 answers with 3, which the
+    // stored counter has already reached.
+    authenticator.rewind_counter(2);
+    let stored = stored_passkey(&repo, user.id).await;
+    assert!(login(&webauthn, &mut authenticator, &stored).is_err());
+
+    let stored = stored_passkey(&repo, user.id).await;
+    assert!(login(&webauthn, &mut authenticator, &stored).is_ok());
+}
+
+#[tokio::test]
+async fn test_locked_passkey_is_left_out_until_unlocked() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new(ORIGIN);
+    let passkey = enroll(&webauthn, &mut authenticator);
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", None).await.unwrap();
+    repo.complete_registration(user.id, "alice", &passkey, None, &[], true)
+        .await
+        .unwrap();
+    let result = login(&webauthn, &mut authenticator, &passkey).unwrap();
+    repo.update_credential("alice", &result).await.unwrap();
+
+    assert!(
+        repo.lock_cloned_credential(user.id, authenticator.credential_id())
+            .await
+            .unwrap()
+    );
+    assert!(
+        !repo
+            .lock_cloned_credential(user.id, authenticator.credential_id())
+            .await
+            .unwrap()
+    );
+    let error = repo
+        .get_active_user_with_credential("alice")
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::CredentialLocked);
+    let error = repo.update_credential("alice", &result).await.unwrap_err();
+    assert_eq!(error.code(), ErrorCode::CredentialLocked);
+    assert!(
+        repo.list_credentials(user.id).await.unwrap()[0]
+            .clone_suspected_at
+            .is_some()
+    );
+
+    // The genuine key is behind the stored counter; unlocking restarts it.
+    authenticator.rewind_counter(0);
+    repo.unlock_credential(user.id, authenticator.credential_id())
+        .await
+        .unwrap();
+    let (_, passkeys) = repo.get_active_user_with_credential("alice").await.unwrap();
+    assert_eq!(Credential::from(passkeys[0].clone()).counter, 0);
+    assert!(login(&webauthn, &mut authenticator, &passkeys[0]).is_ok());
+}
+
+#[tokio::test]
+async fn test_only_locked_passkeys_can_be_unlocked() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new(ORIGIN);
+    let passkey = enroll(&webauthn, &mut authenticator);
+    let repo = MemoryRepository::new();
+    let user = repo.create_user("alice", None).await.unwrap();
+    repo.complete_registration(user.id, "alice", &passkey, None, &[], true)
+        .await
+        .unwrap();
+
+    let error = repo
+        .unlock_credential(user.id, authenticator.credential_id())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AppError::NotFound(_)));
+
+    repo.lock_cloned_credential(user.id, authenticator.credential_id())
+        .await
+        .unwrap();
+    let error = repo
+        .unlock_credential(Uuid::new_v4(), authenticator.credential_id())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AppError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn test_used_passkey_drops_the_cached_login() {
+    let webauthn = webauthn();
+    let mut authenticator = SoftPasskey::new(ORIGIN);
+    let passkey = enroll(&webauthn, &mut authenticator);
+    let store = Arc::new(MockLoginStore::default());
+    let repo = CachedRepository::new(MemoryRepository::new(), Some(store.clone() as _));
+    let user = repo.create_user("alice", None).await.unwrap();
+    repo.complete_registration(user.id, "alice", &passkey, None, &[], true)
+        .await
+        .unwrap();
+
+    for expected in 2..4 {
+        let (_, passkeys) = repo.get_active_user_with_credential("alice").await.unwrap();
+        assert!(store.entry("alice").is_some());
+        let result = login(&webauthn, &mut authenticator, &passkeys[0]).unwrap();
+        repo.update_credential("alice", &result).await.unwrap();
+        assert!(store.entry("alice").is_none());
+
+        let (_, passkeys) = repo.get_active_user_with_credential("alice").await.unwrap();
+        assert_eq!(Credential::from(passkeys[0].clone()).counter, expected);
+    }
+}
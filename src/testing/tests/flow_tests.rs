@@ -0,0 +1,224 @@
+//! Runs against Docker containers; each test starts its own pair.
+
+use std::{sync::Arc, time::Duration};
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use crate::{
+    config::{CircuitBreaker, CircuitBreakerConfig},
+    sessions::{model::SessionCriteria, repo::Repository, traits::SessionRepository},
+    testing::TestApp,
+};
+
+#[tokio::test]
+async fn test_register_login_refresh() {
+    let app = TestApp::spawn().await;
+    let mut user = app.register("alice", None).await;
+    assert!(!user.recovery_codes.is_empty());
+    assert_eq!(
+        user.credential,
+        json!({ "discoverable": false, "user_verified": true })
+    );
+
+    let session = app.login(&mut user).await;
+    let profile = app.get_as("/auth/me", &session).await.expect_ok();
+    assert_eq!(profile.body["username"], "alice");
+    assert_eq!(profile.body["status"], "active");
+
+    let refreshed = app.refresh(&session).await;
+    assert_ne!(refreshed.refresh_token, session.refresh_token);
+    app.get_as("/auth/me", &refreshed).await.expect_ok();
+
+    // Within the grace window the rotated-out token gets the same pair once,
+    // then it is spent.
+    let replayed = app.refresh(&session).await;
+    assert_eq!(replayed.refresh_token, refreshed.refresh_token);
+    let reused = app
+        .request(Method::POST, "/auth/refresh", None, &[session.cookie()])
+        .await;
+    assert_eq!(reused.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_refresh_grace_ends_once_the_new_token_is_used() {
+    let app = TestApp::spawn().await;
+    let mut user = app.register("alice", None).await;
+    let session = app.login(&mut user).await;
+
+    let refreshed = app.refresh(&session).await;
+    app.refresh(&refreshed).await;
+
+    let reused = app
+        .request(Method::POST, "/auth/refresh", None, &[session.cookie()])
+        .await;
+    assert_eq!(reused.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_rotated_session_is_listed_once() {
+    let app = TestApp::spawn().await;
+    let mut user = app.register("alice", None).await;
+    let session = app.login(&mut user).await;
+    let refreshed = app.refresh(&session).await;
+    app.refresh(&refreshed).await;
+
+    // Issuances are written after the response.
+    let client = app.db().get().await.unwrap();
+    for _ in 0..50 {
+        let row = client
+            .query_one("SELECT COUNT(*) AS issued FROM token_issuances", &[])
+            .await
+            .unwrap();
+        if row.get::<_, i64>("issued") == 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let repo = Repository::new(
+        app.db().clone(),
+        Arc::new(CircuitBreaker::new(
+            "database",
+            CircuitBreakerConfig::default(),
+        )),
+    );
+    let criteria = SessionCriteria::default();
+    let sessions = repo.live_sessions(&criteria, None, 10).await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(repo.count_live_sessions(&criteria).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_credentials_list_reports_metadata() {
+    let app = TestApp::spawn().await;
+    let mut user = app.register("alice", None).await;
+    app.login(&mut user).await;
+    let session = app.login(&mut user).await;
+
+    let listing = app.get_as("/auth/credentials", &session).await.expect_ok();
+    let credentials = listing.body["credentials"].as_array().unwrap();
+    assert_eq!(credentials.len(), 1);
+    // The soft passkey sends the zero AAGUID and lives in process memory.
+    assert_eq!(credentials[0]["aaguid"], serde_json::Value::Null);
+    assert_eq!(credentials[0]["backup_eligible"], false);
+    assert_eq!(credentials[0]["backup_state"], false);
+    assert!(credentials[0]["last_used_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_registered_username_conflicts() {
+    let app = TestApp::spawn().await;
+    app.register("alice", None).await;
+
+    let response = app
+        .post("/auth/register/begin", json!({ "username": "alice" }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_login_with_unknown_passkey_fails() {
+    let app = TestApp::spawn().await;
+    let mut user = app.register("alice", None).await;
+    user.passkey = app.passkey();
+
+    let begin = app
+        .post("/auth/login/begin", json!({ "username": "alice" }))
+        .await
+        .expect_ok();
+    let response = app
+        .post(
+            "/auth/login/finish",
+            json!({
+                "username": "alice",
+                "session_id": begin.body["session_id"],
+                "credentials": user.passkey.authenticate(&begin.field("options")),
+            }),
+        )
+        .await;
+
+    assert!(response.status.is_client_error());
+}
+
+#[tokio::test]
+async fn test_recovery_replaces_passkey() {
+    let app = TestApp::spawn().await;
+    let mut user = app.register("alice", None).await;
+    let mut replacement = app.passkey();
+
+    let begin = app
+        .post(
+            "/auth/recover/begin",
+            json!({ "username": "alice", "recovery_code": user.recovery_codes[0] }),
+        )
+        .await
+        .expect_ok();
+    app.post(
+        "/auth/recover/finish",
+        json!({
+            "username": "alice",
+            "session_id": begin.body["session_id"],
+            "credentials": replacement.register(&begin.field("options")),
+        }),
+    )
+    .await
+    .expect_ok();
+
+    std::mem::swap(&mut user.passkey, &mut replacement);
+    app.login(&mut user).await;
+}
+
+// The in-memory auth repository does not read the seeded table.
+#[cfg(not(feature = "memory-store"))]
+#[tokio::test]
+async fn test_seeded_role_grants_permissions() {
+    let app = TestApp::spawn().await;
+    let client = app.db().get().await.unwrap();
+    client
+        .batch_execute(
+            "INSERT INTO roles (name) VALUES ('auditor');
+             INSERT INTO role_permissions (role, permission) VALUES ('auditor', 'audit:read');",
+        )
+        .await
+        .unwrap();
+
+    let mut user = app.register("carol", Some("auditor")).await;
+    let session = app.login(&mut user).await;
+    let profile = app.get_as("/auth/me", &session).await.expect_ok();
+
+    assert_eq!(profile.body["roles"], json!(["auditor"]));
+    assert_eq!(profile.body["permissions"], json!(["audit:read"]));
+}
+
+#[tokio::test]
+async fn test_privileged_role_needs_an_administrator() {
+    let app = TestApp::spawn().await;
+
+    let unknown = app
+        .post(
+            "/auth/register/begin",
+            json!({ "username": "mallory", "role": "superuser" }),
+        )
+        .await;
+    assert_eq!(unknown.status, StatusCode::BAD_REQUEST);
+
+    let privileged = app
+        .post(
+            "/auth/register/begin",
+            json!({ "username": "mallory", "role": "admin" }),
+        )
+        .await;
+    assert_eq!(privileged.status, StatusCode::FORBIDDEN);
+    assert_eq!(privileged.body["code"], "ROLE_NOT_ALLOWED");
+    assert_eq!(privileged.body["details"]["role"], "admin");
+
+    let invited = app
+        .post(
+            "/auth/register/begin",
+            json!({ "username": "mallory", "role": "admin", "invitation": "guessed" }),
+        )
+        .await;
+    assert_eq!(invited.status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,4 @@
+#[cfg(test)]
+mod authenticator_tests;
+#[cfg(test)]
+mod flow_tests;
@@ -0,0 +1,5 @@
+pub(crate) mod request;
+pub(crate) mod response;
+
+pub(crate) use request::IssuanceLogQuery;
+pub(crate) use response::{IssuanceLogEntry, IssuanceLogResponse};
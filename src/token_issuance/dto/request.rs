@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError, impl_validated_query_request, token_issuance::model::IssuanceFilter,
+    utils::Validatable,
+};
+
+pub const DEFAULT_ISSUANCE_LIMIT: u32 = 100;
+pub const MAX_ISSUANCE_LIMIT: u32 = 500;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct IssuanceLogQuery {
+    /// Only tokens issued to this user
+    pub user_id: Option<Uuid>,
+    /// Refresh token id
+    pub jti: Option<String>,
+    /// Id of the key that signed the access token
+    pub kid: Option<String>,
+    /// Inclusive lower bound (RFC 3339)
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound (RFC 3339); pass the oldest `issued_at` seen to page back
+    pub to: Option<DateTime<Utc>>,
+    /// Number of issuances to return
+    #[param(example = 100, minimum = 1, maximum = 500)]
+    pub limit: Option<u32>,
+}
+
+impl IssuanceLogQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_ISSUANCE_LIMIT)
+    }
+
+    pub fn to_filter(&self) -> IssuanceFilter {
+        IssuanceFilter {
+            user_id: self.user_id,
+            jti: self.jti.clone(),
+            kid: self.kid.clone(),
+            from: self.from,
+            to: self.to,
+            limit: i64::from(self.limit()),
+        }
+    }
+}
+
+impl Validatable for IssuanceLogQuery {
+    fn validate(&self) -> Result<(), AppError> {
+        if !(1..=MAX_ISSUANCE_LIMIT).contains(&self.limit()) {
+            return Err(AppError::BadRequest(format!(
+                "limit must be between 1 and {}",
+                MAX_ISSUANCE_LIMIT
+            )));
+        }
+
+        if let (Some(from), Some(to)) = (self.from, self.to)
+            && from >= to
+        {
+            return Err(AppError::BadRequest(String::from(
+                "from must be earlier than to",
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_query_request!(IssuanceLogQuery);
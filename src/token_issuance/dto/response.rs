@@ -0,0 +1,51 @@
+use axum::{Json, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::token_issuance::model::IssuanceRecord;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IssuanceLogResponse {
+    pub issuances: Vec<IssuanceLogEntry>,
+}
+
+impl IntoResponse for IssuanceLogResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IssuanceLogEntry {
+    pub user_id: Uuid,
+    #[schema(example = "3q2-7wAAQZ2x2u6eVvQK5A")]
+    pub jti: String,
+    #[schema(example = "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs")]
+    pub kid: String,
+    #[schema(example = "login")]
+    pub grant_type: String,
+    #[schema(example = "ios-2.3.1")]
+    pub client_app: Option<String>,
+    #[schema(example = "203.0.113.7")]
+    pub ip: Option<String>,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub issued_at: String,
+    #[schema(example = "2024-01-08T12:00:00Z")]
+    pub expires_at: String,
+}
+
+impl From<IssuanceRecord> for IssuanceLogEntry {
+    fn from(record: IssuanceRecord) -> Self {
+        Self {
+            user_id: record.user_id,
+            jti: record.jti,
+            kid: record.kid,
+            grant_type: record.grant_type,
+            client_app: record.client_app,
+            ip: record.ip.map(|ip| ip.to_string()),
+            issued_at: record.issued_at.to_rfc3339(),
+            expires_at: record.expires_at.to_rfc3339(),
+        }
+    }
+}
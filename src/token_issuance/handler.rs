@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    auth::permissions::AuditRead,
+    token_issuance::dto::{IssuanceLogQuery, IssuanceLogResponse},
+};
+
+/// Query token issuances
+///
+/// Returns issued token pairs, newest first, optionally filtered by user,
+/// refresh token id, signing key and time range. Requires `audit:read`.
+#[utoipa::path(
+    get,
+    path = "/admin/tokens/issuances",
+    tag = "Admin",
+    params(IssuanceLogQuery),
+    responses(
+        (status = 200, description = "Token issuances", body = IssuanceLogResponse),
+        (status = 400, description = "Invalid query parameters", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn search(
+    _admin: RequirePermission<AuditRead>,
+    State(state): State<Arc<AppState>>,
+    query: IssuanceLogQuery,
+) -> Result<IssuanceLogResponse, AppError> {
+    state.issuance_service.search(query).await
+}
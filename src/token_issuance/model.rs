@@ -0,0 +1,109 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    audit::model::AuditContext,
+    auth::jwt::TokenPair,
+    utils::FromRow,
+};
+
+/// The flow a token pair was issued by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrantType {
+    Login,
+    Refresh,
+    SessionUpdate,
+}
+
+impl GrantType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GrantType::Login => "login",
+            GrantType::Refresh => "refresh",
+            GrantType::SessionUpdate => "session_update",
+        }
+    }
+}
+
+/// One issued token pair. `jti` is the refresh token's, the one that can be
+/// revoked; `kid` is the key that signed the access token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenIssuance {
+    pub user_id: Uuid,
+    pub jti: String,
+    pub kid: String,
+    pub grant_type: GrantType,
+    pub client_app: Option<String>,
+    pub ip: Option<IpAddr>,
+    pub expires_at: DateTime<Utc>,
+    /// The refresh token a refresh or session update rotated out.
+    pub rotated_from: Option<String>,
+    /// The tenant of the request the pair was issued in.
+    pub tenant: String,
+}
+
+impl TokenIssuance {
+    pub fn new(
+        user_id: Uuid,
+        tokens: &TokenPair,
+        grant_type: GrantType,
+        ctx: &AuditContext,
+    ) -> Self {
+        Self {
+            user_id,
+            jti: tokens.jti.clone(),
+            kid: tokens.kid.clone(),
+            grant_type,
+            client_app: ctx.client_app.clone(),
+            ip: ctx.ip,
+            expires_at: DateTime::from_timestamp(tokens.expires_at, 0).unwrap_or_default(),
+            rotated_from: None,
+            tenant: current_tenant(),
+        }
+    }
+
+    pub fn with_rotated_from(mut self, jti: &str) -> Self {
+        self.rotated_from = Some(jti.to_owned());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IssuanceRecord {
+    pub user_id: Uuid,
+    pub jti: String,
+    pub kid: String,
+    pub grant_type: String,
+    pub client_app: Option<String>,
+    pub ip: Option<IpAddr>,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl FromRow for IssuanceRecord {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(IssuanceRecord {
+            user_id: row.try_get("user_id")?,
+            jti: row.try_get("jti")?,
+            kid: row.try_get("kid")?,
+            grant_type: row.try_get("grant_type")?,
+            client_app: row.try_get("client_app")?,
+            ip: row.try_get("ip")?,
+            issued_at: row.try_get("issued_at")?,
+            expires_at: row.try_get("expires_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IssuanceFilter {
+    pub user_id: Option<Uuid>,
+    pub jti: Option<String>,
+    pub kid: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: i64,
+}
@@ -0,0 +1,18 @@
+/// Every query takes the tenant of the request as its last parameter.
+pub mod token_issuances {
+    pub const INSERT: &str = "INSERT INTO token_issuances
+             (user_id, jti, kid, grant_type, client_app, ip, expires_at, rotated_from, tenant_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)";
+
+    pub const SEARCH: &str = "SELECT user_id, jti, kid, grant_type, client_app, ip,
+                issued_at, expires_at
+         FROM token_issuances
+         WHERE ($1::uuid IS NULL OR user_id = $1)
+           AND ($2::text IS NULL OR jti = $2)
+           AND ($3::text IS NULL OR kid = $3)
+           AND ($4::timestamptz IS NULL OR issued_at >= $4)
+           AND ($5::timestamptz IS NULL OR issued_at < $5)
+           AND tenant_id = $7
+         ORDER BY issued_at DESC
+         LIMIT $6";
+}
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    config::CircuitBreaker,
+    db_insert, db_select,
+    token_issuance::{
+        model::{IssuanceFilter, IssuanceRecord, TokenIssuance},
+        queries,
+        traits::IssuanceRepository,
+    },
+    utils::{BaseRepository, FromRow, QueryKind, ReadReplica},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+
+    /// Runs searches on `replica` when there is one.
+    pub fn with_replica(mut self, replica: Option<ReadReplica>) -> Self {
+        self.base = self.base.with_replica(replica);
+        self
+    }
+}
+
+impl IssuanceRepository for Repository {
+    async fn insert(&self, issuance: &TokenIssuance) -> Result<(), AppError> {
+        db_insert!("token_issuances", {
+            self.base
+                .execute_prepared_raw(
+                    queries::token_issuances::INSERT,
+                    &[
+                        &issuance.user_id as &(dyn ToSql + Sync),
+                        &issuance.jti,
+                        &issuance.kid,
+                        &issuance.grant_type.as_str(),
+                        &issuance.client_app,
+                        &issuance.ip,
+                        &issuance.expires_at,
+                        &issuance.rotated_from,
+                        &issuance.tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        Ok(())
+    }
+
+    async fn search(&self, filter: &IssuanceFilter) -> Result<Vec<IssuanceRecord>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("token_issuances", {
+            self.base
+                .execute_prepared_on(
+                    QueryKind::Read,
+                    queries::token_issuances::SEARCH,
+                    &[
+                        &filter.user_id as &(dyn ToSql + Sync),
+                        &filter.jti,
+                        &filter.kid,
+                        &filter.from,
+                        &filter.to,
+                        &filter.limit,
+                        &tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        rows.iter().map(IssuanceRecord::from_row).collect()
+    }
+}
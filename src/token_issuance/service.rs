@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use tracing::Instrument;
+
+use crate::{
+    app::AppError,
+    token_issuance::{
+        dto::{IssuanceLogQuery, IssuanceLogResponse},
+        model::TokenIssuance,
+        traits::{IssuanceRecorder, IssuanceRepository},
+    },
+};
+
+pub struct IssuanceService<R>
+where
+    R: IssuanceRepository + 'static,
+{
+    issuance_repo: Arc<R>,
+}
+
+impl<R> IssuanceService<R>
+where
+    R: IssuanceRepository + 'static,
+{
+    pub fn new(issuance_repo: Arc<R>) -> Self {
+        Self { issuance_repo }
+    }
+
+    pub async fn search(&self, query: IssuanceLogQuery) -> Result<IssuanceLogResponse, AppError> {
+        let records = self.issuance_repo.search(&query.to_filter()).await?;
+
+        Ok(IssuanceLogResponse {
+            issuances: records.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+impl<R> IssuanceRecorder for IssuanceService<R>
+where
+    R: IssuanceRepository + 'static,
+{
+    /// Also emitted on the `audit` tracing target, so the trail survives a
+    /// database outage in the logs.
+    fn record(&self, issuance: TokenIssuance) {
+        tracing::info!(
+            target: "audit",
+            event = "token_issued",
+            user_id = %issuance.user_id,
+            jti = issuance.jti,
+            kid = issuance.kid,
+            grant_type = issuance.grant_type.as_str(),
+            client_app = issuance.client_app.as_deref(),
+            ip = issuance.ip.map(|ip| ip.to_string()),
+        );
+
+        let issuance_repo = Arc::clone(&self.issuance_repo);
+        tokio::spawn(
+            async move {
+                if let Err(e) = issuance_repo.insert(&issuance).await {
+                    tracing::error!(
+                        user_id = %issuance.user_id,
+                        "Failed to persist token issuance: {}",
+                        e
+                    );
+                }
+            }
+            .in_current_span(),
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use chrono::{Duration, Utc};
+
+use crate::{app::AppError, token_issuance::dto::IssuanceLogQuery, utils::Validatable};
+
+#[test]
+fn test_default_query_is_valid() {
+    let query = IssuanceLogQuery::default();
+
+    assert!(query.validate().is_ok());
+    assert_eq!(query.to_filter().limit, 100);
+}
+
+#[test]
+fn test_limit_out_of_range() {
+    for limit in [0, 501] {
+        let query = IssuanceLogQuery {
+            limit: Some(limit),
+            ..Default::default()
+        };
+        assert!(matches!(query.validate(), Err(AppError::BadRequest(_))));
+    }
+}
+
+#[test]
+fn test_inverted_range_rejected() {
+    let now = Utc::now();
+    let query = IssuanceLogQuery {
+        from: Some(now),
+        to: Some(now - Duration::hours(1)),
+        ..Default::default()
+    };
+
+    assert!(matches!(query.validate(), Err(AppError::BadRequest(_))));
+}
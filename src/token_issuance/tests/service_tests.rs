@@ -0,0 +1,132 @@
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    audit::model::AuditContext,
+    auth::jwt::{RefreshToken, TokenPair},
+    token_issuance::{
+        dto::IssuanceLogQuery,
+        model::{GrantType, IssuanceFilter, IssuanceRecord, TokenIssuance},
+        service::IssuanceService,
+        traits::{IssuanceRecorder, IssuanceRepository},
+    },
+};
+
+#[derive(Default)]
+struct MockRepository {
+    records: Vec<IssuanceRecord>,
+    inserted: Mutex<Vec<TokenIssuance>>,
+    searched_with: Mutex<Option<IssuanceFilter>>,
+}
+
+impl IssuanceRepository for MockRepository {
+    async fn insert(&self, issuance: &TokenIssuance) -> Result<(), AppError> {
+        self.inserted.lock().unwrap().push(issuance.clone());
+        Ok(())
+    }
+
+    async fn search(&self, filter: &IssuanceFilter) -> Result<Vec<IssuanceRecord>, AppError> {
+        *self.searched_with.lock().unwrap() = Some(filter.clone());
+        Ok(self.records.clone())
+    }
+}
+
+fn token_pair() -> TokenPair {
+    TokenPair {
+        access_token: String::from("access"),
+        refresh_token: RefreshToken {
+            value: String::from("refresh"),
+            trusted: false,
+        },
+        jti: String::from("jti-1"),
+        kid: String::from("kid-1"),
+        expires_at: 1_700_000_000,
+    }
+}
+
+fn record(grant_type: &str) -> IssuanceRecord {
+    IssuanceRecord {
+        user_id: Uuid::new_v4(),
+        jti: String::from("jti-1"),
+        kid: String::from("kid-1"),
+        grant_type: String::from(grant_type),
+        client_app: Some(String::from("ios-2.3.1")),
+        ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+        issued_at: Utc::now(),
+        expires_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_issuance_takes_ids_from_pair_and_origin_from_context() {
+    let user_id = Uuid::new_v4();
+    let ctx = AuditContext::new(Some(IpAddr::V4(Ipv4Addr::LOCALHOST)), None)
+        .with_client_app(Some(String::from("web")));
+
+    let issuance = TokenIssuance::new(user_id, &token_pair(), GrantType::Refresh, &ctx);
+
+    assert_eq!(issuance.user_id, user_id);
+    assert_eq!(issuance.jti, "jti-1");
+    assert_eq!(issuance.kid, "kid-1");
+    assert_eq!(issuance.grant_type.as_str(), "refresh");
+    assert_eq!(issuance.client_app.as_deref(), Some("web"));
+    assert_eq!(issuance.ip, ctx.ip);
+    assert_eq!(issuance.expires_at.timestamp(), 1_700_000_000);
+}
+
+#[tokio::test]
+async fn test_search_maps_records() {
+    let repo = Arc::new(MockRepository {
+        records: vec![record("login"), record("session_update")],
+        ..Default::default()
+    });
+    let service = IssuanceService::new(Arc::clone(&repo));
+
+    let response = service
+        .search(IssuanceLogQuery {
+            jti: Some(String::from("jti-1")),
+            limit: Some(10),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(response.issuances.len(), 2);
+    assert_eq!(response.issuances[1].grant_type, "session_update");
+    assert_eq!(response.issuances[0].ip.as_deref(), Some("127.0.0.1"));
+
+    let filter = repo.searched_with.lock().unwrap().clone().unwrap();
+    assert_eq!(filter.jti.as_deref(), Some("jti-1"));
+    assert_eq!(filter.limit, 10);
+}
+
+#[tokio::test]
+async fn test_record_persists_issuance() {
+    let repo = Arc::new(MockRepository::default());
+    let service = IssuanceService::new(Arc::clone(&repo));
+    let issuance = TokenIssuance::new(
+        Uuid::new_v4(),
+        &token_pair(),
+        GrantType::Login,
+        &AuditContext::default(),
+    );
+
+    service.record(issuance.clone());
+
+    // Persisting is spawned so the token response never waits on it.
+    for _ in 0..50 {
+        if !repo.inserted.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(*repo.inserted.lock().unwrap(), vec![issuance]);
+}
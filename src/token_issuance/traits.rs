@@ -0,0 +1,21 @@
+use std::future::Future;
+
+use crate::{
+    app::AppError,
+    token_issuance::model::{IssuanceFilter, IssuanceRecord, TokenIssuance},
+};
+
+pub trait IssuanceRepository: Send + Sync {
+    fn insert(&self, issuance: &TokenIssuance)
+    -> impl Future<Output = Result<(), AppError>> + Send;
+    fn search(
+        &self,
+        filter: &IssuanceFilter,
+    ) -> impl Future<Output = Result<Vec<IssuanceRecord>, AppError>> + Send;
+}
+
+/// Entry point used by the token flows. Like auditing, recording never
+/// blocks or fails the request that issued the tokens.
+pub trait IssuanceRecorder: Send + Sync {
+    fn record(&self, issuance: TokenIssuance);
+}
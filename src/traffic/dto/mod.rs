@@ -0,0 +1,5 @@
+pub(crate) mod request;
+pub(crate) mod response;
+
+pub(crate) use request::TrafficReportQuery;
+pub(crate) use response::{IpTrafficSummary, TrafficReportResponse};
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    app::AppError, impl_validated_query_request, traffic::model::RETENTION_MINUTES,
+    utils::Validatable,
+};
+
+pub const DEFAULT_REPORT_MINUTES: u32 = 15;
+pub const DEFAULT_REPORT_LIMIT: u32 = 10;
+pub const MAX_REPORT_LIMIT: u32 = 100;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrafficReportQuery {
+    /// Size of the sliding window in minutes
+    #[param(example = 15, minimum = 1, maximum = 60)]
+    pub minutes: Option<u32>,
+    /// Number of IPs to return
+    #[param(example = 10, minimum = 1, maximum = 100)]
+    pub limit: Option<u32>,
+}
+
+impl TrafficReportQuery {
+    pub fn minutes(&self) -> u32 {
+        self.minutes.unwrap_or(DEFAULT_REPORT_MINUTES)
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_REPORT_LIMIT)
+    }
+}
+
+impl Validatable for TrafficReportQuery {
+    fn validate(&self) -> Result<(), AppError> {
+        if !(1..=RETENTION_MINUTES).contains(&self.minutes()) {
+            return Err(AppError::BadRequest(format!(
+                "minutes must be between 1 and {}",
+                RETENTION_MINUTES
+            )));
+        }
+
+        if !(1..=MAX_REPORT_LIMIT).contains(&self.limit()) {
+            return Err(AppError::BadRequest(format!(
+                "limit must be between 1 and {}",
+                MAX_REPORT_LIMIT
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_query_request!(TrafficReportQuery);
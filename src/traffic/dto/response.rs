@@ -0,0 +1,32 @@
+use axum::{Json, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrafficReportResponse {
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub generated_at: String,
+    #[schema(example = 15)]
+    pub window_minutes: u32,
+    pub ips: Vec<IpTrafficSummary>,
+}
+
+impl IntoResponse for TrafficReportResponse {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+pub struct IpTrafficSummary {
+    #[schema(example = "203.0.113.7")]
+    pub ip: String,
+    #[schema(example = 120)]
+    pub requests: u64,
+    #[schema(example = 30)]
+    pub errors: u64,
+    #[schema(example = 0.25)]
+    pub error_rate: f64,
+    #[schema(example = 12)]
+    pub rate_limited: u64,
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    auth::permissions::TrafficRead,
+    traffic::dto::{TrafficReportQuery, TrafficReportResponse},
+};
+
+/// Top IPs by request volume
+///
+/// Summarizes the busiest client IPs over the last N minutes, including their
+/// error rate and how often they hit the rate limiter. Requires `traffic:read`.
+#[utoipa::path(
+    get,
+    path = "/admin/traffic/top-ips",
+    tag = "Admin",
+    params(TrafficReportQuery),
+    responses(
+        (status = 200, description = "Traffic report generated", body = TrafficReportResponse),
+        (status = 400, description = "Invalid query parameters", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 503, description = "Redis unavailable", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn top_ips(
+    _admin: RequirePermission<TrafficRead>,
+    State(state): State<Arc<AppState>>,
+    query: TrafficReportQuery,
+) -> Result<TrafficReportResponse, AppError> {
+    state.traffic_service.top_ips(query).await
+}
@@ -0,0 +1,30 @@
+use axum::http::StatusCode;
+
+/// How long per-minute buckets are kept, and therefore the widest report window.
+pub const RETENTION_MINUTES: u32 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    Success,
+    Error,
+    RateLimited,
+}
+
+impl RequestOutcome {
+    pub fn from_status(status: StatusCode) -> Self {
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            RequestOutcome::RateLimited
+        } else if status.is_client_error() || status.is_server_error() {
+            RequestOutcome::Error
+        } else {
+            RequestOutcome::Success
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IpCounters {
+    pub requests: u64,
+    pub errors: u64,
+    pub rate_limited: u64,
+}
@@ -0,0 +1,9 @@
+pub mod buckets {
+    pub const REQUESTS: &str = "requests";
+    pub const ERRORS: &str = "errors";
+    pub const RATE_LIMITED: &str = "rate_limited";
+
+    pub fn key(metric: &str, minute: i64) -> String {
+        format!("traffic:{}:{}", metric, minute)
+    }
+}
@@ -0,0 +1,109 @@
+use std::{collections::HashMap, net::IpAddr, sync::Arc};
+
+use redis::aio::ConnectionManager;
+
+use crate::{
+    app::AppError,
+    config::CircuitBreaker,
+    redis_pipeline,
+    traffic::{
+        model::{IpCounters, RETENTION_MINUTES, RequestOutcome},
+        queries,
+        traits::TrafficRepository,
+    },
+    utils::BaseRedisRepository,
+};
+
+const BUCKET_TTL_SECS: i64 = (RETENTION_MINUTES as i64 + 1) * 60;
+
+pub struct Repository {
+    base: BaseRedisRepository,
+}
+
+impl Repository {
+    pub fn new(conn_manager: ConnectionManager, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRedisRepository::new(conn_manager, circuit_breaker),
+        }
+    }
+}
+
+impl TrafficRepository for Repository {
+    async fn record(
+        &self,
+        ip: IpAddr,
+        outcome: RequestOutcome,
+        minute: i64,
+    ) -> Result<(), AppError> {
+        let member = ip.to_string();
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let mut pipe = redis::pipe();
+                pipe.atomic();
+
+                let mut metrics = vec![queries::buckets::REQUESTS];
+                match outcome {
+                    RequestOutcome::Success => {}
+                    RequestOutcome::Error => metrics.push(queries::buckets::ERRORS),
+                    RequestOutcome::RateLimited => {
+                        metrics.push(queries::buckets::ERRORS);
+                        metrics.push(queries::buckets::RATE_LIMITED);
+                    }
+                }
+
+                for metric in metrics {
+                    let key = queries::buckets::key(metric, minute);
+                    pipe.zincr(&key, &member, 1)
+                        .ignore()
+                        .expire(&key, BUCKET_TTL_SECS)
+                        .ignore();
+                }
+
+                let _: () = redis_pipeline!({ pipe.query_async(&mut conn).await })?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn counters_between(
+        &self,
+        from_minute: i64,
+        to_minute: i64,
+    ) -> Result<HashMap<String, IpCounters>, AppError> {
+        const METRICS: [&str; 3] = [
+            queries::buckets::REQUESTS,
+            queries::buckets::ERRORS,
+            queries::buckets::RATE_LIMITED,
+        ];
+
+        self.base
+            .execute_with_circuit_breaker(move |mut conn| async move {
+                let mut pipe = redis::pipe();
+                for minute in from_minute..=to_minute {
+                    for metric in METRICS {
+                        pipe.zrange_withscores(queries::buckets::key(metric, minute), 0, -1);
+                    }
+                }
+
+                let buckets: Vec<Vec<(String, f64)>> =
+                    redis_pipeline!({ pipe.query_async(&mut conn).await })?;
+
+                let mut counters: HashMap<String, IpCounters> = HashMap::new();
+                for (index, bucket) in buckets.into_iter().enumerate() {
+                    for (ip, score) in bucket {
+                        let entry = counters.entry(ip).or_default();
+                        let value = score as u64;
+                        match METRICS[index % METRICS.len()] {
+                            queries::buckets::REQUESTS => entry.requests += value,
+                            queries::buckets::ERRORS => entry.errors += value,
+                            _ => entry.rate_limited += value,
+                        }
+                    }
+                }
+
+                Ok(counters)
+            })
+            .await
+    }
+}
@@ -0,0 +1,97 @@
+use std::{net::IpAddr, sync::Arc};
+
+use chrono::Utc;
+use tracing::Instrument;
+
+use crate::{
+    app::{AppError, middleware::metrics},
+    traffic::{
+        dto::{IpTrafficSummary, TrafficReportQuery, TrafficReportResponse},
+        model::RequestOutcome,
+        traits::TrafficRepository,
+    },
+    utils::MemoryPressure,
+};
+
+pub struct TrafficService<R>
+where
+    R: TrafficRepository + 'static,
+{
+    traffic_repo: Arc<R>,
+    memory_pressure: Arc<MemoryPressure>,
+}
+
+impl<R> TrafficService<R>
+where
+    R: TrafficRepository + 'static,
+{
+    pub fn new(traffic_repo: Arc<R>, memory_pressure: Arc<MemoryPressure>) -> Self {
+        Self {
+            traffic_repo,
+            memory_pressure,
+        }
+    }
+
+    /// Accounting is best effort and must never delay the response. It is
+    /// the first thing dropped when Redis runs short of memory.
+    pub fn record(&self, ip: IpAddr, outcome: RequestOutcome) {
+        if self.memory_pressure.is_high() {
+            metrics::track_redis_shed_write("traffic");
+            return;
+        }
+
+        let traffic_repo = Arc::clone(&self.traffic_repo);
+        let minute = current_minute();
+
+        tokio::spawn(
+            async move {
+                if let Err(e) = traffic_repo.record(ip, outcome, minute).await {
+                    tracing::debug!("Failed to record traffic for {}: {}", ip, e);
+                }
+            }
+            .in_current_span(),
+        );
+    }
+
+    pub async fn top_ips(
+        &self,
+        query: TrafficReportQuery,
+    ) -> Result<TrafficReportResponse, AppError> {
+        let window_minutes = query.minutes();
+        let to_minute = current_minute();
+        let from_minute = to_minute - i64::from(window_minutes) + 1;
+
+        let counters = self
+            .traffic_repo
+            .counters_between(from_minute, to_minute)
+            .await?;
+
+        let mut ips: Vec<IpTrafficSummary> = counters
+            .into_iter()
+            .map(|(ip, c)| IpTrafficSummary {
+                ip,
+                requests: c.requests,
+                errors: c.errors,
+                error_rate: if c.requests == 0 {
+                    0.0
+                } else {
+                    c.errors as f64 / c.requests as f64
+                },
+                rate_limited: c.rate_limited,
+            })
+            .collect();
+
+        ips.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.ip.cmp(&b.ip)));
+        ips.truncate(query.limit() as usize);
+
+        Ok(TrafficReportResponse {
+            generated_at: Utc::now().to_rfc3339(),
+            window_minutes,
+            ips,
+        })
+    }
+}
+
+fn current_minute() -> i64 {
+    Utc::now().timestamp() / 60
+}
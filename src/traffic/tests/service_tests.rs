@@ -0,0 +1,146 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::{
+    app::AppError,
+    traffic::{
+        dto::TrafficReportQuery,
+        model::{IpCounters, RequestOutcome},
+        service::TrafficService,
+        traits::TrafficRepository,
+    },
+    utils::MemoryPressure,
+};
+
+#[derive(Default)]
+struct MockRepository {
+    counters: HashMap<String, IpCounters>,
+    recorded: AtomicUsize,
+}
+
+impl TrafficRepository for MockRepository {
+    async fn record(&self, _: IpAddr, _: RequestOutcome, _: i64) -> Result<(), AppError> {
+        self.recorded.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn counters_between(
+        &self,
+        _: i64,
+        _: i64,
+    ) -> Result<HashMap<String, IpCounters>, AppError> {
+        Ok(self.counters.clone())
+    }
+}
+
+fn service(entries: &[(&str, u64, u64, u64)]) -> TrafficService<MockRepository> {
+    let counters = entries
+        .iter()
+        .map(|(ip, requests, errors, rate_limited)| {
+            (
+                ip.to_string(),
+                IpCounters {
+                    requests: *requests,
+                    errors: *errors,
+                    rate_limited: *rate_limited,
+                },
+            )
+        })
+        .collect();
+
+    TrafficService::new(
+        Arc::new(MockRepository {
+            counters,
+            ..Default::default()
+        }),
+        Arc::new(MemoryPressure::default()),
+    )
+}
+
+#[tokio::test]
+async fn test_top_ips_sorted_by_volume() {
+    let service = service(&[("10.0.0.1", 5, 0, 0), ("10.0.0.2", 50, 10, 2)]);
+
+    let report = service
+        .top_ips(TrafficReportQuery::default())
+        .await
+        .unwrap();
+
+    assert_eq!(report.ips[0].ip, "10.0.0.2");
+    assert_eq!(report.ips[1].ip, "10.0.0.1");
+}
+
+#[tokio::test]
+async fn test_top_ips_error_rate() {
+    let service = service(&[("10.0.0.1", 40, 10, 4)]);
+
+    let report = service
+        .top_ips(TrafficReportQuery::default())
+        .await
+        .unwrap();
+
+    assert_eq!(report.ips[0].error_rate, 0.25);
+    assert_eq!(report.ips[0].rate_limited, 4);
+}
+
+#[tokio::test]
+async fn test_top_ips_respects_limit() {
+    let service = service(&[
+        ("10.0.0.1", 1, 0, 0),
+        ("10.0.0.2", 2, 0, 0),
+        ("10.0.0.3", 3, 0, 0),
+    ]);
+    let query = TrafficReportQuery {
+        minutes: Some(5),
+        limit: Some(2),
+    };
+
+    let report = service.top_ips(query).await.unwrap();
+
+    assert_eq!(report.window_minutes, 5);
+    assert_eq!(report.ips.len(), 2);
+    assert_eq!(report.ips[0].ip, "10.0.0.3");
+}
+
+#[tokio::test]
+async fn test_top_ips_empty_window() {
+    let service = service(&[]);
+
+    let report = service
+        .top_ips(TrafficReportQuery::default())
+        .await
+        .unwrap();
+
+    assert!(report.ips.is_empty());
+    assert_eq!(report.window_minutes, 15);
+}
+
+#[tokio::test]
+async fn test_record_is_shed_under_memory_pressure() {
+    let repo = Arc::new(MockRepository::default());
+    let pressure = Arc::new(MemoryPressure::default());
+    let service = TrafficService::new(Arc::clone(&repo), Arc::clone(&pressure));
+    let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    pressure.set(true);
+    service.record(ip, RequestOutcome::Success);
+    pressure.set(false);
+    service.record(ip, RequestOutcome::Success);
+
+    for _ in 0..50 {
+        if repo.recorded.load(Ordering::Relaxed) > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    assert_eq!(repo.recorded.load(Ordering::Relaxed), 1);
+}
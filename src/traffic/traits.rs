@@ -0,0 +1,20 @@
+use std::{collections::HashMap, future::Future, net::IpAddr};
+
+use crate::{
+    app::AppError,
+    traffic::model::{IpCounters, RequestOutcome},
+};
+
+pub trait TrafficRepository: Send + Sync {
+    fn record(
+        &self,
+        ip: IpAddr,
+        outcome: RequestOutcome,
+        minute: i64,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+    fn counters_between(
+        &self,
+        from_minute: i64,
+        to_minute: i64,
+    ) -> impl Future<Output = Result<HashMap<String, IpCounters>, AppError>> + Send;
+}
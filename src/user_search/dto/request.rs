@@ -0,0 +1,77 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    app::AppError,
+    impl_validated_query_request,
+    user_search::model::UserSearch,
+    utils::{Validatable, normalize_username, validation::username_policy},
+};
+
+pub const DEFAULT_SEARCH_LIMIT: u32 = 20;
+pub const MAX_SEARCH_LIMIT: u32 = 100;
+/// Deep pages get slower with every row skipped; past this, narrow the query.
+pub const MAX_SEARCH_OFFSET: u32 = 10_000;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserSearchQuery {
+    /// Part of a username, matched case-insensitively after normalization
+    #[param(example = "john")]
+    pub q: String,
+    /// Number of users to return
+    #[param(example = 20, minimum = 1, maximum = 100)]
+    pub limit: Option<u32>,
+    /// Matches to skip; pass `next_offset` from the previous page
+    #[param(example = 0, minimum = 0, maximum = 10000)]
+    pub offset: Option<u32>,
+}
+
+impl UserSearchQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_SEARCH_LIMIT)
+    }
+
+    pub fn offset(&self) -> u32 {
+        self.offset.unwrap_or(0)
+    }
+
+    pub fn to_search(&self) -> UserSearch {
+        UserSearch::new(&self.q, i64::from(self.limit()), i64::from(self.offset()))
+    }
+}
+
+impl Validatable for UserSearchQuery {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.q.trim().is_empty() {
+            return Err(AppError::BadRequest(String::from("q cannot be empty")));
+        }
+
+        // Nothing longer than a username can match part of one.
+        let max_chars = username_policy().max_chars;
+        if normalize_username(self.q.trim()).chars().count() > max_chars {
+            return Err(AppError::BadRequest(format!(
+                "q must be at most {} characters",
+                max_chars
+            )));
+        }
+
+        if !(1..=MAX_SEARCH_LIMIT).contains(&self.limit()) {
+            return Err(AppError::BadRequest(format!(
+                "limit must be between 1 and {}",
+                MAX_SEARCH_LIMIT
+            )));
+        }
+
+        if self.offset() > MAX_SEARCH_OFFSET {
+            return Err(AppError::BadRequest(format!(
+                "offset must be at most {}",
+                MAX_SEARCH_OFFSET
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl_validated_query_request!(UserSearchQuery);
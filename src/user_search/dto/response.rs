@@ -0,0 +1,70 @@
+use axum::{
+    Json,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::user_search::model::{UserMatch, UserSearch};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserSearchResponse {
+    /// The query as matched, normalized like usernames are.
+    #[schema(example = "john")]
+    pub query: String,
+    /// Every matching user, including those on other pages.
+    #[schema(example = 42)]
+    pub total: i64,
+    /// Exact match first, then usernames starting with the query, then the
+    /// closest of the rest.
+    pub users: Vec<UserSearchEntry>,
+    /// Offset of the next page; absent on the last one.
+    #[schema(example = 20)]
+    pub next_offset: Option<i64>,
+}
+
+impl UserSearchResponse {
+    pub fn new(search: UserSearch, total: i64, users: Vec<UserMatch>) -> Self {
+        let end = search.offset + users.len() as i64;
+
+        Self {
+            query: search.normalized,
+            total,
+            next_offset: (!users.is_empty() && end < total).then_some(end),
+            users: users.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl IntoResponse for UserSearchResponse {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserSearchEntry {
+    pub id: Uuid,
+    #[schema(example = "john_doe")]
+    pub username: String,
+    #[schema(example = "active")]
+    pub status: String,
+    #[schema(example = "2024-01-01T12:00:00Z")]
+    pub created_at: String,
+    /// Passkeys registered to the account.
+    #[schema(example = 2)]
+    pub credentials: i64,
+}
+
+impl From<UserMatch> for UserSearchEntry {
+    fn from(user: UserMatch) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            status: user.status,
+            created_at: user.created_at.to_rfc3339(),
+            credentials: user.credentials,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+
+use crate::{
+    app::{AppError, AppState, middleware::auth::RequirePermission},
+    auth::permissions::AdminActions,
+    user_search::dto::{UserSearchQuery, UserSearchResponse},
+};
+
+/// Search users by username
+///
+/// Matches active users whose normalized username contains the query or is
+/// close to it, best matches first, a page at a time. Requires
+/// `admin:actions`.
+#[utoipa::path(
+    get,
+    path = "/admin/users/search",
+    tag = "Admin",
+    params(UserSearchQuery),
+    responses(
+        (status = 200, description = "Matching users", body = UserSearchResponse),
+        (status = 400, description = "Invalid query parameters", body = crate::app::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid access token", body = crate::app::error::ErrorResponse),
+        (status = 403, description = "Missing permission", body = crate::app::error::ErrorResponse),
+        (status = 500, description = "Internal server error", body = crate::app::error::ErrorResponse)
+    )
+)]
+pub async fn search(
+    _admin: RequirePermission<AdminActions>,
+    State(state): State<Arc<AppState>>,
+    query: UserSearchQuery,
+) -> Result<UserSearchResponse, AppError> {
+    state.user_search_service.search(&query).await
+}
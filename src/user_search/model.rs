@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    utils::{FromRow, escape_like, normalize_username},
+};
+
+/// A search as the repository runs it. The patterns are escaped, so `_` or
+/// `%` in the query match themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserSearch {
+    pub normalized: String,
+    pub prefix_pattern: String,
+    pub contains_pattern: String,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl UserSearch {
+    pub fn new(query: &str, limit: i64, offset: i64) -> Self {
+        let normalized = normalize_username(query.trim());
+        let escaped = escape_like(&normalized);
+
+        Self {
+            prefix_pattern: format!("{}%", escaped),
+            contains_pattern: format!("%{}%", escaped),
+            normalized,
+            limit,
+            offset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserMatch {
+    pub id: Uuid,
+    pub username: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub credentials: i64,
+}
+
+impl FromRow for UserMatch {
+    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError> {
+        Ok(UserMatch {
+            id: row.try_get("id")?,
+            username: row.try_get("username")?,
+            status: row.try_get("status")?,
+            created_at: row.try_get("created_at")?,
+            credentials: row.try_get("credentials")?,
+        })
+    }
+}
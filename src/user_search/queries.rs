@@ -0,0 +1,23 @@
+/// `$1` is the normalized query, `$2` the escaped query followed by `%` and
+/// `$3` the escaped query between `%`. Exact matches rank first, then
+/// prefixes, then the rest by trigram similarity; the trigram index serves
+/// both the `ILIKE` and the `%` condition.
+pub mod users {
+    pub const SEARCH: &str = "SELECT u.id, u.username, u.status, u.created_at,
+                (SELECT COUNT(*) FROM credentials c WHERE c.user_id = u.id) AS credentials
+         FROM users u
+         WHERE u.is_active
+           AND (u.normalized_username ILIKE $3 OR u.normalized_username % $1)
+           AND u.tenant_id = $6
+         ORDER BY u.normalized_username = $1 DESC,
+                  u.normalized_username ILIKE $2 DESC,
+                  similarity(u.normalized_username, $1) DESC,
+                  u.normalized_username
+         LIMIT $4 OFFSET $5";
+
+    pub const COUNT: &str = "SELECT COUNT(*) AS total
+         FROM users u
+         WHERE u.is_active
+           AND (u.normalized_username ILIKE $2 OR u.normalized_username % $1)
+           AND u.tenant_id = $3";
+}
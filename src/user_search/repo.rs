@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    app::{AppError, context::current_tenant},
+    config::CircuitBreaker,
+    db_select,
+    user_search::{
+        model::{UserMatch, UserSearch},
+        queries,
+        traits::UserSearchRepository,
+    },
+    utils::{BaseRepository, FromRow},
+};
+
+pub struct Repository {
+    base: BaseRepository,
+}
+
+impl Repository {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+        }
+    }
+}
+
+impl UserSearchRepository for Repository {
+    async fn search_users(&self, search: &UserSearch) -> Result<Vec<UserMatch>, AppError> {
+        let tenant = current_tenant();
+
+        let rows = db_select!("users", {
+            self.base
+                .execute_prepared(
+                    queries::users::SEARCH,
+                    &[
+                        &search.normalized as &(dyn ToSql + Sync),
+                        &search.prefix_pattern,
+                        &search.contains_pattern,
+                        &search.limit,
+                        &search.offset,
+                        &tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        rows.iter().map(UserMatch::from_row).collect()
+    }
+
+    async fn count_users(&self, search: &UserSearch) -> Result<i64, AppError> {
+        let tenant = current_tenant();
+
+        let row = db_select!("users", {
+            self.base
+                .execute_prepared_one(
+                    queries::users::COUNT,
+                    &[
+                        &search.normalized as &(dyn ToSql + Sync),
+                        &search.contains_pattern,
+                        &tenant,
+                    ],
+                )
+                .await
+        })?;
+
+        Ok(row.try_get("total")?)
+    }
+}
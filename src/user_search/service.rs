@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use crate::{
+    app::AppError,
+    user_search::{
+        dto::{UserSearchQuery, UserSearchResponse},
+        traits::UserSearchRepository,
+    },
+};
+
+pub struct UserSearchService<R>
+where
+    R: UserSearchRepository + 'static,
+{
+    search_repo: Arc<R>,
+}
+
+impl<R> UserSearchService<R>
+where
+    R: UserSearchRepository + 'static,
+{
+    pub fn new(search_repo: Arc<R>) -> Self {
+        Self { search_repo }
+    }
+
+    /// Active users of the current tenant whose username matches the query.
+    pub async fn search(&self, query: &UserSearchQuery) -> Result<UserSearchResponse, AppError> {
+        let search = query.to_search();
+        let total = self.search_repo.count_users(&search).await?;
+        let users = if search.offset < total {
+            self.search_repo.search_users(&search).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(UserSearchResponse::new(search, total, users))
+    }
+}
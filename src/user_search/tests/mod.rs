@@ -0,0 +1,4 @@
+#[cfg(test)]
+mod query_tests;
+#[cfg(test)]
+mod service_tests;
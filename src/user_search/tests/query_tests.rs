@@ -0,0 +1,56 @@
+use crate::{
+    app::AppError,
+    user_search::{dto::UserSearchQuery, model::UserSearch},
+    utils::Validatable,
+};
+
+fn query(q: &str) -> UserSearchQuery {
+    UserSearchQuery {
+        q: q.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_default_page() {
+    let query = query("john");
+
+    assert!(query.validate().is_ok());
+    assert_eq!(query.to_search(), UserSearch::new("john", 20, 0));
+}
+
+#[test]
+fn test_query_is_normalized_and_escaped() {
+    let search = query("  Ｊohn_50% ").to_search();
+
+    assert_eq!(search.normalized, "john_50%");
+    assert_eq!(search.prefix_pattern, "john\\_50\\%%");
+    assert_eq!(search.contains_pattern, "%john\\_50\\%%");
+}
+
+#[test]
+fn test_blank_query_rejected() {
+    for q in ["", "   "] {
+        assert!(matches!(query(q).validate(), Err(AppError::BadRequest(_))));
+    }
+}
+
+#[test]
+fn test_query_longer_than_a_username_rejected() {
+    assert!(matches!(
+        query(&"a".repeat(1000)).validate(),
+        Err(AppError::BadRequest(_))
+    ));
+}
+
+#[test]
+fn test_page_out_of_range() {
+    for (limit, offset) in [(Some(0), None), (Some(101), None), (None, Some(10_001))] {
+        let query = UserSearchQuery {
+            limit,
+            offset,
+            ..query("john")
+        };
+        assert!(matches!(query.validate(), Err(AppError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    app::AppError,
+    user_search::{
+        dto::UserSearchQuery,
+        model::{UserMatch, UserSearch},
+        service::UserSearchService,
+        traits::UserSearchRepository,
+    },
+};
+
+#[derive(Default)]
+struct MockRepository {
+    users: Vec<UserMatch>,
+    total: i64,
+    searches: Mutex<Vec<UserSearch>>,
+}
+
+impl UserSearchRepository for MockRepository {
+    async fn search_users(&self, search: &UserSearch) -> Result<Vec<UserMatch>, AppError> {
+        self.searches.lock().unwrap().push(search.clone());
+        Ok(self.users.clone())
+    }
+
+    async fn count_users(&self, _: &UserSearch) -> Result<i64, AppError> {
+        Ok(self.total)
+    }
+}
+
+fn user(username: &str) -> UserMatch {
+    UserMatch {
+        id: Uuid::new_v4(),
+        username: username.to_string(),
+        status: String::from("active"),
+        created_at: Utc::now(),
+        credentials: 1,
+    }
+}
+
+fn query(limit: u32, offset: u32) -> UserSearchQuery {
+    UserSearchQuery {
+        q: String::from("John"),
+        limit: Some(limit),
+        offset: Some(offset),
+    }
+}
+
+#[tokio::test]
+async fn test_search_points_to_next_page() {
+    let repo = Arc::new(MockRepository {
+        users: vec![user("john"), user("johnny")],
+        total: 5,
+        ..Default::default()
+    });
+    let service = UserSearchService::new(Arc::clone(&repo));
+
+    let response = service.search(&query(2, 2)).await.unwrap();
+
+    assert_eq!(response.query, "john");
+    assert_eq!(response.total, 5);
+    assert_eq!(response.users.len(), 2);
+    assert_eq!(response.users[0].username, "john");
+    assert_eq!(response.next_offset, Some(4));
+    assert_eq!(repo.searches.lock().unwrap()[0].offset, 2);
+}
+
+#[tokio::test]
+async fn test_last_page_has_no_next_offset() {
+    let repo = Arc::new(MockRepository {
+        users: vec![user("john")],
+        total: 3,
+        ..Default::default()
+    });
+    let service = UserSearchService::new(Arc::clone(&repo));
+
+    let response = service.search(&query(2, 2)).await.unwrap();
+
+    assert_eq!(response.next_offset, None);
+}
+
+#[tokio::test]
+async fn test_offset_past_the_matches_skips_the_search() {
+    let repo = Arc::new(MockRepository {
+        users: vec![user("john")],
+        total: 3,
+        ..Default::default()
+    });
+    let service = UserSearchService::new(Arc::clone(&repo));
+
+    let response = service.search(&query(20, 40)).await.unwrap();
+
+    assert_eq!(response.total, 3);
+    assert!(response.users.is_empty());
+    assert_eq!(response.next_offset, None);
+    assert!(repo.searches.lock().unwrap().is_empty());
+}
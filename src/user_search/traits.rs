@@ -0,0 +1,19 @@
+use std::future::Future;
+
+use crate::{
+    app::AppError,
+    user_search::model::{UserMatch, UserSearch},
+};
+
+pub trait UserSearchRepository: Send + Sync {
+    /// Best matches first, the page given by `search.limit` and `search.offset`.
+    fn search_users(
+        &self,
+        search: &UserSearch,
+    ) -> impl Future<Output = Result<Vec<UserMatch>, AppError>> + Send;
+    /// Counts every match, regardless of the page.
+    fn count_users(
+        &self,
+        search: &UserSearch,
+    ) -> impl Future<Output = Result<i64, AppError>> + Send;
+}
@@ -0,0 +1,40 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::ConnectInfo,
+    http::{Extensions, HeaderMap, Request, request::Parts},
+};
+
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Resolves the caller address. `X-Forwarded-For` is only honoured when the
+/// server sits behind a trusted proxy, otherwise any client could spoof it.
+pub fn client_ip<B>(request: &Request<B>, trust_proxy: bool) -> Option<IpAddr> {
+    resolve(request.headers(), request.extensions(), trust_proxy)
+}
+
+pub fn client_ip_from_parts(parts: &Parts, trust_proxy: bool) -> Option<IpAddr> {
+    resolve(&parts.headers, &parts.extensions, trust_proxy)
+}
+
+fn resolve(headers: &HeaderMap, extensions: &Extensions, trust_proxy: bool) -> Option<IpAddr> {
+    if trust_proxy && let Some(ip) = forwarded_for(headers) {
+        return Some(ip);
+    }
+
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+fn forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get(FORWARDED_FOR_HEADER)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
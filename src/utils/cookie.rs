@@ -1,41 +1,77 @@
 use axum_extra::extract::cookie::{Cookie, SameSite};
 use time::Duration;
 
-use crate::{app::AppError, config::origin::OriginConfig};
+use crate::{
+    app::{AppError, ErrorCode, context::current_origin},
+    config::{
+        CookieConfig,
+        origin::{OriginConfig, RelyingParty},
+    },
+};
 
-const PATH: &str = "/auth";
 const HTTP_ONLY: bool = true;
-const MAX_AGE: Duration = Duration::days(1);
 pub const REFRESH_TOKEN_COOKIE_NAME: &str = "refresh_token";
 
-#[derive(Debug, Clone)]
-pub struct CookieService {
+/// The cookie attributes that depend on which frontend a response goes to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookieSite {
     pub secure: bool,
     pub same_site: SameSite,
     pub domain: Option<String>,
+}
+
+impl CookieSite {
+    pub fn new(party: &RelyingParty) -> Self {
+        let is_https = party.url.scheme() == "https";
+        let is_local = party.rp_id.contains("localhost") || party.rp_id.contains("127.0.0.1");
+
+        Self {
+            secure: is_https,
+            same_site: CookieService::determine_same_site(is_https, is_local),
+            domain: CookieService::determine_cookie_domain(party, is_local),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CookieService {
+    /// One per frontend origin, in `OriginConfig` order.
+    sites: Vec<(Box<str>, CookieSite)>,
     pub path: String,
     pub http_only: bool,
     pub max_age: Duration,
+    pub trusted_max_age: Duration,
 }
 
 impl CookieService {
-    pub fn new(origin_config: &OriginConfig) -> Self {
-        let is_https = origin_config.frontend_url.scheme() == "https";
-        let is_local = origin_config.backend_domain.contains("localhost")
-            || origin_config.backend_domain.contains("127.0.0.1");
-
+    pub fn new(origin_config: &OriginConfig, cookie_config: &CookieConfig) -> Self {
         Self {
-            secure: is_https,
-            same_site: Self::determine_same_site(is_https, is_local),
-            domain: Self::determine_cookie_domain(origin_config, is_local),
-            path: String::from(PATH),
+            sites: origin_config
+                .relying_parties
+                .iter()
+                .map(|party| (party.origin.clone(), CookieSite::new(party)))
+                .collect(),
+            path: cookie_config.path.clone(),
             http_only: HTTP_ONLY,
-            max_age: MAX_AGE,
+            max_age: Duration::seconds(cookie_config.max_age.as_secs() as i64),
+            trusted_max_age: Duration::seconds(cookie_config.trusted_max_age.as_secs() as i64),
         }
     }
 
-    pub fn create_refresh_token_cookie(&self, token: &str) -> Cookie<'static> {
-        self.build_cookie(REFRESH_TOKEN_COOKIE_NAME, token, Some(self.max_age))
+    /// The attributes for `origin`, or the first frontend's.
+    pub fn site(&self, origin: Option<&str>) -> &CookieSite {
+        origin
+            .and_then(|origin| self.sites.iter().find(|(o, _)| **o == *origin))
+            .map_or(&self.sites[0].1, |(_, site)| site)
+    }
+
+    pub fn create_refresh_token_cookie(&self, token: &str, trusted: bool) -> Cookie<'static> {
+        let max_age = if trusted {
+            self.trusted_max_age
+        } else {
+            self.max_age
+        };
+        self.build_cookie(REFRESH_TOKEN_COOKIE_NAME, token, Some(max_age))
     }
 
     pub fn get_refresh_token_from_jar(
@@ -46,6 +82,7 @@ impl CookieService {
             .map(|cookie| cookie.value().to_owned())
             .ok_or_else(|| {
                 AppError::Unauthorized(String::from("Refresh token not found in cookies"))
+                    .with_code(ErrorCode::AuthRefreshTokenMissing)
             })
     }
 
@@ -58,17 +95,19 @@ impl CookieService {
         N: Into<String>,
         V: Into<String>,
     {
+        let origin = current_origin();
+        let site = self.site(origin.as_deref());
         let mut cookie_builder = Cookie::build((name.into(), value.into()))
             .path(self.path.clone())
             .http_only(self.http_only)
-            .secure(self.secure)
-            .same_site(self.same_site);
+            .secure(site.secure)
+            .same_site(site.same_site);
 
         if let Some(age) = max_age {
             cookie_builder = cookie_builder.max_age(age);
         }
 
-        if let Some(ref domain) = self.domain {
+        if let Some(ref domain) = site.domain {
             cookie_builder = cookie_builder.domain(domain.clone());
         }
 
@@ -85,21 +124,18 @@ impl CookieService {
         }
     }
 
-    pub(crate) fn determine_cookie_domain(
-        origin_config: &OriginConfig,
-        is_local: bool,
-    ) -> Option<String> {
+    pub(crate) fn determine_cookie_domain(party: &RelyingParty, is_local: bool) -> Option<String> {
         if is_local {
             return None;
         }
 
-        let frontend_domain = origin_config.frontend_url.host_str().unwrap();
-        let backend_domain = origin_config.rp_id();
+        let frontend_domain = party.url.host_str().unwrap();
+        let backend_domain = &party.rp_id;
 
-        if Self::are_subdomains_of_same(frontend_domain, backend_domain) {
-            if let Some(base_domain) = Self::get_base_domain(frontend_domain, backend_domain) {
-                return Some(format!(".{}", base_domain));
-            }
+        if Self::are_subdomains_of_same(frontend_domain, backend_domain)
+            && let Some(base_domain) = Self::get_base_domain(frontend_domain, backend_domain)
+        {
+            return Some(format!(".{}", base_domain));
         }
 
         None
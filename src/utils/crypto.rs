@@ -0,0 +1,211 @@
+//! Primitives whose implementation depends on the crypto backend. The default
+//! build uses RustCrypto and ring; the `aws-lc` feature routes them through
+//! aws-lc-rs, and `fips` through its FIPS 140-3 validated module.
+
+use std::sync::Arc;
+
+use rustls::crypto::CryptoProvider;
+
+#[cfg(not(feature = "aws-lc"))]
+mod backend {
+    use chacha20poly1305::{
+        XChaCha20Poly1305, XNonce,
+        aead::{Aead, Generate, KeyInit, Payload},
+    };
+    use ed25519_dalek::SigningKey;
+    use rustls::crypto::{CryptoProvider, ring};
+    use sha2::{Digest, Sha256};
+
+    pub const NAME: &str = "ring";
+    const NONCE_LEN: usize = 24;
+
+    pub struct Cipher(XChaCha20Poly1305);
+
+    impl Cipher {
+        pub fn new(key: &[u8]) -> Option<Self> {
+            XChaCha20Poly1305::new_from_slice(key).ok().map(Self)
+        }
+
+        pub fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+            let nonce = XNonce::generate();
+            let ciphertext = self
+                .0
+                .encrypt(
+                    &nonce,
+                    Payload {
+                        msg: plaintext,
+                        aad,
+                    },
+                )
+                .ok()?;
+
+            let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+            sealed.extend_from_slice(&nonce);
+            sealed.extend_from_slice(&ciphertext);
+            Some(sealed)
+        }
+
+        pub fn open(&self, sealed: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+            if sealed.len() <= NONCE_LEN {
+                return None;
+            }
+            let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+            let nonce = XNonce::try_from(nonce).ok()?;
+            self.0
+                .decrypt(
+                    &nonce,
+                    Payload {
+                        msg: ciphertext,
+                        aad,
+                    },
+                )
+                .ok()
+        }
+    }
+
+    pub fn ed25519_public_key(seed: &[u8; 32]) -> [u8; 32] {
+        SigningKey::from_bytes(seed).verifying_key().to_bytes()
+    }
+
+    pub fn sha256(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+
+    pub fn tls_provider() -> CryptoProvider {
+        ring::default_provider()
+    }
+}
+
+#[cfg(feature = "aws-lc")]
+mod backend {
+    use aws_lc_rs::{
+        aead::{AES_256_GCM, Aad, NONCE_LEN, Nonce, RandomizedNonceKey},
+        digest::{SHA256, digest},
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+    use rustls::crypto::CryptoProvider;
+
+    #[cfg(not(feature = "fips"))]
+    pub const NAME: &str = "aws-lc";
+    #[cfg(feature = "fips")]
+    pub const NAME: &str = "aws-lc-fips";
+
+    pub struct Cipher(RandomizedNonceKey);
+
+    impl Cipher {
+        pub fn new(key: &[u8]) -> Option<Self> {
+            RandomizedNonceKey::new(&AES_256_GCM, key).ok().map(Self)
+        }
+
+        pub fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+            let mut ciphertext = plaintext.to_vec();
+            let nonce = self
+                .0
+                .seal_in_place_append_tag(Aad::from(aad), &mut ciphertext)
+                .ok()?;
+
+            let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+            sealed.extend_from_slice(nonce.as_ref());
+            sealed.extend_from_slice(&ciphertext);
+            Some(sealed)
+        }
+
+        pub fn open(&self, sealed: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+            if sealed.len() <= NONCE_LEN {
+                return None;
+            }
+            let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+            let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+
+            let mut in_out = ciphertext.to_vec();
+            let plaintext_len = self
+                .0
+                .open_in_place(nonce, Aad::from(aad), &mut in_out)
+                .ok()?
+                .len();
+            in_out.truncate(plaintext_len);
+            Some(in_out)
+        }
+    }
+
+    pub fn ed25519_public_key(seed: &[u8; 32]) -> [u8; 32] {
+        let pair = Ed25519KeyPair::from_seed_unchecked(seed)
+            .expect("a 32-byte seed is a valid Ed25519 key");
+        pair.public_key()
+            .as_ref()
+            .try_into()
+            .expect("Ed25519 public keys are 32 bytes")
+    }
+
+    pub fn sha256(data: &[u8]) -> [u8; 32] {
+        digest(&SHA256, data)
+            .as_ref()
+            .try_into()
+            .expect("SHA-256 digests are 32 bytes")
+    }
+
+    /// FIPS-only when rustls is built with its `fips` feature.
+    pub fn tls_provider() -> CryptoProvider {
+        rustls::crypto::aws_lc_rs::default_provider()
+    }
+}
+
+/// Authenticated encryption, with a random nonce put in front of the
+/// ciphertext: XChaCha20-Poly1305 by default, AES-256-GCM with `aws-lc`.
+/// Output of one backend cannot be opened by the other.
+pub struct Cipher(backend::Cipher);
+
+impl Cipher {
+    /// `None` unless `key` is 32 bytes.
+    pub fn new(key: &[u8]) -> Option<Self> {
+        backend::Cipher::new(key).map(Self)
+    }
+
+    pub fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        self.0.seal(plaintext, aad)
+    }
+
+    /// `None` for anything not sealed by this key with this `aad`.
+    pub fn open(&self, sealed: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        self.0.open(sealed, aad)
+    }
+}
+
+pub use backend::{ed25519_public_key, sha256};
+
+/// `N` bytes from the operating system's CSPRNG, for secrets, tokens and
+/// keys. Panics when the OS cannot provide them, as nothing else is safe to
+/// fall back to.
+pub fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    getrandom::fill(&mut bytes).expect("The operating system RNG is unavailable");
+    bytes
+}
+
+/// The backend's name, for the startup log.
+pub fn backend_name() -> &'static str {
+    backend::NAME
+}
+
+/// The rustls provider for every TLS client of the process.
+pub fn tls_provider() -> Arc<CryptoProvider> {
+    Arc::new(backend::tls_provider())
+}
+
+/// Makes `tls_provider` the process default, which reqwest and the OTLP
+/// exporter pick up. With `fips`, refuses to start unless both AWS-LC and
+/// rustls run the validated module, which takes the `aws-lc-rs/fips` and
+/// `rustls/fips` features on top.
+pub fn install_tls_provider() {
+    let provider = backend::tls_provider();
+
+    #[cfg(feature = "fips")]
+    if aws_lc_rs::try_fips_mode().is_err() || !provider.fips() {
+        panic!(
+            "The fips feature needs the validated AWS-LC module, \
+             build with --features fips,aws-lc-rs/fips,rustls/fips"
+        );
+    }
+
+    let _ = CryptoProvider::install_default(provider);
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use reqwest::{Client, Method, Request, RequestBuilder, Response, StatusCode, Url};
+
+use crate::{
+    app::{
+        AppError,
+        middleware::metrics::{track_http_client_request, track_http_client_retry},
+    },
+    config::{CircuitBreaker, HttpClientConfig},
+    utils::http::{RetryBudget, SsrfGuard},
+};
+
+/// Delay before the first retry, doubled for each one after.
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+struct Destination {
+    circuit_breaker: CircuitBreaker,
+    retry_budget: RetryBudget,
+}
+
+/// Outbound HTTP shared by every integration. Each destination host gets
+/// its own circuit breaker and retry budget, so one failing provider never
+/// slows calls to the others.
+pub struct HttpClientService {
+    client: Client,
+    guarded_client: Client,
+    guard: Arc<SsrfGuard>,
+    config: HttpClientConfig,
+    destinations: Mutex<HashMap<String, Arc<Destination>>>,
+}
+
+// Without `notifications`, nothing calls it yet.
+#[cfg_attr(not(feature = "strict"), allow(dead_code))]
+impl HttpClientService {
+    pub fn new(config: HttpClientConfig) -> Self {
+        let (guarded_client, guard) = config.create_guarded_client();
+
+        Self {
+            client: config.create_client(),
+            guarded_client,
+            guard,
+            config,
+            destinations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Builds requests to pass to `execute` or `execute_guarded`.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Sends the request behind its destination's breaker, retrying
+    /// connection failures and, for idempotent methods, timeouts and 5xx or
+    /// 429 responses while the budget allows. A 5xx response left after
+    /// retries is returned as an error and counts against the breaker.
+    pub async fn execute(&self, request: RequestBuilder) -> Result<Response, AppError> {
+        self.send(&self.client, request.build()?).await
+    }
+
+    /// Like `execute`, for URLs that admins or users configured: the target
+    /// must not be, or resolve to, a private or otherwise internal address.
+    pub async fn execute_guarded(&self, request: RequestBuilder) -> Result<Response, AppError> {
+        let request = request.build()?;
+        self.guard.check_url(request.url())?;
+        self.send(&self.guarded_client, request).await
+    }
+
+    async fn send(&self, client: &Client, request: Request) -> Result<Response, AppError> {
+        let name = destination(request.url());
+        let destination = self.destination(&name);
+        destination.retry_budget.deposit();
+
+        destination
+            .circuit_breaker
+            .call(|| self.send_with_retries(client, &name, &destination.retry_budget, request))
+            .await
+    }
+
+    fn destination(&self, name: &str) -> Arc<Destination> {
+        let mut destinations = self.destinations.lock().unwrap();
+        let destination = destinations.entry(name.to_owned()).or_insert_with(|| {
+            Arc::new(Destination {
+                circuit_breaker: CircuitBreaker::new(
+                    &format!("http:{}", name),
+                    self.config.circuit_breaker,
+                ),
+                retry_budget: RetryBudget::new(self.config.retry_budget_percent),
+            })
+        });
+        Arc::clone(destination)
+    }
+
+    async fn send_with_retries(
+        &self,
+        client: &Client,
+        name: &str,
+        retry_budget: &RetryBudget,
+        mut request: Request,
+    ) -> Result<Response, AppError> {
+        let idempotent = is_idempotent(request.method());
+        let mut attempt = 0;
+
+        loop {
+            // Streaming bodies cannot be replayed, so those are sent once.
+            let retry = (attempt < self.config.max_retries)
+                .then(|| request.try_clone())
+                .flatten();
+
+            let start = Instant::now();
+            let result = client.execute(request).await;
+            track_http_client_request(name, outcome(&result), start.elapsed().as_secs_f64());
+
+            let retryable = match &result {
+                Ok(response) => idempotent && is_retryable_status(response.status()),
+                Err(e) => e.is_connect() || (idempotent && e.is_timeout()),
+            };
+
+            if let (true, Some(next)) = (retryable, retry) {
+                if retry_budget.try_withdraw() {
+                    track_http_client_retry(name, "attempted");
+                    tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(attempt)).await;
+                    request = next;
+                    attempt += 1;
+                    continue;
+                }
+                track_http_client_retry(name, "budget_exhausted");
+            }
+
+            return match result {
+                Ok(response) if response.status().is_server_error() => {
+                    Err(AppError::ServiceUnavailable(format!(
+                        "{} responded with {}",
+                        name,
+                        response.status()
+                    )))
+                }
+                Ok(response) => Ok(response),
+                Err(e) => Err(e.into()),
+            };
+        }
+    }
+}
+
+/// The breaker and metrics key of a URL: its host, with the port when it
+/// is not the scheme's default.
+pub fn destination(url: &Url) -> String {
+    let host = url.host_str().unwrap_or("unknown");
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_owned(),
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn outcome(result: &Result<Response, reqwest::Error>) -> &'static str {
+    match result {
+        Ok(response) => match response.status().as_u16() {
+            200..=299 => "2xx",
+            300..=399 => "3xx",
+            400..=499 => "4xx",
+            _ => "5xx",
+        },
+        Err(_) => "error",
+    }
+}
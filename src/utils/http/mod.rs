@@ -0,0 +1,11 @@
+mod client;
+mod retry;
+mod ssrf;
+
+pub(crate) use client::HttpClientService;
+#[cfg(test)]
+pub(crate) use client::destination;
+pub(crate) use retry::RetryBudget;
+#[cfg(test)]
+pub(crate) use ssrf::is_public;
+pub(crate) use ssrf::{GuardedResolver, SsrfGuard};
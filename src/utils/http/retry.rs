@@ -0,0 +1,37 @@
+use std::sync::Mutex;
+
+/// Retries a destination can always afford, whatever its recent traffic.
+const RESERVE: f64 = 10.0;
+
+/// Caps retries to a share of the requests made, so a failing destination
+/// sees at most a few percent more traffic instead of every call repeated.
+/// Each request deposits a fraction of a retry and each retry withdraws one,
+/// with the balance capped at the reserve.
+pub struct RetryBudget {
+    balance: Mutex<f64>,
+    deposit: f64,
+}
+
+impl RetryBudget {
+    pub fn new(percent: u32) -> Self {
+        Self {
+            balance: Mutex::new(RESERVE),
+            deposit: f64::from(percent) / 100.0,
+        }
+    }
+
+    pub fn deposit(&self) {
+        let mut balance = self.balance.lock().unwrap();
+        *balance = (*balance + self.deposit).min(RESERVE);
+    }
+
+    /// Whether a retry may go ahead, paying for it when it can.
+    pub fn try_withdraw(&self) -> bool {
+        let mut balance = self.balance.lock().unwrap();
+        if *balance < 1.0 {
+            return false;
+        }
+        *balance -= 1.0;
+        true
+    }
+}
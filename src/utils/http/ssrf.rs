@@ -0,0 +1,208 @@
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+};
+
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect,
+};
+use url::{Host, Url};
+
+use crate::app::AppError;
+
+/// Redirects followed before giving up, as reqwest does by default.
+const MAX_REDIRECTS: usize = 10;
+
+/// Keeps requests to URLs that come from admins or users (webhooks, OIDC
+/// issuers) away from the server's own network: loopback, private,
+/// link-local (cloud metadata) and other non-public addresses.
+///
+/// URLs are checked before sending and on every redirect. Host names are
+/// checked again when the connection resolves them, against the addresses
+/// actually dialled, so a name rebound to a private address after the
+/// first check is still refused.
+#[derive(Debug, Clone, Default)]
+pub struct SsrfGuard {
+    allowed_hosts: Vec<String>,
+}
+
+impl SsrfGuard {
+    /// `allowed_hosts` may resolve anywhere, e.g. an internal webhook receiver.
+    pub fn new(allowed_hosts: Vec<String>) -> Self {
+        Self {
+            allowed_hosts: allowed_hosts
+                .into_iter()
+                .map(|host| normalize_host(&host))
+                .collect(),
+        }
+    }
+
+    pub fn check_url(&self, url: &Url) -> Result<(), AppError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::Forbidden(format!(
+                "URL scheme not allowed: {}",
+                url.scheme()
+            )));
+        }
+
+        // The url crate already turns decimal, octal, hex and shortened
+        // IPv4 forms into a plain address, so they are checked like one.
+        match url.host() {
+            None => Err(AppError::Forbidden(String::from("URL has no host"))),
+            Some(Host::Domain(domain)) if self.is_allowed_host(domain) => Ok(()),
+            // Resolved and checked when connecting.
+            Some(Host::Domain(_)) => Ok(()),
+            Some(Host::Ipv4(ip)) => self.check_ip(&ip.to_string(), IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => self.check_ip(&format!("[{}]", ip), IpAddr::V6(ip)),
+        }
+    }
+
+    fn check_ip(&self, host: &str, ip: IpAddr) -> Result<(), AppError> {
+        if self.is_allowed_host(host) || is_public(ip) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!(
+                "Address not allowed for outbound requests: {}",
+                ip
+            )))
+        }
+    }
+
+    fn is_allowed_host(&self, host: &str) -> bool {
+        let host = normalize_host(host);
+        self.allowed_hosts.contains(&host)
+    }
+
+    /// Refuses redirects to URLs that `check_url` rejects.
+    pub fn redirect_policy(self: &Arc<Self>) -> redirect::Policy {
+        let guard = Arc::clone(self);
+        redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match guard.check_url(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e.to_string()),
+            }
+        })
+    }
+}
+
+/// Resolves host names for the guarded client, failing when any address
+/// is not public rather than dialling the others.
+pub struct GuardedResolver {
+    guard: Arc<SsrfGuard>,
+}
+
+impl GuardedResolver {
+    pub fn new(guard: Arc<SsrfGuard>) -> Self {
+        Self { guard }
+    }
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let guard = Arc::clone(&self.guard);
+        let host = name.as_str().to_owned();
+
+        Box::pin(async move {
+            let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+
+            if !guard.is_allowed_host(&host)
+                && let Some(blocked) = addrs.iter().find(|addr| !is_public(addr.ip()))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "{} resolves to a non-public address: {}",
+                        host,
+                        blocked.ip()
+                    ),
+                )
+                .into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Whether an address is globally routable, looking through the IPv6
+/// forms that embed an IPv4 address.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8, "this network"
+        || a == 0
+        // 100.64.0.0/10, carrier-grade NAT
+        || (a == 100 && (b & 0xc0) == 64)
+        // 192.0.0.0/24, protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // 198.18.0.0/15, benchmarking
+        || (a == 198 && (b & 0xfe) == 18)
+        // 240.0.0.0/4, reserved
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    if let Some(embedded) = embedded_v4(ip) {
+        return is_public_v4(embedded);
+    }
+
+    let segments = ip.segments();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // fc00::/7, unique local
+        || (segments[0] & 0xfe00) == 0xfc00
+        // fe80::/10 link-local and fec0::/10 site-local
+        || (segments[0] & 0xffc0) == 0xfe80
+        || (segments[0] & 0xffc0) == 0xfec0
+        // 2001:db8::/32, documentation
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+        // 2001::/32, Teredo, whose client address cannot be checked
+        || (segments[0] == 0x2001 && segments[1] == 0))
+}
+
+/// The IPv4 address carried by mapped, compatible, NAT64 and 6to4 addresses.
+fn embedded_v4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let segments = ip.segments();
+    let from = |high: u16, low: u16| {
+        let [a, b] = high.to_be_bytes();
+        let [c, d] = low.to_be_bytes();
+        Ipv4Addr::new(a, b, c, d)
+    };
+
+    if let Some(mapped) = ip.to_ipv4_mapped() {
+        return Some(mapped);
+    }
+    match segments {
+        // ::/96, deprecated IPv4-compatible, which covers :: and ::1 too
+        [0, 0, 0, 0, 0, 0, high, low] => Some(from(high, low)),
+        // 64:ff9b::/96, NAT64
+        [0x64, 0xff9b, 0, 0, 0, 0, high, low] => Some(from(high, low)),
+        // 2002::/16, 6to4
+        [0x2002, high, low, ..] => Some(from(high, low)),
+        _ => None,
+    }
+}
@@ -0,0 +1,261 @@
+//! In-memory stand-ins for the Jwt, audit and notification services and the
+//! login cache, and the claims of an acting administrator, shared by the unit
+//! tests of every module that depends on them.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+use webauthn_rs::prelude::Passkey;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    audit::{model::AuditEntry, traits::AuditLogger},
+    auth::{
+        dto::{HealthStatus, ServiceHealth},
+        jwt::{
+            AccessTokenClaims, JwtService, RefreshToken, RefreshTokenClaims, RotatedPair, TokenPair,
+        },
+        model::{Grants, SessionDevice, User},
+        traits::{LoginStore, LoginStoreFuture},
+    },
+    notification::{model::Notification, traits::NotificationDispatcher},
+    utils::normalize_username,
+};
+
+/// The access token claims of an administrator holding `permissions`.
+pub(crate) fn admin_claims(permissions: &[&str]) -> AccessTokenClaims {
+    AccessTokenClaims::new(
+        Uuid::new_v4(),
+        String::from("root"),
+        Grants {
+            roles: vec![String::from("admin")],
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        },
+        Duration::from_secs(60),
+    )
+}
+
+/// Records the revocations, blacklisting and secret rotation it is asked
+/// for. Client access tokens are `token-for-<name>`; access tokens never
+/// validate. Refresh tokens it issued validate until their jti is
+/// blacklisted, and rotations are kept in memory for `refresh_grace`.
+#[derive(Default)]
+pub(crate) struct MockJwt {
+    pub(crate) rotated: AtomicBool,
+    pub(crate) revoked: Mutex<Vec<Uuid>>,
+    pub(crate) blacklisted: Mutex<Vec<(String, i64)>>,
+    /// Blacklisting this jti fails as if Redis were down.
+    pub(crate) failing_jti: Option<String>,
+    /// Zero, the default, turns the grace window off.
+    pub(crate) refresh_grace: Duration,
+    pub(crate) issued: AtomicUsize,
+    pub(crate) refresh_tokens: Mutex<HashMap<String, RefreshTokenClaims>>,
+    /// What rotating out each refresh token returned, and when.
+    pub(crate) rotations: Mutex<HashMap<String, (RotatedPair, Instant)>>,
+}
+
+impl JwtService for MockJwt {
+    async fn check_redis(&self) -> ServiceHealth {
+        ServiceHealth {
+            status: HealthStatus::Healthy,
+            message: String::new(),
+            response_time_ms: None,
+        }
+    }
+
+    async fn generate_token_pair(
+        &self,
+        user_id: Uuid,
+        username: &str,
+        _: Grants,
+        device: &SessionDevice,
+    ) -> Result<TokenPair, AppError> {
+        let n = self.issued.fetch_add(1, Ordering::Relaxed);
+        let claims = RefreshTokenClaims::new(
+            user_id,
+            username.to_owned(),
+            device.clone(),
+            Duration::from_secs(3600),
+        );
+        let refresh_token = format!("refresh-{}", n);
+        let pair = TokenPair {
+            access_token: format!("access-{}", n),
+            refresh_token: RefreshToken {
+                value: refresh_token.clone(),
+                trusted: false,
+            },
+            jti: claims.jti.clone(),
+            kid: String::new(),
+            expires_at: claims.exp,
+        };
+        self.refresh_tokens
+            .lock()
+            .unwrap()
+            .insert(refresh_token, claims);
+        Ok(pair)
+    }
+
+    async fn issue_access_token(
+        &self,
+        subject: Uuid,
+        name: &str,
+        grants: Grants,
+    ) -> Result<(String, AccessTokenClaims), AppError> {
+        let claims =
+            AccessTokenClaims::new(subject, name.to_owned(), grants, Duration::from_secs(900));
+        Ok((format!("token-for-{}", name), claims))
+    }
+
+    async fn validate_refresh(&self, token: &str) -> Result<RefreshTokenClaims, AppError> {
+        let Some(claims) = self.refresh_tokens.lock().unwrap().get(token).cloned() else {
+            return Err(AppError::Unauthorized(String::new()));
+        };
+        if self.is_blacklisted(&claims.jti).await? {
+            return Err(
+                AppError::Unauthorized(String::from("Token has been revoked"))
+                    .with_code(ErrorCode::AuthTokenRevoked),
+            );
+        }
+        Ok(claims)
+    }
+
+    async fn validate_access(
+        &self,
+        _: &str,
+        _: Option<&str>,
+    ) -> Result<AccessTokenClaims, AppError> {
+        Err(AppError::Unauthorized(String::new()))
+    }
+
+    async fn introspect_access(&self, _: &str) -> Result<Option<AccessTokenClaims>, AppError> {
+        Ok(None)
+    }
+
+    async fn blacklist(&self, jti: &str, exp: i64) -> Result<(), AppError> {
+        if self.failing_jti.as_deref() == Some(jti) {
+            return Err(AppError::ServiceUnavailable(String::from("redis down")));
+        }
+        self.blacklisted.lock().unwrap().push((jti.to_owned(), exp));
+        Ok(())
+    }
+
+    async fn remember_rotation(&self, previous: &str, pair: &RotatedPair) -> Result<(), AppError> {
+        if !self.refresh_grace.is_zero() {
+            self.rotations
+                .lock()
+                .unwrap()
+                .insert(previous.to_owned(), (pair.clone(), Instant::now()));
+        }
+        Ok(())
+    }
+
+    async fn take_rotation(&self, previous: &str) -> Result<Option<RotatedPair>, AppError> {
+        let Some((pair, remembered_at)) = self.rotations.lock().unwrap().remove(previous) else {
+            return Ok(None);
+        };
+        if remembered_at.elapsed() >= self.refresh_grace {
+            return Ok(None);
+        }
+        match self.validate_refresh(&pair.refresh_token).await {
+            Ok(_) => Ok(Some(pair)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    async fn revoke_subject(&self, user_id: Uuid) -> Result<(), AppError> {
+        self.revoked.lock().unwrap().push(user_id);
+        Ok(())
+    }
+
+    async fn rotate_refresh_secret(&self) -> Result<(), AppError> {
+        self.rotated.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn rotate_signing_key(&self) -> Result<String, AppError> {
+        Ok(String::from("next-key"))
+    }
+
+    async fn is_blacklisted(&self, jti: &str) -> Result<bool, AppError> {
+        Ok(self
+            .blacklisted
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(blacklisted, _)| blacklisted == jti))
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct MockAuditLogger {
+    pub(crate) entries: Mutex<Vec<AuditEntry>>,
+}
+
+impl AuditLogger for MockAuditLogger {
+    fn record(&self, entry: AuditEntry) {
+        self.entries.lock().unwrap().push(entry);
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct MockDispatcher {
+    pub(crate) sent: Mutex<Vec<Notification>>,
+}
+
+impl NotificationDispatcher for MockDispatcher {
+    fn dispatch(&self, notification: Notification) {
+        self.sent.lock().unwrap().push(notification);
+    }
+}
+
+/// Keeps logins by normalized username, as the Redis cache does.
+#[derive(Default)]
+pub(crate) struct MockLoginStore {
+    pub(crate) entries: Mutex<HashMap<String, (User, Vec<Passkey>)>>,
+}
+
+impl MockLoginStore {
+    pub(crate) fn entry(&self, username: &str) -> Option<(User, Vec<Passkey>)> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&normalize_username(username))
+            .cloned()
+    }
+}
+
+impl LoginStore for MockLoginStore {
+    fn get<'a>(&'a self, username: &'a str) -> LoginStoreFuture<'a, Option<(User, Vec<Passkey>)>> {
+        Box::pin(async move { self.entry(username) })
+    }
+
+    fn put<'a>(
+        &'a self,
+        username: &'a str,
+        user: &'a User,
+        passkeys: &'a [Passkey],
+    ) -> LoginStoreFuture<'a> {
+        Box::pin(async move {
+            self.entries.lock().unwrap().insert(
+                normalize_username(username),
+                (user.clone(), passkeys.to_vec()),
+            );
+        })
+    }
+
+    fn invalidate<'a>(&'a self, username: &'a str) -> LoginStoreFuture<'a> {
+        Box::pin(async move {
+            self.entries
+                .lock()
+                .unwrap()
+                .remove(&normalize_username(username));
+        })
+    }
+}
@@ -1,19 +1,34 @@
+pub(crate) mod client_ip;
 pub(crate) mod cookie;
+pub(crate) mod crypto;
 pub(crate) mod health;
+#[cfg(feature = "http-client")]
+pub(crate) mod http;
+#[cfg(test)]
+pub(crate) mod mocks;
 pub(crate) mod postgres;
 pub(crate) mod redis;
 pub(crate) mod validation;
 
+pub(crate) use client_ip::{client_ip, client_ip_from_parts};
 pub(crate) use cookie::CookieService;
 pub(crate) use health::{check_database_health, check_redis_health};
+#[cfg(feature = "http-client")]
+pub(crate) use http::HttpClientService;
 #[cfg_attr(not(feature = "strict"), allow(unused_imports))]
 pub(crate) use postgres::{
-    BaseRepository, DeleteBuilder, FromRow, InsertBuilder, RepositoryMetrics, SelectBuilder,
-    UpdateBuilder,
+    BaseRepository, DeleteBuilder, FromRow, InsertBuilder, MIGRATIONS, PgListener, PgNotifier,
+    PreparedStatementCache, QueryKind, ReadReplica, RegionDatabase, RepositoryMetrics,
+    ReturningClause, SelectBuilder, UpdateBuilder, escape_like, run_migrations,
+    set_statement_cache_limits,
+};
+pub(crate) use redis::{
+    BaseRedisRepository, MemoryMonitor, MemoryPressure, RedisShard, RedisShards,
 };
-pub(crate) use redis::BaseRedisRepository;
 pub(crate) use validation::{
-    Validatable, validate_json_credentials, validate_text, validate_username,
+    Validatable, normalize_username, set_username_policy, username_skeleton, validate_device_name,
+    validate_email, validate_json_credentials, validate_new_username, validate_text,
+    validate_username,
 };
 
 #[cfg(test)]
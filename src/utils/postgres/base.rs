@@ -1,14 +1,78 @@
-use crate::{app::AppError, config::CircuitBreaker, utils::check_database_health};
+use crate::{
+    app::{
+        AppError,
+        context::current_region,
+        deadline::check_deadline,
+        middleware::metrics::{track_replica_read, update_db_region_pool_stats},
+    },
+    config::CircuitBreaker,
+    utils::check_database_health,
+};
 use deadpool_postgres::Pool;
-use std::sync::Arc;
-use tokio_postgres::types::ToSql;
+use std::{future::Future, sync::Arc};
+use tokio_postgres::{Row, types::ToSql};
 
-use super::{metrics::RepositoryMetrics, prepared_cache::PreparedStatementCache};
+use super::{
+    metrics::RepositoryMetrics,
+    prepared_cache::PreparedStatementCache,
+    query_builder::{InsertBuilder, UpdateBuilder},
+};
+
+const BACKEND: &str = "postgres";
+
+/// Whether a query only reads, and so may run on the replica.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryKind {
+    Read,
+    Write,
+}
+
+/// A read-only pool with a breaker of its own, so a failing replica sends
+/// reads back to the primary instead of opening the primary's breaker.
+#[derive(Clone)]
+pub struct ReadReplica {
+    pub db: Pool,
+    pub circuit_breaker: Arc<CircuitBreaker>,
+}
+
+struct Replica {
+    db: Pool,
+    circuit_breaker: Arc<CircuitBreaker>,
+    prepared_cache: PreparedStatementCache,
+}
+
+/// The database of a data residency region, with a breaker of its own so a
+/// failing region leaves the others alone.
+#[cfg_attr(any(feature = "sqlx", feature = "memory-store"), allow(dead_code))]
+#[derive(Clone)]
+pub struct RegionDatabase {
+    pub name: Box<str>,
+    pub db: Pool,
+    pub circuit_breaker: Arc<CircuitBreaker>,
+}
+
+struct Region {
+    name: Box<str>,
+    db: Pool,
+    circuit_breaker: Arc<CircuitBreaker>,
+    prepared_cache: PreparedStatementCache,
+}
+
+/// The database a query runs on: the home database, or the region of the
+/// current request.
+struct Target<'a> {
+    region: Option<&'a str>,
+    db: &'a Pool,
+    circuit_breaker: &'a Arc<CircuitBreaker>,
+    prepared_cache: &'a PreparedStatementCache,
+}
 
 pub struct BaseRepository {
     db: Pool,
     circuit_breaker: Arc<CircuitBreaker>,
     prepared_cache: PreparedStatementCache,
+    replica: Option<Replica>,
+    regions: Vec<Region>,
 }
 
 impl BaseRepository {
@@ -17,26 +81,131 @@ impl BaseRepository {
             db,
             circuit_breaker,
             prepared_cache: PreparedStatementCache::new(),
+            replica: None,
+            regions: Vec::new(),
         }
     }
 
+    /// Sends `QueryKind::Read` queries to `replica`. Statements are cached
+    /// apart from the primary's, since pool object ids only identify a
+    /// connection within one pool.
+    pub fn with_replica(mut self, replica: Option<ReadReplica>) -> Self {
+        self.replica = replica.map(|replica| Replica {
+            db: replica.db,
+            circuit_breaker: replica.circuit_breaker,
+            prepared_cache: PreparedStatementCache::new(),
+        });
+        self
+    }
+
+    /// Routes every query of a request resolved to one of `regions` to that
+    /// region's database. Without regions, everything stays home, whatever
+    /// the request's region.
+    #[cfg_attr(any(feature = "sqlx", feature = "memory-store"), allow(dead_code))]
+    pub fn with_regions(mut self, regions: Vec<RegionDatabase>) -> Self {
+        self.regions = regions
+            .into_iter()
+            .map(|region| Region {
+                name: region.name,
+                db: region.db,
+                circuit_breaker: region.circuit_breaker,
+                prepared_cache: PreparedStatementCache::new(),
+            })
+            .collect();
+        self
+    }
+
+    /// A region this repository has no database for is an error rather than
+    /// a silent write to the home database.
+    fn target(&self) -> Result<Target<'_>, AppError> {
+        let home = Target {
+            region: None,
+            db: &self.db,
+            circuit_breaker: &self.circuit_breaker,
+            prepared_cache: &self.prepared_cache,
+        };
+        if self.regions.is_empty() {
+            return Ok(home);
+        }
+        let Some(name) = current_region() else {
+            return Ok(home);
+        };
+
+        self.regions
+            .iter()
+            .find(|region| *region.name == name)
+            .map(|region| Target {
+                region: Some(&region.name),
+                db: &region.db,
+                circuit_breaker: &region.circuit_breaker,
+                prepared_cache: &region.prepared_cache,
+            })
+            .ok_or_else(|| AppError::InternalServer(format!("No database for region {}", name)))
+    }
+
     pub async fn execute_with_circuit_breaker<F, Fut, T>(&self, operation: F) -> Result<T, AppError>
     where
         F: FnOnce(Pool) -> Fut + Send,
         Fut: std::future::Future<Output = Result<T, AppError>> + Send,
         T: Send,
     {
-        let db = self.db.clone();
-        let circuit_breaker = self.circuit_breaker.clone();
+        check_deadline(BACKEND)?;
+        let target = self.target()?;
+        let db = target.db.clone();
+        let circuit_breaker = target.circuit_breaker.clone();
 
         circuit_breaker
             .call(|| async move { operation(db).await })
             .await
     }
 
+    /// Prepares `queries` into the cache on up to `connections` pooled
+    /// connections, reads on the replica too and everything on each region,
+    /// and returns how many statements were prepared. A failing replica or
+    /// region is only logged: reads fall back to the primary, and a region
+    /// prepares on first use.
+    #[cfg_attr(any(feature = "sqlx", feature = "memory-store"), allow(dead_code))]
+    pub async fn warm_up(
+        &self,
+        queries: &[(QueryKind, &str)],
+        connections: usize,
+    ) -> Result<usize, AppError> {
+        let all = queries.iter().map(|(_, query)| *query);
+        let mut prepared = warm_pool(&self.db, &self.prepared_cache, all, connections).await?;
+
+        if let Some(replica) = &self.replica {
+            let reads = queries
+                .iter()
+                .filter(|(kind, _)| *kind == QueryKind::Read)
+                .map(|(_, query)| *query);
+            match warm_pool(&replica.db, &replica.prepared_cache, reads, connections).await {
+                Ok(count) => prepared += count,
+                Err(e) => tracing::warn!("Failed to warm the replica's statement cache: {}", e),
+            }
+        }
+        for region in &self.regions {
+            let all = queries.iter().map(|(_, query)| *query);
+            match warm_pool(&region.db, &region.prepared_cache, all, connections).await {
+                Ok(count) => prepared += count,
+                Err(e) => tracing::warn!(
+                    region = %region.name,
+                    "Failed to warm the region's statement cache: {}",
+                    e
+                ),
+            }
+        }
+
+        Ok(prepared)
+    }
+
+    #[cfg_attr(any(feature = "sqlx", feature = "memory-store"), allow(dead_code))]
     pub async fn check_database_health(&self) -> crate::auth::dto::ServiceHealth {
-        let db = self.db.clone();
-        let circuit_breaker = self.circuit_breaker.clone();
+        let target = match self.target() {
+            Ok(target) => target,
+            Err(e) => return check_database_health(|| async { Err(e) }).await,
+        };
+        let db = target.db.clone();
+        let circuit_breaker = target.circuit_breaker.clone();
 
         check_database_health(|| async move {
             circuit_breaker
@@ -55,10 +224,9 @@ impl BaseRepository {
         &self,
         query: &str,
         params: &[&(dyn ToSql + Sync)],
-    ) -> Result<Vec<tokio_postgres::Row>, AppError> {
-        let client = self.db.get().await?;
-        let stmt = self.prepared_cache.get_or_prepare(&client, query).await?;
-        Ok(client.query(&stmt, params).await?)
+    ) -> Result<Vec<Row>, AppError> {
+        self.execute_prepared_on(QueryKind::Write, query, params)
+            .await
     }
 
     #[cfg_attr(not(feature = "strict"), allow(dead_code))]
@@ -66,20 +234,146 @@ impl BaseRepository {
         &self,
         query: &str,
         params: &[&(dyn ToSql + Sync)],
-    ) -> Result<tokio_postgres::Row, AppError> {
-        let client = self.db.get().await?;
-        let stmt = self.prepared_cache.get_or_prepare(&client, query).await?;
-        Ok(client.query_one(&stmt, params).await?)
+    ) -> Result<Row, AppError> {
+        self.execute_prepared_one_on(QueryKind::Write, query, params)
+            .await
     }
 
     pub async fn execute_prepared_opt(
         &self,
         query: &str,
         params: &[&(dyn ToSql + Sync)],
-    ) -> Result<Option<tokio_postgres::Row>, AppError> {
-        let client = self.db.get().await?;
-        let stmt = self.prepared_cache.get_or_prepare(&client, query).await?;
-        Ok(client.query_opt(&stmt, params).await?)
+    ) -> Result<Option<Row>, AppError> {
+        self.execute_prepared_opt_on(QueryKind::Write, query, params)
+            .await
+    }
+
+    /// Like `execute_prepared`, on the replica for reads when there is one.
+    pub async fn execute_prepared_on(
+        &self,
+        kind: QueryKind,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, AppError> {
+        check_deadline(BACKEND)?;
+        let target = self.target()?;
+        if let Some(result) = self
+            .on_replica(&target, kind, |db, cache| {
+                prepared_query(db, cache, query, params)
+            })
+            .await
+        {
+            return result;
+        }
+        prepared_query(target.db, target.prepared_cache, query, params).await
+    }
+
+    /// Like `execute_prepared_one`, on the replica for reads when there is one.
+    #[cfg_attr(not(feature = "strict"), allow(dead_code))]
+    pub async fn execute_prepared_one_on(
+        &self,
+        kind: QueryKind,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, AppError> {
+        check_deadline(BACKEND)?;
+        let target = self.target()?;
+        if let Some(result) = self
+            .on_replica(&target, kind, |db, cache| {
+                prepared_query_one(db, cache, query, params)
+            })
+            .await
+        {
+            return result;
+        }
+        prepared_query_one(target.db, target.prepared_cache, query, params).await
+    }
+
+    /// Like `execute_prepared_opt`, on the replica for reads when there is
+    /// one. A row the replica does not have yet is looked up on the primary,
+    /// so a read right after the write that created it still finds it.
+    pub async fn execute_prepared_opt_on(
+        &self,
+        kind: QueryKind,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, AppError> {
+        check_deadline(BACKEND)?;
+        let target = self.target()?;
+        match self
+            .on_replica(&target, kind, |db, cache| {
+                prepared_query_opt(db, cache, query, params)
+            })
+            .await
+        {
+            Some(Ok(None)) | None => {
+                prepared_query_opt(target.db, target.prepared_cache, query, params).await
+            }
+            Some(result) => result,
+        }
+    }
+
+    /// `None` when the query belongs on the primary: a write, no replica, a
+    /// request of a region, which has no replica, or a replica that is
+    /// failing or behind its open breaker.
+    async fn on_replica<'a, T, F, Fut>(
+        &'a self,
+        target: &Target<'_>,
+        kind: QueryKind,
+        run: F,
+    ) -> Option<Result<T, AppError>>
+    where
+        F: FnOnce(&'a Pool, &'a PreparedStatementCache) -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        let replica = self
+            .replica
+            .as_ref()
+            .filter(|_| kind == QueryKind::Read && target.region.is_none())?;
+
+        match replica
+            .circuit_breaker
+            .call(|| run(&replica.db, &replica.prepared_cache))
+            .await
+        {
+            Err(e @ (AppError::InternalServer(_) | AppError::CircuitBreakerOpen(_))) => {
+                tracing::debug!(error = %e, "Replica read failed, reading from the primary");
+                track_replica_read("fallback");
+                None
+            }
+            result => {
+                track_replica_read("replica");
+                Some(result)
+            }
+        }
+    }
+
+    /// Runs an `INSERT ... RETURNING` and maps the inserted row.
+    #[cfg_attr(not(feature = "strict"), allow(dead_code))]
+    pub async fn insert_returning<T: FromRow>(
+        &self,
+        query: InsertBuilder,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<T, AppError> {
+        let row = self
+            .execute_prepared_one(&query.build_returning()?, params)
+            .await?;
+        T::from_row(&row)
+    }
+
+    /// Runs an `UPDATE ... RETURNING` and maps the updated row, if any
+    /// matched.
+    #[cfg_attr(not(feature = "strict"), allow(dead_code))]
+    pub async fn update_returning<T: FromRow>(
+        &self,
+        query: UpdateBuilder,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<T>, AppError> {
+        self.execute_prepared_opt(&query.build_returning()?, params)
+            .await?
+            .as_ref()
+            .map(T::from_row)
+            .transpose()
     }
 
     #[cfg_attr(not(feature = "strict"), allow(dead_code))]
@@ -88,14 +382,85 @@ impl BaseRepository {
         query: &str,
         params: &[&(dyn ToSql + Sync)],
     ) -> Result<u64, AppError> {
-        let client = self.db.get().await?;
-        let stmt = self.prepared_cache.get_or_prepare(&client, query).await?;
-        Ok(client.execute(&stmt, params).await?)
+        check_deadline(BACKEND)?;
+        let target = self.target()?;
+        let client = target.db.get().await?;
+        let stmt = target.prepared_cache.get_or_prepare(&client, query).await?;
+        client
+            .execute(&stmt, params)
+            .await
+            .map_err(|e| target.prepared_cache.check_error(&client, query, e))
     }
 }
 
+/// Holds the connections at once so each query is prepared on distinct
+/// ones, never more than the pool allows.
+#[cfg_attr(any(feature = "sqlx", feature = "memory-store"), allow(dead_code))]
+async fn warm_pool<'a>(
+    db: &Pool,
+    cache: &PreparedStatementCache,
+    queries: impl Iterator<Item = &'a str> + Clone,
+    connections: usize,
+) -> Result<usize, AppError> {
+    let mut clients = Vec::new();
+    for _ in 0..connections.min(db.status().max_size) {
+        clients.push(db.get().await?);
+    }
+
+    let mut prepared = 0;
+    for client in &clients {
+        for query in queries.clone() {
+            cache.get_or_prepare(client, query).await?;
+            prepared += 1;
+        }
+    }
+    Ok(prepared)
+}
+
+async fn prepared_query(
+    db: &Pool,
+    cache: &PreparedStatementCache,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Vec<Row>, AppError> {
+    let client = db.get().await?;
+    let stmt = cache.get_or_prepare(&client, query).await?;
+    client
+        .query(&stmt, params)
+        .await
+        .map_err(|e| cache.check_error(&client, query, e))
+}
+
+async fn prepared_query_one(
+    db: &Pool,
+    cache: &PreparedStatementCache,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Row, AppError> {
+    let client = db.get().await?;
+    let stmt = cache.get_or_prepare(&client, query).await?;
+    client
+        .query_one(&stmt, params)
+        .await
+        .map_err(|e| cache.check_error(&client, query, e))
+}
+
+async fn prepared_query_opt(
+    db: &Pool,
+    cache: &PreparedStatementCache,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Option<Row>, AppError> {
+    let client = db.get().await?;
+    let stmt = cache.get_or_prepare(&client, query).await?;
+    client
+        .query_opt(&stmt, params)
+        .await
+        .map_err(|e| cache.check_error(&client, query, e))
+}
+
 pub trait FromRow: Sized {
-    fn from_row(row: &tokio_postgres::Row) -> Result<Self, AppError>;
+    fn from_row(row: &Row) -> Result<Self, AppError>;
 }
 
 impl RepositoryMetrics for BaseRepository {
@@ -107,5 +472,14 @@ impl RepositoryMetrics for BaseRepository {
             status.available,
             status.max_size,
         );
+        for region in &self.regions {
+            let status = region.db.status();
+            update_db_region_pool_stats(
+                &region.name,
+                status.size,
+                status.available,
+                status.max_size,
+            );
+        }
     }
 }
@@ -0,0 +1,173 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use deadpool_postgres::Pool;
+use futures_util::{StreamExt, stream};
+use tokio::sync::mpsc;
+use tokio_postgres::{
+    AsyncMessage, Client, Notification, Socket,
+    tls::{MakeTlsConnect, TlsConnect},
+};
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+use crate::{
+    app::{AppError, middleware::metrics::track_pg_notification},
+    config::CircuitBreaker,
+};
+
+use super::base::BaseRepository;
+
+pub const NOTIFY: &str = "SELECT pg_notify($1, $2)";
+
+type Handler = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Subscribes to Postgres channels on a connection of its own, outside the
+/// pool, and hands every notification's payload to the handlers registered
+/// for its channel. The connection is opened again after it drops;
+/// notifications sent in between are lost, so handlers should only act on
+/// state that can be rebuilt, such as caches.
+pub struct PgListener {
+    config: tokio_postgres::Config,
+    tls: Option<MakeRustlsConnect>,
+    reconnect_delay: Duration,
+    handlers: HashMap<Box<str>, Vec<Handler>>,
+}
+
+impl PgListener {
+    pub fn new(
+        config: tokio_postgres::Config,
+        tls: Option<MakeRustlsConnect>,
+        reconnect_delay: Duration,
+    ) -> Self {
+        Self {
+            config,
+            tls,
+            reconnect_delay,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Channel names must be plain lowercase identifiers; `DbListenConfig`
+    /// checks the configured ones.
+    pub fn on(&mut self, channel: &str, handler: impl Fn(&str) + Send + Sync + 'static) {
+        self.handlers
+            .entry(channel.into())
+            .or_default()
+            .push(Box::new(handler));
+    }
+
+    /// Runs the handlers of `channel` and returns how many there were.
+    pub fn dispatch(&self, channel: &str, payload: &str) -> usize {
+        let handlers = self.handlers.get(channel).map(Vec::as_slice).unwrap_or(&[]);
+        for handler in handlers {
+            handler(payload);
+        }
+        handlers.len()
+    }
+
+    pub fn spawn(self) {
+        if self.handlers.is_empty() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            loop {
+                match self.listen().await {
+                    Ok(()) => tracing::warn!("Postgres listener connection closed"),
+                    Err(e) => tracing::warn!("Postgres listener failed: {}", e),
+                }
+                tokio::time::sleep(self.reconnect_delay).await;
+            }
+        });
+    }
+
+    async fn listen(&self) -> Result<(), AppError> {
+        let (client, mut notifications) = match &self.tls {
+            Some(tls) => connect(&self.config, tls.clone()).await?,
+            None => connect(&self.config, tokio_postgres::NoTls).await?,
+        };
+
+        let statements: String = self
+            .handlers
+            .keys()
+            .map(|channel| format!("LISTEN {};", channel))
+            .collect();
+        client.batch_execute(&statements).await?;
+        tracing::info!(
+            channels = ?self.handlers.keys().collect::<Vec<_>>(),
+            "Listening for Postgres notifications"
+        );
+
+        while let Some(notification) = notifications.recv().await {
+            track_pg_notification(notification.channel());
+            self.dispatch(notification.channel(), notification.payload());
+        }
+        Ok(())
+    }
+}
+
+/// Forwards the connection's notifications to a channel, which closes when
+/// the connection ends.
+async fn connect<T>(
+    config: &tokio_postgres::Config,
+    tls: T,
+) -> Result<(Client, mpsc::UnboundedReceiver<Notification>), tokio_postgres::Error>
+where
+    T: MakeTlsConnect<Socket>,
+    T::Stream: Send + 'static,
+    T::TlsConnect: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    let (client, mut connection) = config.connect(tls).await?;
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+        while let Some(message) = messages.next().await {
+            match message {
+                Ok(AsyncMessage::Notification(notification)) => {
+                    if sender.send(notification).is_err() {
+                        break;
+                    }
+                }
+                Ok(AsyncMessage::Notice(notice)) => {
+                    tracing::debug!("Postgres listener notice: {}", notice)
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("Postgres listener connection error: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok((client, receiver))
+}
+
+/// Sends notifications on one channel through the pool, reaching the
+/// `PgListener` of every instance, this one included.
+pub struct PgNotifier {
+    base: BaseRepository,
+    channel: Box<str>,
+}
+
+impl PgNotifier {
+    pub fn new(db: Pool, circuit_breaker: Arc<CircuitBreaker>, channel: &str) -> Self {
+        Self {
+            base: BaseRepository::new(db, circuit_breaker),
+            channel: channel.into(),
+        }
+    }
+
+    pub async fn notify(&self, payload: &str) -> Result<(), AppError> {
+        self.base
+            .execute_with_circuit_breaker(|db| async move {
+                let client = db.get().await?;
+                client
+                    .execute(NOTIFY, &[&self.channel.as_ref(), &payload])
+                    .await?;
+                Ok(())
+            })
+            .await
+    }
+}
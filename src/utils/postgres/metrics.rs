@@ -5,7 +5,25 @@ macro_rules! track_db_operation {
         let _op = $operation;
         let _tbl = $table;
 
-        let result = $body;
+        // A child span per round trip, so exported traces show where the
+        // request spent its time.
+        let _span = tracing::info_span!(
+            "db.query",
+            otel.kind = "client",
+            otel.status_code = tracing::field::Empty,
+            db.system.name = "postgresql",
+            db.operation.name = _op,
+            db.collection.name = _tbl,
+        );
+        let result = tracing::Instrument::instrument(
+            async {
+                #[allow(unused_braces)]
+                let result = $body;
+                result
+            },
+            _span.clone(),
+        )
+        .await;
 
         let duration = _start.elapsed().as_secs_f64();
         $crate::app::middleware::metrics::track_db_query(_op, _tbl, duration);
@@ -13,6 +31,7 @@ macro_rules! track_db_operation {
         match &result {
             Ok(_) => {}
             Err(_) => {
+                _span.record("otel.status_code", "ERROR");
                 $crate::app::middleware::metrics::track_db_error(_op, "query_failed");
             }
         }
@@ -49,6 +68,7 @@ macro_rules! db_delete {
     };
 }
 
+#[cfg_attr(any(feature = "sqlx", feature = "memory-store"), allow(dead_code))]
 pub trait RepositoryMetrics {
     fn update_pool_metrics(&self);
 }
@@ -0,0 +1,230 @@
+use sha2::{Digest, Sha256};
+use tokio_postgres::Client;
+
+use crate::app::AppError;
+
+/// A schema migration compiled into the binary, with a table (or index, or
+/// function signature such as `name()`) it creates so databases bootstrapped
+/// by the Postgres init scripts can be recognised.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub table: &'static str,
+    pub sql: &'static str,
+}
+
+impl Migration {
+    pub fn checksum(&self) -> String {
+        Sha256::digest(self.sql.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+macro_rules! migration {
+    ($version:literal, $name:literal, $table:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            table: $table,
+            sql: include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/migrations/",
+                $name,
+                ".sql"
+            )),
+        }
+    };
+}
+
+/// V0 is left out: it creates the application role with a placeholder
+/// password and belongs to whoever provisions the database.
+pub const MIGRATIONS: &[Migration] = &[
+    migration!(1, "V1__Create_User_Table", "users"),
+    migration!(2, "V2__Create_Webauthn_Table", "webauthn_sessions"),
+    migration!(
+        3,
+        "V3__Create_Notification_Routes_Table",
+        "notification_routes"
+    ),
+    migration!(
+        4,
+        "V4__Create_Notification_Templates_Table",
+        "notification_templates"
+    ),
+    migration!(5, "V5__Create_Recovery_Codes_Table", "recovery_codes"),
+    migration!(
+        6,
+        "V6__Create_Enrollment_Reminders_Table",
+        "enrollment_reminders"
+    ),
+    migration!(7, "V7__Create_Audit_Log_Table", "audit_log"),
+    migration!(8, "V8__Create_Login_Banner_Table", "login_banner"),
+    migration!(9, "V9__Create_Revoked_Tokens_Table", "revoked_tokens"),
+    migration!(10, "V10__Create_Roles_Tables", "roles"),
+    migration!(11, "V11__Create_Role_Aaguids_Table", "role_aaguids"),
+    migration!(
+        12,
+        "V12__Add_Normalized_Username",
+        "idx_users_normalized_username"
+    ),
+    migration!(13, "V13__Create_Login_History_Table", "login_history"),
+    migration!(14, "V14__Fix_Last_Used_Trigger", "touch_last_used()"),
+    migration!(15, "V15__Add_Credential_Aaguid", "idx_credentials_aaguid"),
+    migration!(16, "V16__Create_Token_Issuances_Table", "token_issuances"),
+    migration!(
+        17,
+        "V17__Partition_Audit_Log",
+        "maintain_partitions(text,integer,integer)"
+    ),
+    migration!(
+        18,
+        "V18__Add_Passkey_Format",
+        "idx_credentials_legacy_passkeys"
+    ),
+    migration!(
+        19,
+        "V19__Add_Credential_Clone_Suspicion",
+        "idx_credentials_clone_suspected"
+    ),
+    migration!(20, "V20__Create_Tenants_Table", "tenants"),
+    migration!(21, "V21__Add_User_Region", "idx_users_region"),
+    migration!(
+        22,
+        "V22__Add_Username_Trigram_Index",
+        "idx_users_normalized_username_trgm"
+    ),
+    migration!(23, "V23__Add_User_Suspension", "idx_users_suspended"),
+    migration!(24, "V24__Create_Machine_Clients_Table", "machine_clients"),
+    migration!(25, "V25__Create_Invitations_Table", "invitations"),
+    migration!(26, "V26__Add_Reusable_Invitations", "idx_invitations_open"),
+    migration!(27, "V27__Add_Log_Tenants", "idx_token_issuances_tenant"),
+    migration!(
+        28,
+        "V28__Add_Token_Issuance_Rotation",
+        "idx_token_issuances_rotated_from"
+    ),
+];
+
+// Arbitrary key shared by every instance, so only one of them migrates at a time.
+const MIGRATION_LOCK_KEY: i64 = 0x7273_5f6d_6967;
+
+const ACQUIRE_LOCK: &str = "SELECT pg_advisory_xact_lock($1)";
+const CREATE_HISTORY_TABLE: &str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+)";
+const SELECT_APPLIED: &str = "SELECT version, checksum FROM schema_migrations";
+const SELECT_EXISTING_TABLES: &str = "SELECT name
+     FROM UNNEST($1::text[]) AS name
+     WHERE CASE WHEN name LIKE '%(%'
+                THEN to_regprocedure(name) IS NOT NULL
+                ELSE to_regclass(name) IS NOT NULL
+           END";
+const INSERT_APPLIED: &str =
+    "INSERT INTO schema_migrations (version, name, checksum) VALUES ($1, $2, $3)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationStep {
+    /// Recorded in `schema_migrations` with a matching checksum.
+    Applied,
+    /// Not recorded, but its table exists: the init scripts already ran it.
+    Baseline,
+    Run,
+}
+
+/// Decides what to do with each embedded migration given the recorded
+/// history and the tables already present.
+pub fn plan_migrations(
+    migrations: &[Migration],
+    applied: &[(i32, String)],
+    existing_tables: &[String],
+) -> Result<Vec<MigrationStep>, AppError> {
+    migrations
+        .iter()
+        .map(|migration| {
+            match applied
+                .iter()
+                .find(|(version, _)| *version == migration.version)
+            {
+                Some((_, checksum)) if *checksum == migration.checksum() => {
+                    Ok(MigrationStep::Applied)
+                }
+                Some(_) => Err(AppError::InternalServer(format!(
+                    "Migration {} was modified after being applied",
+                    migration.name
+                ))),
+                None if existing_tables.iter().any(|t| t == migration.table) => {
+                    Ok(MigrationStep::Baseline)
+                }
+                None => Ok(MigrationStep::Run),
+            }
+        })
+        .collect()
+}
+
+/// Applies every pending migration in one transaction, so a failure leaves
+/// the schema as it was. Returns the names of the migrations that ran.
+pub async fn run_migrations(client: &mut Client) -> Result<Vec<&'static str>, AppError> {
+    let transaction = client.transaction().await?;
+
+    transaction
+        .execute(ACQUIRE_LOCK, &[&MIGRATION_LOCK_KEY])
+        .await?;
+    transaction.batch_execute(CREATE_HISTORY_TABLE).await?;
+
+    let applied: Vec<(i32, String)> = transaction
+        .query(SELECT_APPLIED, &[])
+        .await?
+        .iter()
+        .map(|row| (row.get("version"), row.get("checksum")))
+        .collect();
+
+    for (version, _) in &applied {
+        if !MIGRATIONS.iter().any(|m| m.version == *version) {
+            tracing::warn!(
+                "Database has migration V{} that this build does not know about",
+                version
+            );
+        }
+    }
+
+    let tables: Vec<&str> = MIGRATIONS.iter().map(|m| m.table).collect();
+    let existing_tables: Vec<String> = transaction
+        .query(SELECT_EXISTING_TABLES, &[&tables])
+        .await?
+        .iter()
+        .map(|row| row.get("name"))
+        .collect();
+
+    let steps = plan_migrations(MIGRATIONS, &applied, &existing_tables)?;
+    let mut ran = Vec::new();
+
+    for (migration, step) in MIGRATIONS.iter().zip(steps) {
+        match step {
+            MigrationStep::Applied => continue,
+            MigrationStep::Baseline => {
+                tracing::info!("Recording existing migration {}", migration.name);
+            }
+            MigrationStep::Run => {
+                tracing::info!("Applying migration {}", migration.name);
+                transaction.batch_execute(migration.sql).await?;
+                ran.push(migration.name);
+            }
+        }
+
+        transaction
+            .execute(
+                INSERT_APPLIED,
+                &[&migration.version, &migration.name, &migration.checksum()],
+            )
+            .await?;
+    }
+
+    transaction.commit().await?;
+    Ok(ran)
+}
@@ -1,11 +1,24 @@
 mod base;
+mod listener;
 mod metrics;
+mod migrations;
 mod prepared_cache;
+#[cfg(test)]
+pub(crate) mod query_audit;
 mod query_builder;
 
-pub(crate) use base::BaseRepository;
 pub(crate) use base::FromRow;
+pub(crate) use base::{BaseRepository, QueryKind, ReadReplica, RegionDatabase};
+pub(crate) use listener::{PgListener, PgNotifier};
 pub(crate) use metrics::RepositoryMetrics;
+pub(crate) use migrations::{MIGRATIONS, run_migrations};
+#[cfg(test)]
+pub(crate) use migrations::{Migration, MigrationStep, plan_migrations};
+#[cfg(test)]
+pub(crate) use prepared_cache::StatementLru;
+pub(crate) use prepared_cache::{PreparedStatementCache, set_statement_cache_limits};
 
 #[cfg_attr(not(feature = "strict"), allow(unused_imports))]
-pub(crate) use query_builder::{DeleteBuilder, InsertBuilder, SelectBuilder, UpdateBuilder};
+pub(crate) use query_builder::{
+    DeleteBuilder, InsertBuilder, ReturningClause, SelectBuilder, UpdateBuilder, escape_like,
+};
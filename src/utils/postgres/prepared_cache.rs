@@ -1,72 +1,122 @@
 use std::{
     collections::HashMap,
-    hash::{Hash, Hasher},
-    sync::{Arc, RwLock},
+    hash::Hash,
+    sync::{Arc, LazyLock, Mutex, OnceLock, Weak},
+    time::{Duration, Instant},
 };
 
-use tokio_postgres::{Client, Statement};
+use deadpool_postgres::{Object, ObjectId};
+use tokio_postgres::{Statement, error::SqlState};
 
-use crate::app::AppError;
+use crate::{
+    app::{AppError, middleware::metrics::track_prepared_statements},
+    config::StatementCacheConfig,
+};
+
+type StatementMap = Mutex<StatementLru<QueryKey, Statement>>;
+
+/// Every cache ever created, so operators can flush them all at once
+/// without each repository exposing its own.
+static REGISTRY: LazyLock<Mutex<Vec<Weak<StatementMap>>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+static LIMITS: OnceLock<StatementCacheConfig> = OnceLock::new();
 
+/// Installs the limits read at startup, for caches created afterwards. Only
+/// the first call has an effect; until then the defaults apply.
+pub fn set_statement_cache_limits(config: StatementCacheConfig) {
+    let _ = LIMITS.set(config);
+}
+
+/// Statements prepared through the pool. A statement only exists on the
+/// connection that prepared it, so entries are keyed by connection as well
+/// as by query; those of connections the pool has dropped age out through
+/// the size limit and TTL.
 #[derive(Clone)]
 pub struct PreparedStatementCache {
-    cache: Arc<RwLock<HashMap<QueryKey, Statement>>>,
+    cache: Arc<StatementMap>,
 }
 
-#[derive(Eq, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 struct QueryKey {
+    connection: ObjectId,
     query: String,
 }
 
-impl PartialEq for QueryKey {
-    fn eq(&self, other: &Self) -> bool {
-        self.query == other.query
-    }
-}
+impl PreparedStatementCache {
+    pub fn new() -> Self {
+        let limits = LIMITS.get_or_init(StatementCacheConfig::default);
+        let cache = Arc::new(Mutex::new(StatementLru::new(limits.max_size, limits.ttl)));
+        REGISTRY.lock().unwrap().push(Arc::downgrade(&cache));
 
-impl Hash for QueryKey {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.query.hash(state);
+        Self { cache }
     }
-}
 
-impl PreparedStatementCache {
-    pub fn new() -> Self {
-        Self {
-            cache: Arc::new(RwLock::new(HashMap::new())),
-        }
+    /// Drops every cached statement process-wide and returns how many were
+    /// removed. Statements are prepared again on next use.
+    pub fn clear_all() -> usize {
+        let mut registry = REGISTRY.lock().unwrap();
+        registry.retain(|cache| cache.strong_count() > 0);
+
+        registry
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|cache| cache.lock().unwrap_or_else(|e| e.into_inner()).clear())
+            .sum()
     }
 
     pub async fn get_or_prepare(
         &self,
-        client: &Client,
+        client: &Object,
         query: &str,
     ) -> Result<Statement, AppError> {
         let key = QueryKey {
+            connection: Object::id(client),
             query: query.to_string(),
         };
 
-        {
-            let cache = self.cache.read().map_err(|_| {
-                AppError::InternalServer("Failed to acquire cache read lock".to_string())
-            })?;
-
-            if let Some(stmt) = cache.get(&key) {
-                return Ok(stmt.clone());
-            }
+        if let Some(stmt) = self.lock()?.get(&key, Instant::now()) {
+            track_prepared_statements("hit", 1);
+            return Ok(stmt);
         }
+        track_prepared_statements("miss", 1);
 
         let stmt = client.prepare(query).await?;
+        self.lock()?.insert(key, stmt.clone(), Instant::now());
 
-        {
-            let mut cache = self.cache.write().map_err(|_| {
-                AppError::InternalServer("Failed to acquire cache write lock".to_string())
-            })?;
+        Ok(stmt)
+    }
 
-            cache.insert(key, stmt.clone());
+    /// Converts the error of a query run with a cached statement, dropping
+    /// the statement if the server no longer knows it (for instance after a
+    /// `DISCARD ALL` or a pooler switching backends), so the next call
+    /// prepares it again.
+    pub fn check_error(
+        &self,
+        client: &Object,
+        query: &str,
+        error: tokio_postgres::Error,
+    ) -> AppError {
+        if error.code() == Some(&SqlState::INVALID_SQL_STATEMENT_NAME) {
+            let key = QueryKey {
+                connection: Object::id(client),
+                query: query.to_string(),
+            };
+            if let Ok(mut cache) = self.lock()
+                && cache.remove(&key)
+            {
+                track_prepared_statements("invalidated", 1);
+            }
         }
+        error.into()
+    }
 
-        Ok(stmt)
+    fn lock(
+        &self,
+    ) -> Result<std::sync::MutexGuard<'_, StatementLru<QueryKey, Statement>>, AppError> {
+        self.cache
+            .lock()
+            .map_err(|_| AppError::InternalServer("Failed to acquire cache lock".to_string()))
     }
 }
 
@@ -75,3 +125,103 @@ impl Default for PreparedStatementCache {
         Self::new()
     }
 }
+
+/// A map holding at most `max_size` entries, each for at most `ttl`. Once
+/// full, the least recently used entry makes room for a new one.
+pub struct StatementLru<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    max_size: usize,
+    ttl: Option<Duration>,
+    clock: u64,
+}
+
+struct Entry<V> {
+    value: V,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> StatementLru<K, V> {
+    pub fn new(max_size: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_size,
+            ttl,
+            clock: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: &K, now: Instant) -> Option<V> {
+        let expired = self
+            .entries
+            .get(key)
+            .is_some_and(|entry| self.is_expired(entry, now));
+        if expired {
+            self.entries.remove(key);
+            track_prepared_statements("expired", 1);
+            return None;
+        }
+
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.clock;
+        Some(entry.value.clone())
+    }
+
+    pub fn insert(&mut self, key: K, value: V, now: Instant) {
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.max_size {
+            self.evict(now);
+        }
+
+        self.clock += 1;
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                inserted_at: now,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    /// Empties the map and returns how many entries it held.
+    pub fn clear(&mut self) -> usize {
+        let removed = self.entries.len();
+        self.entries.clear();
+        removed
+    }
+
+    /// Drops whatever has expired, or the least recently used entry if
+    /// nothing has.
+    fn evict(&mut self, now: Instant) {
+        if let Some(ttl) = self.ttl {
+            let before = self.entries.len();
+            self.entries
+                .retain(|_, entry| now.duration_since(entry.inserted_at) < ttl);
+            let expired = before - self.entries.len();
+            if expired > 0 {
+                track_prepared_statements("expired", expired);
+                return;
+            }
+        }
+
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            self.entries.remove(&oldest);
+            track_prepared_statements("evicted", 1);
+        }
+    }
+
+    fn is_expired(&self, entry: &Entry<V>, now: Instant) -> bool {
+        self.ttl
+            .is_some_and(|ttl| now.duration_since(entry.inserted_at) >= ttl)
+    }
+}
@@ -0,0 +1,527 @@
+//! Test-only check that SQL and its call sites agree. Every `.rs` file under
+//! `src/` is read from disk, so modules added later are covered without
+//! registering them here. SQL is found in three places: string constants
+//! anywhere in the tree, `queries::module::NAME` paths and string literals
+//! passed straight to a call.
+//!
+//! Statements are lexed with `sqlparser`'s Postgres tokenizer, not parsed:
+//! its parser rejects valid Postgres such as data-modifying CTEs, so a
+//! statement that lexes with balanced parentheses is taken as well formed.
+//! Beyond that, the check cannot see:
+//! - SQL built at runtime, by `format!` or the query builders;
+//! - parameter lists that are not an array literal at the call, such as a
+//!   `Vec` built beforehand, whose length is only known when running;
+//! - macro bodies that do not read as a list of expressions;
+//! - whether the parameter types match the columns, which only the
+//!   database knows.
+
+use std::{
+    collections::BTreeSet,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use sqlparser::{
+    dialect::PostgreSqlDialect,
+    tokenizer::{Token as SqlToken, Tokenizer},
+};
+use syn::{
+    Expr, ExprArray, ExprLit, Lit, Token,
+    punctuated::Punctuated,
+    visit::{self, Visit},
+};
+
+/// Leading keywords of the strings treated as SQL. Anything else, such as a
+/// Redis key, is left alone.
+const SQL_KEYWORDS: &[&str] = &[
+    "ALTER", "CALL", "CREATE", "DELETE", "DROP", "INSERT", "LISTEN", "LOCK", "NOTIFY", "SELECT",
+    "TRUNCATE", "UPDATE", "VALUES", "WITH",
+];
+
+#[derive(Debug, Clone)]
+pub struct QueryConstant {
+    pub file: PathBuf,
+    /// The `mod` the constant is declared in, e.g. `credentials`, or the
+    /// file stem for one declared at the top of a file.
+    pub module: String,
+    pub name: String,
+    pub sql: String,
+}
+
+impl QueryConstant {
+    pub fn is_sql(&self) -> bool {
+        is_sql(&self.sql)
+    }
+
+    fn in_queries_file(&self) -> bool {
+        self.file
+            .file_name()
+            .is_some_and(|name| name == "queries.rs")
+    }
+}
+
+/// What a call passes as its statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// `queries::module::NAME`, with the path segment before `queries`, as
+    /// in `crate::auth::queries`.
+    Query {
+        qualifier: Option<String>,
+        module: String,
+        name: String,
+    },
+    /// A bare `NAME` or `Self::NAME`, looked up among the constants of the
+    /// calling file.
+    /// Names that are not found there are imports and go unchecked.
+    Local(String),
+    /// SQL written as a string literal at the call.
+    Inline(String),
+}
+
+/// A statement passed to a call, with the parameter slice that follows it
+/// when that is an array literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallSite {
+    pub file: PathBuf,
+    pub target: Target,
+    /// `None` when the parameters are not an array literal and cannot be
+    /// counted without running the code.
+    pub params: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    Malformed {
+        constant: String,
+        reason: String,
+    },
+    /// `$1` up to the highest placeholder must all appear.
+    PlaceholderGap {
+        constant: String,
+        missing: Vec<usize>,
+    },
+    ParamCount {
+        constant: String,
+        file: PathBuf,
+        placeholders: usize,
+        params: usize,
+    },
+    UnknownConstant {
+        constant: String,
+        file: PathBuf,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Malformed { constant, reason } => write!(f, "{}: {}", constant, reason),
+            Problem::PlaceholderGap { constant, missing } => {
+                write!(f, "{}: placeholders {:?} are never used", constant, missing)
+            }
+            Problem::ParamCount {
+                constant,
+                file,
+                placeholders,
+                params,
+            } => write!(
+                f,
+                "{} in {}: {} placeholders but {} parameters",
+                constant,
+                file.display(),
+                placeholders,
+                params
+            ),
+            Problem::UnknownConstant { constant, file } => {
+                write!(
+                    f,
+                    "{} in {}: no such query constant",
+                    constant,
+                    file.display()
+                )
+            }
+        }
+    }
+}
+
+/// Whether `sql` starts like a statement rather than a key or a fragment.
+pub fn is_sql(sql: &str) -> bool {
+    let first = sql
+        .trim_start()
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default();
+    SQL_KEYWORDS.contains(&first)
+}
+
+/// The `$n` placeholders of a Postgres statement. Lookalikes inside string
+/// literals, quoted identifiers, comments and dollar-quoted bodies are
+/// skipped; unterminated ones and unbalanced parentheses are errors.
+pub fn placeholders(sql: &str) -> Result<BTreeSet<usize>, String> {
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql)
+        .tokenize()
+        .map_err(|e| e.message)?;
+    let mut found = BTreeSet::new();
+    let mut depth = 0usize;
+
+    for token in tokens {
+        match token {
+            SqlToken::Placeholder(placeholder) => {
+                let Some(digits) = placeholder.strip_prefix('$') else {
+                    continue;
+                };
+                let index: usize = digits.parse().map_err(|_| "placeholder out of range")?;
+                if index == 0 {
+                    return Err(String::from("placeholders start at $1"));
+                }
+                found.insert(index);
+            }
+            SqlToken::LParen => depth += 1,
+            SqlToken::RParen => {
+                depth = depth.checked_sub(1).ok_or("unbalanced parentheses")?;
+            }
+            _ => {}
+        }
+    }
+
+    if depth != 0 {
+        return Err(String::from("unbalanced parentheses"));
+    }
+    Ok(found)
+}
+
+/// The string constants in `source`, at any depth: in modules, impls and
+/// function bodies alike.
+pub fn constants_in(file: &Path, source: &str) -> Vec<QueryConstant> {
+    let Ok(parsed) = syn::parse_file(source) else {
+        return Vec::new();
+    };
+
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut visitor = ConstantVisitor {
+        file,
+        modules: vec![stem],
+        constants: Vec::new(),
+    };
+    visitor.visit_file(&parsed);
+    visitor.constants
+}
+
+struct ConstantVisitor<'a> {
+    file: &'a Path,
+    /// Enclosing modules, innermost last, starting with the file stem.
+    modules: Vec<String>,
+    constants: Vec<QueryConstant>,
+}
+
+impl ConstantVisitor<'_> {
+    fn record(&mut self, name: &syn::Ident, expr: &Expr) {
+        if let Expr::Lit(ExprLit {
+            lit: Lit::Str(sql), ..
+        }) = expr
+        {
+            self.constants.push(QueryConstant {
+                file: self.file.to_owned(),
+                module: self.modules.last().cloned().unwrap_or_default(),
+                name: name.to_string(),
+                sql: sql.value(),
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ConstantVisitor<'_> {
+    fn visit_item_mod(&mut self, module: &'ast syn::ItemMod) {
+        self.modules.push(module.ident.to_string());
+        visit::visit_item_mod(self, module);
+        self.modules.pop();
+    }
+
+    fn visit_item_const(&mut self, constant: &'ast syn::ItemConst) {
+        self.record(&constant.ident, &constant.expr);
+        visit::visit_item_const(self, constant);
+    }
+
+    fn visit_impl_item_const(&mut self, constant: &'ast syn::ImplItemConst) {
+        self.record(&constant.ident, &constant.expr);
+        visit::visit_impl_item_const(self, constant);
+    }
+}
+
+/// Every statement passed as a call argument in `source`, macro bodies such
+/// as `db_select!` included.
+pub fn call_sites_in(file: &Path, source: &str) -> Vec<CallSite> {
+    let Ok(parsed) = syn::parse_file(source) else {
+        return Vec::new();
+    };
+
+    let mut visitor = CallSiteVisitor {
+        file,
+        sites: Vec::new(),
+    };
+    visitor.visit_file(&parsed);
+    visitor.sites
+}
+
+struct CallSiteVisitor<'a> {
+    file: &'a Path,
+    sites: Vec<CallSite>,
+}
+
+impl CallSiteVisitor<'_> {
+    fn check_args<'e>(&mut self, args: impl IntoIterator<Item = &'e Expr>) {
+        let args: Vec<&Expr> = args.into_iter().collect();
+        for (index, arg) in args.iter().enumerate() {
+            let Some(target) = target(arg) else {
+                continue;
+            };
+            let params = match args.get(index + 1) {
+                Some(Expr::Reference(reference)) => match &*reference.expr {
+                    Expr::Array(ExprArray { elems, .. }) => Some(elems.len()),
+                    _ => None,
+                },
+                _ => None,
+            };
+            self.sites.push(CallSite {
+                file: self.file.to_owned(),
+                target,
+                params,
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CallSiteVisitor<'_> {
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        self.check_args(&call.args);
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        self.check_args(&call.args);
+        visit::visit_expr_method_call(self, call);
+    }
+
+    /// `syn` leaves macro bodies as tokens; those that read as a list of
+    /// expressions are visited like code.
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if let Ok(exprs) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for expr in &exprs {
+                self.visit_expr(expr);
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+/// The statement `expr` names, if it is a query path, a bare constant name
+/// or a SQL literal.
+fn target(expr: &Expr) -> Option<Target> {
+    let path = match expr {
+        Expr::Path(path) => path,
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(sql), ..
+        }) => {
+            let sql = sql.value();
+            return is_sql(&sql).then_some(Target::Inline(sql));
+        }
+        _ => return None,
+    };
+    let segments: Vec<String> = path
+        .path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    if let [name] | [_, name] = segments.as_slice()
+        && (segments.len() == 1 || segments[0] == "Self")
+    {
+        let constant_like = name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        return constant_like.then(|| Target::Local(name.clone()));
+    }
+    let [.., queries, module, name] = segments.as_slice() else {
+        return None;
+    };
+    if queries != "queries" {
+        return None;
+    }
+    let qualifier = segments
+        .len()
+        .checked_sub(4)
+        .map(|index| segments[index].clone());
+    Some(Target::Query {
+        qualifier,
+        module: module.clone(),
+        name: name.clone(),
+    })
+}
+
+/// Finds the `queries.rs` constant a query path names: the only one with
+/// that module and name, else the one in the directory the qualifier names,
+/// else the nearest `queries.rs` above the calling file.
+fn resolve<'c>(
+    file: &Path,
+    qualifier: Option<&str>,
+    module: &str,
+    name: &str,
+    constants: &'c [QueryConstant],
+) -> Option<&'c QueryConstant> {
+    let candidates: Vec<&QueryConstant> = constants
+        .iter()
+        .filter(|constant| {
+            constant.in_queries_file() && constant.module == module && constant.name == name
+        })
+        .collect();
+    if candidates.len() <= 1 {
+        return candidates.into_iter().next();
+    }
+
+    let directory = |constant: &QueryConstant| constant.file.parent().map(Path::to_path_buf);
+    if let Some(qualifier) = qualifier
+        && let Some(found) = candidates.iter().find(|constant| {
+            directory(constant)
+                .and_then(|dir| dir.file_name().map(|name| name == qualifier))
+                .unwrap_or(false)
+        })
+    {
+        return Some(found);
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|constant| {
+            let dir = directory(constant)?;
+            file.starts_with(&dir)
+                .then(|| (dir.components().count(), constant))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, constant)| constant)
+}
+
+/// Checks a statement on its own: it must lex, and `$1` up to its highest
+/// placeholder must all appear.
+fn check_statement(label: &str, sql: &str, problems: &mut Vec<Problem>) {
+    match placeholders(sql) {
+        Ok(found) => {
+            let highest = found.last().copied().unwrap_or(0);
+            let missing: Vec<usize> = (1..=highest)
+                .filter(|index| !found.contains(index))
+                .collect();
+            if !missing.is_empty() {
+                problems.push(Problem::PlaceholderGap {
+                    constant: label.to_owned(),
+                    missing,
+                });
+            }
+        }
+        Err(reason) => problems.push(Problem::Malformed {
+            constant: label.to_owned(),
+            reason,
+        }),
+    }
+}
+
+/// Checks every SQL constant and inline statement on its own, then every
+/// call site whose parameters can be counted.
+pub fn audit(constants: &[QueryConstant], sites: &[CallSite]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for constant in constants.iter().filter(|constant| constant.is_sql()) {
+        let label = format!("{}::{}", constant.module, constant.name);
+        check_statement(&label, &constant.sql, &mut problems);
+    }
+
+    for site in sites {
+        let (label, sql) = match &site.target {
+            Target::Query {
+                qualifier,
+                module,
+                name,
+            } => {
+                let label = format!("{}::{}", module, name);
+                let Some(constant) =
+                    resolve(&site.file, qualifier.as_deref(), module, name, constants)
+                else {
+                    problems.push(Problem::UnknownConstant {
+                        constant: label,
+                        file: site.file.clone(),
+                    });
+                    continue;
+                };
+                (label, constant.sql.as_str())
+            }
+            Target::Local(name) => {
+                let Some(constant) = constants
+                    .iter()
+                    .find(|constant| constant.file == site.file && &constant.name == name)
+                else {
+                    continue;
+                };
+                (
+                    format!("{}::{}", constant.module, name),
+                    constant.sql.as_str(),
+                )
+            }
+            Target::Inline(sql) => {
+                let label = format!("inline SQL in {}", site.file.display());
+                check_statement(&label, sql, &mut problems);
+                (String::from("inline SQL"), sql.as_str())
+            }
+        };
+        let (Some(params), true) = (site.params, is_sql(sql)) else {
+            continue;
+        };
+        let Ok(found) = placeholders(sql) else {
+            continue;
+        };
+        let highest = found.last().copied().unwrap_or(0);
+        if highest != params {
+            problems.push(Problem::ParamCount {
+                constant: label,
+                file: site.file.clone(),
+                placeholders: highest,
+                params,
+            });
+        }
+    }
+
+    problems
+}
+
+/// Every `.rs` file under `dir`, skipping `tests` directories.
+pub fn source_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if path.file_name().is_some_and(|name| name != "tests") {
+                files.extend(source_files(&path));
+            }
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// The string constants and call sites of every source file under `dir`.
+pub fn scan(dir: &Path) -> (Vec<QueryConstant>, Vec<CallSite>) {
+    let mut constants = Vec::new();
+    let mut sites = Vec::new();
+
+    for file in source_files(dir) {
+        let source = std::fs::read_to_string(&file).unwrap_or_default();
+        constants.extend(constants_in(&file, &source));
+        sites.extend(call_sites_in(&file, &source));
+    }
+    (constants, sites)
+}
@@ -23,9 +23,83 @@ trait WhereClause {
         self.wheres_mut().push(format!("{} = ${}", column, count));
         self
     }
+
+    /// Matches any of `values`, bound as a single array parameter so the
+    /// statement stays the same whatever the list length.
+    fn where_in<T>(mut self, column: &str, _values: &[T]) -> Self
+    where
+        Self: Sized,
+    {
+        *self.param_count_mut() += 1;
+        let count = *self.param_count_mut();
+        self.wheres_mut()
+            .push(format!("{} = ANY(${})", column, count));
+        self
+    }
+
+    /// Adds the conditions built on the group joined by OR, continuing the
+    /// parameter numbering; an empty group adds nothing.
+    fn or_where<F>(mut self, build: F) -> Self
+    where
+        Self: Sized,
+        F: FnOnce(OrGroup) -> OrGroup,
+    {
+        let group = build(OrGroup::new(*self.param_count_mut()));
+        *self.param_count_mut() = group.param_count;
+        if let Some(condition) = group.build() {
+            self.wheres_mut().push(condition);
+        }
+        self
+    }
+}
+
+/// Escapes `value` for a `LIKE` or `ILIKE` pattern using the default `\`
+/// escape character, so user input only ever matches itself.
+pub fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Conditions of an `or_where` call.
+pub struct OrGroup {
+    conditions: Vec<String>,
+    param_count: i32,
 }
 
-trait ReturningClause {
+impl OrGroup {
+    fn new(param_count: i32) -> Self {
+        Self {
+            conditions: Vec::new(),
+            param_count,
+        }
+    }
+
+    fn build(self) -> Option<String> {
+        match self.conditions.len() {
+            0 => None,
+            1 => self.conditions.into_iter().next(),
+            _ => Some(format!("({})", self.conditions.join(" OR "))),
+        }
+    }
+}
+
+impl WhereClause for OrGroup {
+    fn wheres_mut(&mut self) -> &mut Vec<String> {
+        &mut self.conditions
+    }
+
+    fn param_count_mut(&mut self) -> &mut i32 {
+        &mut self.param_count
+    }
+}
+
+pub trait ReturningClause {
     fn returning_mut(&mut self) -> &mut Vec<String>;
 
     fn returning(mut self, column: &str) -> Self
@@ -86,6 +160,8 @@ pub struct SelectBuilder {
     from: Option<String>,
     joins: Vec<String>,
     wheres: Vec<String>,
+    group_by: Vec<String>,
+    havings: Vec<String>,
     order_by: Vec<String>,
     limit: Option<i64>,
     offset: Option<i64>,
@@ -99,6 +175,8 @@ impl SelectBuilder {
             from: None,
             joins: Vec::new(),
             wheres: Vec::new(),
+            group_by: Vec::new(),
+            havings: Vec::new(),
             order_by: Vec::new(),
             limit: None,
             offset: None,
@@ -131,6 +209,26 @@ impl SelectBuilder {
         self
     }
 
+    pub fn group_by(mut self, column: &str) -> Self {
+        self.group_by.push(column.to_string());
+        self
+    }
+
+    pub fn having(mut self, condition: &str) -> Self {
+        self.havings.push(condition.to_string());
+        self
+    }
+
+    /// Compares an aggregate against a parameter, e.g.
+    /// `having_param("COUNT(*) >=", &min)`. Parameters are numbered in call
+    /// order, so bind HAVING values where they were added.
+    pub fn having_param<T>(mut self, expression: &str, _value: &T) -> Self {
+        self.param_count += 1;
+        self.havings
+            .push(format!("{} ${}", expression, self.param_count));
+        self
+    }
+
     pub fn order_by(mut self, column: &str, direction: OrderDirection) -> Self {
         self.order_by
             .push(format!("{} {}", column, direction.as_str()));
@@ -151,6 +249,11 @@ impl SelectBuilder {
         if self.from.is_none() {
             return Err(AppError::BadRequest("FROM clause is required".to_string()));
         }
+        if !self.havings.is_empty() && self.group_by.is_empty() {
+            return Err(AppError::BadRequest(
+                "HAVING requires a GROUP BY clause".to_string(),
+            ));
+        }
 
         let columns = if self.columns.is_empty() {
             "*"
@@ -163,6 +266,8 @@ impl SelectBuilder {
         let query = QueryFragment::new(base)
             .append_if("", &self.joins, " ")
             .append_if("WHERE", &self.wheres, " AND ")
+            .append_if("GROUP BY", &self.group_by, ", ")
+            .append_if("HAVING", &self.havings, " AND ")
             .append_if("ORDER BY", &self.order_by, ", ")
             .append_option("LIMIT", self.limit)
             .append_option("OFFSET", self.offset)
@@ -186,10 +291,49 @@ impl WhereClause for SelectBuilder {
     }
 }
 
+enum OnConflict {
+    DoNothing(Vec<String>),
+    DoUpdate {
+        target: Vec<String>,
+        columns: Vec<String>,
+    },
+}
+
+impl OnConflict {
+    fn build(&self) -> Result<String, AppError> {
+        match self {
+            OnConflict::DoNothing(target) if target.is_empty() => {
+                Ok("ON CONFLICT DO NOTHING".to_string())
+            }
+            OnConflict::DoNothing(target) => {
+                Ok(format!("ON CONFLICT ({}) DO NOTHING", target.join(", ")))
+            }
+            OnConflict::DoUpdate { target, columns } => {
+                if target.is_empty() || columns.is_empty() {
+                    return Err(AppError::BadRequest(
+                        "DO UPDATE requires conflict and update columns".to_string(),
+                    ));
+                }
+                let sets: Vec<String> = columns
+                    .iter()
+                    .map(|column| format!("{} = EXCLUDED.{}", column, column))
+                    .collect();
+                Ok(format!(
+                    "ON CONFLICT ({}) DO UPDATE SET {}",
+                    target.join(", "),
+                    sets.join(", ")
+                ))
+            }
+        }
+    }
+}
+
 pub struct InsertBuilder {
     table: Option<String>,
     columns: Vec<String>,
+    rows: usize,
     param_count: i32,
+    on_conflict: Option<OnConflict>,
     returning: Vec<String>,
 }
 
@@ -198,7 +342,9 @@ impl InsertBuilder {
         Self {
             table: None,
             columns: Vec::new(),
+            rows: 1,
             param_count: 0,
+            on_conflict: None,
             returning: Vec::new(),
         }
     }
@@ -214,6 +360,36 @@ impl InsertBuilder {
         self
     }
 
+    /// Inserts `rows` rows of the chosen columns in one statement. Bind the
+    /// values row by row, each in column order.
+    pub fn rows(mut self, rows: usize) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    /// Skips rows conflicting on `target`, or on any constraint when empty.
+    pub fn on_conflict_do_nothing(mut self, target: &[&str]) -> Self {
+        self.on_conflict = Some(OnConflict::DoNothing(
+            target.iter().map(|column| column.to_string()).collect(),
+        ));
+        self
+    }
+
+    /// Overwrites `columns` of the existing row with the inserted values
+    /// when a row conflicts on `target`.
+    pub fn on_conflict_do_update(mut self, target: &[&str], columns: &[&str]) -> Self {
+        self.on_conflict = Some(OnConflict::DoUpdate {
+            target: target.iter().map(|column| column.to_string()).collect(),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+        });
+        self
+    }
+
+    /// Parameters the statement expects, across every row.
+    pub fn param_count(&self) -> i32 {
+        self.param_count * self.rows as i32
+    }
+
     pub fn build(self) -> Result<String, AppError> {
         if self.table.is_none() {
             return Err(AppError::BadRequest("Table name is required".to_string()));
@@ -223,22 +399,50 @@ impl InsertBuilder {
                 "At least one column is required".to_string(),
             ));
         }
+        if self.rows == 0 {
+            return Err(AppError::BadRequest(
+                "At least one row is required".to_string(),
+            ));
+        }
 
-        let placeholders: Vec<String> = (1..=self.param_count).map(|i| format!("${}", i)).collect();
+        let per_row = self.param_count;
+        let rows: Vec<String> = (0..self.rows as i32)
+            .map(|row| {
+                let placeholders: Vec<String> = (1..=per_row)
+                    .map(|i| format!("${}", row * per_row + i))
+                    .collect();
+                format!("({})", placeholders.join(", "))
+            })
+            .collect();
 
         let base = format!(
-            "INSERT INTO {} ({}) VALUES ({})",
+            "INSERT INTO {} ({}) VALUES {}",
             self.table.unwrap(),
             self.columns.join(", "),
-            placeholders.join(", ")
+            rows.join(", ")
         );
 
+        let on_conflict = match &self.on_conflict {
+            Some(on_conflict) => vec![on_conflict.build()?],
+            None => Vec::new(),
+        };
+
         let query = QueryFragment::new(base)
+            .append_if("", &on_conflict, "")
             .append_if("RETURNING", &self.returning, ", ")
             .build();
 
         Ok(query)
     }
+
+    /// Like `build`, returning every column when none was chosen, so the row
+    /// can be mapped into a `FromRow` type.
+    pub fn build_returning(mut self) -> Result<String, AppError> {
+        if self.returning.is_empty() {
+            self = self.returning_all();
+        }
+        self.build()
+    }
 }
 
 impl ReturningClause for InsertBuilder {
@@ -322,6 +526,15 @@ impl UpdateBuilder {
         Ok(query)
     }
 
+    /// Like `build`, returning every column when none was chosen, so the row
+    /// can be mapped into a `FromRow` type.
+    pub fn build_returning(mut self) -> Result<String, AppError> {
+        if self.returning.is_empty() {
+            self = self.returning_all();
+        }
+        self.build()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.sets.is_empty()
     }
@@ -453,6 +666,101 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_select_builder_group_by_having() {
+        let min_credentials = 2;
+        let query = SelectBuilder::new()
+            .select("user_id")
+            .select("COUNT(*) AS credentials")
+            .from("credentials")
+            .where_param("backup_eligible", &true)
+            .group_by("user_id")
+            .having_param("COUNT(*) >=", &min_credentials)
+            .order_by("credentials", OrderDirection::Desc)
+            .limit(10)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            query,
+            "SELECT user_id, COUNT(*) AS credentials FROM credentials WHERE backup_eligible = $1 GROUP BY user_id HAVING COUNT(*) >= $2 ORDER BY credentials DESC LIMIT 10"
+        );
+    }
+
+    #[test]
+    fn test_select_builder_having_requires_group_by() {
+        let result = SelectBuilder::new()
+            .from("credentials")
+            .having("COUNT(*) > 1")
+            .build();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_select_builder_where_in_binds_one_array() {
+        let roles = ["admin", "auditor"];
+        let query = SelectBuilder::new()
+            .from("users")
+            .where_in("role", &roles)
+            .where_param("status", &"active")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            query,
+            "SELECT * FROM users WHERE role = ANY($1) AND status = $2"
+        );
+    }
+
+    #[test]
+    fn test_select_builder_or_where_continues_numbering() {
+        let query = SelectBuilder::new()
+            .from("users")
+            .where_param("status", &"active")
+            .or_where(|group| {
+                group
+                    .where_param("username", &"alice")
+                    .where_in("id", &[1, 2])
+                    .where_clause("role = 'admin'")
+            })
+            .group_by("role")
+            .having_param("COUNT(*) >", &1)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            query,
+            "SELECT * FROM users WHERE status = $1 AND (username = $2 OR id = ANY($3) OR role = 'admin') GROUP BY role HAVING COUNT(*) > $4"
+        );
+    }
+
+    #[test]
+    fn test_or_where_single_or_empty_group() {
+        let builder = SelectBuilder::new()
+            .from("users")
+            .or_where(|group| group)
+            .or_where(|group| group.where_param("username", &"alice"));
+        assert_eq!(builder.param_count(), 1);
+
+        assert_eq!(
+            builder.build().unwrap(),
+            "SELECT * FROM users WHERE username = $1"
+        );
+    }
+
+    #[test]
+    fn test_delete_builder_where_in() {
+        let ids = [1, 2, 3];
+        let query = DeleteBuilder::new()
+            .from("products")
+            .where_in("id", &ids)
+            .build()
+            .unwrap();
+
+        assert_eq!(query, "DELETE FROM products WHERE id = ANY($1)");
+    }
+
     #[test]
     fn test_insert_builder() {
         let name = "product";
@@ -471,6 +779,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_insert_builder_multiple_rows() {
+        let (user_id, name) = (1, "laptop");
+        let builder = InsertBuilder::new()
+            .into("credentials")
+            .column("user_id", &user_id)
+            .column("name", &name)
+            .rows(3)
+            .returning("id");
+        assert_eq!(builder.param_count(), 6);
+
+        assert_eq!(
+            builder.build().unwrap(),
+            "INSERT INTO credentials (user_id, name) VALUES ($1, $2), ($3, $4), ($5, $6) RETURNING id"
+        );
+    }
+
+    #[test]
+    fn test_insert_builder_upsert() {
+        let (id, name) = (1, "laptop");
+        let query = InsertBuilder::new()
+            .into("credentials")
+            .column("id", &id)
+            .column("name", &name)
+            .column("last_used", &"now")
+            .rows(2)
+            .on_conflict_do_update(&["id"], &["name", "last_used"])
+            .build_returning()
+            .unwrap();
+
+        assert_eq!(
+            query,
+            "INSERT INTO credentials (id, name, last_used) VALUES ($1, $2, $3), ($4, $5, $6) ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name, last_used = EXCLUDED.last_used RETURNING *"
+        );
+    }
+
+    #[test]
+    fn test_insert_builder_do_nothing() {
+        let jti = "abc";
+        let builder = InsertBuilder::new().into("sessions").column("jti", &jti);
+
+        assert_eq!(
+            builder.on_conflict_do_nothing(&["jti"]).build().unwrap(),
+            "INSERT INTO sessions (jti) VALUES ($1) ON CONFLICT (jti) DO NOTHING"
+        );
+        assert_eq!(
+            InsertBuilder::new()
+                .into("sessions")
+                .column("jti", &jti)
+                .on_conflict_do_nothing(&[])
+                .build()
+                .unwrap(),
+            "INSERT INTO sessions (jti) VALUES ($1) ON CONFLICT DO NOTHING"
+        );
+    }
+
+    #[test]
+    fn test_insert_builder_rejects_incomplete_upsert() {
+        let jti = "abc";
+        let insert = || InsertBuilder::new().into("sessions").column("jti", &jti);
+
+        assert!(
+            insert()
+                .on_conflict_do_update(&[], &["jti"])
+                .build()
+                .is_err()
+        );
+        assert!(
+            insert()
+                .on_conflict_do_update(&["jti"], &[])
+                .build()
+                .is_err()
+        );
+        assert!(insert().rows(0).build().is_err());
+    }
+
     #[test]
     fn test_update_builder() {
         let name = Some("new_name");
@@ -505,6 +889,38 @@ mod tests {
         assert_eq!(query, "UPDATE products SET price = $1 WHERE id = $2");
     }
 
+    #[test]
+    fn test_insert_builder_returning_defaults_to_all() {
+        let username = "alice";
+        let query = InsertBuilder::new()
+            .into("users")
+            .column("username", &username)
+            .build_returning()
+            .unwrap();
+
+        assert_eq!(
+            query,
+            "INSERT INTO users (username) VALUES ($1) RETURNING *"
+        );
+    }
+
+    #[test]
+    fn test_builders_returning_keep_chosen_columns() {
+        let status = Some("active");
+        let query = UpdateBuilder::new()
+            .table("users")
+            .set("status", &status)
+            .where_param("username", &"alice")
+            .returning("id")
+            .build_returning()
+            .unwrap();
+
+        assert_eq!(
+            query,
+            "UPDATE users SET status = $1 WHERE username = $2 RETURNING id"
+        );
+    }
+
     #[test]
     fn test_delete_builder() {
         let id = 1;
@@ -516,4 +932,12 @@ mod tests {
 
         assert_eq!(query, "DELETE FROM products WHERE id = $1");
     }
+
+    #[test]
+    fn test_escape_like_quotes_wildcards() {
+        assert_eq!(escape_like("john_doe"), "john\\_doe");
+        assert_eq!(escape_like("100%"), "100\\%");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+        assert_eq!(escape_like("alice"), "alice");
+    }
 }
@@ -1,12 +1,18 @@
 use crate::{
-    app::AppError, auth::dto::ServiceHealth, config::CircuitBreaker, utils::check_redis_health,
+    app::{AppError, deadline::check_deadline},
+    auth::dto::{HealthStatus, ServiceHealth},
+    config::CircuitBreaker,
+    utils::{check_redis_health, redis::shard::RedisShards},
 };
 use redis::aio::ConnectionManager;
 use std::sync::Arc;
 
+const BACKEND: &str = "redis";
+
 pub struct BaseRedisRepository {
     connection_manager: ConnectionManager,
     circuit_breaker: Arc<CircuitBreaker>,
+    shards: Option<Arc<RedisShards>>,
 }
 
 impl BaseRedisRepository {
@@ -17,15 +23,23 @@ impl BaseRedisRepository {
         Self {
             connection_manager,
             circuit_breaker,
+            shards: None,
         }
     }
 
+    /// Routes `execute_on_shard` calls across `shards` instead of the primary.
+    pub fn with_shards(mut self, shards: Option<Arc<RedisShards>>) -> Self {
+        self.shards = shards;
+        self
+    }
+
     pub async fn execute_with_circuit_breaker<F, Fut, T>(&self, operation: F) -> Result<T, AppError>
     where
         F: FnOnce(ConnectionManager) -> Fut + Send,
         Fut: std::future::Future<Output = Result<T, AppError>> + Send,
         T: Send,
     {
+        check_deadline(BACKEND)?;
         let conn = self.connection_manager.clone();
         let circuit_breaker = self.circuit_breaker.clone();
 
@@ -34,20 +48,104 @@ impl BaseRedisRepository {
             .await
     }
 
+    /// Runs `operation` on the shard owning `key`, behind that shard's own
+    /// breaker. Without shards this is `execute_with_circuit_breaker`.
+    pub async fn execute_on_shard<F, Fut, T>(&self, key: &str, operation: F) -> Result<T, AppError>
+    where
+        F: FnOnce(ConnectionManager) -> Fut + Send,
+        Fut: std::future::Future<Output = Result<T, AppError>> + Send,
+        T: Send,
+    {
+        let Some(shards) = &self.shards else {
+            return self.execute_with_circuit_breaker(operation).await;
+        };
+
+        check_deadline(BACKEND)?;
+        let shard = shards.locate(key);
+        let conn = shard.connection_manager.clone();
+
+        shard
+            .circuit_breaker
+            .call(|| async move { operation(conn).await })
+            .await
+    }
+
+    /// Runs `operation` on the shard that owned `key` before the ring last
+    /// changed. `None` when no migration is under way or the owner is the
+    /// same.
+    pub async fn execute_on_previous_shard<F, Fut, T>(
+        &self,
+        key: &str,
+        operation: F,
+    ) -> Result<Option<T>, AppError>
+    where
+        F: FnOnce(ConnectionManager) -> Fut + Send,
+        Fut: std::future::Future<Output = Result<T, AppError>> + Send,
+        T: Send,
+    {
+        let Some(shard) = self
+            .shards
+            .as_ref()
+            .and_then(|shards| shards.locate_previous(key))
+        else {
+            return Ok(None);
+        };
+
+        check_deadline(BACKEND)?;
+        let conn = shard.connection_manager.clone();
+
+        shard
+            .circuit_breaker
+            .call(|| async move { operation(conn).await })
+            .await
+            .map(Some)
+    }
+
     pub async fn check_redis_health(&self) -> ServiceHealth {
-        let conn = self.connection_manager.clone();
-        let circuit_breaker = self.circuit_breaker.clone();
+        let mut health = ping(
+            self.connection_manager.clone(),
+            self.circuit_breaker.clone(),
+        )
+        .await;
 
-        check_redis_health(|| async move {
-            circuit_breaker
-                .call(|| async move {
-                    let mut conn = conn.clone();
-                    use redis::AsyncCommands;
-                    let _: String = conn.ping().await?;
-                    Ok(())
-                })
-                .await
-        })
-        .await
+        let Some(shards) = &self.shards else {
+            return health;
+        };
+
+        for shard in shards.iter() {
+            let shard_health = ping(
+                shard.connection_manager.clone(),
+                shard.circuit_breaker.clone(),
+            )
+            .await;
+
+            health.response_time_ms = match (health.response_time_ms, shard_health.response_time_ms)
+            {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                _ => None,
+            };
+            if shard_health.status != HealthStatus::Healthy
+                && health.status == HealthStatus::Healthy
+            {
+                health.status = shard_health.status;
+                health.message = format!("Shard {}: {}", shard.name, shard_health.message);
+            }
+        }
+
+        health
     }
 }
+
+async fn ping(conn: ConnectionManager, circuit_breaker: Arc<CircuitBreaker>) -> ServiceHealth {
+    check_redis_health(|| async move {
+        circuit_breaker
+            .call(|| async move {
+                let mut conn = conn.clone();
+                use redis::AsyncCommands;
+                let _: String = conn.ping().await?;
+                Ok(())
+            })
+            .await
+    })
+    .await
+}
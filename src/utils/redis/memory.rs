@@ -0,0 +1,134 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use redis::aio::ConnectionManager;
+
+use crate::{
+    app::{AppError, middleware::metrics},
+    config::{CircuitBreaker, RedisMemoryConfig},
+    track_redis_operation,
+    utils::BaseRedisRepository,
+};
+
+/// Shared flag telling Redis users to shed optional writes, so `maxmemory`
+/// eviction does not reach security-critical keys like the token blacklist.
+#[derive(Debug, Default)]
+pub struct MemoryPressure {
+    high: AtomicBool,
+}
+
+impl MemoryPressure {
+    pub fn is_high(&self) -> bool {
+        self.high.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether the state changed.
+    pub fn set(&self, high: bool) -> bool {
+        self.high.swap(high, Ordering::Relaxed) != high
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub used: u64,
+    pub max: u64,
+}
+
+impl MemoryUsage {
+    /// Parses the reply of `INFO memory`. `maxmemory` is 0 when unlimited.
+    pub fn parse(info: &str) -> Option<Self> {
+        let field = |name: &str| {
+            info.lines()
+                .filter_map(|line| line.trim().split_once(':'))
+                .find(|(key, _)| *key == name)
+                .and_then(|(_, value)| value.parse().ok())
+        };
+
+        Some(Self {
+            used: field("used_memory")?,
+            max: field("maxmemory").unwrap_or(0),
+        })
+    }
+
+    pub fn is_high(&self, config: &RedisMemoryConfig) -> bool {
+        config
+            .threshold(self.max)
+            .is_some_and(|threshold| self.used >= threshold)
+    }
+}
+
+pub struct MemoryMonitor {
+    base: BaseRedisRepository,
+    config: RedisMemoryConfig,
+    pressure: Arc<MemoryPressure>,
+}
+
+impl MemoryMonitor {
+    pub fn new(
+        conn_manager: ConnectionManager,
+        circuit_breaker: Arc<CircuitBreaker>,
+        config: RedisMemoryConfig,
+        pressure: Arc<MemoryPressure>,
+    ) -> Self {
+        Self {
+            base: BaseRedisRepository::new(conn_manager, circuit_breaker),
+            config,
+            pressure,
+        }
+    }
+
+    pub fn spawn(self) {
+        let Some(interval) = self.config.check_interval else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.check().await {
+                    tracing::debug!("Redis memory check failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// A failed check keeps the last known state rather than guessing.
+    async fn check(&self) -> Result<(), AppError> {
+        let info = self
+            .base
+            .execute_with_circuit_breaker(|mut conn| async move {
+                let info: String = track_redis_operation!(
+                    "info",
+                    redis::cmd("INFO")
+                        .arg("memory")
+                        .query_async(&mut conn)
+                        .await
+                )?;
+                Ok(info)
+            })
+            .await?;
+
+        let usage = MemoryUsage::parse(&info).ok_or_else(|| {
+            AppError::InternalServer(String::from("Unexpected INFO memory reply"))
+        })?;
+        let high = usage.is_high(&self.config);
+
+        metrics::track_redis_memory(usage.used, high);
+        if self.pressure.set(high) {
+            if high {
+                tracing::warn!(
+                    used = usage.used,
+                    max = usage.max,
+                    "Redis memory pressure, shedding non-essential writes"
+                );
+            } else {
+                tracing::info!(used = usage.used, "Redis memory pressure cleared");
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -4,7 +4,24 @@ macro_rules! track_redis_operation {
         let _start = std::time::Instant::now();
         let _op = $operation;
 
-        let result = $body;
+        // A child span per round trip, so exported traces show where the
+        // request spent its time.
+        let _span = tracing::info_span!(
+            "redis.command",
+            otel.kind = "client",
+            otel.status_code = tracing::field::Empty,
+            db.system.name = "redis",
+            db.operation.name = _op,
+        );
+        let result = tracing::Instrument::instrument(
+            async {
+                #[allow(unused_braces)]
+                let result = $body;
+                result
+            },
+            _span.clone(),
+        )
+        .await;
 
         let duration = _start.elapsed().as_secs_f64();
         $crate::app::middleware::metrics::track_redis_operation(_op, duration);
@@ -12,6 +29,7 @@ macro_rules! track_redis_operation {
         match &result {
             Ok(_) => {}
             Err(_) => {
+                _span.record("otel.status_code", "ERROR");
                 $crate::app::middleware::metrics::track_redis_error(_op, "operation_failed");
             }
         }
@@ -54,3 +72,31 @@ macro_rules! redis_delete {
         $crate::track_redis_operation!("delete", $body)
     };
 }
+
+#[macro_export]
+macro_rules! redis_pipeline {
+    ($body:expr) => {
+        $crate::track_redis_operation!("pipeline", $body)
+    };
+}
+
+#[macro_export]
+macro_rules! redis_stream_write {
+    ($body:expr) => {
+        $crate::track_redis_operation!("stream_write", $body)
+    };
+}
+
+#[macro_export]
+macro_rules! redis_stream_read {
+    ($body:expr) => {
+        $crate::track_redis_operation!("stream_read", $body)
+    };
+}
+
+#[macro_export]
+macro_rules! redis_publish {
+    ($body:expr) => {
+        $crate::track_redis_operation!("publish", $body)
+    };
+}
@@ -1,4 +1,8 @@
 mod base;
+pub(crate) mod memory;
 mod metrics;
+pub(crate) mod shard;
 
 pub(crate) use base::BaseRedisRepository;
+pub(crate) use memory::{MemoryMonitor, MemoryPressure};
+pub(crate) use shard::{RedisShard, RedisShards};
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::CircuitBreaker;
+
+// Enough points per shard to keep the key spread within a few percent.
+const VIRTUAL_NODES: usize = 160;
+
+/// The ring last started with, in the primary Redis, as a `RingRecord`.
+pub const RING_KEY: &str = "redis:shard_ring";
+
+/// Consistent hash ring over shard names. Adding or removing a shard only
+/// remaps the keys that land on it, about 1/N of the total.
+#[derive(Debug)]
+pub struct HashRing {
+    points: Vec<(u64, usize)>,
+}
+
+impl HashRing {
+    pub fn new<S: AsRef<str>>(nodes: &[S]) -> Self {
+        let mut points: Vec<(u64, usize)> = nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(index, node)| {
+                (0..VIRTUAL_NODES)
+                    .map(move |vnode| (hash(&format!("{}#{}", node.as_ref(), vnode)), index))
+            })
+            .collect();
+        points.sort_unstable();
+
+        Self { points }
+    }
+
+    /// Index of the node owning `key`: the first point clockwise from its hash.
+    pub fn locate(&self, key: &str) -> usize {
+        let hash = hash(key);
+        let position = self.points.partition_point(|(point, _)| *point < hash);
+        self.points[position % self.points.len()].1
+    }
+}
+
+fn hash(value: &str) -> u64 {
+    let digest = Sha256::digest(value.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
+}
+
+pub struct RedisShard {
+    pub name: Box<str>,
+    pub connection_manager: ConnectionManager,
+    pub circuit_breaker: Arc<CircuitBreaker>,
+}
+
+/// A ring over some of the shards: `members[i]` is the shard of ring node `i`.
+struct Placement {
+    ring: HashRing,
+    members: Vec<usize>,
+}
+
+impl Placement {
+    fn new(shards: &[RedisShard], names: &[Box<str>]) -> Self {
+        let members = names
+            .iter()
+            .map(|name| {
+                shards
+                    .iter()
+                    .position(|shard| shard.name == *name)
+                    .unwrap_or_else(|| panic!("No connection to Redis shard {}", name))
+            })
+            .collect();
+        Self {
+            ring: HashRing::new(names),
+            members,
+        }
+    }
+
+    fn locate(&self, key: &str) -> usize {
+        self.members[self.ring.locate(key)]
+    }
+}
+
+/// Redis endpoints sharing one keyspace. Shards are placed on the ring by
+/// name, so reordering `REDIS_SHARDS` does not move any key. While
+/// `REDIS_SHARDS_PREVIOUS` is set, the ring before the last change is kept
+/// too, so entries written under it can still be read from their old owner.
+pub struct RedisShards {
+    current: Placement,
+    previous: Option<Placement>,
+    shards: Vec<RedisShard>,
+}
+
+impl RedisShards {
+    /// `shards` holds a connection for every name in `ring` and `previous`.
+    pub fn new(shards: Vec<RedisShard>, ring: &[Box<str>], previous: Option<&[Box<str>]>) -> Self {
+        assert!(!ring.is_empty(), "At least one Redis shard is required");
+
+        Self {
+            current: Placement::new(&shards, ring),
+            previous: previous.map(|names| Placement::new(&shards, names)),
+            shards,
+        }
+    }
+
+    pub fn locate(&self, key: &str) -> &RedisShard {
+        &self.shards[self.current.locate(key)]
+    }
+
+    /// The shard that owned `key` before the ring changed, when that is
+    /// another one than `locate` returns.
+    pub fn locate_previous(&self, key: &str) -> Option<&RedisShard> {
+        let previous = self.previous.as_ref()?.locate(key);
+        (previous != self.current.locate(key)).then(|| &self.shards[previous])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RedisShard> {
+        self.shards.iter()
+    }
+
+    pub fn circuit_breakers(&self) -> Vec<Arc<CircuitBreaker>> {
+        self.shards
+            .iter()
+            .map(|shard| Arc::clone(&shard.circuit_breaker))
+            .collect()
+    }
+}
+
+/// The ring the servers last started with, kept in the primary Redis so a
+/// ring change without a migration is noticed. Names are sorted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RingRecord {
+    pub shards: Vec<Box<str>>,
+    /// The ring before the last change, while its entries may be live.
+    pub previous: Option<Vec<Box<str>>>,
+    /// When `shards` became the ring, in seconds.
+    pub changed_at: i64,
+}
+
+/// Compares the configured rings with the recorded one. Returns the record
+/// to store, if it changes, or why the server must not start: a blacklist
+/// entry left behind on a shard that no longer owns it would let its
+/// revoked token refresh again. `retention_secs` is how long an entry can
+/// live, so how long the previous ring must still be read.
+pub fn plan_ring(
+    recorded: Option<&RingRecord>,
+    ring: &[Box<str>],
+    previous: Option<&[Box<str>]>,
+    now: i64,
+    retention_secs: i64,
+) -> Result<Option<RingRecord>, String> {
+    let ring = sorted(ring);
+    let previous = previous.map(sorted);
+
+    let Some(recorded) = recorded else {
+        return Ok(Some(RingRecord {
+            shards: ring,
+            previous,
+            changed_at: now,
+        }));
+    };
+    let expires = recorded.changed_at + retention_secs;
+
+    if recorded.shards != ring {
+        if previous.as_ref() != Some(&recorded.shards) {
+            let recorded = recorded.shards.join(",");
+            return Err(format!(
+                "Redis shards changed from {} without a migration: set REDIS_SHARDS_PREVIOUS={} for {}s after the change",
+                recorded, recorded, retention_secs
+            ));
+        }
+        if recorded.previous.is_some() && now < expires {
+            return Err(format!(
+                "Redis shards are still migrating from {}: keep REDIS_SHARDS={} until {}",
+                recorded.previous.as_deref().unwrap_or_default().join(","),
+                recorded.shards.join(","),
+                timestamp(expires)
+            ));
+        }
+        return Ok(Some(RingRecord {
+            shards: ring,
+            previous,
+            changed_at: now,
+        }));
+    }
+
+    if recorded.previous == previous {
+        return Ok(None);
+    }
+    match (&recorded.previous, previous) {
+        (Some(_), None) if now >= expires => Ok(Some(RingRecord {
+            previous: None,
+            ..recorded.clone()
+        })),
+        (Some(recorded_previous), None) => Err(format!(
+            "Blacklist entries on {} may still be live: keep REDIS_SHARDS_PREVIOUS set until {}",
+            recorded_previous.join(","),
+            timestamp(expires)
+        )),
+        (None, None) => Ok(None),
+        (_, Some(previous)) => Err(format!(
+            "REDIS_SHARDS_PREVIOUS={} is not the ring the shards changed from",
+            previous.join(",")
+        )),
+    }
+}
+
+fn sorted(names: &[Box<str>]) -> Vec<Box<str>> {
+    let mut names = names.to_vec();
+    names.sort_unstable();
+    names
+}
+
+fn timestamp(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0).map_or_else(|| secs.to_string(), |at| at.to_rfc3339())
+}
@@ -1,13 +1,9 @@
 use super::super::cookie::*;
-use crate::config::origin::OriginConfig;
+use crate::config::{CookieConfig, origin::OriginConfig};
 use axum_extra::extract::cookie::SameSite;
 
 fn create_test_origin_config(frontend_url: &str, backend_domain: &str) -> OriginConfig {
-    OriginConfig {
-        frontend_origin: frontend_url.into(),
-        frontend_url: url::Url::parse(frontend_url).unwrap(),
-        backend_domain: backend_domain.into(),
-    }
+    OriginConfig::parse(frontend_url, backend_domain)
 }
 
 #[test]
@@ -122,10 +118,10 @@ fn test_get_base_domain_three_level_subdomain() {
 #[test]
 fn test_cookie_service_new_https_production() {
     let origin_config = create_test_origin_config("https://app.example.com", "api.example.com");
-    let cookie_service = CookieService::new(&origin_config);
+    let cookie_service = CookieService::new(&origin_config, &CookieConfig::default());
 
-    assert!(cookie_service.secure);
-    assert_eq!(cookie_service.same_site, SameSite::Strict);
+    assert!(cookie_service.site(None).secure);
+    assert_eq!(cookie_service.site(None).same_site, SameSite::Strict);
     assert_eq!(cookie_service.path, "/auth");
     assert!(cookie_service.http_only);
 }
@@ -133,56 +129,56 @@ fn test_cookie_service_new_https_production() {
 #[test]
 fn test_cookie_service_new_http_localhost() {
     let origin_config = create_test_origin_config("http://localhost:3000", "localhost");
-    let cookie_service = CookieService::new(&origin_config);
+    let cookie_service = CookieService::new(&origin_config, &CookieConfig::default());
 
-    assert!(!cookie_service.secure);
-    assert_eq!(cookie_service.same_site, SameSite::Lax);
-    assert_eq!(cookie_service.domain, None);
+    assert!(!cookie_service.site(None).secure);
+    assert_eq!(cookie_service.site(None).same_site, SameSite::Lax);
+    assert_eq!(cookie_service.site(None).domain, None);
 }
 
 #[test]
 fn test_cookie_service_new_http_127() {
     let origin_config = create_test_origin_config("http://127.0.0.1:3000", "127.0.0.1");
-    let cookie_service = CookieService::new(&origin_config);
+    let cookie_service = CookieService::new(&origin_config, &CookieConfig::default());
 
-    assert!(!cookie_service.secure);
-    assert_eq!(cookie_service.domain, None);
+    assert!(!cookie_service.site(None).secure);
+    assert_eq!(cookie_service.site(None).domain, None);
 }
 
 #[test]
 fn test_determine_cookie_domain_localhost() {
     let origin_config = create_test_origin_config("http://localhost:3000", "localhost");
-    let domain = CookieService::determine_cookie_domain(&origin_config, true);
+    let domain = CookieService::determine_cookie_domain(&origin_config.relying_parties[0], true);
     assert_eq!(domain, None);
 }
 
 #[test]
 fn test_determine_cookie_domain_subdomains() {
     let origin_config = create_test_origin_config("https://app.example.com", "api.example.com");
-    let domain = CookieService::determine_cookie_domain(&origin_config, false);
+    let domain = CookieService::determine_cookie_domain(&origin_config.relying_parties[0], false);
     assert_eq!(domain, Some(".example.com".to_string()));
 }
 
 #[test]
 fn test_determine_cookie_domain_different_domains() {
     let origin_config = create_test_origin_config("https://app.example.com", "different.com");
-    let domain = CookieService::determine_cookie_domain(&origin_config, false);
+    let domain = CookieService::determine_cookie_domain(&origin_config.relying_parties[0], false);
     assert_eq!(domain, None);
 }
 
 #[test]
 fn test_determine_cookie_domain_same_domain() {
     let origin_config = create_test_origin_config("https://example.com", "example.com");
-    let domain = CookieService::determine_cookie_domain(&origin_config, false);
+    let domain = CookieService::determine_cookie_domain(&origin_config.relying_parties[0], false);
     assert_eq!(domain, None);
 }
 
 #[test]
 fn test_create_refresh_token_cookie() {
     let origin_config = create_test_origin_config("https://app.example.com", "api.example.com");
-    let cookie_service = CookieService::new(&origin_config);
+    let cookie_service = CookieService::new(&origin_config, &CookieConfig::default());
 
-    let cookie = cookie_service.create_refresh_token_cookie("test_token_value");
+    let cookie = cookie_service.create_refresh_token_cookie("test_token_value", false);
 
     assert_eq!(cookie.name(), "refresh_token");
     assert_eq!(cookie.value(), "test_token_value");
@@ -195,7 +191,7 @@ fn test_create_refresh_token_cookie() {
 #[test]
 fn test_clear_refresh_token_cookie() {
     let origin_config = create_test_origin_config("https://app.example.com", "api.example.com");
-    let cookie_service = CookieService::new(&origin_config);
+    let cookie_service = CookieService::new(&origin_config, &CookieConfig::default());
 
     let cookie = cookie_service.clear_refresh_token_cookie();
 
@@ -203,3 +199,52 @@ fn test_clear_refresh_token_cookie() {
     assert_eq!(cookie.value(), "");
     assert!(cookie.max_age().is_some());
 }
+
+#[test]
+fn test_cookie_settings_from_config() {
+    let origin_config = create_test_origin_config("https://app.example.com", "api.example.com");
+    let cookie_config = CookieConfig {
+        path: String::from("/api/auth"),
+        max_age: std::time::Duration::from_secs(3600),
+        trusted_max_age: std::time::Duration::from_secs(7200),
+    };
+    let cookie_service = CookieService::new(&origin_config, &cookie_config);
+
+    let cookie = cookie_service.create_refresh_token_cookie("test_token_value", false);
+
+    assert_eq!(cookie.path(), Some("/api/auth"));
+    assert_eq!(cookie.max_age(), Some(time::Duration::hours(1)));
+}
+
+#[test]
+fn test_trusted_cookie_uses_trusted_max_age() {
+    let origin_config = create_test_origin_config("https://app.example.com", "api.example.com");
+    let cookie_config = CookieConfig {
+        path: String::from("/auth"),
+        max_age: std::time::Duration::from_secs(3600),
+        trusted_max_age: std::time::Duration::from_secs(7200),
+    };
+    let cookie_service = CookieService::new(&origin_config, &cookie_config);
+
+    let cookie = cookie_service.create_refresh_token_cookie("test_token_value", true);
+
+    assert_eq!(cookie.max_age(), Some(time::Duration::hours(2)));
+}
+
+#[test]
+fn test_cookie_site_follows_the_origin() {
+    let origin_config = OriginConfig::parse(
+        "https://app.example.com,http://localhost:3000=localhost",
+        "api.example.com",
+    );
+    let cookie_service = CookieService::new(&origin_config, &CookieConfig::default());
+
+    let local = cookie_service.site(Some("http://localhost:3000"));
+    assert!(!local.secure);
+    assert_eq!(local.same_site, SameSite::Lax);
+    assert_eq!(local.domain, None);
+
+    let unknown = cookie_service.site(Some("https://other.example"));
+    assert!(unknown.secure);
+    assert_eq!(unknown.domain, Some(".example.com".to_string()));
+}
@@ -0,0 +1,45 @@
+use crate::utils::crypto::{Cipher, ed25519_public_key, random_bytes};
+
+#[test]
+fn test_ed25519_public_key_matches_rfc8032() {
+    // Test 1 of RFC 8032, section 7.1.
+    let seed = hex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60");
+    let public = hex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+
+    assert_eq!(
+        ed25519_public_key(&seed.try_into().unwrap()).as_slice(),
+        public
+    );
+}
+
+#[test]
+fn test_cipher_needs_32_byte_key() {
+    assert!(Cipher::new(&[7; 32]).is_some());
+    assert!(Cipher::new(&[7; 16]).is_none());
+}
+
+#[test]
+fn test_cipher_rejects_truncated_input() {
+    let cipher = Cipher::new(&[7; 32]).unwrap();
+    let sealed = cipher.seal(b"state", b"login").unwrap();
+
+    assert_eq!(cipher.open(&sealed, b"login").unwrap(), b"state");
+    assert!(cipher.open(&sealed[..sealed.len() - 1], b"login").is_none());
+    assert!(cipher.open(&sealed[..8], b"login").is_none());
+}
+
+#[test]
+fn test_random_bytes_differ_between_calls() {
+    let first: [u8; 32] = random_bytes();
+    let second: [u8; 32] = random_bytes();
+
+    assert_ne!(first, second);
+    assert_ne!(first, [0; 32]);
+}
+
+fn hex(value: &str) -> Vec<u8> {
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).unwrap())
+        .collect()
+}
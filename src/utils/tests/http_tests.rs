@@ -0,0 +1,129 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{Router, http::StatusCode, routing::any};
+use url::Url;
+
+use crate::{
+    app::AppError,
+    config::{CircuitBreakerConfig, HttpClientConfig},
+    utils::http::{HttpClientService, RetryBudget, destination},
+};
+
+fn config(max_retries: u32) -> HttpClientConfig {
+    HttpClientConfig {
+        timeout: Duration::from_secs(5),
+        connect_timeout: Duration::from_secs(1),
+        proxy: None,
+        no_proxy: None,
+        max_retries,
+        retry_budget_percent: 20,
+        circuit_breaker: CircuitBreakerConfig::default().with_failure_threshold(1),
+        ssrf_allowed_hosts: Vec::new(),
+    }
+}
+
+/// Serves `failures` 503 responses, then 200, counting every hit.
+async fn flaky_server(failures: usize) -> (String, Arc<AtomicUsize>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&hits);
+    let app = Router::new().route(
+        "/",
+        any(move || {
+            let counter = Arc::clone(&counter);
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::OK
+                }
+            }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (url, hits)
+}
+
+#[test]
+fn test_retry_budget_spends_reserve_then_deposits() {
+    let budget = RetryBudget::new(50);
+    for _ in 0..10 {
+        assert!(budget.try_withdraw());
+    }
+    assert!(!budget.try_withdraw());
+
+    budget.deposit();
+    assert!(!budget.try_withdraw());
+    budget.deposit();
+    assert!(budget.try_withdraw());
+}
+
+#[test]
+fn test_retry_budget_caps_balance_at_reserve() {
+    let budget = RetryBudget::new(100);
+    for _ in 0..100 {
+        budget.deposit();
+    }
+
+    let allowed = (0..20).filter(|_| budget.try_withdraw()).count();
+    assert_eq!(allowed, 10);
+}
+
+#[test]
+fn test_destination_keeps_non_default_port() {
+    let parse = |url: &str| destination(&Url::parse(url).unwrap());
+
+    assert_eq!(
+        parse("https://hooks.example.com/a?b=c"),
+        "hooks.example.com"
+    );
+    assert_eq!(
+        parse("https://hooks.example.com:443/a"),
+        "hooks.example.com"
+    );
+    assert_eq!(parse("http://127.0.0.1:8080/"), "127.0.0.1:8080");
+}
+
+#[tokio::test]
+async fn test_idempotent_request_retried_after_server_error() {
+    let (url, hits) = flaky_server(2).await;
+    let http = HttpClientService::new(config(2));
+
+    let response = http.execute(http.client().get(&url)).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_post_not_retried_after_server_error() {
+    let (url, hits) = flaky_server(1).await;
+    let http = HttpClientService::new(config(2));
+
+    let result = http.execute(http.client().post(&url).body("{}")).await;
+
+    assert!(matches!(result, Err(AppError::ServiceUnavailable(_))));
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_breaker_opens_per_destination() {
+    let (failing, failing_hits) = flaky_server(usize::MAX).await;
+    let (healthy, _) = flaky_server(0).await;
+    let http = HttpClientService::new(config(0));
+
+    assert!(http.execute(http.client().get(&failing)).await.is_err());
+    let rejected = http.execute(http.client().get(&failing)).await;
+
+    assert!(matches!(rejected, Err(AppError::CircuitBreakerOpen(_))));
+    assert_eq!(failing_hits.load(Ordering::SeqCst), 1);
+    assert!(http.execute(http.client().get(&healthy)).await.is_ok());
+}
@@ -0,0 +1,46 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::utils::PgListener;
+
+fn listener() -> PgListener {
+    PgListener::new(tokio_postgres::Config::new(), None, Duration::from_secs(1))
+}
+
+#[test]
+fn test_dispatch_runs_every_handler_of_the_channel() {
+    let mut listener = listener();
+    let payloads = Arc::new(Mutex::new(Vec::new()));
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let seen = Arc::clone(&payloads);
+    listener.on("credential_revoked", move |payload| {
+        seen.lock().unwrap().push(payload.to_owned())
+    });
+    let counter = Arc::clone(&calls);
+    listener.on("credential_revoked", move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+
+    assert_eq!(listener.dispatch("credential_revoked", "jti-1"), 2);
+    assert_eq!(*payloads.lock().unwrap(), vec!["jti-1"]);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_dispatch_ignores_channels_without_handlers() {
+    let mut listener = listener();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    listener.on("prepared_cache_flush", move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+
+    assert_eq!(listener.dispatch("other", ""), 0);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
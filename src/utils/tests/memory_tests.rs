@@ -0,0 +1,47 @@
+use crate::{config::RedisMemoryConfig, utils::redis::memory::MemoryUsage};
+
+const INFO: &str = "# Memory\r\nused_memory:900\r\nused_memory_human:900B\r\nmaxmemory:1000\r\nmaxmemory_policy:volatile-lru\r\n";
+
+fn config(threshold_percent: u8, threshold_bytes: Option<u64>) -> RedisMemoryConfig {
+    RedisMemoryConfig {
+        check_interval: None,
+        threshold_percent,
+        threshold_bytes,
+    }
+}
+
+#[test]
+fn test_parse_memory_info() {
+    let usage = MemoryUsage::parse(INFO).unwrap();
+
+    assert_eq!(usage.used, 900);
+    assert_eq!(usage.max, 1000);
+}
+
+#[test]
+fn test_parse_rejects_missing_used_memory() {
+    assert!(MemoryUsage::parse("# Memory\r\nmaxmemory:1000\r\n").is_none());
+}
+
+#[test]
+fn test_percent_threshold_uses_maxmemory() {
+    let usage = MemoryUsage::parse(INFO).unwrap();
+
+    assert!(usage.is_high(&config(85, None)));
+    assert!(!usage.is_high(&config(95, None)));
+}
+
+#[test]
+fn test_byte_threshold_overrides_percent() {
+    let usage = MemoryUsage::parse(INFO).unwrap();
+
+    assert!(!usage.is_high(&config(50, Some(2000))));
+}
+
+#[test]
+fn test_unlimited_redis_needs_byte_threshold() {
+    let usage = MemoryUsage { used: 5000, max: 0 };
+
+    assert!(!usage.is_high(&config(85, None)));
+    assert!(usage.is_high(&config(85, Some(4096))));
+}
@@ -0,0 +1,71 @@
+use crate::utils::postgres::{MIGRATIONS, Migration, MigrationStep, plan_migrations};
+
+fn recorded(migration: &Migration) -> (i32, String) {
+    (migration.version, migration.checksum())
+}
+
+#[test]
+fn test_every_migration_file_is_embedded() {
+    let mut files: Vec<String> =
+        std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter_map(|name| name.strip_suffix(".sql").map(str::to_string))
+            // V0 creates the application role, not a table.
+            .filter(|name| !name.starts_with("V0__"))
+            .collect();
+    // By version, not lexically: V10 follows V9.
+    files.sort_by_key(|name| name[1..name.find("__").unwrap()].parse::<i32>().unwrap());
+
+    let embedded: Vec<&str> = MIGRATIONS.iter().map(|migration| migration.name).collect();
+    assert_eq!(files, embedded);
+}
+
+#[test]
+fn test_versions_match_names_and_increase() {
+    for (index, migration) in MIGRATIONS.iter().enumerate() {
+        assert!(
+            migration
+                .name
+                .starts_with(&format!("V{}__", migration.version))
+        );
+        assert_eq!(migration.version, index as i32 + 1);
+    }
+}
+
+#[test]
+fn test_fresh_database_runs_everything() {
+    let steps = plan_migrations(MIGRATIONS, &[], &[]).unwrap();
+
+    assert!(steps.iter().all(|step| *step == MigrationStep::Run));
+}
+
+#[test]
+fn test_recorded_migrations_are_skipped() {
+    let applied = vec![recorded(&MIGRATIONS[0]), recorded(&MIGRATIONS[1])];
+
+    let steps = plan_migrations(MIGRATIONS, &applied, &[]).unwrap();
+
+    assert_eq!(steps[..2], [MigrationStep::Applied, MigrationStep::Applied]);
+    assert_eq!(steps[2], MigrationStep::Run);
+}
+
+#[test]
+fn test_existing_tables_are_baselined() {
+    let tables = vec![String::from("users"), String::from("webauthn_sessions")];
+
+    let steps = plan_migrations(MIGRATIONS, &[], &tables).unwrap();
+
+    assert_eq!(
+        steps[..2],
+        [MigrationStep::Baseline, MigrationStep::Baseline]
+    );
+    assert_eq!(steps[2], MigrationStep::Run);
+}
+
+#[test]
+fn test_modified_migration_is_rejected() {
+    let applied = vec![(MIGRATIONS[0].version, String::from("0000"))];
+
+    assert!(plan_migrations(MIGRATIONS, &applied, &[]).is_err());
+}
@@ -1,4 +1,22 @@
 #[cfg(test)]
 mod cookie_tests;
 #[cfg(test)]
+mod crypto_tests;
+#[cfg(all(test, feature = "http-client"))]
+mod http_tests;
+#[cfg(test)]
+mod listener_tests;
+#[cfg(test)]
+mod memory_tests;
+#[cfg(test)]
+mod migration_tests;
+#[cfg(test)]
+mod prepared_cache_tests;
+#[cfg(test)]
+mod query_audit_tests;
+#[cfg(test)]
+mod shard_tests;
+#[cfg(all(test, feature = "http-client"))]
+mod ssrf_tests;
+#[cfg(test)]
 mod validation_tests;
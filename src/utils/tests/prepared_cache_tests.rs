@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+use crate::utils::postgres::StatementLru;
+
+const TTL: Duration = Duration::from_secs(60);
+
+#[test]
+fn test_full_cache_evicts_least_recently_used() {
+    let now = Instant::now();
+    let mut cache = StatementLru::new(2, None);
+
+    cache.insert("a", 1, now);
+    cache.insert("b", 2, now);
+    cache.get(&"a", now);
+    cache.insert("c", 3, now);
+
+    assert_eq!(cache.get(&"a", now), Some(1));
+    assert_eq!(cache.get(&"b", now), None);
+    assert_eq!(cache.get(&"c", now), Some(3));
+}
+
+#[test]
+fn test_replacing_entry_does_not_evict() {
+    let now = Instant::now();
+    let mut cache = StatementLru::new(2, None);
+
+    cache.insert("a", 1, now);
+    cache.insert("b", 2, now);
+    cache.insert("a", 10, now);
+
+    assert_eq!(cache.get(&"a", now), Some(10));
+    assert_eq!(cache.get(&"b", now), Some(2));
+}
+
+#[test]
+fn test_expired_entries_are_not_returned() {
+    let now = Instant::now();
+    let mut cache = StatementLru::new(2, Some(TTL));
+
+    cache.insert("a", 1, now);
+
+    assert_eq!(cache.get(&"a", now + TTL - Duration::from_secs(1)), Some(1));
+    assert_eq!(cache.get(&"a", now + TTL), None);
+}
+
+#[test]
+fn test_expired_entries_make_room_first() {
+    let now = Instant::now();
+    let later = now + TTL;
+    let mut cache = StatementLru::new(2, Some(TTL));
+
+    cache.insert("old", 1, now);
+    cache.insert("recent", 2, later - Duration::from_secs(1));
+    cache.get(&"old", now);
+    cache.insert("new", 3, later);
+
+    assert_eq!(cache.get(&"recent", later), Some(2));
+    assert_eq!(cache.get(&"new", later), Some(3));
+}
+
+#[test]
+fn test_clear_reports_removed_entries() {
+    let now = Instant::now();
+    let mut cache = StatementLru::new(4, None);
+
+    cache.insert("a", 1, now);
+    cache.insert("b", 2, now);
+
+    assert!(cache.remove(&"a"));
+    assert!(!cache.remove(&"a"));
+    assert_eq!(cache.clear(), 1);
+    assert_eq!(cache.get(&"b", now), None);
+}
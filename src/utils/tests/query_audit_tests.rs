@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+
+use crate::utils::postgres::query_audit::{
+    Problem, Target, audit, call_sites_in, constants_in, placeholders, scan,
+};
+
+fn src_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src")
+}
+
+#[test]
+fn test_placeholders_ignore_strings_comments_and_dollar_quotes() {
+    let sql = "SELECT '$9', \"$8\", E'it\\'s $7' -- $6\n /* $5 /* $4 */ */ \
+               FROM t WHERE a = $1 AND b = $body$ $3 $body$ AND c = $2";
+
+    let found = placeholders(sql).unwrap();
+
+    assert_eq!(found.into_iter().collect::<Vec<_>>(), vec![1, 2]);
+}
+
+#[test]
+fn test_placeholders_reject_malformed_sql() {
+    assert!(placeholders("SELECT 'open FROM t").is_err());
+    assert!(placeholders("SELECT count(* FROM t").is_err());
+    assert!(placeholders("SELECT * FROM t WHERE a = $0").is_err());
+}
+
+#[test]
+fn test_audit_catches_drifted_parameters() {
+    let queries = Path::new("src/widgets/queries.rs");
+    let constants = constants_in(
+        queries,
+        r#"
+        pub mod widgets {
+            pub const INSERT: &str = "INSERT INTO widgets (id, name) VALUES ($1, $2)";
+            pub const GAPPED: &str = "SELECT * FROM widgets WHERE id = $1 AND name = $3";
+            pub const CACHE_KEY: &str = "widgets:";
+        }
+        "#,
+    );
+    let sites = call_sites_in(
+        Path::new("src/widgets/repo.rs"),
+        r#"
+        fn save(client: &Client, id: i32) {
+            db_select!("widgets", {
+                client.execute(queries::widgets::INSERT, &[&id]).await
+            });
+            client.query(queries::widgets::MISSING, &[]);
+        }
+        "#,
+    );
+
+    let problems = audit(&constants, &sites);
+
+    assert_eq!(problems.len(), 3, "{:?}", problems);
+    assert!(problems.iter().any(|p| matches!(
+        p,
+        Problem::PlaceholderGap { missing, .. } if missing == &vec![2]
+    )));
+    assert!(problems.iter().any(|p| matches!(
+        p,
+        Problem::ParamCount {
+            placeholders: 2,
+            params: 1,
+            ..
+        }
+    )));
+    assert!(
+        problems
+            .iter()
+            .any(|p| matches!(p, Problem::UnknownConstant { .. }))
+    );
+}
+
+#[test]
+fn test_audit_covers_local_constants_and_inline_sql() {
+    let file = Path::new("src/widgets/sync.rs");
+    let source = r#"
+        const LOCK: &str = "SELECT pg_advisory_xact_lock($1)";
+
+        impl Sync {
+            const CLAIM: &str = "UPDATE widgets SET owner = $1 WHERE id = $2";
+        }
+
+        fn sync(client: &Client, key: i64) {
+            client.execute(LOCK, &[&key, &key]);
+            client.execute(Self::CLAIM, &[&key, &key, &key]);
+            client.query("SELECT * FROM widgets WHERE id = $2", &[&key]);
+            client.query_one("SELECT count(* FROM widgets", &[]);
+            client.query(IMPORTED, &[]);
+        }
+        "#;
+    let constants = constants_in(file, source);
+    let sites = call_sites_in(file, source);
+
+    assert_eq!(constants.len(), 2);
+    assert!(constants.iter().all(|constant| constant.module == "sync"));
+
+    let problems = audit(&constants, &sites);
+
+    assert_eq!(problems.len(), 5, "{:?}", problems);
+    assert!(problems.iter().any(|p| matches!(
+        p,
+        Problem::ParamCount {
+            constant,
+            placeholders: 1,
+            params: 2,
+            ..
+        } if constant == "sync::LOCK"
+    )));
+    assert!(problems.iter().any(|p| matches!(
+        p,
+        Problem::ParamCount {
+            constant,
+            placeholders: 2,
+            params: 3,
+            ..
+        } if constant == "sync::CLAIM"
+    )));
+    assert!(problems.iter().any(|p| matches!(
+        p,
+        Problem::PlaceholderGap { missing, .. } if missing == &vec![1]
+    )));
+    assert!(problems.iter().any(|p| matches!(
+        p,
+        Problem::ParamCount {
+            placeholders: 2,
+            params: 1,
+            ..
+        }
+    )));
+    assert!(
+        problems
+            .iter()
+            .any(|p| matches!(p, Problem::Malformed { .. }))
+    );
+}
+
+#[test]
+fn test_query_constants_match_their_call_sites() {
+    let (constants, sites) = scan(&src_dir());
+
+    assert!(constants.iter().any(|constant| constant.is_sql()));
+    assert!(sites.iter().any(|site| site.params.is_some()));
+    assert!(
+        sites
+            .iter()
+            .any(|site| matches!(site.target, Target::Local(_)))
+    );
+    assert!(
+        sites
+            .iter()
+            .any(|site| matches!(site.target, Target::Inline(_)))
+    );
+
+    let problems = audit(&constants, &sites);
+    let report: Vec<String> = problems.iter().map(ToString::to_string).collect();
+    assert!(problems.is_empty(), "\n{}", report.join("\n"));
+}
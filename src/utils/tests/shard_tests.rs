@@ -0,0 +1,203 @@
+use crate::{
+    config::redis::parse_shards,
+    utils::redis::shard::{HashRing, RingRecord, plan_ring},
+};
+
+const SHARDS: [&str; 3] = ["redis-a:6379", "redis-b:6379", "redis-c:6379"];
+const KEYS: usize = 10_000;
+
+fn key(i: usize) -> String {
+    format!("blacklist:{}", i)
+}
+
+fn owners(ring: &HashRing, shards: &[&str]) -> Vec<String> {
+    (0..KEYS)
+        .map(|i| shards[ring.locate(&key(i))].to_string())
+        .collect()
+}
+
+#[test]
+fn test_single_shard_owns_every_key() {
+    let ring = HashRing::new(&["redis-a:6379"]);
+
+    assert!((0..100).all(|i| ring.locate(&key(i)) == 0));
+}
+
+#[test]
+fn test_keys_spread_across_shards() {
+    let ring = HashRing::new(&SHARDS);
+    let mut counts = [0usize; 3];
+
+    for i in 0..KEYS {
+        counts[ring.locate(&key(i))] += 1;
+    }
+
+    // An even split is 3333 each; virtual nodes keep every shard near it.
+    assert!(
+        counts.iter().all(|count| (2500..4200).contains(count)),
+        "{:?}",
+        counts
+    );
+}
+
+#[test]
+fn test_shard_order_does_not_move_keys() {
+    let reversed = [SHARDS[2], SHARDS[1], SHARDS[0]];
+
+    assert_eq!(
+        owners(&HashRing::new(&SHARDS), &SHARDS),
+        owners(&HashRing::new(&reversed), &reversed)
+    );
+}
+
+#[test]
+fn test_adding_shard_only_moves_keys_to_it() {
+    let grown = [SHARDS[0], SHARDS[1], SHARDS[2], "redis-d:6379"];
+
+    let before = owners(&HashRing::new(&SHARDS), &SHARDS);
+    let after = owners(&HashRing::new(&grown), &grown);
+
+    let moved: Vec<_> = before.iter().zip(&after).filter(|(b, a)| b != a).collect();
+    assert!(moved.iter().all(|(_, a)| a.as_str() == "redis-d:6379"));
+    // Roughly a quarter of the keys should follow the new shard.
+    assert!((1500..3500).contains(&moved.len()), "{}", moved.len());
+}
+
+#[test]
+fn test_parse_shards() {
+    assert_eq!(
+        parse_shards(" redis-a:6379, redis-b:6380 ,"),
+        vec![Box::from("redis-a:6379"), Box::from("redis-b:6380")]
+    );
+    assert!(parse_shards("").is_empty());
+}
+
+#[test]
+#[should_panic(expected = "host:port")]
+fn test_parse_shards_rejects_missing_port() {
+    parse_shards("redis-a");
+}
+
+#[test]
+#[should_panic(expected = "more than once")]
+fn test_parse_shards_rejects_duplicates() {
+    parse_shards("redis-a:6379,redis-a:6379");
+}
+
+const RETENTION: i64 = 3600;
+
+fn names(names: &[&str]) -> Vec<Box<str>> {
+    names.iter().map(|name| Box::from(*name)).collect()
+}
+
+fn recorded(shards: &[&str], previous: Option<&[&str]>, changed_at: i64) -> RingRecord {
+    RingRecord {
+        shards: names(shards),
+        previous: previous.map(names),
+        changed_at,
+    }
+}
+
+#[test]
+fn test_plan_ring_records_the_first_ring() {
+    let plan = plan_ring(None, &names(&[SHARDS[1], SHARDS[0]]), None, 100, RETENTION);
+
+    assert_eq!(plan, Ok(Some(recorded(&SHARDS[..2], None, 100))));
+}
+
+#[test]
+fn test_plan_ring_keeps_an_unchanged_ring() {
+    let record = recorded(&SHARDS, None, 0);
+    let reordered = names(&[SHARDS[2], SHARDS[0], SHARDS[1]]);
+
+    assert_eq!(
+        plan_ring(Some(&record), &reordered, None, 100, RETENTION),
+        Ok(None)
+    );
+}
+
+#[test]
+fn test_plan_ring_refuses_a_change_without_migration() {
+    let record = recorded(&SHARDS, None, 0);
+    let grown = names(&[SHARDS[0], SHARDS[1], SHARDS[2], "redis-d:6379"]);
+
+    let refused = plan_ring(Some(&record), &grown, None, 100, RETENTION).unwrap_err();
+    assert!(refused.contains("REDIS_SHARDS_PREVIOUS"), "{}", refused);
+}
+
+#[test]
+fn test_plan_ring_starts_a_migration_from_the_recorded_ring() {
+    let record = recorded(&["redis-a:6379"], None, 0);
+
+    assert_eq!(
+        plan_ring(
+            Some(&record),
+            &names(&SHARDS),
+            Some(&names(&["redis-a:6379"])),
+            100,
+            RETENTION
+        ),
+        Ok(Some(recorded(&SHARDS, Some(&["redis-a:6379"]), 100)))
+    );
+}
+
+#[test]
+fn test_plan_ring_keeps_the_previous_ring_until_its_entries_expire() {
+    let record = recorded(&SHARDS, Some(&["redis-a:6379"]), 100);
+
+    assert_eq!(
+        plan_ring(
+            Some(&record),
+            &names(&SHARDS),
+            Some(&names(&["redis-a:6379"])),
+            200,
+            RETENTION
+        ),
+        Ok(None)
+    );
+    assert!(plan_ring(Some(&record), &names(&SHARDS), None, 200, RETENTION).is_err());
+    assert_eq!(
+        plan_ring(
+            Some(&record),
+            &names(&SHARDS),
+            None,
+            100 + RETENTION,
+            RETENTION
+        ),
+        Ok(Some(recorded(&SHARDS, None, 100)))
+    );
+}
+
+#[test]
+fn test_plan_ring_refuses_another_change_during_a_migration() {
+    let record = recorded(&SHARDS, Some(&["redis-a:6379"]), 100);
+    let grown = names(&[SHARDS[0], SHARDS[1], SHARDS[2], "redis-d:6379"]);
+
+    assert!(plan_ring(Some(&record), &grown, Some(&names(&SHARDS)), 200, RETENTION).is_err());
+    assert!(
+        plan_ring(
+            Some(&record),
+            &grown,
+            Some(&names(&SHARDS)),
+            100 + RETENTION,
+            RETENTION
+        )
+        .is_ok()
+    );
+}
+
+#[test]
+fn test_plan_ring_refuses_an_unrelated_previous_ring() {
+    let record = recorded(&SHARDS, None, 0);
+
+    assert!(
+        plan_ring(
+            Some(&record),
+            &names(&SHARDS),
+            Some(&names(&["redis-z:6379"])),
+            100,
+            RETENTION
+        )
+        .is_err()
+    );
+}
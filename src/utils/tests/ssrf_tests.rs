@@ -0,0 +1,167 @@
+use std::{
+    net::IpAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{Router, http::StatusCode, routing::any};
+use url::Url;
+
+use crate::{
+    app::AppError,
+    config::{CircuitBreakerConfig, HttpClientConfig},
+    utils::http::{HttpClientService, SsrfGuard, is_public},
+};
+
+fn config(allowed_hosts: &[&str]) -> HttpClientConfig {
+    HttpClientConfig {
+        timeout: Duration::from_secs(5),
+        connect_timeout: Duration::from_secs(1),
+        proxy: None,
+        no_proxy: None,
+        max_retries: 0,
+        retry_budget_percent: 20,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        ssrf_allowed_hosts: allowed_hosts.iter().map(|host| host.to_string()).collect(),
+    }
+}
+
+/// Answers 200 on loopback, counting every hit.
+async fn local_server() -> (u16, Arc<AtomicUsize>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&hits);
+    let app = Router::new().route(
+        "/",
+        any(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { StatusCode::OK }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (port, hits)
+}
+
+fn check(guard: &SsrfGuard, url: &str) -> Result<(), AppError> {
+    guard.check_url(&Url::parse(url).unwrap())
+}
+
+#[test]
+fn test_blocks_alternate_ipv4_notations() {
+    let guard = SsrfGuard::default();
+
+    for url in [
+        "http://127.0.0.1/",
+        "http://2130706433/",
+        "http://0177.0.0.1/",
+        "http://0x7f.1/",
+        "http://127.1/",
+        "http://evil.example.com@127.0.0.1/",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://100.64.0.1/",
+        "http://0.0.0.0/",
+    ] {
+        assert!(
+            matches!(check(&guard, url), Err(AppError::Forbidden(_))),
+            "{} was allowed",
+            url
+        );
+    }
+}
+
+#[test]
+fn test_blocks_ipv6_forms_of_private_addresses() {
+    let guard = SsrfGuard::default();
+
+    for url in [
+        "http://[::1]/",
+        "http://[::ffff:127.0.0.1]/",
+        "http://[::ffff:7f00:1]/",
+        "http://[::127.0.0.1]/",
+        "http://[64:ff9b::a00:1]/",
+        "http://[2002:7f00:1::]/",
+        "http://[fd00::1]/",
+        "http://[fe80::1]/",
+    ] {
+        assert!(
+            matches!(check(&guard, url), Err(AppError::Forbidden(_))),
+            "{} was allowed",
+            url
+        );
+    }
+}
+
+#[test]
+fn test_blocks_non_http_schemes() {
+    let guard = SsrfGuard::default();
+
+    assert!(check(&guard, "file:///etc/passwd").is_err());
+    assert!(check(&guard, "gopher://example.com/").is_err());
+}
+
+#[test]
+fn test_allows_public_addresses_and_allowed_hosts() {
+    let guard = SsrfGuard::new(vec![
+        String::from("10.0.0.5"),
+        String::from("Hooks.Internal"),
+    ]);
+
+    assert!(check(&guard, "https://93.184.215.14/").is_ok());
+    assert!(check(&guard, "https://[2606:4700::1111]/").is_ok());
+    assert!(check(&guard, "https://hooks.example.com/").is_ok());
+    assert!(check(&guard, "http://10.0.0.5:8080/").is_ok());
+    assert!(check(&guard, "http://hooks.internal./").is_ok());
+    assert!(check(&guard, "http://10.0.0.6/").is_err());
+}
+
+#[test]
+fn test_is_public_looks_through_embedded_ipv4() {
+    let parse = |ip: &str| is_public(ip.parse::<IpAddr>().unwrap());
+
+    assert!(parse("8.8.8.8"));
+    assert!(parse("::ffff:8.8.8.8"));
+    assert!(parse("64:ff9b::808:808"));
+    assert!(!parse("::ffff:10.0.0.1"));
+    assert!(!parse("2002:c0a8:101::"));
+    assert!(!parse("2001:db8::1"));
+}
+
+#[tokio::test]
+async fn test_host_resolving_to_loopback_refused_when_connecting() {
+    let (port, hits) = local_server().await;
+    let http = HttpClientService::new(config(&[]));
+
+    for host in ["localhost", "localhost."] {
+        let url = format!("http://{}:{}/", host, port);
+        let result = http.execute_guarded(http.client().get(&url)).await;
+        assert!(result.is_err(), "{} was allowed", url);
+    }
+
+    assert_eq!(hits.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_allowed_host_reaches_private_address() {
+    let (port, hits) = local_server().await;
+    let http = HttpClientService::new(config(&["localhost", "127.0.0.1"]));
+
+    let by_name = format!("http://localhost:{}/", port);
+    let by_address = format!("http://127.0.0.1:{}/", port);
+    assert!(
+        http.execute_guarded(http.client().get(&by_name))
+            .await
+            .is_ok()
+    );
+    assert!(
+        http.execute_guarded(http.client().get(&by_address))
+            .await
+            .is_ok()
+    );
+
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+}
@@ -1,4 +1,13 @@
-use crate::{app::AppError, utils::*};
+use serde_json::value::RawValue;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    utils::*,
+};
+
+fn raw(value: serde_json::Value) -> Box<RawValue> {
+    serde_json::value::to_raw_value(&value).unwrap()
+}
 
 #[test]
 fn test_validate_text_valid() {
@@ -96,19 +105,109 @@ fn test_validate_username_three_chars_with_spaces() {
     }
 }
 
+#[test]
+fn test_validate_username_too_long() {
+    let result = validate_username(&"a".repeat(65));
+    match result {
+        Err(AppError::BadRequest(msg)) => {
+            assert_eq!(msg, "Username must be at most 64 characters");
+        }
+        _ => panic!("Expected BadRequest error"),
+    }
+}
+
+#[test]
+fn test_validate_username_other_scripts() {
+    assert!(validate_username("josé.m").is_ok());
+    assert!(validate_username("Ｊｏｈｎ_Ｄｏｅ").is_ok());
+}
+
+#[test]
+fn test_validate_username_disallowed_characters() {
+    for username in ["john doe", "bob!", "eve\u{202e}lin", "  abc"] {
+        match validate_username(username) {
+            Err(AppError::BadRequest(msg)) => {
+                assert_eq!(msg, "Username contains characters that are not allowed");
+            }
+            _ => panic!("Expected BadRequest for {:?}", username),
+        }
+    }
+}
+
+#[test]
+fn test_normalize_username_folds_width_and_case() {
+    assert_eq!(normalize_username("ＡＬＩＣＥ"), "alice");
+    assert_eq!(normalize_username("Alice"), "alice");
+    // U+212A KELVIN SIGN is a compatibility variant of K.
+    assert_eq!(normalize_username("\u{212a}evin"), "kevin");
+    assert_eq!(normalize_username("e\u{301}lise"), "élise");
+}
+
+#[test]
+fn test_validate_new_username_rejects_mixed_scripts() {
+    // Latin "pay" followed by a Cyrillic "раl".
+    match validate_new_username("pay\u{440}\u{430}l") {
+        Err(error) => {
+            assert_eq!(error.code(), ErrorCode::UsernameNotAllowed);
+            assert_eq!(
+                error.to_string(),
+                "bad request: Username mixes characters from different scripts"
+            );
+        }
+        _ => panic!("Expected BadRequest error"),
+    }
+}
+
+#[test]
+fn test_validate_new_username_rejects_whole_script_confusables() {
+    // Cyrillic "раура" reads as Latin "paypa".
+    match validate_new_username("\u{440}\u{430}\u{443}\u{440}\u{430}") {
+        Err(error) => {
+            assert_eq!(error.code(), ErrorCode::UsernameNotAllowed);
+            assert_eq!(
+                error.to_string(),
+                "bad request: Username can be mistaken for a Latin one"
+            );
+        }
+        _ => panic!("Expected BadRequest error"),
+    }
+}
+
+#[test]
+fn test_validate_new_username_accepts_single_scripts() {
+    for username in [
+        "josé.m",
+        "Ｊｏｈｎ_Ｄｏｅ",
+        "дмитрий",
+        "山田太郎",
+        "user_42",
+    ] {
+        assert!(validate_new_username(username).is_ok(), "{:?}", username);
+    }
+}
+
+#[test]
+fn test_username_skeleton_matches_look_alikes() {
+    assert_eq!(
+        username_skeleton("p\u{430}ypal"),
+        username_skeleton("paypal")
+    );
+    assert_ne!(username_skeleton("paypal"), username_skeleton("paypa1x"));
+}
+
 #[test]
 fn test_validate_json_credentials_valid_object() {
-    let credentials = serde_json::json!({
+    let credentials = raw(serde_json::json!({
         "id": "test_id",
         "type": "public-key"
-    });
+    }));
     let result = validate_json_credentials(&credentials);
     assert!(result.is_ok());
 }
 
 #[test]
 fn test_validate_json_credentials_null() {
-    let credentials = serde_json::json!(null);
+    let credentials = raw(serde_json::json!(null));
     let result = validate_json_credentials(&credentials);
     assert!(result.is_err());
     match result {
@@ -121,7 +220,7 @@ fn test_validate_json_credentials_null() {
 
 #[test]
 fn test_validate_json_credentials_not_object() {
-    let credentials = serde_json::json!("string_value");
+    let credentials = raw(serde_json::json!("string_value"));
     let result = validate_json_credentials(&credentials);
     assert!(result.is_err());
     match result {
@@ -134,7 +233,7 @@ fn test_validate_json_credentials_not_object() {
 
 #[test]
 fn test_validate_json_credentials_array() {
-    let credentials = serde_json::json!([1, 2, 3]);
+    let credentials = raw(serde_json::json!([1, 2, 3]));
     let result = validate_json_credentials(&credentials);
     assert!(result.is_err());
     match result {
@@ -147,7 +246,7 @@ fn test_validate_json_credentials_array() {
 
 #[test]
 fn test_validate_json_credentials_empty_object() {
-    let credentials = serde_json::json!({});
+    let credentials = raw(serde_json::json!({}));
     let result = validate_json_credentials(&credentials);
     assert!(result.is_err());
     match result {
@@ -160,14 +259,38 @@ fn test_validate_json_credentials_empty_object() {
 
 #[test]
 fn test_validate_json_credentials_number() {
-    let credentials = serde_json::json!(42);
+    let credentials = raw(serde_json::json!(42));
     let result = validate_json_credentials(&credentials);
     assert!(result.is_err());
 }
 
 #[test]
 fn test_validate_json_credentials_boolean() {
-    let credentials = serde_json::json!(true);
+    let credentials = raw(serde_json::json!(true));
     let result = validate_json_credentials(&credentials);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_validate_email_valid() {
+    assert!(validate_email("john.doe@example.com").is_ok());
+    assert!(validate_email("a+tag@mail.example.co").is_ok());
+}
+
+#[test]
+fn test_validate_email_invalid() {
+    for email in [
+        "",
+        "john.doe",
+        "@example.com",
+        "john@localhost",
+        "john@@example.com",
+        "john doe@example.com",
+        "john@example.com.",
+    ] {
+        match validate_email(email) {
+            Err(AppError::BadRequest(_)) => {}
+            _ => panic!("Expected BadRequest for {:?}", email),
+        }
+    }
+}
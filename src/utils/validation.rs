@@ -1,9 +1,18 @@
-use crate::app::AppError;
+use std::sync::OnceLock;
+
+use crate::{
+    app::{AppError, ErrorCode},
+    config::username::{MIN_USERNAME_CHARS, UsernamePolicy},
+};
 
 use axum::{
-    Json,
-    extract::{FromRequest, Request},
+    Form, Json,
+    extract::{FromRequest, FromRequestParts, Query, Request},
+    http::request::Parts,
 };
+use serde_json::value::RawValue;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{MixedScript, mixed_script::AugmentedScriptSet, skeleton};
 
 pub trait Validatable {
     fn validate(&self) -> Result<(), AppError>;
@@ -19,6 +28,16 @@ where
     Ok(request)
 }
 
+pub async fn extract_and_validate_query<T, S>(parts: &mut Parts, state: &S) -> Result<T, AppError>
+where
+    T: Validatable + serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    let Query(request) = Query::<T>::from_request_parts(parts, state).await?;
+    request.validate()?;
+    Ok(request)
+}
+
 #[macro_export]
 macro_rules! impl_validated_json_request {
     ($type:ty) => {
@@ -38,6 +57,54 @@ macro_rules! impl_validated_json_request {
     };
 }
 
+pub async fn extract_and_validate_form<T, S>(req: Request, state: &S) -> Result<T, AppError>
+where
+    T: Validatable + serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    let Form(request) = Form::<T>::from_request(req, state).await?;
+    request.validate()?;
+    Ok(request)
+}
+
+#[macro_export]
+macro_rules! impl_validated_form_request {
+    ($type:ty) => {
+        impl<S> axum::extract::FromRequest<S> for $type
+        where
+            S: Send + Sync,
+        {
+            type Rejection = $crate::app::AppError;
+
+            fn from_request(
+                req: axum::extract::Request,
+                state: &S,
+            ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
+                $crate::utils::validation::extract_and_validate_form(req, state)
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! impl_validated_query_request {
+    ($type:ty) => {
+        impl<S> axum::extract::FromRequestParts<S> for $type
+        where
+            S: Send + Sync,
+        {
+            type Rejection = $crate::app::AppError;
+
+            async fn from_request_parts(
+                parts: &mut axum::http::request::Parts,
+                state: &S,
+            ) -> Result<Self, Self::Rejection> {
+                $crate::utils::validation::extract_and_validate_query(parts, state).await
+            }
+        }
+    };
+}
+
 // ============================================================================
 // Validation Helpers
 // ============================================================================
@@ -50,34 +117,165 @@ pub fn validate_text(text: &str, field: &str) -> Result<(), AppError> {
     Ok(())
 }
 
+static USERNAME_POLICY: OnceLock<UsernamePolicy> = OnceLock::new();
+
+/// Installs the policy read at startup. Only the first call has an effect;
+/// until then the defaults apply.
+pub fn set_username_policy(policy: UsernamePolicy) {
+    let _ = USERNAME_POLICY.set(policy);
+}
+
+pub fn username_policy() -> &'static UsernamePolicy {
+    USERNAME_POLICY.get_or_init(UsernamePolicy::default)
+}
+
+/// The form usernames are unique in: NFKC folds compatibility variants such
+/// as fullwidth letters into their plain spelling, then case is dropped.
+pub fn normalize_username(username: &str) -> String {
+    username.nfkc().collect::<String>().to_lowercase()
+}
+
 #[inline]
 pub fn validate_username(username: &str) -> Result<(), AppError> {
     validate_text(username, "Username")?;
 
-    if username.trim().len() < 3 {
+    let policy = username_policy();
+    let normalized = normalize_username(username);
+
+    if normalized.trim().chars().count() < MIN_USERNAME_CHARS {
+        return Err(AppError::BadRequest(format!(
+            "Username must be at least {} characters",
+            MIN_USERNAME_CHARS
+        )));
+    }
+
+    if normalized.chars().count() > policy.max_chars {
+        return Err(AppError::BadRequest(format!(
+            "Username must be at most {} characters",
+            policy.max_chars
+        )));
+    }
+
+    if !policy.pattern.is_match(&normalized) {
         return Err(AppError::BadRequest(String::from(
-            "Username must be at least 3 characters",
+            "Username contains characters that are not allowed",
         )));
     }
 
     Ok(())
 }
 
+/// The UTS #39 skeleton: names with the same one look alike, as `paypal`
+/// and `pаypal` with a Cyrillic `а` do.
+pub fn username_skeleton(normalized: &str) -> String {
+    skeleton(normalized).collect()
+}
+
+/// A name in a script other than Latin made only of letters that look
+/// Latin, such as a Cyrillic `раура`.
+pub fn is_whole_script_confusable(normalized: &str) -> bool {
+    if normalized.is_ascii() {
+        return false;
+    }
+
+    let mut latin = AugmentedScriptSet::for_char('a');
+    latin.intersect_with(normalized.resolve_script_set());
+    latin.is_empty() && username_skeleton(normalized).is_ascii()
+}
+
+/// Registration also keeps reserved names and look-alikes out. Logins do
+/// not check them, so tightening the policy never locks out an account that
+/// already holds a name.
 #[inline]
-pub fn validate_json_credentials(credentials: &serde_json::Value) -> Result<(), AppError> {
-    if credentials.is_null() {
-        return Err(AppError::BadRequest(String::from("Invalid credentials")));
+pub fn validate_new_username(username: &str) -> Result<(), AppError> {
+    validate_username(username)?;
+
+    let policy = username_policy();
+    let normalized = normalize_username(username);
+
+    if policy.reject_mixed_script && !normalized.as_str().is_single_script() {
+        return Err(AppError::BadRequest(String::from(
+            "Username mixes characters from different scripts",
+        ))
+        .with_code(ErrorCode::UsernameNotAllowed));
     }
 
-    if !credentials.is_object() {
-        return Err(AppError::BadRequest(String::from("Invalid credentials")));
+    if policy.reject_confusables && is_whole_script_confusable(&normalized) {
+        return Err(
+            AppError::BadRequest(String::from("Username can be mistaken for a Latin one"))
+                .with_code(ErrorCode::UsernameNotAllowed),
+        );
     }
 
-    if let Some(obj) = credentials.as_object() {
-        if obj.is_empty() {
-            return Err(AppError::BadRequest(String::from("Invalid credentials")));
-        }
+    if policy.is_reserved(username) {
+        return Err(AppError::BadRequest(String::from("Username is reserved"))
+            .with_code(ErrorCode::UsernameNotAllowed));
+    }
+
+    Ok(())
+}
+
+const MAX_DEVICE_NAME_CHARS: usize = 64;
+
+#[inline]
+pub fn validate_device_name(name: &str) -> Result<(), AppError> {
+    validate_text(name, "Device name")?;
+
+    if name.chars().count() > MAX_DEVICE_NAME_CHARS {
+        return Err(AppError::BadRequest(format!(
+            "Device name must be at most {} characters",
+            MAX_DEVICE_NAME_CHARS
+        )));
     }
 
     Ok(())
 }
+
+// RFC 5321 caps a forward path at 254 characters.
+const MAX_EMAIL_CHARS: usize = 254;
+
+/// Only the shape is checked; delivery of the verification token proves the
+/// address.
+#[inline]
+pub fn validate_email(email: &str) -> Result<(), AppError> {
+    validate_text(email, "Email")?;
+
+    let valid = email.len() <= MAX_EMAIL_CHARS
+        && !email.chars().any(char::is_whitespace)
+        && email.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+        });
+
+    if !valid {
+        return Err(AppError::BadRequest(String::from("Invalid email address")));
+    }
+
+    Ok(())
+}
+
+// Attestations with a full certificate chain stay well below this.
+const MAX_CREDENTIALS_BYTES: usize = 64 * 1024;
+
+#[inline]
+pub fn validate_json_credentials(credentials: &RawValue) -> Result<(), AppError> {
+    let json = credentials.get();
+
+    if json.len() > MAX_CREDENTIALS_BYTES {
+        return Err(AppError::BadRequest(String::from(
+            "Credentials payload too large",
+        )));
+    }
+
+    // A raw value is already valid JSON, so only its outer shape is left to check.
+    let inner = json
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'));
+    match inner {
+        Some(inner) if !inner.trim().is_empty() => Ok(()),
+        _ => Err(AppError::BadRequest(String::from("Invalid credentials"))),
+    }
+}